@@ -50,6 +50,9 @@ use definitions::{unpack, Action, State};
 const MAX_INTERMEDIATES: usize = 2;
 const MAX_OSC_PARAMS: usize = 16;
 const MAX_OSC_RAW: usize = 1024;
+/// Kitty graphics payloads are sent in chunks of up to 4096 bytes,
+/// plus the control data before them.
+const MAX_APC_RAW: usize = 4096 + 512;
 
 struct VtUtf8Receiver<'a, P: Perform>(&'a mut P, &'a mut State);
 
@@ -84,6 +87,14 @@ pub struct Parser<const OSC_RAW_BUF_SIZE: usize = MAX_OSC_RAW> {
     osc_raw: Vec<u8>,
     osc_params: [(usize, usize); MAX_OSC_PARAMS],
     osc_num_params: usize,
+    #[cfg(feature = "no_std")]
+    apc_raw: ArrayVec<u8, MAX_APC_RAW>,
+    #[cfg(not(feature = "no_std"))]
+    apc_raw: Vec<u8>,
+    /// The current SOS/PM/APC string is an APC one, the only kind dispatched.
+    is_apc: bool,
+    /// The current APC string didn't fit `apc_raw`.
+    apc_truncated: bool,
     ignoring: bool,
     utf8_parser: utf8::Parser,
 }
@@ -189,6 +200,9 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
                     State::OscString => {
                         self.perform_action(performer, Action::OscEnd, byte);
                     }
+                    State::SosPmApcString if self.is_apc => {
+                        performer.apc_dispatch(&self.apc_raw, self.apc_truncated);
+                    }
                     _ => (),
                 }
 
@@ -204,6 +218,11 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
                     State::OscString => {
                         self.perform_action(performer, Action::OscStart, byte);
                     }
+                    State::SosPmApcString => {
+                        self.apc_raw.clear();
+                        self.apc_truncated = false;
+                        self.is_apc = byte == 0x5f;
+                    }
                     _ => (),
                 }
 
@@ -375,7 +394,28 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
                 self.params.clear();
             }
             Action::BeginUtf8 => self.process_utf8(performer, byte),
-            Action::Ignore => (),
+            // SOS/PM/APC strings have no action of their own, keep the
+            // printable bytes of APC ones until the string terminates.
+            Action::Ignore => {
+                if let State::SosPmApcString = self.state {
+                    if self.is_apc && (0x20..0x7f).contains(&byte) {
+                        #[cfg(feature = "no_std")]
+                        {
+                            if self.apc_raw.try_push(byte).is_err() {
+                                self.apc_truncated = true;
+                            }
+                        }
+                        #[cfg(not(feature = "no_std"))]
+                        {
+                            if self.apc_raw.len() < MAX_APC_RAW {
+                                self.apc_raw.push(byte);
+                            } else {
+                                self.apc_truncated = true;
+                            }
+                        }
+                    }
+                }
+            }
             Action::None => (),
         }
     }
@@ -429,6 +469,13 @@ pub trait Perform {
     /// Dispatch an operating system command.
     fn osc_dispatch(&mut self, _params: &[&[u8]], _bell_terminated: bool) {}
 
+    /// Dispatch an application program command, `data` is everything
+    /// between `ESC _` and the string terminator.
+    ///
+    /// Strings longer than the internal buffer are cut, `truncated` is then
+    /// set and `data` only holds their beginning.
+    fn apc_dispatch(&mut self, _data: &[u8], _truncated: bool) {}
+
     /// A final character has arrived for a CSI sequence
     ///
     /// The `ignore` flag indicates that either more than two intermediates arrived
@@ -480,6 +527,7 @@ mod tests {
         DcsHook(Vec<Vec<u16>>, Vec<u8>, bool, char),
         DcsPut(u8),
         DcsUnhook,
+        Apc(Vec<u8>, bool),
     }
 
     impl Perform for Dispatcher {
@@ -521,6 +569,52 @@ mod tests {
         fn unhook(&mut self) {
             self.dispatched.push(Sequence::DcsUnhook);
         }
+
+        fn apc_dispatch(&mut self, data: &[u8], truncated: bool) {
+            self.dispatched
+                .push(Sequence::Apc(data.to_vec(), truncated));
+        }
+    }
+
+    #[test]
+    fn parse_apc() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+
+        // SOS and PM strings are ignored, only APC ones are dispatched.
+        for byte in b"\x1bXsos\x1b\\\x1b^pm\x1b\\\x1b_Ga=q,i=1;AAAA\x1b\\" {
+            parser.advance(&mut dispatcher, *byte);
+        }
+
+        let apc: Vec<&Sequence> = dispatcher
+            .dispatched
+            .iter()
+            .filter(|sequence| !matches!(sequence, Sequence::Esc(..)))
+            .collect();
+        assert_eq!(apc, [&Sequence::Apc(b"Ga=q,i=1;AAAA".to_vec(), false)]);
+    }
+
+    #[test]
+    fn parse_apc_too_long() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+
+        let mut input = b"\x1b_Gm=1;".to_vec();
+        input.resize(input.len() + MAX_APC_RAW, b'A');
+        input.extend_from_slice(b"\x1b\\\x1b_Gm=0;AA\x1b\\");
+        for byte in input {
+            parser.advance(&mut dispatcher, byte);
+        }
+
+        let apc: Vec<(usize, bool)> = dispatcher
+            .dispatched
+            .iter()
+            .filter_map(|sequence| match sequence {
+                Sequence::Apc(data, truncated) => Some((data.len(), *truncated)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(apc, [(MAX_APC_RAW, true), (7, false)]);
     }
 
     #[test]
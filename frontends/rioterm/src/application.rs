@@ -1,9 +1,11 @@
+use crate::bindings::key::{KeyEvent, KeyState};
 use crate::event::{ClickState, EventPayload, EventProxy, RioEvent, RioEventType};
 use crate::ime::Preedit;
 use crate::router::{RouteWindow, Router};
 use crate::routes::RoutePath;
 use crate::scheduler::{Scheduler, TimerId, Topic};
 use crate::screen::touch::on_touch;
+use crate::screen::DragBegin;
 use crate::watcher::configuration_file_updates;
 use raw_window_handle::HasDisplayHandle;
 use rio_backend::clipboard::{Clipboard, ClipboardType};
@@ -24,11 +26,16 @@ use rio_window::window::{CursorIcon, Fullscreen};
 use std::error::Error;
 use std::time::{Duration, Instant};
 
+/// Time given to the clipboard owner to answer a paste.
+const CLIPBOARD_PASTE_TIMEOUT_MS: u64 = 3000;
+
 pub struct Application {
     config: rio_backend::config::Config,
     event_proxy: EventProxy,
     router: Router,
     scheduler: Scheduler,
+    /// The power source is polled for `renderer.auto-performance`.
+    power_monitor: Option<crate::power::PowerMonitor>,
 }
 
 impl Application {
@@ -54,6 +61,10 @@ impl Application {
             event_proxy.clone(),
         );
         let scheduler = Scheduler::new(proxy);
+        let power_monitor = config
+            .renderer
+            .auto_performance
+            .then(|| crate::power::power_state_updates(event_proxy.clone()));
 
         event_loop.listen_device_events(DeviceEvents::Never);
 
@@ -62,6 +73,7 @@ impl Application {
             event_proxy,
             router,
             scheduler,
+            power_monitor,
         }
     }
 
@@ -128,6 +140,7 @@ impl ApplicationHandler<EventPayload> for Application {
         )
         .unwrap();
         window.is_focused = true;
+        window.screen.on_application_startup_complete();
         self.router.create_route_from_window(window);
 
         log::info!("Initialisation complete");
@@ -135,6 +148,7 @@ impl ApplicationHandler<EventPayload> for Application {
 
     fn user_event(&mut self, event_loop: &ActiveEventLoop, event: EventPayload) {
         let window_id = event.window_id;
+        let terminal_id = event.terminal_id;
         match event.payload {
             RioEventType::Frame => {
                 if let Some(route) = self.router.routes.get_mut(&window_id) {
@@ -154,7 +168,7 @@ impl ApplicationHandler<EventPayload> for Application {
 
                     route.window.screen.update_content();
                     route.window.has_updates = true;
-                    if route.window.has_frame {
+                    if route.can_redraw() {
                         route.request_redraw();
                     }
                 }
@@ -167,11 +181,11 @@ impl ApplicationHandler<EventPayload> for Application {
                         return;
                     }
 
-                    if route_id == route.window.screen.ctx().current_route() {
+                    if route.window.screen.ctx().is_visible_route(route_id) {
                         route.window.has_updates = true;
 
                         route.window.screen.update_content();
-                        if route.window.has_frame {
+                        if route.can_redraw() {
                             route.request_redraw();
                         }
                     }
@@ -194,6 +208,24 @@ impl ApplicationHandler<EventPayload> for Application {
                     }
                 }
             }
+            RioEventType::Rio(RioEvent::PowerStateChange(battery_level, on_ac)) => {
+                for (_id, route) in self.router.routes.iter_mut() {
+                    route
+                        .window
+                        .screen
+                        .on_power_state_change(battery_level, on_ac);
+                    route.request_redraw();
+                }
+            }
+            RioEventType::Rio(RioEvent::LineLengthExceeded(limit)) => {
+                if let Some(route) = self.router.routes.get_mut(&window_id) {
+                    route.window.screen.status_message(
+                        &format!("Line exceeded {limit} characters and has been broken"),
+                        3000,
+                    );
+                    route.request_redraw();
+                }
+            }
             RioEventType::Rio(RioEvent::ReportToAssistant(error)) => {
                 if let Some(route) = self.router.routes.get_mut(&window_id) {
                     route.report_error(&error);
@@ -219,6 +251,12 @@ impl ApplicationHandler<EventPayload> for Application {
                 };
 
                 self.config = config;
+                if !self.config.renderer.auto_performance {
+                    self.power_monitor = None;
+                } else if self.power_monitor.is_none() {
+                    self.power_monitor =
+                        Some(crate::power::power_state_updates(self.event_proxy.clone()));
+                }
                 for (_id, route) in self.router.routes.iter_mut() {
                     if has_font_updates {
                         if let Some(ref err) = font_library_errors {
@@ -271,6 +309,10 @@ impl ApplicationHandler<EventPayload> for Application {
                     } else {
                         let size = route.window.screen.context_manager.len();
                         route.window.screen.resize_top_or_bottom_line(size);
+                        // The remaining panes take the space of a closed one.
+                        route.window.screen.resize_all_contexts();
+                        route.window.screen.update_content();
+                        route.request_redraw();
                     }
                 }
             }
@@ -295,6 +337,27 @@ impl ApplicationHandler<EventPayload> for Application {
                     );
                 }
             }
+            RioEventType::Rio(RioEvent::PrepareStartupScript(millis)) => {
+                let timer_id = TimerId::new(Topic::StartupScript, window_id);
+                let event = EventPayload::new(
+                    RioEventType::Rio(RioEvent::StartupScript),
+                    window_id,
+                );
+
+                if !self.scheduler.scheduled(timer_id) {
+                    self.scheduler.schedule(
+                        event,
+                        Duration::from_millis(millis),
+                        false,
+                        timer_id,
+                    );
+                }
+            }
+            RioEventType::Rio(RioEvent::StartupScript | RioEvent::PromptReady(_)) => {
+                if let Some(route) = self.router.routes.get_mut(&window_id) {
+                    route.window.screen.run_startup_script();
+                }
+            }
             RioEventType::Rio(RioEvent::PrepareRenderOnRoute(millis, route_id)) => {
                 let timer_id = TimerId::new(Topic::RenderRoute, window_id);
                 let event = EventPayload::new(
@@ -323,6 +386,23 @@ impl ApplicationHandler<EventPayload> for Application {
                 }
             }
             RioEventType::BlinkCursor | RioEventType::BlinkCursorTimeout => {}
+            RioEventType::Rio(RioEvent::SetCursorIcon(icon)) => {
+                if let Some(route) = self.router.routes.get_mut(&window_id) {
+                    route.window.winit_window.set_cursor(icon);
+                }
+            }
+            RioEventType::Rio(RioEvent::SetCursorVisible(visible)) => {
+                if let Some(route) = self.router.routes.get_mut(&window_id) {
+                    route.window.winit_window.set_cursor_visible(visible);
+                }
+            }
+            RioEventType::Rio(RioEvent::ScrollbarVisibility(visible)) => {
+                if let Some(route) = self.router.routes.get_mut(&window_id) {
+                    route.window.screen.renderer.set_scrollbar_visible(visible);
+                    route.window.screen.update_content();
+                    route.request_redraw();
+                }
+            }
             RioEventType::Rio(RioEvent::MouseCursorDirty) => {
                 if let Some(route) = self.router.routes.get_mut(&window_id) {
                     route.window.screen.reset_mouse();
@@ -339,42 +419,98 @@ impl ApplicationHandler<EventPayload> for Application {
             RioEventType::Rio(RioEvent::ClipboardLoad(clipboard_type, format)) => {
                 if let Some(route) = self.router.routes.get_mut(&window_id) {
                     if route.window.is_focused {
-                        let text = format(
-                            self.router
-                                .clipboard
-                                .borrow_mut()
-                                .get(clipboard_type)
-                                .as_str(),
+                        route.window.screen.handle_osc52_read(
+                            clipboard_type,
+                            format.as_ref(),
+                            terminal_id,
                         );
-                        route
-                            .window
-                            .screen
-                            .ctx_mut()
-                            .current_mut()
-                            .messenger
-                            .send_bytes(text.into_bytes());
                     }
                 }
             }
             RioEventType::Rio(RioEvent::ClipboardStore(clipboard_type, content)) => {
                 if let Some(route) = self.router.routes.get_mut(&window_id) {
                     if route.window.is_focused {
-                        self.router
-                            .clipboard
-                            .borrow_mut()
-                            .set(clipboard_type, content);
+                        route
+                            .window
+                            .screen
+                            .handle_osc52(clipboard_type, content.as_bytes());
                     }
                 }
             }
             RioEventType::Rio(RioEvent::PtyWrite(text)) => {
                 if let Some(route) = self.router.routes.get_mut(&window_id) {
-                    route
-                        .window
-                        .screen
-                        .ctx_mut()
-                        .current_mut()
-                        .messenger
-                        .send_bytes(text.into_bytes());
+                    if let Some(context) =
+                        route.window.screen.ctx_mut().event_context_mut(terminal_id)
+                    {
+                        context.messenger.send_bytes(text.into_bytes());
+                    }
+                }
+            }
+            RioEventType::Rio(RioEvent::PrepareMotionReport(millis)) => {
+                let timer_id = TimerId::new(Topic::MotionReport, window_id);
+                let event = EventPayload::new(
+                    RioEventType::Rio(RioEvent::MotionReport),
+                    window_id,
+                );
+
+                if !self.scheduler.scheduled(timer_id) {
+                    self.scheduler.schedule(
+                        event,
+                        Duration::from_millis(millis),
+                        false,
+                        timer_id,
+                    );
+                }
+            }
+            RioEventType::Rio(RioEvent::MotionReport) => {
+                if let Some(route) = self.router.routes.get_mut(&window_id) {
+                    route.window.screen.flush_motion_report();
+                }
+            }
+            RioEventType::Rio(RioEvent::PrepareClipboardPoll(millis)) => {
+                let timer_id = TimerId::new(Topic::ClipboardPoll, window_id);
+                let event = EventPayload::new(
+                    RioEventType::Rio(RioEvent::ClipboardPoll),
+                    window_id,
+                );
+
+                if !self.scheduler.scheduled(timer_id) {
+                    self.scheduler.schedule(
+                        event,
+                        Duration::from_millis(millis),
+                        true,
+                        timer_id,
+                    );
+                }
+            }
+            RioEventType::Rio(RioEvent::ClipboardPoll) => {
+                if let Some(route) = self.router.routes.get_mut(&window_id) {
+                    route.window.screen.poll_clipboard_change();
+                }
+            }
+            RioEventType::Rio(RioEvent::PrepareClipboardPaste(paste_id)) => {
+                let timer_id = TimerId::new(Topic::ClipboardPaste, window_id);
+                let event = EventPayload::new(
+                    RioEventType::Rio(RioEvent::ClipboardPaste(paste_id, None)),
+                    window_id,
+                );
+
+                // Only the latest request is pasted, so its timeout replaces
+                // the previous one.
+                self.scheduler.unschedule(timer_id);
+                self.scheduler.schedule(
+                    event,
+                    Duration::from_millis(CLIPBOARD_PASTE_TIMEOUT_MS),
+                    false,
+                    timer_id,
+                );
+            }
+            RioEventType::Rio(RioEvent::ClipboardPaste(paste_id, text)) => {
+                if let Some(route) = self.router.routes.get_mut(&window_id) {
+                    if route.window.screen.complete_paste(paste_id, text) {
+                        self.scheduler
+                            .unschedule(TimerId::new(Topic::ClipboardPaste, window_id));
+                    }
                 }
             }
             RioEventType::Rio(RioEvent::TextAreaSizeRequest(format)) => {
@@ -382,13 +518,11 @@ impl ApplicationHandler<EventPayload> for Application {
                     let layout = route.window.screen.sugarloaf.layout();
                     let text =
                         format(crate::renderer::utils::terminal_dimensions(&layout));
-                    route
-                        .window
-                        .screen
-                        .ctx_mut()
-                        .current_mut()
-                        .messenger
-                        .send_bytes(text.into_bytes());
+                    if let Some(context) =
+                        route.window.screen.ctx_mut().event_context_mut(terminal_id)
+                    {
+                        context.messenger.send_bytes(text.into_bytes());
+                    }
                 }
             }
             RioEventType::Rio(RioEvent::ColorRequest(index, format)) => {
@@ -402,13 +536,11 @@ impl ApplicationHandler<EventPayload> for Application {
                 if let Some(route) = self.router.routes.get_mut(&window_id) {
                     let color = route.window.screen.renderer.colors[index];
                     let rgb = ColorRgb::from_color_arr(color);
-                    route
-                        .window
-                        .screen
-                        .ctx_mut()
-                        .current_mut()
-                        .messenger
-                        .send_bytes(format(rgb).into_bytes());
+                    if let Some(context) =
+                        route.window.screen.ctx_mut().event_context_mut(terminal_id)
+                    {
+                        context.messenger.send_bytes(format(rgb).into_bytes());
+                    }
                 }
             }
             RioEventType::Rio(RioEvent::CreateWindow) => {
@@ -527,6 +659,14 @@ impl ApplicationHandler<EventPayload> for Application {
                     route.window.winit_window.set_minimized(set_minimize);
                 }
             }
+            RioEventType::Rio(
+                RioEvent::UpdateFontSize(_) | RioEvent::UpdateRenderScale,
+            ) => {
+                // The cell size changed, so did the minimum window size
+                if let Some(route) = self.router.routes.get_mut(&window_id) {
+                    route.window.update_min_inner_size();
+                }
+            }
             RioEventType::Rio(RioEvent::ToggleFullScreen) => {
                 if let Some(route) = self.router.routes.get_mut(&window_id) {
                     match route.window.winit_window.fullscreen() {
@@ -614,9 +754,7 @@ impl ApplicationHandler<EventPayload> for Application {
                 route.window.screen.set_modifiers(modifiers);
 
                 if route.window.screen.search_nearest_hyperlink_from_pos() {
-                    if self.config.hide_cursor_when_typing {
-                        route.window.winit_window.set_cursor_visible(true);
-                    }
+                    route.window.screen.set_mouse_visible(true);
 
                     route.window.winit_window.set_cursor(CursorIcon::Pointer);
                     route.window.screen.update_content();
@@ -629,9 +767,7 @@ impl ApplicationHandler<EventPayload> for Application {
                     return;
                 }
 
-                if self.config.hide_cursor_when_typing {
-                    route.window.winit_window.set_cursor_visible(true);
-                }
+                route.window.screen.set_mouse_visible(true);
 
                 match button {
                     MouseButton::Left => {
@@ -648,39 +784,27 @@ impl ApplicationHandler<EventPayload> for Application {
 
                 #[cfg(target_os = "macos")]
                 {
-                    if route.window.is_macos_deadzone {
+                    // Left presses in the dead zone drag the window, that is
+                    // decided by `on_drag_begin_at_pos`.
+                    if route.window.is_macos_deadzone
+                        && (button != MouseButton::Left
+                            || state == ElementState::Released)
+                    {
                         return;
                     }
                 }
 
                 match state {
                     ElementState::Pressed => {
-                        if route.window.screen.trigger_hyperlink() {
-                            return;
-                        }
-
-                        // Process mouse press before bindings to update the `click_state`.
-                        if !route.window.screen.modifiers.state().shift_key()
-                            && route.window.screen.mouse_mode()
-                        {
-                            route.window.screen.mouse.click_state = ClickState::None;
-
-                            let code = match button {
-                                MouseButton::Left => 0,
-                                MouseButton::Middle => 1,
-                                MouseButton::Right => 2,
-                                // Can't properly report more than three buttons..
-                                MouseButton::Back
-                                | MouseButton::Forward
-                                | MouseButton::Other(_) => return,
-                            };
+                        // Clicking a pane of a split tab focuses it.
+                        route.window.screen.select_split_under_mouse();
 
-                            route
-                                .window
-                                .screen
-                                .mouse_report(code, ElementState::Pressed);
+                        let modifiers = route.window.screen.modifiers.state();
+                        let mouse_mode =
+                            !modifiers.shift_key() && route.window.screen.mouse_mode();
 
-                            route.window.screen.process_mouse_bindings(button);
+                        if mouse_mode {
+                            route.window.screen.mouse.click_state = ClickState::None;
                         } else {
                             // Calculate time since the last click to handle double/triple clicks.
                             let now = Instant::now();
@@ -706,16 +830,38 @@ impl ApplicationHandler<EventPayload> for Application {
                                 }
                                 _ => ClickState::Click,
                             };
+                        }
 
+                        if let MouseButton::Left = button {
                             // Load mouse point, treating message bar and padding as the closest square.
                             let display_offset = route.window.screen.display_offset();
-
-                            if let MouseButton::Left = button {
-                                let pos =
-                                    route.window.screen.mouse_position(display_offset);
-                                route.window.screen.on_left_click(pos);
+                            let pos = route.window.screen.mouse_position(display_offset);
+                            match route.window.screen.on_drag_begin_at_pos(pos, modifiers)
+                            {
+                                DragBegin::Window | DragBegin::Hyperlink => return,
+                                DragBegin::Selection | DragBegin::None => (),
                             }
+                        }
 
+                        // Process mouse press before bindings to update the `click_state`.
+                        if mouse_mode {
+                            let code = match button {
+                                MouseButton::Left => 0,
+                                MouseButton::Middle => 1,
+                                MouseButton::Right => 2,
+                                // Can't properly report more than three buttons..
+                                MouseButton::Back
+                                | MouseButton::Forward
+                                | MouseButton::Other(_) => return,
+                            };
+
+                            route
+                                .window
+                                .screen
+                                .mouse_report(code, ElementState::Pressed);
+
+                            route.window.screen.process_mouse_bindings(button);
+                        } else {
                             route.window.screen.update_content();
                             route.request_redraw();
                         }
@@ -750,9 +896,7 @@ impl ApplicationHandler<EventPayload> for Application {
             }
 
             WindowEvent::CursorMoved { position, .. } => {
-                if self.config.hide_cursor_when_typing {
-                    route.window.winit_window.set_cursor_visible(true);
-                }
+                route.window.screen.set_mouse_visible(true);
 
                 if route.path != RoutePath::Terminal {
                     route.window.winit_window.set_cursor(CursorIcon::Default);
@@ -779,6 +923,7 @@ impl ApplicationHandler<EventPayload> for Application {
                     {
                         route.window.winit_window.set_cursor(CursorIcon::Default);
 
+                        route.window.screen.mouse.y = y as usize;
                         route.window.is_macos_deadzone = true;
                         return;
                     }
@@ -815,21 +960,11 @@ impl ApplicationHandler<EventPayload> for Application {
                     return;
                 }
 
-                if route.window.screen.search_nearest_hyperlink_from_pos() {
-                    route.window.winit_window.set_cursor(CursorIcon::Pointer);
+                if route.window.screen.on_mouse_cursor_shape_change()
+                    == CursorIcon::Pointer
+                {
                     route.window.screen.context_manager.schedule_render(60);
                 } else {
-                    let cursor_icon =
-                        if !route.window.screen.modifiers.state().shift_key()
-                            && route.window.screen.mouse_mode()
-                        {
-                            CursorIcon::Default
-                        } else {
-                            CursorIcon::Text
-                        };
-
-                    route.window.winit_window.set_cursor(cursor_icon);
-
                     // In case hyperlink range has cleaned trigger one more render
                     if route.window.screen.renderer.has_hyperlink_range() {
                         route.window.screen.renderer.set_hyperlink_range(None);
@@ -840,6 +975,10 @@ impl ApplicationHandler<EventPayload> for Application {
                 route.window.screen.mouse.inside_text_area = inside_text_area;
                 route.window.screen.mouse.square_side = square_side;
 
+                if square_changed && route.window.screen.is_inspecting_cell() {
+                    route.window.screen.context_manager.schedule_render(60);
+                }
+
                 if (lmb_pressed || rmb_pressed)
                     && (route.window.screen.modifiers.state().shift_key()
                         || !route.window.screen.mouse_mode())
@@ -870,9 +1009,7 @@ impl ApplicationHandler<EventPayload> for Application {
                     return;
                 }
 
-                if self.config.hide_cursor_when_typing {
-                    route.window.winit_window.set_cursor_visible(true);
-                }
+                route.window.screen.set_mouse_visible(true);
 
                 match delta {
                     MouseScrollDelta::LineDelta(columns, lines) => {
@@ -890,6 +1027,7 @@ impl ApplicationHandler<EventPayload> for Application {
                                 // Reset offset to zero.
                                 route.window.screen.mouse.accumulated_scroll =
                                     Default::default();
+                                route.window.screen.mouse.scroll_started();
                             }
                             TouchPhase::Moved => {
                                 // When the angle between (x, 0) and (x, y) is lower than ~25 degrees
@@ -900,9 +1038,15 @@ impl ApplicationHandler<EventPayload> for Application {
                                     lpos.x = 0.;
                                 }
 
-                                route.window.screen.scroll(lpos.x, lpos.y);
+                                let kinetic = route.window.screen.mouse.kinetic_scroll;
+                                route
+                                    .window
+                                    .screen
+                                    .on_scroll_wheel_modulated(lpos.x, lpos.y, kinetic);
+                            }
+                            TouchPhase::Ended | TouchPhase::Cancelled => {
+                                route.window.screen.mouse.scroll_ended();
                             }
-                            _ => (),
                         }
                     }
                 }
@@ -913,9 +1057,11 @@ impl ApplicationHandler<EventPayload> for Application {
                 event: key_event,
                 ..
             } => {
-                if route.has_key_wait(&key_event) {
+                let modifiers = route.window.screen.modifiers.state();
+                let key = KeyEvent::from_window_event(&key_event, modifiers);
+                if route.has_key_wait(&key) {
                     if route.path != RoutePath::Terminal
-                        && key_event.state == ElementState::Released
+                        && key.state == KeyState::Released
                     {
                         // Scheduler must be cleaned after leave the terminal route
                         self.scheduler
@@ -925,13 +1071,7 @@ impl ApplicationHandler<EventPayload> for Application {
                 }
 
                 route.window.screen.renderer.last_typing = Some(Instant::now());
-                route.window.screen.process_key_event(&key_event);
-
-                if key_event.state == ElementState::Released
-                    && self.config.hide_cursor_when_typing
-                {
-                    route.window.winit_window.set_cursor_visible(false);
-                }
+                route.window.screen.input_key_event(key);
             }
 
             WindowEvent::Ime(ime) => {
@@ -972,9 +1112,7 @@ impl ApplicationHandler<EventPayload> for Application {
             }
 
             WindowEvent::Focused(focused) => {
-                if self.config.hide_cursor_when_typing {
-                    route.window.winit_window.set_cursor_visible(true);
-                }
+                route.window.screen.set_mouse_visible(true);
 
                 let has_regained_focus = !route.window.is_focused && focused;
                 route.window.is_focused = focused;
@@ -1025,6 +1163,7 @@ impl ApplicationHandler<EventPayload> for Application {
                     .window
                     .screen
                     .set_scale(scale, route.window.winit_window.inner_size());
+                route.window.update_min_inner_size();
             }
 
             WindowEvent::RedrawRequested => {
@@ -1076,6 +1215,9 @@ impl ApplicationHandler<EventPayload> for Application {
         // Renderer and contexts ran.
         self.router.routes.clear();
 
+        // Stop watching the power source.
+        self.power_monitor = None;
+
         // SAFETY: The clipboard must be dropped before the event loop, so use the nop clipboard
         // as a safe placeholder.
         std::mem::swap(
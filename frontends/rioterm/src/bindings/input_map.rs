@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+
+/// Scancode translations applied to key events before bindings are
+/// evaluated, for keyboards that report codes not matching the physical
+/// keys. Scancodes are the platform ones, see `PhysicalKeyExtScancode`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct InputMap {
+    pub remaps: HashMap<u32, u32>,
+}
+
+impl InputMap {
+    /// Map of `bindings.scancode-remaps`, later pairs win.
+    pub fn from_config(remaps: &[[u32; 2]]) -> InputMap {
+        InputMap {
+            remaps: remaps.iter().map(|[from, to]| (*from, *to)).collect(),
+        }
+    }
+
+    /// Scancode that `scancode` stands for, `scancode` itself if it isn't
+    /// remapped.
+    pub fn remap(&self, scancode: Option<u32>) -> Option<u32> {
+        scancode.map(|code| self.remaps.get(&code).copied().unwrap_or(code))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rio_window::keyboard::KeyCode;
+    use rio_window::platform::scancode::PhysicalKeyExtScancode;
+
+    #[test]
+    fn test_remap() {
+        let a = KeyCode::KeyA.to_scancode().unwrap();
+        let b = KeyCode::KeyB.to_scancode().unwrap();
+        let input_map = InputMap {
+            remaps: HashMap::from([(a, b)]),
+        };
+
+        let c = KeyCode::KeyC.to_scancode().unwrap();
+
+        assert_eq!(input_map.remap(Some(a)), Some(b));
+        assert_eq!(input_map.remap(Some(c)), Some(c));
+        assert_eq!(input_map.remap(None), None);
+        assert_eq!(InputMap::default().remap(Some(a)), Some(a));
+    }
+
+    #[test]
+    fn test_from_config() {
+        let input_map = InputMap::from_config(&[[30, 48], [48, 30], [30, 46]]);
+
+        assert_eq!(input_map.remap(Some(30)), Some(46));
+        assert_eq!(input_map.remap(Some(48)), Some(30));
+        assert_eq!(input_map.remap(Some(46)), Some(46));
+    }
+}
@@ -0,0 +1,323 @@
+use rio_window::keyboard::ModifiersState;
+use rio_window::platform::modifier_supplement::KeyEventExtModifierSupplement;
+use rio_window::platform::scancode::PhysicalKeyExtScancode;
+
+/// Key press or release as seen by `Screen::input_key_event`. Window events
+/// are converted once when they come in, everything past that point only
+/// deals with Rio types, so key handling doesn't need a real window to be
+/// driven.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyEvent {
+    /// Key with the modifiers applied, `A` for `Shift + a`.
+    pub logical_key: Key,
+    /// Key without the modifiers applied, `1` for `!`.
+    pub key_without_modifiers: Key,
+    /// Platform scancode of the key position, used by scancode bindings.
+    pub scancode: Option<u32>,
+    /// Text produced by the key with all modifiers applied.
+    pub text: Option<String>,
+    pub location: KeyLocation,
+    pub state: KeyState,
+    pub repeat: bool,
+    /// Modifiers held when the key was pressed or released.
+    pub modifiers: ModifiersState,
+}
+
+impl KeyEvent {
+    /// Convert a key event of the window, `modifiers` being the ones held.
+    pub fn from_window_event(
+        key: &rio_window::event::KeyEvent,
+        modifiers: ModifiersState,
+    ) -> Self {
+        Self {
+            logical_key: Key::from(&key.logical_key),
+            key_without_modifiers: Key::from(&key.key_without_modifiers()),
+            scancode: key.physical_key.to_scancode(),
+            text: key.text_with_all_modifiers().map(String::from),
+            location: KeyLocation::from(key.location),
+            state: KeyState::from(key.state),
+            repeat: key.repeat,
+            modifiers,
+        }
+    }
+
+    #[inline]
+    pub fn text_with_all_modifiers(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+}
+
+/// Whether the key went down or up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyState {
+    Pressed,
+    Released,
+}
+
+impl KeyState {
+    #[inline]
+    pub fn is_pressed(self) -> bool {
+        self == KeyState::Pressed
+    }
+}
+
+impl From<rio_window::event::ElementState> for KeyState {
+    fn from(state: rio_window::event::ElementState) -> Self {
+        match state {
+            rio_window::event::ElementState::Pressed => KeyState::Pressed,
+            rio_window::event::ElementState::Released => KeyState::Released,
+        }
+    }
+}
+
+/// Where the key is on the keyboard, for keys found more than once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyLocation {
+    Standard,
+    Left,
+    Right,
+    Numpad,
+}
+
+impl From<rio_window::keyboard::KeyLocation> for KeyLocation {
+    fn from(location: rio_window::keyboard::KeyLocation) -> Self {
+        match location {
+            rio_window::keyboard::KeyLocation::Standard => KeyLocation::Standard,
+            rio_window::keyboard::KeyLocation::Left => KeyLocation::Left,
+            rio_window::keyboard::KeyLocation::Right => KeyLocation::Right,
+            rio_window::keyboard::KeyLocation::Numpad => KeyLocation::Numpad,
+        }
+    }
+}
+
+/// Meaning of a key, `Str` is only borrowed to match on it, see
+/// [`Key::as_ref`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Key<Str = String> {
+    Named(NamedKey),
+    Character(Str),
+    /// Keys Rio has no use for, like dead keys, their text still comes
+    /// with the event.
+    Unidentified,
+}
+
+impl Key {
+    #[inline]
+    pub fn as_ref(&self) -> Key<&str> {
+        match self {
+            Key::Named(named) => Key::Named(*named),
+            Key::Character(character) => Key::Character(character.as_str()),
+            Key::Unidentified => Key::Unidentified,
+        }
+    }
+}
+
+impl From<&rio_window::keyboard::Key> for Key {
+    fn from(key: &rio_window::keyboard::Key) -> Self {
+        match key {
+            rio_window::keyboard::Key::Named(named) => {
+                NamedKey::from_window(*named).map_or(Key::Unidentified, Key::Named)
+            }
+            rio_window::keyboard::Key::Character(character) => {
+                Key::Character(character.to_string())
+            }
+            _ => Key::Unidentified,
+        }
+    }
+}
+
+macro_rules! named_keys {
+    ($($name:ident),* $(,)?) => {
+        /// Keys without a character that Rio binds or encodes.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum NamedKey {
+            $($name,)*
+        }
+
+        impl NamedKey {
+            fn from_window(key: rio_window::keyboard::NamedKey) -> Option<Self> {
+                match key {
+                    $(rio_window::keyboard::NamedKey::$name => Some(NamedKey::$name),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+named_keys!(
+    Alt,
+    AltGraph,
+    CapsLock,
+    Control,
+    Fn,
+    FnLock,
+    NumLock,
+    ScrollLock,
+    Shift,
+    Symbol,
+    SymbolLock,
+    Meta,
+    Hyper,
+    Super,
+    Enter,
+    Tab,
+    Space,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+    ArrowUp,
+    End,
+    Home,
+    PageDown,
+    PageUp,
+    Backspace,
+    Clear,
+    Copy,
+    Cut,
+    Delete,
+    Insert,
+    Paste,
+    Redo,
+    Undo,
+    ContextMenu,
+    Escape,
+    Pause,
+    PrintScreen,
+    MediaPlay,
+    MediaPause,
+    MediaPlayPause,
+    MediaStop,
+    MediaFastForward,
+    MediaRewind,
+    MediaTrackNext,
+    MediaTrackPrevious,
+    MediaRecord,
+    AudioVolumeDown,
+    AudioVolumeUp,
+    AudioVolumeMute,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
+    F25,
+    F26,
+    F27,
+    F28,
+    F29,
+    F30,
+    F31,
+    F32,
+    F33,
+    F34,
+    F35,
+);
+
+impl NamedKey {
+    /// Text of the named keys that produce one.
+    pub fn to_text(self) -> Option<&'static str> {
+        match self {
+            NamedKey::Enter => Some("\r"),
+            NamedKey::Backspace => Some("\x08"),
+            NamedKey::Tab => Some("\t"),
+            NamedKey::Space => Some(" "),
+            NamedKey::Escape => Some("\x1b"),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::kitty_keyboard::build_key_sequence;
+    use rio_backend::crosswords::Mode;
+
+    /// Key pressed without modifiers, `text` being what it types.
+    fn pressed(key: Key, text: Option<&str>) -> KeyEvent {
+        KeyEvent {
+            logical_key: key.clone(),
+            key_without_modifiers: key,
+            scancode: None,
+            text: text.map(String::from),
+            location: KeyLocation::Standard,
+            state: KeyState::Pressed,
+            repeat: false,
+            modifiers: ModifiersState::empty(),
+        }
+    }
+
+    #[test]
+    fn test_key_from_window() {
+        use rio_window::keyboard::{Key as WindowKey, NamedKey as WindowNamedKey};
+
+        assert_eq!(
+            Key::from(&WindowKey::Named(WindowNamedKey::ArrowUp)),
+            Key::Named(NamedKey::ArrowUp)
+        );
+        assert_eq!(
+            Key::from(&WindowKey::Character("a".into())),
+            Key::Character(String::from("a"))
+        );
+        // Keys Rio doesn't handle.
+        assert_eq!(
+            Key::from(&WindowKey::Named(WindowNamedKey::BrowserBack)),
+            Key::Unidentified
+        );
+        assert_eq!(Key::from(&WindowKey::Dead(Some('~'))), Key::Unidentified);
+    }
+
+    #[test]
+    fn test_build_key_sequence_without_window() {
+        let up = pressed(Key::Named(NamedKey::ArrowUp), None);
+        assert_eq!(
+            build_key_sequence(&up, ModifiersState::empty(), Mode::empty()),
+            b"\x1b[A"
+        );
+        assert_eq!(
+            build_key_sequence(&up, ModifiersState::CONTROL, Mode::empty()),
+            b"\x1b[1;5A"
+        );
+
+        let a = pressed(Key::Character("a".into()), Some("a"));
+        assert_eq!(
+            build_key_sequence(
+                &a,
+                ModifiersState::CONTROL,
+                Mode::KEYBOARD_DISAMBIGUATE_ESC_CODES
+            ),
+            b"\x1b[97;5u"
+        );
+
+        let mut released = a.clone();
+        released.state = KeyState::Released;
+        assert_eq!(
+            build_key_sequence(
+                &released,
+                ModifiersState::empty(),
+                Mode::KEYBOARD_REPORT_ALL_KEYS_AS_ESC | Mode::KEYBOARD_REPORT_EVENT_TYPES
+            ),
+            b"\x1b[97;1:3u"
+        );
+    }
+}
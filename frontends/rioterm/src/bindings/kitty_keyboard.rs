@@ -1,13 +1,9 @@
 // build_key_sequence was originally taken from alacritty
 // which is licensed under Apache 2.0 license.
 
+use crate::bindings::key::{Key, KeyEvent, KeyLocation, KeyState, NamedKey};
 use rio_backend::crosswords::Mode;
-use rio_window::event::{ElementState, KeyEvent};
-use rio_window::keyboard::Key;
-use rio_window::keyboard::KeyLocation;
 use rio_window::keyboard::ModifiersState;
-use rio_window::keyboard::NamedKey;
-use rio_window::platform::modifier_supplement::KeyEventExtModifierSupplement;
 use std::borrow::Cow;
 
 #[inline(never)]
@@ -23,7 +19,7 @@ pub fn build_key_sequence(key: &KeyEvent, mods: ModifiersState, mode: Mode) -> V
     let kitty_encode_all = mode.contains(Mode::KEYBOARD_REPORT_ALL_KEYS_AS_ESC);
     // The default parameter is 1, so we can omit it.
     let kitty_event_type = mode.contains(Mode::KEYBOARD_REPORT_EVENT_TYPES)
-        && (key.repeat || key.state == ElementState::Released);
+        && (key.repeat || key.state == KeyState::Released);
 
     let context = SequenceBuilder {
         mode,
@@ -48,7 +44,7 @@ pub fn build_key_sequence(key: &KeyEvent, mods: ModifiersState, mode: Mode) -> V
 
     let associated_text = text.filter(|text| {
         mode.contains(Mode::KEYBOARD_REPORT_ASSOCIATED_TEXT)
-            && key.state != ElementState::Released
+            && key.state != KeyState::Released
             && !text.is_empty()
             && !is_control_character(text)
     });
@@ -80,8 +76,8 @@ pub fn build_key_sequence(key: &KeyEvent, mods: ModifiersState, mode: Mode) -> V
         payload.push(':');
         let event_type = match key.state {
             _ if key.repeat => '2',
-            ElementState::Pressed => '1',
-            ElementState::Released => '3',
+            KeyState::Pressed => '1',
+            KeyState::Released => '3',
         };
         payload.push(event_type);
     }
@@ -133,7 +129,7 @@ impl SequenceBuilder {
             let mut unicode_key_code = u32::from(base_character);
 
             // Try to get the base for keys which change based on modifier, like `1` for `!`.
-            match key.key_without_modifiers().as_ref() {
+            match key.key_without_modifiers.as_ref() {
                 Key::Character(unmodded) if alternate_key_code == unicode_key_code => {
                     unicode_key_code =
                         u32::from(unmodded.chars().next().unwrap_or(base_character));
@@ -2,19 +2,21 @@
 // was originally taken from https://github.com/alacritty/alacritty/blob/e35e5ad14fce8456afdd89f2b392b9924bb27471/alacritty/src/config/bindings.rs
 // which is licensed under Apache 2.0 license.
 
+pub mod input_map;
+pub mod key;
 pub mod kitty_keyboard;
 
 use crate::crosswords::vi_mode::ViMotion;
 use crate::crosswords::Mode;
 use bitflags::bitflags;
+use key::Key::*;
+use key::NamedKey::*;
+use key::{Key, KeyLocation};
 use rio_backend::config::bindings::KeyBinding as ConfigKeyBinding;
 use rio_backend::config::keyboard::Keyboard as ConfigKeyboard;
 use rio_window::event::MouseButton;
-use rio_window::keyboard::Key::*;
-use rio_window::keyboard::NamedKey::*;
-use rio_window::keyboard::{Key, KeyLocation, ModifiersState, PhysicalKey};
+use rio_window::keyboard::ModifiersState;
 use std::fmt::Debug;
-// use rio_window::platform::scancode::PhysicalKeyExtScancode;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum FontSizeAction {
@@ -137,12 +139,8 @@ impl<T: Eq> Binding<T> {
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub enum BindingKey {
-    #[allow(dead_code)]
-    Scancode(PhysicalKey),
-    Keycode {
-        key: Key,
-        location: KeyLocation,
-    },
+    Scancode(u32),
+    Keycode { key: Key, location: KeyLocation },
 }
 
 pub type KeyBinding = Binding<BindingKey>;
@@ -162,6 +160,7 @@ bitflags! {
         const SEARCH              = 0b0001_0000;
         const DISAMBIGUATE_KEYS   = 0b0010_0000;
         const ALL_KEYS_AS_ESC     = 0b0100_0000;
+        const BACKARROW_BS        = 0b1000_0000;
     }
 }
 
@@ -181,10 +180,30 @@ impl BindingMode {
             mode.contains(Mode::KEYBOARD_REPORT_ALL_KEYS_AS_ESC),
         );
         binding_mode.set(BindingMode::VI, mode.contains(Mode::VI));
+        binding_mode.set(
+            BindingMode::BACKARROW_BS,
+            mode.contains(Mode::BACKARROW_SENDS_BACKSPACE),
+        );
         binding_mode
     }
 }
 
+/// Check whether the key is a modifier, which is only pressed to prepare
+/// a shortcut and shouldn't behave like a regular input.
+#[inline]
+pub fn is_modifier_key(key: &Key) -> bool {
+    matches!(
+        key,
+        Key::Named(Shift)
+            | Key::Named(Control)
+            | Key::Named(Alt)
+            | Key::Named(AltGraph)
+            | Key::Named(Super)
+            | Key::Named(Hyper)
+            | Key::Named(Meta)
+    )
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 #[allow(unused)]
 pub enum Program {
@@ -208,8 +227,40 @@ impl Program {
     }
 }
 
+/// Macro slot of `StartRecordMacro` and `PlayMacro`, `None` (with a
+/// warning) when it is not a number or there is no such slot.
+fn macro_slot(value: &str) -> Option<usize> {
+    match value.trim().parse::<usize>() {
+        Ok(slot) if slot < crate::screen::macros::MACRO_SLOTS => Some(slot),
+        _ => {
+            log::warn!(
+                "invalid macro slot {value:?}, expected a number below {}",
+                crate::screen::macros::MACRO_SLOTS
+            );
+            None
+        }
+    }
+}
+
+/// `AddWordSeparator(ch)` and `RemoveWordSeparator(ch)`, the separator
+/// keeps its case.
+fn word_separator_action(action: &str) -> Option<Action> {
+    let re = regex::Regex::new(r"(?i)^(add|remove)wordseparator\((.)\)$").unwrap();
+    let capture = re.captures(action)?;
+    let ch = capture[2].chars().next()?;
+    if capture[1].eq_ignore_ascii_case("add") {
+        Some(Action::AddWordSeparator(ch))
+    } else {
+        Some(Action::RemoveWordSeparator(ch))
+    }
+}
+
 impl From<String> for Action {
     fn from(action: String) -> Action {
+        if let Some(action) = word_separator_action(&action) {
+            return action;
+        }
+
         let action = action.to_lowercase();
 
         let action_from_string = match action.as_str() {
@@ -231,23 +282,52 @@ impl From<String> for Action {
                 Some(Action::Search(SearchAction::SearchHistoryPrevious))
             }
             "clearhistory" => Some(Action::ClearHistory),
+            "clearscreen" => Some(Action::ClearScreen),
             "resetfontsize" => Some(Action::ResetFontSize),
             "increasefontsize" => Some(Action::IncreaseFontSize),
             "decreasefontsize" => Some(Action::DecreaseFontSize),
             "createwindow" => Some(Action::WindowCreateNew),
             "createtab" => Some(Action::TabCreateNew),
             "closetab" => Some(Action::TabCloseCurrent),
+            "renametab" => Some(Action::TabRename),
             "closeunfocusedtabs" => Some(Action::TabCloseUnfocused),
+            "splithorizontally" => Some(Action::SplitHorizontally),
+            "splitvertically" => Some(Action::SplitVertically),
+            "closepane" => Some(Action::ClosePane),
+            "panefocusnext" | "splitfocusnext" => Some(Action::PaneFocusNext),
+            "panefocusprev" | "splitfocusprev" => Some(Action::PaneFocusPrev),
+            "panegrow" => Some(Action::PaneGrow),
+            "paneshrink" => Some(Action::PaneShrink),
             "openconfigeditor" => Some(Action::ConfigEditor),
             "selectprevtab" => Some(Action::SelectPrevTab),
             "selectnexttab" => Some(Action::SelectNextTab),
             "selectlasttab" => Some(Action::SelectLastTab),
+            "sorttabsbytitle" => Some(Action::SortTabsByTitle),
+            "movetableft" => Some(Action::TabMoveLeft),
+            "movetabright" => Some(Action::TabMoveRight),
             "receivechar" => Some(Action::ReceiveChar),
             "scrollhalfpageup" => Some(Action::ScrollHalfPageUp),
             "scrollhalfpagedown" => Some(Action::ScrollHalfPageDown),
             "scrolltotop" => Some(Action::ScrollToTop),
             "scrolltobottom" => Some(Action::ScrollToBottom),
+            "scrolltocursor" => Some(Action::ScrollToCursor),
+            "scrolltoprevprompt" => Some(Action::ScrollToPrevPrompt),
+            "scrolltonextprompt" => Some(Action::ScrollToNextPrompt),
+            "findselection" => Some(Action::FindSelection),
+            "selectlastoutput" => Some(Action::SelectLastOutput),
+            "togglelinewrap" => Some(Action::ToggleLineWrap),
+            "togglebracketedpastemode" => Some(Action::ToggleBracketedPasteMode),
+            "copyscreenimage" => Some(Action::CopyScreenImage),
             "togglevimode" => Some(Action::ToggleViMode),
+            "unicodeinput" => Some(Action::UnicodeInput),
+            "stoprecordmacro" => Some(Action::StopRecordMacro),
+            "logoutput" => Some(Action::LogOutput),
+            "startrecording" => Some(Action::StartRecording),
+            "stoprecording" => Some(Action::StopRecording),
+            "sessionsave" => Some(Action::SessionSave),
+            "sessionrestore" => Some(Action::SessionRestore),
+            "inspectcell" => Some(Action::InspectCell),
+            "openhyperlink" => Some(Action::OpenHyperlink),
             "none" => Some(Action::None),
             _ => None,
         };
@@ -265,6 +345,22 @@ impl From<String> for Action {
             }
         }
 
+        let re = regex::Regex::new(r"startrecordmacro\(([^()]+)\)").unwrap();
+        for capture in re.captures_iter(&action) {
+            if let Some(matched) = capture.get(1) {
+                return macro_slot(matched.as_str())
+                    .map_or(Action::None, Action::StartRecordMacro);
+            }
+        }
+
+        let re = regex::Regex::new(r"playmacro\(([^()]+)\)").unwrap();
+        for capture in re.captures_iter(&action) {
+            if let Some(matched) = capture.get(1) {
+                return macro_slot(matched.as_str())
+                    .map_or(Action::None, Action::PlayMacro);
+            }
+        }
+
         let re = regex::Regex::new(r"run\(([^()]+)\)").unwrap();
         for capture in re.captures_iter(&action) {
             if let Some(matched) = capture.get(1) {
@@ -365,9 +461,30 @@ pub enum Action {
     /// Scroll all the way to the bottom.
     ScrollToBottom,
 
+    /// Scroll until the terminal cursor line is visible.
+    ScrollToCursor,
+
+    /// Scroll back to the previous prompt marked by OSC 133.
+    ScrollToPrevPrompt,
+
+    /// Scroll forward to the next prompt marked by OSC 133.
+    ScrollToNextPrompt,
+
+    /// Toggle between wrapping and truncating long lines on display.
+    ToggleLineWrap,
+
+    /// Flip bracketed paste mode regardless of the application, for debugging.
+    ToggleBracketedPasteMode,
+
+    /// Save the current frame as a PNG and copy its path.
+    CopyScreenImage,
+
     /// Clear the display buffer(s) to remove history.
     ClearHistory,
 
+    /// Clear the visible lines, keeping history and the cursor line.
+    ClearScreen,
+
     /// Hide the Rio window.
     #[allow(dead_code)]
     Hide,
@@ -413,6 +530,30 @@ pub enum Action {
     /// Close all other tabs (leave only the current tab).
     TabCloseUnfocused,
 
+    /// Type a name for the current tab.
+    TabRename,
+
+    /// Split the focused pane, the new one below.
+    SplitHorizontally,
+
+    /// Split the focused pane, the new one on the right.
+    SplitVertically,
+
+    /// Close the focused pane, the tab if it is the last one.
+    ClosePane,
+
+    /// Focus the next pane of the tab.
+    PaneFocusNext,
+
+    /// Focus the previous pane of the tab.
+    PaneFocusPrev,
+
+    /// Grow the focused pane, moving the separator of its split.
+    PaneGrow,
+
+    /// Shrink the focused pane, moving the separator of its split.
+    PaneShrink,
+
     /// Toggle fullscreen.
     #[allow(dead_code)]
     ToggleFullscreen,
@@ -429,6 +570,10 @@ pub enum Action {
     /// Clear active selection.
     ClearSelection,
 
+    /// Select the output of the last command, using the prompts marked
+    /// by OSC 133.
+    SelectLastOutput,
+
     /// Toggle vi mode.
     ToggleViMode,
 
@@ -436,6 +581,15 @@ pub enum Action {
     SelectTab(usize),
     SelectLastTab,
 
+    /// Sort the tabs alphabetically by title.
+    SortTabsByTitle,
+
+    /// Move the current tab one position left.
+    TabMoveLeft,
+
+    /// Move the current tab one position right.
+    TabMoveRight,
+
     Search(SearchAction),
     /// Start a forward buffer search.
     SearchForward,
@@ -443,6 +597,50 @@ pub enum Action {
     /// Start a backward buffer search.
     SearchBackward,
 
+    /// Start a forward buffer search for the selected text.
+    FindSelection,
+
+    /// Start typing a unicode codepoint by its hex value.
+    UnicodeInput,
+
+    /// Start recording the typed input into a macro slot.
+    StartRecordMacro(usize),
+
+    /// Stop recording and store the macro in its slot.
+    StopRecordMacro,
+
+    /// Send the input recorded in a macro slot to the current tab.
+    PlayMacro(usize),
+
+    /// Treat a character as a word boundary of semantic selection.
+    AddWordSeparator(char),
+
+    /// Stop treating a character as a word boundary of semantic selection.
+    RemoveWordSeparator(char),
+
+    /// Toggle an overlay describing the cell under the mouse, or under
+    /// the vi cursor in vi mode.
+    InspectCell,
+
+    /// Open the hyperlink or URL under the mouse, or under the vi cursor
+    /// in vi mode.
+    OpenHyperlink,
+
+    /// Log the raw output of the current tab, escape sequences included.
+    LogOutput,
+
+    /// Record the input and output of the current tab.
+    StartRecording,
+
+    /// Stop recording and save it as an asciinema file.
+    StopRecording,
+
+    /// Write the content of the current tab to the session file.
+    SessionSave,
+
+    /// Bring the session file back into the scrollback of the current tab.
+    SessionRestore,
+
     /// Allow receiving char input.
     ReceiveChar,
 
@@ -481,6 +679,10 @@ pub enum ViAction {
     ToggleSemanticSelection,
     /// Centers the screen around the vi mode cursor.
     CenterAroundViCursor,
+    /// Move the vi cursor to the next match of the last search.
+    SearchNext,
+    /// Move the vi cursor to the previous match of the last search.
+    SearchPrevious,
 }
 
 macro_rules! bindings {
@@ -552,11 +754,13 @@ pub fn default_mouse_bindings() -> Vec<MouseBinding> {
     )
 }
 
+/// Default bindings with the config ones applied on top, along with the
+/// errors of the config entries that could not be used.
 pub fn default_key_bindings(
     unprocessed_config_key_bindings: Vec<ConfigKeyBinding>,
     use_navigation_key_bindings: bool,
     config_keyboard: ConfigKeyboard,
-) -> Vec<KeyBinding> {
+) -> (Vec<KeyBinding>, Vec<String>) {
     let mut bindings = bindings!(
         KeyBinding;
         Key::Named(Copy);  Action::Copy;
@@ -603,6 +807,8 @@ pub fn default_key_bindings(
         "v", ModifiersState::CONTROL, +BindingMode::VI; ViAction::ToggleBlockSelection;
         "v", ModifiersState::ALT, +BindingMode::VI; ViAction::ToggleSemanticSelection;
         "z", +BindingMode::VI; ViAction::CenterAroundViCursor;
+        "n", +BindingMode::VI, ~BindingMode::SEARCH; ViAction::SearchNext;
+        "n", ModifiersState::SHIFT, +BindingMode::VI, ~BindingMode::SEARCH; ViAction::SearchPrevious;
         "k", +BindingMode::VI; ViMotion::Up;
         "j", +BindingMode::VI; ViMotion::Down;
         "h", +BindingMode::VI; ViMotion::Left;
@@ -656,7 +862,8 @@ pub fn default_key_bindings(
             Key::Named(ArrowDown), ~BindingMode::APP_CURSOR, ~BindingMode::VI, ~BindingMode::SEARCH; Action::Esc("\x1b[B".into());
             Key::Named(ArrowRight), ~BindingMode::APP_CURSOR, ~BindingMode::VI, ~BindingMode::SEARCH; Action::Esc("\x1b[C".into());
             Key::Named(ArrowLeft),  ~BindingMode::APP_CURSOR, ~BindingMode::VI, ~BindingMode::SEARCH; Action::Esc("\x1b[D".into());
-            Key::Named(Backspace), ~BindingMode::VI, ~BindingMode::SEARCH; Action::Esc("\x7f".into());
+            Key::Named(Backspace), ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::BACKARROW_BS; Action::Esc("\x7f".into());
+            Key::Named(Backspace), +BindingMode::BACKARROW_BS, ~BindingMode::VI, ~BindingMode::SEARCH; Action::Esc("\x08".into());
             Key::Named(Insert), ~BindingMode::VI, ~BindingMode::SEARCH; Action::Esc("\x1b[2~".into());
             Key::Named(Delete), ~BindingMode::VI, ~BindingMode::SEARCH; Action::Esc("\x1b[3~".into());
             Key::Named(PageUp), ~BindingMode::VI, ~BindingMode::SEARCH; Action::Esc("\x1b[5~".into());
@@ -796,9 +1003,12 @@ pub fn default_key_bindings(
             Key::Named(Delete),     ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::ALL_KEYS_AS_ESC, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1b[3~".into());
             Key::Named(PageUp),     ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::ALL_KEYS_AS_ESC, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1b[5~".into());
             Key::Named(PageDown),   ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::ALL_KEYS_AS_ESC, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1b[6~".into());
-            Key::Named(Backspace),  ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::ALL_KEYS_AS_ESC; Action::Esc("\x7f".into());
-            Key::Named(Backspace), ModifiersState::ALT,     ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::ALL_KEYS_AS_ESC; Action::Esc("\x1b\x7f".into());
-            Key::Named(Backspace), ModifiersState::SHIFT,   ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::ALL_KEYS_AS_ESC; Action::Esc("\x7f".into());
+            Key::Named(Backspace),  ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::ALL_KEYS_AS_ESC, ~BindingMode::BACKARROW_BS; Action::Esc("\x7f".into());
+            Key::Named(Backspace), ModifiersState::ALT,     ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::ALL_KEYS_AS_ESC, ~BindingMode::BACKARROW_BS; Action::Esc("\x1b\x7f".into());
+            Key::Named(Backspace), ModifiersState::SHIFT,   ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::ALL_KEYS_AS_ESC, ~BindingMode::BACKARROW_BS; Action::Esc("\x7f".into());
+            Key::Named(Backspace),  +BindingMode::BACKARROW_BS, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::ALL_KEYS_AS_ESC; Action::Esc("\x08".into());
+            Key::Named(Backspace), ModifiersState::ALT,     +BindingMode::BACKARROW_BS, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::ALL_KEYS_AS_ESC; Action::Esc("\x1b\x08".into());
+            Key::Named(Backspace), ModifiersState::SHIFT,   +BindingMode::BACKARROW_BS, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::ALL_KEYS_AS_ESC; Action::Esc("\x08".into());
             Key::Named(F1), ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::ALL_KEYS_AS_ESC, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOP".into());
             Key::Named(F2), ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::ALL_KEYS_AS_ESC, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOQ".into());
             Key::Named(F3), ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::ALL_KEYS_AS_ESC, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOR".into());
@@ -822,15 +1032,131 @@ pub struct ModeWrapper {
     pub not_mode: BindingMode,
 }
 
+/// Parse modifiers combined with `|`, e.g. `"control | shift"`.
+pub fn modifiers_from_str(with: &str) -> ModifiersState {
+    let mut res = ModifiersState::empty();
+    for modifier in with.split('|') {
+        match modifier.trim().to_lowercase().as_str() {
+            "command" | "super" => res.insert(ModifiersState::SUPER),
+            "shift" => res.insert(ModifiersState::SHIFT),
+            "alt" | "option" => res.insert(ModifiersState::ALT),
+            "control" => res.insert(ModifiersState::CONTROL),
+            "none" => (),
+            _ => (),
+        }
+    }
+    res
+}
+
+/// Parse `navigation.home-path-modifier`. The modifier is rejected when
+/// every binding that creates a tab already holds it, new tabs would then
+/// always open in the home directory.
+pub fn home_path_modifier(
+    with: &str,
+    bindings: &[KeyBinding],
+) -> Result<ModifiersState, String> {
+    let modifier = modifiers_from_str(with);
+    let mut create_tab = bindings
+        .iter()
+        .filter(|binding| binding.action == Action::TabCreateNew)
+        .peekable();
+
+    if !modifier.is_empty()
+        && create_tab.peek().is_some()
+        && create_tab.all(|binding| binding.mods.contains(modifier))
+    {
+        return Err(format!(
+            "navigation.home-path-modifier \"{with}\" is part of every CreateTab binding, add a binding without it"
+        ));
+    }
+
+    Ok(modifier)
+}
+
 #[inline]
 fn convert(config_key_binding: ConfigKeyBinding) -> Result<KeyBinding, String> {
-    let (key, location) = if config_key_binding.key.chars().count() == 1 {
-        (
-            Key::Character(config_key_binding.key.to_lowercase().into()),
-            KeyLocation::Standard,
-        )
+    let trigger = match config_key_binding
+        .key
+        .to_lowercase()
+        .strip_prefix("scancode:")
+    {
+        Some(scancode) => match scancode.trim().parse::<u32>() {
+            Ok(scancode) => BindingKey::Scancode(scancode),
+            Err(_) => {
+                return Err(format!(
+                    "Invalid scancode in key '{}'",
+                    config_key_binding.key
+                ))
+            }
+        },
+        None => keycode_trigger(&config_key_binding.key)?,
+    };
+
+    let res = modifiers_from_str(&config_key_binding.with);
+
+    let mut action: Action = config_key_binding.action.clone().into();
+    // Bytes win over text when a binding sets both.
+    let bytes = std::str::from_utf8(&config_key_binding.bytes)
+        .ok()
+        .filter(|bytes| !bytes.is_empty());
+    if let Some(bytes) = bytes {
+        action = Action::Esc(bytes.into());
+    } else if !config_key_binding.text.is_empty() {
+        action = Action::Esc(config_key_binding.text);
+    } else if config_key_binding.bytes.is_empty()
+        && action == Action::None
+        && !config_key_binding.action.trim().is_empty()
+        && !config_key_binding
+            .action
+            .trim()
+            .eq_ignore_ascii_case("none")
+    {
+        // Unknown names fall back to `Action::None`, which would silently
+        // unbind the key instead of doing what was asked for.
+        return Err(format!(
+            "Unknown action '{}' for key '{}'",
+            config_key_binding.action, config_key_binding.key
+        ));
+    }
+
+    let mut res_mode = ModeWrapper {
+        mode: BindingMode::empty(),
+        not_mode: BindingMode::empty(),
+    };
+
+    for modifier in config_key_binding.mode.split('|') {
+        match modifier.trim().to_lowercase().as_str() {
+            "appcursor" => res_mode.mode |= BindingMode::APP_CURSOR,
+            "~appcursor" => res_mode.not_mode |= BindingMode::APP_CURSOR,
+            "appkeypad" => res_mode.mode |= BindingMode::APP_KEYPAD,
+            "~appkeypad" => res_mode.not_mode |= BindingMode::APP_KEYPAD,
+            "alt" => res_mode.mode |= BindingMode::ALT_SCREEN,
+            "~alt" => res_mode.not_mode |= BindingMode::ALT_SCREEN,
+            "vi" => res_mode.mode |= BindingMode::VI,
+            "~vi" => res_mode.not_mode |= BindingMode::VI,
+            _ => {
+                res_mode.not_mode |= BindingMode::empty();
+                res_mode.mode |= BindingMode::empty();
+            }
+        }
+    }
+
+    Ok(KeyBinding {
+        trigger,
+        mods: res,
+        action,
+        mode: res_mode.mode,
+        notmode: res_mode.not_mode,
+    })
+}
+
+/// Trigger for a key given by name, see the `key` section of the
+/// bindings documentation.
+fn keycode_trigger(name: &str) -> Result<BindingKey, String> {
+    let (key, location) = if name.chars().count() == 1 {
+        (Key::Character(name.to_lowercase()), KeyLocation::Standard)
     } else {
-        match config_key_binding.key.to_lowercase().as_str() {
+        match name.to_lowercase().as_str() {
             "home" => (Key::Named(Home), KeyLocation::Standard),
             "space" => (Key::Named(Space), KeyLocation::Standard),
             "delete" => (Key::Named(Delete), KeyLocation::Standard),
@@ -893,110 +1219,48 @@ fn convert(config_key_binding: ConfigKeyBinding) -> Result<KeyBinding, String> {
 
             // Special cases
             "tab" => (Key::Named(Tab), KeyLocation::Standard),
-            _ => return Err("Unable to find defined 'keycode'".to_string()),
-        }
-    };
-
-    let trigger = BindingKey::Keycode { key, location };
-
-    let mut res = ModifiersState::empty();
-    for modifier in config_key_binding.with.split('|') {
-        match modifier.trim().to_lowercase().as_str() {
-            "command" | "super" => res.insert(ModifiersState::SUPER),
-            "shift" => res.insert(ModifiersState::SHIFT),
-            "alt" | "option" => res.insert(ModifiersState::ALT),
-            "control" => res.insert(ModifiersState::CONTROL),
-            "none" => (),
-            _ => (),
-        }
-    }
-
-    let mut action: Action = config_key_binding.action.into();
-    if !config_key_binding.text.is_empty() {
-        action = Action::Esc(config_key_binding.text);
-    }
-
-    if !config_key_binding.bytes.is_empty() {
-        if let Ok(str_from_bytes) = std::str::from_utf8(&config_key_binding.bytes) {
-            action = Action::Esc(str_from_bytes.into());
+            _ => return Err(format!("Unable to find defined 'keycode' for '{name}'")),
         }
-    }
-
-    let mut res_mode = ModeWrapper {
-        mode: BindingMode::empty(),
-        not_mode: BindingMode::empty(),
     };
 
-    for modifier in config_key_binding.mode.split('|') {
-        match modifier.trim().to_lowercase().as_str() {
-            "appcursor" => res_mode.mode |= BindingMode::APP_CURSOR,
-            "~appcursor" => res_mode.not_mode |= BindingMode::APP_CURSOR,
-            "appkeypad" => res_mode.mode |= BindingMode::APP_KEYPAD,
-            "~appkeypad" => res_mode.not_mode |= BindingMode::APP_KEYPAD,
-            "alt" => res_mode.mode |= BindingMode::ALT_SCREEN,
-            "~alt" => res_mode.not_mode |= BindingMode::ALT_SCREEN,
-            "vi" => res_mode.mode |= BindingMode::VI,
-            "~vi" => res_mode.not_mode |= BindingMode::VI,
-            _ => {
-                res_mode.not_mode |= BindingMode::empty();
-                res_mode.mode |= BindingMode::empty();
-            }
-        }
-    }
-
-    Ok(KeyBinding {
-        trigger,
-        mods: res,
-        action,
-        mode: res_mode.mode,
-        notmode: res_mode.not_mode,
-    })
+    Ok(BindingKey::Keycode { key, location })
 }
 
+/// Apply the config key bindings on top of `bindings`, entries that fail
+/// to convert are skipped and their error messages returned.
 pub fn config_key_bindings(
     config_key_bindings: Vec<ConfigKeyBinding>,
     mut bindings: Vec<KeyBinding>,
-) -> Vec<KeyBinding> {
-    if config_key_bindings.is_empty() {
-        return bindings;
-    }
+) -> (Vec<KeyBinding>, Vec<String>) {
+    let mut errors = Vec::new();
 
     for ckb in config_key_bindings {
         match convert(ckb) {
-            Ok(key_binding) => match key_binding.action {
-                Action::None | Action::ReceiveChar => {
-                    let mut found_idx = None;
-                    for (idx, binding) in bindings.iter().enumerate() {
-                        if binding.triggers_match(&key_binding) {
-                            found_idx = Some(idx);
-                            break;
-                        }
-                    }
-
-                    if let Some(idx) = found_idx {
-                        bindings.remove(idx);
-                        log::warn!(
-                            "overwritten a previous key_binding with new one: {:?}",
-                            key_binding
-                        );
-                    } else {
-                        log::info!("added a new key_binding: {:?}", key_binding);
-                    }
-
-                    bindings.push(key_binding)
-                }
-                _ => {
+            Ok(key_binding) => {
+                // Later bindings win over defaults and earlier config
+                // entries with the same key, modifiers and mode.
+                let previous = bindings.len();
+                bindings.retain(|binding| !binding.triggers_match(&key_binding));
+
+                if bindings.len() < previous {
+                    log::warn!(
+                        "overwritten a previous key_binding with new one: {:?}",
+                        key_binding
+                    );
+                } else {
                     log::info!("added a new key_binding: {:?}", key_binding);
-                    bindings.push(key_binding)
                 }
-            },
+
+                bindings.push(key_binding)
+            }
             Err(err_message) => {
-                log::error!("error loading a key binding: {:?}", err_message);
+                log::error!("error loading a key binding: {}", err_message);
+                errors.push(err_message);
             }
         }
     }
 
-    bindings
+    (bindings, errors)
 }
 
 // Macos
@@ -1028,6 +1292,7 @@ pub fn platform_key_bindings(
         "q", ModifiersState::SUPER; Action::Quit;
         "n", ModifiersState::SUPER; Action::WindowCreateNew;
         ",", ModifiersState::SUPER; Action::ConfigEditor;
+        "u", ModifiersState::SUPER | ModifiersState::SHIFT; Action::UnicodeInput;
 
         // Search
         "f", ModifiersState::SUPER, ~BindingMode::SEARCH; Action::SearchForward;
@@ -1054,6 +1319,8 @@ pub fn platform_key_bindings(
             "w", ModifiersState::SUPER; Action::TabCloseCurrent;
             "[", ModifiersState::SUPER | ModifiersState::SHIFT; Action::SelectPrevTab;
             "]", ModifiersState::SUPER | ModifiersState::SHIFT; Action::SelectNextTab;
+            Key::Named(ArrowLeft), ModifiersState::SUPER | ModifiersState::SHIFT; Action::TabMoveLeft;
+            Key::Named(ArrowRight), ModifiersState::SUPER | ModifiersState::SHIFT; Action::TabMoveRight;
             "1", ModifiersState::SUPER; Action::SelectTab(0);
             "2", ModifiersState::SUPER; Action::SelectTab(1);
             "3", ModifiersState::SUPER; Action::SelectTab(2);
@@ -1100,6 +1367,7 @@ pub fn platform_key_bindings(
         "-", ModifiersState::CONTROL;  Action::DecreaseFontSize;
         "n", ModifiersState::CONTROL | ModifiersState::SHIFT; Action::WindowCreateNew;
         ",", ModifiersState::CONTROL | ModifiersState::SHIFT; Action::ConfigEditor;
+        "u", ModifiersState::CONTROL | ModifiersState::SHIFT; Action::UnicodeInput;
 
         // Search
         "f", ModifiersState::CONTROL | ModifiersState::SHIFT, ~BindingMode::SEARCH; Action::SearchForward;
@@ -1126,6 +1394,8 @@ pub fn platform_key_bindings(
             "[", ModifiersState::CONTROL | ModifiersState::SHIFT; Action::SelectPrevTab;
             "]", ModifiersState::CONTROL | ModifiersState::SHIFT; Action::SelectNextTab;
             "w", ModifiersState::CONTROL | ModifiersState::SHIFT; Action::TabCloseCurrent;
+            "[", ModifiersState::CONTROL | ModifiersState::SHIFT | ModifiersState::ALT; Action::TabMoveLeft;
+            "]", ModifiersState::CONTROL | ModifiersState::SHIFT | ModifiersState::ALT; Action::TabMoveRight;
         ));
     }
 
@@ -1153,6 +1423,7 @@ pub fn platform_key_bindings(
         Key::Named(Enter), ModifiersState::ALT; Action::ToggleFullscreen;
         "n", ModifiersState::CONTROL | ModifiersState::SHIFT; Action::WindowCreateNew;
         ",", ModifiersState::CONTROL | ModifiersState::SHIFT; Action::ConfigEditor;
+        "u", ModifiersState::CONTROL | ModifiersState::SHIFT; Action::UnicodeInput;
         // This is actually a Windows Powershell shortcut
         // https://github.com/alacritty/alacritty/issues/2930
         // https://github.com/raphamorim/rio/issues/220#issuecomment-1761651339
@@ -1184,6 +1455,8 @@ pub fn platform_key_bindings(
             "w", ModifiersState::CONTROL | ModifiersState::SHIFT; Action::TabCloseCurrent;
             "[", ModifiersState::CONTROL | ModifiersState::SHIFT; Action::SelectPrevTab;
             "]", ModifiersState::CONTROL | ModifiersState::SHIFT; Action::SelectNextTab;
+            "[", ModifiersState::CONTROL | ModifiersState::SHIFT | ModifiersState::ALT; Action::TabMoveLeft;
+            "]", ModifiersState::CONTROL | ModifiersState::SHIFT | ModifiersState::ALT; Action::TabMoveRight;
         ));
     }
 
@@ -1449,6 +1722,178 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn modifier_only_keys() {
+        assert!(is_modifier_key(&Key::Named(Control)));
+        assert!(is_modifier_key(&Key::Named(Shift)));
+        assert!(is_modifier_key(&Key::Named(Super)));
+        assert!(!is_modifier_key(&Key::Named(Enter)));
+        assert!(!is_modifier_key(&Key::Character("c".into())));
+    }
+
+    #[test]
+    fn modifiers_from_config_str() {
+        assert_eq!(modifiers_from_str(""), ModifiersState::empty());
+        assert_eq!(modifiers_from_str("shift"), ModifiersState::SHIFT);
+        assert_eq!(
+            modifiers_from_str("Control | option"),
+            ModifiersState::CONTROL | ModifiersState::ALT
+        );
+    }
+
+    #[test]
+    fn backspace_follows_backarrow_key_mode() {
+        let trigger = BindingKey::Keycode {
+            key: Key::Named(Backspace),
+            location: KeyLocation::Standard,
+        };
+
+        for use_kitty_keyboard_protocol in [false, true] {
+            let (bindings, _) = default_key_bindings(
+                vec![],
+                false,
+                ConfigKeyboard {
+                    use_kitty_keyboard_protocol,
+                    ..ConfigKeyboard::default()
+                },
+            );
+            let action = |mode: BindingMode, mods: ModifiersState| {
+                bindings
+                    .iter()
+                    .find(|binding| binding.is_triggered_by(mode.clone(), mods, &trigger))
+                    .map(|binding| binding.action.clone())
+            };
+
+            let none = ModifiersState::empty();
+            assert_eq!(
+                action(BindingMode::empty(), none),
+                Some(Action::Esc("\x7f".into()))
+            );
+            assert_eq!(
+                action(BindingMode::BACKARROW_BS, none),
+                Some(Action::Esc("\x08".into()))
+            );
+        }
+    }
+
+    #[test]
+    fn control_shift_page_keys_send_escapes() {
+        let keyboard = ConfigKeyboard {
+            use_kitty_keyboard_protocol: false,
+            ..ConfigKeyboard::default()
+        };
+        let (bindings, _) = default_key_bindings(vec![], true, keyboard);
+        let mods = ModifiersState::CONTROL | ModifiersState::SHIFT;
+        let actions = |key| {
+            let trigger = BindingKey::Keycode {
+                key: Key::Named(key),
+                location: KeyLocation::Standard,
+            };
+            bindings
+                .iter()
+                .filter(|binding| {
+                    binding.is_triggered_by(BindingMode::empty(), mods, &trigger)
+                })
+                .map(|binding| binding.action.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(actions(PageUp), vec![Action::Esc("\x1b[5;6~".into())]);
+        assert_eq!(actions(PageDown), vec![Action::Esc("\x1b[6;6~".into())]);
+    }
+
+    #[test]
+    fn macro_actions_from_string() {
+        assert_eq!(
+            Action::from(String::from("StartRecordMacro(2)")),
+            Action::StartRecordMacro(2)
+        );
+        assert_eq!(
+            Action::from(String::from("StopRecordMacro")),
+            Action::StopRecordMacro
+        );
+        assert_eq!(
+            Action::from(String::from("PlayMacro(2)")),
+            Action::PlayMacro(2)
+        );
+        assert_eq!(
+            Action::from(String::from("StartRecordMacro(a)")),
+            Action::None
+        );
+        assert_eq!(Action::from(String::from("PlayMacro(10)")), Action::None);
+    }
+
+    #[test]
+    fn pane_actions_from_string() {
+        assert_eq!(
+            Action::from(String::from("PaneFocusNext")),
+            Action::PaneFocusNext
+        );
+        assert_eq!(
+            Action::from(String::from("PaneFocusPrev")),
+            Action::PaneFocusPrev
+        );
+        assert_eq!(
+            Action::from(String::from("SplitFocusNext")),
+            Action::PaneFocusNext
+        );
+        assert_eq!(Action::from(String::from("PaneGrow")), Action::PaneGrow);
+        assert_eq!(Action::from(String::from("PaneShrink")), Action::PaneShrink);
+    }
+
+    #[test]
+    fn word_separator_actions_from_string() {
+        assert_eq!(
+            Action::from(String::from("AddWordSeparator(-)")),
+            Action::AddWordSeparator('-')
+        );
+        assert_eq!(
+            Action::from(String::from("removewordseparator(X)")),
+            Action::RemoveWordSeparator('X')
+        );
+        assert_eq!(
+            Action::from(String::from("AddWordSeparator())")),
+            Action::AddWordSeparator(')')
+        );
+        assert_eq!(
+            Action::from(String::from("AddWordSeparator(ab)")),
+            Action::None
+        );
+    }
+
+    #[test]
+    fn log_output_action_from_string() {
+        assert_eq!(Action::from(String::from("LogOutput")), Action::LogOutput);
+    }
+
+    #[test]
+    fn recording_actions_from_string() {
+        assert_eq!(
+            Action::from(String::from("StartRecording")),
+            Action::StartRecording
+        );
+        assert_eq!(
+            Action::from(String::from("StopRecording")),
+            Action::StopRecording
+        );
+    }
+
+    #[test]
+    fn prompt_actions_from_string() {
+        assert_eq!(
+            Action::from(String::from("ScrollToPrevPrompt")),
+            Action::ScrollToPrevPrompt
+        );
+        assert_eq!(
+            Action::from(String::from("ScrollToNextPrompt")),
+            Action::ScrollToNextPrompt
+        );
+        assert_eq!(
+            Action::from(String::from("SelectLastOutput")),
+            Action::SelectLastOutput
+        );
+    }
+
     #[test]
     fn bindings_overwrite() {
         let bindings = bindings!(
@@ -1466,9 +1911,132 @@ mod tests {
             mode: String::from(""),
         }];
 
-        let new_bindings = config_key_bindings(config_bindings, bindings);
+        let (new_bindings, errors) = config_key_bindings(config_bindings, bindings);
 
+        assert!(errors.is_empty());
         assert_eq!(new_bindings.len(), 2);
         assert_eq!(new_bindings[1].action, Action::ReceiveChar);
     }
+
+    #[test]
+    fn home_path_modifier_conflicts() {
+        let bindings = bindings!(
+            KeyBinding;
+            "t", ModifiersState::CONTROL | ModifiersState::SHIFT; Action::TabCreateNew;
+        );
+
+        assert_eq!(
+            home_path_modifier("alt", &bindings),
+            Ok(ModifiersState::ALT)
+        );
+        assert_eq!(
+            home_path_modifier("", &bindings),
+            Ok(ModifiersState::empty())
+        );
+        assert!(home_path_modifier("shift", &bindings).is_err());
+
+        // A binding without the modifier lets it pick the home directory.
+        let (bindings, _) = config_key_bindings(
+            vec![config_binding("t", "control", "", "createtab")],
+            bindings,
+        );
+        assert_eq!(
+            home_path_modifier("shift", &bindings),
+            Ok(ModifiersState::SHIFT)
+        );
+    }
+
+    fn config_binding(
+        key: &str,
+        with: &str,
+        mode: &str,
+        action: &str,
+    ) -> ConfigKeyBinding {
+        ConfigKeyBinding {
+            key: String::from(key),
+            action: String::from(action),
+            with: String::from(with),
+            bytes: vec![],
+            text: String::from(""),
+            mode: String::from(mode),
+        }
+    }
+
+    #[test]
+    fn config_bindings_later_entries_win() {
+        let bindings = bindings!(
+            KeyBinding;
+            "q", ModifiersState::SUPER; Action::Quit;
+            ",", ModifiersState::SUPER; Action::ConfigEditor;
+        );
+
+        let config_bindings = vec![
+            config_binding("q", "super", "", "copy"),
+            config_binding("q", "super", "", "paste"),
+            config_binding("t", "control | shift", "~vi", "createtab"),
+            config_binding("scancode:30", "alt", "", "quit"),
+        ];
+
+        let (new_bindings, errors) = config_key_bindings(config_bindings, bindings);
+        assert!(errors.is_empty());
+        assert_eq!(new_bindings.len(), 4);
+        assert_eq!(new_bindings[0].action, Action::ConfigEditor);
+        assert_eq!(new_bindings[1].action, Action::Paste);
+
+        let tab = &new_bindings[2];
+        let t = BindingKey::Keycode {
+            key: Key::Character("t".into()),
+            location: KeyLocation::Standard,
+        };
+        let mods = ModifiersState::CONTROL | ModifiersState::SHIFT;
+        assert_eq!(tab.action, Action::TabCreateNew);
+        assert!(tab.is_triggered_by(BindingMode::empty(), mods, &t));
+        assert!(!tab.is_triggered_by(BindingMode::VI, mods, &t));
+        assert!(!tab.is_triggered_by(BindingMode::empty(), ModifiersState::CONTROL, &t));
+
+        let scancode = BindingKey::Scancode(30);
+        assert!(new_bindings[3].is_triggered_by(
+            BindingMode::empty(),
+            ModifiersState::ALT,
+            &scancode
+        ));
+    }
+
+    #[test]
+    fn config_bindings_invalid_entries() {
+        let err = convert(config_binding("q", "super", "", "qiut")).unwrap_err();
+        assert_eq!(err, "Unknown action 'qiut' for key 'q'");
+
+        let err = convert(config_binding("scancode:x", "", "", "quit")).unwrap_err();
+        assert_eq!(err, "Invalid scancode in key 'scancode:x'");
+
+        assert!(convert(config_binding("unknownkey", "", "", "quit")).is_err());
+        assert_eq!(
+            convert(config_binding("q", "super", "", "None"))
+                .unwrap()
+                .action,
+            Action::None
+        );
+
+        let mut text = config_binding("r", "control", "", "");
+        text.text = String::from("Rio");
+        assert_eq!(
+            convert(text.clone()).unwrap().action,
+            Action::Esc(String::from("Rio"))
+        );
+        text.bytes = vec![27, 91, 65];
+        assert_eq!(
+            convert(text).unwrap().action,
+            Action::Esc(String::from("\x1b[A"))
+        );
+
+        // Invalid entries are skipped and reported, the defaults stay.
+        let bindings = bindings!(KeyBinding; "q", ModifiersState::SUPER; Action::Quit;);
+        let (new_bindings, errors) =
+            config_key_bindings(vec![config_binding("q", "super", "", "qiut")], bindings);
+        assert_eq!(new_bindings.len(), 1);
+        assert_eq!(new_bindings[0].action, Action::Quit);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("qiut"));
+    }
 }
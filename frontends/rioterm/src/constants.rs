@@ -27,3 +27,4 @@ pub const DEADZONE_END_Y: f64 = -2.0;
 
 pub const PADDING_X_COLLAPSED_TABS: f32 = 30.;
 pub const PADDING_Y_BOTTOM_TABS: f32 = 22.0;
+pub const STATUS_LINE_HEIGHT: f32 = 22.0;
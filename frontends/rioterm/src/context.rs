@@ -1,17 +1,22 @@
+pub mod split;
+
 use crate::ansi::CursorShape;
 use crate::crosswords::pos::CursorState;
 use crate::event::sync::FairMutex;
 use crate::event::RioEvent;
 use crate::messenger::Messenger;
 use crate::performer::Machine;
+use rio_backend::clipboard::{Clipboard, ClipboardType};
 use rio_backend::config::Shell;
 use rio_backend::crosswords::CrosswordsSize;
-use rio_backend::crosswords::{Crosswords, MIN_COLUMNS, MIN_LINES};
+use rio_backend::crosswords::{Crosswords, DEFAULT_URL_REGEX, MIN_COLUMNS, MIN_LINES};
 use rio_backend::error::{RioError, RioErrorLevel, RioErrorType};
 use rio_backend::event::EventListener;
 use rio_backend::event::WindowId;
 use rio_backend::sugarloaf::layout::SugarloafLayout;
 use rio_backend::sugarloaf::{font::SugarloafFont, SugarloafErrors};
+use rio_window::window::CursorIcon;
+use split::{pane_layout, PaneRect, SplitDirection, SplitLayout, SplitTree};
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::error::Error;
@@ -27,12 +32,106 @@ const DEFAULT_CONTEXT_CAPACITY: usize = 28;
 
 pub struct Context<T: EventListener> {
     pub route_id: usize,
+    /// Same as `Crosswords::terminal_id`, kept to route terminal events
+    /// without locking the terminal.
+    pub terminal_id: u64,
     pub terminal: Arc<FairMutex<Crosswords<T>>>,
     pub messenger: Messenger,
     #[cfg(not(target_os = "windows"))]
     pub main_fd: Arc<i32>,
     #[cfg(not(target_os = "windows"))]
     pub shell_pid: u32,
+    /// Display soft-wrapped lines wrapped, otherwise they are truncated
+    /// with a marker. Stored content is not affected.
+    pub wrap_lines: bool,
+    /// Cells the truncated lines are scrolled to the right.
+    pub line_scroll: usize,
+    /// Title set by the user, it takes precedence over the one set by the
+    /// application.
+    pub title: Option<String>,
+    /// Other panes of the tab when it is split, the context holding them
+    /// is the focused one.
+    pub panes: Vec<Context<T>>,
+    /// Layout of the panes, `None` when the tab has a single pane.
+    pub split: Option<SplitTree>,
+}
+
+impl<T: EventListener> Context<T> {
+    /// Area of every pane of the tab when it takes `area`, in focus order.
+    pub fn pane_rects(&self, area: PaneRect) -> Vec<(usize, PaneRect)> {
+        match &self.split {
+            Some(split) => split.rects(area),
+            None => vec![(self.route_id, area)],
+        }
+    }
+
+    /// Pane of the tab with `route_id`, the focused one included.
+    pub fn pane(&self, route_id: usize) -> Option<&Context<T>> {
+        if self.route_id == route_id {
+            return Some(self);
+        }
+        self.panes.iter().find(|pane| pane.route_id == route_id)
+    }
+
+    /// Give the focus to the pane with `route_id`. Returns `false` if the
+    /// tab has no such pane or if it is already focused.
+    fn focus_pane(&mut self, route_id: usize) -> bool {
+        let Some(index) = self.panes.iter().position(|pane| pane.route_id == route_id)
+        else {
+            return false;
+        };
+
+        let mut panes = std::mem::take(&mut self.panes);
+        let split = self.split.take();
+        std::mem::swap(self, &mut panes[index]);
+        self.panes = panes;
+        self.split = split;
+        true
+    }
+
+    /// Close the pane with `route_id`, the next one gets the focus if it
+    /// was the focused pane. The last pane of a tab can't be closed this
+    /// way, returns `false` if nothing was closed.
+    fn close_pane(&mut self, route_id: usize) -> bool {
+        let Some(split) = &mut self.split else {
+            return false;
+        };
+
+        let order = split.panes();
+        if !split.remove(route_id) {
+            return false;
+        }
+        if matches!(split, SplitTree::Pane(_)) {
+            self.split = None;
+        }
+
+        if self.route_id == route_id {
+            let position = order.iter().position(|id| *id == route_id).unwrap_or(0);
+            self.focus_pane(order[(position + 1) % order.len()]);
+        }
+        self.panes.retain(|pane| pane.route_id != route_id);
+        true
+    }
+
+    /// Focus the pane after (or before) the focused one, wrapping around.
+    fn focus_pane_by(&mut self, forward: bool) {
+        let Some(split) = &self.split else {
+            return;
+        };
+
+        let order = split.panes();
+        let len = order.len();
+        let position = order
+            .iter()
+            .position(|id| *id == self.route_id)
+            .unwrap_or(0);
+        let next = if forward {
+            (position + 1) % len
+        } else {
+            (position + len - 1) % len
+        };
+        self.focus_pane(order[next]);
+    }
 }
 
 impl<T: rio_backend::event::EventListener> Drop for Context<T> {
@@ -42,6 +141,28 @@ impl<T: rio_backend::event::EventListener> Drop for Context<T> {
     }
 }
 
+/// Read-only snapshot of a context, for drawing tabs outside of the renderer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextInfo {
+    pub index: usize,
+    pub title: String,
+    /// Working directory of the foreground process, refreshed along with
+    /// the titles.
+    pub cwd: Option<String>,
+    pub has_activity: bool,
+    pub has_bell: bool,
+    pub is_active: bool,
+}
+
+/// Binary name of the shell program, used as the default title.
+#[inline]
+fn shell_name(program: &str) -> String {
+    std::path::Path::new(program)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| String::from("tab"))
+}
+
 #[derive(Clone, Default)]
 pub struct ContextManagerConfig {
     pub shell: Shell,
@@ -52,6 +173,21 @@ pub struct ContextManagerConfig {
     pub use_current_path: bool,
     pub is_native: bool,
     pub should_update_titles: bool,
+    /// Environment variables merged with the system environment
+    /// whenever a new context gets spawned (currently unix only).
+    pub env_overrides: HashMap<String, String>,
+    pub show_control_characters: bool,
+    pub max_line_length: usize,
+    pub scrollback_lines: usize,
+    pub alt_sends_escape: bool,
+    pub alt_screen_scrollback: bool,
+    pub disable_alt_screen: bool,
+    pub preserve_scrollback_on_clear: bool,
+    pub parse_budget: usize,
+    /// Word boundaries for semantic selection, `None` keeps the terminal default.
+    pub semantic_escape_chars: Option<String>,
+    /// Regex used to detect URLs, `None` keeps the terminal default.
+    pub url_regex: Option<String>,
 }
 
 pub struct ContextManagerTitles {
@@ -103,6 +239,8 @@ pub struct ContextManager<T: EventListener> {
     window_id: WindowId,
     pub config: ContextManagerConfig,
     pub titles: ContextManagerTitles,
+    /// Columns and lines the contexts were last resized to.
+    grid_size: (usize, usize),
 }
 
 impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
@@ -115,17 +253,24 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
         let size = CrosswordsSize::new(MIN_COLUMNS, MIN_LINES);
         let terminal =
             Crosswords::new(size, CursorShape::Block, event_proxy, window_id, route_id);
+        let terminal_id = terminal.terminal_id();
         let terminal: Arc<FairMutex<Crosswords<T>>> = Arc::new(FairMutex::new(terminal));
         let (sender, _receiver) = corcovado::channel::channel();
 
         Context {
             route_id,
+            terminal_id,
             #[cfg(not(target_os = "windows"))]
             main_fd: Arc::new(-1),
             #[cfg(not(target_os = "windows"))]
             shell_pid: 1,
             messenger: Messenger::new(sender),
             terminal,
+            wrap_lines: true,
+            line_scroll: 0,
+            title: None,
+            panes: Vec::new(),
+            split: None,
         }
     }
 
@@ -149,6 +294,18 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
             route_id,
         );
         terminal.blinking_cursor = cursor_state.1;
+        terminal.show_control_characters = config.show_control_characters;
+        terminal.max_line_length = config.max_line_length;
+        terminal.set_scrollback_lines(config.scrollback_lines);
+        terminal.set_alt_sends_escape(config.alt_sends_escape);
+        terminal.set_alt_screen_scrollback(config.alt_screen_scrollback);
+        terminal.disable_alt_screen = config.disable_alt_screen;
+        terminal.preserve_scrollback_on_clear = config.preserve_scrollback_on_clear;
+        if let Some(chars) = &config.semantic_escape_chars {
+            terminal.set_semantic_escape_chars(chars);
+        }
+        set_url_regex(&mut terminal, config.url_regex.as_deref());
+        let terminal_id = terminal.terminal_id();
         let terminal: Arc<FairMutex<Crosswords<T>>> = Arc::new(FairMutex::new(terminal));
 
         let pty;
@@ -158,6 +315,7 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
                 log::info!("rio -> teletypewriter: create_pty_with_fork");
                 pty = match create_pty_with_fork(
                     &Cow::Borrowed(&config.shell.program),
+                    &config.env_overrides,
                     cols,
                     rows,
                 ) {
@@ -173,6 +331,7 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
                     &Cow::Borrowed(&config.shell.program),
                     config.shell.args.clone(),
                     &config.working_dir,
+                    &config.env_overrides,
                     cols,
                     rows,
                 ) {
@@ -213,6 +372,7 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
             event_proxy.clone(),
             window_id,
             route_id,
+            config.parse_budget,
         )?;
         let channel = machine.channel();
         if config.spawn_performer {
@@ -223,12 +383,18 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
 
         Ok(Context {
             route_id,
+            terminal_id,
             #[cfg(not(target_os = "windows"))]
             main_fd,
             #[cfg(not(target_os = "windows"))]
             shell_pid,
             messenger,
             terminal,
+            wrap_lines: true,
+            line_scroll: 0,
+            title: None,
+            panes: Vec::new(),
+            split: None,
         })
     }
 
@@ -242,6 +408,7 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
         size: SugarloafLayout,
         sugarloaf_errors: Option<SugarloafErrors>,
     ) -> Result<Self, Box<dyn Error>> {
+        let grid_size = (size.columns, size.lines);
         let initial_context = match ContextManager::create_context(
             cursor_state,
             event_proxy.clone(),
@@ -274,7 +441,7 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
 
         let titles = ContextManagerTitles::new(
             0,
-            String::from("tab"),
+            shell_name(&ctx_config.shell.program),
             String::new(),
             ctx_config.working_dir.clone().unwrap_or_default(),
         );
@@ -304,6 +471,7 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
             window_id,
             config: ctx_config,
             titles,
+            grid_size,
         })
     }
 
@@ -325,6 +493,17 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
             is_native: false,
             should_update_titles: false,
             use_current_path: false,
+            env_overrides: HashMap::new(),
+            show_control_characters: false,
+            max_line_length: 0,
+            scrollback_lines: rio_backend::crosswords::DEFAULT_SCROLLBACK_LINES,
+            alt_sends_escape: true,
+            alt_screen_scrollback: false,
+            disable_alt_screen: false,
+            preserve_scrollback_on_clear: false,
+            parse_budget: rio_backend::config::defaults::default_parse_budget(),
+            semantic_escape_chars: None,
+            url_regex: None,
         };
         let initial_context = ContextManager::create_context(
             (&CursorState::new('_'), false),
@@ -348,6 +527,7 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
             window_id,
             config,
             titles,
+            grid_size: (MIN_COLUMNS, MIN_LINES),
         })
     }
 
@@ -365,6 +545,19 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
         // However if the tab is killed by Pty and not a tab action then
         // it means we need to clean the context with the specified route_id.
         // If there's no context then should return true and kill the window.
+        //
+        // A pane of a split tab is closed without touching the tabs.
+        if let Some(context) = self
+            .contexts
+            .iter_mut()
+            .find(|ctx| ctx.pane(route_id).is_some())
+        {
+            if context.close_pane(route_id) {
+                self.current_route = self.contexts[self.current_index].route_id;
+                return false;
+            }
+        }
+
         if !self.contexts.is_empty() {
             if let Some(index_to_remove) = self
                 .contexts
@@ -397,6 +590,40 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
             .send_event(RioEvent::PrepareRender(scheduled_time), self.window_id);
     }
 
+    #[inline]
+    pub fn schedule_startup_script(&mut self, scheduled_time: u64) {
+        self.event_proxy.send_event(
+            RioEvent::PrepareStartupScript(scheduled_time),
+            self.window_id,
+        );
+    }
+
+    #[inline]
+    pub fn schedule_motion_report(&self, scheduled_time: u64) {
+        self.event_proxy.send_event(
+            RioEvent::PrepareMotionReport(scheduled_time),
+            self.window_id,
+        );
+    }
+
+    #[inline]
+    pub fn schedule_clipboard_poll(&self, interval: u64) {
+        self.event_proxy
+            .send_event(RioEvent::PrepareClipboardPoll(interval), self.window_id);
+    }
+
+    #[inline]
+    pub fn set_cursor_icon(&self, icon: CursorIcon) {
+        self.event_proxy
+            .send_event(RioEvent::SetCursorIcon(icon), self.window_id);
+    }
+
+    #[inline]
+    pub fn set_cursor_visible(&self, visible: bool) {
+        self.event_proxy
+            .send_event(RioEvent::SetCursorVisible(visible), self.window_id);
+    }
+
     // #[inline]
     pub fn schedule_render_on_route(&mut self, scheduled_time: u64) {
         // PrepareRender will force a render for any route that is focused on window
@@ -422,6 +649,60 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
         }
     }
 
+    /// Read the clipboard without blocking, the text comes back with
+    /// `RioEvent::ClipboardPaste(paste_id, _)`, or `None` once the request
+    /// times out.
+    pub fn load_clipboard_async(
+        &self,
+        clipboard: &Clipboard,
+        ty: ClipboardType,
+        paste_id: u64,
+    ) {
+        let event_proxy = self.event_proxy.clone();
+        let window_id = self.window_id;
+        clipboard.get_async(ty, move |text| {
+            event_proxy
+                .send_event(RioEvent::ClipboardPaste(paste_id, Some(text)), window_id);
+        });
+        self.event_proxy
+            .send_event(RioEvent::PrepareClipboardPaste(paste_id), self.window_id);
+    }
+
+    #[inline]
+    pub fn report_error_clipboard_paste_timeout(&self) {
+        self.event_proxy.send_event(
+            RioEvent::ReportToAssistant(RioError {
+                report: RioErrorType::ClipboardPasteTimeout,
+                level: RioErrorLevel::Warning,
+            }),
+            self.window_id,
+        );
+    }
+
+    #[inline]
+    pub fn report_error_key_bindings(&self, errors: Vec<String>) {
+        if !errors.is_empty() {
+            self.event_proxy.send_event(
+                RioEvent::ReportToAssistant(RioError {
+                    report: RioErrorType::InvalidKeyBindings(errors),
+                    level: RioErrorLevel::Warning,
+                }),
+                self.window_id,
+            );
+        }
+    }
+
+    #[inline]
+    pub fn report_error_hyperlink_open_failed(&self, message: String) {
+        self.event_proxy.send_event(
+            RioEvent::ReportToAssistant(RioError {
+                report: RioErrorType::HyperlinkOpenFailed(message),
+                level: RioErrorLevel::Warning,
+            }),
+            self.window_id,
+        );
+    }
+
     #[inline]
     pub fn create_new_window(&self) {
         self.event_proxy
@@ -448,6 +729,18 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
         self.set_current(tab_index);
     }
 
+    #[inline]
+    pub fn update_font_size(&self, action: u8) {
+        self.event_proxy
+            .send_event(RioEvent::UpdateFontSize(action), self.window_id);
+    }
+
+    #[inline]
+    pub fn update_render_scale(&self) {
+        self.event_proxy
+            .send_event(RioEvent::UpdateRenderScale, self.window_id);
+    }
+
     #[inline]
     pub fn toggle_full_screen(&mut self) {
         self.event_proxy
@@ -494,6 +787,144 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
             .send_event(RioEvent::CreateConfigEditor, self.window_id);
     }
 
+    /// Inject an environment variable into the next spawned contexts,
+    /// already running contexts are not affected.
+    #[inline]
+    #[allow(unused)]
+    pub fn set_env(&mut self, key: &str, value: &str) {
+        self.config
+            .env_overrides
+            .insert(key.to_string(), value.to_string());
+    }
+
+    /// Set the title of the current context, overriding the one set by
+    /// the application.
+    #[inline]
+    pub fn set_current_title(&mut self, title: &str) {
+        self.current_mut().title = Some(title.to_string());
+        self.event_proxy
+            .send_event(RioEvent::Title(self.current_title()), self.window_id);
+    }
+
+    /// Drop the title set by the user, the application one is used again.
+    #[inline]
+    pub fn clear_current_title(&mut self) {
+        self.current_mut().title = None;
+        self.event_proxy
+            .send_event(RioEvent::Title(self.current_title()), self.window_id);
+    }
+
+    /// Title of the current context: the one set by the user, then the one
+    /// set by the application and lastly the shell name.
+    pub fn current_title(&self) -> String {
+        let context = self.current();
+        if let Some(title) = &context.title {
+            return title.to_owned();
+        }
+
+        let terminal_title = context.terminal.lock().application_title().to_string();
+        if terminal_title.is_empty() {
+            shell_name(&self.config.shell.program)
+        } else {
+            terminal_title
+        }
+    }
+
+    /// Snapshot of every context. Each terminal is locked only to copy
+    /// its title and flags, the working directory comes from the titles
+    /// cache so it is cheap enough to call on every frame.
+    pub fn contexts_info(&self) -> Vec<ContextInfo> {
+        self.contexts
+            .iter()
+            .enumerate()
+            .map(|(index, context)| {
+                let (terminal_title, has_activity, has_bell) = {
+                    let terminal = context.terminal.lock();
+                    (
+                        terminal.application_title().to_string(),
+                        terminal.has_activity,
+                        terminal.bell,
+                    )
+                };
+
+                let cached = self.titles.titles.get(&index);
+                let title = match &context.title {
+                    Some(title) => title.to_owned(),
+                    None if !terminal_title.is_empty() => terminal_title,
+                    None => cached
+                        .map(|[program, _, _]| program.to_owned())
+                        .filter(|program| !program.is_empty())
+                        .unwrap_or_else(|| shell_name(&self.config.shell.program)),
+                };
+
+                ContextInfo {
+                    index,
+                    title,
+                    cwd: cached
+                        .map(|[_, _, path]| path.to_owned())
+                        .filter(|path| !path.is_empty()),
+                    has_activity,
+                    has_bell,
+                    is_active: index == self.current_index,
+                }
+            })
+            .collect()
+    }
+
+    /// Sort the tabs alphabetically by title, ignoring case. Tabs with the
+    /// same title keep their order and the current one stays selected.
+    pub fn sort_contexts_by_title(&mut self) {
+        let keys: Vec<String> = self
+            .contexts_info()
+            .into_iter()
+            .map(|info| info.title.to_lowercase())
+            .collect();
+        let mut order: Vec<usize> = (0..self.contexts.len()).collect();
+        order.sort_by(|a, b| keys[*a].cmp(&keys[*b]));
+
+        let mut contexts: Vec<Option<Context<T>>> =
+            self.contexts.drain(..).map(Some).collect();
+        let mut titles = std::mem::take(&mut self.titles.titles);
+        for (new_index, old_index) in order.iter().enumerate() {
+            if let Some(context) = contexts[*old_index].take() {
+                self.contexts.push(context);
+            }
+            if let Some(title) = titles.remove(old_index) {
+                self.titles.titles.insert(new_index, title);
+            }
+        }
+
+        self.current_index = order
+            .iter()
+            .position(|index| *index == self.current_index)
+            .unwrap_or(0);
+    }
+
+    /// Swap the tabs at `a` and `b`, the current tab stays selected.
+    /// Indexes out of bounds are ignored.
+    pub fn swap_contexts(&mut self, a: usize, b: usize) {
+        let len = self.contexts.len();
+        if a >= len || b >= len || a == b {
+            return;
+        }
+
+        self.contexts.swap(a, b);
+        let title_a = self.titles.titles.remove(&a);
+        let title_b = self.titles.titles.remove(&b);
+        if let Some(title) = title_a {
+            self.titles.titles.insert(b, title);
+        }
+        if let Some(title) = title_b {
+            self.titles.titles.insert(a, title);
+        }
+
+        if self.current_index == a {
+            self.current_index = b;
+        } else if self.current_index == b {
+            self.current_index = a;
+        }
+    }
+
     #[inline]
     pub fn len(&self) -> usize {
         self.contexts.len()
@@ -523,9 +954,9 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
                     .map(|p| p.to_string_lossy().to_string())
                     .unwrap_or_default();
 
-                    let terminal_title = {
-                        let terminal = context.terminal.lock();
-                        terminal.title.to_string()
+                    let terminal_title = match &context.title {
+                        Some(title) => title.to_owned(),
+                        None => context.terminal.lock().application_title().to_string(),
                     };
 
                     if self.config.is_native {
@@ -552,17 +983,17 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
             if self.titles.last_title_update.elapsed() > Duration::from_secs(2) {
                 self.titles.last_title_update = Instant::now();
                 let mut id = String::from("");
-                for (i, _context) in self.contexts.iter().enumerate() {
-                    let program = self.config.shell.program.to_owned();
-                    let empty_string = String::from("");
-
-                    id = id.to_owned() + &(format!("{}{}{};", i, program, empty_string));
-                    self.titles.set_key_val(
-                        i,
-                        program,
-                        empty_string.clone(),
-                        empty_string,
-                    );
+                for (i, context) in self.contexts.iter().enumerate() {
+                    let program = shell_name(&self.config.shell.program);
+                    let terminal_title = match &context.title {
+                        Some(title) => title.to_owned(),
+                        None => context.terminal.lock().application_title().to_string(),
+                    };
+
+                    id =
+                        id.to_owned() + &(format!("{}{}{};", i, program, terminal_title));
+                    self.titles
+                        .set_key_val(i, program, terminal_title, String::new());
                 }
                 self.titles.set_key(id);
             }
@@ -574,6 +1005,13 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
         &self.contexts
     }
 
+    /// Every terminal of the window, the panes of split tabs included.
+    pub fn all_contexts(&self) -> impl Iterator<Item = &Context<T>> {
+        self.contexts
+            .iter()
+            .flat_map(|context| std::iter::once(context).chain(&context.panes))
+    }
+
     #[cfg(test)]
     pub fn increase_capacity(&mut self, inc_val: usize) {
         self.capacity += inc_val;
@@ -587,6 +1025,16 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
         }
     }
 
+    /// Close the focused pane of a split tab. Returns `false` if the tab
+    /// has a single pane, closing it means closing the tab.
+    #[inline]
+    pub fn close_current_pane(&mut self) -> bool {
+        let route_id = self.current_route;
+        let closed = self.current_mut().close_pane(route_id);
+        self.current_route = self.current().route_id;
+        closed
+    }
+
     #[inline]
     pub fn close_current_context(&mut self) {
         if self.contexts.len() == 1 {
@@ -623,7 +1071,7 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
         self.current_index
     }
 
-    #[inline]
+    #[cfg(test)]
     pub fn current_route(&self) -> usize {
         self.current_route
     }
@@ -638,6 +1086,170 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
         &mut self.contexts[self.current_index]
     }
 
+    /// Context an event should go to: the one of the terminal that sent
+    /// it, or the current one for window events. `None` if that terminal
+    /// was closed in the meantime.
+    #[inline]
+    pub fn event_context_mut(
+        &mut self,
+        terminal_id: Option<u64>,
+    ) -> Option<&mut Context<T>> {
+        match terminal_id {
+            Some(terminal_id) => self.contexts.iter_mut().find_map(|context| {
+                if context.terminal_id == terminal_id {
+                    return Some(context);
+                }
+                context
+                    .panes
+                    .iter_mut()
+                    .find(|pane| pane.terminal_id == terminal_id)
+            }),
+            None => Some(self.current_mut()),
+        }
+    }
+
+    /// Whether the terminal with `route_id` is shown, either as the current
+    /// tab or as one of its panes.
+    #[inline]
+    pub fn is_visible_route(&self, route_id: usize) -> bool {
+        self.current().pane(route_id).is_some()
+    }
+
+    /// Resize every pane to its share of `layout` and send the new size
+    /// to the shells, unchanged sizes are skipped by the messenger.
+    pub fn resize_all(&mut self, layout: &SugarloafLayout) {
+        self.grid_size = (layout.columns, layout.lines);
+        let area = PaneRect::new(layout.columns, layout.lines);
+        for context in self.contexts.iter() {
+            // Each pane of a split tab gets its share of the window.
+            for (route_id, rect) in context.pane_rects(area) {
+                let Some(pane) = context.pane(route_id) else {
+                    continue;
+                };
+
+                let pane_layout = pane_layout(layout, rect);
+                let mut terminal = pane.terminal.lock();
+                terminal.resize::<SugarloafLayout>(pane_layout);
+                drop(terminal);
+                let winsize = crate::renderer::utils::terminal_dimensions(&pane_layout);
+                let _ = pane.messenger.send_resize(winsize);
+            }
+        }
+    }
+
+    /// Whether `layout` has another grid than the one the contexts were
+    /// last resized to. Cell dimensions are only measured while rendering,
+    /// so font size changes and the first frame change the grid afterwards.
+    #[inline]
+    pub fn grid_changed(&self, layout: &SugarloafLayout) -> bool {
+        (layout.columns, layout.lines) != self.grid_size
+    }
+
+    /// Split the focused pane of the current tab, the new pane gets the
+    /// focus. Nothing happens if one of the panes would be too small.
+    pub fn split(
+        &mut self,
+        direction: SplitDirection,
+        layout: SugarloafLayout,
+        cursor_state: (&CursorState, bool),
+    ) {
+        let current = self.current();
+        let route_id = self.acc_current_route + 1;
+        let mut split = current
+            .split
+            .clone()
+            .unwrap_or(SplitTree::Pane(current.route_id));
+        split.split(current.route_id, route_id, direction);
+
+        let rects = split.rects(PaneRect::new(layout.columns, layout.lines));
+        if rects.iter().any(|(_, rect)| !rect.is_usable()) {
+            return;
+        }
+        let Some((_, rect)) = rects.into_iter().find(|(id, _)| *id == route_id) else {
+            return;
+        };
+
+        let mut cloned_config = self.config.clone();
+        if let Some(working_dir) = self.current_working_dir() {
+            cloned_config.working_dir = Some(working_dir);
+        }
+
+        self.acc_current_route = route_id;
+        match ContextManager::create_context(
+            cursor_state,
+            self.event_proxy.clone(),
+            self.window_id,
+            route_id,
+            pane_layout(&layout, rect),
+            &cloned_config,
+        ) {
+            Ok(new_context) => {
+                let current = self.current_mut();
+                current.split = Some(split);
+                current.panes.push(new_context);
+                current.focus_pane(route_id);
+                self.current_route = route_id;
+            }
+            Err(..) => {
+                log::error!("not able to create a new context");
+            }
+        }
+    }
+
+    #[inline]
+    pub fn select_next_split(&mut self) {
+        self.current_mut().focus_pane_by(true);
+        self.current_route = self.current().route_id;
+    }
+
+    #[inline]
+    pub fn select_prev_split(&mut self) {
+        self.current_mut().focus_pane_by(false);
+        self.current_route = self.current().route_id;
+    }
+
+    /// Give the focus to the pane of the current tab with `route_id`.
+    #[inline]
+    pub fn select_split(&mut self, route_id: usize) -> bool {
+        let focused = self.current_mut().focus_pane(route_id);
+        self.current_route = self.current().route_id;
+        focused
+    }
+
+    /// How the split holding the focused pane divides its area.
+    #[inline]
+    pub fn split_layout(&self) -> SplitLayout {
+        let current = self.current();
+        current.split.as_ref().map_or(SplitLayout::Single, |split| {
+            split.layout_of(current.route_id)
+        })
+    }
+
+    /// Grow the focused pane by `delta` of the split holding it, a negative
+    /// `delta` shrinks it. Nothing happens if one of the panes would be too
+    /// small, returns `false` if the panes didn't change.
+    pub fn resize_split(&mut self, delta: f32, layout: &SugarloafLayout) -> bool {
+        if self.split_layout() == SplitLayout::Single {
+            return false;
+        }
+
+        let current = self.current_mut();
+        // Resized on a copy, it is dropped if a pane gets too small.
+        let Some(mut split) = current.split.clone() else {
+            return false;
+        };
+        if !split.resize(current.route_id, delta) {
+            return false;
+        }
+
+        let rects = split.rects(PaneRect::new(layout.columns, layout.lines));
+        if rects.iter().any(|(_, rect)| !rect.is_usable()) {
+            return false;
+        }
+        current.split = Some(split);
+        true
+    }
+
     #[inline]
     pub fn switch_to_next(&mut self) {
         if self.config.is_native {
@@ -679,6 +1291,13 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
         layout: SugarloafLayout,
         cursor_state: (&CursorState, bool),
     ) {
+        let working_dir = self.current_working_dir();
+        self.add_context_in(redirect, layout, cursor_state, working_dir);
+    }
+
+    /// Working directory of the current terminal when new terminals should
+    /// start from it (`use-current-path`).
+    fn current_working_dir(&self) -> Option<String> {
         let mut working_dir = None;
         if self.config.use_current_path && self.config.working_dir.is_none() {
             #[cfg(not(target_os = "windows"))]
@@ -701,7 +1320,19 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
                 working_dir = None;
             }
         }
+        working_dir
+    }
 
+    /// Same as `add_context` but with an explicit working directory, a `None`
+    /// value falls back to the configured one.
+    #[inline]
+    pub fn add_context_in(
+        &mut self,
+        redirect: bool,
+        layout: SugarloafLayout,
+        cursor_state: (&CursorState, bool),
+        working_dir: Option<String>,
+    ) {
         if self.config.is_native {
             self.event_proxy
                 .send_event(RioEvent::CreateNativeTab(working_dir), self.window_id);
@@ -741,6 +1372,19 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
     }
 }
 
+/// Use `url_regex` to detect URLs, or the default regex if it is `None`.
+/// An invalid regex is reported and the default one is used instead.
+pub fn set_url_regex<T: EventListener>(
+    terminal: &mut Crosswords<T>,
+    url_regex: Option<&str>,
+) {
+    let url_regex = url_regex.unwrap_or(DEFAULT_URL_REGEX);
+    if let Err(err) = terminal.set_url_regex(url_regex) {
+        log::warn!("invalid url-regex {url_regex:?}: {err}");
+        let _ = terminal.set_url_regex(DEFAULT_URL_REGEX);
+    }
+}
+
 pub fn process_open_url(
     mut shell: Shell,
     mut working_dir: Option<String>,
@@ -775,6 +1419,7 @@ pub fn process_open_url(
 pub mod test {
     use super::*;
     use crate::event::VoidListener;
+    use rio_backend::crosswords::grid::Dimensions;
 
     #[test]
     fn test_capacity() {
@@ -790,6 +1435,146 @@ pub mod test {
         assert_eq!(context_manager.capacity, 8);
     }
 
+    #[test]
+    fn test_shell_name() {
+        assert_eq!(shell_name("/bin/zsh"), "zsh");
+        assert_eq!(shell_name("fish"), "fish");
+        assert_eq!(shell_name(""), "tab");
+    }
+
+    #[test]
+    fn test_set_current_title() {
+        let window_id: WindowId = WindowId::from(0);
+
+        let mut context_manager =
+            ContextManager::start_with_capacity(5, VoidListener {}, window_id).unwrap();
+        assert_eq!(
+            context_manager.current_title(),
+            shell_name(&context_manager.config.shell.program)
+        );
+
+        context_manager.current().terminal.lock().title = String::from("vim");
+        assert_eq!(context_manager.current_title(), "vim");
+
+        context_manager.set_current_title("notes");
+        assert_eq!(context_manager.current_title(), "notes");
+
+        // Other contexts keep their own title.
+        context_manager.add_context(
+            true,
+            SugarloafLayout::default(),
+            (&CursorState::new('_'), false),
+        );
+        assert_eq!(context_manager.current_index, 1);
+        assert_eq!(context_manager.current().title, None);
+    }
+
+    #[test]
+    fn test_event_context_mut() {
+        let window_id: WindowId = WindowId::from(0);
+
+        let mut context_manager =
+            ContextManager::start_with_capacity(5, VoidListener {}, window_id).unwrap();
+        context_manager.add_context(
+            false,
+            SugarloafLayout::default(),
+            (&CursorState::new('_'), false),
+        );
+        let first = context_manager.contexts[0].terminal_id;
+        let second = context_manager.contexts[1].terminal_id;
+        assert_ne!(first, second);
+        assert_eq!(
+            context_manager.contexts[1].terminal.lock().terminal_id(),
+            second
+        );
+
+        // Events go to the terminal that sent them, not the current one.
+        context_manager.set_current(0);
+        let context = context_manager.event_context_mut(Some(second)).unwrap();
+        assert_eq!(context.terminal_id, second);
+        let context = context_manager.event_context_mut(None).unwrap();
+        assert_eq!(context.terminal_id, first);
+        assert!(context_manager.event_context_mut(Some(u64::MAX)).is_none());
+    }
+
+    #[test]
+    fn test_contexts_info() {
+        let window_id: WindowId = WindowId::from(0);
+
+        let mut context_manager =
+            ContextManager::start_with_capacity(5, VoidListener {}, window_id).unwrap();
+        context_manager.add_context(
+            false,
+            SugarloafLayout::default(),
+            (&CursorState::new('_'), false),
+        );
+        context_manager.contexts[1].terminal.lock().title = String::from("vim");
+        context_manager.contexts[1].terminal.lock().bell = true;
+
+        let info = context_manager.contexts_info();
+        assert_eq!(info.len(), 2);
+        assert_eq!(info[0].index, 0);
+        assert!(info[0].is_active);
+        assert!(!info[0].has_bell);
+        assert_eq!(
+            info[0].title,
+            shell_name(&context_manager.config.shell.program)
+        );
+        assert_eq!(info[1].index, 1);
+        assert!(!info[1].is_active);
+        assert!(info[1].has_bell);
+        assert_eq!(info[1].title, "vim");
+    }
+
+    #[test]
+    fn test_sort_contexts_by_title() {
+        let window_id: WindowId = WindowId::from(0);
+
+        let mut context_manager =
+            ContextManager::start_with_capacity(5, VoidListener {}, window_id).unwrap();
+        for _ in 0..3 {
+            context_manager.add_context(
+                false,
+                SugarloafLayout::default(),
+                (&CursorState::new('_'), false),
+            );
+        }
+        for (index, title) in ["vim", "Logs", "htop", "build"].iter().enumerate() {
+            context_manager.contexts[index].terminal.lock().title = title.to_string();
+        }
+        context_manager.set_current(1);
+        let route = context_manager.current_route();
+
+        context_manager.sort_contexts_by_title();
+        let titles: Vec<String> = context_manager
+            .contexts_info()
+            .into_iter()
+            .map(|info| info.title)
+            .collect();
+        assert_eq!(titles, ["build", "htop", "Logs", "vim"]);
+        assert_eq!(context_manager.current_index(), 2);
+        assert_eq!(context_manager.current_route(), route);
+        assert_eq!(context_manager.current_title(), "Logs");
+    }
+
+    #[test]
+    fn test_set_env() {
+        let window_id: WindowId = WindowId::from(0);
+
+        let mut context_manager =
+            ContextManager::start_with_capacity(5, VoidListener {}, window_id).unwrap();
+        assert!(context_manager.config.env_overrides.is_empty());
+
+        context_manager.set_env("COLORTERM", "24bit");
+        context_manager.set_env("COLORTERM", "truecolor");
+        context_manager.set_env("TERM_PROGRAM", "rio");
+        assert_eq!(context_manager.config.env_overrides.len(), 2);
+        assert_eq!(
+            context_manager.config.env_overrides.get("COLORTERM"),
+            Some(&String::from("truecolor"))
+        );
+    }
+
     #[test]
     fn test_add_context() {
         let window_id: WindowId = WindowId::from(0);
@@ -1049,4 +1834,276 @@ pub mod test {
         context_manager.switch_to_next();
         assert_eq!(context_manager.current_index, 1);
     }
+
+    #[test]
+    fn test_new_contexts_preserve_scrollback_on_clear() {
+        use rio_backend::performer::handler::ParserProcessor;
+
+        let window_id: WindowId = WindowId::from(0);
+        let mut context_manager =
+            ContextManager::start_with_capacity(5, VoidListener {}, window_id).unwrap();
+        context_manager.config.preserve_scrollback_on_clear = true;
+        let layout = SugarloafLayout {
+            columns: 10,
+            lines: 2,
+            ..SugarloafLayout::default()
+        };
+        context_manager.add_context(false, layout, (&CursorState::new('_'), false));
+
+        let mut terminal = context_manager.contexts[1].terminal.lock();
+        let mut parser = ParserProcessor::new();
+        for byte in b"1\r\n2\r\n3\r\n4\x1b[3J" {
+            parser.advance(&mut *terminal, *byte);
+        }
+
+        // `CSI 3 J` keeps the scrollback.
+        assert_eq!(terminal.grid.history_size(), 2);
+    }
+
+    #[test]
+    fn test_new_contexts_semantic_separators() {
+        use crate::crosswords::pos::{Column, Line, Pos, Side};
+        use crate::selection::{Selection, SelectionType};
+        use rio_backend::performer::handler::ParserProcessor;
+
+        let window_id: WindowId = WindowId::from(0);
+        let mut context_manager =
+            ContextManager::start_with_capacity(5, VoidListener {}, window_id).unwrap();
+        context_manager.config.semantic_escape_chars = Some(String::from("/"));
+        let layout = SugarloafLayout {
+            columns: 10,
+            lines: 2,
+            ..SugarloafLayout::default()
+        };
+        context_manager.add_context(false, layout, (&CursorState::new('_'), false));
+
+        let mut terminal = context_manager.contexts[1].terminal.lock();
+        let mut parser = ParserProcessor::new();
+        for byte in b"foo/bar" {
+            parser.advance(&mut *terminal, *byte);
+        }
+
+        // Double click only stops at the configured separators, the blank
+        // end of the line is part of the word without the space separator.
+        let pos = Pos::new(Line(0), Column(5));
+        let selection = Selection::new(SelectionType::Semantic, pos, Side::Left);
+        let range = selection.to_range(&terminal).unwrap();
+        assert_eq!((range.start.col, range.end.col), (Column(4), Column(9)));
+    }
+
+    #[test]
+    fn test_resize_all() {
+        let window_id: WindowId = WindowId::from(0);
+
+        let mut context_manager =
+            ContextManager::start_with_capacity(5, VoidListener {}, window_id).unwrap();
+        let mut layout = SugarloafLayout {
+            columns: 81,
+            lines: 24,
+            ..SugarloafLayout::default()
+        };
+        let size = |context_manager: &ContextManager<VoidListener>, index: usize| {
+            let context = &context_manager.contexts[index];
+            let terminal = context.terminal.lock();
+            (terminal.columns(), terminal.screen_lines())
+        };
+
+        assert!(context_manager.grid_changed(&layout));
+        context_manager.resize_all(&layout);
+        assert!(!context_manager.grid_changed(&layout));
+        assert_eq!(size(&context_manager, 0), (81, 24));
+
+        // A font size change keeps the window but changes the grid once
+        // the cells are measured, every tab and pane follows.
+        context_manager.add_context(false, layout, (&CursorState::new('_'), false));
+        context_manager.split(
+            SplitDirection::Right,
+            layout,
+            (&CursorState::new('_'), false),
+        );
+        layout.columns = 101;
+        layout.lines = 30;
+        assert!(context_manager.grid_changed(&layout));
+        context_manager.resize_all(&layout);
+        assert!(!context_manager.grid_changed(&layout));
+        assert_eq!(size(&context_manager, 1), (101, 30));
+
+        let split = &context_manager.contexts[0];
+        let rects = split.pane_rects(PaneRect::new(101, 30));
+        assert_eq!(rects.len(), 2);
+        for (route_id, rect) in rects {
+            let terminal = split.pane(route_id).unwrap().terminal.lock();
+            assert_eq!(
+                (terminal.columns(), terminal.screen_lines()),
+                (rect.columns, rect.lines)
+            );
+        }
+    }
+
+    #[test]
+    fn test_split_panes() {
+        let window_id: WindowId = WindowId::from(0);
+
+        let mut context_manager =
+            ContextManager::start_with_capacity(5, VoidListener {}, window_id).unwrap();
+        let layout = SugarloafLayout {
+            columns: 81,
+            lines: 24,
+            ..SugarloafLayout::default()
+        };
+        let first = context_manager.current_route();
+
+        context_manager.split(
+            SplitDirection::Right,
+            layout,
+            (&CursorState::new('_'), false),
+        );
+        let second = context_manager.current_route();
+        assert_ne!(first, second);
+        context_manager.split(
+            SplitDirection::Down,
+            layout,
+            (&CursorState::new('_'), false),
+        );
+        let third = context_manager.current_route();
+
+        assert_eq!(context_manager.len(), 1);
+        assert_eq!(context_manager.current().panes.len(), 2);
+        assert!(context_manager.is_visible_route(first));
+        let mut all_routes: Vec<usize> = context_manager
+            .all_contexts()
+            .map(|context| context.route_id)
+            .collect();
+        all_routes.sort();
+        assert_eq!(all_routes, vec![first, second, third]);
+        let rects = context_manager
+            .current()
+            .pane_rects(PaneRect::new(layout.columns, layout.lines));
+        assert_eq!(
+            rects.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![first, second, third]
+        );
+
+        context_manager.select_next_split();
+        assert_eq!(context_manager.current_route(), first);
+        context_manager.select_prev_split();
+        assert_eq!(context_manager.current_route(), third);
+        assert!(context_manager.select_split(second));
+        assert!(!context_manager.select_split(second));
+
+        // A pane that exits is closed without closing the tab.
+        assert!(!context_manager.should_close_context_manager(first));
+        assert!(!context_manager.is_visible_route(first));
+        assert_eq!(context_manager.current_route(), second);
+
+        // The next pane gets the focus.
+        assert!(context_manager.close_current_pane());
+        assert_eq!(context_manager.current_route(), third);
+        assert!(context_manager.current().split.is_none());
+        assert!(context_manager.current().panes.is_empty());
+
+        // Closing the last pane means closing the tab.
+        assert!(!context_manager.close_current_pane());
+        assert_eq!(context_manager.len(), 1);
+    }
+
+    #[test]
+    fn test_split_too_small() {
+        let window_id: WindowId = WindowId::from(0);
+
+        let mut context_manager =
+            ContextManager::start_with_capacity(5, VoidListener {}, window_id).unwrap();
+        let layout = SugarloafLayout {
+            columns: 80,
+            lines: 1,
+            ..SugarloafLayout::default()
+        };
+
+        context_manager.split(
+            SplitDirection::Down,
+            layout,
+            (&CursorState::new('_'), false),
+        );
+        assert!(context_manager.current().split.is_none());
+        assert!(context_manager.current().panes.is_empty());
+    }
+
+    #[test]
+    fn test_resize_split() {
+        let window_id: WindowId = WindowId::from(0);
+
+        let mut context_manager =
+            ContextManager::start_with_capacity(5, VoidListener {}, window_id).unwrap();
+        let layout = SugarloafLayout {
+            columns: 11,
+            lines: 24,
+            ..SugarloafLayout::default()
+        };
+        assert_eq!(context_manager.split_layout(), SplitLayout::Single);
+        assert!(!context_manager.resize_split(0.25, &layout));
+
+        context_manager.split(
+            SplitDirection::Right,
+            layout,
+            (&CursorState::new('_'), false),
+        );
+        assert_eq!(
+            context_manager.split_layout(),
+            SplitLayout::VSplit { ratio: 0.5 }
+        );
+        // The new pane on the right is focused, shrinking it moves the
+        // separator to the right.
+        assert!(context_manager.resize_split(-0.25, &layout));
+        assert_eq!(
+            context_manager.split_layout(),
+            SplitLayout::VSplit { ratio: 0.75 }
+        );
+        let rects = context_manager
+            .current()
+            .pane_rects(PaneRect::new(layout.columns, layout.lines));
+        assert_eq!(rects[1].1.columns, 3);
+
+        // The right pane would be too small for a terminal.
+        assert!(!context_manager.resize_split(-0.25, &layout));
+        assert_eq!(
+            context_manager.split_layout(),
+            SplitLayout::VSplit { ratio: 0.75 }
+        );
+    }
+
+    #[test]
+    fn test_swap_contexts() {
+        let window_id: WindowId = WindowId::from(0);
+
+        let mut context_manager =
+            ContextManager::start_with_capacity(5, VoidListener {}, window_id).unwrap();
+        for _ in 0..2 {
+            context_manager.add_context(
+                false,
+                SugarloafLayout::default(),
+                (&CursorState::new('_'), false),
+            );
+        }
+        let routes: Vec<usize> = context_manager
+            .contexts
+            .iter()
+            .map(|context| context.route_id)
+            .collect();
+        context_manager.set_current(1);
+
+        context_manager.swap_contexts(1, 2);
+        assert_eq!(context_manager.current_index(), 2);
+        assert_eq!(context_manager.current_route(), routes[1]);
+        assert_eq!(context_manager.contexts[1].route_id, routes[2]);
+
+        context_manager.swap_contexts(0, 1);
+        assert_eq!(context_manager.current_index(), 2);
+        assert_eq!(context_manager.contexts[0].route_id, routes[2]);
+        assert_eq!(context_manager.contexts[1].route_id, routes[0]);
+
+        // Out of bounds swaps are ignored.
+        context_manager.swap_contexts(2, 3);
+        assert_eq!(context_manager.current_index(), 2);
+        assert_eq!(context_manager.contexts[2].route_id, routes[1]);
+    }
 }
@@ -0,0 +1,447 @@
+use rio_backend::crosswords::{MIN_COLUMNS, MIN_LINES};
+use rio_backend::sugarloaf::layout::SugarloafLayout;
+
+/// Where the new pane goes when the current one is split.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitDirection {
+    /// Side by side, the new pane on the right.
+    Right,
+    /// Stacked, the new pane below.
+    Down,
+}
+
+/// Area of a pane in the window grid, in cells.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PaneRect {
+    pub column: usize,
+    pub line: usize,
+    pub columns: usize,
+    pub lines: usize,
+}
+
+impl PaneRect {
+    pub fn new(columns: usize, lines: usize) -> Self {
+        Self {
+            column: 0,
+            line: 0,
+            columns,
+            lines,
+        }
+    }
+
+    #[inline]
+    pub fn contains(&self, column: usize, line: usize) -> bool {
+        column >= self.column
+            && column < self.column + self.columns
+            && line >= self.line
+            && line < self.line + self.lines
+    }
+
+    /// Split the area in two with a one cell separator between them, the
+    /// first part gets `ratio` of the remaining cells, rounded down.
+    pub fn split(&self, direction: SplitDirection, ratio: f32) -> (PaneRect, PaneRect) {
+        let part = |size: usize| {
+            let available = size.saturating_sub(1);
+            let first = ((available as f32 * ratio) as usize).min(available);
+            (first, size.saturating_sub(first + 1))
+        };
+
+        match direction {
+            SplitDirection::Right => {
+                let (first, second) = part(self.columns);
+                (
+                    PaneRect {
+                        columns: first,
+                        ..*self
+                    },
+                    PaneRect {
+                        column: self.column + first + 1,
+                        columns: second,
+                        ..*self
+                    },
+                )
+            }
+            SplitDirection::Down => {
+                let (first, second) = part(self.lines);
+                (
+                    PaneRect {
+                        lines: first,
+                        ..*self
+                    },
+                    PaneRect {
+                        line: self.line + first + 1,
+                        lines: second,
+                        ..*self
+                    },
+                )
+            }
+        }
+    }
+
+    /// Whether the area is big enough for a terminal.
+    #[inline]
+    pub fn is_usable(&self) -> bool {
+        self.columns >= MIN_COLUMNS && self.lines >= MIN_LINES
+    }
+}
+
+/// Share of the area the first pane of a new split gets.
+pub const DEFAULT_SPLIT_RATIO: f32 = 0.5;
+
+/// Share of the area a pane grows or shrinks by at once.
+pub const SPLIT_RATIO_STEP: f32 = 0.05;
+
+/// Bounds of a split ratio, so neither pane disappears.
+const MIN_SPLIT_RATIO: f32 = 0.1;
+const MAX_SPLIT_RATIO: f32 = 0.9;
+
+/// How the split holding a pane divides its area.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SplitLayout {
+    /// The pane is alone in the tab.
+    Single,
+    /// Stacked, `ratio` is the share of the top pane.
+    HSplit { ratio: f32 },
+    /// Side by side, `ratio` is the share of the left pane.
+    VSplit { ratio: f32 },
+}
+
+impl SplitLayout {
+    fn new(direction: SplitDirection, ratio: f32) -> Self {
+        match direction {
+            SplitDirection::Right => SplitLayout::VSplit { ratio },
+            SplitDirection::Down => SplitLayout::HSplit { ratio },
+        }
+    }
+}
+
+/// Panes of a tab, leaves are the `route_id` of the contexts.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SplitTree {
+    Pane(usize),
+    Split {
+        direction: SplitDirection,
+        /// Share of the area taken by `first`.
+        ratio: f32,
+        first: Box<SplitTree>,
+        second: Box<SplitTree>,
+    },
+}
+
+impl SplitTree {
+    /// Replace `pane` by a split of `pane` and `new_pane`. Returns `false`
+    /// if `pane` is not part of the tree.
+    pub fn split(
+        &mut self,
+        pane: usize,
+        new_pane: usize,
+        direction: SplitDirection,
+    ) -> bool {
+        match self {
+            SplitTree::Pane(id) if *id == pane => {
+                *self = SplitTree::Split {
+                    direction,
+                    ratio: DEFAULT_SPLIT_RATIO,
+                    first: Box::new(SplitTree::Pane(pane)),
+                    second: Box::new(SplitTree::Pane(new_pane)),
+                };
+                true
+            }
+            SplitTree::Pane(_) => false,
+            SplitTree::Split { first, second, .. } => {
+                first.split(pane, new_pane, direction)
+                    || second.split(pane, new_pane, direction)
+            }
+        }
+    }
+
+    /// Remove `pane`, its sibling takes the space of their split. The last
+    /// pane can't be removed. Returns `false` if nothing was removed.
+    pub fn remove(&mut self, pane: usize) -> bool {
+        let SplitTree::Split { first, second, .. } = self else {
+            return false;
+        };
+
+        let sibling = match (first.as_ref(), second.as_ref()) {
+            (SplitTree::Pane(id), _) if *id == pane => second.as_ref().clone(),
+            (_, SplitTree::Pane(id)) if *id == pane => first.as_ref().clone(),
+            _ => return first.remove(pane) || second.remove(pane),
+        };
+
+        *self = sibling;
+        true
+    }
+
+    /// Panes in focus order, left to right and top to bottom.
+    pub fn panes(&self) -> Vec<usize> {
+        match self {
+            SplitTree::Pane(id) => vec![*id],
+            SplitTree::Split { first, second, .. } => {
+                let mut panes = first.panes();
+                panes.extend(second.panes());
+                panes
+            }
+        }
+    }
+
+    /// Layout of the innermost split holding `pane`, `Single` if `pane` is
+    /// not split.
+    pub fn layout_of(&self, pane: usize) -> SplitLayout {
+        let SplitTree::Split {
+            direction,
+            ratio,
+            first,
+            second,
+        } = self
+        else {
+            return SplitLayout::Single;
+        };
+
+        if matches!(**first, SplitTree::Pane(id) if id == pane)
+            || matches!(**second, SplitTree::Pane(id) if id == pane)
+        {
+            return SplitLayout::new(*direction, *ratio);
+        }
+        match first.layout_of(pane) {
+            SplitLayout::Single => second.layout_of(pane),
+            layout => layout,
+        }
+    }
+
+    /// Grow `pane` by `delta` of the innermost split holding it, shrinking
+    /// its sibling, within bounds. Returns `false` if `pane` is not split
+    /// or the split is already at its bound.
+    pub fn resize(&mut self, pane: usize, delta: f32) -> bool {
+        let SplitTree::Split {
+            ratio,
+            first,
+            second,
+            ..
+        } = self
+        else {
+            return false;
+        };
+
+        let delta = if matches!(**first, SplitTree::Pane(id) if id == pane) {
+            delta
+        } else if matches!(**second, SplitTree::Pane(id) if id == pane) {
+            -delta
+        } else {
+            return first.resize(pane, delta) || second.resize(pane, delta);
+        };
+        let resized = (*ratio + delta).clamp(MIN_SPLIT_RATIO, MAX_SPLIT_RATIO);
+        if resized == *ratio {
+            return false;
+        }
+        *ratio = resized;
+        true
+    }
+
+    /// Area of every pane when the tab takes `area`, in focus order.
+    pub fn rects(&self, area: PaneRect) -> Vec<(usize, PaneRect)> {
+        match self {
+            SplitTree::Pane(id) => vec![(*id, area)],
+            SplitTree::Split {
+                direction,
+                ratio,
+                first,
+                second,
+            } => {
+                let (first_area, second_area) = area.split(*direction, *ratio);
+                let mut rects = first.rects(first_area);
+                rects.extend(second.rects(second_area));
+                rects
+            }
+        }
+    }
+}
+
+/// Layout for a terminal taking `rect` of the window grid. The pixel
+/// size is scaled down so the pty gets the size of the pane.
+pub fn pane_layout(layout: &SugarloafLayout, rect: PaneRect) -> SugarloafLayout {
+    let mut pane_layout = *layout;
+    let text_width = layout.width - layout.margin.x * 2.;
+    let text_height = layout.height - layout.margin.top_y - layout.margin.bottom_y;
+
+    pane_layout.columns = rect.columns;
+    pane_layout.lines = rect.lines;
+    pane_layout.width = layout.margin.x * 2.
+        + text_width * rect.columns as f32 / layout.columns.max(1) as f32;
+    pane_layout.height = layout.margin.top_y
+        + layout.margin.bottom_y
+        + text_height * rect.lines as f32 / layout.lines.max(1) as f32;
+    pane_layout
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_rect() {
+        let area = PaneRect::new(81, 24);
+
+        let (left, right) = area.split(SplitDirection::Right, DEFAULT_SPLIT_RATIO);
+        assert_eq!(left, PaneRect::new(40, 24));
+        assert_eq!(
+            right,
+            PaneRect {
+                column: 41,
+                line: 0,
+                columns: 40,
+                lines: 24
+            }
+        );
+
+        let (top, bottom) = right.split(SplitDirection::Down, DEFAULT_SPLIT_RATIO);
+        assert_eq!(
+            top,
+            PaneRect {
+                column: 41,
+                line: 0,
+                columns: 40,
+                lines: 11
+            }
+        );
+        assert_eq!(
+            bottom,
+            PaneRect {
+                column: 41,
+                line: 12,
+                columns: 40,
+                lines: 12
+            }
+        );
+        assert!(bottom.contains(41, 12));
+        assert!(!bottom.contains(40, 12));
+        assert!(!bottom.contains(41, 24));
+
+        // Too small to fit two terminals.
+        let (left, right) =
+            PaneRect::new(4, 1).split(SplitDirection::Right, DEFAULT_SPLIT_RATIO);
+        assert_eq!((left.columns, right.columns), (1, 2));
+        assert!(!left.is_usable());
+        assert!(right.is_usable());
+        assert!(!PaneRect::new(80, 1)
+            .split(SplitDirection::Down, DEFAULT_SPLIT_RATIO)
+            .0
+            .is_usable());
+    }
+
+    #[test]
+    fn test_split_tree() {
+        let mut tree = SplitTree::Pane(1);
+        assert!(!tree.remove(1));
+        assert!(!tree.split(2, 3, SplitDirection::Right));
+
+        assert!(tree.split(1, 2, SplitDirection::Right));
+        assert!(tree.split(2, 3, SplitDirection::Down));
+        assert_eq!(tree.panes(), vec![1, 2, 3]);
+
+        let rects = tree.rects(PaneRect::new(21, 11));
+        assert_eq!(
+            rects,
+            vec![
+                (1, PaneRect::new(10, 11)),
+                (
+                    2,
+                    PaneRect {
+                        column: 11,
+                        line: 0,
+                        columns: 10,
+                        lines: 5
+                    }
+                ),
+                (
+                    3,
+                    PaneRect {
+                        column: 11,
+                        line: 6,
+                        columns: 10,
+                        lines: 5
+                    }
+                ),
+            ]
+        );
+
+        // The sibling takes the space back.
+        assert!(tree.remove(2));
+        assert_eq!(
+            tree.rects(PaneRect::new(21, 11)),
+            vec![
+                (1, PaneRect::new(10, 11)),
+                (
+                    3,
+                    PaneRect {
+                        column: 11,
+                        line: 0,
+                        columns: 10,
+                        lines: 11
+                    }
+                ),
+            ]
+        );
+
+        assert!(tree.remove(1));
+        assert_eq!(tree, SplitTree::Pane(3));
+    }
+
+    #[test]
+    fn test_split_ratio() {
+        let mut tree = SplitTree::Pane(1);
+        assert_eq!(tree.layout_of(1), SplitLayout::Single);
+        assert!(!tree.resize(1, 0.1));
+
+        assert!(tree.split(1, 2, SplitDirection::Right));
+        assert!(tree.split(2, 3, SplitDirection::Down));
+        assert_eq!(tree.layout_of(1), SplitLayout::VSplit { ratio: 0.5 });
+        assert_eq!(tree.layout_of(3), SplitLayout::HSplit { ratio: 0.5 });
+        assert_eq!(tree.layout_of(4), SplitLayout::Single);
+
+        // Only the innermost split holding the pane changes, growing the
+        // second pane shrinks the first.
+        assert!(tree.resize(3, 0.25));
+        assert_eq!(tree.layout_of(2), SplitLayout::HSplit { ratio: 0.25 });
+        assert_eq!(tree.layout_of(1), SplitLayout::VSplit { ratio: 0.5 });
+        assert!(tree.resize(1, -0.25));
+
+        let rects = tree.rects(PaneRect::new(21, 11));
+        assert_eq!(rects[0], (1, PaneRect::new(5, 11)));
+        assert_eq!(
+            rects[1].1,
+            PaneRect {
+                column: 6,
+                line: 0,
+                columns: 15,
+                lines: 2
+            }
+        );
+
+        // Neither pane disappears.
+        assert!(tree.resize(1, -1.));
+        assert_eq!(tree.layout_of(1), SplitLayout::VSplit { ratio: 0.1 });
+        assert!(tree.resize(1, 2.));
+        assert_eq!(tree.layout_of(1), SplitLayout::VSplit { ratio: 0.9 });
+        // Already at its bound.
+        assert!(!tree.resize(1, 0.1));
+    }
+
+    #[test]
+    fn test_pane_layout() {
+        let mut layout = SugarloafLayout {
+            columns: 80,
+            lines: 20,
+            width: 820.,
+            height: 430.,
+            ..SugarloafLayout::default()
+        };
+        layout.margin.x = 10.;
+        layout.margin.top_y = 20.;
+        layout.margin.bottom_y = 10.;
+
+        let pane = pane_layout(&layout, PaneRect::new(40, 10));
+        assert_eq!((pane.columns, pane.lines), (40, 10));
+        assert_eq!(pane.width, 420.);
+        assert_eq!(pane.height, 230.);
+    }
+}
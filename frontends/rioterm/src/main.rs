@@ -17,6 +17,7 @@ mod mouse;
 #[cfg(windows)]
 mod panic;
 mod platform;
+mod power;
 mod renderer;
 mod router;
 mod routes;
@@ -38,11 +39,11 @@ use windows_sys::Win32::System::Console::{
 
 pub fn setup_environment_variables(config: &rio_backend::config::Config) {
     #[cfg(unix)]
-    let terminfo = if teletypewriter::terminfo_exists("rio") {
-        "rio"
-    } else {
-        "xterm-256color"
-    };
+    let terminfo = rio_backend::config::resolve_term(
+        &config.term,
+        config.keyboard.use_kitty_keyboard_protocol,
+        teletypewriter::terminfo_exists,
+    );
 
     #[cfg(unix)]
     {
@@ -1,14 +1,21 @@
-use crate::event::Msg;
+use crate::event::{Msg, OutputFilter};
+use rio_backend::recording::SharedRecording;
 use std::borrow::Cow;
+use std::cell::Cell;
 use teletypewriter::WinsizeBuilder;
 
 pub struct Messenger {
     channel: corcovado::channel::Sender<Msg>,
+    /// Last size sent to the PTY, to not signal the child without changes.
+    last_size: Cell<Option<WinsizeBuilder>>,
 }
 
 impl Messenger {
     pub fn new(channel: corcovado::channel::Sender<Msg>) -> Messenger {
-        Messenger { channel }
+        Messenger {
+            channel,
+            last_size: Cell::new(None),
+        }
     }
 
     #[inline]
@@ -29,9 +36,83 @@ impl Messenger {
 
     #[inline]
     pub fn send_resize(&self, new_size: WinsizeBuilder) -> Result<&str, String> {
+        if self.last_size.get() == Some(new_size) {
+            return Ok("Unchanged");
+        }
+
         match self.channel.send(Msg::Resize(new_size)) {
-            Ok(..) => Ok("Resized"),
+            Ok(..) => {
+                self.last_size.set(Some(new_size));
+                Ok("Resized")
+            }
             Err(..) => Err("Error sending message".to_string()),
         }
     }
+
+    #[inline]
+    pub fn send_output_filter(&self, filter: OutputFilter) {
+        let _ = self.channel.send(Msg::OutputFilter(filter));
+    }
+
+    #[inline]
+    pub fn send_recording(&self, recording: Option<SharedRecording>) {
+        let _ = self.channel.send(Msg::Recording(recording));
+    }
+
+    #[inline]
+    pub fn send_parse_budget(&self, budget: usize) {
+        let _ = self.channel.send(Msg::ParseBudget(budget));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn winsize(cols: u16, rows: u16) -> WinsizeBuilder {
+        WinsizeBuilder {
+            rows,
+            cols,
+            width: cols * 10,
+            height: rows * 20,
+        }
+    }
+
+    fn received_resizes(receiver: &corcovado::channel::Receiver<Msg>) -> usize {
+        let mut count = 0;
+        while let Ok(msg) = receiver.try_recv() {
+            if let Msg::Resize(..) = msg {
+                count += 1;
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn test_send_resize_only_on_change() {
+        let (sender, receiver) = corcovado::channel::channel();
+        let messenger = Messenger::new(sender);
+
+        assert_eq!(messenger.send_resize(winsize(80, 24)), Ok("Resized"));
+        assert_eq!(messenger.send_resize(winsize(80, 24)), Ok("Unchanged"));
+        assert_eq!(received_resizes(&receiver), 1);
+
+        // Font size changes keep the window size but change the grid.
+        assert_eq!(messenger.send_resize(winsize(100, 30)), Ok("Resized"));
+        // Pixel size changes are sent too, for the applications using it.
+        let mut size = winsize(100, 30);
+        size.width += 1;
+        assert_eq!(messenger.send_resize(size), Ok("Resized"));
+        assert_eq!(received_resizes(&receiver), 2);
+    }
+
+    #[test]
+    fn test_send_resize_failure_is_not_remembered() {
+        let (sender, receiver) = corcovado::channel::channel();
+        let messenger = Messenger::new(sender);
+        drop(receiver);
+
+        assert!(messenger.send_resize(winsize(80, 24)).is_err());
+        assert_eq!(messenger.last_size.get(), None);
+    }
 }
@@ -5,7 +5,10 @@ use crate::event::ClickState;
 use rio_backend::crosswords::pos::Pos;
 use rio_window::event::ElementState;
 use rio_window::event::MouseButton;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+
+/// Momentum scroll starts right after the finger is lifted from the trackpad.
+const KINETIC_SCROLL_DELAY: Duration = Duration::from_millis(100);
 
 #[derive(Default, Debug)]
 pub struct AccumulatedScroll {
@@ -31,6 +34,16 @@ pub struct Mouse {
     pub inside_text_area: bool,
     pub x: usize,
     pub y: usize,
+    /// Whether the current scroll gesture is momentum (kinetic) scroll.
+    pub kinetic_scroll: bool,
+    /// Whether the pointer is shown, it gets hidden while typing.
+    pub mouse_visible: bool,
+    /// Hide the pointer while typing, `hide-mouse-cursor-when-typing`.
+    pub hide_when_typing: bool,
+    last_scroll_end: Option<Instant>,
+    last_motion_report: Option<Instant>,
+    /// Button code of the latest motion held back by the throttle.
+    pub pending_motion: Option<u8>,
 }
 
 impl Default for Mouse {
@@ -49,6 +62,12 @@ impl Default for Mouse {
             accumulated_scroll: AccumulatedScroll::default(),
             x: Default::default(),
             y: Default::default(),
+            kinetic_scroll: false,
+            mouse_visible: true,
+            hide_when_typing: false,
+            last_scroll_end: None,
+            last_motion_report: None,
+            pending_motion: None,
         }
     }
 }
@@ -67,6 +86,74 @@ impl Mouse {
         self.multiplier = multiplier;
         self.divider = divider;
     }
+
+    /// Show or hide the pointer, returns whether it changed. Hiding only
+    /// happens with `hide_when_typing`.
+    #[inline]
+    pub fn set_visible(&mut self, visible: bool) -> bool {
+        if !visible && !self.hide_when_typing {
+            return false;
+        }
+
+        let changed = self.mouse_visible != visible;
+        self.mouse_visible = visible;
+        changed
+    }
+
+    /// Whether the motion with `button` can be reported at `now`. Motion
+    /// coming faster than `throttle` is held back so it doesn't flood the
+    /// pty, only the latest one is kept to be reported once the throttle
+    /// window ends.
+    pub fn throttle_motion(
+        &mut self,
+        button: u8,
+        throttle: Duration,
+        now: Instant,
+    ) -> MotionThrottle {
+        if let Some(last) = self.last_motion_report {
+            let elapsed = now.saturating_duration_since(last);
+            if elapsed < throttle {
+                return match self.pending_motion.replace(button) {
+                    None => MotionThrottle::Schedule(throttle - elapsed),
+                    Some(_) => MotionThrottle::Held,
+                };
+            }
+        }
+
+        self.last_motion_report = Some(now);
+        self.pending_motion = None;
+        MotionThrottle::Report
+    }
+
+    /// A scroll gesture started, it is kinetic when it follows the end of
+    /// a finger gesture right away.
+    #[inline]
+    pub fn scroll_started(&mut self) {
+        self.kinetic_scroll = self
+            .last_scroll_end
+            .take()
+            .is_some_and(|ended| ended.elapsed() < KINETIC_SCROLL_DELAY);
+    }
+
+    #[inline]
+    pub fn scroll_ended(&mut self) {
+        self.last_scroll_end = if self.kinetic_scroll {
+            None
+        } else {
+            Some(Instant::now())
+        };
+        self.kinetic_scroll = false;
+    }
+}
+
+/// Outcome of [`Mouse::throttle_motion`].
+#[derive(Debug, PartialEq)]
+pub enum MotionThrottle {
+    Report,
+    /// Held back, the report has to be flushed after this delay.
+    Schedule(Duration),
+    /// Held back, replacing a motion that already waits to be flushed.
+    Held,
 }
 
 #[inline]
@@ -79,20 +166,20 @@ pub fn calculate_mouse_position(
     margin_y_top: f32,
     cell_dimension: (f32, f32),
 ) -> Pos {
+    let cell_width = cell_dimension.0 as usize;
+    let cell_height = cell_dimension.1 as usize;
+
     // In case sugarloaf hasn't obtained the dimensions
-    if cell_dimension.0 == 0.0 || cell_dimension.1 == 0.0 {
+    if cell_width == 0 || cell_height == 0 {
         return Pos::default();
     }
-
-    let cell_width = cell_dimension.0 as usize;
-    let cell_height = cell_dimension.1 as usize;
     let scaled_margin_x = (margin_x_left * scale_factor) as usize;
 
     let col: Column = if (scaled_margin_x + cell_width) > mouse.x {
         Column(0)
     } else {
         let col = (mouse.x - scaled_margin_x) / cell_width;
-        std::cmp::min(Column(col), Column(columns_rows.0 - 1))
+        std::cmp::min(Column(col), Column(columns_rows.0.saturating_sub(1)))
     };
 
     // TODO: Refactor row position
@@ -100,12 +187,41 @@ pub fn calculate_mouse_position(
         .y
         .saturating_sub((margin_y_top * scale_factor) as usize)
         / cell_height;
-    let calc_row = std::cmp::min(row, columns_rows.1 - 1);
+    let calc_row = std::cmp::min(row, columns_rows.1.saturating_sub(1));
     let row = Line(calc_row as i32) - (display_offset);
 
     Pos::new(row, col)
 }
 
+#[inline]
+pub fn calculate_side_by_pos(
+    x: usize,
+    cell_width: f32,
+    margin_x: f32,
+    layout_width: f32,
+) -> Side {
+    let width = cell_width as usize;
+    // In case sugarloaf hasn't obtained the dimensions
+    if width == 0 {
+        return Side::Left;
+    }
+
+    let cell_x = x.saturating_sub(margin_x as usize) % width;
+    let half_cell_width = width / 2;
+
+    let additional_padding = (layout_width - margin_x) % width as f32;
+    let end_of_grid = layout_width - margin_x - additional_padding;
+
+    if cell_x > half_cell_width
+        // Edge case when mouse leaves the window.
+        || x as f32 >= end_of_grid
+    {
+        Side::Right
+    } else {
+        Side::Left
+    }
+}
+
 #[cfg(test)]
 pub mod test {
     use super::*;
@@ -574,4 +690,138 @@ pub mod test {
         );
         assert_eq!(pos, Pos::new(Line(0), Column(2)));
     }
+
+    #[test]
+    fn test_pos_calc_with_tiny_sizes() {
+        let mouse = Mouse {
+            x: 40,
+            y: 40,
+            ..Default::default()
+        };
+
+        // Cell dimensions smaller than a pixel.
+        let pos = calculate_mouse_position(&mouse, 0, 1.0, (2, 1), 0.0, 0.0, (0.5, 0.5));
+        assert_eq!(pos, Pos::default());
+
+        // Grid without columns or lines.
+        let pos = calculate_mouse_position(&mouse, 0, 1.0, (0, 0), 0.0, 0.0, (9.0, 18.0));
+        assert_eq!(pos, Pos::new(Line(0), Column(0)));
+
+        // Single column grid.
+        let pos = calculate_mouse_position(&mouse, 0, 1.0, (1, 1), 0.0, 0.0, (9.0, 18.0));
+        assert_eq!(pos, Pos::new(Line(0), Column(0)));
+    }
+
+    #[test]
+    fn test_side_by_pos() {
+        assert_eq!(calculate_side_by_pos(2, 10.0, 0.0, 100.0), Side::Left);
+        assert_eq!(calculate_side_by_pos(8, 10.0, 0.0, 100.0), Side::Right);
+        // Edge case when mouse leaves the window.
+        assert_eq!(calculate_side_by_pos(101, 10.0, 0.0, 100.0), Side::Right);
+        // Sugarloaf hasn't obtained the dimensions yet.
+        assert_eq!(calculate_side_by_pos(8, 0.0, 0.0, 100.0), Side::Left);
+        assert_eq!(calculate_side_by_pos(8, 0.5, 0.0, 0.0), Side::Left);
+    }
+
+    #[test]
+    fn test_kinetic_scroll() {
+        let mut mouse = Mouse::default();
+
+        // Finger scroll.
+        mouse.scroll_started();
+        assert!(!mouse.kinetic_scroll);
+        mouse.scroll_ended();
+
+        // Momentum right after the finger is lifted.
+        mouse.scroll_started();
+        assert!(mouse.kinetic_scroll);
+        mouse.scroll_ended();
+        assert!(!mouse.kinetic_scroll);
+
+        // A new gesture after momentum is a finger scroll again.
+        mouse.scroll_started();
+        assert!(!mouse.kinetic_scroll);
+        mouse.scroll_ended();
+
+        // Too late to be momentum.
+        mouse.last_scroll_end = Some(Instant::now() - KINETIC_SCROLL_DELAY * 2);
+        mouse.scroll_started();
+        assert!(!mouse.kinetic_scroll);
+    }
+
+    #[test]
+    fn test_mouse_visibility() {
+        let mut mouse = Mouse::default();
+        assert!(mouse.mouse_visible);
+
+        // Typing keeps it unless the option is set.
+        assert!(!mouse.set_visible(false));
+        assert!(mouse.mouse_visible);
+
+        // Typing hides it once.
+        mouse.hide_when_typing = true;
+        assert!(mouse.set_visible(false));
+        assert!(!mouse.set_visible(false));
+        assert!(!mouse.mouse_visible);
+
+        // Motion is still reported to the application while hidden.
+        assert_eq!(
+            mouse.throttle_motion(35, Duration::from_millis(16), Instant::now()),
+            MotionThrottle::Report
+        );
+
+        // Moving brings it back.
+        assert!(mouse.set_visible(true));
+        assert!(!mouse.set_visible(true));
+        assert!(mouse.mouse_visible);
+    }
+
+    #[test]
+    fn test_motion_report_throttle() {
+        let mut mouse = Mouse::default();
+        let throttle = Duration::from_millis(16);
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+
+        assert_eq!(
+            mouse.throttle_motion(35, throttle, start),
+            MotionThrottle::Report
+        );
+        assert_eq!(
+            mouse.throttle_motion(35, throttle, at(8)),
+            MotionThrottle::Schedule(Duration::from_millis(8))
+        );
+
+        // Only the latest motion is kept and it is scheduled once.
+        assert_eq!(
+            mouse.throttle_motion(32, throttle, at(10)),
+            MotionThrottle::Held
+        );
+        assert_eq!(mouse.pending_motion, Some(32));
+
+        // The flush comes after the window and gets reported.
+        let pending = mouse.pending_motion.take();
+        assert_eq!(pending, Some(32));
+        assert_eq!(
+            mouse.throttle_motion(32, throttle, at(17)),
+            MotionThrottle::Report
+        );
+
+        // A motion reported on time drops the held back one.
+        assert_eq!(
+            mouse.throttle_motion(35, throttle, at(20)),
+            MotionThrottle::Schedule(Duration::from_millis(13))
+        );
+        assert_eq!(
+            mouse.throttle_motion(35, throttle, at(40)),
+            MotionThrottle::Report
+        );
+        assert_eq!(mouse.pending_motion, None);
+
+        // No throttle reports everything.
+        assert_eq!(
+            mouse.throttle_motion(35, Duration::ZERO, at(40)),
+            MotionThrottle::Report
+        );
+    }
 }
@@ -0,0 +1,354 @@
+use crate::event::{EventListener, RioEvent};
+use std::sync::mpsc;
+use std::time::Duration;
+
+const POLLING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Power source of the machine, only known when it has a battery.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PowerState {
+    /// Battery charge, from 0.0 to 1.0.
+    pub battery_level: f32,
+    pub on_ac: bool,
+}
+
+/// Keeps the power source watched, the watching thread stops once it
+/// is dropped.
+pub struct PowerMonitor {
+    _stop: mpsc::Sender<()>,
+}
+
+/// Poll the power source and send a `RioEvent::PowerStateChange` whenever
+/// the machine gets plugged in or unplugged.
+pub fn power_state_updates<T: EventListener + Send + 'static>(
+    event_proxy: T,
+) -> PowerMonitor {
+    watch_power_state(event_proxy, power_state, POLLING_INTERVAL)
+}
+
+fn watch_power_state<T, P>(event_proxy: T, probe: P, interval: Duration) -> PowerMonitor
+where
+    T: EventListener + Send + 'static,
+    P: Fn() -> Option<PowerState> + Send + 'static,
+{
+    let (stop, stopped) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut on_ac = probe().map(|state| state.on_ac);
+
+        // Waiting on the channel ends as soon as the monitor is dropped.
+        while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+            let Some(state) = probe() else {
+                continue;
+            };
+            if on_ac != Some(state.on_ac) {
+                on_ac = Some(state.on_ac);
+                event_proxy.send_event(
+                    RioEvent::PowerStateChange(state.battery_level, state.on_ac),
+                    rio_backend::event::WindowId::from(0),
+                );
+            }
+        }
+    });
+
+    PowerMonitor { _stop: stop }
+}
+
+#[cfg(target_os = "linux")]
+pub fn power_state() -> Option<PowerState> {
+    power_supply_state(std::path::Path::new("/sys/class/power_supply"))
+}
+
+/// Power state from the batteries in a sysfs `power_supply` directory.
+#[cfg(target_os = "linux")]
+fn power_supply_state(dir: &std::path::Path) -> Option<PowerState> {
+    let mut levels = Vec::new();
+    let mut discharging = false;
+
+    for entry in std::fs::read_dir(dir).ok()?.flatten() {
+        let path = entry.path();
+        let read = |name: &str| {
+            std::fs::read_to_string(path.join(name))
+                .map(|value| value.trim().to_string())
+                .unwrap_or_default()
+        };
+        if read("type") != "Battery" {
+            continue;
+        }
+
+        // Peripherals like mice report their battery here too.
+        if read("scope") == "Device" {
+            continue;
+        }
+
+        if let Ok(capacity) = read("capacity").parse::<f32>() {
+            levels.push(capacity / 100.);
+        }
+        discharging |= read("status") == "Discharging";
+    }
+
+    if levels.is_empty() {
+        return None;
+    }
+
+    Some(PowerState {
+        battery_level: levels.iter().sum::<f32>() / levels.len() as f32,
+        on_ac: !discharging,
+    })
+}
+
+/// Power state from the IOKit power sources, read in process rather than
+/// through `pmset`.
+#[cfg(target_os = "macos")]
+pub fn power_state() -> Option<PowerState> {
+    use std::ffi::{c_char, c_void, CStr};
+
+    type CFTypeRef = *const c_void;
+
+    const K_CF_NUMBER_SINT32_TYPE: isize = 3;
+    const K_CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+
+    #[link(name = "IOKit", kind = "framework")]
+    extern "C" {
+        fn IOPSCopyPowerSourcesInfo() -> CFTypeRef;
+        fn IOPSCopyPowerSourcesList(blob: CFTypeRef) -> CFTypeRef;
+        fn IOPSGetPowerSourceDescription(blob: CFTypeRef, source: CFTypeRef)
+            -> CFTypeRef;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFRelease(cf: CFTypeRef);
+        fn CFEqual(cf1: CFTypeRef, cf2: CFTypeRef) -> u8;
+        fn CFArrayGetCount(array: CFTypeRef) -> isize;
+        fn CFArrayGetValueAtIndex(array: CFTypeRef, index: isize) -> CFTypeRef;
+        fn CFDictionaryGetValue(dict: CFTypeRef, key: CFTypeRef) -> CFTypeRef;
+        fn CFNumberGetValue(number: CFTypeRef, kind: isize, value: *mut c_void) -> u8;
+        fn CFStringCreateWithCString(
+            alloc: CFTypeRef,
+            c_str: *const c_char,
+            encoding: u32,
+        ) -> CFTypeRef;
+    }
+
+    /// Released once dropped, like every object returned by a Copy or
+    /// Create function.
+    struct Owned(CFTypeRef);
+
+    impl Drop for Owned {
+        fn drop(&mut self) {
+            if !self.0.is_null() {
+                unsafe { CFRelease(self.0) };
+            }
+        }
+    }
+
+    let string = |value: &CStr| {
+        Owned(unsafe {
+            CFStringCreateWithCString(
+                std::ptr::null(),
+                value.as_ptr(),
+                K_CF_STRING_ENCODING_UTF8,
+            )
+        })
+    };
+    let type_key = string(c"Type");
+    let internal_battery = string(c"InternalBattery");
+    let state_key = string(c"Power Source State");
+    let battery_power = string(c"Battery Power");
+    let current_key = string(c"Current Capacity");
+    let max_key = string(c"Max Capacity");
+
+    let info = Owned(unsafe { IOPSCopyPowerSourcesInfo() });
+    if info.0.is_null() {
+        return None;
+    }
+    let sources = Owned(unsafe { IOPSCopyPowerSourcesList(info.0) });
+    if sources.0.is_null() {
+        return None;
+    }
+
+    let mut levels = Vec::new();
+    let mut discharging = false;
+    for index in 0..unsafe { CFArrayGetCount(sources.0) } {
+        // Descriptions and their values belong to `info`.
+        let description = unsafe {
+            IOPSGetPowerSourceDescription(
+                info.0,
+                CFArrayGetValueAtIndex(sources.0, index),
+            )
+        };
+        if description.is_null() {
+            continue;
+        }
+        let value = |key: &Owned| unsafe { CFDictionaryGetValue(description, key.0) };
+        let equals = |key: &Owned, expected: &Owned| {
+            let value = value(key);
+            !value.is_null() && unsafe { CFEqual(value, expected.0) } != 0
+        };
+        let number = |key: &Owned| {
+            let value = value(key);
+            let mut number = 0i32;
+            (!value.is_null()
+                && unsafe {
+                    CFNumberGetValue(
+                        value,
+                        K_CF_NUMBER_SINT32_TYPE,
+                        &mut number as *mut i32 as *mut c_void,
+                    )
+                } != 0)
+                .then_some(number)
+        };
+
+        if !equals(&type_key, &internal_battery) {
+            continue;
+        }
+
+        if let (Some(current), Some(max)) = (number(&current_key), number(&max_key)) {
+            if max > 0 {
+                levels.push(current as f32 / max as f32);
+            }
+        }
+        discharging |= equals(&state_key, &battery_power);
+    }
+
+    if levels.is_empty() {
+        return None;
+    }
+
+    Some(PowerState {
+        battery_level: levels.iter().sum::<f32>() / levels.len() as f32,
+        on_ac: !discharging,
+    })
+}
+
+#[cfg(windows)]
+pub fn power_state() -> Option<PowerState> {
+    use windows_sys::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return None;
+    }
+
+    // 128 is "no system battery", 255 an unknown status.
+    if status.BatteryFlag == 128 || status.BatteryFlag == 255 {
+        return None;
+    }
+
+    Some(PowerState {
+        battery_level: f32::from(status.BatteryLifePercent.min(100)) / 100.,
+        on_ac: status.ACLineStatus == 1,
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn power_state() -> Option<PowerState> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<RioEvent>>>);
+
+    impl EventListener for Recorder {
+        fn event(&self) -> (Option<RioEvent>, bool) {
+            (None, false)
+        }
+
+        fn send_event(&self, event: RioEvent, _id: rio_backend::event::WindowId) {
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    #[test]
+    fn power_monitor_stops_when_dropped() {
+        let probes = Arc::new(AtomicUsize::new(0));
+        let recorder = Recorder::default();
+        let monitor = watch_power_state(
+            recorder.clone(),
+            {
+                let probes = probes.clone();
+                move || {
+                    // Unplugged from the second probe on.
+                    let count = probes.fetch_add(1, Ordering::SeqCst);
+                    Some(PowerState {
+                        battery_level: 0.5,
+                        on_ac: count == 0,
+                    })
+                }
+            },
+            Duration::from_millis(1),
+        );
+
+        while probes.load(Ordering::SeqCst) < 3 {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        drop(monitor);
+        std::thread::sleep(Duration::from_millis(20));
+        let stopped_at = probes.load(Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(probes.load(Ordering::SeqCst), stopped_at);
+
+        // Only the change is reported.
+        let events = recorder.0.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            events[0],
+            RioEvent::PowerStateChange(level, false) if level == 0.5
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn linux_power_supply() {
+        let dir = std::env::temp_dir().join(format!("rio-power-{}", std::process::id()));
+        let supply = |name: &str, files: &[(&str, &str)]| {
+            let path = dir.join(name);
+            std::fs::create_dir_all(&path).unwrap();
+            for (file, value) in files {
+                std::fs::write(path.join(file), format!("{value}\n")).unwrap();
+            }
+        };
+
+        supply("AC", &[("type", "Mains"), ("online", "1")]);
+        assert_eq!(power_supply_state(&dir), None);
+
+        supply(
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("capacity", "80"),
+                ("status", "Charging"),
+            ],
+        );
+        supply(
+            "hid-mouse-battery",
+            &[
+                ("type", "Battery"),
+                ("scope", "Device"),
+                ("capacity", "10"),
+                ("status", "Discharging"),
+            ],
+        );
+        assert_eq!(
+            power_supply_state(&dir),
+            Some(PowerState {
+                battery_level: 0.8,
+                on_ac: true,
+            })
+        );
+
+        supply("BAT0", &[("status", "Discharging")]);
+        assert_eq!(
+            power_supply_state(&dir).map(|state| state.on_ac),
+            Some(false)
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
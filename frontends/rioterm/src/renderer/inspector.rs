@@ -0,0 +1,148 @@
+use rio_backend::config::colors::{AnsiColor, ColorRgb, Colors};
+use rio_backend::crosswords::pos::Pos;
+use rio_backend::crosswords::square::Flags;
+use rio_backend::crosswords::Crosswords;
+use rio_backend::event::EventListener;
+use rio_backend::sugarloaf::{Object, Rect, Text};
+
+const INSPECTOR_LINE_HEIGHT: f32 = 18.;
+const INSPECTOR_CHAR_WIDTH: f32 = 8.5;
+const INSPECTOR_PADDING: f32 = 10.;
+
+/// Lines shown by the cell inspector for the cell at `pos`: position,
+/// codepoints, resolved colors and flags.
+pub fn describe_cell<T: EventListener>(
+    terminal: &Crosswords<T>,
+    pos: Pos,
+) -> Vec<String> {
+    let Some((fg, bg)) = terminal.cell_color_at(pos) else {
+        return Vec::new();
+    };
+    let square = &terminal.grid[pos];
+
+    let chars: Vec<char> = std::iter::once(square.c)
+        .chain(square.zerowidth().unwrap_or_default().iter().copied())
+        .collect();
+    let text: String = chars.iter().filter(|c| !c.is_control()).collect();
+    let codepoints = chars
+        .iter()
+        .map(|c| format!("U+{:04X}", *c as u32))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let flags = square
+        .flags
+        .iter_names()
+        .filter(|(_, flag)| *flag != Flags::DIM_BOLD && *flag != Flags::ALL_UNDERLINES)
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+
+    let mut lines = vec![
+        format!("Cell {}:{}", pos.row.0, pos.col.0),
+        format!("{:?} {}", text, codepoints),
+        format!("fg {} {}", hex(fg), color_source(square.fg)),
+        format!("bg {} {}", hex(bg), color_source(square.bg)),
+        if flags.is_empty() {
+            String::from("flags none")
+        } else {
+            format!("flags {}", flags.join(" | "))
+        },
+    ];
+
+    if let Some(color) = square.underline_color() {
+        lines.push(format!("underline {}", color_source(color)));
+    }
+
+    if let Some(hyperlink) = square.hyperlink() {
+        lines.push(format!("link {}", hyperlink.uri()));
+    }
+
+    lines
+}
+
+fn hex(color: ColorRgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+fn color_source(color: AnsiColor) -> String {
+    match color {
+        AnsiColor::Named(name) => format!("{name:?}"),
+        AnsiColor::Indexed(index) => format!("index {index}"),
+        AnsiColor::Spec(rgb) => format!("rgb {}", hex(rgb)),
+    }
+}
+
+#[inline]
+pub fn draw_cell_inspector(
+    objects: &mut Vec<Object>,
+    colors: &Colors,
+    dimensions: (f32, f32, f32),
+    margin_top: f32,
+    lines: &[String],
+) {
+    let (width, _, scale) = dimensions;
+
+    let longest = lines.iter().map(|line| line.chars().count()).max();
+    let panel_width = longest.unwrap_or_default() as f32 * INSPECTOR_CHAR_WIDTH
+        + INSPECTOR_PADDING * 2.;
+    let panel_height =
+        lines.len() as f32 * INSPECTOR_LINE_HEIGHT + INSPECTOR_PADDING * 2.;
+    let position_x = (width / scale) - panel_width - INSPECTOR_PADDING;
+    let position_y = margin_top + INSPECTOR_PADDING;
+
+    objects.push(Object::Rect(Rect {
+        position: [position_x, position_y],
+        color: [colors.bar[0], colors.bar[1], colors.bar[2], 0.9],
+        size: [panel_width, panel_height],
+    }));
+
+    let mut line_y = position_y + INSPECTOR_PADDING + INSPECTOR_LINE_HEIGHT / 2.;
+    for line in lines {
+        objects.push(Object::Text(Text::single_line(
+            (position_x + INSPECTOR_PADDING, line_y),
+            line.to_owned(),
+            14.,
+            colors.foreground,
+        )));
+        line_y += INSPECTOR_LINE_HEIGHT;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rio_backend::ansi::CursorShape;
+    use rio_backend::crosswords::pos::{Column, Line};
+    use rio_backend::crosswords::CrosswordsSize;
+    use rio_backend::event::{VoidListener, WindowId};
+    use rio_backend::performer::handler::ParserProcessor;
+
+    #[test]
+    fn test_describe_cell() {
+        let size = CrosswordsSize::new(10, 2);
+        let mut terminal = Crosswords::new(
+            size,
+            CursorShape::Block,
+            VoidListener {},
+            WindowId::from(0),
+            0,
+        );
+
+        let mut parser = ParserProcessor::new();
+        for byte in "a\x1b[1;3;38;5;196;48;2;1;2;3m\u{1f600}".as_bytes() {
+            parser.advance(&mut terminal, *byte);
+        }
+
+        let lines = describe_cell(&terminal, Pos::new(Line(0), Column(1)));
+        assert_eq!(lines[0], "Cell 0:1");
+        assert_eq!(lines[1], "\"\u{1f600}\" U+1F600");
+        assert_eq!(lines[2], "fg #ff0000 index 196");
+        assert_eq!(lines[3], "bg #010203 rgb #010203");
+        assert_eq!(lines[4], "flags BOLD | ITALIC | WIDE_CHAR");
+        assert_eq!(lines.len(), 5);
+
+        let lines = describe_cell(&terminal, Pos::new(Line(0), Column(0)));
+        assert_eq!(lines[1], "\"a\" U+0061");
+        assert_eq!(lines[4], "flags none");
+    }
+}
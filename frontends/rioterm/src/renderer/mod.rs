@@ -1,11 +1,17 @@
+pub mod inspector;
 pub mod navigation;
+mod paste;
+mod scrollbar;
 mod search;
+pub mod status;
 pub mod utils;
 
-use crate::ansi::CursorShape;
+use crate::ansi::{kitty_graphics, CursorShape};
+use crate::context::split::PaneRect;
+use crate::crosswords::color::{self, Palette};
 use crate::crosswords::grid::row::Row;
 use crate::crosswords::pos::{Column, CursorState, Line, Pos};
-use crate::crosswords::square::{Flags, Square};
+use crate::crosswords::square::{Flags, LineLength, Square};
 use crate::ime::Preedit;
 use crate::screen::hint::HintMatches;
 use crate::selection::SelectionRange;
@@ -14,7 +20,7 @@ use rio_backend::config::colors::{
     term::{List, TermColors},
     AnsiColor, ColorArray, Colors, NamedColor,
 };
-use rio_backend::config::Config;
+use rio_backend::config::{Config, HyperlinkUnderlineStyle, StatusLine};
 use rio_backend::sugarloaf::{
     Content, ContentBuilder, FragmentStyle, FragmentStyleDecoration, Graphic, Stretch,
     Style, SugarCursor, Sugarloaf, UnderlineInfo, UnderlineShape, Weight,
@@ -50,12 +56,27 @@ pub struct Renderer {
     term_has_blinking_enabled: bool,
     pub is_blinking: bool,
     ignore_selection_fg_color: bool,
+    hyperlink_underline_style: HyperlinkUnderlineStyle,
+    render_whitespace: bool,
+    /// Set by the application through DECSET 30.
+    scrollbar_visible: bool,
     // Dynamic background keep track of the original bg color and
     // the same r,g,b with the mutated alpha channel.
     pub dynamic_background: ([f32; 4], wgpu::Color, bool),
     hyperlink_range: Option<SelectionRange>,
     width_cache: FxHashMap<char, f32>,
     active_search: Option<String>,
+    active_unicode_input: Option<String>,
+    active_tab_rename: Option<String>,
+    active_paste_preview: Option<String>,
+    active_status_message: Option<String>,
+    active_cell_inspector: Option<Vec<String>>,
+    /// `status-line-format` while the status line is enabled.
+    status_line_format: Option<String>,
+    active_status_line: Option<String>,
+    /// Grid rows of each display row of the focused pane, set while its
+    /// soft-wrapped lines are truncated.
+    displayed_rows: Option<Vec<DisplaySpan>>,
 }
 
 impl Renderer {
@@ -107,6 +128,9 @@ impl Renderer {
             config_has_blinking_enabled: config.blinking_cursor,
             term_has_blinking_enabled: false,
             ignore_selection_fg_color: config.ignore_selection_fg_color,
+            hyperlink_underline_style: config.hyperlink_underline_style,
+            render_whitespace: config.renderer.render_whitespace,
+            scrollbar_visible: false,
             colors,
             navigation: ScreenNavigation::new(
                 config.navigation.clone(),
@@ -116,9 +140,18 @@ impl Renderer {
             font_size: config.fonts.size,
             selection_range: None,
             hyperlink_range: None,
+            displayed_rows: None,
             named_colors,
             dynamic_background,
             active_search: None,
+            active_unicode_input: None,
+            active_tab_rename: None,
+            active_paste_preview: None,
+            active_status_message: None,
+            active_cell_inspector: None,
+            status_line_format: (config.status_line == StatusLine::Bottom)
+                .then(|| config.status_line_format.to_owned()),
+            active_status_line: None,
             cursor: Cursor {
                 content: config.cursor,
                 content_ref: config.cursor,
@@ -148,6 +181,54 @@ impl Renderer {
         self.active_search = active_search;
     }
 
+    #[inline]
+    pub fn set_scrollbar_visible(&mut self, visible: bool) {
+        self.scrollbar_visible = visible;
+    }
+
+    #[inline]
+    pub fn set_active_unicode_input(&mut self, active_unicode_input: Option<String>) {
+        self.active_unicode_input = active_unicode_input;
+    }
+
+    #[inline]
+    pub fn set_active_tab_rename(&mut self, active_tab_rename: Option<String>) {
+        self.active_tab_rename = active_tab_rename;
+    }
+
+    #[inline]
+    pub fn set_active_paste_preview(&mut self, active_paste_preview: Option<String>) {
+        self.active_paste_preview = active_paste_preview;
+    }
+
+    #[inline]
+    pub fn set_active_status_message(&mut self, active_status_message: Option<String>) {
+        self.active_status_message = active_status_message;
+    }
+
+    #[inline]
+    pub fn set_active_cell_inspector(
+        &mut self,
+        active_cell_inspector: Option<Vec<String>>,
+    ) {
+        self.active_cell_inspector = active_cell_inspector;
+    }
+
+    #[inline]
+    pub fn status_line_format(&self) -> Option<&str> {
+        self.status_line_format.as_deref()
+    }
+
+    #[inline]
+    pub fn has_status_line(&self) -> bool {
+        self.status_line_format.is_some()
+    }
+
+    #[inline]
+    pub fn set_active_status_line(&mut self, active_status_line: Option<String>) {
+        self.active_status_line = active_status_line;
+    }
+
     #[inline]
     pub fn set_hyperlink_range(&mut self, hyperlink_range: Option<SelectionRange>) {
         self.hyperlink_range = hyperlink_range;
@@ -278,33 +359,59 @@ impl Renderer {
         (decoration, decoration_color)
     }
 
+    /// Add the first `columns` cells of `row` to the current line, padded
+    /// with blanks if the row is shorter. `span` tells the grid rows the
+    /// cells come from and `cursor` is the grid position of the cursor.
     #[inline]
-    fn create_line(
+    #[allow(clippy::too_many_arguments)]
+    fn add_row(
         &mut self,
         content_builder: &mut ContentBuilder,
         row: &Row<Square>,
-        has_cursor: bool,
-        line: Line,
+        columns: usize,
+        span: DisplaySpan,
+        display_offset: i32,
+        cursor: Option<Pos>,
         search_hints: &mut Option<HintMatches>,
         focused_match: &Option<RangeInclusive<Pos>>,
     ) {
-        let columns: usize = row.len();
         let mut content = String::default();
         let mut last_style = FragmentStyle::default();
-
-        for column in 0..columns {
+        for column in 0..columns.min(row.len()) {
             let square = &row.inner[column];
 
             if square.flags.contains(Flags::WIDE_CHAR_SPACER) {
                 continue;
             }
 
-            let (mut style, square_content) =
-                if has_cursor && column == self.cursor.state.pos.col {
-                    self.create_cursor_style(square)
-                } else {
-                    self.create_style(square)
+            let pos = span.pos(column, row.len(), display_offset);
+            let is_cursor = pos.is_some() && pos == cursor;
+            let (mut style, mut square_content) = if is_cursor {
+                self.create_cursor_style(square)
+            } else {
+                self.create_style(square)
+            };
+
+            // Whitespace markers are only drawn, the grid content is untouched.
+            if self.render_whitespace
+                && !is_cursor
+                && !square.flags.contains(Flags::HIDDEN)
+            {
+                let marker = match square.c {
+                    ' ' => Some('·'),
+                    '\t' => Some('→'),
+                    _ => None,
                 };
+                if let Some(marker) = marker {
+                    square_content = marker;
+                    style.color[3] *= 0.3;
+                }
+            }
+
+            // Kitty placeholders only show the image set on their cell.
+            if square.c == kitty_graphics::PLACEHOLDER {
+                square_content = ' ';
+            }
 
             if square.flags.contains(Flags::GRAPHICS) {
                 // let graphics = square.graphics().map(|graphics| {
@@ -328,23 +435,27 @@ impl Renderer {
 
             if self.hyperlink_range.is_some()
                 && square.hyperlink().is_some()
-                && self
-                    .hyperlink_range
-                    .unwrap()
-                    .contains(Pos::new(line, Column(column)))
+                && pos.is_some_and(|pos| self.hyperlink_range.unwrap().contains(pos))
             {
+                // Cells of the same hyperlink share this style, so they end
+                // up in a single fragment with one continuous underline.
+                let (shape, is_doubled) = match self.hyperlink_underline_style {
+                    HyperlinkUnderlineStyle::Regular => (UnderlineShape::Regular, false),
+                    HyperlinkUnderlineStyle::Double => (UnderlineShape::Regular, true),
+                    HyperlinkUnderlineStyle::Dotted => (UnderlineShape::Dotted, false),
+                    HyperlinkUnderlineStyle::Dashed => (UnderlineShape::Dashed, false),
+                    HyperlinkUnderlineStyle::Curly => (UnderlineShape::Curly, false),
+                };
                 style.decoration =
                     Some(FragmentStyleDecoration::Underline(UnderlineInfo {
                         offset: -1.0,
                         size: -1.0,
-                        is_doubled: false,
-                        shape: UnderlineShape::Regular,
+                        is_doubled,
+                        shape,
                     }));
+                style.decoration_color = Some(self.named_colors.hyperlink);
             } else if self.selection_range.is_some()
-                && self
-                    .selection_range
-                    .unwrap()
-                    .contains(Pos::new(line, Column(column)))
+                && pos.is_some_and(|pos| self.selection_range.unwrap().contains(pos))
             {
                 style.color = if self.ignore_selection_fg_color {
                     self.compute_color(&square.fg, square.flags)
@@ -352,14 +463,13 @@ impl Renderer {
                     self.named_colors.selection_foreground
                 };
                 style.background_color = Some(self.named_colors.selection_background);
-            } else if search_hints.is_some()
-                && search_hints.as_mut().map_or(false, |search| {
-                    search.advance(Pos::new(line, Column(column)))
-                })
-            {
-                let is_focused = focused_match
-                    .as_ref()
-                    .map_or(false, |fm| fm.contains(&Pos::new(line, Column(column))));
+            } else if let Some(pos) = pos.filter(|pos| {
+                search_hints
+                    .as_mut()
+                    .map_or(false, |search| search.advance(*pos))
+            }) {
+                let is_focused =
+                    focused_match.as_ref().map_or(false, |fm| fm.contains(&pos));
                 if is_focused {
                     style.color = self.named_colors.search_focused_match_foreground;
                     style.background_color =
@@ -381,146 +491,80 @@ impl Renderer {
             }
 
             content.push(square_content);
+        }
 
-            // Render last column and break row
-            if column == (columns - 1) {
-                if !content.is_empty() {
-                    content_builder.add_text(&content, last_style);
-                }
-
-                break;
-            }
+        if !content.is_empty() {
+            content_builder.add_text(&content, last_style);
         }
 
-        content_builder.finish_line();
+        if columns > row.len() {
+            content_builder
+                .add_text(&" ".repeat(columns - row.len()), FragmentStyle::default());
+        }
     }
 
+    /// Add line `line` of `pane` to the current line, only the focused pane
+    /// shows the cursor, selection, hints and hovered hyperlink.
     #[inline]
-    fn compute_color(&self, color: &AnsiColor, flags: Flags) -> ColorArray {
-        match color {
-            AnsiColor::Named(ansi_name) => match (ansi_name, flags) {
-                (NamedColor::Background, _) => self.named_colors.background.0,
-                (NamedColor::Cursor, _) => self.named_colors.cursor,
-
-                (NamedColor::Black, Flags::DIM) => self.named_colors.dim_black,
-                (NamedColor::Black, Flags::BOLD) => self.named_colors.light_black,
-                (NamedColor::Black, _) => self.named_colors.black,
-                (NamedColor::Blue, Flags::DIM) => self.named_colors.dim_blue,
-                (NamedColor::Blue, Flags::BOLD) => self.named_colors.light_blue,
-                (NamedColor::Blue, _) => self.named_colors.blue,
-                (NamedColor::Cyan, Flags::DIM) => self.named_colors.dim_cyan,
-                (NamedColor::Cyan, Flags::BOLD) => self.named_colors.light_cyan,
-                (NamedColor::Cyan, _) => self.named_colors.cyan,
-                (NamedColor::Foreground, _) => self.named_colors.foreground,
-                (NamedColor::Green, Flags::DIM) => self.named_colors.dim_green,
-                (NamedColor::Green, Flags::BOLD) => self.named_colors.light_green,
-                (NamedColor::Green, _) => self.named_colors.green,
-                (NamedColor::Magenta, Flags::DIM) => self.named_colors.dim_magenta,
-                (NamedColor::Magenta, Flags::BOLD) => self.named_colors.light_magenta,
-                (NamedColor::Magenta, _) => self.named_colors.magenta,
-                (NamedColor::Red, Flags::DIM) => self.named_colors.dim_red,
-                (NamedColor::Red, Flags::BOLD) => self.named_colors.light_red,
-                (NamedColor::Red, _) => self.named_colors.red,
-                (NamedColor::White, Flags::DIM) => self.named_colors.dim_white,
-                (NamedColor::White, Flags::BOLD) => self.named_colors.light_white,
-                (NamedColor::White, _) => self.named_colors.white,
-                (NamedColor::Yellow, Flags::DIM) => self.named_colors.dim_yellow,
-                (NamedColor::Yellow, Flags::BOLD) => self.named_colors.light_yellow,
-                (NamedColor::Yellow, _) => self.named_colors.yellow,
-                (NamedColor::LightBlack, _) => self.named_colors.light_black,
-                (NamedColor::LightBlue, _) => self.named_colors.light_blue,
-                (NamedColor::LightCyan, _) => self.named_colors.light_cyan,
-                (NamedColor::LightForeground, _) => self.named_colors.light_foreground,
-                (NamedColor::LightGreen, _) => self.named_colors.light_green,
-                (NamedColor::LightMagenta, _) => self.named_colors.light_magenta,
-                (NamedColor::LightRed, _) => self.named_colors.light_red,
-                (NamedColor::LightWhite, _) => self.named_colors.light_white,
-                (NamedColor::LightYellow, _) => self.named_colors.light_yellow,
-                (NamedColor::DimBlack, _) => self.named_colors.dim_black,
-                (NamedColor::DimBlue, _) => self.named_colors.dim_blue,
-                (NamedColor::DimCyan, _) => self.named_colors.dim_cyan,
-                (NamedColor::DimForeground, _) => self.named_colors.dim_foreground,
-                (NamedColor::DimGreen, _) => self.named_colors.dim_green,
-                (NamedColor::DimMagenta, _) => self.named_colors.dim_magenta,
-                (NamedColor::DimRed, _) => self.named_colors.dim_red,
-                (NamedColor::DimWhite, _) => self.named_colors.dim_white,
-                (NamedColor::DimYellow, _) => self.named_colors.dim_yellow,
-            },
-            AnsiColor::Spec(rgb) => {
-                if !flags.contains(Flags::DIM) {
-                    rgb.to_arr()
-                } else {
-                    rgb.to_arr_with_dim()
-                }
+    #[allow(clippy::too_many_arguments)]
+    fn add_pane_row(
+        &mut self,
+        content_builder: &mut ContentBuilder,
+        pane: &PaneContent,
+        line: Option<(DisplaySpan, &Row<Square>)>,
+        columns: usize,
+        is_cursor_visible: bool,
+        search_hints: &mut Option<HintMatches>,
+        focused_match: &Option<RangeInclusive<Pos>>,
+    ) {
+        let Some((span, row)) = line else {
+            if columns > 0 {
+                content_builder.add_text(&" ".repeat(columns), FragmentStyle::default());
             }
-            AnsiColor::Indexed(index) => {
-                let index = match (flags & Flags::DIM_BOLD, index) {
-                    (Flags::DIM, 8..=15) => *index as usize - 8,
-                    (Flags::DIM, 0..=7) => {
-                        NamedColor::DimBlack as usize + *index as usize
-                    }
-                    _ => *index as usize,
-                };
+            return;
+        };
 
-                self.colors[index]
-            }
+        if pane.is_focused {
+            let cursor = is_cursor_visible.then(|| {
+                let pos = self.cursor.state.pos;
+                Pos::new(pos.row - pane.display_offset, pos.col)
+            });
+            self.add_row(
+                content_builder,
+                row,
+                columns,
+                span,
+                pane.display_offset,
+                cursor,
+                search_hints,
+                focused_match,
+            );
+        } else {
+            let selection_range = self.selection_range.take();
+            let hyperlink_range = self.hyperlink_range.take();
+            self.add_row(
+                content_builder,
+                row,
+                columns,
+                span,
+                pane.display_offset,
+                None,
+                &mut None,
+                &None,
+            );
+            self.selection_range = selection_range;
+            self.hyperlink_range = hyperlink_range;
         }
     }
 
+    #[inline]
+    fn compute_color(&self, color: &AnsiColor, flags: Flags) -> ColorArray {
+        color::resolve_fg(self, *color, flags)
+    }
+
     #[inline]
     fn compute_bg_color(&self, square: &Square) -> ColorArray {
-        match square.bg {
-            AnsiColor::Named(ansi_name) => match (ansi_name, square.flags) {
-                (NamedColor::Background, _) => self.named_colors.background.0,
-                (NamedColor::Cursor, _) => self.named_colors.cursor,
-
-                (NamedColor::Black, Flags::DIM) => self.named_colors.dim_black,
-                (NamedColor::Black, Flags::BOLD) => self.named_colors.light_black,
-                (NamedColor::Black, _) => self.named_colors.black,
-                (NamedColor::Blue, Flags::DIM) => self.named_colors.dim_blue,
-                (NamedColor::Blue, Flags::BOLD) => self.named_colors.light_blue,
-                (NamedColor::Blue, _) => self.named_colors.blue,
-                (NamedColor::Cyan, Flags::DIM) => self.named_colors.dim_cyan,
-                (NamedColor::Cyan, Flags::BOLD) => self.named_colors.light_cyan,
-                (NamedColor::Cyan, _) => self.named_colors.cyan,
-                (NamedColor::Foreground, _) => self.named_colors.foreground,
-                (NamedColor::Green, Flags::DIM) => self.named_colors.dim_green,
-                (NamedColor::Green, Flags::BOLD) => self.named_colors.light_green,
-                (NamedColor::Green, _) => self.named_colors.green,
-                (NamedColor::Magenta, Flags::DIM) => self.named_colors.dim_magenta,
-                (NamedColor::Magenta, Flags::BOLD) => self.named_colors.light_magenta,
-                (NamedColor::Magenta, _) => self.named_colors.magenta,
-                (NamedColor::Red, Flags::DIM) => self.named_colors.dim_red,
-                (NamedColor::Red, Flags::BOLD) => self.named_colors.light_red,
-                (NamedColor::Red, _) => self.named_colors.red,
-                (NamedColor::White, Flags::DIM) => self.named_colors.dim_white,
-                (NamedColor::White, Flags::BOLD) => self.named_colors.light_white,
-                (NamedColor::White, _) => self.named_colors.white,
-                (NamedColor::Yellow, Flags::DIM) => self.named_colors.dim_yellow,
-                (NamedColor::Yellow, Flags::BOLD) => self.named_colors.light_yellow,
-                (NamedColor::Yellow, _) => self.named_colors.yellow,
-                (NamedColor::LightBlack, _) => self.named_colors.light_black,
-                (NamedColor::LightBlue, _) => self.named_colors.light_blue,
-                (NamedColor::LightCyan, _) => self.named_colors.light_cyan,
-                (NamedColor::LightForeground, _) => self.named_colors.light_foreground,
-                (NamedColor::LightGreen, _) => self.named_colors.light_green,
-                (NamedColor::LightMagenta, _) => self.named_colors.light_magenta,
-                (NamedColor::LightRed, _) => self.named_colors.light_red,
-                (NamedColor::LightWhite, _) => self.named_colors.light_white,
-                (NamedColor::LightYellow, _) => self.named_colors.light_yellow,
-                (NamedColor::DimBlack, _) => self.named_colors.dim_black,
-                (NamedColor::DimBlue, _) => self.named_colors.dim_blue,
-                (NamedColor::DimCyan, _) => self.named_colors.dim_cyan,
-                (NamedColor::DimForeground, _) => self.named_colors.dim_foreground,
-                (NamedColor::DimGreen, _) => self.named_colors.dim_green,
-                (NamedColor::DimMagenta, _) => self.named_colors.dim_magenta,
-                (NamedColor::DimRed, _) => self.named_colors.dim_red,
-                (NamedColor::DimWhite, _) => self.named_colors.dim_white,
-                (NamedColor::DimYellow, _) => self.named_colors.dim_yellow,
-            },
-            AnsiColor::Spec(rgb) => rgb.to_arr(),
-            AnsiColor::Indexed(idx) => self.colors[idx as usize],
-        }
+        color::resolve_bg(self, square.bg, square.flags)
     }
 
     // #[inline]
@@ -633,6 +677,25 @@ impl Renderer {
         self.selection_range = selection_range;
     }
 
+    /// Grid row and column shown on the display `line` and `column` of the
+    /// focused pane, which is `columns` wide.
+    #[inline]
+    pub fn grid_cell(
+        &self,
+        line: usize,
+        column: usize,
+        columns: usize,
+    ) -> (usize, usize) {
+        match self
+            .displayed_rows
+            .as_ref()
+            .and_then(|rows| rows.get(line).or(rows.last()))
+        {
+            Some(span) => span.cell(column, columns),
+            None => (line, column),
+        }
+    }
+
     #[inline]
     pub fn set_vi_mode(&mut self, is_vi_mode_enabled: bool) {
         self.is_vi_mode_enabled = is_vi_mode_enabled;
@@ -642,11 +705,10 @@ impl Renderer {
     #[allow(clippy::too_many_arguments)]
     pub fn prepare_term(
         &mut self,
-        rows: &[Row<Square>],
+        panes: &[PaneContent],
         cursor: CursorState,
         sugarloaf: &mut Sugarloaf,
         context_manager: &crate::context::ContextManager<rio_backend::event::EventProxy>,
-        display_offset: i32,
         has_blinking_enabled: bool,
         hints: &mut Option<HintMatches>,
         focused_match: &Option<RangeInclusive<Pos>>,
@@ -676,16 +738,99 @@ impl Renderer {
 
         let mut content_builder = Content::builder();
 
-        for (i, row) in rows.iter().enumerate() {
-            let has_cursor = is_cursor_visible && self.cursor.state.pos.row == i;
-            self.create_line(
-                &mut content_builder,
-                row,
-                has_cursor,
-                Line((i as i32) - display_offset),
-                hints,
-                focused_match,
-            );
+        // Truncated panes keep the same amount of lines on screen, the
+        // lines after the truncated ones are left blank.
+        let truncated: Vec<_> = panes
+            .iter()
+            .map(|pane| {
+                let cursor_row = pane
+                    .is_focused
+                    .then(|| usize::try_from(self.cursor.state.pos.row.0).ok())
+                    .flatten();
+                (!pane.wrap_lines).then(|| {
+                    truncate_wrapped_rows(&pane.rows, cursor_row, pane.line_scroll)
+                })
+            })
+            .collect();
+        self.displayed_rows = panes
+            .iter()
+            .zip(&truncated)
+            .find(|(pane, _)| pane.is_focused)
+            .and_then(|(_, truncated)| truncated.as_ref())
+            .map(|truncated| truncated.iter().map(|(span, _)| *span).collect());
+        let pane_line = |index: usize, k: usize| match &truncated[index] {
+            Some(truncated) => truncated.get(k).map(|(span, row)| (*span, row)),
+            None => panes[index]
+                .rows
+                .get(k)
+                .map(|row| (DisplaySpan::row(k), row)),
+        };
+
+        if let [pane] = panes {
+            for k in 0..pane.rows.len() {
+                let line = pane_line(0, k);
+                let columns = line.map_or(0, |(_, row)| row.len());
+                self.add_pane_row(
+                    &mut content_builder,
+                    pane,
+                    line,
+                    columns,
+                    is_cursor_visible,
+                    hints,
+                    focused_match,
+                );
+                content_builder.finish_line();
+            }
+        } else {
+            // Panes of a split tab are composed line by line, with a
+            // separator cell between them.
+            let mut separator_style = FragmentStyle {
+                color: self.named_colors.foreground,
+                ..FragmentStyle::default()
+            };
+            separator_style.color[3] *= 0.3;
+
+            let grid_columns = panes
+                .iter()
+                .map(|pane| pane.rect.column + pane.rect.columns)
+                .max()
+                .unwrap_or(0);
+            let grid_lines = panes
+                .iter()
+                .map(|pane| pane.rect.line + pane.rect.lines)
+                .max()
+                .unwrap_or(0);
+            let is_pane = |column: usize, line: usize| {
+                panes.iter().any(|pane| pane.rect.contains(column, line))
+            };
+
+            for y in 0..grid_lines {
+                let mut x = 0;
+                while x < grid_columns {
+                    if let Some(index) =
+                        panes.iter().position(|pane| pane.rect.contains(x, y))
+                    {
+                        let pane = &panes[index];
+                        self.add_pane_row(
+                            &mut content_builder,
+                            pane,
+                            pane_line(index, y - pane.rect.line),
+                            pane.rect.columns,
+                            is_cursor_visible,
+                            hints,
+                            focused_match,
+                        );
+                        x += pane.rect.columns;
+                    } else {
+                        let is_vertical =
+                            (x > 0 && is_pane(x - 1, y)) || is_pane(x + 1, y);
+                        let separator = if is_vertical { "│" } else { "─" };
+                        content_builder.add_text(separator, separator_style);
+                        x += 1;
+                    }
+                }
+                content_builder.finish_line();
+            }
         }
 
         sugarloaf.set_content(content_builder.build());
@@ -695,10 +840,46 @@ impl Renderer {
             (layout.width, layout.height, layout.dimensions.scale),
             &self.named_colors,
             context_manager,
-            self.active_search.is_some(),
+            self.active_search.is_some()
+                || self.active_unicode_input.is_some()
+                || self.active_tab_rename.is_some(),
             &mut objects,
         );
 
+        if self.scrollbar_visible {
+            let scale = layout.dimensions.scale;
+            let cell_width = layout.dimensions.width / scale;
+            let cell_height = layout.dimensions.height * layout.line_height / scale;
+            for pane in panes {
+                let rect = pane.rect;
+                scrollbar::draw_scrollbar(
+                    &mut objects,
+                    &self.named_colors,
+                    (
+                        layout.margin.x
+                            + (rect.column + rect.columns) as f32 * cell_width,
+                        layout.margin.top_y + rect.line as f32 * cell_height,
+                        rect.lines as f32 * cell_height,
+                    ),
+                    pane.history_size,
+                    rect.lines,
+                    pane.display_offset as usize,
+                );
+            }
+        }
+
+        if let Some(active_status_line) = &self.active_status_line {
+            status::draw_status_line(
+                &mut objects,
+                &self.named_colors,
+                (layout.width, layout.height, layout.dimensions.scale),
+                layout.margin.bottom_y,
+                active_status_line,
+            );
+
+            self.active_status_line = None;
+        }
+
         if let Some(active_search_content) = &self.active_search {
             search::draw_search_bar(
                 &mut objects,
@@ -710,6 +891,415 @@ impl Renderer {
             self.active_search = None;
         }
 
+        if let Some(active_unicode_input) = &self.active_unicode_input {
+            search::draw_unicode_input_bar(
+                &mut objects,
+                &self.named_colors,
+                (layout.width, layout.height, layout.dimensions.scale),
+                active_unicode_input,
+            );
+
+            self.active_unicode_input = None;
+        }
+
+        if let Some(active_tab_rename) = &self.active_tab_rename {
+            search::draw_tab_rename_bar(
+                &mut objects,
+                &self.named_colors,
+                (layout.width, layout.height, layout.dimensions.scale),
+                active_tab_rename,
+            );
+
+            self.active_tab_rename = None;
+        }
+
+        if let Some(active_paste_preview) = &self.active_paste_preview {
+            paste::draw_paste_preview(
+                &mut objects,
+                &self.named_colors,
+                (layout.width, layout.height, layout.dimensions.scale),
+                active_paste_preview,
+            );
+
+            self.active_paste_preview = None;
+        }
+
+        if let Some(active_status_message) = &self.active_status_message {
+            status::draw_status_message(
+                &mut objects,
+                &self.named_colors,
+                (layout.width, layout.height, layout.dimensions.scale),
+                active_status_message,
+            );
+
+            self.active_status_message = None;
+        }
+
+        if let Some(active_cell_inspector) = &self.active_cell_inspector {
+            inspector::draw_cell_inspector(
+                &mut objects,
+                &self.named_colors,
+                (layout.width, layout.height, layout.dimensions.scale),
+                layout.margin.top_y,
+                active_cell_inspector,
+            );
+
+            self.active_cell_inspector = None;
+        }
+
         sugarloaf.set_objects(objects);
     }
 }
+
+impl Palette for Renderer {
+    #[inline]
+    fn named(&self, color: NamedColor) -> ColorArray {
+        match color {
+            NamedColor::Background => self.named_colors.background.0,
+            NamedColor::Cursor => self.named_colors.cursor,
+            NamedColor::Black => self.named_colors.black,
+            NamedColor::Blue => self.named_colors.blue,
+            NamedColor::Cyan => self.named_colors.cyan,
+            NamedColor::Foreground => self.named_colors.foreground,
+            NamedColor::Green => self.named_colors.green,
+            NamedColor::Magenta => self.named_colors.magenta,
+            NamedColor::Red => self.named_colors.red,
+            NamedColor::White => self.named_colors.white,
+            NamedColor::Yellow => self.named_colors.yellow,
+            NamedColor::LightBlack => self.named_colors.light_black,
+            NamedColor::LightBlue => self.named_colors.light_blue,
+            NamedColor::LightCyan => self.named_colors.light_cyan,
+            NamedColor::LightForeground => self.named_colors.light_foreground,
+            NamedColor::LightGreen => self.named_colors.light_green,
+            NamedColor::LightMagenta => self.named_colors.light_magenta,
+            NamedColor::LightRed => self.named_colors.light_red,
+            NamedColor::LightWhite => self.named_colors.light_white,
+            NamedColor::LightYellow => self.named_colors.light_yellow,
+            NamedColor::DimBlack => self.named_colors.dim_black,
+            NamedColor::DimBlue => self.named_colors.dim_blue,
+            NamedColor::DimCyan => self.named_colors.dim_cyan,
+            NamedColor::DimForeground => self.named_colors.dim_foreground,
+            NamedColor::DimGreen => self.named_colors.dim_green,
+            NamedColor::DimMagenta => self.named_colors.dim_magenta,
+            NamedColor::DimRed => self.named_colors.dim_red,
+            NamedColor::DimWhite => self.named_colors.dim_white,
+            NamedColor::DimYellow => self.named_colors.dim_yellow,
+        }
+    }
+
+    #[inline]
+    fn indexed(&self, index: usize) -> ColorArray {
+        self.colors[index]
+    }
+}
+
+/// Terminal content of a pane of the current tab, the whole tab when it
+/// isn't split.
+pub struct PaneContent {
+    /// Area of the pane in the window grid.
+    pub rect: PaneRect,
+    pub rows: Vec<Row<Square>>,
+    pub display_offset: i32,
+    pub history_size: usize,
+    pub wrap_lines: bool,
+    /// Cells the truncated lines are scrolled to the right.
+    pub line_scroll: usize,
+    pub is_focused: bool,
+}
+
+/// Grid rows `index..end` shown on one display line, starting `offset`
+/// cells into them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplaySpan {
+    pub index: usize,
+    pub end: usize,
+    pub offset: usize,
+}
+
+impl DisplaySpan {
+    #[inline]
+    fn row(index: usize) -> Self {
+        Self {
+            index,
+            end: index + 1,
+            offset: 0,
+        }
+    }
+
+    /// Grid row and column of the display `column`, clamped to the last
+    /// cell of the span.
+    #[inline]
+    fn cell(&self, column: usize, columns: usize) -> (usize, usize) {
+        let cell = self.offset + column;
+        let row = self.index + cell / columns.max(1);
+        if row < self.end {
+            (row, cell % columns.max(1))
+        } else {
+            (self.end - 1, columns.saturating_sub(1))
+        }
+    }
+
+    /// Grid position of the display `column`, `None` past the end of the
+    /// span.
+    #[inline]
+    fn pos(&self, column: usize, columns: usize, display_offset: i32) -> Option<Pos> {
+        let cell = self.offset + column;
+        let row = self.index + cell / columns.max(1);
+        (row < self.end).then(|| {
+            Pos::new(
+                Line(row as i32 - display_offset),
+                Column(cell % columns.max(1)),
+            )
+        })
+    }
+}
+
+#[inline]
+fn wraps(row: &Row<Square>) -> bool {
+    row.len() > 0 && row[Column(row.len() - 1)].flags.contains(Flags::WRAPLINE)
+}
+
+/// Index of the rows still on display once the continuation rows of
+/// soft-wrapped lines are dropped. The cursor row is always kept.
+fn truncated_row_indices(rows: &[Row<Square>], cursor_row: Option<usize>) -> Vec<usize> {
+    let mut indices = Vec::with_capacity(rows.len());
+    let mut is_continuation = false;
+
+    for (i, row) in rows.iter().enumerate() {
+        if !is_continuation || cursor_row == Some(i) {
+            indices.push(i);
+        }
+        is_continuation = wraps(row);
+    }
+
+    indices
+}
+
+/// Cells of grid rows `index..end` up to the last one with content.
+#[inline]
+fn line_length(rows: &[Row<Square>], index: usize, end: usize) -> usize {
+    (end - index - 1) * rows[index].len() + rows[end - 1].line_length().0
+}
+
+/// Furthest truncated lines can be scrolled to the right, so the end of
+/// the longest line in `rows` is still on display.
+pub fn max_line_scroll(rows: &[Row<Square>]) -> usize {
+    let indices = truncated_row_indices(rows, None);
+    indices
+        .iter()
+        .enumerate()
+        .map(|(k, &index)| {
+            let end = indices.get(k + 1).copied().unwrap_or(rows.len());
+            line_length(rows, index, end).saturating_sub(rows[index].len())
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Display lines once soft-wrapped lines are truncated. Each line shows
+/// the cells of a logical line from `offset` on, a `>` in the last column
+/// tells that it goes on past the right edge.
+fn truncate_wrapped_rows(
+    rows: &[Row<Square>],
+    cursor_row: Option<usize>,
+    offset: usize,
+) -> Vec<(DisplaySpan, Row<Square>)> {
+    let indices = truncated_row_indices(rows, cursor_row);
+
+    indices
+        .iter()
+        .enumerate()
+        .map(|(k, &index)| {
+            let end = indices.get(k + 1).copied().unwrap_or(rows.len());
+            let columns = rows[index].len();
+            let cells = (end - index) * columns;
+
+            let mut row = Row::new(columns);
+            for column in 0..columns.min(cells.saturating_sub(offset)) {
+                let cell = offset + column;
+                let mut square =
+                    rows[index + cell / columns][Column(cell % columns)].clone();
+                square.flags.remove(Flags::WRAPLINE);
+                row[Column(column)] = square;
+            }
+
+            if columns > 0 {
+                // A wide char cut by the left edge leaves its spacer alone.
+                if row[Column(0)].flags.contains(Flags::WIDE_CHAR_SPACER) {
+                    row[Column(0)] = Square::default();
+                }
+
+                if offset + columns < line_length(rows, index, end)
+                    || wraps(&rows[end - 1])
+                {
+                    let last_column = Column(columns - 1);
+                    if last_column.0 > 0
+                        && row[last_column].flags.contains(Flags::WIDE_CHAR_SPACER)
+                    {
+                        row[last_column - 1] = Square::default();
+                    }
+                    row[last_column] = Square {
+                        c: '>',
+                        ..Square::default()
+                    };
+                }
+            }
+
+            (DisplaySpan { index, end, offset }, row)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(text: &str, wraps: bool) -> Row<Square> {
+        let mut row = Row::<Square>::new(4);
+        for (column, c) in text.chars().enumerate() {
+            row[Column(column)].c = c;
+        }
+        if wraps {
+            row[Column(3)].flags.insert(Flags::WRAPLINE);
+        }
+        row
+    }
+
+    fn text(row: &Row<Square>) -> String {
+        row.inner.iter().map(|square| square.c).collect()
+    }
+
+    #[test]
+    fn test_render_whitespace() {
+        let mut config = Config::default();
+        config.renderer.render_whitespace = true;
+        let mut renderer = Renderer::new(&config, None);
+        let mut row = Row::<Square>::new(6);
+        row[Column(0)].c = 'a';
+        row[Column(2)].c = 'b';
+
+        // Trailing whitespace is marked as well, but not under the cursor.
+        let mut content_builder = Content::builder();
+        renderer.add_row(
+            &mut content_builder,
+            &row,
+            6,
+            DisplaySpan::row(0),
+            0,
+            Some(Pos::new(Line(0), Column(4))),
+            &mut None,
+            &None,
+        );
+        assert_eq!(content_builder.build_ref().text, "a·b· ·");
+    }
+
+    #[test]
+    fn test_truncate_wrapped_rows() {
+        let rows = vec![
+            row("abcd", true),
+            row("efgh", true),
+            row("ij", false),
+            row("rio", false),
+        ];
+
+        let truncated = truncate_wrapped_rows(&rows, None, 0);
+        assert_eq!(truncated.len(), 2);
+
+        // The line goes on past the edge, the last column shows a marker.
+        let (span, first) = &truncated[0];
+        assert_eq!(
+            *span,
+            DisplaySpan {
+                index: 0,
+                end: 3,
+                offset: 0
+            }
+        );
+        assert_eq!(text(first), "abc>");
+        assert!(!first[Column(3)].flags.contains(Flags::WRAPLINE));
+
+        // Rows keep their index so selection and hints still match.
+        let (span, second) = &truncated[1];
+        assert_eq!(*span, DisplaySpan::row(3));
+        assert_eq!(second, &rows[3]);
+
+        // Stored rows are untouched.
+        assert_eq!(rows[0][Column(3)].c, 'd');
+    }
+
+    #[test]
+    fn test_truncate_wrapped_rows_scrolled() {
+        let rows = vec![
+            row("abcd", true),
+            row("efgh", true),
+            row("ij", false),
+            row("rio", false),
+        ];
+        assert_eq!(max_line_scroll(&rows), 6);
+
+        let truncated = truncate_wrapped_rows(&rows, None, 3);
+        assert_eq!(text(&truncated[0].1), "def>");
+        // Short lines scroll out of view.
+        assert_eq!(text(&truncated[1].1), "    ");
+
+        // The end of the line has no marker.
+        let truncated = truncate_wrapped_rows(&rows, None, 8);
+        assert_eq!(text(&truncated[0].1), "ij  ");
+
+        // Display cells map back to the grid rows they come from.
+        let span = truncated_row_span(&rows, 3);
+        assert_eq!(span.cell(0, 4), (0, 3));
+        assert_eq!(span.cell(1, 4), (1, 0));
+        assert_eq!(span.pos(1, 4, 0), Some(Pos::new(Line(1), Column(0))));
+        assert_eq!(span.pos(3, 4, 2), Some(Pos::new(Line(-1), Column(2))));
+        let span = truncated_row_span(&rows, 10);
+        assert_eq!(span.pos(2, 4, 0), None);
+        assert_eq!(span.cell(2, 4), (2, 3));
+    }
+
+    fn truncated_row_span(rows: &[Row<Square>], offset: usize) -> DisplaySpan {
+        truncate_wrapped_rows(rows, None, offset)[0].0
+    }
+
+    #[test]
+    fn test_truncate_wrapped_rows_keeps_cursor_row() {
+        let rows = vec![
+            row("abcd", true),
+            row("efgh", true),
+            row("ij", false),
+            row("rio", false),
+        ];
+
+        assert_eq!(truncated_row_indices(&rows, None), vec![0, 3]);
+        assert_eq!(truncated_row_indices(&rows, Some(2)), vec![0, 2, 3]);
+
+        // The line before the cursor row is cut there, the cursor row
+        // shows the rest of it.
+        let truncated = truncate_wrapped_rows(&rows, Some(1), 0);
+        assert_eq!(truncated.len(), 3);
+        assert_eq!(text(&truncated[0].1), "abc>");
+        assert_eq!(truncated[1].0.index, 1);
+        assert_eq!(text(&truncated[1].1), "efg>");
+    }
+
+    #[test]
+    fn test_truncate_wrapped_rows_wide_char() {
+        let mut wide = row("ab", true);
+        wide[Column(2)].c = '漢';
+        wide[Column(2)].flags.insert(Flags::WIDE_CHAR);
+        wide[Column(3)].flags.insert(Flags::WIDE_CHAR_SPACER);
+        let rows = vec![wide, row("cd", false)];
+
+        // The marker takes the place of a wide char that doesn't fit.
+        let truncated = truncate_wrapped_rows(&rows, None, 0);
+        assert_eq!(text(&truncated[0].1), "ab >");
+        assert!(!truncated[0].1[Column(2)].flags.contains(Flags::WIDE_CHAR));
+
+        // A wide char cut by the left edge leaves no spacer behind.
+        let truncated = truncate_wrapped_rows(&rows, None, 3);
+        assert_eq!(text(&truncated[0].1), " cd ");
+        assert!(!truncated[0].1[Column(0)]
+            .flags
+            .contains(Flags::WIDE_CHAR_SPACER));
+    }
+}
@@ -205,14 +205,9 @@ impl ScreenNavigation {
 
         let text_pos_mod = 11.;
         for i in tabs {
-            let mut background_color = colors.bar;
-            let mut foreground_color = colors.tabs_foreground;
-
             let is_current = i == self.current;
-            if is_current {
-                foreground_color = colors.tabs_active_foreground;
-                background_color = colors.tabs_active;
-            }
+            let (mut background_color, mut foreground_color) =
+                tab_colors(colors, is_current, self.navigation.dim_inactive_tabs);
 
             let mut name = String::from("tab");
             if let Some(title) = titles.get(&i) {
@@ -274,6 +269,26 @@ impl ScreenNavigation {
     }
 }
 
+/// Background and foreground colors of a tab, the whole tab area keeps
+/// being drawn either way.
+#[inline]
+fn tab_colors(
+    colors: &Colors,
+    is_current: bool,
+    dim_inactive: bool,
+) -> ([f32; 4], [f32; 4]) {
+    if is_current {
+        return (colors.tabs_active, colors.tabs_active_foreground);
+    }
+
+    let mut foreground_color = colors.tabs_foreground;
+    if dim_inactive {
+        foreground_color[3] *= 0.5;
+    }
+
+    (colors.tabs_inactive.unwrap_or(colors.bar), foreground_color)
+}
+
 #[inline]
 fn get_color_overwrite<'a>(
     color_automation: &'a HashMap<String, HashMap<String, [f32; 4]>>,
@@ -290,7 +305,37 @@ fn get_color_overwrite<'a>(
 mod tests {
     use std::collections::HashMap;
 
-    use crate::renderer::navigation::get_color_overwrite;
+    use crate::renderer::navigation::{get_color_overwrite, tab_colors};
+    use rio_backend::config::colors::Colors;
+
+    #[test]
+    fn test_tab_colors() {
+        let colors = Colors {
+            tabs_inactive: Some([0.1, 0.1, 0.1, 1.0]),
+            tabs_foreground: [0.8, 0.8, 0.8, 1.0],
+            ..Colors::default()
+        };
+
+        assert_eq!(
+            tab_colors(&colors, true, true),
+            (colors.tabs_active, colors.tabs_active_foreground)
+        );
+        assert_eq!(
+            tab_colors(&colors, false, false),
+            ([0.1, 0.1, 0.1, 1.0], [0.8, 0.8, 0.8, 1.0])
+        );
+        assert_eq!(
+            tab_colors(&colors, false, true),
+            ([0.1, 0.1, 0.1, 1.0], [0.8, 0.8, 0.8, 0.5])
+        );
+
+        let colors = Colors {
+            tabs_inactive: None,
+            bar: [0.2, 0.2, 0.2, 1.0],
+            ..Colors::default()
+        };
+        assert_eq!(tab_colors(&colors, false, false).0, [0.2, 0.2, 0.2, 1.0]);
+    }
 
     #[test]
     fn test_get_color_overwrite() {
@@ -0,0 +1,79 @@
+use rio_backend::config::colors::Colors;
+use rio_backend::sugarloaf::{Object, Rect, Text};
+
+const PREVIEW_LINES: usize = 8;
+const PREVIEW_LINE_HEIGHT: f32 = 18.;
+const PREVIEW_MAX_CHARS: usize = 120;
+
+#[inline]
+pub fn draw_paste_preview(
+    objects: &mut Vec<Object>,
+    colors: &Colors,
+    dimensions: (f32, f32, f32),
+    content: &str,
+) {
+    let (width, height, scale) = dimensions;
+
+    let lines: Vec<&str> = content.lines().collect();
+    let shown = lines.len().min(PREVIEW_LINES);
+    let hidden = lines.len() - shown;
+
+    // Title, preview lines, optional "more lines" and the help line.
+    let rows = shown + 2 + usize::from(hidden > 0);
+    let panel_height = rows as f32 * PREVIEW_LINE_HEIGHT + 10.;
+    let position_y = (height / scale) - panel_height;
+
+    objects.push(Object::Rect(Rect {
+        position: [0.0, position_y],
+        color: colors.bar,
+        size: [width * 2., panel_height],
+    }));
+
+    let dimmed = [
+        colors.foreground[0],
+        colors.foreground[1],
+        colors.foreground[2],
+        colors.foreground[3] - 0.3,
+    ];
+
+    let mut line_y = position_y + 10.;
+    objects.push(Object::Text(Text::single_line(
+        (4., line_y),
+        format!("Paste {} lines?", lines.len()),
+        14.,
+        colors.foreground,
+    )));
+
+    for line in &lines[..shown] {
+        line_y += PREVIEW_LINE_HEIGHT;
+        let line: String = line
+            .chars()
+            .filter(|c| !c.is_control())
+            .take(PREVIEW_MAX_CHARS)
+            .collect();
+        objects.push(Object::Text(Text::single_line(
+            (14., line_y),
+            line,
+            14.,
+            colors.foreground,
+        )));
+    }
+
+    if hidden > 0 {
+        line_y += PREVIEW_LINE_HEIGHT;
+        objects.push(Object::Text(Text::single_line(
+            (14., line_y),
+            format!("... {} more lines", hidden),
+            14.,
+            dimmed,
+        )));
+    }
+
+    line_y += PREVIEW_LINE_HEIGHT;
+    objects.push(Object::Text(Text::single_line(
+        (4., line_y),
+        String::from("Enter to paste, Escape to cancel"),
+        14.,
+        dimmed,
+    )));
+}
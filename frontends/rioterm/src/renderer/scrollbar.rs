@@ -0,0 +1,95 @@
+use rio_backend::config::colors::Colors;
+use rio_backend::sugarloaf::{Object, Rect};
+
+const SCROLLBAR_WIDTH: f32 = 4.;
+const MIN_THUMB_HEIGHT: f32 = 8.;
+
+/// Start and length of the scrollbar thumb, as fractions of the pane
+/// height. Nothing to draw without scrollback.
+pub fn thumb(
+    history_size: usize,
+    screen_lines: usize,
+    display_offset: usize,
+) -> Option<(f32, f32)> {
+    if history_size == 0 || screen_lines == 0 {
+        return None;
+    }
+
+    let total = (history_size + screen_lines) as f32;
+    let top = history_size.saturating_sub(display_offset) as f32;
+    Some((top / total, screen_lines as f32 / total))
+}
+
+/// Draw the scrollbar along the right edge of a pane, `area` is its
+/// right edge, top and height.
+#[inline]
+pub fn draw_scrollbar(
+    objects: &mut Vec<Object>,
+    colors: &Colors,
+    area: (f32, f32, f32),
+    history_size: usize,
+    screen_lines: usize,
+    display_offset: usize,
+) {
+    let Some((start, length)) = thumb(history_size, screen_lines, display_offset) else {
+        return;
+    };
+
+    let (right, top, height) = area;
+    let thumb_height = (height * length).max(MIN_THUMB_HEIGHT).min(height);
+    let position_y = (top + height * start).min(top + height - thumb_height);
+
+    objects.push(Object::Rect(Rect {
+        position: [right - SCROLLBAR_WIDTH, position_y],
+        color: [
+            colors.foreground[0],
+            colors.foreground[1],
+            colors.foreground[2],
+            0.3,
+        ],
+        size: [SCROLLBAR_WIDTH, thumb_height],
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thumb() {
+        assert_eq!(thumb(0, 10, 0), None);
+
+        // At the bottom the thumb ends with the pane.
+        assert_eq!(thumb(30, 10, 0), Some((0.75, 0.25)));
+        assert_eq!(thumb(30, 10, 30), Some((0., 0.25)));
+        assert_eq!(thumb(30, 10, 15), Some((0.375, 0.25)));
+    }
+
+    #[test]
+    fn test_draw_scrollbar() {
+        let colors = Colors::default();
+        let mut objects = Vec::new();
+        draw_scrollbar(&mut objects, &colors, (100., 10., 200.), 0, 10, 0);
+        assert!(objects.is_empty());
+
+        draw_scrollbar(&mut objects, &colors, (100., 10., 200.), 30, 10, 0);
+        match objects.as_slice() {
+            [Object::Rect(rect)] => {
+                assert_eq!(rect.position, [96., 160.]);
+                assert_eq!(rect.size, [4., 50.]);
+            }
+            _ => panic!("expected a single thumb"),
+        }
+
+        // A long history keeps the thumb visible.
+        objects.clear();
+        draw_scrollbar(&mut objects, &colors, (100., 10., 200.), 10_000, 10, 0);
+        match objects.as_slice() {
+            [Object::Rect(rect)] => {
+                assert_eq!(rect.position, [96., 202.]);
+                assert_eq!(rect.size, [4., 8.]);
+            }
+            _ => panic!("expected a single thumb"),
+        }
+    }
+}
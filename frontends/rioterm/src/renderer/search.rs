@@ -1,4 +1,5 @@
 use crate::constants::*;
+use crate::screen::char_from_codepoint;
 use rio_backend::config::colors::Colors;
 use rio_backend::sugarloaf::{Object, Rect, Text};
 
@@ -40,3 +41,78 @@ pub fn draw_search_bar(
         colors.foreground,
     )));
 }
+
+#[inline]
+pub fn draw_unicode_input_bar(
+    objects: &mut Vec<Object>,
+    colors: &Colors,
+    dimensions: (f32, f32, f32),
+    content: &str,
+) {
+    draw_input_bar(objects, colors, dimensions, &unicode_input_text(content));
+}
+
+#[inline]
+pub fn draw_tab_rename_bar(
+    objects: &mut Vec<Object>,
+    colors: &Colors,
+    dimensions: (f32, f32, f32),
+    content: &str,
+) {
+    draw_input_bar(
+        objects,
+        colors,
+        dimensions,
+        &format!("Tab name: {}", content),
+    );
+}
+
+/// Bar at the bottom of the window showing the text being typed.
+#[inline]
+fn draw_input_bar(
+    objects: &mut Vec<Object>,
+    colors: &Colors,
+    dimensions: (f32, f32, f32),
+    text: &str,
+) {
+    let (width, height, scale) = dimensions;
+    let position_y = (height / scale) - PADDING_Y_BOTTOM_TABS;
+
+    objects.push(Object::Rect(Rect {
+        position: [0.0, position_y],
+        color: colors.bar,
+        size: [width * 2., PADDING_Y_BOTTOM_TABS],
+    }));
+
+    objects.push(Object::Text(Text::single_line(
+        (4., position_y + 10.),
+        text.to_owned(),
+        14.,
+        colors.foreground,
+    )));
+}
+
+/// Label of the unicode input bar, flagging codepoints that can not be
+/// typed.
+fn unicode_input_text(content: &str) -> String {
+    if content.is_empty() || char_from_codepoint(content).is_some() {
+        format!("Unicode: U+{}", content)
+    } else {
+        format!("Unicode: U+{} is not a valid codepoint", content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unicode_input_text() {
+        assert_eq!(unicode_input_text(""), "Unicode: U+");
+        assert_eq!(unicode_input_text("E9"), "Unicode: U+E9");
+        assert_eq!(
+            unicode_input_text("DFFF"),
+            "Unicode: U+DFFF is not a valid codepoint"
+        );
+    }
+}
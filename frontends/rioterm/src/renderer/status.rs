@@ -0,0 +1,186 @@
+use crate::constants::STATUS_LINE_HEIGHT;
+use crate::context::ContextInfo;
+use rio_backend::config::colors::Colors;
+use rio_backend::sugarloaf::{Object, Rect, Text};
+
+const BADGE_FONT_SIZE: f32 = 14.;
+const BADGE_CHAR_WIDTH: f32 = 8.5;
+const BADGE_PADDING: f32 = 12.;
+const BADGE_HEIGHT: f32 = 28.;
+
+#[inline]
+pub fn draw_status_message(
+    objects: &mut Vec<Object>,
+    colors: &Colors,
+    dimensions: (f32, f32, f32),
+    message: &str,
+) {
+    let (width, height, scale) = dimensions;
+
+    let text_width = message.chars().count() as f32 * BADGE_CHAR_WIDTH;
+    let badge_width = text_width + BADGE_PADDING * 2.;
+    let position_x = ((width / scale) - badge_width) / 2.;
+    let position_y = ((height / scale) - BADGE_HEIGHT) / 2.;
+
+    objects.push(Object::Rect(Rect {
+        position: [position_x, position_y],
+        color: [colors.bar[0], colors.bar[1], colors.bar[2], 0.8],
+        size: [badge_width, BADGE_HEIGHT],
+    }));
+
+    objects.push(Object::Text(Text::single_line(
+        (
+            position_x + BADGE_PADDING,
+            position_y + BADGE_HEIGHT / 2. - 2.,
+        ),
+        message.to_owned(),
+        BADGE_FONT_SIZE,
+        colors.foreground,
+    )));
+}
+
+/// Values available to `status-line-format`, collected by `Screen`
+/// on every frame.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct StatusLineInfo {
+    pub mode: &'static str,
+    pub title: String,
+    pub cwd: String,
+    pub selection: String,
+    pub time: String,
+    pub tabs: String,
+}
+
+/// Replace the placeholders of `template` with `info`, unknown
+/// placeholders are kept as they are.
+pub fn format_status_line(template: &str, info: &StatusLineInfo) -> String {
+    template
+        .replace("{mode}", info.mode)
+        .replace("{title}", &info.title)
+        .replace("{cwd}", &info.cwd)
+        .replace("{selection}", &info.selection)
+        .replace("{time}", &info.time)
+        .replace("{tabs}", &info.tabs)
+        .trim()
+        .to_owned()
+}
+
+/// Content of `{tabs}`: the current tab in brackets and the ones with
+/// activity or a bell marked with `*`.
+pub fn format_tabs(contexts: &[ContextInfo]) -> String {
+    contexts
+        .iter()
+        .map(|info| {
+            let marker = if info.has_activity || info.has_bell {
+                "*"
+            } else {
+                ""
+            };
+            let tab = format!("{}:{}{marker}", info.index + 1, info.title);
+            if info.is_active {
+                format!("[{tab}]")
+            } else {
+                tab
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Local time as `HH:MM`, UTC where the local timezone is not available.
+pub fn local_time() -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+
+    #[cfg(unix)]
+    {
+        let seconds = now as libc::time_t;
+        let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+        if !unsafe { libc::localtime_r(&seconds, &mut tm) }.is_null() {
+            return format!("{:02}:{:02}", tm.tm_hour, tm.tm_min);
+        }
+    }
+
+    format!("{:02}:{:02}", (now / 3600) % 24, (now / 60) % 60)
+}
+
+#[inline]
+pub fn draw_status_line(
+    objects: &mut Vec<Object>,
+    colors: &Colors,
+    dimensions: (f32, f32, f32),
+    margin_bottom: f32,
+    content: &str,
+) {
+    let (width, height, scale) = dimensions;
+    let position_y = (height / scale) - margin_bottom;
+
+    objects.push(Object::Rect(Rect {
+        position: [0.0, position_y],
+        color: colors.bar,
+        size: [width * 2., STATUS_LINE_HEIGHT],
+    }));
+
+    objects.push(Object::Text(Text::single_line(
+        (4., position_y + 10.),
+        content.to_owned(),
+        14.,
+        colors.foreground,
+    )));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_status_line() {
+        let info = StatusLineInfo {
+            mode: "VI",
+            title: String::from("zsh"),
+            cwd: String::from("/home/rio"),
+            selection: String::new(),
+            time: String::from("09:05"),
+            tabs: String::from("[1:zsh]"),
+        };
+
+        assert_eq!(
+            format_status_line("{mode}  {cwd}  {time}", &info),
+            "VI  /home/rio  09:05"
+        );
+        assert_eq!(format_status_line("{title} {selection}", &info), "zsh");
+        assert_eq!(format_status_line("{unknown}", &info), "{unknown}");
+        assert_eq!(format_status_line("{tabs}", &info), "[1:zsh]");
+
+        let info = StatusLineInfo::default();
+        assert_eq!(format_status_line("{mode} {cwd}", &info), "");
+    }
+
+    #[test]
+    fn test_format_tabs() {
+        let tab = |index: usize, title: &str| ContextInfo {
+            index,
+            title: String::from(title),
+            cwd: None,
+            has_activity: false,
+            has_bell: false,
+            is_active: false,
+        };
+        let contexts = [
+            tab(0, "zsh"),
+            ContextInfo {
+                is_active: true,
+                ..tab(1, "vim")
+            },
+            ContextInfo {
+                has_bell: true,
+                ..tab(2, "cargo")
+            },
+        ];
+
+        assert_eq!(format_tabs(&contexts), "1:zsh [2:vim] 3:cargo*");
+        assert_eq!(format_tabs(&[]), "");
+    }
+}
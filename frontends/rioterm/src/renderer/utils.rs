@@ -1,5 +1,6 @@
 use crate::constants;
 use rio_backend::config::navigation::{Navigation, NavigationMode};
+use rio_backend::crosswords::{MIN_COLUMNS, MIN_LINES};
 
 #[inline]
 pub fn padding_top_from_config(
@@ -36,7 +37,14 @@ pub fn padding_bottom_from_config(
     padding_y_bottom: f32,
     num_tabs: usize,
     is_search_active: bool,
+    has_status_line: bool,
 ) -> f32 {
+    // The status line sits right below the grid, above the bottom tabs.
+    let padding_y_bottom = if has_status_line {
+        padding_y_bottom + constants::STATUS_LINE_HEIGHT
+    } else {
+        padding_y_bottom
+    };
     let default_padding = 0.0 + padding_y_bottom;
 
     if is_search_active {
@@ -54,6 +62,33 @@ pub fn padding_bottom_from_config(
     default_padding
 }
 
+/// Physical window size needed to fit a grid of `columns` x `lines`,
+/// the inverse of how the layout computes its columns and lines.
+/// Returns `None` while the font dimensions are still unknown.
+pub fn minimum_window_size(
+    layout: &rio_backend::sugarloaf::layout::SugarloafLayout,
+    columns: usize,
+    lines: usize,
+) -> Option<(u32, u32)> {
+    let dimensions = layout.dimensions;
+    if dimensions.width <= 0. || dimensions.height <= 0. || dimensions.scale <= 0. {
+        return None;
+    }
+
+    let columns = columns.max(MIN_COLUMNS) as f32;
+    let lines = lines.max(MIN_LINES) as f32;
+    let margin_x = (layout.margin.x * dimensions.scale).floor();
+    let margin_y = layout.margin.top_y + layout.margin.bottom_y;
+
+    let width =
+        (columns * (dimensions.width / dimensions.scale) + margin_x) * dimensions.scale;
+    let height = (lines * (dimensions.height / dimensions.scale) * layout.line_height
+        + margin_y)
+        * dimensions.scale;
+
+    Some((width.ceil() as u32, height.ceil() as u32))
+}
+
 #[inline]
 pub fn terminal_dimensions(
     layout: &rio_backend::sugarloaf::layout::SugarloafLayout,
@@ -63,7 +98,37 @@ pub fn terminal_dimensions(
     teletypewriter::WinsizeBuilder {
         width: width as u16,
         height: height as u16,
-        cols: layout.columns as u16,
-        rows: layout.lines as u16,
+        cols: layout.columns.max(MIN_COLUMNS) as u16,
+        rows: layout.lines.max(MIN_LINES) as u16,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rio_backend::sugarloaf::layout::{Delta, SugarDimensions, SugarloafLayout};
+
+    #[test]
+    fn test_minimum_window_size() {
+        let mut layout = SugarloafLayout::default();
+        assert_eq!(minimum_window_size(&layout, 10, 5), None);
+
+        layout.dimensions = SugarDimensions {
+            width: 8.,
+            height: 16.,
+            scale: 2.,
+        };
+        layout.margin = Delta {
+            x: 10.,
+            top_y: 5.,
+            bottom_y: 5.,
+        };
+        assert_eq!(minimum_window_size(&layout, 10, 5), Some((120, 100)));
+
+        // Never smaller than the grid the terminal clamps to
+        assert_eq!(
+            minimum_window_size(&layout, 0, 0),
+            minimum_window_size(&layout, MIN_COLUMNS, MIN_LINES)
+        );
     }
 }
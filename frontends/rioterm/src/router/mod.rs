@@ -1,4 +1,5 @@
 mod window;
+use crate::bindings::key::{Key, KeyEvent, NamedKey};
 use crate::event::{EventPayload, EventProxy};
 use crate::frame::FrameTimer;
 use crate::router::window::{configure_window, create_window_builder};
@@ -12,8 +13,8 @@ use rio_backend::clipboard::Clipboard;
 use rio_backend::config::Config as RioConfig;
 use rio_backend::error::{RioError, RioErrorLevel, RioErrorType};
 use rio_backend::event::RioEventType;
+use rio_window::dpi::PhysicalSize;
 use rio_window::event_loop::ActiveEventLoop;
-use rio_window::keyboard::{Key, NamedKey};
 #[cfg(not(any(target_os = "macos", windows)))]
 use rio_window::platform::startup_notify::{
     self, EventLoopExtStartupNotify, WindowAttributesExtStartupNotify,
@@ -53,6 +54,13 @@ impl Route {
         self.window.winit_window.request_redraw();
     }
 
+    /// Whether a redraw can be requested now: either a frame is available
+    /// or it is a response to typing with low latency input enabled.
+    #[inline]
+    pub fn can_redraw(&mut self) -> bool {
+        self.window.screen.take_input_redraw() || self.window.has_frame
+    }
+
     /// Request a new frame for a window
     pub fn request_frame(&mut self, scheduler: &mut Scheduler) {
         // Mark that we've used a frame.
@@ -95,6 +103,7 @@ impl Route {
         self.window
             .screen
             .update_config(config, self.window.winit_window.theme(), db);
+        self.window.update_min_inner_size();
     }
 
     #[inline]
@@ -137,7 +146,7 @@ impl Route {
     }
 
     #[inline]
-    pub fn has_key_wait(&mut self, key_event: &rio_window::event::KeyEvent) -> bool {
+    pub fn has_key_wait(&mut self, key_event: &KeyEvent) -> bool {
         if self.path == RoutePath::Terminal {
             return false;
         }
@@ -343,6 +352,7 @@ pub struct RouteWindow {
     pub winit_window: Window,
     pub frame_timer: FrameTimer,
     pub screen: Screen<'static>,
+    min_inner_size: Option<PhysicalSize<u32>>,
     #[cfg(target_os = "macos")]
     pub is_macos_deadzone: bool,
 }
@@ -389,9 +399,27 @@ impl RouteWindow {
             frame_timer: FrameTimer::new(),
             winit_window,
             screen,
+            min_inner_size: None,
             #[cfg(target_os = "macos")]
             is_macos_deadzone: false,
-        })
+        }
+        .with_min_inner_size())
+    }
+
+    #[inline]
+    fn with_min_inner_size(mut self) -> Self {
+        self.update_min_inner_size();
+        self
+    }
+
+    /// Keep the window from shrinking below the configured grid, it
+    /// has to be refreshed whenever the cell size changes.
+    pub fn update_min_inner_size(&mut self) {
+        let min_inner_size = self.screen.min_window_size();
+        if min_inner_size != self.min_inner_size {
+            self.winit_window.set_min_inner_size(min_inner_size);
+            self.min_inner_size = min_inner_size;
+        }
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -449,8 +477,10 @@ impl RouteWindow {
             is_occluded: false,
             winit_window,
             screen,
+            min_inner_size: None,
             #[cfg(target_os = "macos")]
             is_macos_deadzone: false,
         }
+        .with_min_inner_size()
     }
 }
@@ -27,6 +27,10 @@ pub enum Topic {
     Render,
     RenderRoute,
     Frame,
+    StartupScript,
+    ClipboardPaste,
+    MotionReport,
+    ClipboardPoll,
 }
 
 /// Event scheduled to be emitted at a specific time.
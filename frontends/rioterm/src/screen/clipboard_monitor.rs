@@ -0,0 +1,88 @@
+use rio_backend::clipboard::ClipboardType;
+
+/// How often the clipboards are checked while a change listener is
+/// installed, in milliseconds.
+pub const CLIPBOARD_POLL_INTERVAL_MS: u64 = 500;
+
+/// Last seen content of the clipboard and the selection clipboard. There
+/// is no change notification from the clipboard providers, changes are
+/// found by polling them.
+#[derive(Default)]
+pub struct ClipboardMonitor {
+    contents: [Option<String>; 2],
+}
+
+impl ClipboardMonitor {
+    /// Forget what was seen, the next poll only sets the baseline.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.contents = [None, None];
+    }
+
+    /// Read both clipboards with `read` and call `listener` for the ones
+    /// that changed since the previous poll. Clipboards `read` returns
+    /// `None` for are checked again on the next poll.
+    pub fn poll(
+        &mut self,
+        mut read: impl FnMut(ClipboardType) -> Option<String>,
+        listener: &dyn Fn(ClipboardType, &str),
+    ) {
+        let types = [ClipboardType::Clipboard, ClipboardType::Selection];
+        for (index, ty) in types.into_iter().enumerate() {
+            let Some(content) = read(ty) else {
+                continue;
+            };
+
+            let last_seen = &mut self.contents[index];
+            if last_seen.as_ref() == Some(&content) {
+                continue;
+            }
+
+            // The first read only sets the baseline.
+            if last_seen.is_some() {
+                listener(ty, &content);
+            }
+            *last_seen = Some(content);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[test]
+    fn test_poll_notifies_changes() {
+        let mut monitor = ClipboardMonitor::default();
+        let changes = RefCell::new(Vec::new());
+        let listener = |ty: ClipboardType, content: &str| {
+            changes.borrow_mut().push((ty, content.to_owned()));
+        };
+
+        monitor.poll(|_| Some(String::from("a")), &listener);
+        monitor.poll(|_| Some(String::from("a")), &listener);
+        assert!(changes.borrow().is_empty());
+
+        // Only the clipboard changed, the selection is busy.
+        monitor.poll(
+            |ty| (ty == ClipboardType::Clipboard).then(|| String::from("b")),
+            &listener,
+        );
+        assert_eq!(
+            *changes.borrow(),
+            [(ClipboardType::Clipboard, String::from("b"))]
+        );
+
+        monitor.poll(|_| Some(String::from("c")), &listener);
+        assert_eq!(changes.borrow().len(), 3);
+        assert_eq!(
+            changes.borrow()[2],
+            (ClipboardType::Selection, String::from("c"))
+        );
+
+        monitor.reset();
+        monitor.poll(|_| Some(String::from("d")), &listener);
+        assert_eq!(changes.borrow().len(), 3);
+    }
+}
@@ -0,0 +1,72 @@
+/// Number of macro slots, `StartRecordMacro(0)` to `StartRecordMacro(9)`.
+pub const MACRO_SLOTS: usize = 10;
+
+/// Keyboard macros, stored as the bytes typed into the terminal. Slots
+/// are kept for the whole session and replayed into the current context.
+#[derive(Default)]
+pub struct Macros {
+    slots: [Option<Vec<u8>>; MACRO_SLOTS],
+    /// Slot being recorded and what was typed so far.
+    recording: Option<(usize, Vec<u8>)>,
+}
+
+impl Macros {
+    /// Start recording into `slot`, a recording in progress is dropped.
+    /// Returns `false` if the slot does not exist.
+    pub fn start(&mut self, slot: usize) -> bool {
+        if slot >= MACRO_SLOTS {
+            return false;
+        }
+
+        self.recording = Some((slot, Vec::new()));
+        true
+    }
+
+    /// Store the recording in its slot, returning the slot.
+    pub fn stop(&mut self) -> Option<usize> {
+        let (slot, bytes) = self.recording.take()?;
+        self.slots[slot] = Some(bytes);
+        Some(slot)
+    }
+
+    #[inline]
+    pub fn record(&mut self, bytes: &[u8]) {
+        if let Some((_, recorded)) = &mut self.recording {
+            recorded.extend_from_slice(bytes);
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, slot: usize) -> Option<&[u8]> {
+        self.slots.get(slot)?.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_replace_macro() {
+        let mut macros = Macros::default();
+        macros.record(b"ignored");
+        assert_eq!(macros.stop(), None);
+
+        assert!(macros.start(1));
+        macros.record(b"ls");
+        macros.record(b"\r");
+        assert_eq!(macros.get(1), None);
+        assert_eq!(macros.stop(), Some(1));
+        assert_eq!(macros.get(1), Some(&b"ls\r"[..]));
+
+        // Recording again replaces the slot only once it stops.
+        assert!(macros.start(1));
+        macros.record(b"pwd\r");
+        assert_eq!(macros.get(1), Some(&b"ls\r"[..]));
+        macros.stop();
+        assert_eq!(macros.get(1), Some(&b"pwd\r"[..]));
+
+        assert!(!macros.start(MACRO_SLOTS));
+        assert_eq!(macros.get(MACRO_SLOTS), None);
+    }
+}
@@ -6,29 +6,42 @@
 // were retired from https://github.com/alacritty/alacritty/blob/c39c3c97f1a1213418c3629cc59a1d46e34070e0/alacritty/src/input.rs
 // which is licensed under Apache 2.0 license.
 
+pub mod clipboard_monitor;
 pub mod hint;
+pub mod macros;
 pub mod touch;
 
 use crate::bindings::{
-    Action as Act, BindingKey, BindingMode, FontSizeAction, MouseBinding, SearchAction,
-    ViAction,
+    input_map::InputMap,
+    key::{Key, KeyEvent, KeyLocation, KeyState, NamedKey},
+    Action as Act, BindingKey, BindingMode, FontSizeAction, MouseAction, MouseBinding,
+    SearchAction, ViAction,
 };
 #[cfg(target_os = "macos")]
 use crate::constants::{DEADZONE_END_Y, DEADZONE_START_Y};
-use crate::context::{self, process_open_url, ContextManager};
+use crate::context::{
+    self, process_open_url,
+    split::{PaneRect, SplitDirection, SPLIT_RATIO_STEP},
+    ContextManager,
+};
 use crate::crosswords::{
     grid::{Dimensions, Scroll},
     pos::{Column, Pos, Side},
-    square::Hyperlink,
     vi_mode::ViMotion,
-    Mode,
+    Crosswords, Mode, INITIAL_TABSTOPS,
 };
 use crate::ime::Ime;
-use crate::mouse::{calculate_mouse_position, Mouse};
+use crate::mouse::{
+    calculate_mouse_position, calculate_side_by_pos, MotionThrottle, Mouse,
+};
 use crate::renderer::{
+    inspector::describe_cell,
+    max_line_scroll,
+    status::{format_status_line, format_tabs, local_time, StatusLineInfo},
     utils::{padding_bottom_from_config, padding_top_from_config},
-    Renderer,
+    PaneContent, Renderer,
 };
+use crate::screen::clipboard_monitor::{ClipboardMonitor, CLIPBOARD_POLL_INTERVAL_MS};
 use crate::screen::hint::HintMatches;
 use crate::selection::{Selection, SelectionType};
 use core::fmt::Debug;
@@ -38,10 +51,14 @@ use rio_backend::clipboard::ClipboardType;
 use rio_backend::config::{
     colors::term::List,
     renderer::{Backend as RendererBackend, Performance as RendererPerformance},
+    MouseWheelInAlt, SelectionClipboard, Shell, StatusLine,
 };
 use rio_backend::crosswords::pos::{Boundary, Direction, Line};
-use rio_backend::crosswords::search::RegexSearch;
-use rio_backend::event::{ClickState, EventProxy, SearchState};
+use rio_backend::crosswords::search::{RegexFlags, RegexSearch};
+use rio_backend::event::{
+    ClickState, EventListener, EventProxy, OutputFilter, SearchState,
+};
+use rio_backend::recording::{Recording, SharedRecording};
 use rio_backend::sugarloaf::{
     layout::SugarloafLayout, Sugarloaf, SugarloafErrors, SugarloafRenderer,
     SugarloafWindow, SugarloafWindowSize,
@@ -51,15 +68,19 @@ use rio_window::event::Modifiers;
 use rio_window::event::MouseButton;
 #[cfg(target_os = "macos")]
 use rio_window::keyboard::ModifiersKeyState;
-use rio_window::keyboard::{Key, KeyLocation, ModifiersState, NamedKey};
-use rio_window::platform::modifier_supplement::KeyEventExtModifierSupplement;
+use rio_window::keyboard::ModifiersState;
+use rio_window::window::CursorIcon;
 use std::borrow::Cow;
 use std::cell::RefCell;
 use std::cmp::{max, min};
+use std::collections::HashMap;
 use std::error::Error;
 use std::ffi::OsStr;
+use std::path::Path;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 use touch::TouchPurpose;
+use unicode_width::UnicodeWidthChar;
 
 /// Minimum number of pixels at the bottom/top where selection scrolling is performed.
 const MIN_SELECTION_SCROLLING_HEIGHT: f32 = 5.;
@@ -73,6 +94,32 @@ const MAX_SEARCH_WHILE_TYPING: Option<usize> = Some(1000);
 /// Maximum number of search terms stored in the history.
 const MAX_SEARCH_HISTORY_SIZE: usize = 255;
 
+/// Maximum number of hex digits accepted by the unicode input, `U+10FFFF` being the last
+/// valid codepoint.
+const MAX_UNICODE_INPUT_SIZE: usize = 6;
+/// Maximum number of characters of a tab name typed with `RenameTab`.
+const MAX_TAB_RENAME_SIZE: usize = 64;
+
+/// Custom URL opener, returns an error message to be reported when it fails.
+pub type HyperlinkOpenHandler = Box<dyn Fn(&str) -> Result<(), String>>;
+
+/// Called with the new content whenever a clipboard changes.
+pub type ClipboardChangeListener = Box<dyn Fn(ClipboardType, &str)>;
+
+/// What a left button press started, see `Screen::on_drag_begin_at_pos`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DragBegin {
+    /// Press is in the title bar area, the window gets dragged instead.
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    Window,
+    /// A hyperlink under the mouse was opened.
+    Hyperlink,
+    /// A cell selection was started at the position.
+    Selection,
+    /// Nothing started, the press is left to mouse reporting.
+    None,
+}
+
 pub struct Screen<'screen> {
     bindings: crate::bindings::KeyBindings,
     mouse_bindings: Vec<MouseBinding>,
@@ -80,11 +127,82 @@ pub struct Screen<'screen> {
     pub mouse: Mouse,
     pub touchpurpose: TouchPurpose,
     pub search_state: SearchState,
+    pub unicode_input: Option<String>,
+    /// Name being typed for the current tab.
+    pub tab_rename: Option<String>,
+    /// Multiline paste waiting for confirmation, along with whether it
+    /// was requested as bracketed.
+    pub pending_paste: Option<(String, bool)>,
+    /// Temporary overlay message, along with when it was shown and for
+    /// how long.
+    pub status_message: Option<(String, Instant, Duration)>,
     pub ime: Ime,
     pub renderer: Renderer,
     pub sugarloaf: Sugarloaf<'screen>,
     pub context_manager: context::ContextManager<EventProxy>,
     pub clipboard: Rc<RefCell<Clipboard>>,
+    hold_paste_trailing_newline: bool,
+    confirm_multiline_paste: bool,
+    paste_expand_tabs: bool,
+    right_click_extends_selection: bool,
+    motion_report_throttle: Duration,
+    selection_clipboard: SelectionClipboard,
+    allow_osc52_write: bool,
+    allow_osc52_read: bool,
+    mouse_wheel_in_alt: MouseWheelInAlt,
+    recording: Option<SharedRecording>,
+    renderer_performance: RendererPerformance,
+    auto_performance: bool,
+    /// Smallest grid the window can be resized to, in columns and lines.
+    min_grid: (usize, usize),
+    /// Scale factor reported by the OS for the window.
+    os_scale: f32,
+    /// Logical scale applied on top of `os_scale`, see [`Screen::set_render_scale`].
+    render_scale: f32,
+    startup_script: Option<String>,
+    startup_delay_ms: u64,
+    /// The startup script is waiting to be sent, see
+    /// `on_application_startup_complete`.
+    startup_pending: bool,
+    input_redraw: InputRedraw,
+    fps_counter: FpsCounter,
+    frame_counter: u64,
+    hyperlink_open_handler: Option<HyperlinkOpenHandler>,
+    clipboard_change_listener: Option<ClipboardChangeListener>,
+    /// Last seen content of the clipboard and of the selection clipboard.
+    clipboard_monitor: ClipboardMonitor,
+    /// Id of the clipboard read waiting to be pasted, see `request_paste`.
+    clipboard_request: Option<u64>,
+    clipboard_requests: u64,
+    home_path_modifier: ModifiersState,
+    macros: macros::Macros,
+    input_map: InputMap,
+    /// `InspectCell` overlay is shown.
+    inspect_cell: bool,
+}
+
+/// Performance picked by `renderer.auto-performance` for the power source.
+#[inline]
+fn auto_performance(on_ac: bool) -> RendererPerformance {
+    if on_ac {
+        RendererPerformance::High
+    } else {
+        RendererPerformance::Low
+    }
+}
+
+/// Render scale of `renderer.scale` and `Screen::set_render_scale`.
+#[inline]
+fn clamp_render_scale(scale: f32) -> f32 {
+    scale.clamp(0.25, 4.0)
+}
+
+#[inline]
+fn power_preference(performance: RendererPerformance) -> wgpu::PowerPreference {
+    match performance {
+        RendererPerformance::High => wgpu::PowerPreference::HighPerformance,
+        RendererPerformance::Low => wgpu::PowerPreference::LowPower,
+    }
 }
 
 pub struct ScreenWindowProperties {
@@ -114,8 +232,13 @@ impl Screen<'_> {
 
         let padding_y_top =
             padding_top_from_config(&config.navigation, config.padding_y[0], 1);
-        let padding_y_bottom =
-            padding_bottom_from_config(&config.navigation, config.padding_y[1], 1, false);
+        let padding_y_bottom = padding_bottom_from_config(
+            &config.navigation,
+            config.padding_y[1],
+            1,
+            false,
+            config.status_line == StatusLine::Bottom,
+        );
 
         let sugarloaf_layout = SugarloafLayout::new(
             size.width as f32,
@@ -138,10 +261,15 @@ impl Screen<'_> {
             },
         };
 
-        let power_preference = match config.renderer.performance {
-            RendererPerformance::High => wgpu::PowerPreference::HighPerformance,
-            RendererPerformance::Low => wgpu::PowerPreference::LowPower,
+        let performance = if config.renderer.auto_performance {
+            match crate::power::power_state() {
+                Some(state) => auto_performance(state.on_ac),
+                None => config.renderer.performance,
+            }
+        } else {
+            config.renderer.performance
         };
+        let power_preference = power_preference(performance);
 
         let backend = match config.renderer.backend {
             RendererBackend::Automatic => {
@@ -177,13 +305,26 @@ impl Screen<'_> {
             }
         };
 
+        let render_scale = clamp_render_scale(config.renderer.scale);
+        if render_scale != 1.0 {
+            sugarloaf.rescale(scale as f32 * render_scale);
+        }
+
         let renderer = Renderer::new(config, theme);
 
-        let bindings = crate::bindings::default_key_bindings(
+        let (bindings, mut binding_errors) = crate::bindings::default_key_bindings(
             config.bindings.keys.to_owned(),
             config.navigation.has_navigation_key_bindings(),
             config.keyboard,
         );
+        let home_path_modifier = crate::bindings::home_path_modifier(
+            &config.navigation.home_path_modifier,
+            &bindings,
+        )
+        .unwrap_or_else(|error| {
+            binding_errors.push(error);
+            ModifiersState::empty()
+        });
         let ime = Ime::new();
 
         let is_collapsed = config.navigation.is_collapsed_mode();
@@ -208,7 +349,20 @@ impl Screen<'_> {
             // does not make sense fetch for foreground process names
             should_update_titles: !(is_collapsed
                 && config.navigation.color_automation.is_empty()),
+            env_overrides: HashMap::new(),
+            show_control_characters: config.developer.show_control_characters,
+            max_line_length: config.max_line_length,
+            scrollback_lines: config.scrollback_lines,
+            alt_sends_escape: config.keyboard.alt_sends_escape,
+            alt_screen_scrollback: config.scroll.alt_screen_scrollback,
+            disable_alt_screen: config.scroll.disable_alt_screen,
+            preserve_scrollback_on_clear: config.scroll.preserve_scrollback_on_clear,
+            url_regex: config.url_regex.clone(),
+            parse_budget: config.parse_budget,
+            semantic_escape_chars: Some(config.selection.semantic_separators.clone()),
         };
+        let mut mouse = Mouse::new(config.scroll.multiplier, config.scroll.divider);
+        mouse.hide_when_typing = config.hide_cursor_when_typing;
         let context_manager = context::ContextManager::start(
             (&renderer.get_cursor_state(), config.blinking_cursor),
             event_proxy,
@@ -218,8 +372,15 @@ impl Screen<'_> {
             sugarloaf.layout(),
             sugarloaf_errors,
         )?;
+        context_manager.report_error_key_bindings(binding_errors);
 
         sugarloaf.set_background_color(renderer.dynamic_background.1);
+        if config.window.opacity < 1. && !sugarloaf.ctx.supports_transparency() {
+            log::warn!(
+                "window.opacity is set to {} but the surface does not support transparency",
+                config.window.opacity
+            );
+        }
         if let Some(image) = &config.window.background_image {
             sugarloaf.set_background_image(image);
         }
@@ -227,16 +388,52 @@ impl Screen<'_> {
 
         Ok(Screen {
             search_state: SearchState::default(),
+            unicode_input: None,
+            tab_rename: None,
+            pending_paste: None,
+            status_message: None,
             mouse_bindings: crate::bindings::default_mouse_bindings(),
             modifiers: Modifiers::default(),
             context_manager,
             ime,
             sugarloaf,
-            mouse: Mouse::new(config.scroll.multiplier, config.scroll.divider),
+            mouse,
             touchpurpose: TouchPurpose::default(),
             renderer,
             bindings,
             clipboard,
+            hold_paste_trailing_newline: config.hold_paste_trailing_newline,
+            allow_osc52_write: config.allow_osc52_write,
+            allow_osc52_read: config.allow_osc52_read,
+            confirm_multiline_paste: config.confirm_multiline_paste,
+            paste_expand_tabs: config.paste_expand_tabs,
+            right_click_extends_selection: config.right_click_extends_selection,
+            motion_report_throttle: Duration::from_millis(
+                config.motion_report_throttle_ms,
+            ),
+            selection_clipboard: config.selection_clipboard,
+            mouse_wheel_in_alt: config.scroll.mouse_wheel_in_alt,
+            recording: None,
+            renderer_performance: config.renderer.performance,
+            auto_performance: config.renderer.auto_performance,
+            min_grid: (config.window.min_columns, config.window.min_lines),
+            os_scale: scale as f32,
+            render_scale,
+            startup_script: config.startup_script.to_owned(),
+            startup_delay_ms: config.startup_delay_ms,
+            startup_pending: false,
+            input_redraw: InputRedraw::new(config.renderer.low_latency_input),
+            fps_counter: FpsCounter::new(config.developer.enable_fps_counter),
+            frame_counter: 0,
+            hyperlink_open_handler: config.hyperlink_opener.clone().map(hyperlink_opener),
+            clipboard_change_listener: None,
+            clipboard_monitor: ClipboardMonitor::default(),
+            clipboard_request: None,
+            clipboard_requests: 0,
+            home_path_modifier,
+            macros: macros::Macros::default(),
+            input_map: InputMap::from_config(&config.bindings.scancode_remaps),
+            inspect_cell: false,
         })
     }
 
@@ -260,17 +457,55 @@ impl Screen<'_> {
         self.search_state.history_index.is_some()
     }
 
+    #[inline]
+    pub fn unicode_input_active(&self) -> bool {
+        self.unicode_input.is_some()
+    }
+
+    /// Whether an input bar takes the bottom line: search, unicode input
+    /// or tab rename.
+    #[inline]
+    pub fn input_bar_active(&self) -> bool {
+        self.search_active() || self.unicode_input_active() || self.tab_rename.is_some()
+    }
+
     #[inline]
     pub fn reset_mouse(&mut self) {
         self.mouse.accumulated_scroll = crate::mouse::AccumulatedScroll::default();
     }
 
+    /// Show or hide the mouse pointer of the window. Hiding only happens
+    /// with `hide-mouse-cursor-when-typing`, mouse reporting keeps working
+    /// either way.
+    #[inline]
+    pub fn set_mouse_visible(&mut self, visible: bool) {
+        if self.mouse.set_visible(visible) {
+            self.context_manager.set_cursor_visible(visible);
+        }
+    }
+
     #[inline]
     pub fn mouse_position(&self, display_offset: usize) -> Pos {
+        // Positions are relative to the focused pane, clamped to its area.
+        let pos = self.grid_position();
+        let rect = self.focused_pane_rect();
+        let column =
+            (pos.col.0.saturating_sub(rect.column)).min(rect.columns.saturating_sub(1));
+        let line = (pos.row.0 as usize)
+            .saturating_sub(rect.line)
+            .min(rect.lines.saturating_sub(1));
+        // Truncated lines skip rows and may be scrolled on display.
+        let (line, column) = self.renderer.grid_cell(line, column, rect.columns);
+        Pos::new(Line(line as i32) - display_offset, Column(column))
+    }
+
+    /// Cell of the window grid under the mouse.
+    #[inline]
+    fn grid_position(&self) -> Pos {
         let layout = self.sugarloaf.layout();
         calculate_mouse_position(
             &self.mouse,
-            display_offset,
+            0,
             layout.dimensions.scale,
             (layout.columns, layout.lines),
             layout.margin.x,
@@ -310,7 +545,8 @@ impl Screen<'_> {
             &config.navigation,
             config.padding_y[1],
             num_tabs,
-            self.search_active(),
+            self.input_bar_active(),
+            config.status_line == StatusLine::Bottom,
         );
 
         self.sugarloaf.update_font(font_library);
@@ -323,19 +559,87 @@ impl Screen<'_> {
         );
 
         self.sugarloaf.layout_mut().update();
+        self.set_render_scale(config.renderer.scale);
         self.renderer = Renderer::new(config, current_theme);
 
-        for context in self.ctx().contexts() {
+        for context in self.ctx().all_contexts() {
             let mut terminal = context.terminal.lock();
             let cursor = self.renderer.get_cursor_state_from_ref().content;
             terminal.cursor_shape = cursor;
             terminal.default_cursor_shape = cursor;
             terminal.blinking_cursor = config.blinking_cursor;
+            terminal.show_control_characters = config.developer.show_control_characters;
+            terminal.max_line_length = config.max_line_length;
+            terminal.set_scrollback_lines(config.scrollback_lines);
+            terminal.set_alt_sends_escape(config.keyboard.alt_sends_escape);
+            terminal.set_alt_screen_scrollback(config.scroll.alt_screen_scrollback);
+            terminal.disable_alt_screen = config.scroll.disable_alt_screen;
+            terminal.preserve_scrollback_on_clear =
+                config.scroll.preserve_scrollback_on_clear;
+            context::set_url_regex(&mut terminal, config.url_regex.as_deref());
             drop(terminal);
+            context.messenger.send_parse_budget(config.parse_budget);
+        }
+        self.context_manager.config.show_control_characters =
+            config.developer.show_control_characters;
+        self.context_manager.config.max_line_length = config.max_line_length;
+        self.context_manager.config.scrollback_lines = config.scrollback_lines;
+        self.context_manager.config.alt_sends_escape = config.keyboard.alt_sends_escape;
+        self.context_manager.config.alt_screen_scrollback =
+            config.scroll.alt_screen_scrollback;
+        self.context_manager.config.disable_alt_screen = config.scroll.disable_alt_screen;
+        self.context_manager.config.preserve_scrollback_on_clear =
+            config.scroll.preserve_scrollback_on_clear;
+        self.set_word_separators(config.selection.semantic_separators.clone());
+        self.apply_input_map(InputMap::from_config(&config.bindings.scancode_remaps));
+        match config.hyperlink_opener.clone() {
+            Some(opener) => self.set_hyperlink_open_handler(hyperlink_opener(opener)),
+            None => self.hyperlink_open_handler = None,
         }
+        self.context_manager.config.url_regex = config.url_regex.clone();
+        self.context_manager.config.parse_budget = config.parse_budget;
+        // Only new contexts are spawned with the updated TERM.
+        #[cfg(unix)]
+        self.context_manager.set_env(
+            "TERM",
+            &rio_backend::config::resolve_term(
+                &config.term,
+                config.keyboard.use_kitty_keyboard_protocol,
+                teletypewriter::terminfo_exists,
+            ),
+        );
 
         self.mouse
             .set_multiplier_and_divider(config.scroll.multiplier, config.scroll.divider);
+        self.hold_paste_trailing_newline = config.hold_paste_trailing_newline;
+        self.allow_osc52_write = config.allow_osc52_write;
+        self.allow_osc52_read = config.allow_osc52_read;
+        self.confirm_multiline_paste = config.confirm_multiline_paste;
+        self.paste_expand_tabs = config.paste_expand_tabs;
+        self.right_click_extends_selection = config.right_click_extends_selection;
+        self.mouse.hide_when_typing = config.hide_cursor_when_typing;
+        self.motion_report_throttle =
+            Duration::from_millis(config.motion_report_throttle_ms);
+        if !config.hide_cursor_when_typing {
+            self.set_mouse_visible(true);
+        }
+        self.selection_clipboard = config.selection_clipboard;
+        self.mouse_wheel_in_alt = config.scroll.mouse_wheel_in_alt;
+        self.home_path_modifier = crate::bindings::home_path_modifier(
+            &config.navigation.home_path_modifier,
+            &self.bindings,
+        )
+        .unwrap_or_else(|error| {
+            self.context_manager.report_error_key_bindings(vec![error]);
+            ModifiersState::empty()
+        });
+        self.renderer_performance = config.renderer.performance;
+        self.auto_performance = config.renderer.auto_performance;
+        self.input_redraw.enabled = config.renderer.low_latency_input;
+        self.fps_counter.enabled = config.developer.enable_fps_counter;
+        self.min_grid = (config.window.min_columns, config.window.min_lines);
+        self.startup_script = config.startup_script.to_owned();
+        self.startup_delay_ms = config.startup_delay_ms;
 
         self.sugarloaf
             .set_background_color(self.renderer.dynamic_background.1);
@@ -347,6 +651,19 @@ impl Screen<'_> {
         self.resize_all_contexts();
     }
 
+    /// Minimum inner window size for the configured grid with the
+    /// current font size and scale.
+    #[inline]
+    pub fn min_window_size(&self) -> Option<rio_window::dpi::PhysicalSize<u32>> {
+        let (columns, lines) = self.min_grid;
+        crate::renderer::utils::minimum_window_size(
+            &self.sugarloaf.layout(),
+            columns,
+            lines,
+        )
+        .map(|(width, height)| rio_window::dpi::PhysicalSize::new(width, height))
+    }
+
     #[inline]
     pub fn change_font_size(&mut self, action: FontSizeAction) {
         let action: u8 = match action {
@@ -359,6 +676,7 @@ impl Screen<'_> {
 
         self.demand_render();
         self.resize_all_contexts();
+        self.context_manager.update_font_size(action);
     }
 
     #[inline]
@@ -377,7 +695,8 @@ impl Screen<'_> {
         new_scale: f32,
         new_size: rio_window::dpi::PhysicalSize<u32>,
     ) -> &mut Self {
-        self.sugarloaf.rescale(new_scale);
+        self.os_scale = new_scale;
+        self.sugarloaf.rescale(new_scale * self.render_scale);
         self.sugarloaf.resize(new_size.width, new_size.height);
         // TODO: Fix this double render hack on scale update
         self.render();
@@ -388,6 +707,22 @@ impl Screen<'_> {
         self
     }
 
+    /// Scale the content independently of the OS scale factor, values
+    /// below 1.0 trade quality for performance and values above 1.0 the
+    /// opposite. Clamped to `[0.25, 4.0]`.
+    pub fn set_render_scale(&mut self, scale: f32) {
+        let scale = clamp_render_scale(scale);
+        if scale == self.render_scale {
+            return;
+        }
+
+        self.render_scale = scale;
+        self.sugarloaf.rescale(self.os_scale * scale);
+        self.resize_all_contexts();
+        self.demand_render();
+        self.context_manager.update_render_scale();
+    }
+
     #[inline]
     pub fn resize_all_contexts(&mut self) {
         // whenever a resize update happens: it will stored in
@@ -395,21 +730,89 @@ impl Screen<'_> {
         // the wakeup from pty it will also trigger a sugarloaf.render()
         // and then eventually a render with the new layout computation.
         let layout = self.sugarloaf.layout();
-        for context in self.ctx().contexts() {
-            let mut terminal = context.terminal.lock();
-            terminal.resize::<SugarloafLayout>(layout);
-            drop(terminal);
-            let winsize = crate::renderer::utils::terminal_dimensions(&layout);
-            let _ = context.messenger.send_resize(winsize);
+        self.context_manager.resize_all(&layout);
+    }
+
+    /// Split the focused pane, the new pane gets the focus.
+    fn split(&mut self, direction: SplitDirection) {
+        let layout = self.sugarloaf.layout();
+        let cursor_state = (
+            &self.renderer.get_cursor_state_from_ref(),
+            self.renderer.config_has_blinking_enabled,
+        );
+        self.clear_selection();
+        self.context_manager.split(direction, layout, cursor_state);
+        self.resize_all_contexts();
+        self.demand_render();
+    }
+
+    /// Grow the focused pane by `delta` of its split, shrink it if negative.
+    fn resize_split(&mut self, delta: f32) {
+        let layout = self.sugarloaf.layout();
+        if self.context_manager.resize_split(delta, &layout) {
+            self.clear_selection();
+            self.resize_all_contexts();
+            self.demand_render();
+        }
+    }
+
+    /// Close the current tab, returns `true` if it was the last one.
+    fn close_current_tab(&mut self) -> bool {
+        self.clear_selection();
+        self.context_manager.close_current_context();
+
+        self.cancel_search();
+        if self.ctx().len() <= 1 {
+            return true;
+        }
+
+        let num_tabs = self.ctx().len().wrapping_sub(1);
+        self.resize_top_or_bottom_line(num_tabs);
+        self.demand_render();
+        false
+    }
+
+    /// Area of the focused pane in the window grid.
+    #[inline]
+    fn focused_pane_rect(&self) -> PaneRect {
+        let layout = self.sugarloaf.layout();
+        let area = PaneRect::new(layout.columns, layout.lines);
+        let current = self.ctx().current();
+        current
+            .pane_rects(area)
+            .into_iter()
+            .find(|(route_id, _)| *route_id == current.route_id)
+            .map_or(area, |(_, rect)| rect)
+    }
+
+    /// Focus the pane under the mouse, returns `true` if the focus moved.
+    pub fn select_split_under_mouse(&mut self) -> bool {
+        let current = self.ctx().current();
+        if current.split.is_none() {
+            return false;
+        }
+
+        let layout = self.sugarloaf.layout();
+        let pos = self.grid_position();
+        let route_id = current
+            .pane_rects(PaneRect::new(layout.columns, layout.lines))
+            .into_iter()
+            .find(|(_, rect)| rect.contains(pos.col.0, pos.row.0 as usize))
+            .map(|(route_id, _)| route_id);
+
+        match route_id {
+            Some(route_id) if route_id != current.route_id => {
+                self.clear_selection();
+                self.context_manager.select_split(route_id)
+            }
+            _ => false,
         }
     }
 
     #[inline]
     pub fn scroll_bottom_when_cursor_not_visible(&mut self) {
         let mut terminal = self.ctx_mut().current_mut().terminal.lock();
-        if terminal.display_offset() != 0 {
-            terminal.scroll_display(Scroll::Bottom);
-        }
+        scroll_bottom(&mut terminal);
         drop(terminal);
     }
 
@@ -444,22 +847,57 @@ impl Screen<'_> {
         mode
     }
 
-    #[inline]
-    pub fn process_key_event(&mut self, key: &rio_window::event::KeyEvent) {
+    /// Handle a key press or release, window events are converted with
+    /// `KeyEvent::from_window_event` before getting here.
+    pub fn input_key_event(&mut self, mut key: KeyEvent) {
+        key.scancode = self.input_map.remap(key.scancode);
+        let key = &key;
         // 1. In case there is a key released event and Rio is not using kitty keyboard protocol
         // then should return drop the key processing
         // 2. In case IME has preedit then also should drop the key processing
         let is_kitty_keyboard_enabled = self.renderer.is_kitty_keyboard_enabled;
-        if !is_kitty_keyboard_enabled && key.state == ElementState::Released
+        if !is_kitty_keyboard_enabled && key.state == KeyState::Released
             || self.ime.preedit().is_some()
         {
             return;
         }
 
+        if key.state == KeyState::Pressed {
+            self.input_redraw.key_pressed();
+        }
+
+        // Unicode input captures every key until it gets confirmed or cancelled
+        if self.unicode_input_active() {
+            if key.state == KeyState::Pressed {
+                self.unicode_input_key(key);
+            }
+            return;
+        }
+
+        // Same for the tab name
+        if self.tab_rename.is_some() {
+            if key.state == KeyState::Pressed {
+                self.tab_rename_key(key);
+            }
+            return;
+        }
+
+        // Paste confirmation only accepts `Enter` or `Escape`
+        if self.pending_paste.is_some() {
+            if key.state == KeyState::Pressed {
+                match key.logical_key.as_ref() {
+                    Key::Named(NamedKey::Enter) => self.confirm_paste(),
+                    Key::Named(NamedKey::Escape) => self.cancel_paste(),
+                    _ => {}
+                }
+            }
+            return;
+        }
+
         let mode = self.get_mode();
-        let mods = self.modifiers.state();
+        let mods = key.modifiers;
 
-        if is_kitty_keyboard_enabled && key.state == ElementState::Released {
+        if is_kitty_keyboard_enabled && key.state == KeyState::Released {
             if !mode.contains(Mode::KEYBOARD_REPORT_EVENT_TYPES)
                 || mode.contains(Mode::VI)
                 || self.search_active()
@@ -494,6 +932,7 @@ impl Screen<'_> {
             };
 
             self.sugarloaf.mark_dirty();
+            self.macros.record(&bytes);
             self.ctx_mut().current_mut().messenger.send_write(bytes);
 
             return;
@@ -512,6 +951,7 @@ impl Screen<'_> {
             for character in text.chars() {
                 self.search_input(character);
             }
+            self.set_mouse_visible(false);
 
             self.demand_render();
             return;
@@ -562,9 +1002,16 @@ impl Screen<'_> {
 
         if !bytes.is_empty() {
             self.sugarloaf.mark_dirty();
-            self.scroll_bottom_when_cursor_not_visible();
-            self.clear_selection();
 
+            let mut terminal = self.ctx_mut().current_mut().terminal.lock();
+            let typed = follow_typed_key(&mut terminal, key);
+            drop(terminal);
+            if typed {
+                self.renderer.set_selection(None);
+                self.set_mouse_visible(false);
+            }
+
+            self.macros.record(&bytes);
             self.ctx_mut().current_mut().messenger.send_bytes(bytes);
         }
     }
@@ -584,18 +1031,28 @@ impl Screen<'_> {
                 binding.mods |= ModifiersState::SHIFT;
             }
 
-            if binding.is_triggered_by(binding_mode.to_owned(), mods, &button)
-                && binding.action == Act::PasteSelection
-            {
-                let content = self.clipboard.borrow_mut().get(ClipboardType::Selection);
-                self.paste(&content, true);
+            if !binding.is_triggered_by(binding_mode.to_owned(), mods, &button) {
+                continue;
+            }
+
+            match binding.action {
+                Act::PasteSelection => self.request_paste(ClipboardType::Selection),
+                Act::Mouse(MouseAction::ExpandSelection)
+                    if self.right_click_extends_selection =>
+                {
+                    let display_offset = self.display_offset();
+                    let pos = self.mouse_position(display_offset);
+                    self.highlight_selection_on_secondary_click(pos);
+                    self.demand_render();
+                }
+                _ => (),
             }
         }
     }
 
     pub fn process_key_bindings(
         &mut self,
-        key: &rio_window::event::KeyEvent,
+        key: &KeyEvent,
         mode: &Mode,
         mods: ModifiersState,
     ) -> bool {
@@ -622,16 +1079,19 @@ impl Screen<'_> {
                 if (mods.shift_key() || mods.alt_key())
                     || mods.alt_key() && (cfg!(windows) && mods.control_key())
                 {
-                    key.key_without_modifiers()
+                    key.key_without_modifiers.clone()
                 } else {
-                    Key::Character(ch.to_lowercase().into())
+                    Key::Character(ch.to_lowercase())
                 }
             } else {
                 key.logical_key.clone()
             };
 
             let key_match = match (&binding.trigger, logical_key) {
-                (BindingKey::Scancode(_), _) => BindingKey::Scancode(key.physical_key),
+                (BindingKey::Scancode(_), _) => match key.scancode {
+                    Some(scancode) => BindingKey::Scancode(scancode),
+                    None => continue,
+                },
                 (_, code) => BindingKey::Keycode {
                     key: code,
                     location: key.location,
@@ -650,22 +1110,22 @@ impl Screen<'_> {
                         terminal.selection.take();
                         terminal.scroll_display(Scroll::Bottom);
                         drop(terminal);
+                        self.macros.record(s.as_bytes());
                         current_context
                             .messenger
                             .send_bytes(s.to_owned().into_bytes());
                     }
                     Act::Paste => {
-                        let content =
-                            self.clipboard.borrow_mut().get(ClipboardType::Clipboard);
-                        self.paste(&content, true);
+                        self.request_paste(ClipboardType::Clipboard);
                     }
                     Act::ClearSelection => {
                         self.clear_selection();
                     }
+                    Act::SelectLastOutput => {
+                        self.select_last_output();
+                    }
                     Act::PasteSelection => {
-                        let content =
-                            self.clipboard.borrow_mut().get(ClipboardType::Selection);
-                        self.paste(&content, true);
+                        self.request_paste(ClipboardType::Selection);
                     }
                     Act::Copy => {
                         self.copy_selection(ClipboardType::Clipboard);
@@ -680,6 +1140,11 @@ impl Screen<'_> {
                         self.resize_top_or_bottom_line(self.ctx().len());
                         self.demand_render();
                     }
+                    Act::FindSelection => {
+                        self.find_selection();
+                        self.resize_top_or_bottom_line(self.ctx().len());
+                        self.demand_render();
+                    }
                     Act::Search(SearchAction::SearchConfirm) => {
                         self.confirm_search();
                         self.resize_top_or_bottom_line(self.ctx().len());
@@ -698,15 +1163,10 @@ impl Screen<'_> {
                         self.demand_render();
                     }
                     Act::Search(SearchAction::SearchFocusNext) => {
-                        self.advance_search_origin(self.search_state.direction);
-                        self.resize_top_or_bottom_line(self.ctx().len());
-                        self.demand_render();
+                        self.search_next(self.search_state.direction);
                     }
                     Act::Search(SearchAction::SearchFocusPrevious) => {
-                        let direction = self.search_state.direction.opposite();
-                        self.advance_search_origin(direction);
-                        self.resize_top_or_bottom_line(self.ctx().len());
-                        self.demand_render();
+                        self.search_next(self.search_state.direction.opposite());
                     }
                     Act::Search(SearchAction::SearchDeleteWord) => {
                         self.search_pop_word();
@@ -720,6 +1180,22 @@ impl Screen<'_> {
                         self.search_history_next();
                         self.demand_render();
                     }
+                    Act::UnicodeInput => {
+                        if self.search_active() {
+                            self.cancel_search();
+                        }
+                        self.unicode_input = Some(String::new());
+                        self.resize_top_or_bottom_line(self.ctx().len());
+                        self.demand_render();
+                    }
+                    Act::TabRename => {
+                        if self.search_active() {
+                            self.cancel_search();
+                        }
+                        self.tab_rename = Some(self.ctx().current_title());
+                        self.resize_top_or_bottom_line(self.ctx().len());
+                        self.demand_render();
+                    }
                     Act::ToggleViMode => {
                         let mut terminal =
                             self.context_manager.current_mut().terminal.lock();
@@ -754,6 +1230,12 @@ impl Screen<'_> {
                         terminal.scroll_display(Scroll::Delta(scroll_lines));
                         drop(terminal);
                     }
+                    Act::Vi(ViAction::SearchNext) => {
+                        self.vi_search_next(self.search_state.direction);
+                    }
+                    Act::Vi(ViAction::SearchPrevious) => {
+                        self.vi_search_next(self.search_state.direction.opposite());
+                    }
                     Act::Vi(ViAction::ToggleNormalSelection) => {
                         self.toggle_selection(SelectionType::Simple, Side::Left);
                         self.demand_render();
@@ -780,33 +1262,66 @@ impl Screen<'_> {
                         let redirect = true;
 
                         let layout = self.sugarloaf.layout();
-                        self.context_manager.add_context(
-                            redirect,
-                            layout,
-                            (
-                                &self.renderer.get_cursor_state_from_ref(),
-                                self.renderer.config_has_blinking_enabled,
-                            ),
+                        let cursor_state = (
+                            &self.renderer.get_cursor_state_from_ref(),
+                            self.renderer.config_has_blinking_enabled,
                         );
 
+                        // Holding the home path modifier skips the inherited path
+                        if !self.home_path_modifier.is_empty()
+                            && self.modifiers.state().contains(self.home_path_modifier)
+                        {
+                            let home_dir = dirs::home_dir()
+                                .map(|path| path.to_string_lossy().to_string());
+                            self.context_manager.add_context_in(
+                                redirect,
+                                layout,
+                                cursor_state,
+                                home_dir,
+                            );
+                        } else {
+                            self.context_manager.add_context(
+                                redirect,
+                                layout,
+                                cursor_state,
+                            );
+                        }
+
                         let num_tabs = self.ctx().len();
                         self.cancel_search();
                         self.resize_top_or_bottom_line(num_tabs);
                         self.demand_render();
                     }
                     Act::TabCloseCurrent => {
+                        let was_last_tab = self.close_current_tab();
+                        if was_last_tab {
+                            return true;
+                        }
+                    }
+                    Act::SplitVertically => self.split(SplitDirection::Right),
+                    Act::SplitHorizontally => self.split(SplitDirection::Down),
+                    Act::ClosePane => {
                         self.clear_selection();
-                        self.context_manager.close_current_context();
-
-                        self.cancel_search();
-                        if self.ctx().len() <= 1 {
+                        if self.context_manager.close_current_pane() {
+                            self.cancel_search();
+                            self.resize_all_contexts();
+                            self.demand_render();
+                        } else if self.close_current_tab() {
                             return true;
                         }
-
-                        let num_tabs = self.ctx().len().wrapping_sub(1);
-                        self.resize_top_or_bottom_line(num_tabs);
+                    }
+                    Act::PaneFocusNext => {
+                        self.clear_selection();
+                        self.context_manager.select_next_split();
+                        self.demand_render();
+                    }
+                    Act::PaneFocusPrev => {
+                        self.clear_selection();
+                        self.context_manager.select_prev_split();
                         self.demand_render();
                     }
+                    Act::PaneGrow => self.resize_split(SPLIT_RATIO_STEP),
+                    Act::PaneShrink => self.resize_split(-SPLIT_RATIO_STEP),
                     Act::TabCloseUnfocused => {
                         self.clear_selection();
                         self.cancel_search();
@@ -890,6 +1405,85 @@ impl Screen<'_> {
                         drop(terminal);
                         self.demand_render();
                     }
+                    Act::ToggleBracketedPasteMode => {
+                        let mut terminal =
+                            self.context_manager.current_mut().terminal.lock();
+                        terminal.toggle_bracketed_paste();
+                        drop(terminal);
+                    }
+                    Act::CopyScreenImage => {
+                        self.copy_screen_image();
+                    }
+                    Act::StartRecordMacro(slot) => {
+                        let slot = *slot;
+                        if self.macros.start(slot) {
+                            self.status_message(&format!("Recording macro {slot}"), 1500);
+                        }
+                    }
+                    Act::StopRecordMacro => {
+                        if let Some(slot) = self.macros.stop() {
+                            self.status_message(&format!("Macro {slot} saved"), 1500);
+                        }
+                    }
+                    Act::PlayMacro(slot) => {
+                        self.play_macro(*slot);
+                    }
+                    Act::AddWordSeparator(ch) => {
+                        self.add_word_separator(*ch);
+                    }
+                    Act::RemoveWordSeparator(ch) => {
+                        self.remove_word_separator(*ch);
+                    }
+                    Act::LogOutput => {
+                        self.log_output();
+                    }
+                    Act::StartRecording => {
+                        self.start_recording();
+                        self.status_message("Recording", 1500);
+                    }
+                    Act::StopRecording => {
+                        self.save_recording();
+                    }
+                    Act::SessionSave => match self.save_session() {
+                        Ok(()) => self.status_message("Session saved", 1500),
+                        Err(error) => {
+                            log::error!("unable to save the session: {error}");
+                            self.status_message("Unable to save the session", 3000);
+                        }
+                    },
+                    Act::SessionRestore => match self.restore_session() {
+                        Ok(()) => self.status_message("Session restored", 1500),
+                        Err(error) => {
+                            log::error!("unable to restore the session: {error}");
+                            self.status_message("Unable to restore the session", 3000);
+                        }
+                    },
+                    Act::InspectCell => {
+                        self.inspect_cell = !self.inspect_cell;
+                        self.demand_render();
+                    }
+                    Act::OpenHyperlink => {
+                        self.open_hyperlink_at_cursor();
+                    }
+                    Act::ToggleLineWrap => {
+                        let current = self.context_manager.current_mut();
+                        current.wrap_lines = !current.wrap_lines;
+                        current.line_scroll = 0;
+                        self.demand_render();
+                    }
+                    Act::ScrollToCursor => {
+                        let mut terminal =
+                            self.context_manager.current_mut().terminal.lock();
+                        terminal.scroll_to_cursor();
+                        drop(terminal);
+                        self.demand_render();
+                    }
+                    Act::ScrollToPrevPrompt => {
+                        self.search_next_prompt();
+                    }
+                    Act::ScrollToNextPrompt => {
+                        self.search_prev_prompt();
+                    }
                     Act::ScrollToBottom => {
                         let mut terminal =
                             self.context_manager.current_mut().terminal.lock();
@@ -914,8 +1508,16 @@ impl Screen<'_> {
                     Act::ClearHistory => {
                         let mut terminal =
                             self.context_manager.current_mut().terminal.lock();
-                        terminal.clear_saved_history();
+                        terminal.clear_history();
+                        drop(terminal);
+                        self.demand_render();
+                    }
+                    Act::ClearScreen => {
+                        let mut terminal =
+                            self.context_manager.current_mut().terminal.lock();
+                        terminal.clear_viewport();
                         drop(terminal);
+                        self.clear_selection();
                         self.demand_render();
                     }
                     Act::ToggleFullscreen => self.context_manager.toggle_full_screen(),
@@ -939,6 +1541,22 @@ impl Screen<'_> {
                         self.context_manager.select_last_tab();
                         self.demand_render();
                     }
+                    Act::SortTabsByTitle => {
+                        self.context_manager.sort_contexts_by_title();
+                        self.demand_render();
+                    }
+                    Act::TabMoveLeft => {
+                        let index = self.ctx().current_index();
+                        if let Some(left) = index.checked_sub(1) {
+                            self.context_manager.swap_contexts(index, left);
+                        }
+                        self.demand_render();
+                    }
+                    Act::TabMoveRight => {
+                        let index = self.ctx().current_index();
+                        self.context_manager.swap_contexts(index, index + 1);
+                        self.demand_render();
+                    }
                     Act::SelectNextTab => {
                         self.cancel_search();
                         self.clear_selection();
@@ -972,7 +1590,8 @@ impl Screen<'_> {
             &self.renderer.navigation.navigation,
             self.renderer.navigation.padding_y[1],
             num_tabs,
-            self.search_active(),
+            self.input_bar_active(),
+            self.renderer.has_status_line(),
         );
 
         if previous_margin.top_y != padding_y_top
@@ -991,6 +1610,103 @@ impl Screen<'_> {
         }
     }
 
+    #[inline]
+    fn unicode_input_key(&mut self, key: &KeyEvent) {
+        let buffer = match &mut self.unicode_input {
+            Some(buffer) => buffer,
+            None => return,
+        };
+
+        match key.logical_key.as_ref() {
+            Key::Named(NamedKey::Enter) => {
+                self.commit_unicode_input();
+                return;
+            }
+            Key::Named(NamedKey::Escape) => {
+                self.exit_unicode_input();
+                return;
+            }
+            Key::Named(NamedKey::Backspace) => {
+                buffer.pop();
+            }
+            _ => {
+                for c in key.text_with_all_modifiers().unwrap_or_default().chars() {
+                    if c.is_ascii_hexdigit() && buffer.len() < MAX_UNICODE_INPUT_SIZE {
+                        buffer.push(c.to_ascii_uppercase());
+                    }
+                }
+            }
+        }
+
+        self.demand_render();
+    }
+
+    fn commit_unicode_input(&mut self) {
+        let buffer = self.unicode_input.as_deref().unwrap_or_default();
+        if buffer.is_empty() {
+            self.exit_unicode_input();
+            return;
+        }
+
+        // An invalid codepoint keeps the bar open, it shows why it was refused.
+        if let Some(c) = char_from_codepoint(buffer) {
+            self.exit_unicode_input();
+            // Same path used by IME commits of a single char.
+            self.paste(&c.to_string(), false);
+        }
+    }
+
+    fn exit_unicode_input(&mut self) {
+        self.unicode_input = None;
+        self.resize_top_or_bottom_line(self.ctx().len());
+        self.demand_render();
+    }
+
+    #[inline]
+    fn tab_rename_key(&mut self, key: &KeyEvent) {
+        let buffer = match &mut self.tab_rename {
+            Some(buffer) => buffer,
+            None => return,
+        };
+
+        match key.logical_key.as_ref() {
+            Key::Named(NamedKey::Enter) => {
+                // An empty name gives the tab back to the application title.
+                let name = self.tab_rename.take().unwrap_or_default();
+                let name = name.trim();
+                if name.is_empty() {
+                    self.context_manager.clear_current_title();
+                } else {
+                    self.context_manager.set_current_title(name);
+                }
+                self.exit_tab_rename();
+                return;
+            }
+            Key::Named(NamedKey::Escape) => {
+                self.exit_tab_rename();
+                return;
+            }
+            Key::Named(NamedKey::Backspace) => {
+                buffer.pop();
+            }
+            _ => {
+                for c in key.text_with_all_modifiers().unwrap_or_default().chars() {
+                    if !c.is_control() && buffer.chars().count() < MAX_TAB_RENAME_SIZE {
+                        buffer.push(c);
+                    }
+                }
+            }
+        }
+
+        self.demand_render();
+    }
+
+    fn exit_tab_rename(&mut self) {
+        self.tab_rename = None;
+        self.resize_top_or_bottom_line(self.ctx().len());
+        self.demand_render();
+    }
+
     #[inline]
     fn search_pop_word(&mut self) {
         if let Some(regex) = self.search_state.regex_mut() {
@@ -1081,14 +1797,19 @@ impl Screen<'_> {
     }
 
     /// Whether we should send `ESC` due to `Alt` being pressed.
-    fn alt_send_esc(&mut self, key: &rio_window::event::KeyEvent, text: &str) -> bool {
+    fn alt_send_esc(&mut self, key: &KeyEvent, text: &str) -> bool {
+        let meta_sends_escape = self
+            .get_mode()
+            .intersects(Mode::META_SENDS_ESCAPE | Mode::ALT_SENDS_ESCAPE);
+
         #[cfg(not(target_os = "macos"))]
-        let alt_send_esc = self.modifiers.state().alt_key();
+        let alt_send_esc = meta_sends_escape && key.modifiers.alt_key();
 
         #[cfg(target_os = "macos")]
         let alt_send_esc = {
             let option_as_alt = &self.renderer.option_as_alt;
-            self.modifiers.state().alt_key()
+            meta_sends_escape
+                && key.modifiers.alt_key()
                 && (option_as_alt == "both"
                     || (option_as_alt == "left"
                         && self.modifiers.lalt_state() == ModifiersKeyState::Pressed)
@@ -1102,36 +1823,200 @@ impl Screen<'_> {
                     alt_send_esc
                 } else {
                     // Treat `Alt` as modifier for named keys without text, like ArrowUp.
-                    self.modifiers.state().alt_key()
+                    key.modifiers.alt_key()
                 }
             }
             _ => alt_send_esc && text.chars().count() == 1,
         }
     }
 
-    pub fn copy_selection(&mut self, ty: ClipboardType) {
-        let terminal = self.ctx().current().terminal.lock();
-        let text = match terminal.selection_to_string().filter(|s| !s.is_empty()) {
-            Some(text) => text,
-            None => return,
-        };
+    /// Jump back to the previous command, using the prompts marked by
+    /// OSC 133. Returns `false` if there are no more prompts.
+    pub fn search_next_prompt(&mut self) -> bool {
+        let mut terminal = self.context_manager.current_mut().terminal.lock();
+        let found = terminal.scroll_to_next_prompt();
         drop(terminal);
-
-        if ty == ClipboardType::Selection {
-            self.clipboard
-                .borrow_mut()
-                .set(ClipboardType::Clipboard, text.clone());
+        if found {
+            self.demand_render();
         }
-        self.clipboard.borrow_mut().set(ty, text);
+        found
     }
 
-    #[inline]
-    pub fn clear_selection(&mut self) {
-        // Clear the selection on the terminal.
-        let mut terminal = self.ctx().current().terminal.lock();
-        terminal.selection.take();
+    /// Jump forward towards the latest command, see [`Screen::search_next_prompt`].
+    pub fn search_prev_prompt(&mut self) -> bool {
+        let mut terminal = self.context_manager.current_mut().terminal.lock();
+        let found = terminal.scroll_to_prev_prompt();
         drop(terminal);
-        self.renderer.set_selection(None);
+        if found {
+            self.demand_render();
+        }
+        found
+    }
+
+    /// Treat `ch` as a word boundary for semantic selection, in every context
+    /// of this session including the ones created afterwards.
+    pub fn add_word_separator(&mut self, ch: char) {
+        let mut separators = self.word_separators();
+        if !separators.contains(ch) {
+            separators.push(ch);
+            self.set_word_separators(separators);
+        }
+    }
+
+    /// Stop treating `ch` as a word boundary, see [`Screen::add_word_separator`].
+    pub fn remove_word_separator(&mut self, ch: char) {
+        let mut separators = self.word_separators();
+        if separators.contains(ch) {
+            separators.retain(|c| c != ch);
+            self.set_word_separators(separators);
+        }
+    }
+
+    fn word_separators(&self) -> String {
+        self.context_manager
+            .config
+            .semantic_escape_chars
+            .clone()
+            .unwrap_or_else(|| {
+                String::from(rio_backend::crosswords::DEFAULT_SEMANTIC_ESCAPE_CHARS)
+            })
+    }
+
+    fn set_word_separators(&mut self, separators: String) {
+        for context in self.ctx().all_contexts() {
+            context
+                .terminal
+                .lock()
+                .set_semantic_escape_chars(&separators);
+        }
+        self.context_manager.config.semantic_escape_chars = Some(separators);
+    }
+
+    /// Snapshot of the contexts for tab strips drawn outside of the renderer.
+    #[inline]
+    pub fn contexts_info(&self) -> Vec<crate::context::ContextInfo> {
+        self.context_manager.contexts_info()
+    }
+
+    /// Text inside the rectangle between `top` and `bottom`, one line per row.
+    #[inline]
+    pub fn get_text_under_rect(&self, top: Pos, bottom: Pos) -> String {
+        let terminal = self.ctx().current().terminal.lock();
+        let text = terminal.block_to_string(top, bottom);
+        drop(terminal);
+        text
+    }
+
+    pub fn copy_selection(&mut self, ty: ClipboardType) {
+        let terminal = self.ctx().current().terminal.lock();
+        let block = terminal
+            .selection
+            .as_ref()
+            .filter(|selection| selection.ty == SelectionType::Block)
+            .and_then(|selection| selection.to_range(&terminal));
+        let text = match block {
+            Some(range) => {
+                drop(terminal);
+                self.get_text_under_rect(range.start, range.end)
+            }
+            None => {
+                let text = terminal.selection_to_string().unwrap_or_default();
+                drop(terminal);
+                text
+            }
+        };
+        if text.is_empty() {
+            return;
+        }
+
+        let mut clipboard = self.clipboard.borrow_mut();
+        // Without a primary selection the regular clipboard takes its place.
+        if ty == ClipboardType::Selection
+            && (self.selection_clipboard == SelectionClipboard::Both
+                || !clipboard.has_selection())
+        {
+            clipboard.set(ClipboardType::Clipboard, text.clone());
+        }
+        clipboard.set(ty, text);
+    }
+
+    /// Render the current frame and save it as a PNG in the temporary
+    /// directory. The clipboard backend only deals with text, so the path
+    /// of the image is what ends up in the clipboard.
+    pub fn copy_screen_image(&mut self) {
+        self.sugarloaf.capture_next_frame();
+        self.render();
+        let Some(capture) = self.sugarloaf.take_capture() else {
+            log::warn!("unable to capture the screen");
+            return;
+        };
+
+        let path = temporary_file_path("rio-screen", "png");
+        let file = match create_private_file(&path) {
+            Ok(file) => file,
+            Err(error) => {
+                log::error!("unable to create {}: {error}", path.display());
+                return;
+            }
+        };
+
+        use image_rs::ImageEncoder;
+        let mut writer = std::io::BufWriter::new(file);
+        let result = image_rs::codecs::png::PngEncoder::new(&mut writer)
+            .write_image(
+                &capture.rgba,
+                capture.width,
+                capture.height,
+                image_rs::ExtendedColorType::Rgba8,
+            )
+            .map_err(|e| e.to_string())
+            .and_then(|_| {
+                writer
+                    .into_inner()
+                    .map(drop)
+                    .map_err(|e| e.error().to_string())
+            });
+
+        match result {
+            Ok(()) => self
+                .clipboard
+                .borrow_mut()
+                .set(ClipboardType::Clipboard, path.display().to_string()),
+            Err(error) => {
+                log::error!("unable to save {}: {error}", path.display());
+                let _ = std::fs::remove_file(&path);
+            }
+        }
+    }
+
+    #[inline]
+    pub fn clear_selection(&mut self) {
+        // Clear the selection on the terminal.
+        let mut terminal = self.ctx().current().terminal.lock();
+        terminal.selection.take();
+        drop(terminal);
+        self.renderer.set_selection(None);
+    }
+
+    /// Select from `start` to `end` in the current context, like a mouse
+    /// selection. Positions are clamped to the grid and may be reversed.
+    pub fn set_selection_range(&mut self, start: Pos, end: Pos, ty: SelectionType) {
+        let mut terminal = self.context_manager.current().terminal.lock();
+        let range = terminal.select_range(start, end, ty);
+        drop(terminal);
+        self.renderer.set_selection(range);
+        self.demand_render();
+    }
+
+    /// Select the rows printed by the last command, if the shell marks
+    /// its prompts with OSC 133.
+    fn select_last_output(&mut self) {
+        let terminal = self.context_manager.current().terminal.lock();
+        let range = terminal.last_output_range();
+        drop(terminal);
+        if let Some((start, end)) = range {
+            self.set_selection_range(start, end, SelectionType::Lines);
+        }
     }
 
     #[inline]
@@ -1144,6 +2029,16 @@ impl Screen<'_> {
         drop(terminal);
     }
 
+    /// Extend the selection up to `pos`, or start a new one there if
+    /// there is no selection.
+    pub fn highlight_selection_on_secondary_click(&mut self, pos: Pos) {
+        let side = self.mouse.square_side;
+        let mut terminal = self.context_manager.current().terminal.lock();
+        let range = terminal.extend_selection(pos, side);
+        drop(terminal);
+        self.renderer.set_selection(range);
+    }
+
     #[inline]
     fn toggle_selection(&mut self, ty: SelectionType, side: Side) {
         let mut terminal = self.context_manager.current().terminal.lock();
@@ -1232,12 +2127,12 @@ impl Screen<'_> {
     }
 
     #[inline]
-    pub fn trigger_hyperlink(&self) -> bool {
+    fn trigger_hyperlink(&self, modifiers: ModifiersState) -> bool {
         #[cfg(target_os = "macos")]
-        let is_hyperlink_key_active = self.modifiers.state().super_key();
+        let is_hyperlink_key_active = modifiers.super_key();
 
         #[cfg(not(target_os = "macos"))]
-        let is_hyperlink_key_active = self.modifiers.state().alt_key();
+        let is_hyperlink_key_active = modifiers.alt_key();
 
         if !is_hyperlink_key_active || !self.renderer.has_hyperlink_range() {
             return false;
@@ -1246,11 +2141,11 @@ impl Screen<'_> {
         let terminal = self.context_manager.current().terminal.lock();
         let display_offset = terminal.display_offset();
         let pos = self.mouse_position(display_offset);
-        let pos_hyperlink = terminal.grid[pos].hyperlink();
+        let uri = terminal.hyperlink_at(pos);
         drop(terminal);
 
-        if let Some(hyperlink) = pos_hyperlink {
-            self.open_hyperlink(hyperlink);
+        if let Some(uri) = uri {
+            self.open_hyperlink(&uri);
 
             return true;
         }
@@ -1258,15 +2153,41 @@ impl Screen<'_> {
         false
     }
 
-    fn open_hyperlink(&self, hyperlink: Hyperlink) {
+    /// Open the hyperlink under the vi cursor in vi mode, otherwise the
+    /// one under the mouse.
+    fn open_hyperlink_at_cursor(&self) {
+        let terminal = self.context_manager.current().terminal.lock();
+        let pos = if terminal.mode().contains(Mode::VI) {
+            terminal.vi_mode_cursor.pos
+        } else {
+            self.mouse_position(terminal.display_offset())
+        };
+        let uri = terminal.hyperlink_at(pos);
+        drop(terminal);
+
+        if let Some(uri) = uri {
+            self.open_hyperlink(&uri);
+        }
+    }
+
+    fn open_hyperlink(&self, uri: &str) {
+        if let Some(handler) = &self.hyperlink_open_handler {
+            if let Err(message) = handler(uri) {
+                log::warn!("Unable to open {}: {}", uri, message);
+                self.context_manager
+                    .report_error_hyperlink_open_failed(message);
+            }
+            return;
+        }
+
         #[cfg(not(any(target_os = "macos", windows)))]
-        self.exec("xdg-open", [hyperlink.uri()]);
+        self.exec("xdg-open", [uri]);
 
         #[cfg(target_os = "macos")]
-        self.exec("open", [hyperlink.uri()]);
+        self.exec("open", [uri]);
 
         #[cfg(windows)]
-        self.exec("cmd", ["/c", "start", "", hyperlink.uri()]);
+        self.exec("cmd", ["/c", "start", "", uri]);
     }
 
     pub fn exec<I, S>(&self, program: &str, args: I)
@@ -1321,6 +2242,32 @@ impl Screen<'_> {
         drop(terminal);
     }
 
+    /// Mouse cursor icon for the current mouse position: a pointer over
+    /// hyperlinks, an arrow outside of the grid or when the application
+    /// handles the mouse and a text beam otherwise.
+    #[inline]
+    pub fn compute_mouse_cursor_shape(&mut self) -> CursorIcon {
+        if self.search_nearest_hyperlink_from_pos() {
+            return CursorIcon::Pointer;
+        }
+
+        if !self.contains_point(self.mouse.x, self.mouse.y)
+            || (!self.modifiers.state().shift_key() && self.mouse_mode())
+        {
+            return CursorIcon::Default;
+        }
+
+        CursorIcon::Text
+    }
+
+    /// Ask the window to update the mouse cursor icon, returns the icon.
+    #[inline]
+    pub fn on_mouse_cursor_shape_change(&mut self) -> CursorIcon {
+        let icon = self.compute_mouse_cursor_shape();
+        self.context_manager.set_cursor_icon(icon);
+        icon
+    }
+
     #[inline]
     pub fn contains_point(&self, x: usize, y: usize) -> bool {
         let layout = self.sugarloaf.layout();
@@ -1336,22 +2283,92 @@ impl Screen<'_> {
     #[inline]
     pub fn side_by_pos(&self, x: usize) -> Side {
         let layout = self.sugarloaf.layout();
-        let width = (layout.dimensions.width) as usize;
-        let margin_x = layout.margin.x * layout.dimensions.scale;
+        calculate_side_by_pos(
+            x,
+            layout.dimensions.width,
+            layout.margin.x * layout.dimensions.scale,
+            layout.width,
+        )
+    }
 
-        let cell_x = x.saturating_sub(margin_x as usize) % width;
-        let half_cell_width = width / 2;
+    #[inline]
+    pub fn is_inspecting_cell(&self) -> bool {
+        self.inspect_cell
+    }
 
-        let additional_padding = (layout.width - margin_x) % width as f32;
-        let end_of_grid = layout.width - margin_x - additional_padding;
+    /// Describe the cell under the vi cursor in vi mode, otherwise the
+    /// one under the mouse.
+    fn inspected_cell_description(&self) -> Vec<String> {
+        let terminal = self.context_manager.current().terminal.lock();
+        let pos = if terminal.mode().contains(Mode::VI) {
+            terminal.vi_mode_cursor.pos
+        } else {
+            self.mouse_position(terminal.display_offset())
+        };
+        let pos = pos.grid_clamp(&*terminal, Boundary::Grid);
+        describe_cell(&terminal, pos)
+    }
+
+    /// State shown by the status line. Values that are not part of
+    /// `status-line-format` are left empty to keep the frame cheap.
+    fn status_line_info(&self) -> StatusLineInfo {
+        let format = self.renderer.status_line_format().unwrap_or_default();
+        let context_manager = &self.context_manager;
+        let terminal = context_manager.current().terminal.lock();
+
+        let mode = if self.search_active() {
+            "SEARCH"
+        } else if terminal.mode().contains(Mode::VI) {
+            "VI"
+        } else if !self.selection_is_empty() {
+            "SELECT"
+        } else {
+            ""
+        };
 
-        if cell_x > half_cell_width
-            // Edge case when mouse leaves the window.
-            || x as f32 >= end_of_grid
-        {
-            Side::Right
+        let selection = if format.contains("{selection}") {
+            terminal
+                .selection_to_string()
+                .filter(|text| !text.is_empty())
+                .map(|text| format!("{} chars", text.chars().count()))
+                .unwrap_or_default()
+        } else {
+            String::new()
+        };
+        drop(terminal);
+
+        let title = if format.contains("{title}") {
+            context_manager.current_title()
+        } else {
+            String::new()
+        };
+
+        let cwd = context_manager
+            .titles
+            .titles
+            .get(&context_manager.current_index())
+            .map(|[_, _, path]| path.to_owned())
+            .unwrap_or_default();
+
+        let time = if format.contains("{time}") {
+            local_time()
         } else {
-            Side::Left
+            String::new()
+        };
+
+        let tabs = if format.contains("{tabs}") {
+            format_tabs(&self.contexts_info())
+        } else {
+            String::new()
+        };
+
+        StatusLineInfo {
+            mode,
+            title,
+            cwd,
+            selection,
+            time,
+            tabs,
         }
     }
 
@@ -1360,8 +2377,39 @@ impl Screen<'_> {
         self.renderer.selection_range.is_none()
     }
 
+    /// Start whatever a left button press at `pos` begins: dragging the
+    /// window from the title bar area, opening the hyperlink under the
+    /// mouse or a cell selection. Mouse reporting presses have a
+    /// `ClickState::None` click state and start nothing.
+    pub fn on_drag_begin_at_pos(
+        &mut self,
+        pos: Pos,
+        modifiers: ModifiersState,
+    ) -> DragBegin {
+        #[cfg(target_os = "macos")]
+        {
+            if !self.context_manager.config.is_native
+                && self.selection_is_empty()
+                && self.is_macos_deadzone(self.mouse.y as f64)
+            {
+                return DragBegin::Window;
+            }
+        }
+
+        if self.trigger_hyperlink(modifiers) {
+            return DragBegin::Hyperlink;
+        }
+
+        if self.mouse.click_state == ClickState::None {
+            return DragBegin::None;
+        }
+
+        self.on_left_click(pos, modifiers);
+        DragBegin::Selection
+    }
+
     #[inline]
-    pub fn on_left_click(&mut self, point: Pos) {
+    fn on_left_click(&mut self, point: Pos, modifiers: ModifiersState) {
         let side = self.mouse.square_side;
 
         match self.mouse.click_state {
@@ -1369,7 +2417,7 @@ impl Screen<'_> {
                 self.clear_selection();
 
                 // Start new empty selection.
-                if self.modifiers.state().control_key() {
+                if modifiers.control_key() {
                     self.start_selection(SelectionType::Block, point, side);
                 } else {
                     self.start_selection(SelectionType::Simple, point, side);
@@ -1392,6 +2440,31 @@ impl Screen<'_> {
         drop(terminal);
     }
 
+    /// Jump the vi cursor to the next match of the last search, nothing
+    /// happens if there was no search yet.
+    fn vi_search_next(&mut self, direction: Direction) {
+        let Some(dfas) = &mut self.search_state.dfas else {
+            return;
+        };
+
+        let mut terminal = self.context_manager.current_mut().terminal.lock();
+        let regex_match = terminal.vi_search_next(dfas, direction);
+        drop(terminal);
+
+        if regex_match.is_some() {
+            self.sugarloaf.mark_dirty();
+            self.render();
+        }
+    }
+
+    /// Focus the next match in `direction`, wrapping around the ends of
+    /// the scrollback, and scroll the display to it.
+    pub fn search_next(&mut self, direction: Direction) {
+        self.advance_search_origin(direction);
+        self.resize_top_or_bottom_line(self.ctx().len());
+        self.demand_render();
+    }
+
     #[inline]
     fn start_search(&mut self, direction: Direction) {
         // Only create new history entry if the previous regex wasn't empty.
@@ -1439,6 +2512,47 @@ impl Screen<'_> {
         self.render();
     }
 
+    /// Build a search regex out of the selected text, `None` if there is no
+    /// selection.
+    pub fn convert_selection_to_regex(&self, flags: RegexFlags) -> Option<RegexSearch> {
+        let pattern = self.selection_search_pattern(flags)?;
+        RegexSearch::new(&pattern).ok()
+    }
+
+    #[inline]
+    fn selection_search_pattern(&self, flags: RegexFlags) -> Option<String> {
+        let terminal = self.ctx().current().terminal.lock();
+        let text = terminal.selection_to_string().filter(|s| !s.is_empty())?;
+        drop(terminal);
+
+        Some(RegexSearch::pattern(&text, flags))
+    }
+
+    /// Start a forward search using the selected text as the pattern.
+    fn find_selection(&mut self) {
+        let flags = RegexFlags { literal: true };
+        let (pattern, dfas) = match (
+            self.selection_search_pattern(flags),
+            self.convert_selection_to_regex(flags),
+        ) {
+            (Some(pattern), Some(dfas)) => (pattern, dfas),
+            _ => return,
+        };
+
+        self.start_search(Direction::Right);
+        self.search_state.history[0] = pattern;
+
+        if !self.get_mode().contains(Mode::VI) {
+            // Clear selection so we do not obstruct any matches.
+            self.renderer.set_selection(None);
+        }
+
+        self.search_state.dfas = Some(dfas);
+        self.goto_match(MAX_SEARCH_WHILE_TYPING);
+        self.sugarloaf.mark_dirty();
+        self.render();
+    }
+
     #[inline]
     fn confirm_search(&mut self) {
         // Just cancel search when not in vi mode.
@@ -1674,6 +2788,13 @@ impl Screen<'_> {
         let mode = terminal.mode();
         drop(terminal);
 
+        // X10 mouse only reports button presses, releases and motion
+        // (flagged by 32 in the button code) are never sent.
+        let is_x10 = mode.contains(Mode::MOUSE_X10);
+        if is_x10 && (state == ElementState::Released || button & 32 != 0) {
+            return;
+        }
+
         let pos = self.mouse_position(display_offset);
 
         // Assure the mouse pos is not in the scrollback.
@@ -1681,9 +2802,32 @@ impl Screen<'_> {
             return;
         }
 
-        // Calculate modifiers value.
+        // Fast motion (`DECSET 1002` and `DECSET 1003`) is coalesced, only
+        // the latest one is kept and reported once the throttle window ends.
+        if button & 32 != 0 {
+            let throttle = self.motion_report_throttle;
+            match self.mouse.throttle_motion(button, throttle, Instant::now()) {
+                MotionThrottle::Report => (),
+                MotionThrottle::Schedule(delay) => {
+                    self.context_manager
+                        .schedule_motion_report(delay.as_millis() as u64 + 1);
+                    return;
+                }
+                MotionThrottle::Held => return,
+            }
+        } else {
+            // Presses and releases carry the position, a held back motion
+            // would be reported after them.
+            self.mouse.pending_motion = None;
+        }
+
+        // Calculate modifiers value, X10 mouse doesn't report them.
         let mut mods = 0;
-        let mod_state = self.modifiers.state();
+        let mod_state = if is_x10 {
+            ModifiersState::empty()
+        } else {
+            self.modifiers.state()
+        };
         if mod_state.shift_key() {
             mods += 4;
         }
@@ -1704,6 +2848,21 @@ impl Screen<'_> {
         }
     }
 
+    /// Send the motion held back by the throttle, if the application still
+    /// tracks the mouse. It is reported at the latest mouse position.
+    pub fn flush_motion_report(&mut self) {
+        let Some(button) = self.mouse.pending_motion.take() else {
+            return;
+        };
+
+        if self
+            .get_mode()
+            .intersects(Mode::MOUSE_MOTION | Mode::MOUSE_DRAG)
+        {
+            self.mouse_report(button, ElementState::Pressed);
+        }
+    }
+
     #[inline]
     fn normal_mouse_report(&mut self, position: Pos, button: u8) {
         let Pos { row, col } = position;
@@ -1741,6 +2900,12 @@ impl Screen<'_> {
 
     #[inline]
     pub fn on_focus_change(&mut self, is_focused: bool) {
+        // Other applications usually change the clipboard while Rio is
+        // not focused.
+        if is_focused {
+            self.poll_clipboard_change();
+        }
+
         if self.get_mode().contains(Mode::FOCUS_IN_OUT) {
             let chr = if is_focused { "I" } else { "O" };
 
@@ -1752,8 +2917,37 @@ impl Screen<'_> {
         }
     }
 
+    /// Whether the mouse wheel is turned into arrow keys, see
+    /// [`MouseWheelInAlt`].
+    #[inline]
+    fn wheel_sends_arrows(&self, mode: Mode) -> bool {
+        if !mode.contains(Mode::ALT_SCREEN) {
+            return false;
+        }
+
+        match self.mouse_wheel_in_alt {
+            // Mode 7786 asks for arrows even if 1007 was turned off.
+            MouseWheelInAlt::Auto => {
+                mode.intersects(Mode::WHEEL_TO_ARROW | Mode::ALTERNATE_SCROLL)
+            }
+            MouseWheelInAlt::Arrows => true,
+            MouseWheelInAlt::Scroll => false,
+        }
+    }
+
     #[inline]
     pub fn scroll(&mut self, new_scroll_x_px: f64, new_scroll_y_px: f64) {
+        self.on_scroll_wheel_modulated(new_scroll_x_px, new_scroll_y_px, false);
+    }
+
+    /// Scroll by pixels, `kinetic` tells if it is momentum scroll sent after
+    /// the finger has been lifted from the trackpad.
+    pub fn on_scroll_wheel_modulated(
+        &mut self,
+        new_scroll_x_px: f64,
+        new_scroll_y_px: f64,
+        kinetic: bool,
+    ) {
         let layout = self.sugarloaf.layout();
         let width = layout.dimensions.width as f64;
         let height = layout.dimensions.height as f64;
@@ -1789,9 +2983,11 @@ impl Screen<'_> {
             for _ in 0..columns {
                 self.mouse_report(code, ElementState::Pressed);
             }
-        } else if mode.contains(Mode::ALT_SCREEN | Mode::ALTERNATE_SCROLL)
-            && !self.modifiers.state().shift_key()
-        {
+        } else if kinetic && mode.contains(Mode::ALT_SCREEN) {
+            // Momentum scroll should not be turned into arrow keys, since the
+            // user is no longer scrolling it would move the application around.
+            self.mouse.accumulated_scroll = Default::default();
+        } else if self.wheel_sends_arrows(mode) && !self.modifiers.state().shift_key() {
             self.mouse.accumulated_scroll.x +=
                 (new_scroll_x_px * self.mouse.multiplier) / self.mouse.divider;
             self.mouse.accumulated_scroll.y +=
@@ -1835,30 +3031,169 @@ impl Screen<'_> {
                 terminal.scroll_display(Scroll::Delta(lines));
                 drop(terminal);
             }
+
+            // Truncated lines scroll sideways.
+            if !self.context_manager.current().wrap_lines {
+                self.mouse.accumulated_scroll.x +=
+                    (new_scroll_x_px * self.mouse.multiplier) / self.mouse.divider;
+                let columns = (self.mouse.accumulated_scroll.x / width) as isize;
+
+                if columns != 0 {
+                    let current = self.context_manager.current_mut();
+                    let max_scroll =
+                        max_line_scroll(&current.terminal.lock().visible_rows());
+                    current.line_scroll = current
+                        .line_scroll
+                        .saturating_add_signed(-columns)
+                        .min(max_scroll);
+                }
+            }
         }
 
         self.mouse.accumulated_scroll.x %= width;
         self.mouse.accumulated_scroll.y %= height;
     }
 
+    /// Paste the content of `ty` once it is read, the clipboard owner may
+    /// take a while to answer and rendering must not wait for it. A newer
+    /// request replaces one still waiting.
+    pub fn request_paste(&mut self, ty: ClipboardType) {
+        self.clipboard_requests += 1;
+        self.clipboard_request = Some(self.clipboard_requests);
+        self.context_manager.load_clipboard_async(
+            &self.clipboard.borrow(),
+            ty,
+            self.clipboard_requests,
+        );
+    }
+
+    /// Finish the paste started by `request_paste`, `text` is `None` when
+    /// the clipboard didn't answer in time. Returns `false` for requests
+    /// that were replaced or already timed out.
+    pub fn complete_paste(&mut self, paste_id: u64, text: Option<String>) -> bool {
+        if self.clipboard_request != Some(paste_id) {
+            return false;
+        }
+        self.clipboard_request = None;
+
+        match text {
+            Some(text) => {
+                self.paste(&text, true);
+                self.render();
+            }
+            None => self.context_manager.report_error_clipboard_paste_timeout(),
+        }
+        true
+    }
+
     #[inline]
     pub fn paste(&mut self, text: &str, bracketed: bool) {
+        if self.confirm_multiline_paste
+            && !self.search_active()
+            && text.trim_end_matches(['\r', '\n']).contains('\n')
+        {
+            self.pending_paste = Some((text.to_owned(), bracketed));
+            self.demand_render();
+            return;
+        }
+
+        self.write_paste(text, bracketed);
+    }
+
+    fn confirm_paste(&mut self) {
+        if let Some((text, bracketed)) = self.pending_paste.take() {
+            self.write_paste(&text, bracketed);
+        }
+        self.demand_render();
+    }
+
+    fn cancel_paste(&mut self) {
+        self.pending_paste = None;
+        self.demand_render();
+    }
+
+    /// Store the decoded payload of an OSC 52 request in the clipboard.
+    /// Ignored unless `allow-osc52-write` is enabled or when the payload
+    /// is not valid UTF-8.
+    pub fn handle_osc52(&mut self, selection: ClipboardType, data: &[u8]) {
+        if let Some(text) = osc52_write_text(self.allow_osc52_write, data) {
+            self.clipboard.borrow_mut().set(selection, text);
+        }
+    }
+
+    /// Answer an OSC 52 query with the clipboard content, `format` builds
+    /// the reply. Ignored unless `allow-osc52-read` is enabled, so the
+    /// clipboard doesn't leak to any program running in the terminal.
+    pub fn handle_osc52_read(
+        &mut self,
+        selection: ClipboardType,
+        format: &dyn Fn(&str) -> String,
+        terminal_id: Option<u64>,
+    ) {
+        let load = || self.clipboard.borrow_mut().get(selection);
+        let Some(text) = osc52_read_reply(self.allow_osc52_read, load, format) else {
+            return;
+        };
+
+        if let Some(context) = self.ctx_mut().event_context_mut(terminal_id) {
+            context.messenger.send_bytes(text.into_bytes());
+        }
+    }
+
+    /// Send the input recorded in the macro `slot` to the current context,
+    /// like if it was typed.
+    pub fn play_macro(&mut self, slot: usize) {
+        let bytes = match self.macros.get(slot) {
+            Some(bytes) if !bytes.is_empty() => bytes.to_vec(),
+            _ => return,
+        };
+
+        self.sugarloaf.mark_dirty();
+        self.scroll_bottom_when_cursor_not_visible();
+        self.clear_selection();
+        // Played macros are part of the one being recorded, if any.
+        self.macros.record(&bytes);
+        self.ctx_mut().current_mut().messenger.send_bytes(bytes);
+    }
+
+    fn write_paste(&mut self, text: &str, bracketed: bool) {
+        let expanded;
+        let text = if self.paste_expand_tabs && !self.search_active() {
+            expanded = expand_tabs(text);
+            expanded.as_str()
+        } else {
+            text
+        };
+
         if self.search_active() {
             for c in text.chars() {
                 self.search_input(c);
             }
         } else if bracketed && self.get_mode().contains(Mode::BRACKETED_PASTE) {
+            self.macros.record(b"\x1b[200~");
             self.ctx_mut()
                 .current_mut()
                 .messenger
                 .send_bytes(b"\x1b[200~"[..].to_vec());
 
+            // Hold back only the last newline, so the shell doesn't run the
+            // pasted command right away.
+            let text = if self.hold_paste_trailing_newline {
+                text.strip_suffix("\r\n")
+                    .or_else(|| text.strip_suffix('\n'))
+                    .unwrap_or(text)
+            } else {
+                text
+            };
+
             // Write filtered escape sequences.
             //
             // We remove `\x1b` to ensure it's impossible for the pasted text to write the bracketed
             // paste end escape `\x1b[201~` and `\x03` since some shells incorrectly terminate
             // bracketed paste on its receival.
             let filtered = text.replace(['\x1b', '\x03'], "");
+            self.macros.record(filtered.as_bytes());
+            self.macros.record(b"\x1b[201~");
             self.ctx_mut()
                 .current_mut()
                 .messenger
@@ -1869,11 +3204,165 @@ impl Screen<'_> {
                 .messenger
                 .send_bytes(b"\x1b[201~"[..].to_vec());
         } else {
+            let text = text.replace("\r\n", "\r").replace('\n', "\r");
+            self.macros.record(text.as_bytes());
             self.ctx_mut()
                 .current_mut()
                 .messenger
-                .send_bytes(text.replace("\r\n", "\r").replace('\n', "\r").into_bytes());
+                .send_bytes(text.into_bytes());
+        }
+    }
+
+    /// Pick the renderer performance based on the power source when
+    /// `renderer.auto-performance` is enabled, otherwise keep the configured one.
+    pub fn on_power_state_change(&mut self, battery_level: f32, on_ac: bool) {
+        let performance = if self.auto_performance {
+            auto_performance(on_ac)
+        } else {
+            self.renderer_performance
+        };
+
+        log::info!(
+            "power state changed (battery level: {battery_level}, on ac: {on_ac}), using {performance} performance"
+        );
+
+        if let Err(err) = self
+            .sugarloaf
+            .update_power_preference(power_preference(performance))
+        {
+            log::error!("unable to switch to {performance} performance: {err}");
+            return;
         }
+        self.demand_render();
+    }
+
+    /// Register a filter for the output of the current context, see [`OutputFilter`]
+    /// for the performance expectations.
+    pub fn set_output_filter(&mut self, filter: OutputFilter) {
+        self.ctx().current().messenger.send_output_filter(filter);
+    }
+
+    /// Log the raw output of the current context until it is closed,
+    /// without consuming any of it.
+    fn log_output(&mut self) {
+        self.set_output_filter(Box::new(|bytes| {
+            log::info!("output: {}", bytes.escape_ascii());
+            0
+        }));
+        self.status_message("Logging the output of this tab", 1500);
+    }
+
+    /// Start recording input and output of the current context, the PTY
+    /// reader of the context records into it.
+    pub fn start_recording(&mut self) {
+        self.stop_recording();
+
+        let terminal = self.ctx().current().terminal.lock();
+        let recording =
+            Recording::new(terminal.grid.columns(), terminal.grid.screen_lines())
+                .into_shared();
+        drop(terminal);
+
+        self.ctx()
+            .current()
+            .messenger
+            .send_recording(Some(recording.clone()));
+        self.recording = Some(recording);
+    }
+
+    /// Stop the active recording and keep it available for export.
+    pub fn stop_recording(&mut self) {
+        for context in self.context_manager.all_contexts() {
+            context.messenger.send_recording(None);
+        }
+    }
+
+    /// Write the last recording to `path` using asciinema v2 format,
+    /// an active recording gets stopped before being written. The typed
+    /// input is part of it, so `path` is created readable by the user only
+    /// and must not exist yet.
+    pub fn export_ansi_recording(&mut self, path: &Path) -> std::io::Result<()> {
+        self.stop_recording();
+
+        let recording = self.recording.as_ref().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "There is no recording")
+        })?;
+        let mut writer = std::io::BufWriter::new(create_private_file(path)?);
+        let written = recording
+            .lock()
+            .write_asciicast(&mut writer)
+            .and_then(|_| writer.into_inner().map(drop).map_err(|e| e.into_error()));
+        if written.is_err() {
+            let _ = std::fs::remove_file(path);
+        }
+        written
+    }
+
+    /// Save the last recording to the temporary directory and copy its
+    /// path, see [`Screen::export_ansi_recording`].
+    fn save_recording(&mut self) {
+        let path = temporary_file_path("rio-recording", "cast");
+        match self.export_ansi_recording(&path) {
+            Ok(()) => {
+                self.clipboard
+                    .borrow_mut()
+                    .set(ClipboardType::Clipboard, path.display().to_string());
+                self.status_message("Recording saved, path copied", 1500);
+            }
+            Err(error) => {
+                log::error!("unable to save the recording: {error}");
+                self.status_message("Unable to save the recording", 3000);
+            }
+        }
+    }
+
+    /// Write the current tab to the session file, see [`rio_backend::session`].
+    /// The rows are streamed to a temporary file that replaces the previous
+    /// session once complete.
+    pub fn save_session(&mut self) -> std::io::Result<()> {
+        let path = session_file_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // A leftover from an interrupted save would keep its own mode.
+        let temporary = path.with_extension("bin.tmp");
+        let _ = std::fs::remove_file(&temporary);
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let terminal = &self.ctx().current().terminal;
+        let written = options.open(&temporary).and_then(|file| {
+            let mut writer = std::io::BufWriter::new(file);
+            terminal.lock().save_session(&mut writer)?;
+            writer
+                .into_inner()
+                .map_err(|error| error.into_error())?
+                .sync_all()
+        });
+        if let Err(error) = written.and_then(|_| std::fs::rename(&temporary, &path)) {
+            let _ = std::fs::remove_file(&temporary);
+            return Err(error);
+        }
+
+        Ok(())
+    }
+
+    /// Read the session file back into the scrollback of the current tab,
+    /// the program running in it keeps the screen. The file is read before
+    /// the terminal gets locked to swap the rows in.
+    pub fn restore_session(&mut self) -> std::io::Result<()> {
+        let mut file = std::io::BufReader::new(std::fs::File::open(session_file_path())?);
+        let terminal = &self.ctx().current().terminal;
+        let size = terminal.lock().session_size();
+        let session = rio_backend::session::read_session(&mut file, size)?;
+        terminal.lock().apply_session(session);
+
+        self.renderer.set_selection(None);
+        self.demand_render();
+        Ok(())
     }
 
     pub fn render_assistant(&mut self, assistant: &crate::routes::assistant::Assistant) {
@@ -1912,6 +3401,40 @@ impl Screen<'_> {
             }
         }
 
+        if let Some(unicode_input) = &self.unicode_input {
+            self.renderer
+                .set_active_unicode_input(Some(unicode_input.to_owned()));
+        }
+
+        if let Some(tab_rename) = &self.tab_rename {
+            self.renderer
+                .set_active_tab_rename(Some(tab_rename.to_owned()));
+        }
+
+        if let Some((pending_paste, _)) = &self.pending_paste {
+            self.renderer
+                .set_active_paste_preview(Some(pending_paste.to_owned()));
+        }
+
+        if let Some((message, shown_at, timeout)) = &self.status_message {
+            if shown_at.elapsed() < *timeout {
+                self.renderer
+                    .set_active_status_message(Some(message.to_owned()));
+            } else {
+                self.status_message = None;
+            }
+        }
+
+        if self.inspect_cell {
+            let description = self.inspected_cell_description();
+            self.renderer.set_active_cell_inspector(Some(description));
+        }
+
+        if let Some(format) = self.renderer.status_line_format() {
+            let status_line = format_status_line(format, &self.status_line_info());
+            self.renderer.set_active_status_line(Some(status_line));
+        }
+
         let mut search_hints = if is_search_active {
             let terminal = self.context_manager.current().terminal.lock();
             let hints = self
@@ -1924,27 +3447,49 @@ impl Screen<'_> {
             None
         };
 
-        let (rows, cursor, display_offset, has_blinking_enabled) = {
-            let terminal = self.context_manager.current().terminal.lock();
-            let data = (
-                terminal.visible_rows(),
-                terminal.cursor(),
-                terminal.display_offset(),
-                terminal.blinking_cursor,
-            );
+        let layout = self.sugarloaf.layout();
+        let current = self.context_manager.current();
+        let mut cursor = None;
+        let mut has_blinking_enabled = false;
+        let mut panes = Vec::new();
+        for (route_id, rect) in
+            current.pane_rects(PaneRect::new(layout.columns, layout.lines))
+        {
+            let Some(pane) = current.pane(route_id) else {
+                continue;
+            };
+
+            let is_focused = route_id == current.route_id;
+            let mut terminal = pane.terminal.lock();
+            // The current context is on screen, so it was seen
+            terminal.has_activity = false;
+            terminal.bell = false;
+            if is_focused {
+                cursor = Some(terminal.cursor());
+                has_blinking_enabled = terminal.blinking_cursor;
+            }
+            panes.push(PaneContent {
+                rect,
+                rows: terminal.visible_rows(),
+                display_offset: terminal.display_offset() as i32,
+                history_size: terminal.history_size(),
+                wrap_lines: pane.wrap_lines,
+                line_scroll: pane.line_scroll,
+                is_focused,
+            });
             drop(terminal);
-            data
-        };
+        }
+
+        let cursor = cursor.unwrap_or_else(|| self.renderer.get_cursor_state_from_ref());
 
         self.context_manager.update_titles();
         self.renderer.set_ime(self.ime.preedit());
 
         self.renderer.prepare_term(
-            &rows,
+            &panes,
             cursor,
             &mut self.sugarloaf,
             &self.context_manager,
-            display_offset as i32,
             has_blinking_enabled,
             &mut search_hints,
             &self.search_state.focused_match,
@@ -1959,6 +3504,15 @@ impl Screen<'_> {
         // println!("Render time elapsed");
 
         self.sugarloaf.render();
+        self.input_redraw.rendered();
+
+        // Cell dimensions are only measured while rendering, so font size
+        // changes and the first frame can change the grid afterwards.
+        let layout = self.sugarloaf.layout();
+        if self.context_manager.grid_changed(&layout) {
+            self.resize_all_contexts();
+            self.context_manager.schedule_render(1);
+        }
 
         // In this case the configuration of blinking cursor is enabled
         // and the terminal also have instructions of blinking enabled
@@ -1967,7 +3521,482 @@ impl Screen<'_> {
             self.context_manager.schedule_render_on_route(800);
         }
 
+        // Keep the clock of the status line up to date.
+        if self
+            .renderer
+            .status_line_format()
+            .is_some_and(|format| format.contains("{time}"))
+        {
+            self.context_manager.schedule_render_on_route(1000);
+        }
+
+        // Render again once the status message expires, so it gets removed.
+        if let Some((_, shown_at, timeout)) = &self.status_message {
+            let remaining = timeout.saturating_sub(shown_at.elapsed());
+            self.context_manager
+                .schedule_render(remaining.as_millis() as u64 + 1);
+        }
+
+        self.frame_counter = self.frame_counter.wrapping_add(1);
+        let frame = self.render_frame_number();
+        if let Some(fps) = self.fps_counter.tick(frame, Instant::now()) {
+            log::info!("fps: {fps:.1}");
+        }
+
         // let duration = start.elapsed();
         // println!("Total render time is: {:?}\n", duration);
     }
+
+    /// Replace the platform opener (`open`, `xdg-open` or `start`) used for
+    /// hyperlinks, useful for sandboxed environments.
+    #[inline]
+    pub fn set_hyperlink_open_handler(&mut self, handler: HyperlinkOpenHandler) {
+        self.hyperlink_open_handler = Some(handler);
+    }
+
+    /// Called once the first window is created. The startup script is
+    /// sent when the shell reports its prompt, on its first output or
+    /// after the configured delay, whichever comes first.
+    #[inline]
+    pub fn on_application_startup_complete(&mut self) {
+        if self.startup_script.is_some() {
+            self.startup_pending = true;
+            self.context_manager
+                .schedule_startup_script(self.startup_delay_ms);
+        }
+    }
+
+    /// Send the startup script to the first context, only once.
+    pub fn run_startup_script(&mut self) {
+        if !self.startup_pending {
+            return;
+        }
+        self.startup_pending = false;
+
+        if let (Some(script), Some(context)) = (
+            &self.startup_script,
+            self.context_manager.contexts().first(),
+        ) {
+            context.messenger.send_write(script.to_owned().into_bytes());
+        }
+    }
+
+    /// Remap scancodes of the following key events, replacing the
+    /// previous map. Scancode bindings match the remapped keys.
+    #[inline]
+    pub fn apply_input_map(&mut self, map: InputMap) {
+        self.input_map = map;
+    }
+
+    /// Listen to clipboard changes. There is no change notification from
+    /// the clipboard providers, so they are polled every
+    /// `CLIPBOARD_POLL_INTERVAL_MS` and when the window gains focus.
+    #[inline]
+    #[allow(unused)]
+    pub fn on_clipboard_change(&mut self, listener: ClipboardChangeListener) {
+        self.clipboard_change_listener = Some(listener);
+        self.clipboard_monitor.reset();
+        self.poll_clipboard_change();
+        self.context_manager
+            .schedule_clipboard_poll(CLIPBOARD_POLL_INTERVAL_MS);
+    }
+
+    /// Compare the clipboards with their last seen content and notify
+    /// the listener about the ones that changed.
+    pub fn poll_clipboard_change(&mut self) {
+        let Some(listener) = &self.clipboard_change_listener else {
+            return;
+        };
+
+        let clipboard = &self.clipboard;
+        self.clipboard_monitor.poll(
+            |ty| {
+                let mut clipboard = clipboard.borrow_mut();
+                if ty == ClipboardType::Selection && !clipboard.has_selection() {
+                    return None;
+                }
+                // Busy with a paste, checked again on the next poll.
+                clipboard.get(ty)
+            },
+            listener,
+        );
+    }
+
+    /// Show a message (e.g. "Copied to clipboard") on top of the terminal
+    /// that goes away after `timeout_ms`.
+    #[inline]
+    pub fn status_message(&mut self, msg: &str, timeout_ms: u64) {
+        self.status_message = Some((
+            msg.to_owned(),
+            Instant::now(),
+            Duration::from_millis(timeout_ms),
+        ));
+        self.context_manager.schedule_render(0);
+    }
+
+    /// Whether the next redraw is a response to typing, which may skip
+    /// frame pacing when `renderer.low-latency-input` is enabled.
+    #[inline]
+    pub fn take_input_redraw(&mut self) -> bool {
+        self.input_redraw.take()
+    }
+
+    /// Number of frames rendered so far, it only increases.
+    #[inline]
+    pub fn render_frame_number(&self) -> u64 {
+        self.frame_counter
+    }
+}
+
+/// Whether the next redraw answers a typed key, it is dropped once a
+/// frame gets drawn so later output keeps its frame pacing.
+struct InputRedraw {
+    /// `renderer.low-latency-input`
+    enabled: bool,
+    pending: bool,
+}
+
+impl InputRedraw {
+    fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            pending: false,
+        }
+    }
+
+    #[inline]
+    fn key_pressed(&mut self) {
+        self.pending |= self.enabled;
+    }
+
+    #[inline]
+    fn take(&mut self) -> bool {
+        std::mem::take(&mut self.pending)
+    }
+
+    #[inline]
+    fn rendered(&mut self) {
+        self.pending = false;
+    }
+}
+
+/// Frame rate logged with `developer.enable-fps-counter`, measured from
+/// the frame numbers about once a second.
+struct FpsCounter {
+    enabled: bool,
+    /// Frame number and time of the last measure.
+    last: (u64, Instant),
+}
+
+impl FpsCounter {
+    fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            last: (0, Instant::now()),
+        }
+    }
+
+    /// Frames per second since the last measure, once a second has passed.
+    fn tick(&mut self, frame: u64, now: Instant) -> Option<f64> {
+        if !self.enabled {
+            return None;
+        }
+
+        let (last_frame, last_time) = self.last;
+        let elapsed = now.saturating_duration_since(last_time);
+        if elapsed < Duration::from_secs(1) {
+            return None;
+        }
+
+        self.last = (frame, now);
+        Some(frame.wrapping_sub(last_frame) as f64 / elapsed.as_secs_f64())
+    }
+}
+
+/// Character of a hex codepoint typed in the unicode input, nothing for
+/// surrogates and values past `U+10FFFF`.
+pub fn char_from_codepoint(hex: &str) -> Option<char> {
+    u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+}
+
+/// Text an OSC 52 request stores in the clipboard, nothing when writes
+/// are not allowed or the payload is not valid UTF-8.
+fn osc52_write_text(allowed: bool, data: &[u8]) -> Option<&str> {
+    if !allowed {
+        return None;
+    }
+
+    std::str::from_utf8(data).ok()
+}
+
+/// Reply to an OSC 52 query, the clipboard is only loaded when reads are
+/// allowed.
+fn osc52_read_reply(
+    allowed: bool,
+    load: impl FnOnce() -> Option<String>,
+    format: &dyn Fn(&str) -> String,
+) -> Option<String> {
+    if !allowed {
+        return None;
+    }
+
+    // A clipboard busy with a paste answers as empty rather than
+    // blocking the terminal.
+    let content = load();
+    Some(format(content.as_deref().unwrap_or_default()))
+}
+
+#[inline]
+fn scroll_bottom<T: EventListener>(terminal: &mut Crosswords<T>) {
+    if !terminal.is_at_bottom() {
+        terminal.scroll_display(Scroll::Bottom);
+    }
+}
+
+/// Scroll back to the bottom and drop the selection for a typed key.
+/// Modifiers alone (e.g. reported by kitty keyboard protocol) only prepare
+/// a shortcut and keep both, returns whether `key` counts as typing.
+fn follow_typed_key<T: EventListener>(
+    terminal: &mut Crosswords<T>,
+    key: &KeyEvent,
+) -> bool {
+    if !key.state.is_pressed() || crate::bindings::is_modifier_key(&key.logical_key) {
+        return false;
+    }
+
+    scroll_bottom(terminal);
+    terminal.selection = None;
+    true
+}
+
+#[inline]
+fn session_file_path() -> std::path::PathBuf {
+    rio_backend::config::config_dir_path().join("session.bin")
+}
+
+/// Open hyperlinks with the `hyperlink-opener` program, the link is its
+/// last argument.
+fn hyperlink_opener(opener: Shell) -> HyperlinkOpenHandler {
+    Box::new(move |uri| {
+        let mut args = opener.args.clone();
+        args.push(uri.to_owned());
+
+        // No terminal to take the working directory from, the lookup of
+        // an invalid fd and pid fails and the program keeps Rio's one.
+        #[cfg(unix)]
+        let spawned = teletypewriter::spawn_daemon(&opener.program, &args, -1, 0);
+        #[cfg(windows)]
+        let spawned = teletypewriter::spawn_daemon(&opener.program, &args);
+
+        spawned.map_err(|error| format!("{}: {error}", opener.program))
+    })
+}
+
+/// Path in the temporary directory that no other Rio process or earlier
+/// call picks, e.g. `rio-screen-42-1700000000000.png`.
+fn temporary_file_path(name: &str, extension: &str) -> std::path::PathBuf {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default();
+    std::env::temp_dir().join(format!(
+        "{name}-{}-{timestamp}.{extension}",
+        std::process::id()
+    ))
+}
+
+/// Create `path` readable by the user only. The temporary directory is
+/// shared, so it never follows or truncates a file that someone else
+/// created at the same path.
+fn create_private_file(path: &Path) -> std::io::Result<std::fs::File> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)
+}
+
+/// Replace tabs with the spaces up to the next default tab stop, columns
+/// count from the start of each pasted line.
+fn expand_tabs(text: &str) -> String {
+    let mut expanded = String::with_capacity(text.len());
+    let mut column = 0;
+
+    for c in text.chars() {
+        match c {
+            '\t' => {
+                let spaces = INITIAL_TABSTOPS - column % INITIAL_TABSTOPS;
+                expanded.extend(std::iter::repeat(' ').take(spaces));
+                column += spaces;
+            }
+            '\n' | '\r' => {
+                expanded.push(c);
+                column = 0;
+            }
+            c => {
+                expanded.push(c);
+                column += c.width().unwrap_or(0);
+            }
+        }
+    }
+
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rio_backend::ansi::CursorShape;
+    use rio_backend::crosswords::CrosswordsSize;
+    use rio_backend::event::{VoidListener, WindowId};
+    use rio_backend::performer::handler::ParserProcessor;
+
+    #[test]
+    fn test_char_from_codepoint() {
+        assert_eq!(char_from_codepoint("41"), Some('A'));
+        assert_eq!(char_from_codepoint("1F600"), Some('\u{1f600}'));
+        assert_eq!(char_from_codepoint("D800"), None);
+        assert_eq!(char_from_codepoint("110000"), None);
+        assert_eq!(char_from_codepoint(""), None);
+    }
+
+    #[test]
+    fn test_input_redraw() {
+        let mut input_redraw = InputRedraw::new(false);
+        input_redraw.key_pressed();
+        assert!(!input_redraw.take());
+
+        let mut input_redraw = InputRedraw::new(true);
+        input_redraw.key_pressed();
+        assert!(input_redraw.take());
+        assert!(!input_redraw.take());
+
+        // A frame drawn for the key press clears it, later output does not
+        // skip frame pacing.
+        input_redraw.key_pressed();
+        input_redraw.rendered();
+        assert!(!input_redraw.take());
+    }
+
+    #[test]
+    fn test_fps_counter() {
+        let start = Instant::now();
+        let mut fps_counter = FpsCounter {
+            enabled: true,
+            last: (10, start),
+        };
+
+        assert_eq!(
+            fps_counter.tick(40, start + Duration::from_millis(500)),
+            None
+        );
+        assert_eq!(
+            fps_counter.tick(70, start + Duration::from_secs(2)),
+            Some(30.0)
+        );
+        // Measured again from the frame of the last measure.
+        assert_eq!(
+            fps_counter.tick(100, start + Duration::from_secs(3)),
+            Some(30.0)
+        );
+
+        let mut fps_counter = FpsCounter {
+            enabled: false,
+            last: (0, start),
+        };
+        assert_eq!(fps_counter.tick(60, start + Duration::from_secs(1)), None);
+    }
+
+    #[test]
+    fn test_osc52_write_gate() {
+        assert_eq!(osc52_write_text(true, b"rio"), Some("rio"));
+        assert_eq!(osc52_write_text(true, &[0xff, 0xfe]), None);
+        assert_eq!(osc52_write_text(false, b"rio"), None);
+    }
+
+    #[test]
+    fn test_osc52_read_gate() {
+        let format = |text: &str| format!("\x1b]52;c;{text}\x07");
+
+        assert_eq!(
+            osc52_read_reply(true, || Some(String::from("rio")), &format),
+            Some(String::from("\x1b]52;c;rio\x07"))
+        );
+        // A busy clipboard answers as empty.
+        assert_eq!(
+            osc52_read_reply(true, || None, &format),
+            Some(String::from("\x1b]52;c;\x07"))
+        );
+
+        // Disabled, the clipboard is not even read.
+        let loaded = std::cell::Cell::new(false);
+        let load = || {
+            loaded.set(true);
+            Some(String::from("secret"))
+        };
+        assert_eq!(osc52_read_reply(false, load, &format), None);
+        assert!(!loaded.get());
+    }
+
+    #[test]
+    fn test_follow_typed_key() {
+        let size = CrosswordsSize::new(10, 3);
+        let mut terminal = Crosswords::new(
+            size,
+            CursorShape::Block,
+            VoidListener {},
+            WindowId::from(0),
+            0,
+        );
+        let mut parser = ParserProcessor::new();
+        for byte in b"1\r\n2\r\n3\r\n4\r\n5\r\n6" {
+            parser.advance(&mut terminal, *byte);
+        }
+        terminal.scroll_display(Scroll::Delta(2));
+        terminal.selection = Some(Selection::new(
+            SelectionType::Simple,
+            Pos::new(Line(-2), Column(0)),
+            Side::Left,
+        ));
+
+        let key = |logical_key: Key, text: Option<&str>, state: KeyState| KeyEvent {
+            logical_key: logical_key.clone(),
+            key_without_modifiers: logical_key,
+            scancode: None,
+            text: text.map(String::from),
+            location: KeyLocation::Left,
+            state,
+            repeat: false,
+            modifiers: ModifiersState::CONTROL,
+        };
+
+        // Ctrl alone while scrolled keeps the viewport and the selection.
+        for state in [KeyState::Pressed, KeyState::Released] {
+            let control = key(Key::Named(NamedKey::Control), None, state);
+            assert!(!follow_typed_key(&mut terminal, &control));
+            assert_eq!(terminal.display_offset(), 2);
+            assert!(terminal.selection.is_some());
+        }
+
+        let c = key(Key::Character("c".into()), Some("\x03"), KeyState::Pressed);
+        assert!(follow_typed_key(&mut terminal, &c));
+        assert_eq!(terminal.display_offset(), 0);
+        assert!(terminal.selection.is_none());
+    }
+
+    #[test]
+    fn test_expand_tabs() {
+        assert_eq!(expand_tabs("\tx"), "        x");
+        assert_eq!(expand_tabs("ab\tc"), "ab      c");
+        assert_eq!(expand_tabs("abcdefgh\ti"), "abcdefgh        i");
+
+        // Every line starts back at the first column.
+        assert_eq!(
+            expand_tabs("abc\n\td\r\ne\tf"),
+            "abc\n        d\r\ne       f"
+        );
+
+        // Wide chars take two columns.
+        assert_eq!(expand_tabs("漢\tx"), "漢      x");
+    }
 }
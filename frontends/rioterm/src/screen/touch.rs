@@ -147,10 +147,9 @@ fn on_touch_motion(route: &mut Route, touch: Touch) {
 
                 route.window.screen.mouse.click_state = ClickState::Click;
                 route.window.screen.mouse.left_button_state = ElementState::Pressed;
-                route
-                    .window
-                    .screen
-                    .on_left_click(route.window.screen.mouse_position(0));
+                let pos = route.window.screen.mouse_position(0);
+                let modifiers = route.window.screen.modifiers.state();
+                route.window.screen.on_drag_begin_at_pos(pos, modifiers);
 
                 // Apply motion since touch start.
                 on_touch_motion(route, touch);
@@ -223,10 +222,9 @@ fn on_touch_end(route: &mut Route, touch: Touch) {
 
             route.window.screen.mouse.click_state = ClickState::Click;
             route.window.screen.mouse.left_button_state = ElementState::Pressed;
-            route
-                .window
-                .screen
-                .on_left_click(route.window.screen.mouse_position(0));
+            let pos = route.window.screen.mouse_position(0);
+            let modifiers = route.window.screen.modifiers.state();
+            route.window.screen.on_drag_begin_at_pos(pos, modifiers);
             route.window.screen.mouse.click_state = ClickState::None;
             route.window.screen.mouse.left_button_state = ElementState::Released;
             log::info!("tap end");
@@ -0,0 +1,1046 @@
+// Kitty graphics protocol, images sent as `ESC _ G <control data> ; <payload> ESC \`.
+// https://sw.kovidgoyal.net/kitty/graphics-protocol/
+
+use crate::ansi::graphics::{TextureRef, MAX_GRAPHIC_DIMENSIONS};
+use crate::sugarloaf::{ColorType, GraphicData, GraphicId};
+use base64::{engine::general_purpose, Engine as _};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Memory used by transmitted images before the oldest ones are dropped.
+const STORAGE_QUOTA: usize = 320 * 1024 * 1024;
+
+/// Placements remembered to be deleted later, older ones are forgotten.
+const MAX_PLACEMENTS: usize = 1024;
+
+/// Largest decoded image, in bytes.
+const MAX_IMAGE_BYTES: usize = MAX_GRAPHIC_DIMENSIONS[0] * MAX_GRAPHIC_DIMENSIONS[1] * 4;
+
+/// Largest base64 payload of a chunked transmission.
+const MAX_PAYLOAD_BYTES: usize = MAX_IMAGE_BYTES.div_ceil(3) * 4;
+
+/// Character printed by the client where a virtual placement is displayed.
+pub const PLACEHOLDER: char = '\u{10EEEE}';
+
+/// Combining characters following a placeholder, their index is the row,
+/// the column and the most significant byte of the image id.
+const DIACRITICS: [u32; 297] = [
+    0x0305, 0x030D, 0x030E, 0x0310, 0x0312, 0x033D, 0x033E, 0x033F, 0x0346, 0x034A,
+    0x034B, 0x034C, 0x0350, 0x0351, 0x0352, 0x0357, 0x035B, 0x0363, 0x0364, 0x0365,
+    0x0366, 0x0367, 0x0368, 0x0369, 0x036A, 0x036B, 0x036C, 0x036D, 0x036E, 0x036F,
+    0x0483, 0x0484, 0x0485, 0x0486, 0x0487, 0x0592, 0x0593, 0x0594, 0x0595, 0x0597,
+    0x0598, 0x0599, 0x059C, 0x059D, 0x059E, 0x059F, 0x05A0, 0x05A1, 0x05A8, 0x05A9,
+    0x05AB, 0x05AC, 0x05AF, 0x05C4, 0x0610, 0x0611, 0x0612, 0x0613, 0x0614, 0x0615,
+    0x0616, 0x0617, 0x0657, 0x0658, 0x0659, 0x065A, 0x065B, 0x065D, 0x065E, 0x06D6,
+    0x06D7, 0x06D8, 0x06D9, 0x06DA, 0x06DB, 0x06DC, 0x06DF, 0x06E0, 0x06E1, 0x06E2,
+    0x06E4, 0x06E7, 0x06E8, 0x06EB, 0x06EC, 0x0730, 0x0732, 0x0733, 0x0735, 0x0736,
+    0x073A, 0x073D, 0x073F, 0x0740, 0x0741, 0x0743, 0x0745, 0x0747, 0x0749, 0x074A,
+    0x07EB, 0x07EC, 0x07ED, 0x07EE, 0x07EF, 0x07F0, 0x07F1, 0x07F3, 0x0816, 0x0817,
+    0x0818, 0x0819, 0x081B, 0x081C, 0x081D, 0x081E, 0x081F, 0x0820, 0x0821, 0x0822,
+    0x0823, 0x0825, 0x0826, 0x0827, 0x0829, 0x082A, 0x082B, 0x082C, 0x082D, 0x0951,
+    0x0953, 0x0954, 0x0F82, 0x0F83, 0x0F86, 0x0F87, 0x135D, 0x135E, 0x135F, 0x17DD,
+    0x193A, 0x1A17, 0x1A75, 0x1A76, 0x1A77, 0x1A78, 0x1A79, 0x1A7A, 0x1A7B, 0x1A7C,
+    0x1B6B, 0x1B6D, 0x1B6E, 0x1B6F, 0x1B70, 0x1B71, 0x1B72, 0x1B73, 0x1CD0, 0x1CD1,
+    0x1CD2, 0x1CDA, 0x1CDB, 0x1CE0, 0x1DC0, 0x1DC1, 0x1DC3, 0x1DC4, 0x1DC5, 0x1DC6,
+    0x1DC7, 0x1DC8, 0x1DC9, 0x1DCB, 0x1DCC, 0x1DD1, 0x1DD2, 0x1DD3, 0x1DD4, 0x1DD5,
+    0x1DD6, 0x1DD7, 0x1DD8, 0x1DD9, 0x1DDA, 0x1DDB, 0x1DDC, 0x1DDD, 0x1DDE, 0x1DDF,
+    0x1DE0, 0x1DE1, 0x1DE2, 0x1DE3, 0x1DE4, 0x1DE5, 0x1DE6, 0x1DFE, 0x20D0, 0x20D1,
+    0x20D4, 0x20D5, 0x20D6, 0x20D7, 0x20DB, 0x20DC, 0x20E1, 0x20E7, 0x20E9, 0x20F0,
+    0x2CEF, 0x2CF0, 0x2CF1, 0x2DE0, 0x2DE1, 0x2DE2, 0x2DE3, 0x2DE4, 0x2DE5, 0x2DE6,
+    0x2DE7, 0x2DE8, 0x2DE9, 0x2DEA, 0x2DEB, 0x2DEC, 0x2DED, 0x2DEE, 0x2DEF, 0x2DF0,
+    0x2DF1, 0x2DF2, 0x2DF3, 0x2DF4, 0x2DF5, 0x2DF6, 0x2DF7, 0x2DF8, 0x2DF9, 0x2DFA,
+    0x2DFB, 0x2DFC, 0x2DFD, 0x2DFE, 0x2DFF, 0xA66F, 0xA67C, 0xA67D, 0xA6F0, 0xA6F1,
+    0xA8E0, 0xA8E1, 0xA8E2, 0xA8E3, 0xA8E4, 0xA8E5, 0xA8E6, 0xA8E7, 0xA8E8, 0xA8E9,
+    0xA8EA, 0xA8EB, 0xA8EC, 0xA8ED, 0xA8EE, 0xA8EF, 0xA8F0, 0xA8F1, 0xAAB0, 0xAAB2,
+    0xAAB3, 0xAAB7, 0xAAB8, 0xAABE, 0xAABF, 0xAAC1, 0xFE20, 0xFE21, 0xFE22, 0xFE23,
+    0xFE24, 0xFE25, 0xFE26, 0x10A0F, 0x10A38, 0x1D185, 0x1D186, 0x1D187, 0x1D188,
+    0x1D189, 0x1D1AA, 0x1D1AB, 0x1D1AC, 0x1D1AD, 0x1D242, 0x1D243, 0x1D244,
+];
+
+/// Number encoded by a diacritic following a placeholder.
+#[inline]
+pub fn diacritic_value(c: char) -> Option<u32> {
+    DIACRITICS
+        .binary_search(&(c as u32))
+        .ok()
+        .map(|index| index as u32)
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// `a=t`, store the image.
+    #[default]
+    Transmit,
+    /// `a=T`, store the image and display it at the cursor.
+    TransmitAndDisplay,
+    /// `a=q`, check if the image could be loaded without storing it.
+    Query,
+    /// `a=p`, display a stored image.
+    Place,
+    /// `a=d`, delete placements and images.
+    Delete,
+    /// Animation and composition actions, not supported.
+    Other(u8),
+}
+
+/// Where the image data comes from, `t` in the control data.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Medium {
+    /// Inside the escape sequence itself.
+    #[default]
+    Direct,
+    /// A file path.
+    File,
+    /// A temporary file path, deleted once read.
+    TemporaryFile,
+    /// A POSIX shared memory object, unlinked once read.
+    SharedMemory,
+}
+
+/// A parsed graphics command, keys that Rio doesn't use are ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Command {
+    pub action: Action,
+    /// `q`, 1 hides `OK` responses and 2 hides errors too.
+    pub quiet: u8,
+    /// `f`, 24 for RGB, 32 for RGBA and 100 for PNG.
+    pub format: u32,
+    pub medium: Medium,
+    /// `o=z`, the data is zlib compressed.
+    pub compressed: bool,
+    /// `s` and `v`, size in pixels for RGB and RGBA data.
+    pub width: u32,
+    pub height: u32,
+    /// `S` and `O`, bytes to read from a file or shared memory.
+    pub size: usize,
+    pub offset: usize,
+    /// `i`, chosen by the client.
+    pub image_id: u32,
+    /// `I`, the terminal picks the image id and reports it back.
+    pub image_number: u32,
+    /// `p`
+    pub placement_id: u32,
+    /// `m=1`, more chunks follow.
+    pub more: bool,
+    /// `x`, `y`, `w` and `h`, the part of the image to display.
+    pub source_x: u32,
+    pub source_y: u32,
+    pub source_width: u32,
+    pub source_height: u32,
+    /// `c` and `r`, cells the image is scaled to.
+    pub columns: u32,
+    pub rows: u32,
+    /// `C=1`, leave the cursor where it is.
+    pub hold_cursor: bool,
+    /// `U=1`, a virtual placement displayed through Unicode placeholders.
+    pub virtual_placement: bool,
+    /// `d`, what to delete.
+    pub delete: u8,
+    /// Base64 payload, concatenated across chunks.
+    pub payload: Vec<u8>,
+    /// Part of the payload was dropped, the command was longer than the
+    /// parser buffer or the chunks went over [`MAX_PAYLOAD_BYTES`].
+    pub truncated: bool,
+}
+
+impl Default for Command {
+    fn default() -> Command {
+        Command {
+            action: Action::default(),
+            quiet: 0,
+            format: 32,
+            medium: Medium::default(),
+            compressed: false,
+            width: 0,
+            height: 0,
+            size: 0,
+            offset: 0,
+            image_id: 0,
+            image_number: 0,
+            placement_id: 0,
+            more: false,
+            source_x: 0,
+            source_y: 0,
+            source_width: 0,
+            source_height: 0,
+            columns: 0,
+            rows: 0,
+            hold_cursor: false,
+            virtual_placement: false,
+            delete: b'a',
+            payload: Vec::new(),
+            truncated: false,
+        }
+    }
+}
+
+/// Error reported back to the client, as `CODE:message`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    code: &'static str,
+    message: String,
+}
+
+impl Error {
+    pub fn invalid(message: impl Into<String>) -> Error {
+        Error {
+            code: "EINVAL",
+            message: message.into(),
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Error {
+        Error {
+            code: "ENOENT",
+            message: message.into(),
+        }
+    }
+
+    fn bad_file(message: impl Into<String>) -> Error {
+        Error {
+            code: "EBADF",
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.code, self.message)
+    }
+}
+
+/// Parse an APC string, returns `None` if it is not a graphics command.
+///
+/// `truncated` tells the string was cut by the parser, the command is then
+/// rejected once its last chunk arrives.
+pub fn parse(data: &[u8], truncated: bool) -> Option<Command> {
+    let data = data.strip_prefix(b"G")?;
+    let (control, payload) = match data.iter().position(|byte| *byte == b';') {
+        Some(index) => (&data[..index], &data[index + 1..]),
+        None => (data, &[][..]),
+    };
+
+    let mut command = Command {
+        payload: payload.to_vec(),
+        truncated,
+        ..Command::default()
+    };
+
+    for pair in control.split(|byte| *byte == b',') {
+        let [key, b'=', value @ ..] = pair else {
+            continue;
+        };
+
+        let number = || {
+            std::str::from_utf8(value)
+                .ok()
+                .and_then(|value| value.parse::<u32>().ok())
+        };
+        let letter = value.first().copied().unwrap_or_default();
+
+        match key {
+            b'a' => {
+                command.action = match letter {
+                    b't' => Action::Transmit,
+                    b'T' => Action::TransmitAndDisplay,
+                    b'q' => Action::Query,
+                    b'p' => Action::Place,
+                    b'd' => Action::Delete,
+                    other => Action::Other(other),
+                }
+            }
+            b't' => {
+                command.medium = match letter {
+                    b'f' => Medium::File,
+                    b't' => Medium::TemporaryFile,
+                    b's' => Medium::SharedMemory,
+                    _ => Medium::Direct,
+                }
+            }
+            b'o' => command.compressed = letter == b'z',
+            b'd' => command.delete = letter,
+            b'q' => command.quiet = number()? as u8,
+            b'f' => command.format = number()?,
+            b's' => command.width = number()?,
+            b'v' => command.height = number()?,
+            b'S' => command.size = number()? as usize,
+            b'O' => command.offset = number()? as usize,
+            b'i' => command.image_id = number()?,
+            b'I' => command.image_number = number()?,
+            b'p' => command.placement_id = number()?,
+            b'm' => command.more = number()? == 1,
+            b'x' => command.source_x = number()?,
+            b'y' => command.source_y = number()?,
+            b'w' => command.source_width = number()?,
+            b'h' => command.source_height = number()?,
+            b'c' => command.columns = number()?,
+            b'r' => command.rows = number()?,
+            b'C' => command.hold_cursor = number()? == 1,
+            b'U' => command.virtual_placement = number()? == 1,
+            _ => (),
+        }
+    }
+
+    Some(command)
+}
+
+/// Response to `command` for the client, if it asked for one.
+pub fn response(
+    command: &Command,
+    image_id: u32,
+    result: &Result<(), Error>,
+) -> Option<String> {
+    if command.image_id == 0 && command.image_number == 0 {
+        return None;
+    }
+
+    let message = match result {
+        Ok(()) if command.quiet == 0 => String::from("OK"),
+        Err(error) if command.quiet < 2 => error.to_string(),
+        _ => return None,
+    };
+
+    let mut keys = format!("i={image_id}");
+    if command.image_number != 0 {
+        keys.push_str(&format!(",I={}", command.image_number));
+    }
+    if command.placement_id != 0 {
+        keys.push_str(&format!(",p={}", command.placement_id));
+    }
+
+    Some(format!("\x1b_G{keys};{message}\x1b\\"))
+}
+
+#[derive(Debug, Clone)]
+struct Image {
+    data: GraphicData,
+    number: u32,
+    /// Insertion order, the oldest images are dropped first.
+    age: u64,
+}
+
+/// A displayed image, to find its cells when it gets deleted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placement {
+    pub image_id: u32,
+    pub placement_id: u32,
+    pub graphic_id: GraphicId,
+    /// Set for virtual placements, only displayed by placeholders.
+    pub virtual_cells: Option<VirtualCells>,
+}
+
+/// Image of a virtual placement, scaled to `columns` by `rows` cells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VirtualCells {
+    pub texture: Arc<TextureRef>,
+    pub columns: u32,
+    pub rows: u32,
+    pub cell_width: u16,
+    pub cell_height: u16,
+}
+
+/// Images transmitted by the client and where they are displayed.
+#[derive(Debug, Default, Clone)]
+pub struct KittyGraphicsState {
+    images: HashMap<u32, Image>,
+    placements: Vec<Placement>,
+    /// Command of a chunked transmission until the last chunk arrives.
+    loading: Option<Command>,
+    age: u64,
+}
+
+impl KittyGraphicsState {
+    /// Join chunked transmissions, returns the command once complete.
+    pub fn chunk(&mut self, command: Command) -> Option<Command> {
+        match self.loading.as_mut() {
+            Some(loading) => {
+                if command.truncated
+                    || loading.payload.len() + command.payload.len() > MAX_PAYLOAD_BYTES
+                {
+                    // Keep reading the chunks to stay in sync with the
+                    // client, but don't hold on to data that gets rejected.
+                    loading.truncated = true;
+                    loading.payload = Vec::new();
+                } else if !loading.truncated {
+                    loading.payload.extend_from_slice(&command.payload);
+                }
+                if command.more {
+                    None
+                } else {
+                    self.loading.take()
+                }
+            }
+            None if command.more => {
+                self.loading = Some(command);
+                None
+            }
+            None => Some(command),
+        }
+    }
+
+    /// Store an image, returning its id. Images sent with a number instead
+    /// of an id get the next free one.
+    pub fn store(&mut self, command: &Command, data: GraphicData) -> u32 {
+        let image_id = if command.image_id != 0 {
+            command.image_id
+        } else {
+            (1..=u32::MAX)
+                .rev()
+                .find(|id| !self.images.contains_key(id))
+                .unwrap_or(u32::MAX)
+        };
+
+        self.age += 1;
+        self.images.insert(
+            image_id,
+            Image {
+                data,
+                number: command.image_number,
+                age: self.age,
+            },
+        );
+
+        let mut used: usize = self
+            .images
+            .values()
+            .map(|image| image.data.pixels.len())
+            .sum();
+        while used > STORAGE_QUOTA {
+            let Some(oldest) = self
+                .images
+                .iter()
+                .filter(|(id, _)| **id != image_id)
+                .min_by_key(|(_, image)| image.age)
+                .map(|(id, _)| *id)
+            else {
+                break;
+            };
+            if let Some(image) = self.images.remove(&oldest) {
+                used -= image.data.pixels.len();
+            }
+        }
+
+        image_id
+    }
+
+    /// Image id used by `command`, looking up its number if it has no id.
+    pub fn image_id(&self, command: &Command) -> Option<u32> {
+        if command.image_id != 0 || command.image_number == 0 {
+            return Some(command.image_id);
+        }
+
+        self.images
+            .iter()
+            .filter(|(_, image)| image.number == command.image_number)
+            .max_by_key(|(_, image)| image.age)
+            .map(|(id, _)| *id)
+    }
+
+    #[inline]
+    pub fn image(&self, image_id: u32) -> Option<&GraphicData> {
+        self.images.get(&image_id).map(|image| &image.data)
+    }
+
+    #[inline]
+    pub fn remove_image(&mut self, image_id: u32) {
+        self.images.remove(&image_id);
+    }
+
+    #[inline]
+    pub fn is_placed(&self, image_id: u32) -> bool {
+        self.placements
+            .iter()
+            .any(|placement| placement.image_id == image_id)
+    }
+
+    /// Remember a placement, replacing the one with the same ids.
+    pub fn add_placement(&mut self, placement: Placement) -> Option<Placement> {
+        let replaced = if placement.placement_id != 0 {
+            self.placements
+                .iter()
+                .position(|old| {
+                    old.image_id == placement.image_id
+                        && old.placement_id == placement.placement_id
+                })
+                .map(|index| self.placements.remove(index))
+        } else {
+            None
+        };
+
+        if self.placements.len() >= MAX_PLACEMENTS {
+            self.placements.remove(0);
+        }
+        self.placements.push(placement);
+
+        replaced
+    }
+
+    /// Virtual placement of `image_id` shown by placeholders, the latest
+    /// one when `placement_id` is 0.
+    pub fn virtual_placement(
+        &self,
+        image_id: u32,
+        placement_id: u32,
+    ) -> Option<&VirtualCells> {
+        self.placements
+            .iter()
+            .rev()
+            .filter(|placement| {
+                placement.image_id == image_id
+                    && (placement_id == 0 || placement.placement_id == placement_id)
+            })
+            .find_map(|placement| placement.virtual_cells.as_ref())
+    }
+
+    /// Placement drawn with the graphic `graphic_id`.
+    pub fn placement_of(&self, graphic_id: GraphicId) -> Option<&Placement> {
+        self.placements
+            .iter()
+            .find(|placement| placement.graphic_id == graphic_id)
+    }
+
+    /// Forget the placements matching `filter`, returning them.
+    pub fn remove_placements(
+        &mut self,
+        filter: impl Fn(&Placement) -> bool,
+    ) -> Vec<Placement> {
+        let (removed, kept) = self
+            .placements
+            .drain(..)
+            .partition(|placement| filter(placement));
+        self.placements = kept;
+        removed
+    }
+
+    /// Read and decode the image of a transmission.
+    pub fn load(&self, command: &Command) -> Result<GraphicData, Error> {
+        let payload = general_purpose::STANDARD
+            .decode(&command.payload)
+            .map_err(|_| Error::invalid("payload is not valid base64"))?;
+
+        let mut bytes = match command.medium {
+            Medium::Direct => payload,
+            Medium::File | Medium::TemporaryFile => {
+                let path = String::from_utf8(payload)
+                    .map_err(|_| Error::invalid("path is not valid UTF-8"))?;
+                read_file(&path, command)?
+            }
+            Medium::SharedMemory => {
+                let name = String::from_utf8(payload)
+                    .map_err(|_| Error::invalid("name is not valid UTF-8"))?;
+                read_shared_memory(&name, command)?
+            }
+        };
+
+        if command.compressed {
+            bytes = miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(
+                &bytes,
+                MAX_IMAGE_BYTES,
+            )
+            .map_err(|_| Error::invalid("zlib decompression failed"))?;
+        }
+
+        decode(command, bytes)
+    }
+}
+
+fn decode(command: &Command, bytes: Vec<u8>) -> Result<GraphicData, Error> {
+    // The renderer uploads RGBA textures, RGB data is expanded.
+    let (width, height, pixels) = match command.format {
+        24 | 32 => {
+            let (width, height) = (command.width as usize, command.height as usize);
+            let channels = if command.format == 24 { 3 } else { 4 };
+            if width == 0 || height == 0 {
+                return Err(Error::invalid("missing image size"));
+            }
+            if bytes.len() < width * height * channels {
+                return Err(Error::invalid("not enough pixel data"));
+            }
+            let mut pixels = bytes;
+            pixels.truncate(width * height * channels);
+            if channels == 3 {
+                pixels = pixels
+                    .chunks_exact(3)
+                    .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 255])
+                    .collect();
+            }
+            (width, height, pixels)
+        }
+        100 => {
+            let image = image_rs::load_from_memory_with_format(
+                &bytes,
+                image_rs::ImageFormat::Png,
+            )
+            .map_err(|_| Error::invalid("PNG decoding failed"))?
+            .into_rgba8();
+            let (width, height) = (image.width() as usize, image.height() as usize);
+            (width, height, image.into_raw())
+        }
+        format => return Err(Error::invalid(format!("unknown format {format}"))),
+    };
+
+    if width > MAX_GRAPHIC_DIMENSIONS[0] || height > MAX_GRAPHIC_DIMENSIONS[1] {
+        return Err(Error::invalid("image is too large"));
+    }
+
+    let is_opaque = pixels.chunks_exact(4).all(|pixel| pixel[3] == 255);
+
+    Ok(GraphicData {
+        id: GraphicId(0),
+        width,
+        height,
+        color_type: ColorType::Rgba,
+        pixels,
+        is_opaque,
+    })
+}
+
+/// Crop `data` to the source rectangle of a placement and scale it to the
+/// cells it asks for. A missing width or height keeps the aspect ratio.
+pub fn fit(
+    data: &GraphicData,
+    command: &Command,
+    cell_width: u32,
+    cell_height: u32,
+) -> Result<GraphicData, Error> {
+    let (width, height) = (data.width as u32, data.height as u32);
+    let x = command.source_x.min(width);
+    let y = command.source_y.min(height);
+    let source_width = match command.source_width {
+        0 => width - x,
+        source_width => source_width.min(width - x),
+    };
+    let source_height = match command.source_height {
+        0 => height - y,
+        source_height => source_height.min(height - y),
+    };
+    if source_width == 0 || source_height == 0 {
+        return Err(Error::invalid("source rectangle is empty"));
+    }
+
+    // Sizes come from the client, anything overflowing is too large anyway.
+    let too_large = || Error::invalid("placement is too large");
+    let (target_width, target_height) = match (command.columns, command.rows) {
+        (0, 0) => (source_width, source_height),
+        (columns, 0) => {
+            let target_width = columns.checked_mul(cell_width).ok_or_else(too_large)?;
+            let target_height = source_height
+                .checked_mul(target_width)
+                .ok_or_else(too_large)?
+                / source_width;
+            (target_width, target_height)
+        }
+        (0, rows) => {
+            let target_height = rows.checked_mul(cell_height).ok_or_else(too_large)?;
+            let target_width = source_width
+                .checked_mul(target_height)
+                .ok_or_else(too_large)?
+                / source_height;
+            (target_width, target_height)
+        }
+        (columns, rows) => (
+            columns.checked_mul(cell_width).ok_or_else(too_large)?,
+            rows.checked_mul(cell_height).ok_or_else(too_large)?,
+        ),
+    };
+
+    if (x, y, source_width, source_height) == (0, 0, width, height)
+        && (target_width, target_height) == (width, height)
+    {
+        return Ok(data.clone());
+    }
+
+    if target_width == 0
+        || target_height == 0
+        || target_width as usize > MAX_GRAPHIC_DIMENSIONS[0]
+        || target_height as usize > MAX_GRAPHIC_DIMENSIONS[1]
+    {
+        return Err(too_large());
+    }
+
+    let image = image_rs::RgbaImage::from_raw(width, height, data.pixels.clone())
+        .ok_or_else(|| Error::invalid("not enough pixel data"))?;
+    let pixels = image_rs::DynamicImage::ImageRgba8(image)
+        .crop_imm(x, y, source_width, source_height)
+        .resize_exact(
+            target_width,
+            target_height,
+            image_rs::imageops::FilterType::Triangle,
+        )
+        .into_rgba8()
+        .into_raw();
+
+    Ok(GraphicData {
+        id: data.id,
+        width: target_width as usize,
+        height: target_height as usize,
+        color_type: data.color_type,
+        pixels,
+        is_opaque: data.is_opaque,
+    })
+}
+
+/// Bytes to read from data of `len` bytes, following the offset and size
+/// of the command. A zero size reads until the end.
+fn read_range(len: usize, command: &Command) -> Result<(usize, usize), Error> {
+    if command.offset > len {
+        return Err(Error::invalid("offset is past the end of the data"));
+    }
+    let available = len - command.offset;
+    let size = match command.size {
+        0 => available,
+        size => size.min(available),
+    };
+    if size > MAX_IMAGE_BYTES {
+        return Err(Error::invalid("data is too large"));
+    }
+    Ok((command.offset, size))
+}
+
+/// Temporary files must look like they were made for this protocol, so
+/// applications can't make Rio delete arbitrary files.
+fn is_temporary_file(path: &std::path::Path) -> bool {
+    let in_temp_dir = [std::env::temp_dir(), "/tmp".into(), "/dev/shm".into()]
+        .iter()
+        .filter_map(|dir| dir.canonicalize().ok())
+        .any(|dir| path.starts_with(dir));
+
+    in_temp_dir
+        && path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().contains("tty-graphics-protocol"))
+}
+
+fn read_file(path: &str, command: &Command) -> Result<Vec<u8>, Error> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    // Symlinks and `..` are resolved first, the checks apply to the file
+    // that actually gets read.
+    let path = std::path::Path::new(path)
+        .canonicalize()
+        .map_err(|error| Error::bad_file(format!("{path}: {error}")))?;
+
+    let is_temporary = command.medium == Medium::TemporaryFile;
+    if is_temporary && !is_temporary_file(&path) {
+        return Err(Error::invalid("not a temporary file for graphics"));
+    }
+
+    let bad_file =
+        |error: std::io::Error| Error::bad_file(format!("{}: {error}", path.display()));
+    let mut file = std::fs::File::open(&path).map_err(bad_file)?;
+    let metadata = file.metadata().map_err(bad_file)?;
+    if !metadata.is_file() {
+        return Err(Error::bad_file(format!(
+            "{} is not a regular file",
+            path.display()
+        )));
+    }
+
+    let (offset, size) = read_range(metadata.len() as usize, command)?;
+    let mut bytes = Vec::with_capacity(size);
+    file.seek(SeekFrom::Start(offset as u64))
+        .and_then(|_| file.take(size as u64).read_to_end(&mut bytes))
+        .map_err(bad_file)?;
+
+    if is_temporary {
+        let _ = std::fs::remove_file(&path);
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(unix)]
+fn read_shared_memory(name: &str, command: &Command) -> Result<Vec<u8>, Error> {
+    let c_name = std::ffi::CString::new(name)
+        .map_err(|_| Error::invalid("shared memory name contains a nul byte"))?;
+
+    unsafe {
+        let fd = libc::shm_open(c_name.as_ptr(), libc::O_RDONLY, 0);
+        if fd < 0 {
+            return Err(Error::bad_file(format!("can't open shared memory {name}")));
+        }
+
+        let mut stat: libc::stat = std::mem::zeroed();
+        let len = if libc::fstat(fd, &mut stat) == 0 {
+            stat.st_size as usize
+        } else {
+            0
+        };
+
+        let range = read_range(len, command);
+        let ptr = match range {
+            Ok(_) if len > 0 => libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_SHARED,
+                fd,
+                0,
+            ),
+            _ => libc::MAP_FAILED,
+        };
+        libc::close(fd);
+        libc::shm_unlink(c_name.as_ptr());
+
+        let (offset, size) = range?;
+        if ptr == libc::MAP_FAILED {
+            return Err(Error::bad_file(format!("can't read shared memory {name}")));
+        }
+
+        let bytes =
+            std::slice::from_raw_parts((ptr as *const u8).add(offset), size).to_vec();
+        libc::munmap(ptr, len);
+        Ok(bytes)
+    }
+}
+
+#[cfg(not(unix))]
+fn read_shared_memory(_name: &str, _command: &Command) -> Result<Vec<u8>, Error> {
+    Err(Error::invalid("shared memory is not supported"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_command() {
+        let command = parse(b"Ga=T,f=24,s=2,v=1,i=7,q=1,C=1,m=1;AAAA", false).unwrap();
+        assert_eq!(command.action, Action::TransmitAndDisplay);
+        assert_eq!(command.format, 24);
+        assert_eq!((command.width, command.height), (2, 1));
+        assert_eq!(command.image_id, 7);
+        assert_eq!(command.quiet, 1);
+        assert!(command.hold_cursor);
+        assert!(command.more);
+        assert_eq!(command.payload, b"AAAA");
+
+        let command = parse(b"Ga=d,d=I,i=3", false).unwrap();
+        assert_eq!(command.action, Action::Delete);
+        assert_eq!(command.delete, b'I');
+        assert!(command.payload.is_empty());
+
+        // Not a graphics command, or numbers that don't parse.
+        assert_eq!(parse(b"Xa=t", false), None);
+        assert_eq!(parse(b"Gi=abc", false), None);
+    }
+
+    #[test]
+    fn placeholder_diacritics() {
+        assert_eq!(diacritic_value('\u{305}'), Some(0));
+        assert_eq!(diacritic_value('\u{30D}'), Some(1));
+        assert_eq!(diacritic_value('\u{1D244}'), Some(296));
+        assert_eq!(diacritic_value('\u{300}'), None);
+        assert_eq!(diacritic_value('a'), None);
+    }
+
+    #[test]
+    fn chunked_transmission() {
+        let mut state = KittyGraphicsState::default();
+        assert_eq!(
+            state.chunk(parse(b"Gf=32,s=1,v=1,i=1,m=1;AAAA", false).unwrap()),
+            None
+        );
+        assert_eq!(state.chunk(parse(b"Gm=1;AAAA", false).unwrap()), None);
+
+        let command = state.chunk(parse(b"Gm=0;AA==", false).unwrap()).unwrap();
+        assert_eq!(command.image_id, 1);
+        assert_eq!(command.payload, b"AAAAAAAAAA==");
+
+        // Raw RGBA data, the extra bytes are dropped.
+        let data = state.load(&command).unwrap();
+        assert_eq!((data.width, data.height), (1, 1));
+        assert_eq!(data.pixels, [0, 0, 0, 0]);
+        assert!(!data.is_opaque);
+    }
+
+    #[test]
+    fn truncated_chunks_are_dropped() {
+        let mut state = KittyGraphicsState::default();
+        assert_eq!(
+            state.chunk(parse(b"Gf=32,s=1,v=1,i=1,m=1;AAAA", false).unwrap()),
+            None
+        );
+        assert_eq!(state.chunk(parse(b"Gm=1;AAAA", true).unwrap()), None);
+
+        let command = state.chunk(parse(b"Gm=0;AA==", false).unwrap()).unwrap();
+        assert!(command.truncated);
+        assert!(command.payload.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_files() {
+        let dir = std::env::temp_dir().join(format!("rio-kitty-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("image.rgba");
+        std::fs::write(&file, [1u8, 2, 3, 4, 5, 6]).unwrap();
+
+        let command = Command {
+            medium: Medium::File,
+            offset: 1,
+            size: 4,
+            ..Command::default()
+        };
+        assert_eq!(
+            read_file(file.to_str().unwrap(), &command).unwrap(),
+            [2, 3, 4, 5]
+        );
+
+        // Only regular files are read.
+        let error = read_file("/dev/null", &command).unwrap_err();
+        assert_eq!(error.code, "EBADF");
+
+        // Temporary files need the protocol marker in their own name, a
+        // directory or symlink with it doesn't count.
+        let command = Command {
+            medium: Medium::TemporaryFile,
+            ..Command::default()
+        };
+        let link = dir.join("tty-graphics-protocol-link");
+        std::os::unix::fs::symlink(&file, &link).unwrap();
+        assert_eq!(
+            read_file(link.to_str().unwrap(), &command)
+                .unwrap_err()
+                .to_string(),
+            "EINVAL:not a temporary file for graphics"
+        );
+        assert!(file.exists());
+
+        let temporary = dir.join("tty-graphics-protocol-image");
+        std::fs::write(&temporary, [7u8]).unwrap();
+        assert_eq!(
+            read_file(temporary.to_str().unwrap(), &command).unwrap(),
+            [7]
+        );
+        assert!(!temporary.exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn load_compressed_and_png() {
+        let state = KittyGraphicsState::default();
+
+        let rgb = [255u8, 0, 0, 0, 255, 0];
+        let compressed = miniz_oxide::deflate::compress_to_vec_zlib(&rgb, 6);
+        let command = Command {
+            format: 24,
+            width: 2,
+            height: 1,
+            compressed: true,
+            payload: general_purpose::STANDARD.encode(compressed).into_bytes(),
+            ..Command::default()
+        };
+        let data = state.load(&command).unwrap();
+        assert_eq!(data.pixels, [255, 0, 0, 255, 0, 255, 0, 255]);
+        assert!(data.is_opaque);
+
+        let mut png = Vec::new();
+        image_rs::RgbaImage::from_raw(1, 2, vec![1, 2, 3, 255, 4, 5, 6, 255])
+            .unwrap()
+            .write_to(
+                &mut std::io::Cursor::new(&mut png),
+                image_rs::ImageFormat::Png,
+            )
+            .unwrap();
+        let command = Command {
+            format: 100,
+            payload: general_purpose::STANDARD.encode(png).into_bytes(),
+            ..Command::default()
+        };
+        let data = state.load(&command).unwrap();
+        assert_eq!((data.width, data.height), (1, 2));
+        assert_eq!(data.pixels, [1, 2, 3, 255, 4, 5, 6, 255]);
+
+        let command = Command {
+            format: 32,
+            width: 4,
+            height: 4,
+            payload: b"AAAA".to_vec(),
+            ..Command::default()
+        };
+        assert_eq!(
+            state.load(&command).unwrap_err().to_string(),
+            "EINVAL:not enough pixel data"
+        );
+    }
+
+    #[test]
+    fn fit_placements() {
+        let data = GraphicData {
+            id: GraphicId(0),
+            width: 4,
+            height: 2,
+            color_type: ColorType::Rgba,
+            pixels: vec![255; 4 * 2 * 4],
+            is_opaque: true,
+        };
+
+        // Only the columns are given, the height keeps the aspect ratio.
+        let command = Command {
+            columns: 2,
+            ..Command::default()
+        };
+        let graphic = fit(&data, &command, 4, 8).unwrap();
+        assert_eq!((graphic.width, graphic.height), (8, 4));
+
+        // Sizes from the client that overflow are rejected, not wrapped.
+        for command in [
+            Command {
+                columns: 500_000_000,
+                ..Command::default()
+            },
+            Command {
+                rows: 500_000_000,
+                ..Command::default()
+            },
+            Command {
+                columns: 500_000_000,
+                rows: 1,
+                ..Command::default()
+            },
+            // The height fits but not the width scaled to it.
+            Command {
+                rows: 200_000_000,
+                ..Command::default()
+            },
+        ] {
+            assert_eq!(
+                fit(&data, &command, 10, 20).unwrap_err().to_string(),
+                "EINVAL:placement is too large"
+            );
+        }
+    }
+
+    #[test]
+    fn image_numbers_and_responses() {
+        let mut state = KittyGraphicsState::default();
+        let command = parse(b"Ga=t,I=13,f=32,s=1,v=1;AAAAAA==", false).unwrap();
+        let data = state.load(&command).unwrap();
+        let image_id = state.store(&command, data);
+        assert_ne!(image_id, 0);
+        assert_eq!(
+            state.image_id(&parse(b"Ga=p,I=13", false).unwrap()),
+            Some(image_id)
+        );
+        assert_eq!(
+            response(&command, image_id, &Ok(())).unwrap(),
+            format!("\x1b_Gi={image_id},I=13;OK\x1b\\")
+        );
+
+        let command = parse(b"Ga=p,i=5,p=2", false).unwrap();
+        assert_eq!(
+            response(&command, 5, &Err(Error::not_found("no such image"))).unwrap(),
+            "\x1b_Gi=5,p=2;ENOENT:no such image\x1b\\"
+        );
+
+        // Quiet and anonymous commands get no response.
+        let command = parse(b"Ga=t,i=5,q=1", false).unwrap();
+        assert_eq!(response(&command, 5, &Ok(())), None);
+        let command = parse(b"Ga=t,q=0", false).unwrap();
+        assert_eq!(response(&command, 0, &Err(Error::invalid("x"))), None);
+    }
+}
@@ -3,6 +3,7 @@ use bitflags::bitflags;
 pub mod charset;
 pub mod control;
 pub mod graphics;
+pub mod kitty_graphics;
 pub mod mode;
 pub mod sixel;
 
@@ -57,6 +58,19 @@ pub enum LineClearMode {
     All,
 }
 
+/// Shell integration marks sent with OSC 133.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SemanticPrompt {
+    /// `A`, start of the prompt.
+    PromptStart,
+    /// `B`, end of the prompt, the shell is ready for input.
+    CommandStart,
+    /// `C`, the command was submitted and its output starts.
+    CommandExecuted,
+    /// `D`, the command finished.
+    CommandFinished,
+}
+
 bitflags! {
     /// A set of [`kitty keyboard protocol'] modes.
     ///
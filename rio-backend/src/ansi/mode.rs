@@ -27,15 +27,32 @@ pub enum Mode {
     Origin = 6,
     /// ?7
     LineWrap = 7,
+    /// ?9
+    ///
+    /// X10 compatibility mouse, only button presses are reported.
+    X10Mouse = 9,
     /// ?12
     BlinkingCursor = 12,
-    /// 20
+    /// ?19
+    ///
+    /// Print form feed (DECPFF), only affects printing so it's accepted
+    /// and ignored.
+    PrintFormFeed = 19,
+    /// 20 (LNM)
     ///
-    /// NB This is actually a private mode. We should consider adding a second
-    /// enumeration for public/private modesets.
+    /// LF, VT and FF also perform a carriage return. Accepted both as
+    /// ANSI mode and as `?20`.
     LineFeedNewLine = 20,
     /// ?25
     ShowCursor = 25,
+    /// ?30
+    ///
+    /// Shows a scrollbar along the right edge of the pane.
+    ShowScrollbar = 30,
+    /// ?67 (DECBKM)
+    ///
+    /// The backarrow key sends BS (`\x08`) when set, DEL (`\x7f`) when reset.
+    BackarrowKey = 67,
     /// ?80
     SixelDisplay = 80,
     /// ?1000
@@ -52,6 +69,10 @@ pub enum Mode {
     SgrMouse = 1006,
     /// ?1007
     AlternateScroll = 1007,
+    /// ?1036
+    MetaSendsEscape = 1036,
+    /// ?1039
+    AltSendsEscape = 1039,
     /// ?1042
     UrgencyHints = 1042,
     /// ?1049
@@ -60,6 +81,10 @@ pub enum Mode {
     SixelPrivateColorRegisters = 1070,
     /// ?2004
     BracketedPaste = 2004,
+    /// ?2500, a one-shot query for the pixel size of the grid area.
+    PixelGeometry = 2500,
+    /// ?7786, mouse wheel sends arrow keys in the alternate screen.
+    WheelToArrow = 7786,
     /// Sixel scrolling leaves cursor to right of graphic.
     SixelCursorToTheRight = 8452,
 }
@@ -79,8 +104,13 @@ impl Mode {
                 3 => Mode::Column,
                 6 => Mode::Origin,
                 7 => Mode::LineWrap,
+                9 => Mode::X10Mouse,
                 12 => Mode::BlinkingCursor,
+                19 => Mode::PrintFormFeed,
+                20 => Mode::LineFeedNewLine,
                 25 => Mode::ShowCursor,
+                30 => Mode::ShowScrollbar,
+                67 => Mode::BackarrowKey,
                 80 => Mode::SixelDisplay,
                 1000 => Mode::ReportMouseClicks,
                 1002 => Mode::ReportSquareMouseMotion,
@@ -89,10 +119,14 @@ impl Mode {
                 1005 => Mode::Utf8Mouse,
                 1006 => Mode::SgrMouse,
                 1007 => Mode::AlternateScroll,
+                1036 => Mode::MetaSendsEscape,
+                1039 => Mode::AltSendsEscape,
                 1042 => Mode::UrgencyHints,
                 1049 => Mode::SwapScreenAndSetRestoreCursor,
                 1070 => Mode::SixelPrivateColorRegisters,
                 2004 => Mode::BracketedPaste,
+                2500 => Mode::PixelGeometry,
+                7786 => Mode::WheelToArrow,
                 8452 => Mode::SixelCursorToTheRight,
                 _ => {
                     warn!("[unimplemented] primitive mode: {}", num);
@@ -3,6 +3,8 @@
 
 use log::warn;
 use raw_window_handle::RawDisplayHandle;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ClipboardType {
@@ -19,9 +21,67 @@ use copypasta::x11_clipboard::{Primary as X11SelectionClipboard, X11ClipboardCon
 use copypasta::ClipboardContext;
 use copypasta::ClipboardProvider;
 
+type Provider = Arc<Mutex<Box<dyn ClipboardProvider>>>;
+
+/// Providers are shared with the threads started by `get_async` and with
+/// the writer.
 pub struct Clipboard {
-    clipboard: Box<dyn ClipboardProvider>,
-    selection: Option<Box<dyn ClipboardProvider>>,
+    clipboard: Provider,
+    selection: Option<Provider>,
+    writer: Writer,
+}
+
+/// Writes that found their provider busy, stored in order by a single
+/// thread started with the first of them.
+#[derive(Default)]
+struct Writer {
+    queue: Option<mpsc::Sender<(Provider, String)>>,
+    /// Queued writes not stored yet, later writes queue behind them.
+    pending: Arc<AtomicUsize>,
+}
+
+impl Writer {
+    #[inline]
+    fn is_idle(&self) -> bool {
+        self.pending.load(Ordering::SeqCst) == 0
+    }
+
+    fn queue(&mut self, provider: Provider, text: String) {
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        let write = match &self.queue {
+            Some(queue) => match queue.send((provider, text)) {
+                Ok(()) => return,
+                Err(mpsc::SendError(write)) => write,
+            },
+            None => (provider, text),
+        };
+
+        let (queue, writes) = mpsc::channel::<(Provider, String)>();
+        let pending = Arc::clone(&self.pending);
+        let spawned = std::thread::Builder::new()
+            .name(String::from("clipboard"))
+            .spawn(move || {
+                for (provider, text) in writes {
+                    store(&mut lock(&provider), text);
+                    pending.fetch_sub(1, Ordering::SeqCst);
+                }
+            });
+        match spawned {
+            Ok(_) => {
+                let _ = queue.send(write);
+                self.queue = Some(queue);
+            }
+            Err(err) => {
+                self.pending.fetch_sub(1, Ordering::SeqCst);
+                warn!("Unable to start clipboard thread: {}", err);
+            }
+        }
+    }
+}
+
+#[inline]
+fn provider(provider: impl ClipboardProvider + 'static) -> Provider {
+    Arc::new(Mutex::new(Box::new(provider)))
 }
 
 impl Clipboard {
@@ -35,8 +95,9 @@ impl Clipboard {
                         display.display.as_ptr(),
                     );
                 Self {
-                    clipboard: Box::new(clipboard),
-                    selection: Some(Box::new(selection)),
+                    clipboard: provider(clipboard),
+                    selection: Some(provider(selection)),
+                    writer: Writer::default(),
                 }
             }
             _ => Self::default(),
@@ -47,8 +108,9 @@ impl Clipboard {
     /// feature.
     pub fn new_nop() -> Self {
         Self {
-            clipboard: Box::new(NopClipboardContext::new().unwrap()),
+            clipboard: provider(NopClipboardContext::new().unwrap()),
             selection: None,
+            writer: Writer::default(),
         }
     }
 }
@@ -57,16 +119,18 @@ impl Default for Clipboard {
     fn default() -> Self {
         #[cfg(any(target_os = "macos", windows))]
         return Self {
-            clipboard: Box::new(ClipboardContext::new().unwrap()),
+            clipboard: provider(ClipboardContext::new().unwrap()),
             selection: None,
+            writer: Writer::default(),
         };
 
         #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
         return Self {
-            clipboard: Box::new(ClipboardContext::new().unwrap()),
-            selection: Some(Box::new(
+            clipboard: provider(ClipboardContext::new().unwrap()),
+            selection: Some(provider(
                 X11ClipboardContext::<X11SelectionClipboard>::new().unwrap(),
             )),
+            writer: Writer::default(),
         };
 
         #[cfg(not(any(feature = "x11", target_os = "macos", windows)))]
@@ -75,30 +139,236 @@ impl Default for Clipboard {
 }
 
 impl Clipboard {
+    /// Store `text`, a clipboard busy with a read started by `get_async`
+    /// gets it from the writer thread once the read is done. Writes are
+    /// stored in the order they were made.
     pub fn set(&mut self, ty: ClipboardType, text: impl Into<String>) {
-        let clipboard = match (ty, &mut self.selection) {
+        let clipboard = match (ty, &self.selection) {
             (ClipboardType::Selection, Some(provider)) => provider,
             (ClipboardType::Selection, None) => return,
-            _ => &mut self.clipboard,
+            _ => &self.clipboard,
         };
 
-        clipboard.set_contents(text.into()).unwrap_or_else(|err| {
-            warn!("Unable to store text in clipboard: {}", err);
-        });
+        let text = text.into();
+        if self.writer.is_idle() {
+            if let Some(mut provider) = try_lock(clipboard) {
+                store(&mut provider, text);
+                return;
+            }
+        }
+
+        self.writer.queue(Arc::clone(clipboard), text);
     }
 
-    pub fn get(&mut self, ty: ClipboardType) -> String {
-        let clipboard = match (ty, &mut self.selection) {
+    /// Whether there is a selection clipboard, otherwise it falls back
+    /// to the regular one.
+    #[inline]
+    pub fn has_selection(&self) -> bool {
+        self.selection.is_some()
+    }
+
+    /// Read the clipboard, `None` while a read started by `get_async` is
+    /// still waiting for the clipboard owner.
+    pub fn get(&mut self, ty: ClipboardType) -> Option<String> {
+        try_lock(self.provider(ty)).map(|mut provider| load(&mut provider))
+    }
+
+    /// Same as `get` but the clipboard is read on another thread, so a
+    /// slow clipboard owner doesn't block the caller. `on_load` is called
+    /// from that thread once the text arrives.
+    pub fn get_async<F>(&self, ty: ClipboardType, on_load: F)
+    where
+        F: FnOnce(String) + Send + 'static,
+    {
+        let clipboard = Arc::clone(self.provider(ty));
+        let spawned = std::thread::Builder::new()
+            .name(String::from("clipboard"))
+            .spawn(move || on_load(load(&mut lock(&clipboard))));
+        if let Err(err) = spawned {
+            warn!("Unable to start clipboard thread: {}", err);
+        }
+    }
+
+    #[inline]
+    fn provider(&self, ty: ClipboardType) -> &Provider {
+        match (ty, &self.selection) {
             (ClipboardType::Selection, Some(provider)) => provider,
-            _ => &mut self.clipboard,
+            _ => &self.clipboard,
+        }
+    }
+}
+
+type ProviderGuard<'a> = std::sync::MutexGuard<'a, Box<dyn ClipboardProvider>>;
+
+#[inline]
+fn lock(provider: &Provider) -> ProviderGuard<'_> {
+    // A panic inside a provider doesn't leave it in a state worth refusing.
+    provider.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Lock without waiting, for the UI thread.
+#[inline]
+fn try_lock(provider: &Provider) -> Option<ProviderGuard<'_>> {
+    match provider.try_lock() {
+        Ok(guard) => Some(guard),
+        Err(std::sync::TryLockError::Poisoned(err)) => Some(err.into_inner()),
+        Err(std::sync::TryLockError::WouldBlock) => None,
+    }
+}
+
+fn store(provider: &mut ProviderGuard<'_>, text: String) {
+    provider.set_contents(text).unwrap_or_else(|err| {
+        warn!("Unable to store text in clipboard: {}", err);
+    });
+}
+
+fn load(provider: &mut ProviderGuard<'_>) -> String {
+    match provider.get_contents() {
+        Err(err) => {
+            warn!("Unable to load text from clipboard: {}", err);
+            String::new()
+        }
+        Ok(text) => normalize_text(&text),
+    }
+}
+
+/// The providers already read the plain text flavor as UTF-8, text copied
+/// on Windows or from browsers still ends its lines with `\r\n`. Those
+/// become `\n`, the paste path converts them as needed.
+pub fn normalize_text(text: &str) -> String {
+    text.replace("\r\n", "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_text() {
+        assert_eq!(normalize_text("echo rio"), "echo rio");
+        assert_eq!(normalize_text("a\r\nb\rc\n"), "a\nb\rc\n");
+        // Anything else is pasted as it was copied.
+        assert_eq!(
+            normalize_text("\u{feff}ls\u{a0}-la\u{200b}"),
+            "\u{feff}ls\u{a0}-la\u{200b}"
+        );
+        assert_eq!(normalize_text("x\0y"), "x\0y");
+        assert_eq!(normalize_text("caf\u{e9} \u{1f980}"), "caf\u{e9} \u{1f980}");
+    }
+
+    type ClipResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+    struct Fixed(&'static str);
+
+    impl ClipboardProvider for Fixed {
+        fn get_contents(&mut self) -> ClipResult<String> {
+            Ok(self.0.to_string())
+        }
+
+        fn set_contents(&mut self, _: String) -> ClipResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_get_async() {
+        let clipboard = Clipboard {
+            clipboard: provider(Fixed("a\r\nb")),
+            selection: Some(provider(Fixed("primary"))),
+            writer: Writer::default(),
         };
 
-        match clipboard.get_contents() {
-            Err(err) => {
-                warn!("Unable to load text from clipboard: {}", err);
-                String::new()
-            }
-            Ok(text) => text,
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let on_load = move |text| sender.send(text).unwrap();
+        clipboard.get_async(ClipboardType::Clipboard, on_load.clone());
+        assert_eq!(receiver.recv().unwrap(), "a\nb");
+        clipboard.get_async(ClipboardType::Selection, on_load);
+        assert_eq!(receiver.recv().unwrap(), "primary");
+    }
+
+    /// Hands out its last content once the test lets it, like a slow
+    /// owner, and keeps every content it was given.
+    struct Slow {
+        contents: Arc<Mutex<Vec<String>>>,
+        gate: std::sync::mpsc::Receiver<()>,
+    }
+
+    impl ClipboardProvider for Slow {
+        fn get_contents(&mut self) -> ClipResult<String> {
+            self.gate.recv().unwrap();
+            Ok(self
+                .contents
+                .lock()
+                .unwrap()
+                .last()
+                .cloned()
+                .unwrap_or_default())
+        }
+
+        fn set_contents(&mut self, text: String) -> ClipResult<()> {
+            self.contents.lock().unwrap().push(text);
+            Ok(())
+        }
+    }
+
+    fn slow_clipboard() -> (Clipboard, Arc<Mutex<Vec<String>>>, mpsc::Sender<()>) {
+        let contents = Arc::new(Mutex::new(vec![String::from("old")]));
+        let (gate, waiting) = mpsc::channel();
+        let clipboard = Clipboard {
+            clipboard: provider(Slow {
+                contents: contents.clone(),
+                gate: waiting,
+            }),
+            selection: None,
+            writer: Writer::default(),
+        };
+        (clipboard, contents, gate)
+    }
+
+    #[test]
+    fn test_busy_clipboard_doesnt_block() {
+        let (mut clipboard, contents, gate) = slow_clipboard();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        clipboard.get_async(ClipboardType::Clipboard, move |text| {
+            sender.send(text).unwrap()
+        });
+        // Wait for the read to hold the provider.
+        while clipboard.clipboard.try_lock().is_ok() {
+            std::thread::yield_now();
+        }
+
+        assert_eq!(clipboard.get(ClipboardType::Clipboard), None);
+        clipboard.set(ClipboardType::Clipboard, "new");
+        assert_eq!(*contents.lock().unwrap(), ["old"]);
+
+        gate.send(()).unwrap();
+        assert_eq!(receiver.recv().unwrap(), "old");
+        while contents.lock().unwrap().len() < 2 {
+            std::thread::yield_now();
+        }
+        assert_eq!(*contents.lock().unwrap(), ["old", "new"]);
+    }
+
+    #[test]
+    fn test_busy_clipboard_keeps_write_order() {
+        let (mut clipboard, contents, gate) = slow_clipboard();
+
+        clipboard.get_async(ClipboardType::Clipboard, |_| {});
+        while clipboard.clipboard.try_lock().is_ok() {
+            std::thread::yield_now();
+        }
+
+        for text in ["a", "b", "c"] {
+            clipboard.set(ClipboardType::Clipboard, text);
+        }
+        gate.send(()).unwrap();
+        // The provider may be free again, it still waits for the queue.
+        clipboard.set(ClipboardType::Clipboard, "d");
+
+        while contents.lock().unwrap().len() < 5 {
+            std::thread::yield_now();
         }
+        assert_eq!(*contents.lock().unwrap(), ["old", "a", "b", "c", "d"]);
     }
 }
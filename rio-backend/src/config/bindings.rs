@@ -22,9 +22,14 @@ pub struct KeyBinding {
 
 pub type KeyBindings = Vec<KeyBinding>;
 
+/// `[from, to]` scancode pairs, the key reporting `from` acts as `to`.
+pub type ScancodeRemaps = Vec<[u32; 2]>;
+
 #[derive(Default, Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Bindings {
     pub keys: KeyBindings,
+    #[serde(default = "Vec::default", rename = "scancode-remaps")]
+    pub scancode_remaps: ScancodeRemaps,
 }
 
 #[cfg(test)]
@@ -53,6 +58,19 @@ mod tests {
         assert_eq!(decoded.bindings.keys[0].with.to_owned(), "super");
         assert_eq!(decoded.bindings.keys[0].action.to_owned(), "quit");
         assert!(decoded.bindings.keys[0].text.to_owned().is_empty());
+        assert!(decoded.bindings.scancode_remaps.is_empty());
+    }
+
+    #[test]
+    fn test_scancode_remaps() {
+        let content = r#"
+            [bindings]
+            keys = []
+            scancode-remaps = [[30, 48], [48, 30]]
+        "#;
+
+        let decoded = toml::from_str::<Root>(content).unwrap();
+        assert_eq!(decoded.bindings.scancode_remaps, [[30, 48], [48, 30]]);
     }
 
     #[test]
@@ -22,6 +22,13 @@ pub fn cursor() -> ColorArray {
         .to_arr()
 }
 
+#[inline]
+pub fn hyperlink() -> ColorArray {
+    ColorBuilder::from_hex(String::from("#44C9F0"), Format::SRGB0_1)
+        .unwrap()
+        .to_arr()
+}
+
 #[inline]
 pub fn vi_cursor() -> ColorArray {
     ColorBuilder::from_hex(String::from("#12d0ff"), Format::SRGB0_1)
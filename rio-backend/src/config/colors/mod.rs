@@ -94,6 +94,13 @@ pub struct Colors {
         rename = "tabs-foreground"
     )]
     pub tabs_foreground: ColorArray,
+    /// Background of inactive tabs, `bar` when unset.
+    #[serde(
+        deserialize_with = "deserialize_to_optional_arr",
+        default,
+        rename = "tabs-inactive"
+    )]
+    pub tabs_inactive: Option<ColorArray>,
     #[serde(default = "defaults::cursor", deserialize_with = "deserialize_to_arr")]
     pub cursor: ColorArray,
     #[serde(
@@ -242,6 +249,11 @@ pub struct Colors {
     pub selection_foreground: ColorArray,
     #[serde(default = "defaults::cursor", deserialize_with = "deserialize_to_arr")]
     pub split: ColorArray,
+    #[serde(
+        default = "defaults::hyperlink",
+        deserialize_with = "deserialize_to_arr"
+    )]
+    pub hyperlink: ColorArray,
     #[serde(
         default = "defaults::search_match_background",
         deserialize_with = "deserialize_to_arr",
@@ -283,8 +295,10 @@ impl Default for Colors {
             tabs_active: defaults::tabs_active(),
             tabs_active_foreground: defaults::tabs_active_foreground(),
             tabs_foreground: defaults::tabs_foreground(),
+            tabs_inactive: None,
             cursor: defaults::cursor(),
             split: defaults::cursor(),
+            hyperlink: defaults::hyperlink(),
             vi_cursor: defaults::vi_cursor(),
             black: defaults::black(),
             cyan: defaults::cyan(),
@@ -638,6 +652,15 @@ where
     }
 }
 
+pub fn deserialize_to_optional_arr<'de, D>(
+    deserializer: D,
+) -> Result<Option<ColorArray>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    deserialize_to_arr(deserializer).map(Some)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -10,6 +10,11 @@ pub fn default_line_height() -> f32 {
     1.0
 }
 
+#[inline]
+pub fn default_render_scale() -> f32 {
+    1.0
+}
+
 #[inline]
 pub fn default_padding_y() -> [f32; 2] {
     [0., 0.]
@@ -57,6 +62,11 @@ pub fn default_opacity() -> f32 {
     1.0
 }
 
+#[inline]
+pub fn default_term() -> String {
+    String::from("auto")
+}
+
 #[inline]
 pub fn default_option_as_alt() -> String {
     String::from("None")
@@ -95,6 +105,46 @@ pub fn default_window_height() -> i32 {
     500
 }
 
+#[inline]
+pub fn default_window_min_columns() -> usize {
+    crate::crosswords::MIN_COLUMNS
+}
+
+#[inline]
+pub fn default_window_min_lines() -> usize {
+    crate::crosswords::MIN_LINES
+}
+
+#[inline]
+pub fn default_scrollback_lines() -> usize {
+    crate::crosswords::DEFAULT_SCROLLBACK_LINES
+}
+
+#[inline]
+pub fn default_max_line_length() -> usize {
+    1_000_000
+}
+
+#[inline]
+pub fn default_parse_budget() -> usize {
+    u16::MAX as usize
+}
+
+#[inline]
+pub fn default_motion_report_throttle_ms() -> u64 {
+    16
+}
+
+#[inline]
+pub fn default_startup_delay_ms() -> u64 {
+    1000
+}
+
+#[inline]
+pub fn default_status_line_format() -> String {
+    String::from("{mode}  {cwd}  {time}")
+}
+
 #[inline]
 pub fn default_disable_ctlseqs_alt() -> bool {
     #[cfg(target_os = "macos")]
@@ -129,6 +179,15 @@ blinking-cursor = false
 #
 # hide-cursor-when-typing = false
 
+# Minimum time between two mouse motion reports sent to applications
+# that track the mouse (`DECSET 1002` and `DECSET 1003`), faster
+# motion is held back and only the latest one gets reported. Set it to 0
+# to report every motion.
+#
+# Default is `16`
+#
+# motion-report-throttle-ms = 16
+
 # Ignore theme selection foreground color
 #
 # Default is false
@@ -191,6 +250,14 @@ blinking-cursor = false
 # Example:
 # env-vars = []
 
+# TERM
+#
+# Value of `TERM` for new terminals. With "auto" the terminfo
+# shipped with Rio is used when installed, otherwise "xterm-256color".
+#
+# Example:
+# term = "auto"
+
 # Use fork
 #
 # Defaults for POSIX-based systems (Windows is not configurable):
@@ -205,6 +272,142 @@ blinking-cursor = false
 #
 # confirm-before-quit = false
 
+# Hold paste trailing newline
+#
+# When bracketed paste is enabled and the pasted content ends with
+# a newline, the final newline is held back so the command can be
+# reviewed before running it. Only the last newline is removed.
+# Default is `false`
+#
+# hold-paste-trailing-newline = false
+
+# Confirm multiline paste
+#
+# Before pasting content with more than one line, show a preview
+# of the first lines and wait for a confirmation.
+# `Enter` confirms the paste and `Escape` cancels it.
+# Default is `false`
+#
+# confirm-multiline-paste = false
+
+# Paste expand tabs
+#
+# Replace tabs in pasted content with spaces up to the next tab stop,
+# for programs that mangle pasted tabs.
+# Default is `false`
+#
+# paste-expand-tabs = false
+
+# Right click extends selection
+#
+# A right click extends the current selection up to the clicked
+# cell, or starts a new selection there if there is none.
+# Default is `false`
+#
+# right-click-extends-selection = false
+
+# Selection clipboard
+#
+# Clipboards that receive the text selected with the mouse.
+# • primary - only the primary selection, pasted with the middle click
+# • both - the primary selection and the regular clipboard
+# Without a primary selection (macOS and Windows) the regular
+# clipboard is always used.
+# Default is `primary`
+#
+# selection-clipboard = "primary"
+
+# OSC 52
+#
+# Applications can set the clipboard with OSC 52, which also works
+# over SSH, and ask for its content. Reading is disabled by default
+# since any program running in the terminal could read the clipboard.
+# • allow-osc52-write - let applications set the clipboard
+# • allow-osc52-read - let applications read the clipboard
+#
+# allow-osc52-write = true
+# allow-osc52-read = false
+
+# Scrollback lines
+#
+# Lines of history kept for each terminal, older lines are dropped.
+# The history grows as output arrives, so large values only use
+# memory once they are filled. Use 0 to disable the scrollback.
+# Default is 10000
+#
+# scrollback-lines = 10000
+
+# Max line length
+#
+# Maximum amount of characters a single line can hold before
+# being broken into a new line. It protects against programs
+# printing huge lines without any newline. Use 0 to disable it.
+# Default is 1000000
+#
+# max-line-length = 1000000
+
+# Parse budget
+#
+# Maximum amount of bytes parsed from the shell output before the
+# terminal is handed back to the renderer. Lower values keep Rio
+# responsive under heavy output (e.g. `yes`), the remaining output
+# is parsed right after, nothing is dropped.
+# Default is 65535
+#
+# parse-budget = 65535
+
+# Hyperlink underline style
+#
+# Underline drawn under the hovered hyperlink, in the `hyperlink`
+# color. Options: "regular", "double", "dotted", "dashed" and "curly".
+# Default is "regular"
+#
+# hyperlink-underline-style = "regular"
+
+# URL regex
+#
+# Regex used to find URLs in the terminal content, they are underlined
+# and opened with a click while holding the hyperlink modifier
+# (`alt` on Linux, BSD and Windows, `command` on macOS). URLs can be
+# wrapped over several lines and trailing punctuation is left out.
+# Default matches common schemes such as https://, file: and mailto:
+#
+# url-regex = '(https://|http://)[^\s]+'
+
+# Hyperlink opener
+#
+# Program used to open hyperlinks and URLs instead of the system handler
+# (`open`, `xdg-open` or `start`), the link is given as the last argument.
+# Useful in sandboxed environments.
+#
+# [hyperlink-opener]
+# program = "flatpak-spawn"
+# args = ["--host", "xdg-open"]
+
+# Startup script
+#
+# Text sent to the shell once Rio starts, use `\n` to run it.
+# It is sent when the shell reports its prompt is ready (OSC 133;B),
+# on the first output of the shell or after `startup-delay-ms`,
+# whichever comes first.
+# Default is none and 1000ms of delay
+#
+# startup-script = "source ~/.rio-startup.sh\n"
+# startup-delay-ms = 1000
+
+# Status line
+#
+# A thin line below the grid with mode indicators and information
+# about the current tab, it takes one line from the grid.
+# • status-line - "hidden" or "bottom"
+# • status-line-format - template with the placeholders:
+#   {mode} (VI, SEARCH or SELECT), {title}, {cwd}, {selection}, {time}
+#   and {tabs}
+# Default is hidden and "{mode}  {cwd}  {time}"
+#
+# status-line = "hidden"
+# status-line-format = "{mode}  {cwd}  {time}"
+
 # Editor
 #
 # Default editor is "vi".
@@ -234,6 +437,10 @@ blinking-cursor = false
 #
 # • decorations - Set window decorations, options: "Enabled", "Disabled", "Transparent", "Buttonless"
 #
+# • min-columns / min-lines - Smallest grid, in cells, the window can be
+#   resized to. It follows font size changes.
+#   Default: 2 columns and 1 line
+#
 # Example:
 # [window]
 # width = 600
@@ -242,6 +449,8 @@ blinking-cursor = false
 # opacity = 1.0
 # blur = false
 # decorations = "enabled"
+# min-columns = 2
+# min-lines = 1
 
 # Renderer
 #
@@ -258,6 +467,18 @@ blinking-cursor = false
 #
 # • disable-unfocused-render: This property disable renderer processes while Rio is unfocused.
 #
+# • auto-performance: Use Low performance while running on battery and
+#   High performance while plugged in, overriding the performance property.
+#
+# • low-latency-input: Redraw right away when a key is typed instead of
+#   waiting for the next frame, output keeps being paced by the display.
+#
+# • render-whitespace: Draw faint markers for spaces (·) and tabs (→)
+#   within each line. It does not change the copied text.
+#
+# • scale: Render at this scale on top of the display scale factor,
+#   between 0.25 and 4.0. Lower values trade quality for performance.
+#
 # • level: Configure renderer level
 #   - Available options: 0 and 1.
 #       Higher the level more rendering features and computations
@@ -269,6 +490,10 @@ blinking-cursor = false
 # performance = "high"
 # backend = "automatic"
 # disable-unfocused-render = false
+# auto-performance = false
+# render-whitespace = false
+# low-latency-input = false
+# scale = 1.0
 # level = 1
 
 # Keyboard
@@ -278,10 +503,24 @@ blinking-cursor = false
 # disable-ctlseqs-alt - Disable ctlseqs with ALT keys
 #   - For example: Terminal.app does not deal with ctlseqs with ALT keys
 #
+# alt-sends-escape - Send ESC before characters typed with ALT
+#   - Initial state of `DECSET 1036`, applications can still change it
+#
 # Example:
 # [keyboard]
 # use-kitty-keyboard-protocol = false
 # disable-ctlseqs-alt = false
+# alt-sends-escape = true
+
+# Selection
+#
+# semantic-separators - Characters that end a word when double clicking
+#   - An empty string makes the whole line a single word
+#   - Default is ",│`|:\"' ()[]{}<>\t"
+#
+# Example:
+# [selection]
+# semantic-separators = ",│`|:\"' ()[]{}<>\t"
 
 # Fonts
 #
@@ -340,10 +579,34 @@ blinking-cursor = false
 #
 # Multiplier default is 3.0.
 # Divider default is 1.0.
+#
+# alt-screen-scrollback keeps a small history for the alternate screen
+# (used by full screen applications), view it with Shift + scroll.
+# Default is false.
+#
+# disable-alt-screen ignores requests to switch to the alternate screen,
+# so the output of applications like less or vim stays in the scrollback.
+# Default is false.
+#
+# preserve-scrollback-on-clear ignores `CSI 3 J`, so `clear` keeps the
+# scrollback. A terminal reset still drops it.
+# Default is false.
+#
+# mouse-wheel-in-alt controls the mouse wheel in the alternate screen
+# when the application doesn't handle the mouse:
+#   • auto - Arrow keys if the application enabled alternate scroll
+#   • arrows - Always arrow keys
+#   • scroll - Never arrow keys
+# Default is auto.
+#
 # Example:
 # [scroll]
 # multiplier = 3.0
 # divider = 1.0
+# alt-screen-scrollback = false
+# disable-alt-screen = false
+# preserve-scrollback-on-clear = false
+# mouse-wheel-in-alt = "auto"
 
 # Navigation
 #
@@ -357,6 +620,8 @@ blinking-cursor = false
 # "hide-if-single" - Hide navigation UI if is single.
 # "clickable" - Enable click on tabs to switch.
 # "use-current-path" - Use same path whenever a new tab is created (Note: requires `use-fork` to be set to false).
+# "home-path-modifier" - Modifier that makes a new tab open in the home directory instead of the current path.
+# "dim-inactive-tabs" - Draw the title of inactive tabs dimmed.
 # "color-automation" - Set a specific color for the tab whenever a specific program is running, or in a specific directory.
 #
 # Example:
@@ -365,6 +630,8 @@ blinking-cursor = false
 # clickable = false
 # hide-if-single = true
 # use-current-path = false
+# home-path-modifier = ""
+# dim-inactive-tabs = false
 # color-automation = []
 
 # Shell
@@ -413,6 +680,7 @@ blinking-cursor = false
 # cursor = '#F38BA3'
 # tabs = '#443d40'
 # tabs-active = '#F38BA3'
+# tabs-inactive = '#1b1a1a'
 # green = '#0BA95B'
 # red = '#ED203D'
 # blue = '#12B5E5'
@@ -438,5 +706,17 @@ blinking-cursor = false
 # Example:
 # [developer]
 # log-level = "OFF"
+
+# Show control characters
+#
+# Render non-printable C0 control characters from the output in
+# caret notation (`^[`, `^G`, ...) instead of interpreting them,
+# similar to `cat -v`. Line feed, carriage return and tab are
+# still interpreted. Useful for debugging escape sequences.
+# Default is `false`
+#
+# Example:
+# [developer]
+# show-control-characters = false
 "#.to_string()
 }
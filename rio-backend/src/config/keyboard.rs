@@ -14,6 +14,9 @@ pub struct Keyboard {
         rename = "disable-ctlseqs-alt"
     )]
     pub disable_ctlseqs_alt: bool,
+    // Prefix characters typed with ALT with ESC, initial state of DECSET 1036
+    #[serde(default = "default_bool_true", rename = "alt-sends-escape")]
+    pub alt_sends_escape: bool,
 }
 
 #[allow(clippy::derivable_impls)]
@@ -25,6 +28,7 @@ impl Default for Keyboard {
             disable_ctlseqs_alt: true,
             #[cfg(not(target_os = "macos"))]
             disable_ctlseqs_alt: false,
+            alt_sends_escape: true,
         }
     }
 }
@@ -4,6 +4,7 @@ pub mod defaults;
 pub mod keyboard;
 pub mod navigation;
 pub mod renderer;
+pub mod selection;
 pub mod theme;
 pub mod window;
 
@@ -12,6 +13,7 @@ use crate::config::defaults::*;
 use crate::config::keyboard::Keyboard;
 use crate::config::navigation::Navigation;
 use crate::config::renderer::Renderer;
+use crate::config::selection::Selection;
 use crate::config::window::Window;
 use colors::Colors;
 use log::warn;
@@ -39,6 +41,69 @@ pub struct Shell {
 pub struct Scroll {
     pub multiplier: f64,
     pub divider: f64,
+    #[serde(default = "bool::default", rename = "alt-screen-scrollback")]
+    pub alt_screen_scrollback: bool,
+    #[serde(default = "bool::default", rename = "disable-alt-screen")]
+    pub disable_alt_screen: bool,
+    #[serde(default = "bool::default", rename = "preserve-scrollback-on-clear")]
+    pub preserve_scrollback_on_clear: bool,
+    #[serde(default = "MouseWheelInAlt::default", rename = "mouse-wheel-in-alt")]
+    pub mouse_wheel_in_alt: MouseWheelInAlt,
+}
+
+/// What the mouse wheel does while the alternate screen is active
+/// and the application doesn't handle the mouse itself.
+#[derive(Default, Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub enum MouseWheelInAlt {
+    /// Send arrow keys if the application enabled alternate scroll (`DECSET 1007`).
+    #[default]
+    #[serde(alias = "auto")]
+    Auto,
+    /// Always send arrow keys.
+    #[serde(alias = "arrows")]
+    Arrows,
+    /// Never send arrow keys, scroll the display instead.
+    #[serde(alias = "scroll")]
+    Scroll,
+}
+
+/// Where the status line is drawn, see `status-line-format` for its content.
+#[derive(Default, Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub enum StatusLine {
+    #[default]
+    #[serde(alias = "hidden")]
+    Hidden,
+    /// A single line below the grid, the grid loses the space it takes.
+    #[serde(alias = "bottom")]
+    Bottom,
+}
+
+/// Underline drawn under the hovered hyperlink.
+#[derive(Default, Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub enum HyperlinkUnderlineStyle {
+    #[default]
+    #[serde(alias = "regular")]
+    Regular,
+    #[serde(alias = "double")]
+    Double,
+    #[serde(alias = "dotted")]
+    Dotted,
+    #[serde(alias = "dashed")]
+    Dashed,
+    #[serde(alias = "curly")]
+    Curly,
+}
+
+/// Clipboards that receive the text selected with the mouse.
+#[derive(Default, Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub enum SelectionClipboard {
+    /// Only the primary selection, where there is one (X11 and Wayland).
+    #[default]
+    #[serde(alias = "primary")]
+    Primary,
+    /// The primary selection and the regular clipboard.
+    #[serde(alias = "both")]
+    Both,
 }
 
 impl Default for Scroll {
@@ -46,6 +111,10 @@ impl Default for Scroll {
         Scroll {
             multiplier: 3.0,
             divider: 1.0,
+            alt_screen_scrollback: false,
+            disable_alt_screen: false,
+            preserve_scrollback_on_clear: false,
+            mouse_wheel_in_alt: MouseWheelInAlt::default(),
         }
     }
 }
@@ -56,6 +125,8 @@ pub struct Developer {
     pub enable_fps_counter: bool,
     #[serde(default = "default_log_level", rename = "log-level")]
     pub log_level: String,
+    #[serde(default = "bool::default", rename = "show-control-characters")]
+    pub show_control_characters: bool,
 }
 
 impl Default for Developer {
@@ -63,6 +134,7 @@ impl Default for Developer {
         Developer {
             log_level: default_log_level(),
             enable_fps_counter: false,
+            show_control_characters: false,
         }
     }
 }
@@ -81,6 +153,8 @@ pub struct Config {
     pub use_fork: bool,
     #[serde(default = "Keyboard::default")]
     pub keyboard: Keyboard,
+    #[serde(default = "Selection::default")]
+    pub selection: Selection,
     #[serde(default = "default_working_dir", rename = "working-dir")]
     pub working_dir: Option<String>,
     #[serde(rename = "line-height", default = "default_line_height")]
@@ -107,6 +181,8 @@ pub struct Config {
     pub cursor: char,
     #[serde(default = "Vec::default", rename = "env-vars")]
     pub env_vars: Vec<String>,
+    #[serde(default = "default_term")]
+    pub term: String,
     #[serde(default = "default_option_as_alt", rename = "option-as-alt")]
     pub option_as_alt: String,
     #[serde(default = "Colors::default", skip_serializing)]
@@ -130,10 +206,84 @@ pub struct Config {
         alias = "hide-cursor-when-typing"
     )]
     pub hide_cursor_when_typing: bool,
+    #[serde(default = "bool::default", rename = "hold-paste-trailing-newline")]
+    pub hold_paste_trailing_newline: bool,
+    #[serde(default = "bool::default", rename = "confirm-multiline-paste")]
+    pub confirm_multiline_paste: bool,
+    #[serde(default = "bool::default", rename = "paste-expand-tabs")]
+    pub paste_expand_tabs: bool,
+    #[serde(default = "bool::default", rename = "right-click-extends-selection")]
+    pub right_click_extends_selection: bool,
+    #[serde(
+        default = "SelectionClipboard::default",
+        rename = "selection-clipboard"
+    )]
+    pub selection_clipboard: SelectionClipboard,
+    #[serde(default = "default_bool_true", rename = "allow-osc52-write")]
+    pub allow_osc52_write: bool,
+    #[serde(default = "bool::default", rename = "allow-osc52-read")]
+    pub allow_osc52_read: bool,
+    #[serde(default = "default_scrollback_lines", rename = "scrollback-lines")]
+    pub scrollback_lines: usize,
+    #[serde(default = "default_max_line_length", rename = "max-line-length")]
+    pub max_line_length: usize,
+    #[serde(default = "default_parse_budget", rename = "parse-budget")]
+    pub parse_budget: usize,
+    #[serde(
+        default = "default_motion_report_throttle_ms",
+        rename = "motion-report-throttle-ms"
+    )]
+    pub motion_report_throttle_ms: u64,
+    #[serde(
+        default = "HyperlinkUnderlineStyle::default",
+        rename = "hyperlink-underline-style"
+    )]
+    pub hyperlink_underline_style: HyperlinkUnderlineStyle,
+    #[serde(default = "Option::default", rename = "url-regex")]
+    pub url_regex: Option<String>,
+    #[serde(default = "Option::default", rename = "hyperlink-opener")]
+    pub hyperlink_opener: Option<Shell>,
+    #[serde(default = "Option::default", rename = "startup-script")]
+    pub startup_script: Option<String>,
+    #[serde(default = "default_startup_delay_ms", rename = "startup-delay-ms")]
+    pub startup_delay_ms: u64,
+    #[serde(default = "StatusLine::default", rename = "status-line")]
+    pub status_line: StatusLine,
+    #[serde(default = "default_status_line_format", rename = "status-line-format")]
+    pub status_line_format: String,
     #[serde(default = "Renderer::default")]
     pub renderer: Renderer,
 }
 
+/// Resolve the `term` option to the `TERM` value given to new terminals.
+///
+/// In "auto" mode the `rio` terminfo is preferred since it describes
+/// truecolor and the kitty keyboard protocol, otherwise it falls back
+/// to `xterm-256color`.
+pub fn resolve_term(
+    term: &str,
+    use_kitty_keyboard_protocol: bool,
+    terminfo_exists: impl Fn(&str) -> bool,
+) -> String {
+    if !term.eq_ignore_ascii_case("auto") {
+        if !terminfo_exists(term) {
+            warn!("TERM {term} was not found in the system terminfo database");
+        }
+        return term.to_owned();
+    }
+
+    if terminfo_exists("rio") {
+        return String::from("rio");
+    }
+
+    if use_kitty_keyboard_protocol {
+        log::info!(
+            "rio terminfo is not installed, applications may not detect the kitty keyboard protocol"
+        );
+    }
+    String::from("xterm-256color")
+}
+
 #[cfg(not(target_os = "windows"))]
 #[inline]
 pub fn config_dir_path() -> PathBuf {
@@ -401,11 +551,13 @@ impl Default for Config {
             cursor: default_cursor(),
             scroll: Scroll::default(),
             keyboard: Keyboard::default(),
+            selection: Selection::default(),
             developer: Developer::default(),
             env_vars: vec![],
             fonts: SugarloafFonts::default(),
             line_height: default_line_height(),
             navigation: Navigation::default(),
+            term: default_term(),
             option_as_alt: default_option_as_alt(),
             padding_x: f32::default(),
             padding_y: default_padding_y(),
@@ -418,6 +570,24 @@ impl Default for Config {
             ignore_selection_fg_color: false,
             confirm_before_quit: true,
             hide_cursor_when_typing: false,
+            hold_paste_trailing_newline: false,
+            confirm_multiline_paste: false,
+            paste_expand_tabs: false,
+            right_click_extends_selection: false,
+            selection_clipboard: SelectionClipboard::default(),
+            allow_osc52_write: true,
+            allow_osc52_read: false,
+            scrollback_lines: default_scrollback_lines(),
+            max_line_length: default_max_line_length(),
+            parse_budget: default_parse_budget(),
+            motion_report_throttle_ms: default_motion_report_throttle_ms(),
+            hyperlink_underline_style: HyperlinkUnderlineStyle::default(),
+            url_regex: None,
+            hyperlink_opener: None,
+            startup_script: None,
+            startup_delay_ms: default_startup_delay_ms(),
+            status_line: StatusLine::default(),
+            status_line_format: default_status_line_format(),
         }
     }
 }
@@ -488,6 +658,7 @@ mod tests {
         // Developer
         assert_eq!(result.developer.log_level, default_log_level());
         assert!(!result.developer.enable_fps_counter);
+        assert!(!result.developer.show_control_characters);
     }
 
     #[test]
@@ -506,8 +677,24 @@ mod tests {
         assert_eq!(result.fonts, SugarloafFonts::default());
         assert_eq!(result.shell, default_shell());
         assert!(!result.renderer.disable_unfocused_render);
+        assert!(!result.renderer.auto_performance);
+        assert_eq!(result.term, "auto");
+        assert!(!result.renderer.render_whitespace);
+        assert!(!result.renderer.low_latency_input);
+        assert_eq!(result.renderer.scale, default_render_scale());
+        assert_eq!(result.window.min_columns, crate::crosswords::MIN_COLUMNS);
+        assert_eq!(result.window.min_lines, crate::crosswords::MIN_LINES);
         assert_eq!(result.use_fork, default_use_fork());
         assert_eq!(result.line_height, default_line_height());
+        assert_eq!(result.max_line_length, default_max_line_length());
+        assert_eq!(result.parse_budget, default_parse_budget());
+        assert_eq!(result.startup_script, None);
+        assert_eq!(result.startup_delay_ms, default_startup_delay_ms());
+        assert_eq!(result.status_line, StatusLine::Hidden);
+        assert!(result.allow_osc52_write);
+        assert!(!result.allow_osc52_read);
+        assert_eq!(result.status_line_format, default_status_line_format());
+        assert!(result.keyboard.alt_sends_escape);
 
         // Colors
         assert_eq!(result.colors, Colors::default());
@@ -790,6 +977,35 @@ mod tests {
         assert_eq!(result.colors.cursor, colors::defaults::cursor());
     }
 
+    #[test]
+    fn test_inactive_tabs() {
+        let result = create_temporary_config(
+            "change-inactive-tabs",
+            r#"
+            [navigation]
+            dim-inactive-tabs = true
+
+            [colors]
+            tabs-inactive = '#000000'
+        "#,
+        );
+
+        assert!(result.navigation.dim_inactive_tabs);
+        assert_eq!(result.colors.tabs_inactive, Some([0.0, 0.0, 0.0, 1.0]));
+        assert_eq!(result.colors.tabs_active, colors::defaults::tabs_active());
+
+        // Without it inactive tabs follow the bar color.
+        let result = create_temporary_config(
+            "change-inactive-tabs-bar",
+            r#"
+            [colors]
+            bar = '#ffffff'
+        "#,
+        );
+        assert_eq!(result.colors.tabs_inactive, None);
+        assert_eq!(result.colors.bar, [1.0, 1.0, 1.0, 1.0]);
+    }
+
     #[test]
     fn test_change_colors() {
         let result = create_temporary_config(
@@ -881,6 +1097,52 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_render_whitespace() {
+        let result = create_temporary_config(
+            "change-render-whitespace",
+            r#"
+            [renderer]
+            render-whitespace = true
+            low-latency-input = true
+        "#,
+        );
+
+        assert!(result.renderer.render_whitespace);
+        assert!(result.renderer.low_latency_input);
+        assert!(!result.renderer.disable_unfocused_render);
+    }
+
+    #[test]
+    fn test_render_scale() {
+        let result = create_temporary_config(
+            "change-render-scale",
+            r#"
+            [renderer]
+            scale = 0.5
+        "#,
+        );
+
+        assert_eq!(result.renderer.scale, 0.5);
+        assert!(!result.renderer.render_whitespace);
+    }
+
+    #[test]
+    fn test_window_min_size() {
+        let result = create_temporary_config(
+            "change-window-min-size",
+            r#"
+            [window]
+            min-columns = 20
+            min-lines = 5
+        "#,
+        );
+
+        assert_eq!(result.window.min_columns, 20);
+        assert_eq!(result.window.min_lines, 5);
+        assert_eq!(result.window.width, default_window_width());
+    }
+
     #[test]
     fn test_use_fork() {
         let result = create_temporary_config(
@@ -906,6 +1168,318 @@ mod tests {
         assert_eq!(result.colors.cursor, colors::defaults::cursor());
     }
 
+    #[test]
+    fn test_hold_paste_trailing_newline() {
+        let result = create_temporary_config(
+            "change-hold-paste-trailing-newline",
+            r#"
+            hold-paste-trailing-newline = true
+        "#,
+        );
+
+        assert!(result.hold_paste_trailing_newline);
+        assert!(!Config::default().hold_paste_trailing_newline);
+    }
+
+    #[test]
+    fn test_keyboard_alt_sends_escape() {
+        let result = create_temporary_config(
+            "change-keyboard-alt-sends-escape",
+            r#"
+            [keyboard]
+            alt-sends-escape = false
+        "#,
+        );
+
+        assert!(!result.keyboard.alt_sends_escape);
+        assert!(result.keyboard.use_kitty_keyboard_protocol);
+    }
+
+    #[test]
+    fn test_selection_semantic_separators() {
+        let result = create_temporary_config(
+            "change-selection-semantic-separators",
+            r#"
+            [selection]
+            semantic-separators = " :"
+        "#,
+        );
+
+        assert_eq!(result.selection.semantic_separators, " :");
+        assert_eq!(
+            Config::default().selection.semantic_separators,
+            crate::crosswords::DEFAULT_SEMANTIC_ESCAPE_CHARS
+        );
+    }
+
+    #[test]
+    fn test_parse_budget() {
+        let result = create_temporary_config(
+            "change-parse-budget",
+            r#"
+            parse-budget = 4096
+        "#,
+        );
+
+        assert_eq!(result.parse_budget, 4096);
+        assert_eq!(Config::default().parse_budget, 65535);
+    }
+
+    #[test]
+    fn test_motion_report_throttle_ms() {
+        let result = create_temporary_config(
+            "change-motion-report-throttle-ms",
+            r#"
+            motion-report-throttle-ms = 0
+        "#,
+        );
+
+        assert_eq!(result.motion_report_throttle_ms, 0);
+        assert_eq!(Config::default().motion_report_throttle_ms, 16);
+    }
+
+    #[test]
+    fn test_max_line_length() {
+        let result = create_temporary_config(
+            "change-max-line-length",
+            r#"
+            max-line-length = 0
+        "#,
+        );
+
+        assert_eq!(result.max_line_length, 0);
+        assert_eq!(Config::default().max_line_length, 1_000_000);
+    }
+
+    #[test]
+    fn test_url_regex() {
+        let result = create_temporary_config(
+            "change-url-regex",
+            r#"
+            url-regex = "https://[a-z.]+"
+        "#,
+        );
+
+        assert_eq!(result.url_regex.as_deref(), Some("https://[a-z.]+"));
+        assert_eq!(Config::default().url_regex, None);
+    }
+
+    #[test]
+    fn test_hyperlink_opener() {
+        let result = create_temporary_config(
+            "change-hyperlink-opener",
+            r#"
+            [hyperlink-opener]
+            program = "flatpak-spawn"
+            args = ["--host", "xdg-open"]
+        "#,
+        );
+
+        assert_eq!(
+            result.hyperlink_opener,
+            Some(Shell {
+                program: String::from("flatpak-spawn"),
+                args: vec![String::from("--host"), String::from("xdg-open")],
+            })
+        );
+        assert_eq!(Config::default().hyperlink_opener, None);
+    }
+
+    #[test]
+    fn test_scrollback_lines() {
+        let result = create_temporary_config(
+            "change-scrollback-lines",
+            r#"
+            scrollback-lines = 100000
+        "#,
+        );
+
+        assert_eq!(result.scrollback_lines, 100_000);
+        assert_eq!(Config::default().scrollback_lines, 10_000);
+    }
+
+    #[test]
+    fn test_hyperlink_underline_style() {
+        let result = create_temporary_config(
+            "change-hyperlink-underline-style",
+            r#"
+            hyperlink-underline-style = "dotted"
+
+            [colors]
+            hyperlink = '#FF0000'
+        "#,
+        );
+
+        assert_eq!(
+            result.hyperlink_underline_style,
+            HyperlinkUnderlineStyle::Dotted
+        );
+        assert_eq!(result.colors.hyperlink, [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(
+            Config::default().hyperlink_underline_style,
+            HyperlinkUnderlineStyle::Regular
+        );
+    }
+
+    #[test]
+    fn test_startup_script() {
+        let result = create_temporary_config(
+            "change-startup-script",
+            r#"
+            startup-script = "cd ~/projects\n"
+            startup-delay-ms = 250
+        "#,
+        );
+
+        assert_eq!(result.startup_script, Some(String::from("cd ~/projects\n")));
+        assert_eq!(result.startup_delay_ms, 250);
+    }
+
+    #[test]
+    fn test_allow_osc52() {
+        let result = create_temporary_config(
+            "change-allow-osc52",
+            r#"
+            allow-osc52-write = false
+            allow-osc52-read = true
+        "#,
+        );
+
+        assert!(!result.allow_osc52_write);
+        assert!(result.allow_osc52_read);
+    }
+
+    #[test]
+    fn test_status_line() {
+        let result = create_temporary_config(
+            "change-status-line",
+            r#"
+            status-line = "bottom"
+            status-line-format = "{mode} {title}"
+        "#,
+        );
+
+        assert_eq!(result.status_line, StatusLine::Bottom);
+        assert_eq!(result.status_line_format, "{mode} {title}");
+    }
+
+    #[test]
+    fn test_alt_screen_scrollback() {
+        let result = create_temporary_config(
+            "change-alt-screen-scrollback",
+            r#"
+            [scroll]
+            multiplier = 2.0
+            divider = 1.0
+            alt-screen-scrollback = true
+            disable-alt-screen = true
+            preserve-scrollback-on-clear = true
+        "#,
+        );
+
+        assert!(result.scroll.alt_screen_scrollback);
+        assert!(result.scroll.disable_alt_screen);
+        assert!(result.scroll.preserve_scrollback_on_clear);
+        assert_eq!(result.scroll.mouse_wheel_in_alt, MouseWheelInAlt::Auto);
+        assert_eq!(result.scroll.multiplier, 2.0);
+        assert!(!Config::default().scroll.alt_screen_scrollback);
+        assert!(!Config::default().scroll.disable_alt_screen);
+        assert!(!Config::default().scroll.preserve_scrollback_on_clear);
+    }
+
+    #[test]
+    fn test_term() {
+        let result = create_temporary_config(
+            "change-term",
+            r#"
+            term = "xterm-kitty"
+        "#,
+        );
+
+        assert_eq!(result.term, "xterm-kitty");
+    }
+
+    #[test]
+    fn test_resolve_term() {
+        let with_rio = |name: &str| name == "rio" || name == "xterm-256color";
+        let without_rio = |name: &str| name == "xterm-256color";
+
+        assert_eq!(resolve_term("auto", true, with_rio), "rio");
+        assert_eq!(resolve_term("auto", true, without_rio), "xterm-256color");
+        assert_eq!(resolve_term("xterm-kitty", true, with_rio), "xterm-kitty");
+        assert_eq!(resolve_term("screen", false, |_| false), "screen");
+    }
+
+    #[test]
+    fn test_mouse_wheel_in_alt() {
+        let result = create_temporary_config(
+            "change-mouse-wheel-in-alt",
+            r#"
+            [scroll]
+            multiplier = 3.0
+            divider = 1.0
+            mouse-wheel-in-alt = "arrows"
+        "#,
+        );
+
+        assert_eq!(result.scroll.mouse_wheel_in_alt, MouseWheelInAlt::Arrows);
+    }
+
+    #[test]
+    fn test_paste_expand_tabs() {
+        let result = create_temporary_config(
+            "change-paste-expand-tabs",
+            r#"
+            paste-expand-tabs = true
+        "#,
+        );
+
+        assert!(result.paste_expand_tabs);
+        assert!(!Config::default().paste_expand_tabs);
+    }
+
+    #[test]
+    fn test_right_click_extends_selection() {
+        let result = create_temporary_config(
+            "change-right-click-extends-selection",
+            r#"
+            right-click-extends-selection = true
+        "#,
+        );
+
+        assert!(result.right_click_extends_selection);
+        assert!(!Config::default().right_click_extends_selection);
+    }
+
+    #[test]
+    fn test_confirm_multiline_paste() {
+        let result = create_temporary_config(
+            "change-confirm-multiline-paste",
+            r#"
+            confirm-multiline-paste = true
+        "#,
+        );
+
+        assert!(result.confirm_multiline_paste);
+        assert!(!Config::default().confirm_multiline_paste);
+    }
+
+    #[test]
+    fn test_selection_clipboard() {
+        let result = create_temporary_config(
+            "change-selection-clipboard",
+            r#"
+            selection-clipboard = "both"
+        "#,
+        );
+
+        assert_eq!(result.selection_clipboard, SelectionClipboard::Both);
+        assert_eq!(
+            Config::default().selection_clipboard,
+            SelectionClipboard::Primary
+        );
+    }
+
     #[test]
     fn test_shell() {
         let result = create_temporary_config(
@@ -931,6 +1505,7 @@ mod tests {
             [developer]
             enable-fps-counter = true
             log-level = "INFO"
+            show-control-characters = true
         "#,
         );
 
@@ -939,6 +1514,7 @@ mod tests {
         // Developer
         assert_eq!(result.developer.log_level, String::from("INFO"));
         assert!(result.developer.enable_fps_counter);
+        assert!(result.developer.show_control_characters);
 
         // Colors
         assert_eq!(result.colors.background, colors::defaults::background());
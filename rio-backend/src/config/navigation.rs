@@ -105,10 +105,14 @@ pub struct Navigation {
     pub clickable: bool,
     #[serde(default = "bool::default", rename = "use-current-path")]
     pub use_current_path: bool,
+    #[serde(default = "String::default", rename = "home-path-modifier")]
+    pub home_path_modifier: String,
     #[serde(default = "bool::default", rename = "use-terminal-title")]
     pub use_terminal_title: bool,
     #[serde(default = "default_bool_true", rename = "hide-if-single")]
     pub hide_if_single: bool,
+    #[serde(default = "bool::default", rename = "dim-inactive-tabs")]
+    pub dim_inactive_tabs: bool,
 }
 
 impl Default for Navigation {
@@ -118,8 +122,10 @@ impl Default for Navigation {
             color_automation: Vec::default(),
             clickable: false,
             use_current_path: false,
+            home_path_modifier: String::default(),
             use_terminal_title: false,
             hide_if_single: true,
+            dim_inactive_tabs: false,
         }
     }
 }
@@ -303,4 +309,18 @@ mod tests {
             hex_to_color_arr("#00b952")
         );
     }
+
+    #[test]
+    fn test_home_path_modifier() {
+        let content = r#"
+            [navigation]
+            use-current-path = true
+            home-path-modifier = "shift"
+        "#;
+
+        let decoded = toml::from_str::<Root>(content).unwrap();
+        assert!(decoded.navigation.use_current_path);
+        assert_eq!(decoded.navigation.home_path_modifier, "shift");
+        assert!(Navigation::default().home_path_modifier.is_empty());
+    }
 }
@@ -1,7 +1,8 @@
+use super::defaults::default_render_scale;
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct Renderer {
     #[serde(default = "Performance::default")]
     pub performance: Performance,
@@ -9,6 +10,28 @@ pub struct Renderer {
     pub backend: Backend,
     #[serde(default = "bool::default", rename = "disable-unfocused-render")]
     pub disable_unfocused_render: bool,
+    #[serde(default = "bool::default", rename = "auto-performance")]
+    pub auto_performance: bool,
+    #[serde(default = "bool::default", rename = "render-whitespace")]
+    pub render_whitespace: bool,
+    #[serde(default = "bool::default", rename = "low-latency-input")]
+    pub low_latency_input: bool,
+    #[serde(default = "default_render_scale")]
+    pub scale: f32,
+}
+
+impl Default for Renderer {
+    fn default() -> Renderer {
+        Renderer {
+            performance: Performance::default(),
+            backend: Backend::default(),
+            disable_unfocused_render: false,
+            auto_performance: false,
+            render_whitespace: false,
+            low_latency_input: false,
+            scale: default_render_scale(),
+        }
+    }
 }
 
 #[derive(Default, Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+use crate::crosswords::DEFAULT_SEMANTIC_ESCAPE_CHARS;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Selection {
+    // Characters that end a word for semantic (double click) selection,
+    // an empty string makes the whole line a single word
+    #[serde(
+        default = "default_semantic_separators",
+        rename = "semantic-separators"
+    )]
+    pub semantic_separators: String,
+}
+
+fn default_semantic_separators() -> String {
+    String::from(DEFAULT_SEMANTIC_ESCAPE_CHARS)
+}
+
+impl Default for Selection {
+    fn default() -> Selection {
+        Selection {
+            semantic_separators: default_semantic_separators(),
+        }
+    }
+}
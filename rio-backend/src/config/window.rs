@@ -43,6 +43,10 @@ pub struct Window {
     pub background_image: Option<ImageProperties>,
     #[serde(default = "Decorations::default")]
     pub decorations: Decorations,
+    #[serde(default = "default_window_min_columns", rename = "min-columns")]
+    pub min_columns: usize,
+    #[serde(default = "default_window_min_lines", rename = "min-lines")]
+    pub min_lines: usize,
 }
 
 impl Default for Window {
@@ -55,6 +59,8 @@ impl Default for Window {
             background_image: None,
             decorations: Decorations::default(),
             blur: false,
+            min_columns: default_window_min_columns(),
+            min_lines: default_window_min_lines(),
         }
     }
 }
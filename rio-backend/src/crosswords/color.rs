@@ -0,0 +1,134 @@
+use crate::config::colors::term::List;
+use crate::config::colors::{AnsiColor, ColorArray, NamedColor};
+use crate::crosswords::square::Flags;
+
+/// Colors a cell is resolved against.
+pub trait Palette {
+    fn named(&self, color: NamedColor) -> ColorArray;
+    fn indexed(&self, index: usize) -> ColorArray;
+}
+
+impl Palette for List {
+    #[inline]
+    fn named(&self, color: NamedColor) -> ColorArray {
+        self[color]
+    }
+
+    #[inline]
+    fn indexed(&self, index: usize) -> ColorArray {
+        self[index]
+    }
+}
+
+/// Foreground color a cell is drawn with.
+///
+/// The eight base colors take their dim or light variant only when the
+/// cell is exactly `DIM` or `BOLD`, truecolor is darkened by `DIM` and
+/// indexed colors only follow `DIM`.
+#[inline]
+pub fn resolve_fg<P: Palette>(palette: &P, color: AnsiColor, flags: Flags) -> ColorArray {
+    match color {
+        AnsiColor::Named(name) => palette.named(variant(name, flags)),
+        AnsiColor::Spec(rgb) if flags.contains(Flags::DIM) => rgb.to_arr_with_dim(),
+        AnsiColor::Spec(rgb) => rgb.to_arr(),
+        AnsiColor::Indexed(index) => {
+            let index = match (flags & Flags::DIM_BOLD, index) {
+                (Flags::DIM, 8..=15) => index as usize - 8,
+                (Flags::DIM, 0..=7) => NamedColor::DimBlack as usize + index as usize,
+                _ => index as usize,
+            };
+            palette.indexed(index)
+        }
+    }
+}
+
+/// Background color a cell is drawn with. Only named colors follow the
+/// cell flags.
+#[inline]
+pub fn resolve_bg<P: Palette>(palette: &P, color: AnsiColor, flags: Flags) -> ColorArray {
+    match color {
+        AnsiColor::Named(name) => palette.named(variant(name, flags)),
+        AnsiColor::Spec(rgb) => rgb.to_arr(),
+        AnsiColor::Indexed(index) => palette.indexed(index as usize),
+    }
+}
+
+#[inline]
+fn variant(name: NamedColor, flags: Flags) -> NamedColor {
+    let base = matches!(
+        name,
+        NamedColor::Black
+            | NamedColor::Red
+            | NamedColor::Green
+            | NamedColor::Yellow
+            | NamedColor::Blue
+            | NamedColor::Magenta
+            | NamedColor::Cyan
+            | NamedColor::White
+    );
+
+    match flags {
+        Flags::DIM if base => name.to_dim(),
+        Flags::BOLD if base => name.to_light(),
+        _ => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::colors::term::TermColors;
+    use crate::config::colors::ColorRgb;
+
+    #[test]
+    fn test_resolve_colors() {
+        let list = List::from(&TermColors::default());
+        let fg = |color: AnsiColor, flags| resolve_fg(&list, color, flags);
+
+        let red = AnsiColor::Named(NamedColor::Red);
+        assert_eq!(fg(red, Flags::empty()), list[NamedColor::Red]);
+        assert_eq!(fg(red, Flags::BOLD), list[NamedColor::LightRed]);
+        assert_eq!(fg(red, Flags::DIM), list[NamedColor::DimRed]);
+        // Dim and bold together cancel out, as do extra flags.
+        assert_eq!(fg(red, Flags::DIM_BOLD), list[NamedColor::Red]);
+        assert_eq!(fg(red, Flags::BOLD | Flags::ITALIC), list[NamedColor::Red]);
+
+        // Bold does not brighten the default foreground.
+        let foreground = AnsiColor::Named(NamedColor::Foreground);
+        assert_eq!(fg(foreground, Flags::BOLD), list[NamedColor::Foreground]);
+        assert_eq!(fg(foreground, Flags::DIM), list[NamedColor::Foreground]);
+
+        // Bold does not brighten indexed colors, dim does darken them.
+        assert_eq!(fg(AnsiColor::Indexed(1), Flags::BOLD), list[1]);
+        assert_eq!(
+            fg(AnsiColor::Indexed(1), Flags::DIM),
+            list[NamedColor::DimRed]
+        );
+        assert_eq!(fg(AnsiColor::Indexed(9), Flags::DIM), list[1]);
+        assert_eq!(fg(AnsiColor::Indexed(9), Flags::DIM_BOLD), list[9]);
+
+        let rgb = ColorRgb {
+            r: 200,
+            g: 100,
+            b: 50,
+        };
+        assert_eq!(
+            fg(AnsiColor::Spec(rgb), Flags::DIM_BOLD),
+            rgb.to_arr_with_dim()
+        );
+
+        // Backgrounds only follow the flags for named colors.
+        assert_eq!(
+            resolve_bg(&list, red, Flags::BOLD),
+            list[NamedColor::LightRed]
+        );
+        assert_eq!(
+            resolve_bg(&list, AnsiColor::Spec(rgb), Flags::DIM),
+            rgb.to_arr()
+        );
+        assert_eq!(
+            resolve_bg(&list, AnsiColor::Indexed(9), Flags::DIM),
+            list[9]
+        );
+    }
+}
@@ -92,6 +92,12 @@ impl<T: GridSquare + Default + PartialEq + Clone> Grid<T> {
         self.max_scroll_limit = history_size;
     }
 
+    /// Maximum number of lines in history.
+    #[inline]
+    pub fn max_scroll_limit(&self) -> usize {
+        self.max_scroll_limit
+    }
+
     pub fn scroll_display(&mut self, scroll: Scroll) {
         self.display_offset = match scroll {
             Scroll::Delta(count) => min(
@@ -6,16 +6,28 @@ use crate::crosswords::grid::GridSquare;
 use crate::crosswords::square::Flags;
 use crate::crosswords::square::ResetDiscriminant;
 use crate::crosswords::Column;
+use bitflags::bitflags;
 use core::cmp::min;
 use std::cmp::max;
 use std::ops::{Index, IndexMut, Range, RangeFrom, RangeFull, RangeTo, RangeToInclusive};
 use std::{ptr, slice};
 
+bitflags! {
+    /// Marks attached to a whole row rather than to its cells.
+    #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+    pub struct RowFlag: u8 {
+        /// The row holds the start of a shell prompt (OSC 133 ; A).
+        const PROMPT_START = 0b0000_0001;
+    }
+}
+
 /// A row in the grid.
 #[derive(Default, Clone, Debug)]
 pub struct Row<T> {
     pub inner: Vec<T>,
 
+    pub flags: RowFlag,
+
     /// Maximum number of occupied entries.
     ///
     /// This is the upper bound on the number of elements in the row, which have been modified
@@ -51,7 +63,11 @@ impl<T: Clone + Default> Row<T> {
             inner.set_len(columns);
         }
 
-        Row { inner, occ: 0 }
+        Row {
+            inner,
+            occ: 0,
+            flags: RowFlag::empty(),
+        }
     }
 
     /// Increase the number of columns in the row.
@@ -114,6 +130,7 @@ impl<T: Clone + Default> Row<T> {
         }
 
         self.occ = 0;
+        self.flags = RowFlag::empty();
     }
 }
 
@@ -121,7 +138,11 @@ impl<T: Clone + Default> Row<T> {
 impl<T> Row<T> {
     #[inline]
     pub fn from_vec(vec: Vec<T>, occ: usize) -> Row<T> {
-        Row { inner: vec, occ }
+        Row {
+            inner: vec,
+            occ,
+            flags: RowFlag::empty(),
+        }
     }
 
     #[inline]
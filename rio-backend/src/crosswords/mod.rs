@@ -15,6 +15,7 @@
 */
 
 pub mod attr;
+pub mod color;
 pub mod grid;
 pub mod pos;
 pub mod search;
@@ -26,10 +27,11 @@ use crate::ansi::graphics::Graphics;
 use crate::ansi::graphics::TextureRef;
 use crate::ansi::graphics::UpdateQueues;
 use crate::ansi::graphics::MAX_GRAPHIC_DIMENSIONS;
+use crate::ansi::kitty_graphics::{self, KittyGraphicsState};
 use crate::ansi::sixel;
 use crate::ansi::{
     mode::Mode as AnsiMode, ClearMode, CursorShape, KeyboardModes,
-    KeyboardModesApplyBehavior, LineClearMode, TabulationClearMode,
+    KeyboardModesApplyBehavior, LineClearMode, SemanticPrompt, TabulationClearMode,
 };
 use crate::clipboard::ClipboardType;
 use crate::config::colors::{
@@ -46,7 +48,7 @@ use attr::*;
 use base64::{engine::general_purpose, Engine as _};
 use bitflags::bitflags;
 use copa::Params;
-use grid::row::Row;
+use grid::row::{Row, RowFlag};
 use log::{debug, info, warn};
 use pos::{
     Boundary, CharsetIndex, Column, Cursor, CursorState, Direction, Line, Pos, Side,
@@ -57,8 +59,9 @@ use std::mem;
 use std::ops::{Index, IndexMut, Range};
 use std::option::Option;
 use std::ptr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use sugarloaf::GraphicData;
+use sugarloaf::{GraphicData, GraphicId};
 use unicode_width::UnicodeWidthChar;
 use vi_mode::{ViModeCursor, ViMotion};
 
@@ -66,10 +69,28 @@ pub type NamedColor = colors::NamedColor;
 
 pub const MIN_COLUMNS: usize = 2;
 pub const MIN_LINES: usize = 1;
+/// History kept for the primary screen unless configured otherwise.
+pub const DEFAULT_SCROLLBACK_LINES: usize = 10_000;
+
+/// Source of `Crosswords::terminal_id`, shared by every window.
+static NEXT_TERMINAL_ID: AtomicU64 = AtomicU64::new(0);
+/// History kept for the alternate screen when it has scrollback enabled.
+pub const ALT_SCREEN_SCROLLBACK_LINES: usize = 1_000;
+/// Characters that end a word for semantic (double click) selection.
+pub const DEFAULT_SEMANTIC_ESCAPE_CHARS: &str = ",│`|:\"' ()[]{}<>\t";
+/// Regex used to detect URLs in the terminal content.
+pub const DEFAULT_URL_REGEX: &str = "(ipfs:|ipns:|magnet:|mailto:|gemini://|gopher://|https://|http://|news:|file:|git://|ssh:|ftp://)\
+                         [^\u{0000}-\u{001F}\u{007F}-\u{009F}<>\"\\s{-}\\^⟨⟩`]+";
+
+/// Max. number of wrapped lines scanned in each direction for a URL.
+const MAX_URL_LINES: usize = 100;
 
 /// Max. number of graphics stored in a single cell.
 const MAX_GRAPHICS_PER_CELL: usize = 20;
 
+/// Unit id reported to tertiary device attributes queries (`CSI = c`).
+const TERTIARY_DA_UNIT_ID: &str = "00000000";
+
 bitflags! {
     #[derive(Debug, Copy, Clone)]
      pub struct Mode: u32 {
@@ -88,7 +109,7 @@ bitflags! {
         const FOCUS_IN_OUT                     = 0b0000_0000_0000_1000_0000_0000;
         const ALT_SCREEN                       = 0b0000_0000_0001_0000_0000_0000;
         const MOUSE_DRAG                       = 0b0000_0000_0010_0000_0000_0000;
-        const MOUSE_MODE                       = 0b0000_0000_0010_0000_0100_1000;
+        const MOUSE_MODE                       = 0b1000_0000_0010_0000_0100_1000;
         const UTF8_MOUSE                       = 0b0000_0000_0100_0000_0000_0000;
         const ALTERNATE_SCROLL                 = 0b0000_0000_1000_0000_0000_0000;
         const VI                               = 0b0000_0001_0000_0000_0000_0000;
@@ -98,6 +119,11 @@ bitflags! {
         const KEYBOARD_REPORT_ALTERNATE_KEYS   = 0b0001_0000_0000_0000_0000_0000;
         const KEYBOARD_REPORT_ALL_KEYS_AS_ESC  = 0b0010_0000_0000_0000_0000_0000;
         const KEYBOARD_REPORT_ASSOCIATED_TEXT  = 0b0100_0000_0000_0000_0000_0000;
+        const MOUSE_X10                        = 0b1000_0000_0000_0000_0000_0000;
+        const META_SENDS_ESCAPE                = 1 << 24;
+        const ALT_SENDS_ESCAPE                 = 1 << 25;
+        const BACKARROW_SENDS_BACKSPACE        = 1 << 26;
+        const WHEEL_TO_ARROW                   = 1 << 27;
         const KEYBOARD_PROTOCOL = Self::KEYBOARD_DISAMBIGUATE_ESC_CODES.bits()
                                 | Self::KEYBOARD_REPORT_EVENT_TYPES.bits()
                                 | Self::KEYBOARD_REPORT_ALTERNATE_KEYS.bits()
@@ -117,6 +143,7 @@ impl Default for Mode {
             | Mode::ALTERNATE_SCROLL
             | Mode::URGENCY_HINTS
             | Mode::SIXEL_PRIV_PALETTE
+            | Mode::META_SENDS_ESCAPE
     }
 }
 
@@ -283,8 +310,8 @@ struct TabStops {
     tabs: Vec<bool>,
 }
 
-/// Default tab interval, corresponding to terminfo `it` value.
-const INITIAL_TABSTOPS: usize = 8;
+/// Columns between the default tab stops, the terminfo `it` value.
+pub const INITIAL_TABSTOPS: usize = 8;
 
 impl TabStops {
     #[inline]
@@ -355,12 +382,25 @@ const TITLE_STACK_MAX_DEPTH: usize = 4096;
 // Max size of the keyboard modes.
 const KEYBOARD_MODE_STACK_MAX_DEPTH: usize = 16384;
 
+/// State saved by DECSC along with the grid's saved cursor, which keeps
+/// the position, SGR template and charset designations since those have
+/// to follow the grid on resize.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SavedCursorState {
+    /// Charset invoked into GL when the cursor was saved.
+    pub active_charset: CharsetIndex,
+    /// Whether origin mode (DECOM) was set.
+    pub origin: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct Crosswords<U>
 where
     U: EventListener,
 {
     active_charset: CharsetIndex,
+    saved_cursor_state: SavedCursorState,
+    inactive_saved_cursor_state: SavedCursorState,
     mode: Mode,
     pub vi_mode_cursor: ViModeCursor,
     semantic_escape_chars: String,
@@ -370,18 +410,42 @@ where
     tabs: TabStops,
     event_proxy: U,
     pub selection: Option<Selection>,
-    #[allow(dead_code)]
     colors: List,
     pub title: String,
+    /// Title set with OSC 0 or OSC 1, shown when there is no window title.
+    pub icon_title: String,
     damage: TermDamageState,
     graphics: Graphics,
+    kitty_graphics: KittyGraphicsState,
     pub cursor_shape: CursorShape,
     pub default_cursor_shape: CursorShape,
     pub blinking_cursor: bool,
+    /// Blinking requested with `DECSCUSR`, `None` until an application
+    /// sets a cursor style.
+    cursor_style_blinking: Option<bool>,
+    pub show_control_characters: bool,
+    /// Maximum amount of characters a single logical line can hold
+    /// before it gets broken into a new one, zero disables it.
+    pub max_line_length: usize,
+    /// Ignore requests to switch to the alternate screen, full screen
+    /// applications draw over the primary screen instead.
+    pub disable_alt_screen: bool,
+    /// Ignore `CSI 3 J`, a reset still drops the scrollback.
+    pub preserve_scrollback_on_clear: bool,
+    alt_sends_escape: bool,
+    /// `DECCARA` changes a rectangle instead of the character stream.
+    rectangular_attribute_changes: bool,
+    wrapped_lines: usize,
+    line_length_warned: bool,
     pub window_id: WindowId,
     pub route_id: usize,
+    terminal_id: u64,
     title_stack: Vec<String>,
     hyperlink_re: regex::Regex,
+    /// The bell rang since the terminal was last displayed.
+    pub bell: bool,
+    /// Output arrived since the terminal was last displayed.
+    pub has_activity: bool,
 
     // The stack for the keyboard modes.
     keyboard_mode_stack: Vec<KeyboardModes>,
@@ -400,16 +464,13 @@ impl<U: EventListener> Crosswords<U> {
     ) -> Crosswords<U> {
         let cols = dimensions.columns();
         let rows = dimensions.screen_lines();
-        let grid = Grid::new(rows, cols, 10_000);
+        let grid = Grid::new(rows, cols, DEFAULT_SCROLLBACK_LINES);
         let alt = Grid::new(rows, cols, 0);
 
         let scroll_region = Line(0)..Line(rows as i32);
-        let semantic_escape_chars = String::from(",│`|:\"' ()[]{}<>\t");
+        let semantic_escape_chars = String::from(DEFAULT_SEMANTIC_ESCAPE_CHARS);
         let term_colors = TermColors::default();
         let colors = List::from(&term_colors);
-        // Regex used for the default URL hint.
-        let url_regex: &str = "(ipfs:|ipns:|magnet:|mailto:|gemini://|gopher://|https://|http://|news:|file:|git://|ssh:|ftp://)\
-                         [^\u{0000}-\u{001F}\u{007F}-\u{009F}<>\"\\s{-}\\^⟨⟩`]+";
 
         Crosswords {
             vi_mode_cursor: ViModeCursor::new(grid.cursor.pos),
@@ -418,29 +479,71 @@ impl<U: EventListener> Crosswords<U> {
             grid,
             inactive_grid: alt,
             active_charset: CharsetIndex::default(),
+            saved_cursor_state: SavedCursorState::default(),
+            inactive_saved_cursor_state: SavedCursorState::default(),
             scroll_region,
             event_proxy,
             colors,
-            hyperlink_re: regex::Regex::new(url_regex).unwrap(),
+            hyperlink_re: regex::Regex::new(DEFAULT_URL_REGEX).unwrap(),
             title: String::from(""),
+            icon_title: String::from(""),
             tabs: TabStops::new(cols),
             mode: Mode::SHOW_CURSOR
                 | Mode::LINE_WRAP
                 | Mode::ALTERNATE_SCROLL
-                | Mode::URGENCY_HINTS,
+                | Mode::URGENCY_HINTS
+                | Mode::META_SENDS_ESCAPE,
             damage: TermDamageState::new(cols, rows),
             graphics: Graphics::new(&dimensions),
+            kitty_graphics: KittyGraphicsState::default(),
             default_cursor_shape: cursor_shape,
             cursor_shape,
             blinking_cursor: false,
+            cursor_style_blinking: None,
+            show_control_characters: false,
+            max_line_length: 0,
+            disable_alt_screen: false,
+            preserve_scrollback_on_clear: false,
+            alt_sends_escape: true,
+            rectangular_attribute_changes: false,
+            wrapped_lines: 0,
+            line_length_warned: false,
             window_id,
             route_id,
+            terminal_id: NEXT_TERMINAL_ID.fetch_add(1, Ordering::Relaxed),
             title_stack: Default::default(),
             keyboard_mode_stack: Default::default(),
             inactive_keyboard_mode_stack: Default::default(),
+            bell: false,
+            has_activity: false,
+        }
+    }
+
+    /// Unique ID of this terminal across all windows, assigned on creation.
+    #[inline]
+    pub fn terminal_id(&self) -> u64 {
+        self.terminal_id
+    }
+
+    /// Title set by the application: the window title, or the icon title
+    /// if there is none.
+    #[inline]
+    pub fn application_title(&self) -> &str {
+        if self.title.is_empty() {
+            &self.icon_title
+        } else {
+            &self.title
         }
     }
 
+    /// Send an event tagged with this terminal, so it can be routed back
+    /// to it instead of whatever terminal is focused.
+    #[inline]
+    fn send_event(&self, event: RioEvent) {
+        self.event_proxy
+            .send_terminal_event(event, self.window_id, self.terminal_id);
+    }
+
     pub fn mark_fully_damaged(&mut self) {
         self.damage.is_fully_damaged = true;
     }
@@ -455,2806 +558,5585 @@ impl<U: EventListener> Crosswords<U> {
         self.grid.display_offset()
     }
 
+    /// Whether the display shows the latest output.
     #[inline]
-    pub fn clear_saved_history(&mut self) {
-        self.clear_screen(ClearMode::Saved);
+    pub fn is_at_bottom(&self) -> bool {
+        self.display_offset() == 0
     }
 
+    /// Whether the display is scrolled up into the history.
     #[inline]
-    pub fn scroll_display(&mut self, scroll: Scroll) {
-        let old_display_offset = self.grid.display_offset();
-        self.event_proxy
-            .send_event(RioEvent::MouseCursorDirty, self.window_id);
-        self.grid.scroll_display(scroll);
-
-        // Clamp vi mode cursor to the viewport.
-        let viewport_start = -(self.grid.display_offset() as i32);
-        let viewport_end = viewport_start + self.grid.bottommost_line().0;
-        let vi_cursor_line = &mut self.vi_mode_cursor.pos.row.0;
-        *vi_cursor_line =
-            std::cmp::min(viewport_end, std::cmp::max(viewport_start, *vi_cursor_line));
-        self.vi_mode_recompute_selection();
+    pub fn is_scrolled(&self) -> bool {
+        !self.is_at_bottom()
+    }
+
+    /// Apply SGR attributes to a range of cells (e.g. for `DECCARA`).
+    ///
+    /// The attributes are folded once into a flags mask and the colors to
+    /// replace, so every cell only gets a bitwise update.
+    pub fn bulk_apply_sgr(cells: &mut [Square], attrs: &[Attr]) {
+        let mut clear = square::Flags::empty();
+        let mut set = square::Flags::empty();
+        let mut fg = None;
+        let mut bg = None;
+        let mut underline_color = None;
+
+        for attr in attrs {
+            match attr {
+                Attr::Foreground(color) => fg = Some(*color),
+                Attr::Background(color) => bg = Some(*color),
+                Attr::UnderlineColor(color) => underline_color = Some(*color),
+                Attr::Reset => {
+                    fg = Some(AnsiColor::Named(NamedColor::Foreground));
+                    bg = Some(AnsiColor::Named(NamedColor::Background));
+                    underline_color = Some(None);
+                    // Only drop the styling, wrapping and wide char flags
+                    // describe the cell content itself.
+                    clear = square::Flags::BOLD
+                        | square::Flags::DIM
+                        | square::Flags::ITALIC
+                        | square::Flags::ALL_UNDERLINES
+                        | square::Flags::INVERSE
+                        | square::Flags::HIDDEN
+                        | square::Flags::STRIKEOUT;
+                    set = square::Flags::empty();
+                }
+                _ => {
+                    let (remove, add) = match attr {
+                        Attr::Reverse => (square::Flags::empty(), square::Flags::INVERSE),
+                        Attr::CancelReverse => {
+                            (square::Flags::INVERSE, square::Flags::empty())
+                        }
+                        Attr::Bold => (square::Flags::empty(), square::Flags::BOLD),
+                        Attr::CancelBold => (square::Flags::BOLD, square::Flags::empty()),
+                        Attr::Dim => (square::Flags::empty(), square::Flags::DIM),
+                        Attr::CancelBoldDim => (
+                            square::Flags::BOLD | square::Flags::DIM,
+                            square::Flags::empty(),
+                        ),
+                        Attr::Italic => (square::Flags::empty(), square::Flags::ITALIC),
+                        Attr::CancelItalic => {
+                            (square::Flags::ITALIC, square::Flags::empty())
+                        }
+                        Attr::Underline => {
+                            (square::Flags::ALL_UNDERLINES, square::Flags::UNDERLINE)
+                        }
+                        Attr::DoubleUnderline => (
+                            square::Flags::ALL_UNDERLINES,
+                            square::Flags::DOUBLE_UNDERLINE,
+                        ),
+                        Attr::Undercurl => {
+                            (square::Flags::ALL_UNDERLINES, square::Flags::UNDERCURL)
+                        }
+                        Attr::DottedUnderline => (
+                            square::Flags::ALL_UNDERLINES,
+                            square::Flags::DOTTED_UNDERLINE,
+                        ),
+                        Attr::DashedUnderline => (
+                            square::Flags::ALL_UNDERLINES,
+                            square::Flags::DASHED_UNDERLINE,
+                        ),
+                        Attr::CancelUnderline => {
+                            (square::Flags::ALL_UNDERLINES, square::Flags::empty())
+                        }
+                        Attr::Hidden => (square::Flags::empty(), square::Flags::HIDDEN),
+                        Attr::CancelHidden => {
+                            (square::Flags::HIDDEN, square::Flags::empty())
+                        }
+                        Attr::Strike => {
+                            (square::Flags::empty(), square::Flags::STRIKEOUT)
+                        }
+                        Attr::CancelStrike => {
+                            (square::Flags::STRIKEOUT, square::Flags::empty())
+                        }
+                        _ => {
+                            warn!("Term got unhandled attr: {:?}", attr);
+                            continue;
+                        }
+                    };
 
-        // Damage everything if display offset changed.
-        if old_display_offset != self.grid.display_offset() {
-            self.mark_fully_damaged();
+                    clear |= remove | add;
+                    set.remove(remove);
+                    set.insert(add);
+                }
+            }
+        }
 
-            self.event_proxy
-                .send_event(RioEvent::Render, self.window_id);
+        for cell in cells {
+            cell.flags = (cell.flags & !clear) | set;
+            if let Some(fg) = fg {
+                cell.fg = fg;
+            }
+            if let Some(bg) = bg {
+                cell.bg = bg;
+            }
+            if let Some(color) = underline_color {
+                cell.set_underline_color(color);
+            }
         }
     }
 
+    /// Initial state of `DECSET 1036`, also restored on reset. A state set
+    /// by the application is kept until the next reset.
     #[inline]
-    pub fn bottommost_line(&self) -> Line {
-        self.grid.bottommost_line()
+    pub fn set_alt_sends_escape(&mut self, alt_sends_escape: bool) {
+        if self.mode.contains(Mode::META_SENDS_ESCAPE) == self.alt_sends_escape {
+            self.mode.set(Mode::META_SENDS_ESCAPE, alt_sends_escape);
+        }
+        self.alt_sends_escape = alt_sends_escape;
     }
 
-    #[inline]
-    pub fn colors(&self) -> List {
-        self.colors
+    /// Lines of history kept by the primary screen, older lines are
+    /// dropped. With `0` there is no scrollback at all.
+    pub fn set_scrollback_lines(&mut self, lines: usize) {
+        let primary = if self.mode.contains(Mode::ALT_SCREEN) {
+            &mut self.inactive_grid
+        } else {
+            &mut self.grid
+        };
+        primary.update_history(lines);
     }
 
-    /// Get queues to update graphic data. If both queues are empty, it returns
-    /// `None`.
-    #[inline]
-    pub fn graphics_take_queues(&mut self) -> Option<UpdateQueues> {
-        self.graphics.take_queues()
+    /// Keep lines scrolled off the alternate screen in a small history of
+    /// its own, separate from the primary screen one.
+    pub fn set_alt_screen_scrollback(&mut self, enabled: bool) {
+        let alt = if self.mode.contains(Mode::ALT_SCREEN) {
+            &mut self.grid
+        } else {
+            &mut self.inactive_grid
+        };
+        alt.update_history(if enabled {
+            ALT_SCREEN_SCROLLBACK_LINES
+        } else {
+            0
+        });
     }
 
-    #[inline]
-    pub fn exit(&mut self)
-    where
-        U: EventListener,
-    {
-        self.event_proxy
-            .send_event(RioEvent::CloseTerminal(self.route_id), self.window_id);
+    /// Answer `DECSET 2500` with the pixel size of the grid area, the
+    /// window without its padding.
+    fn pixel_geometry(&mut self) {
+        debug!("pixel_geometry");
+        self.send_event(RioEvent::TextAreaSizeRequest(Arc::new(
+            move |window_size| {
+                let width = window_size.width;
+                let height = window_size.height;
+                format!("\x1b[?2500;{width};{height}h")
+            },
+        )));
     }
 
-    pub fn resize<S: Dimensions>(&mut self, size: S) {
-        let old_cols = self.grid.columns();
-        let old_lines = self.grid.screen_lines();
-        let num_cols = size.columns();
-        let num_lines = size.screen_lines();
-
-        if old_cols == num_cols && old_lines == num_lines {
-            info!("Crosswords::resize dimensions unchanged");
-            return;
+    /// Display a kitty graphics image at the cursor.
+    fn place_kitty_graphic(
+        &mut self,
+        command: &kitty_graphics::Command,
+        image_id: u32,
+    ) -> Result<(), kitty_graphics::Error> {
+        if command.virtual_placement {
+            return self.place_virtual_kitty_graphic(command, image_id);
         }
-        // Move vi mode cursor with the content.
-        let history_size = self.history_size();
-        let mut delta = num_lines as i32 - old_lines as i32;
-        let min_delta =
-            std::cmp::min(0, num_lines as i32 - self.grid.cursor.pos.row.0 - 1);
 
-        delta = std::cmp::min(std::cmp::max(delta, min_delta), history_size as i32);
-        self.vi_mode_cursor.pos.row += delta;
+        let Some(image) = self.kitty_graphics.image(image_id) else {
+            return Err(kitty_graphics::Error::not_found("no such image"));
+        };
+        let graphic = kitty_graphics::fit(
+            image,
+            command,
+            self.graphics.cell_width as u32,
+            self.graphics.cell_height as u32,
+        )?;
+
+        let origin = self.grid.cursor.pos;
+        let Some((graphic_id, graphic_columns)) =
+            self.place_graphic(graphic, origin, !command.hold_cursor)
+        else {
+            return Err(kitty_graphics::Error::invalid("image can't be displayed"));
+        };
 
-        let is_alt = self.mode.contains(Mode::ALT_SCREEN);
-        self.grid.resize(!is_alt, num_lines, num_cols);
-        self.inactive_grid.resize(is_alt, num_lines, num_cols);
+        if !command.hold_cursor {
+            self.move_forward(Column(graphic_columns));
+        }
 
-        // Invalidate selection and tabs only when necessary.
-        if old_cols != num_cols {
-            self.selection = None;
+        let replaced = self
+            .kitty_graphics
+            .add_placement(kitty_graphics::Placement {
+                image_id,
+                placement_id: command.placement_id,
+                graphic_id,
+                virtual_cells: None,
+            });
+        if let Some(replaced) = replaced {
+            self.remove_graphics(&[replaced.graphic_id]);
+        }
 
-            // Recreate tabs list.
-            self.tabs.resize(num_cols);
-        } else if let Some(selection) = self.selection.take() {
-            let max_lines = std::cmp::max(num_lines, old_lines) as i32;
-            let range = Line(0)..Line(max_lines);
-            self.selection = selection.rotate(&self.grid, &range, -delta);
+        Ok(())
+    }
+
+    /// Keep a kitty graphics image to be displayed where the client prints
+    /// Unicode placeholders, nothing is drawn at the cursor.
+    fn place_virtual_kitty_graphic(
+        &mut self,
+        command: &kitty_graphics::Command,
+        image_id: u32,
+    ) -> Result<(), kitty_graphics::Error> {
+        let Some(image) = self.kitty_graphics.image(image_id) else {
+            return Err(kitty_graphics::Error::not_found("no such image"));
+        };
+        let cell_width = self.graphics.cell_width as u32;
+        let cell_height = self.graphics.cell_height as u32;
+        if cell_width == 0 || cell_height == 0 {
+            return Err(kitty_graphics::Error::invalid("image can't be displayed"));
         }
 
-        // Clamp vi cursor to viewport.
-        let vi_pos = self.vi_mode_cursor;
-        let viewport_top = Line(-(self.grid.display_offset() as i32));
-        let viewport_bottom = viewport_top + self.bottommost_line();
-        self.vi_mode_cursor.pos.row =
-            std::cmp::max(std::cmp::min(vi_pos.pos.row, viewport_bottom), viewport_top);
-        self.vi_mode_cursor.pos.col =
-            std::cmp::min(vi_pos.pos.col, self.grid.last_column());
+        // Placeholders cover whole cells, the image is scaled to them.
+        let columns = match command.columns {
+            0 => (image.width as u32).div_ceil(cell_width),
+            columns => columns,
+        };
+        let rows = match command.rows {
+            0 => (image.height as u32).div_ceil(cell_height),
+            rows => rows,
+        };
+        let sized = kitty_graphics::Command {
+            columns,
+            rows,
+            payload: Vec::new(),
+            ..command.clone()
+        };
+        let graphic = kitty_graphics::fit(image, &sized, cell_width, cell_height)?;
 
-        // Reset scrolling region.
-        self.scroll_region = Line(0)..Line(self.grid.screen_lines() as i32);
+        let graphic_id = self.graphics.next_id();
+        let texture = Arc::new(TextureRef {
+            id: graphic_id,
+            width: graphic.width as u16,
+            height: graphic.height as u16,
+            cell_height: cell_height as usize,
+            texture_operations: Arc::downgrade(&self.graphics.texture_operations),
+        });
+        self.graphics.pending.push(GraphicData {
+            id: graphic_id,
+            ..graphic
+        });
+        self.send_event(RioEvent::UpdateGraphicLibrary);
+
+        let replaced = self
+            .kitty_graphics
+            .add_placement(kitty_graphics::Placement {
+                image_id,
+                placement_id: command.placement_id,
+                graphic_id,
+                virtual_cells: Some(kitty_graphics::VirtualCells {
+                    texture,
+                    columns,
+                    rows,
+                    cell_width: cell_width as u16,
+                    cell_height: cell_height as u16,
+                }),
+            });
+        if let Some(replaced) = replaced {
+            self.remove_graphics(&[replaced.graphic_id]);
+        }
 
-        // Resize damage information.
-        self.damage.resize(num_cols, num_lines);
+        // Placeholders may be printed before the placement exists.
+        for line in 0..self.grid.screen_lines() {
+            for column in 0..self.grid.columns() {
+                let pos = Pos::new(Line(line as i32), Column(column));
+                if self.grid[pos].c == kitty_graphics::PLACEHOLDER {
+                    self.update_placeholder(pos);
+                }
+            }
+        }
 
-        // Update size information for graphics.
-        self.graphics.resize(&size);
+        Ok(())
     }
 
-    /// Toggle the vi mode.
-    #[inline]
-    pub fn toggle_vi_mode(&mut self)
-    where
-        U: EventListener,
-    {
-        self.mode ^= Mode::VI;
+    /// Draw the part of a virtual placement the placeholder at `pos`
+    /// stands for. Its foreground color holds the image id, its underline
+    /// color the placement id and its diacritics the row, the column and
+    /// the most significant byte of the image id. Missing diacritics
+    /// continue the placeholder on the left.
+    fn update_placeholder(&mut self, pos: Pos) {
+        let color_value = |color: AnsiColor| match color {
+            AnsiColor::Spec(rgb) => {
+                Some((rgb.r as u32) << 16 | (rgb.g as u32) << 8 | rgb.b as u32)
+            }
+            AnsiColor::Indexed(index) => Some(index as u32),
+            AnsiColor::Named(_) => None,
+        };
 
-        if self.mode.contains(Mode::VI) {
-            let display_offset = self.grid.display_offset() as i32;
-            if self.grid.cursor.pos.row > self.grid.bottommost_line() - display_offset {
-                // Move cursor to top-left if terminal cursor is not visible.
-                let pos = Pos::new(Line(-display_offset), Column(0));
-                self.vi_mode_cursor.pos = pos;
-            } else {
-                // Reset vi mode cursor position to match primary cursor.
-                self.vi_mode_cursor.pos = self.grid.cursor.pos;
+        let square = &self.grid[pos];
+        let image_low = color_value(square.fg);
+        let placement_id = square
+            .underline_color()
+            .and_then(color_value)
+            .unwrap_or_default();
+        let mut diacritics = square
+            .zerowidth()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|c| kitty_graphics::diacritic_value(*c));
+        let (row, column, msb) =
+            (diacritics.next(), diacritics.next(), diacritics.next());
+
+        // Row, column and image id of the placeholder on the left.
+        let left = (pos.col.0 > 0)
+            .then(|| &self.grid[pos.row][pos.col - 1])
+            .filter(|left| {
+                left.c == kitty_graphics::PLACEHOLDER
+                    && left.fg == square.fg
+                    && left.underline_color() == square.underline_color()
+            })
+            .and_then(|left| left.graphics()?.first())
+            .and_then(|graphic| {
+                let placement = self.kitty_graphics.placement_of(graphic.texture.id)?;
+                let cells = placement.virtual_cells.as_ref()?;
+                Some((
+                    graphic.offset_y as u32 / cells.cell_height as u32,
+                    graphic.offset_x as u32 / cells.cell_width as u32,
+                    placement.image_id,
+                ))
+            });
+
+        let row = row.or(left.map(|(row, _, _)| row)).unwrap_or_default();
+        let left = left.filter(|(left_row, _, _)| *left_row == row);
+        let column = column
+            .or(left.map(|(_, column, _)| column + 1))
+            .unwrap_or_default();
+        let image_id = match (image_low, msb, left) {
+            (Some(low), Some(msb), _) => Some(low | msb << 24),
+            (Some(_), None, Some((_, _, image_id))) => Some(image_id),
+            (low, None, _) => low,
+            (None, Some(_), _) => None,
+        };
+
+        let graphic = image_id
+            .and_then(|image_id| {
+                self.kitty_graphics
+                    .virtual_placement(image_id, placement_id)
+            })
+            .filter(|cells| row < cells.rows && column < cells.columns)
+            .map(|cells| GraphicCell {
+                texture: cells.texture.clone(),
+                offset_x: (column * cells.cell_width as u32) as u16,
+                offset_y: (row * cells.cell_height as u32) as u16,
+                texture_operations: Arc::downgrade(&self.graphics.texture_operations),
+            });
+
+        let square = &mut self.grid[pos];
+        match graphic {
+            Some(graphic) => square.set_graphics(smallvec::smallvec![graphic]),
+            None => {
+                if square.take_graphics().is_some() {
+                    square.flags.remove(square::Flags::GRAPHICS);
+                }
             }
         }
-
-        // Update UI about cursor blinking state changes.
-        self.event_proxy
-            .send_event(RioEvent::CursorBlinkingChange, self.window_id);
+        self.damage.damage_point(pos);
     }
 
-    /// Update the active selection to match the vi mode cursor position.
-    #[inline]
-    fn vi_mode_recompute_selection(&mut self) {
-        // Require vi mode to be active.
-        if !self.mode.contains(Mode::VI) {
-            return;
-        }
+    /// Delete kitty graphics placements, uppercase targets also free the
+    /// image data once no placement uses it.
+    ///
+    /// Targets picking cells use the `x` and `y` keys, 1-based, and only
+    /// see the screen. Z-index and animation targets are not supported.
+    fn delete_kitty_graphics(&mut self, command: &kitty_graphics::Command) {
+        let screen_lines = self.grid.screen_lines();
+        let columns = self.grid.columns();
+        let column = (command.source_x as usize).saturating_sub(1);
+        let line = (command.source_y as usize).saturating_sub(1);
 
-        // Update only if non-empty selection is present.
-        if let Some(selection) = self.selection.as_mut().filter(|s| !s.is_empty()) {
-            selection.update(self.vi_mode_cursor.pos, Side::Left);
-            selection.include_all();
+        let removed = match command.delete.to_ascii_lowercase() {
+            b'a' => self.kitty_graphics.remove_placements(|_| true),
+            b'i' | b'n' => {
+                let Some(image_id) = self.kitty_graphics.image_id(command) else {
+                    return;
+                };
+                self.kitty_graphics.remove_placements(|placement| {
+                    placement.image_id == image_id
+                        && (command.placement_id == 0
+                            || placement.placement_id == command.placement_id)
+                })
+            }
+            b'r' => {
+                // `x` and `y` are the first and last image ids here.
+                let ids = command.source_x..=command.source_y;
+                self.kitty_graphics
+                    .remove_placements(|placement| ids.contains(&placement.image_id))
+            }
+            target @ (b'c' | b'p' | b'x' | b'y') => {
+                let cells: Vec<Pos> = match target {
+                    b'c' => vec![self.grid.cursor.pos],
+                    b'p' if line < screen_lines && column < columns => {
+                        vec![Pos::new(Line(line as i32), Column(column))]
+                    }
+                    b'x' if column < columns => (0..screen_lines)
+                        .map(|line| Pos::new(Line(line as i32), Column(column)))
+                        .collect(),
+                    b'y' if line < screen_lines => (0..columns)
+                        .map(|column| Pos::new(Line(line as i32), Column(column)))
+                        .collect(),
+                    _ => return,
+                };
+                let graphic_ids: Vec<GraphicId> = cells
+                    .into_iter()
+                    .filter_map(|pos| self.grid[pos].graphics())
+                    .flat_map(|graphics| {
+                        graphics.iter().map(|graphic| graphic.texture.id)
+                    })
+                    .collect();
+                self.kitty_graphics.remove_placements(|placement| {
+                    graphic_ids.contains(&placement.graphic_id)
+                })
+            }
+            other => {
+                debug!(
+                    "Unsupported kitty graphics delete target {:?}",
+                    other as char
+                );
+                return;
+            }
+        };
+
+        let graphic_ids: Vec<GraphicId> = removed
+            .iter()
+            .map(|placement| placement.graphic_id)
+            .collect();
+        self.remove_graphics(&graphic_ids);
+
+        if command.delete.is_ascii_uppercase() {
+            for placement in removed {
+                if !self.kitty_graphics.is_placed(placement.image_id) {
+                    self.kitty_graphics.remove_image(placement.image_id);
+                }
+            }
         }
     }
 
-    #[inline]
-    pub fn vi_motion(&mut self, motion: ViMotion)
-    where
-        U: EventListener,
-    {
-        // Require vi mode to be active.
-        if !self.mode.contains(Mode::VI) {
+    /// Remove the graphics in `graphic_ids` from every cell, including the
+    /// scrollback history.
+    fn remove_graphics(&mut self, graphic_ids: &[GraphicId]) {
+        if graphic_ids.is_empty() {
             return;
         }
 
-        // Move cursor.
-        self.vi_mode_cursor = self.vi_mode_cursor.motion(self, motion);
-        self.vi_mode_recompute_selection();
-    }
-
-    /// Move vi cursor to a point in the grid.
-    #[inline]
-    pub fn vi_goto_pos(&mut self, pos: Pos)
-    where
-        U: EventListener,
-    {
-        // Move viewport to make pos visible.
-        self.scroll_to_pos(pos);
+        let topmost = self.grid.topmost_line().0;
+        let bottommost = self.grid.bottommost_line().0;
+        let columns = self.grid.columns();
+        for line in (topmost..=bottommost).map(Line) {
+            for column in 0..columns {
+                let square = &mut self.grid[line][Column(column)];
+                if !square.flags.contains(square::Flags::GRAPHICS) {
+                    continue;
+                }
 
-        // Move vi cursor to the pos.
-        self.vi_mode_cursor.pos = pos;
+                if let Some(mut graphics) = square.take_graphics() {
+                    graphics.retain(|graphic| !graphic_ids.contains(&graphic.texture.id));
+                    if graphics.is_empty() {
+                        square.flags.remove(square::Flags::GRAPHICS);
+                    } else {
+                        square.set_graphics(graphics);
+                    }
+                }
+            }
+        }
 
-        self.vi_mode_recompute_selection();
+        self.mark_fully_damaged();
     }
 
-    /// Scroll display to point if it is outside of viewport.
-    #[inline]
-    pub fn scroll_to_pos(&mut self, pos: Pos)
-    where
-        U: EventListener,
-    {
-        let display_offset = self.grid.display_offset() as i32;
-        let screen_lines = self.grid.screen_lines() as i32;
+    /// Fill the cells under `graphic`, starting at `origin`, and queue it
+    /// for the renderer. With `scrolling` the graphic follows the cursor,
+    /// which ends in its last line, scrolling the grid if needed. Otherwise
+    /// the cursor is left alone and the graphic is cut at the bottom of the
+    /// screen.
+    ///
+    /// Returns the graphic identifier and the columns it takes, or `None` if
+    /// it could not be placed.
+    fn place_graphic(
+        &mut self,
+        graphic: GraphicData,
+        origin: Pos,
+        scrolling: bool,
+    ) -> Option<(GraphicId, usize)> {
+        let cell_width = self.graphics.cell_width as usize;
+        let cell_height = self.graphics.cell_height as usize;
 
-        if pos.row < -display_offset {
-            let lines = pos.row + display_offset;
-            self.scroll_display(Scroll::Delta(-lines.0));
-        } else if pos.row >= (screen_lines - display_offset) {
-            let lines = pos.row + display_offset - screen_lines + 1i32;
-            self.scroll_display(Scroll::Delta(-lines.0));
+        if graphic.width > MAX_GRAPHIC_DIMENSIONS[0]
+            || graphic.height > MAX_GRAPHIC_DIMENSIONS[1]
+            || cell_width == 0
+            || cell_height == 0
+        {
+            return None;
         }
-    }
 
-    /// Jump to the end of a wide cell.
-    pub fn expand_wide(&self, mut pos: Pos, direction: Direction) -> Pos {
-        let flags = self.grid[pos.row][pos.col].flags;
+        let width = graphic.width as u16;
+        let height = graphic.height as u16;
 
-        match direction {
-            Direction::Right
-                if flags.contains(square::Flags::LEADING_WIDE_CHAR_SPACER) =>
-            {
-                pos.col = Column(1);
-                pos.row += 1;
-            }
-            Direction::Right if flags.contains(square::Flags::WIDE_CHAR) => {
-                pos.col = std::cmp::min(pos.col + 1, self.grid.last_column());
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        let graphic_id = self.graphics.next_id();
+        let graphic_columns = graphic.width.div_ceil(cell_width);
+
+        let leftmost = origin.col.0;
+
+        // A very simple optimization is to detect is a new graphic is replacing
+        // completely a previous one. This happens if the following conditions
+        // are met:
+        //
+        // - Both graphics are attached to the same top-left cell.
+        // - Both graphics have the same size.
+        // - The new graphic does not contain transparent pixels.
+        //
+        // In this case, we will ignore cells with a reference to the replaced
+        // graphic.
+
+        let skip_textures = {
+            if graphic.maybe_transparent() {
+                HashSet::new()
+            } else {
+                let mut set = HashSet::new();
+
+                if let Some(old_graphics) =
+                    self.grid[origin.row][Column(leftmost)].graphics()
+                {
+                    for graphic in old_graphics {
+                        let tex = &*graphic.texture;
+                        if tex.width == width
+                            && tex.height == height
+                            && tex.cell_height == cell_height
+                        {
+                            set.insert(tex.id);
+                        }
+                    }
+                }
+
+                set
             }
-            Direction::Left
-                if flags.intersects(
-                    square::Flags::WIDE_CHAR | square::Flags::WIDE_CHAR_SPACER,
-                ) =>
-            {
-                if flags.contains(square::Flags::WIDE_CHAR_SPACER) {
-                    pos.col -= 1;
+        };
+
+        // Fill the cells under the graphic.
+        //
+        // The cell in the first column contains a reference to the
+        // graphic, with the offset from the start. The rest of the
+        // cells are not overwritten, allowing any text behind
+        // transparent portions of the image to be visible.
+
+        let texture = Arc::new(TextureRef {
+            id: graphic_id,
+            width,
+            height,
+            cell_height,
+            texture_operations: Arc::downgrade(&self.graphics.texture_operations),
+        });
+
+        for (top, offset_y) in (0..).zip((0..height).step_by(cell_height)) {
+            let line = if scrolling {
+                self.grid.cursor.pos.row
+            } else {
+                let line = origin.row + top;
+
+                // Check if the image is beyond the screen limit.
+                if line > self.grid.bottommost_line() {
+                    break;
                 }
 
-                let prev = pos.sub(&self.grid, Boundary::Grid, 1);
-                if self.grid[prev]
-                    .flags
-                    .contains(square::Flags::LEADING_WIDE_CHAR_SPACER)
-                {
-                    pos = prev;
+                line
+            };
+
+            // Store a reference to the graphic in the first column.
+            let row_len = self.grid[line].len();
+            for (left, offset_x) in (leftmost..).zip((0..width).step_by(cell_width)) {
+                if left >= row_len {
+                    break;
                 }
+
+                let texture_operations =
+                    Arc::downgrade(&self.graphics.texture_operations);
+                let graphic_cell = GraphicCell {
+                    texture: texture.clone(),
+                    offset_x,
+                    offset_y,
+                    texture_operations,
+                };
+
+                let mut cell = self.grid.cursor.template.clone();
+                let cell_ref = &mut self.grid[line][Column(left)];
+
+                // If the cell contains any graphics, and the region of the cell
+                // is not fully filled by the new graphic, the old graphics are
+                // kept in the cell.
+                let graphics = match cell_ref.take_graphics() {
+                    Some(mut old_graphics)
+                        if old_graphics.iter().any(|graphic| {
+                            !skip_textures.contains(&graphic.texture.id)
+                        }) && !graphic.is_filled(
+                            offset_x as usize,
+                            offset_y as usize,
+                            cell_width,
+                            cell_height,
+                        ) =>
+                    {
+                        // Ensure that we don't exceed the graphics limit per cell.
+                        while old_graphics.len() >= MAX_GRAPHICS_PER_CELL {
+                            drop(old_graphics.remove(0));
+                        }
+
+                        old_graphics.push(graphic_cell);
+                        old_graphics
+                    }
+
+                    _ => smallvec::smallvec![graphic_cell],
+                };
+
+                cell.set_graphics(graphics);
+                *cell_ref = cell;
+
+                self.damage
+                    .damage_point(Pos::new((line.0 as usize).into(), Column(left)));
+            }
+
+            if scrolling && offset_y < height.saturating_sub(cell_height as u16) {
+                self.linefeed();
             }
-            _ => (),
         }
 
-        pos
+        // Add the graphic data to the pending queue.
+        self.graphics.pending.push(GraphicData {
+            id: graphic_id,
+            ..graphic
+        });
+
+        self.send_event(RioEvent::UpdateGraphicLibrary);
+
+        Some((graphic_id, graphic_columns))
     }
 
+    /// Flip bracketed paste without the application asking for it, the
+    /// application may still change it with `DECSET 2004`.
     #[inline]
-    pub fn semantic_escape_chars(&self) -> &str {
-        &self.semantic_escape_chars
+    pub fn toggle_bracketed_paste(&mut self) {
+        self.mode ^= Mode::BRACKETED_PASTE;
     }
 
+    /// Scroll just enough to make the terminal cursor line visible.
     #[inline]
-    pub fn wrapline(&mut self) {
-        if !self.mode.contains(Mode::LINE_WRAP) {
-            return;
+    pub fn scroll_to_cursor(&mut self) {
+        let max_offset = (self.grid.bottommost_line() - self.grid.cursor.pos.row).0;
+        let display_offset = self.grid.display_offset() as i32;
+        if display_offset > max_offset {
+            self.scroll_display(Scroll::Delta(max_offset - display_offset));
         }
+    }
 
-        self.grid
-            .cursor_cell()
-            .flags
-            .insert(square::Flags::WRAPLINE);
+    /// Walk back through the history and scroll the nearest prompt above
+    /// the viewport to its top. Returns `false` if there are no more prompts.
+    pub fn scroll_to_next_prompt(&mut self) -> bool {
+        let top = -(self.grid.display_offset() as i32);
+        let topmost = self.grid.topmost_line().0;
+        let target = (topmost..top)
+            .rev()
+            .find(|line| self.grid[Line(*line)].flags.contains(RowFlag::PROMPT_START));
 
-        if self.grid.cursor.pos.row + 1 >= self.scroll_region.end {
-            self.linefeed();
+        match target {
+            Some(line) => {
+                self.scroll_display(Scroll::Delta(top - line));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Counterpart of [`Crosswords::scroll_to_next_prompt`], scroll the
+    /// nearest prompt below the top of the viewport to the top, as far as
+    /// the history allows. Returns `false` if the viewport did not move.
+    pub fn scroll_to_prev_prompt(&mut self) -> bool {
+        let top = -(self.grid.display_offset() as i32);
+        let bottommost = self.grid.bottommost_line().0;
+        let target = (top + 1..=bottommost)
+            .find(|line| self.grid[Line(*line)].flags.contains(RowFlag::PROMPT_START));
+
+        match target {
+            Some(line) if top < 0 => {
+                self.scroll_display(Scroll::Delta(top - std::cmp::min(line, 0)));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Rows printed by the last command: the ones between the two latest
+    /// prompts marked by OSC 133, without the prompt rows themselves.
+    /// Returns `None` if the command printed nothing.
+    pub fn last_output_range(&self) -> Option<(Pos, Pos)> {
+        let topmost = self.grid.topmost_line().0;
+        let bottommost = self.grid.bottommost_line().0;
+        let mut prompts = (topmost..=bottommost)
+            .rev()
+            .filter(|line| self.grid[Line(*line)].flags.contains(RowFlag::PROMPT_START));
+        let current = prompts.next()?;
+        let previous = prompts.next()?;
+
+        let (start, end) = (Line(previous + 1), Line(current - 1));
+        (start <= end).then(|| {
+            (
+                Pos::new(start, Column(0)),
+                Pos::new(end, self.grid.last_column()),
+            )
+        })
+    }
+
+    /// Line of the last row holding non-whitespace content, searching up
+    /// from the bottom of the viewport and then through the history, so
+    /// the content is still found after a `clear`. Returns `None` if the
+    /// buffer is blank.
+    pub fn get_last_output_line(&self) -> Option<i32> {
+        let bottom = self.grid.bottommost_line().0 - self.grid.display_offset() as i32;
+        let topmost = self.grid.topmost_line().0;
+        (topmost..=bottom).rev().find(|line| {
+            self.grid[Line(*line)][..]
+                .iter()
+                .any(|square| !square.c.is_whitespace() && square.c != '\0')
+        })
+    }
+
+    /// Write the primary screen and its scrollback as a session snapshot,
+    /// see [`crate::session`].
+    pub fn save_session<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        let primary = if self.mode.contains(Mode::ALT_SCREEN) {
+            &self.inactive_grid
         } else {
-            self.damage_cursor();
-            self.grid.cursor.pos.row += 1;
+            &self.grid
+        };
+        crate::session::write_session(primary, writer)
+    }
+
+    /// Size a session has to be read with, see [`crate::session::read_session`].
+    pub fn session_size(&self) -> crate::session::SessionSize {
+        let primary = if self.mode.contains(Mode::ALT_SCREEN) {
+            &self.inactive_grid
+        } else {
+            &self.grid
+        };
+        crate::session::SessionSize {
+            lines: primary.screen_lines(),
+            columns: primary.columns(),
+            scrollback: primary.max_scroll_limit(),
         }
+    }
 
-        self.grid.cursor.pos.col = Column(0);
-        self.grid.cursor.should_wrap = false;
-        self.damage_cursor();
+    /// Bring back a session into the scrollback of the primary screen, the
+    /// screen itself and the running program are untouched.
+    pub fn apply_session(&mut self, session: crate::session::Session) {
+        let primary = if self.mode.contains(Mode::ALT_SCREEN) {
+            &mut self.inactive_grid
+        } else {
+            &mut self.grid
+        };
+        session.restore(primary);
+
+        self.selection = None;
+        self.vi_mode_cursor.pos.row = self
+            .vi_mode_cursor
+            .pos
+            .row
+            .grid_clamp(&self.grid, Boundary::Cursor);
+        self.mark_fully_damaged();
     }
 
-    pub fn history_size(&self) -> usize {
-        self.grid
-            .total_lines()
-            .saturating_sub(self.grid.screen_lines())
+    /// Read a session snapshot and bring it back, see [`Self::apply_session`].
+    pub fn restore_session<R: std::io::Read>(
+        &mut self,
+        reader: &mut R,
+    ) -> std::io::Result<()> {
+        let session = crate::session::read_session(reader, self.session_size())?;
+        self.apply_session(session);
+        Ok(())
     }
 
+    /// Drop the scrollback and jump back to the bottom of the terminal.
     #[inline]
-    fn damage_cursor(&mut self) {
-        // The normal cursor coordinates are always in viewport.
-        let point = Pos::new(Line(self.grid.cursor.pos.row.0), self.grid.cursor.pos.col);
-        self.damage.damage_point(point);
+    pub fn clear_history(&mut self) {
+        self.scroll_display(Scroll::Bottom);
+        self.drop_history();
     }
 
-    #[inline]
-    fn scroll_down_relative(&mut self, origin: Line, mut lines: usize) {
-        debug!(
-            "Scrolling down relative: origin={}, lines={}",
-            origin, lines
-        );
+    fn drop_history(&mut self) {
+        if self.history_size() == 0 {
+            return;
+        }
 
-        lines = std::cmp::min(
-            lines,
-            (self.scroll_region.end - self.scroll_region.start).0 as usize,
-        );
-        lines = std::cmp::min(lines, (self.scroll_region.end - origin).0 as usize);
+        self.grid.clear_history();
 
-        let region = origin..self.scroll_region.end;
+        self.vi_mode_cursor.pos.row = self
+            .vi_mode_cursor
+            .pos
+            .row
+            .grid_clamp(&self.grid, Boundary::Cursor);
 
-        // Scroll selection.
         self.selection = self
             .selection
             .take()
-            .and_then(|s| s.rotate(&self.grid, &region, -(lines as i32)));
-
-        // Scroll vi mode cursor.
-        let line = &mut self.vi_mode_cursor.pos.row;
-        if region.start <= *line && region.end > *line {
-            *line = std::cmp::min(*line + lines, region.end - 1);
-        }
-
-        // Scroll between origin and bottom
-        self.grid.scroll_down(&region, lines);
+            .filter(|s| !s.intersects_range(..Line(0)));
         self.mark_fully_damaged();
     }
 
-    #[inline]
-    pub fn scroll_up_relative(&mut self, origin: Line, mut lines: usize) {
-        debug!("Scrolling up: origin={origin}, lines={lines}");
-
-        lines = std::cmp::min(
-            lines,
-            (self.scroll_region.end - self.scroll_region.start).0 as usize,
-        );
-
-        let region = origin..self.scroll_region.end;
+    /// Clear the visible lines without touching the scrollback, the
+    /// cursor line is kept and moved to the top of the screen.
+    pub fn clear_viewport(&mut self) {
+        self.scroll_display(Scroll::Bottom);
 
-        // Scroll selection.
+        let cursor_row = self.grid.cursor.pos.row;
+        let cursor_line = self.grid[cursor_row].clone();
+        self.grid.reset_region(..);
+        self.grid[Line(0)] = cursor_line;
+        self.grid.cursor.pos.row = Line(0);
+
+        self.vi_mode_cursor.pos.row = self
+            .vi_mode_cursor
+            .pos
+            .row
+            .grid_clamp(&self.grid, Boundary::Cursor);
         self.selection = self
             .selection
             .take()
-            .and_then(|s| s.rotate(&self.grid, &region, lines as i32));
-
-        self.grid.scroll_up(&region, lines);
-
-        // Scroll vi mode cursor.
-        let viewport_top = Line(-(self.grid.display_offset() as i32));
-        let top = if region.start == 0 {
-            viewport_top
-        } else {
-            region.start
-        };
-        let line = &mut self.vi_mode_cursor.pos.row;
-        if (top <= *line) && region.end > *line {
-            *line = std::cmp::max(*line - lines, top);
-        }
+            .filter(|s| !s.intersects_range(Line(0)..));
         self.mark_fully_damaged();
     }
 
     #[inline]
-    pub fn search_nearest_hyperlink_from_pos(
-        &mut self,
-        pos: Pos,
-    ) -> Option<SelectionRange> {
-        // Limit the starting pos to the last line in the history
-        let wide = square::Flags::WIDE_CHAR
-            | square::Flags::WIDE_CHAR_SPACER
-            | square::Flags::LEADING_WIDE_CHAR_SPACER;
+    pub fn scroll_display(&mut self, scroll: Scroll) {
+        let old_display_offset = self.grid.display_offset();
+        self.send_event(RioEvent::MouseCursorDirty);
+        self.grid.scroll_display(scroll);
 
-        let last_column = self.grid.columns() - 1;
-        if pos.col > last_column {
-            return None;
-        }
+        // Clamp vi mode cursor to the viewport.
+        let viewport_start = -(self.grid.display_offset() as i32);
+        let viewport_end = viewport_start + self.grid.bottommost_line().0;
+        let vi_cursor_line = &mut self.vi_mode_cursor.pos.row.0;
+        *vi_cursor_line =
+            std::cmp::min(viewport_end, std::cmp::max(viewport_start, *vi_cursor_line));
+        self.vi_mode_recompute_selection();
 
-        let first_column = 0;
-        let last_row = self.grid.screen_lines();
-        let starting_square: &Square = &self.grid[pos];
+        // Damage everything if display offset changed.
+        if old_display_offset != self.grid.display_offset() {
+            self.mark_fully_damaged();
 
-        let is_existent_hyperlink = starting_square.hyperlink().is_some();
-        if !is_existent_hyperlink && starting_square.c == ' ' {
-            return None;
+            self.send_event(RioEvent::Render);
         }
+    }
 
-        let mut content: std::collections::VecDeque<char> =
-            std::collections::VecDeque::from([starting_square.c]);
-        // TODO: Remove positions_to_update and fully rely on
-        // selection_end and selection_start
-        let mut positions_to_update: Vec<Pos> = vec![pos];
-        let mut selection_start: Pos = pos;
-        let mut selection_end: Pos = pos;
-
-        // Next adjacents squares
-        for square in self.grid.iter_from(pos) {
-            if square.hyperlink().is_some() {
-                content.push_back(square.c);
-                selection_end = square.pos;
-                positions_to_update.push(square.pos);
-                continue;
-            }
+    #[inline]
+    pub fn bottommost_line(&self) -> Line {
+        self.grid.bottommost_line()
+    }
 
-            if square.flags.intersects(wide) || square.c == ' ' {
-                break;
-            }
+    #[inline]
+    pub fn colors(&self) -> List {
+        self.colors
+    }
 
-            content.push_back(square.c);
-            selection_end = square.pos;
-            positions_to_update.push(square.pos);
+    /// Get queues to update graphic data. If both queues are empty, it returns
+    /// `None`.
+    #[inline]
+    pub fn graphics_take_queues(&mut self) -> Option<UpdateQueues> {
+        self.graphics.take_queues()
+    }
 
-            if pos.col == last_column && pos.row == last_row {
-                break; // cut off if on new line or hit escape char
-            }
-        }
+    #[inline]
+    pub fn exit(&mut self)
+    where
+        U: EventListener,
+    {
+        self.send_event(RioEvent::CloseTerminal(self.route_id));
+    }
 
-        // Previous adjacents squares
-        let mut iter = self.grid.iter_from(pos);
-        while let Some(square) = iter.prev() {
-            if square.hyperlink().is_some() {
-                content.push_front(square.c);
-                selection_start = square.pos;
-                positions_to_update.push(square.pos);
-                continue;
-            }
+    pub fn resize<S: Dimensions>(&mut self, size: S) {
+        let old_cols = self.grid.columns();
+        let old_lines = self.grid.screen_lines();
+        let num_cols = std::cmp::max(size.columns(), MIN_COLUMNS);
+        let num_lines = std::cmp::max(size.screen_lines(), MIN_LINES);
 
-            if square.flags.intersects(wide) || square.c == ' ' {
-                break;
-            }
+        if old_cols == num_cols && old_lines == num_lines {
+            info!("Crosswords::resize dimensions unchanged");
+            return;
+        }
+        // Move vi mode cursor with the content.
+        let history_size = self.history_size();
+        let mut delta = num_lines as i32 - old_lines as i32;
+        let min_delta =
+            std::cmp::min(0, num_lines as i32 - self.grid.cursor.pos.row.0 - 1);
 
-            content.push_front(square.c);
-            selection_start = square.pos;
-            positions_to_update.push(square.pos);
+        delta = std::cmp::min(std::cmp::max(delta, min_delta), history_size as i32);
+        self.vi_mode_cursor.pos.row += delta;
 
-            if square.pos.col == first_column && square.pos.row == 0 {
-                break; // cut off if on new line or hit escape char
-            }
-        }
+        let is_alt = self.mode.contains(Mode::ALT_SCREEN);
+        self.grid.resize(!is_alt, num_lines, num_cols);
+        self.inactive_grid.resize(is_alt, num_lines, num_cols);
 
-        if is_existent_hyperlink {
-            let range = SelectionRange {
-                start: selection_start,
-                end: selection_end,
-                is_block: false,
-            };
-            return Some(range);
-        }
+        // Invalidate selection and tabs only when necessary.
+        if old_cols != num_cols {
+            self.selection = None;
 
-        if content.len() <= 4 {
-            return None;
+            // Recreate tabs list.
+            self.tabs.resize(num_cols);
+        } else if let Some(selection) = self.selection.take() {
+            let max_lines = std::cmp::max(num_lines, old_lines) as i32;
+            let range = Line(0)..Line(max_lines);
+            self.selection = selection.rotate(&self.grid, &range, -delta);
         }
 
-        let value = content.iter().collect::<String>();
-        if let Some(uri) = self.hyperlink_re.find(&value) {
-            let uri = uri.as_str().to_string();
-            let hyperlink = Some(Hyperlink::new(None, uri));
+        // Clamp vi cursor to viewport.
+        let vi_pos = self.vi_mode_cursor;
+        let viewport_top = Line(-(self.grid.display_offset() as i32));
+        let viewport_bottom = viewport_top + self.bottommost_line();
+        self.vi_mode_cursor.pos.row =
+            std::cmp::max(std::cmp::min(vi_pos.pos.row, viewport_bottom), viewport_top);
+        self.vi_mode_cursor.pos.col =
+            std::cmp::min(vi_pos.pos.col, self.grid.last_column());
 
-            for link_pos in positions_to_update.iter() {
-                self.grid[link_pos.row][link_pos.col].set_hyperlink(hyperlink.to_owned());
-            }
+        // Reset scrolling region.
+        self.scroll_region = Line(0)..Line(self.grid.screen_lines() as i32);
 
-            let range = SelectionRange {
-                start: selection_start,
-                end: selection_end,
-                is_block: false,
-            };
-            return Some(range);
-        }
+        // Resize damage information.
+        self.damage.resize(num_cols, num_lines);
 
-        None
+        // Update size information for graphics.
+        self.graphics.resize(&size);
     }
 
-    #[inline(always)]
-    pub fn write_at_cursor(&mut self, c: char) {
-        let c = self.grid.cursor.charsets[self.active_charset].map(c);
-        let fg = self.grid.cursor.template.fg;
-        let bg = self.grid.cursor.template.bg;
-        let flags = self.grid.cursor.template.flags;
-        let extra = self.grid.cursor.template.extra.clone();
-
-        let mut cursor_square = self.grid.cursor_square();
-        if cursor_square
-            .flags
-            .intersects(square::Flags::WIDE_CHAR | square::Flags::WIDE_CHAR_SPACER)
-        {
-            // Remove wide char and spacer.
-            let wide = cursor_square.flags.contains(square::Flags::WIDE_CHAR);
-            let point = self.grid.cursor.pos;
-            if wide && point.col < self.grid.last_column() {
-                self.grid[point.row][point.col + 1]
-                    .flags
-                    .remove(square::Flags::WIDE_CHAR_SPACER);
-            } else if point.col > 0 {
-                self.grid[point.row][point.col - 1].clear_wide();
-            }
+    /// Toggle the vi mode.
+    #[inline]
+    pub fn toggle_vi_mode(&mut self)
+    where
+        U: EventListener,
+    {
+        self.mode ^= Mode::VI;
 
-            // Remove leading spacers.
-            if point.col <= 1 && point.row != self.grid.topmost_line() {
-                let column = self.grid.last_column();
-                self.grid[point.row - 1i32][column]
-                    .flags
-                    .remove(square::Flags::LEADING_WIDE_CHAR_SPACER);
+        if self.mode.contains(Mode::VI) {
+            let display_offset = self.grid.display_offset() as i32;
+            if self.grid.cursor.pos.row > self.grid.bottommost_line() - display_offset {
+                // Move cursor to top-left if terminal cursor is not visible.
+                let pos = Pos::new(Line(-display_offset), Column(0));
+                self.vi_mode_cursor.pos = pos;
+            } else {
+                // Reset vi mode cursor position to match primary cursor.
+                self.vi_mode_cursor.pos = self.grid.cursor.pos;
             }
-
-            cursor_square = self.grid.cursor_cell();
         }
 
-        cursor_square.c = c;
-        cursor_square.fg = fg;
-        cursor_square.bg = bg;
-        cursor_square.flags = flags;
-        cursor_square.extra = extra;
+        // Update UI about cursor blinking state changes.
+        self.send_event(RioEvent::CursorBlinkingChange);
     }
 
+    /// Update the active selection to match the vi mode cursor position.
     #[inline]
-    pub fn visible_rows(&self) -> Vec<Row<Square>> {
-        let mut start = self.scroll_region.start.0;
-        let mut end = self.scroll_region.end.0;
-        let mut visible_rows = Vec::with_capacity(self.grid.screen_lines());
+    fn vi_mode_recompute_selection(&mut self) {
+        // Require vi mode to be active.
+        if !self.mode.contains(Mode::VI) {
+            return;
+        }
 
-        let scroll = self.display_offset() as i32;
-        if scroll != 0 {
-            start -= scroll;
-            end -= scroll;
+        // Update only if non-empty selection is present.
+        if let Some(selection) = self.selection.as_mut().filter(|s| !s.is_empty()) {
+            selection.update(self.vi_mode_cursor.pos, Side::Left);
+            selection.include_all();
         }
+    }
 
-        for row in start..end {
-            visible_rows.push(self.grid[Line(row)].clone());
+    #[inline]
+    pub fn vi_motion(&mut self, motion: ViMotion)
+    where
+        U: EventListener,
+    {
+        // Require vi mode to be active.
+        if !self.mode.contains(Mode::VI) {
+            return;
         }
 
-        visible_rows
+        // Move cursor.
+        self.vi_mode_cursor = self.vi_mode_cursor.motion(self, motion);
+        self.vi_mode_recompute_selection();
     }
 
-    fn deccolm(&mut self)
+    /// Move vi cursor to a point in the grid.
+    #[inline]
+    pub fn vi_goto_pos(&mut self, pos: Pos)
     where
         U: EventListener,
     {
-        // Setting 132 column font makes no sense, but run the other side effects.
-        // Clear scrolling region.
-        self.set_scrolling_region(1, None);
+        // Move viewport to make pos visible.
+        self.scroll_to_pos(pos);
 
-        // Clear grid.
-        self.grid.reset_region(..);
-        self.mark_fully_damaged();
-    }
+        // Move vi cursor to the pos.
+        self.vi_mode_cursor.pos = pos;
 
-    pub fn mode(&self) -> Mode {
-        self.mode
+        self.vi_mode_recompute_selection();
     }
 
+    /// Scroll display to point if it is outside of viewport.
     #[inline]
-    pub fn cursor(&self) -> CursorState {
-        let mut content = self.cursor_shape;
-        let vi_mode = self.mode.contains(Mode::VI);
-        let scroll = self.display_offset() as i32;
-        let mut pos = if vi_mode {
-            let mut vi_cursor_pos = self.vi_mode_cursor.pos;
-            if scroll > 0 {
-                vi_cursor_pos.row += scroll;
-            }
-            vi_cursor_pos
-        } else {
-            if scroll != 0 {
-                content = CursorShape::Hidden;
-            }
-            self.grid.cursor.pos
-        };
-        if self.grid[pos]
-            .flags
-            .contains(square::Flags::WIDE_CHAR_SPACER)
-        {
-            pos.col -= 1;
-        }
-
-        // If the cursor is hidden then set content as hidden
-        if !vi_mode && !self.mode.contains(Mode::SHOW_CURSOR) {
-            content = CursorShape::Hidden;
-        }
+    pub fn scroll_to_pos(&mut self, pos: Pos)
+    where
+        U: EventListener,
+    {
+        let display_offset = self.grid.display_offset() as i32;
+        let screen_lines = self.grid.screen_lines() as i32;
 
-        // If is not using app cursor then use default
-        if content != CursorShape::Hidden && !self.mode.contains(Mode::ALT_SCREEN) {
-            content = self.default_cursor_shape;
+        if pos.row < -display_offset {
+            let lines = pos.row + display_offset;
+            self.scroll_display(Scroll::Delta(-lines.0));
+        } else if pos.row >= (screen_lines - display_offset) {
+            let lines = pos.row + display_offset - screen_lines + 1i32;
+            self.scroll_display(Scroll::Delta(-lines.0));
         }
-
-        CursorState { pos, content }
     }
 
-    pub fn swap_alt(&mut self) {
-        if !self.mode.contains(Mode::ALT_SCREEN) {
-            // Set alt screen cursor to the current primary screen cursor.
-            self.inactive_grid.cursor = self.grid.cursor.clone();
+    /// Jump to the end of a wide cell.
+    pub fn expand_wide(&self, mut pos: Pos, direction: Direction) -> Pos {
+        let flags = self.grid[pos.row][pos.col].flags;
 
-            // Drop information about the primary screens saved cursor.
-            self.grid.saved_cursor = self.grid.cursor.clone();
+        match direction {
+            Direction::Right
+                if flags.contains(square::Flags::LEADING_WIDE_CHAR_SPACER) =>
+            {
+                pos.col = Column(1);
+                pos.row += 1;
+            }
+            Direction::Right if flags.contains(square::Flags::WIDE_CHAR) => {
+                pos.col = std::cmp::min(pos.col + 1, self.grid.last_column());
+            }
+            Direction::Left
+                if flags.intersects(
+                    square::Flags::WIDE_CHAR | square::Flags::WIDE_CHAR_SPACER,
+                ) =>
+            {
+                if flags.contains(square::Flags::WIDE_CHAR_SPACER) {
+                    pos.col -= 1;
+                }
 
-            // Reset alternate screen contents.
-            self.inactive_grid.reset_region(..);
+                let prev = pos.sub(&self.grid, Boundary::Grid, 1);
+                if self.grid[prev]
+                    .flags
+                    .contains(square::Flags::LEADING_WIDE_CHAR_SPACER)
+                {
+                    pos = prev;
+                }
+            }
+            _ => (),
         }
 
-        mem::swap(
-            &mut self.keyboard_mode_stack,
-            &mut self.inactive_keyboard_mode_stack,
-        );
-        self.set_keyboard_mode(
-            self.keyboard_mode_stack
-                .last()
-                .copied()
-                .unwrap_or(KeyboardModes::NO_MODE)
-                .into(),
-            KeyboardModesApplyBehavior::Replace,
-        );
+        pos
+    }
 
-        mem::swap(&mut self.grid, &mut self.inactive_grid);
-        self.mode ^= Mode::ALT_SCREEN;
-        self.selection = None;
-        self.mark_fully_damaged();
+    #[inline]
+    pub fn semantic_escape_chars(&self) -> &str {
+        &self.semantic_escape_chars
     }
 
-    pub fn selection_to_string(&self) -> Option<String> {
-        let selection_range = self.selection.as_ref().and_then(|s| s.to_range(self))?;
-        let SelectionRange { start, end, .. } = selection_range;
+    #[inline]
+    pub fn set_semantic_escape_chars(&mut self, chars: &str) {
+        chars.clone_into(&mut self.semantic_escape_chars);
+    }
 
-        let mut res = String::new();
+    /// Character at the given position, `None` if the position is outside of the grid.
+    #[inline]
+    pub fn char_at(&self, pos: Pos) -> Option<char> {
+        if pos.row < self.grid.topmost_line()
+            || pos.row > self.grid.bottommost_line()
+            || pos.col >= self.grid.columns()
+        {
+            return None;
+        }
 
-        match self.selection.as_ref() {
-            Some(Selection {
-                ty: SelectionType::Block,
-                ..
-            }) => {
-                for line in (start.row.0..end.row.0).map(Line::from) {
-                    res += self
-                        .line_to_string(line, start.col..end.col, start.col.0 != 0)
-                        .trim_end();
-                    res += "\n";
-                }
+        Some(self.grid[pos].c)
+    }
 
-                res += self
-                    .line_to_string(end.row, start.col..end.col, true)
-                    .trim_end();
-            }
-            Some(Selection {
-                ty: SelectionType::Lines,
-                ..
-            }) => {
-                res = self.bounds_to_string(start, end) + "\n";
-            }
-            _ => {
-                res = self.bounds_to_string(start, end);
+    #[inline]
+    pub fn wrapline(&mut self) {
+        if !self.mode.contains(Mode::LINE_WRAP) {
+            return;
+        }
+
+        let mut wrapped_lines = self.wrapped_lines + 1;
+        if self.max_line_length > 0
+            && wrapped_lines.saturating_mul(self.grid.columns()) >= self.max_line_length
+        {
+            // Break the logical line instead of wrapping, so reflow and
+            // selection never have to deal with a line above the limit.
+            wrapped_lines = 0;
+            if !self.line_length_warned {
+                self.line_length_warned = true;
+                warn!(
+                    "Line exceeded {} characters and has been broken",
+                    self.max_line_length
+                );
+                self.send_event(RioEvent::LineLengthExceeded(self.max_line_length));
             }
+        } else {
+            self.grid
+                .cursor_cell()
+                .flags
+                .insert(square::Flags::WRAPLINE);
         }
 
-        Some(res)
+        if self.grid.cursor.pos.row + 1 >= self.scroll_region.end {
+            self.linefeed();
+        } else {
+            self.damage_cursor();
+            self.grid.cursor.pos.row += 1;
+        }
+
+        self.grid.cursor.pos.col = Column(0);
+        self.grid.cursor.should_wrap = false;
+        self.wrapped_lines = wrapped_lines;
+        self.damage_cursor();
     }
 
-    pub fn bounds_to_string(&self, start: Pos, end: Pos) -> String {
-        let mut res = String::new();
+    pub fn history_size(&self) -> usize {
+        self.grid
+            .total_lines()
+            .saturating_sub(self.grid.screen_lines())
+    }
 
-        for line in (start.row.0..=end.row.0).map(Line::from) {
-            let start_col = if line == start.row {
-                start.col
-            } else {
-                Column(0)
-            };
-            let end_col = if line == end.row {
-                end.col
-            } else {
-                self.grid.last_column()
-            };
+    #[inline]
+    fn damage_cursor(&mut self) {
+        // The normal cursor coordinates are always in viewport.
+        let point = Pos::new(Line(self.grid.cursor.pos.row.0), self.grid.cursor.pos.col);
+        self.damage.damage_point(point);
+    }
 
-            res += &self.line_to_string(line, start_col..end_col, line == end.row);
-        }
+    #[inline]
+    fn scroll_down_relative(&mut self, origin: Line, mut lines: usize) {
+        debug!(
+            "Scrolling down relative: origin={}, lines={}",
+            origin, lines
+        );
 
-        res.strip_suffix('\n').map(str::to_owned).unwrap_or(res)
-    }
+        lines = std::cmp::min(
+            lines,
+            (self.scroll_region.end - self.scroll_region.start).0 as usize,
+        );
+        lines = std::cmp::min(lines, (self.scroll_region.end - origin).0 as usize);
 
-    /// Convert a single line in the grid to a String.
-    fn line_to_string(
-        &self,
-        line: Line,
-        mut cols: Range<Column>,
-        include_wrapped_wide: bool,
-    ) -> String {
-        let mut text = String::new();
+        let region = origin..self.scroll_region.end;
 
-        let grid_line = &self.grid[line];
-        let line_length = std::cmp::min(grid_line.line_length(), cols.end + 1);
+        // Scroll selection.
+        self.selection = self
+            .selection
+            .take()
+            .and_then(|s| s.rotate(&self.grid, &region, -(lines as i32)));
 
-        // Include wide char when trailing spacer is selected.
-        if grid_line[cols.start]
-            .flags
-            .contains(square::Flags::WIDE_CHAR_SPACER)
-        {
-            cols.start -= 1;
+        // Scroll vi mode cursor.
+        let line = &mut self.vi_mode_cursor.pos.row;
+        if region.start <= *line && region.end > *line {
+            *line = std::cmp::min(*line + lines, region.end - 1);
         }
 
-        let mut tab_mode = false;
-        for column in (cols.start.0..line_length.0).map(Column::from) {
-            let cell = &grid_line[column];
+        // Scroll between origin and bottom
+        self.grid.scroll_down(&region, lines);
+        self.mark_fully_damaged();
+    }
 
-            // Skip over cells until next tab-stop once a tab was found.
-            if tab_mode {
-                if self.tabs[column] || cell.c != ' ' {
-                    tab_mode = false;
-                } else {
-                    continue;
-                }
-            }
+    #[inline]
+    pub fn scroll_up_relative(&mut self, origin: Line, mut lines: usize) {
+        debug!("Scrolling up: origin={origin}, lines={lines}");
 
-            if cell.c == '\t' {
-                tab_mode = true;
-            }
+        lines = std::cmp::min(
+            lines,
+            (self.scroll_region.end - self.scroll_region.start).0 as usize,
+        );
 
-            if !cell.flags.intersects(
-                square::Flags::WIDE_CHAR_SPACER | square::Flags::LEADING_WIDE_CHAR_SPACER,
-            ) {
-                // Push cells primary character.
-                text.push(cell.c);
+        let region = origin..self.scroll_region.end;
 
-                // Push zero-width characters.
-                for c in cell.zerowidth().into_iter().flatten() {
-                    text.push(*c);
-                }
-            }
+        // Scroll selection.
+        self.selection = self
+            .selection
+            .take()
+            .and_then(|s| s.rotate(&self.grid, &region, lines as i32));
+
+        self.grid.scroll_up(&region, lines);
+
+        // Scroll vi mode cursor.
+        let viewport_top = Line(-(self.grid.display_offset() as i32));
+        let top = if region.start == 0 {
+            viewport_top
+        } else {
+            region.start
+        };
+        let line = &mut self.vi_mode_cursor.pos.row;
+        if (top <= *line) && region.end > *line {
+            *line = std::cmp::max(*line - lines, top);
         }
+        self.mark_fully_damaged();
+    }
 
-        if cols.end >= self.grid.columns() - 1
-            && (line_length.0 == 0
-                || !self.grid[line][line_length - 1]
-                    .flags
-                    .contains(square::Flags::WRAPLINE))
+    /// Use `pattern` to detect URLs, the previous regex is kept if it
+    /// doesn't compile.
+    pub fn set_url_regex(&mut self, pattern: &str) -> Result<(), regex::Error> {
+        self.hyperlink_re = regex::Regex::new(pattern)?;
+        Ok(())
+    }
+
+    /// URI under `pos`, either from an OSC 8 hyperlink or a URL found in
+    /// the text, which can span wrapped lines.
+    pub fn hyperlink_at(&self, pos: Pos) -> Option<String> {
+        if pos.row < self.grid.topmost_line()
+            || pos.row > self.grid.bottommost_line()
+            || pos.col > self.grid.last_column()
         {
-            text.push('\n');
+            return None;
         }
 
-        // If wide char is not part of the selection, but leading spacer is, include it.
-        if line_length == self.grid.columns()
-            && line_length.0 >= 2
-            && grid_line[line_length - 1]
+        if let Some(hyperlink) = self.grid[pos].hyperlink() {
+            return Some(hyperlink.uri().to_owned());
+        }
+
+        self.url_at(pos).map(|(uri, _)| uri)
+    }
+
+    /// URL detected under `pos` and the positions of its characters.
+    fn url_at(&self, pos: Pos) -> Option<(String, Vec<Pos>)> {
+        let last_column = self.grid.last_column();
+        let wraps = |line: Line| {
+            self.grid[line][last_column]
                 .flags
-                .contains(square::Flags::LEADING_WIDE_CHAR_SPACER)
-            && include_wrapped_wide
+                .contains(square::Flags::WRAPLINE)
+        };
+
+        // Lines wrapped into the one under `pos` and the ones it wraps into.
+        let mut start = pos.row;
+        while start > self.grid.topmost_line()
+            && pos.row.0 - start.0 < MAX_URL_LINES as i32
+            && wraps(start - 1)
         {
-            text.push(self.grid[line - 1i32][Column(0)].c);
+            start -= 1;
+        }
+        let mut end = pos.row;
+        while end < self.grid.bottommost_line()
+            && end.0 - pos.row.0 < MAX_URL_LINES as i32
+            && wraps(end)
+        {
+            end += 1;
         }
 
-        text
+        let spacers =
+            square::Flags::WIDE_CHAR_SPACER | square::Flags::LEADING_WIDE_CHAR_SPACER;
+        let mut text = String::new();
+        let mut positions: Vec<(usize, Pos)> = Vec::new();
+        for line in (start.0..=end.0).map(Line) {
+            for column in 0..self.grid.columns() {
+                let square = &self.grid[line][Column(column)];
+                if square.flags.intersects(spacers) {
+                    continue;
+                }
+                positions.push((text.len(), Pos::new(line, Column(column))));
+                text.push(square.c);
+            }
+        }
+
+        // Wide chars are found from their spacer too.
+        let index = positions
+            .iter()
+            .rposition(|(_, char_pos)| *char_pos <= pos)?;
+        let offset = positions[index].0;
+
+        let url = find_urls(&self.hyperlink_re, &text)
+            .into_iter()
+            .find(|url| url.contains(&offset))?;
+        let url_positions = positions
+            .iter()
+            .filter(|(offset, _)| url.contains(offset))
+            .map(|(_, char_pos)| *char_pos)
+            .collect();
+
+        Some((text[url].to_owned(), url_positions))
     }
 
     #[inline]
-    fn set_keyboard_mode(&mut self, mode: Mode, apply: KeyboardModesApplyBehavior) {
-        // println!("{:?}", mode);
-        let active_mode = self.mode & Mode::KEYBOARD_PROTOCOL;
-        self.mode &= !Mode::KEYBOARD_PROTOCOL;
-        let new_mode = match apply {
-            KeyboardModesApplyBehavior::Replace => mode,
-            KeyboardModesApplyBehavior::Union => active_mode.union(mode),
-            KeyboardModesApplyBehavior::Difference => active_mode.difference(mode),
-        };
-        info!("Setting keyboard mode to {new_mode:?}");
-        self.mode |= new_mode;
-    }
-
-    /// Find the beginning of the current line across linewraps.
-    pub fn row_search_left(&self, mut point: Pos) -> Pos {
-        while point.row > self.grid.topmost_line()
-            && self.grid[point.row - 1i32][self.grid.last_column()]
-                .flags
-                .contains(square::Flags::WRAPLINE)
-        {
-            point.row -= 1;
+    pub fn search_nearest_hyperlink_from_pos(
+        &mut self,
+        pos: Pos,
+    ) -> Option<SelectionRange> {
+        if pos.col > self.grid.last_column() {
+            return None;
         }
 
-        point.col = Column(0);
-
-        point
-    }
+        // Detected URLs are stored as hyperlinks in their cells, so they
+        // can be found like OSC 8 ones from now on.
+        if self.grid[pos].hyperlink().is_none() {
+            let (uri, positions) = self.url_at(pos)?;
+            let hyperlink = Some(Hyperlink::new(None, uri));
+            for link_pos in positions.iter() {
+                self.grid[link_pos.row][link_pos.col].set_hyperlink(hyperlink.to_owned());
+            }
 
-    /// Find the end of the current line across linewraps.
-    pub fn row_search_right(&self, mut point: Pos) -> Pos {
-        while point.row + 1 < self.grid.screen_lines()
-            && self.grid[point.row][self.grid.last_column()]
-                .flags
-                .contains(square::Flags::WRAPLINE)
-        {
-            point.row += 1;
+            return Some(SelectionRange {
+                start: *positions.first()?,
+                end: *positions.last()?,
+                is_block: false,
+            });
         }
 
-        point.col = self.grid.last_column();
-
-        point
-    }
-}
+        let hyperlink = self.grid[pos].hyperlink();
+        let mut selection_start: Pos = pos;
+        let mut selection_end: Pos = pos;
 
-impl<U: EventListener> Handler for Crosswords<U> {
-    #[inline]
-    fn set_mode(&mut self, mode: AnsiMode) {
-        match mode {
-            AnsiMode::UrgencyHints => self.mode.insert(Mode::URGENCY_HINTS),
-            AnsiMode::SwapScreenAndSetRestoreCursor => {
-                if !self.mode.contains(Mode::ALT_SCREEN) {
-                    self.swap_alt();
-                }
-            }
-            AnsiMode::ShowCursor => self.mode.insert(Mode::SHOW_CURSOR),
-            AnsiMode::CursorKeys => self.mode.insert(Mode::APP_CURSOR),
-            // Mouse protocols are mutually exclusive.
-            AnsiMode::ReportMouseClicks => {
-                self.mode.remove(Mode::MOUSE_MODE);
-                self.mode.insert(Mode::MOUSE_REPORT_CLICK);
-                self.event_proxy
-                    .send_event(RioEvent::MouseCursorDirty, self.window_id);
-            }
-            AnsiMode::ReportSquareMouseMotion => {
-                self.mode.remove(Mode::MOUSE_MODE);
-                self.mode.insert(Mode::MOUSE_DRAG);
-                self.event_proxy
-                    .send_event(RioEvent::MouseCursorDirty, self.window_id);
-            }
-            AnsiMode::ReportAllMouseMotion => {
-                self.mode.remove(Mode::MOUSE_MODE);
-                self.mode.insert(Mode::MOUSE_MOTION);
-                self.event_proxy
-                    .send_event(RioEvent::MouseCursorDirty, self.window_id);
-            }
-            AnsiMode::ReportFocusInOut => self.mode.insert(Mode::FOCUS_IN_OUT),
-            AnsiMode::BracketedPaste => self.mode.insert(Mode::BRACKETED_PASTE),
-            // Mouse encodings are mutually exclusive.
-            AnsiMode::SgrMouse => {
-                self.mode.remove(Mode::UTF8_MOUSE);
-                self.mode.insert(Mode::SGR_MOUSE);
-            }
-            AnsiMode::Utf8Mouse => {
-                self.mode.remove(Mode::SGR_MOUSE);
-                self.mode.insert(Mode::UTF8_MOUSE);
-            }
-            AnsiMode::AlternateScroll => self.mode.insert(Mode::ALTERNATE_SCROLL),
-            AnsiMode::LineWrap => self.mode.insert(Mode::LINE_WRAP),
-            AnsiMode::LineFeedNewLine => self.mode.insert(Mode::LINE_FEED_NEW_LINE),
-            AnsiMode::Origin => self.mode.insert(Mode::ORIGIN),
-            AnsiMode::Column => self.deccolm(),
-            AnsiMode::Insert => self.mode.insert(Mode::INSERT),
-            AnsiMode::BlinkingCursor => {
-                self.blinking_cursor = true;
-                self.event_proxy
-                    .send_event(RioEvent::CursorBlinkingChange, self.window_id);
-            }
-            AnsiMode::SixelDisplay => self.mode.insert(Mode::SIXEL_DISPLAY),
-            AnsiMode::SixelPrivateColorRegisters => {
-                self.mode.insert(Mode::SIXEL_PRIV_PALETTE)
-            }
-            AnsiMode::SixelCursorToTheRight => {
-                self.mode.insert(Mode::SIXEL_CURSOR_TO_THE_RIGHT);
+        // Next adjacents squares
+        for square in self.grid.iter_from(pos) {
+            if square.hyperlink() != hyperlink {
+                break;
             }
+            selection_end = square.pos;
         }
-    }
 
-    #[inline]
-    fn dynamic_color_sequence(&mut self, prefix: String, index: usize, terminator: &str) {
-        debug!(
-            "Requested write of escape sequence for color code {}: color[{}]",
-            prefix, index
-        );
+        // Previous adjacents squares
+        let mut iter = self.grid.iter_from(pos);
+        while let Some(square) = iter.prev() {
+            if square.hyperlink() != hyperlink {
+                break;
+            }
+            selection_start = square.pos;
+        }
 
-        let terminator = terminator.to_owned();
-        self.event_proxy.send_event(
-            RioEvent::ColorRequest(
-                index,
-                Arc::new(move |color| {
-                    format!(
-                        "\x1b]{};rgb:{1:02x}{1:02x}/{2:02x}{2:02x}/{3:02x}{3:02x}{4}",
-                        prefix, color.r, color.g, color.b, terminator
-                    )
-                }),
-            ),
-            self.window_id,
-        );
+        Some(SelectionRange {
+            start: selection_start,
+            end: selection_end,
+            is_block: false,
+        })
     }
 
-    #[inline]
-    fn unset_mode(&mut self, mode: AnsiMode) {
-        match mode {
-            AnsiMode::UrgencyHints => self.mode.remove(Mode::URGENCY_HINTS),
-            AnsiMode::SwapScreenAndSetRestoreCursor => {
-                if self.mode.contains(Mode::ALT_SCREEN) {
-                    self.swap_alt();
-                }
-            }
-            AnsiMode::ShowCursor => self.mode.remove(Mode::SHOW_CURSOR),
-            AnsiMode::CursorKeys => self.mode.remove(Mode::APP_CURSOR),
-            AnsiMode::ReportMouseClicks => {
-                self.mode.remove(Mode::MOUSE_REPORT_CLICK);
-                self.event_proxy
-                    .send_event(RioEvent::MouseCursorDirty, self.window_id);
-            }
-            AnsiMode::ReportSquareMouseMotion => {
-                self.mode.remove(Mode::MOUSE_DRAG);
-                self.event_proxy
-                    .send_event(RioEvent::MouseCursorDirty, self.window_id);
-            }
-            AnsiMode::ReportAllMouseMotion => {
-                self.mode.remove(Mode::MOUSE_MOTION);
-                self.event_proxy
-                    .send_event(RioEvent::MouseCursorDirty, self.window_id);
-            }
-            AnsiMode::ReportFocusInOut => self.mode.remove(Mode::FOCUS_IN_OUT),
-            AnsiMode::BracketedPaste => self.mode.remove(Mode::BRACKETED_PASTE),
-            AnsiMode::SgrMouse => self.mode.remove(Mode::SGR_MOUSE),
-            AnsiMode::Utf8Mouse => self.mode.remove(Mode::UTF8_MOUSE),
-            AnsiMode::AlternateScroll => self.mode.remove(Mode::ALTERNATE_SCROLL),
-            AnsiMode::LineWrap => self.mode.remove(Mode::LINE_WRAP),
-            AnsiMode::LineFeedNewLine => self.mode.remove(Mode::LINE_FEED_NEW_LINE),
-            AnsiMode::Origin => self.mode.remove(Mode::ORIGIN),
-            AnsiMode::Column => self.deccolm(),
-            AnsiMode::Insert => {
-                self.mode.remove(Mode::INSERT);
-                self.mark_fully_damaged();
-            }
-            AnsiMode::BlinkingCursor => {
-                // TODO: Update it
-                // self.blinking_cursor = false;
-                // self.event_proxy
-                //     .send_event(RioEvent::CursorBlinkingChange, self.window_id);
-            }
-            AnsiMode::SixelDisplay => self.mode.remove(Mode::SIXEL_DISPLAY),
-            AnsiMode::SixelPrivateColorRegisters => {
-                self.graphics.sixel_shared_palette = None;
-                self.mode.remove(Mode::SIXEL_PRIV_PALETTE);
+    #[inline(always)]
+    pub fn write_at_cursor(&mut self, c: char) {
+        let c = self.grid.cursor.charsets[self.active_charset].map(c);
+        let fg = self.grid.cursor.template.fg;
+        let bg = self.grid.cursor.template.bg;
+        let flags = self.grid.cursor.template.flags;
+        let extra = self.grid.cursor.template.extra.clone();
+
+        let mut cursor_square = self.grid.cursor_square();
+        if cursor_square
+            .flags
+            .intersects(square::Flags::WIDE_CHAR | square::Flags::WIDE_CHAR_SPACER)
+        {
+            // Remove wide char and spacer.
+            let wide = cursor_square.flags.contains(square::Flags::WIDE_CHAR);
+            let point = self.grid.cursor.pos;
+            if wide && point.col < self.grid.last_column() {
+                self.grid[point.row][point.col + 1]
+                    .flags
+                    .remove(square::Flags::WIDE_CHAR_SPACER);
+            } else if point.col > 0 {
+                self.grid[point.row][point.col - 1].clear_wide();
             }
-            AnsiMode::SixelCursorToTheRight => {
-                self.mode.remove(Mode::SIXEL_CURSOR_TO_THE_RIGHT)
+
+            // Remove leading spacers.
+            if point.col <= 1 && point.row != self.grid.topmost_line() {
+                let column = self.grid.last_column();
+                self.grid[point.row - 1i32][column]
+                    .flags
+                    .remove(square::Flags::LEADING_WIDE_CHAR_SPACER);
             }
+
+            cursor_square = self.grid.cursor_cell();
         }
+
+        cursor_square.c = c;
+        cursor_square.fg = fg;
+        cursor_square.bg = bg;
+        cursor_square.flags = flags;
+        cursor_square.extra = extra;
     }
 
     #[inline]
-    fn goto(&mut self, line: Line, col: Column) {
-        let (y_offset, max_y) = if self.mode.contains(Mode::ORIGIN) {
-            (self.scroll_region.start, self.scroll_region.end - 1)
-        } else {
-            (Line(0), self.grid.bottommost_line())
-        };
+    pub fn visible_rows(&self) -> Vec<Row<Square>> {
+        let mut start = self.scroll_region.start.0;
+        let mut end = self.scroll_region.end.0;
+        let mut visible_rows = Vec::with_capacity(self.grid.screen_lines());
 
-        self.damage_cursor();
-        self.grid.cursor.pos.row =
-            std::cmp::max(std::cmp::min(line + y_offset, max_y), Line(0));
-        self.grid.cursor.pos.col = std::cmp::min(col, self.grid.last_column());
-        self.damage_cursor();
-        self.grid.cursor.should_wrap = false;
-    }
+        let scroll = self.display_offset() as i32;
+        if scroll != 0 {
+            start -= scroll;
+            end -= scroll;
+        }
 
-    #[inline]
-    fn set_active_charset(&mut self, index: CharsetIndex) {
-        self.active_charset = index;
+        for row in start..end {
+            visible_rows.push(self.grid[Line(row)].clone());
+        }
+
+        visible_rows
     }
 
-    #[inline]
-    fn move_forward(&mut self, cols: Column) {
-        let last_column =
-            std::cmp::min(self.grid.cursor.pos.col + cols, self.grid.last_column());
+    fn deccolm(&mut self)
+    where
+        U: EventListener,
+    {
+        // Setting 132 column font makes no sense, but run the other side effects.
+        // Clear scrolling region.
+        self.set_scrolling_region(1, None);
 
-        let cursor_line = self.grid.cursor.pos.row.0 as usize;
-        self.damage
-            .damage_line(cursor_line, self.grid.cursor.pos.col.0, last_column.0);
+        // Clear grid.
+        self.grid.reset_region(..);
+        self.mark_fully_damaged();
+    }
 
-        self.grid.cursor.pos.col = last_column;
-        self.grid.cursor.should_wrap = false;
+    pub fn mode(&self) -> Mode {
+        self.mode
     }
 
     #[inline]
-    fn move_backward(&mut self, cols: Column) {
-        let column = self.grid.cursor.pos.col.saturating_sub(cols.0);
-
-        let cursor_line = self.grid.cursor.pos.row.0 as usize;
-        self.damage
-            .damage_line(cursor_line, column, self.grid.cursor.pos.col.0);
-
-        self.grid.cursor.pos.col = Column(column);
-        self.grid.cursor.should_wrap = false;
-    }
+    pub fn cursor(&self) -> CursorState {
+        let mut content = self.cursor_shape;
+        let vi_mode = self.mode.contains(Mode::VI);
+        let scroll = self.display_offset() as i32;
+        let mut pos = if vi_mode {
+            let mut vi_cursor_pos = self.vi_mode_cursor.pos;
+            if scroll > 0 {
+                vi_cursor_pos.row += scroll;
+            }
+            vi_cursor_pos
+        } else {
+            if scroll != 0 {
+                content = CursorShape::Hidden;
+            }
+            self.grid.cursor.pos
+        };
+        if self.grid[pos]
+            .flags
+            .contains(square::Flags::WIDE_CHAR_SPACER)
+        {
+            pos.col -= 1;
+        }
 
-    #[inline]
-    fn move_backward_tabs(&mut self, count: u16) {
-        self.damage_cursor();
+        // If the cursor is hidden then set content as hidden
+        if !vi_mode && !self.mode.contains(Mode::SHOW_CURSOR) {
+            content = CursorShape::Hidden;
+        }
 
-        let old_col = self.grid.cursor.pos.col.0;
-        for _ in 0..count {
-            let mut col = self.grid.cursor.pos.col;
-            for i in (0..(col.0)).rev() {
-                if self.tabs[Column(i)] {
-                    col = Column(i);
-                    break;
-                }
-            }
-            self.grid.cursor.pos.col = col;
+        // If is not using app cursor then use default
+        if content != CursorShape::Hidden && !self.mode.contains(Mode::ALT_SCREEN) {
+            content = self.default_cursor_shape;
         }
 
-        let line = self.grid.cursor.pos.row.0 as usize;
-        self.damage
-            .damage_line(line, self.grid.cursor.pos.col.0, old_col);
+        CursorState { pos, content }
     }
 
     #[inline]
-    fn goto_line(&mut self, line: Line) {
-        self.goto(line, self.grid.cursor.pos.col)
+    fn current_cursor_state(&self) -> SavedCursorState {
+        SavedCursorState {
+            active_charset: self.active_charset,
+            origin: self.mode.contains(Mode::ORIGIN),
+        }
     }
 
-    #[inline]
-    fn goto_col(&mut self, col: Column) {
-        self.goto(self.grid.cursor.pos.row, col)
-    }
+    pub fn swap_alt(&mut self) {
+        if !self.mode.contains(Mode::ALT_SCREEN) {
+            // Set alt screen cursor to the current primary screen cursor.
+            self.inactive_grid.cursor = self.grid.cursor.clone();
 
-    #[inline]
-    fn decaln(&mut self) {
-        for line in (0..self.grid.screen_lines()).map(Line::from) {
-            for column in 0..self.grid.columns() {
-                let cell = &mut self.grid[line][Column(column)];
-                *cell = Square::default();
-                cell.c = 'E';
-            }
+            // Drop information about the primary screens saved cursor.
+            self.grid.saved_cursor = self.grid.cursor.clone();
+            self.saved_cursor_state = self.current_cursor_state();
+
+            // Reset alternate screen contents.
+            self.inactive_grid.reset_region(..);
+            self.inactive_grid.clear_history();
         }
 
+        mem::swap(
+            &mut self.keyboard_mode_stack,
+            &mut self.inactive_keyboard_mode_stack,
+        );
+        self.set_keyboard_mode(
+            self.keyboard_mode_stack
+                .last()
+                .copied()
+                .unwrap_or(KeyboardModes::NO_MODE)
+                .into(),
+            KeyboardModesApplyBehavior::Replace,
+        );
+
+        mem::swap(&mut self.grid, &mut self.inactive_grid);
+        mem::swap(
+            &mut self.saved_cursor_state,
+            &mut self.inactive_saved_cursor_state,
+        );
+        self.mode ^= Mode::ALT_SCREEN;
+        self.selection = None;
+        self.wrapped_lines = 0;
         self.mark_fully_damaged();
     }
 
-    #[inline]
-    fn move_up(&mut self, rows: usize) {
-        self.goto(self.grid.cursor.pos.row - rows, self.grid.cursor.pos.col)
-    }
+    /// Select from `start` to `end`, both included, as if it was done
+    /// with the mouse. Positions are clamped to the grid and may come in
+    /// any order.
+    pub fn select_range(
+        &mut self,
+        start: Pos,
+        end: Pos,
+        ty: SelectionType,
+    ) -> Option<SelectionRange> {
+        let start = start.grid_clamp(self, Boundary::Grid);
+        let end = end.grid_clamp(self, Boundary::Grid);
+        let (start, end) = if start <= end {
+            (start, end)
+        } else {
+            (end, start)
+        };
 
-    #[inline]
-    fn move_down(&mut self, rows: usize) {
-        self.goto(self.grid.cursor.pos.row + rows, self.grid.cursor.pos.col)
-    }
+        let mut selection = Selection::new(ty, start, Side::Left);
+        selection.update(end, Side::Right);
+        let range = selection.to_range(self);
+        self.selection = Some(selection);
+        range
+    }
+
+    /// Extend the selection up to `pos`, or start a simple one there when
+    /// there is none, like a secondary click does.
+    pub fn extend_selection(&mut self, pos: Pos, side: Side) -> Option<SelectionRange> {
+        let pos = pos.grid_clamp(self, Boundary::Grid);
+        let selection = match self.selection.take() {
+            Some(mut selection) => {
+                selection.update(pos, side);
+                // Move vi cursor and expand selection.
+                if self.mode.contains(Mode::VI) {
+                    self.vi_mode_cursor.pos = pos;
+                    selection.include_all();
+                }
+                selection
+            }
+            None => Selection::new(SelectionType::Simple, pos, side),
+        };
 
-    #[inline]
-    fn move_down_and_cr(&mut self, rows: usize) {
-        self.goto(self.grid.cursor.pos.row + rows, Column(0))
+        let range = selection.to_range(self);
+        self.selection = Some(selection);
+        range
     }
 
-    #[inline]
-    fn move_up_and_cr(&mut self, lines: usize) {
-        self.goto(self.grid.cursor.pos.row - lines, Column(0))
-    }
+    pub fn selection_to_string(&self) -> Option<String> {
+        let selection_range = self.selection.as_ref().and_then(|s| s.to_range(self))?;
+        let SelectionRange { start, end, .. } = selection_range;
 
-    #[inline]
-    fn scroll_up(&mut self, lines: usize) {
-        let origin = self.scroll_region.start;
-        self.scroll_up_relative(origin, lines);
+        let res = match self.selection.as_ref() {
+            Some(Selection {
+                ty: SelectionType::Block,
+                ..
+            }) => self.block_to_string(start, end),
+            Some(Selection {
+                ty: SelectionType::Lines,
+                ..
+            }) => self.bounds_to_string(start, end) + "\n",
+            _ => self.bounds_to_string(start, end),
+        };
+
+        Some(res)
     }
 
-    #[inline]
-    fn delete_lines(&mut self, lines: usize) {
-        let origin = self.grid.cursor.pos.row;
-        let lines = std::cmp::min(self.grid.screen_lines() - origin.0 as usize, lines);
+    /// Text inside the rectangle between `start` and `end` (both inclusive),
+    /// one line per row.
+    pub fn block_to_string(&self, start: Pos, end: Pos) -> String {
+        let (top, bottom) = (start.row.min(end.row), start.row.max(end.row));
+        let (left, right) = (start.col.min(end.col), start.col.max(end.col));
+        let mut res = String::new();
 
-        if lines > 0 && self.scroll_region.contains(&origin) {
-            self.scroll_up_relative(origin, lines);
+        // Every row is cut on its own, so a wide char wrapped to the next
+        // row never belongs to this one.
+        for line in (top.0..bottom.0).map(Line::from) {
+            res += self.line_to_string(line, left..right, false).trim_end();
+            res += "\n";
         }
+
+        res += self.line_to_string(bottom, left..right, false).trim_end();
+        res
     }
 
-    #[inline]
-    fn push_title(&mut self) {
-        log::trace!("Pushing '{:?}' onto title stack", self.title);
+    pub fn bounds_to_string(&self, start: Pos, end: Pos) -> String {
+        let mut res = String::new();
 
-        if self.title_stack.len() >= TITLE_STACK_MAX_DEPTH {
-            let removed = self.title_stack.remove(0);
-            log::trace!(
-                "Removing '{:?}' from bottom of title stack that exceeds its maximum depth",
-                removed
-            );
+        for line in (start.row.0..=end.row.0).map(Line::from) {
+            let start_col = if line == start.row {
+                start.col
+            } else {
+                Column(0)
+            };
+            let end_col = if line == end.row {
+                end.col
+            } else {
+                self.grid.last_column()
+            };
+
+            res += &self.line_to_string(line, start_col..end_col, line == end.row);
         }
 
-        self.title_stack.push(self.title.clone());
+        res.strip_suffix('\n').map(str::to_owned).unwrap_or(res)
     }
 
-    #[inline]
-    fn pop_title(&mut self) {
-        log::trace!("Attempting to pop title from stack...");
+    /// `XTGETXRES` reply for the terminal property `name`, `None` if the
+    /// property is unknown.
+    pub fn encode_termprop(&self, name: &str) -> Option<Vec<u8>> {
+        let value = match name {
+            "version" => String::from(env!("CARGO_PKG_VERSION")),
+            "colors" => String::from("16M"),
+            "background" => {
+                let color =
+                    ColorRgb::from_color_arr(self.colors[colors::NamedColor::Background]);
+                format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+            }
+            "columns" => self.grid.columns().to_string(),
+            "rows" => self.grid.screen_lines().to_string(),
+            _ => return None,
+        };
 
-        if let Some(popped) = self.title_stack.pop() {
-            log::trace!("Title '{:?}' popped from stack", popped);
-            self.set_title(Some(popped));
-        }
+        Some(format!("\x1bP>|{name}={value}\x1b\\").into_bytes())
     }
 
-    #[inline]
-    fn erase_chars(&mut self, count: Column) {
-        let cursor = &self.grid.cursor;
+    /// Final `(fg, bg)` of the cell at `pos` resolved against the terminal
+    /// palette the same way the renderer draws it, inverse included. `None`
+    /// if the position is outside of the grid.
+    pub fn cell_color_at(&self, pos: Pos) -> Option<(ColorRgb, ColorRgb)> {
+        if pos.row < self.grid.topmost_line()
+            || pos.row > self.grid.bottommost_line()
+            || pos.col >= self.grid.columns()
+        {
+            return None;
+        }
 
-        let start = cursor.pos.col;
-        let end = std::cmp::min(start + count, Column(self.grid.columns()));
+        let square = &self.grid[pos];
+        let fg = color::resolve_fg(&self.colors, square.fg, square.flags);
+        let bg = color::resolve_bg(&self.colors, square.bg, square.flags);
+        let (fg, bg) = (ColorRgb::from_color_arr(fg), ColorRgb::from_color_arr(bg));
 
-        // Cleared cells have current background color set.
-        let bg = self.grid.cursor.template.bg;
-        let line = cursor.pos.row;
-        self.damage.damage_line(line.0 as usize, start.0, end.0);
-        let row = &mut self.grid[line];
-        for cell in &mut row[start..end] {
-            *cell = bg.into();
+        if square.flags.contains(square::Flags::INVERSE) {
+            Some((bg, fg))
+        } else {
+            Some((fg, bg))
         }
     }
 
-    #[inline]
-    fn delete_chars(&mut self, count: usize) {
-        let columns = self.grid.columns();
-        let cursor = &self.grid.cursor;
-        let bg = cursor.template.bg;
-
-        // Ensure deleting within terminal bounds.
-        let count = std::cmp::min(count, columns);
-
-        let start = cursor.pos.col.0;
-        let end = std::cmp::min(start + count, columns - 1);
-        let num_cells = columns - end;
-
-        let line = cursor.pos.row;
-        self.damage
-            .damage_line(line.0 as usize, 0, self.grid.columns() - 1);
-        let row = &mut self.grid[line][..];
-
-        for offset in 0..num_cells {
-            row.swap(start + offset, end + offset);
+    /// Text of the logical line starting at `row`, following soft wraps
+    /// and without surrounding spaces.
+    pub fn line_content(&self, row: i32) -> String {
+        let row = Line(row);
+        if row < self.grid.topmost_line() || row > self.grid.bottommost_line() {
+            return String::new();
         }
 
-        // Clear last `count` cells in the row. If deleting 1 char, need to delete
-        // 1 cell.
-        let end = columns - count;
-        for cell in &mut row[end..] {
-            *cell = bg.into();
-        }
+        let start = Pos::new(row, Column(0));
+        let end = self.line_search_right(start);
+        self.bounds_to_string(start, end).trim().to_owned()
     }
 
-    #[inline]
-    fn scroll_down(&mut self, lines: usize) {
-        let origin = self.scroll_region.start;
-        self.scroll_down_relative(origin, lines);
-    }
+    /// Convert a single line in the grid to a String.
+    fn line_to_string(
+        &self,
+        line: Line,
+        mut cols: Range<Column>,
+        include_wrapped_wide: bool,
+    ) -> String {
+        let mut text = String::new();
 
-    #[inline]
-    fn insert_blank_lines(&mut self, lines: usize) {
-        let origin = self.grid.cursor.pos.row;
-        if self.scroll_region.contains(&origin) {
-            self.scroll_down_relative(origin, lines);
+        let grid_line = &self.grid[line];
+        let line_length = std::cmp::min(grid_line.line_length(), cols.end + 1);
+
+        // Include wide char when trailing spacer is selected.
+        if grid_line[cols.start]
+            .flags
+            .contains(square::Flags::WIDE_CHAR_SPACER)
+        {
+            cols.start -= 1;
         }
-    }
 
-    #[inline]
-    fn insert_blank(&mut self, count: usize) {
-        let cursor = &self.grid.cursor;
-        let bg = cursor.template.bg;
+        let mut tab_mode = false;
+        for column in (cols.start.0..line_length.0).map(Column::from) {
+            let cell = &grid_line[column];
 
-        // Ensure inserting within terminal bounds
-        let count = std::cmp::min(count, self.grid.columns() - cursor.pos.col.0);
+            // Skip over cells until next tab-stop once a tab was found.
+            if tab_mode {
+                if self.tabs[column] || cell.c != ' ' {
+                    tab_mode = false;
+                } else {
+                    continue;
+                }
+            }
 
-        let source = cursor.pos.col;
-        let destination = cursor.pos.col.0 + count;
-        let num_cells = self.grid.columns() - destination;
+            if cell.c == '\t' {
+                tab_mode = true;
+            }
 
-        let line = cursor.pos.row;
-        self.damage
-            .damage_line(line.0 as usize, 0, self.grid.columns() - 1);
+            if !cell.flags.intersects(
+                square::Flags::WIDE_CHAR_SPACER | square::Flags::LEADING_WIDE_CHAR_SPACER,
+            ) {
+                // Push cells primary character.
+                text.push(cell.c);
 
-        let row = &mut self.grid[line][..];
+                // Push zero-width characters.
+                for c in cell.zerowidth().into_iter().flatten() {
+                    text.push(*c);
+                }
+            }
+        }
 
-        for offset in (0..num_cells).rev() {
-            row.swap(destination + offset, source.0 + offset);
+        if cols.end >= self.grid.columns() - 1
+            && (line_length.0 == 0
+                || !self.grid[line][line_length - 1]
+                    .flags
+                    .contains(square::Flags::WRAPLINE))
+        {
+            text.push('\n');
         }
 
-        // Squares were just moved out toward the end of the line;
-        // fill in between source and dest with blanks.
-        for cell in &mut row[source.0..destination] {
-            *cell = bg.into();
+        // If wide char is not part of the selection, but leading spacer is, include it.
+        if line_length == self.grid.columns()
+            && line_length.0 >= 2
+            && grid_line[line_length - 1]
+                .flags
+                .contains(square::Flags::LEADING_WIDE_CHAR_SPACER)
+            && include_wrapped_wide
+        {
+            text.push(self.grid[line - 1i32][Column(0)].c);
         }
+
+        text
     }
 
     #[inline]
-    fn reverse_index(&mut self) {
-        // If cursor is at the top.
-        if self.grid.cursor.pos.row == self.scroll_region.start {
-            self.scroll_down(1);
-        } else {
-            self.damage_cursor();
-            self.grid.cursor.pos.row =
-                std::cmp::max(self.grid.cursor.pos.row - 1, Line(0));
-            self.damage_cursor();
+    fn set_keyboard_mode(&mut self, mode: Mode, apply: KeyboardModesApplyBehavior) {
+        // println!("{:?}", mode);
+        let active_mode = self.mode & Mode::KEYBOARD_PROTOCOL;
+        self.mode &= !Mode::KEYBOARD_PROTOCOL;
+        let new_mode = match apply {
+            KeyboardModesApplyBehavior::Replace => mode,
+            KeyboardModesApplyBehavior::Union => active_mode.union(mode),
+            KeyboardModesApplyBehavior::Difference => active_mode.difference(mode),
+        };
+        info!("Setting keyboard mode to {new_mode:?}");
+        self.mode |= new_mode;
+    }
+
+    /// Find the beginning of the current line across linewraps.
+    pub fn row_search_left(&self, mut point: Pos) -> Pos {
+        while point.row > self.grid.topmost_line()
+            && self.grid[point.row - 1i32][self.grid.last_column()]
+                .flags
+                .contains(square::Flags::WRAPLINE)
+        {
+            point.row -= 1;
         }
+
+        point.col = Column(0);
+
+        point
     }
 
-    #[inline]
-    fn reset_state(&mut self) {
-        if self.mode.contains(Mode::ALT_SCREEN) {
-            std::mem::swap(&mut self.grid, &mut self.inactive_grid);
+    /// Find the end of the current line across linewraps.
+    pub fn row_search_right(&self, mut point: Pos) -> Pos {
+        while point.row + 1 < self.grid.screen_lines()
+            && self.grid[point.row][self.grid.last_column()]
+                .flags
+                .contains(square::Flags::WRAPLINE)
+        {
+            point.row += 1;
         }
-        self.active_charset = Default::default();
-        self.cursor_shape = self.default_cursor_shape;
-        self.grid.reset();
-        self.inactive_grid.reset();
-        self.scroll_region = Line(0)..Line(self.grid.screen_lines() as i32);
-        self.tabs = TabStops::new(self.grid.columns());
-        self.title_stack = Vec::new();
-        self.keyboard_mode_stack = Vec::new();
-        self.title = String::from("");
-        self.selection = None;
-        self.vi_mode_cursor = Default::default();
-        self.keyboard_mode_stack = Default::default();
-        self.inactive_keyboard_mode_stack = Default::default();
 
-        // Preserve vi mode across resets.
-        self.mode &= Mode::VI;
-        self.mode.insert(Mode::default());
+        point.col = self.grid.last_column();
 
-        self.event_proxy
-            .send_event(RioEvent::CursorBlinkingChange, self.window_id);
-        self.mark_fully_damaged();
+        point
     }
+}
 
+impl<U: EventListener> Handler for Crosswords<U> {
     #[inline]
-    fn terminal_attribute(&mut self, attr: Attr) {
-        let cursor = &mut self.grid.cursor;
-        match attr {
-            Attr::Foreground(color) => cursor.template.fg = color,
-            Attr::Background(color) => cursor.template.bg = color,
-            Attr::UnderlineColor(color) => cursor.template.set_underline_color(color),
-            Attr::Reset => {
-                cursor.template.fg = AnsiColor::Named(NamedColor::Foreground);
-                cursor.template.bg = AnsiColor::Named(NamedColor::Background);
-                cursor.template.flags = square::Flags::empty();
-                cursor.template.set_underline_color(None);
+    fn set_mode(&mut self, mode: AnsiMode) {
+        match mode {
+            AnsiMode::UrgencyHints => self.mode.insert(Mode::URGENCY_HINTS),
+            AnsiMode::SwapScreenAndSetRestoreCursor => {
+                if self.disable_alt_screen {
+                    self.save_cursor_position();
+                } else if !self.mode.contains(Mode::ALT_SCREEN) {
+                    self.swap_alt();
+                }
             }
-            Attr::Reverse => cursor.template.flags.insert(square::Flags::INVERSE),
-            Attr::CancelReverse => cursor.template.flags.remove(square::Flags::INVERSE),
-            Attr::Bold => cursor.template.flags.insert(square::Flags::BOLD),
-            Attr::CancelBold => cursor.template.flags.remove(square::Flags::BOLD),
-            Attr::Dim => cursor.template.flags.insert(square::Flags::DIM),
-            Attr::CancelBoldDim => cursor
-                .template
-                .flags
-                .remove(square::Flags::BOLD | square::Flags::DIM),
-            Attr::Italic => cursor.template.flags.insert(square::Flags::ITALIC),
-            Attr::CancelItalic => cursor.template.flags.remove(square::Flags::ITALIC),
-            Attr::Underline => {
-                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES);
-                cursor.template.flags.insert(square::Flags::UNDERLINE);
+            AnsiMode::ShowCursor => self.mode.insert(Mode::SHOW_CURSOR),
+            AnsiMode::ShowScrollbar => {
+                self.send_event(RioEvent::ScrollbarVisibility(true));
             }
-            Attr::DoubleUnderline => {
-                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES);
-                cursor
-                    .template
-                    .flags
-                    .insert(square::Flags::DOUBLE_UNDERLINE);
+            AnsiMode::CursorKeys => self.mode.insert(Mode::APP_CURSOR),
+            // Mouse protocols are mutually exclusive.
+            AnsiMode::X10Mouse => {
+                self.mode.remove(Mode::MOUSE_MODE);
+                self.mode.insert(Mode::MOUSE_X10);
+                self.send_event(RioEvent::MouseCursorDirty);
             }
-            Attr::Undercurl => {
-                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES);
-                cursor.template.flags.insert(square::Flags::UNDERCURL);
+            AnsiMode::ReportMouseClicks => {
+                self.mode.remove(Mode::MOUSE_MODE);
+                self.mode.insert(Mode::MOUSE_REPORT_CLICK);
+                self.send_event(RioEvent::MouseCursorDirty);
             }
-            Attr::DottedUnderline => {
-                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES);
-                cursor
-                    .template
-                    .flags
-                    .insert(square::Flags::DOTTED_UNDERLINE);
+            AnsiMode::ReportSquareMouseMotion => {
+                self.mode.remove(Mode::MOUSE_MODE);
+                self.mode.insert(Mode::MOUSE_DRAG);
+                self.send_event(RioEvent::MouseCursorDirty);
             }
-            Attr::DashedUnderline => {
-                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES);
-                cursor
-                    .template
-                    .flags
-                    .insert(square::Flags::DASHED_UNDERLINE);
+            AnsiMode::ReportAllMouseMotion => {
+                self.mode.remove(Mode::MOUSE_MODE);
+                self.mode.insert(Mode::MOUSE_MOTION);
+                self.send_event(RioEvent::MouseCursorDirty);
             }
-            Attr::CancelUnderline => {
-                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES)
+            AnsiMode::ReportFocusInOut => self.mode.insert(Mode::FOCUS_IN_OUT),
+            AnsiMode::BracketedPaste => self.mode.insert(Mode::BRACKETED_PASTE),
+            // Mouse encodings are mutually exclusive.
+            AnsiMode::SgrMouse => {
+                self.mode.remove(Mode::UTF8_MOUSE);
+                self.mode.insert(Mode::SGR_MOUSE);
             }
-            Attr::Hidden => cursor.template.flags.insert(square::Flags::HIDDEN),
-            Attr::CancelHidden => cursor.template.flags.remove(square::Flags::HIDDEN),
-            Attr::Strike => cursor.template.flags.insert(square::Flags::STRIKEOUT),
-            Attr::CancelStrike => cursor.template.flags.remove(square::Flags::STRIKEOUT),
-            _ => {
-                warn!("Term got unhandled attr: {:?}", attr);
+            AnsiMode::Utf8Mouse => {
+                self.mode.remove(Mode::SGR_MOUSE);
+                self.mode.insert(Mode::UTF8_MOUSE);
             }
+            AnsiMode::AlternateScroll => self.mode.insert(Mode::ALTERNATE_SCROLL),
+            AnsiMode::WheelToArrow => self.mode.insert(Mode::WHEEL_TO_ARROW),
+            AnsiMode::LineWrap => self.mode.insert(Mode::LINE_WRAP),
+            AnsiMode::LineFeedNewLine => self.mode.insert(Mode::LINE_FEED_NEW_LINE),
+            AnsiMode::Origin => self.mode.insert(Mode::ORIGIN),
+            AnsiMode::Column => self.deccolm(),
+            AnsiMode::Insert => self.mode.insert(Mode::INSERT),
+            AnsiMode::BlinkingCursor => {
+                self.blinking_cursor = true;
+                self.send_event(RioEvent::CursorBlinkingChange);
+            }
+            AnsiMode::SixelDisplay => self.mode.insert(Mode::SIXEL_DISPLAY),
+            AnsiMode::SixelPrivateColorRegisters => {
+                self.mode.insert(Mode::SIXEL_PRIV_PALETTE)
+            }
+            AnsiMode::SixelCursorToTheRight => {
+                self.mode.insert(Mode::SIXEL_CURSOR_TO_THE_RIGHT);
+            }
+            AnsiMode::PrintFormFeed => (),
+            AnsiMode::MetaSendsEscape => self.mode.insert(Mode::META_SENDS_ESCAPE),
+            AnsiMode::AltSendsEscape => self.mode.insert(Mode::ALT_SENDS_ESCAPE),
+            AnsiMode::BackarrowKey => self.mode.insert(Mode::BACKARROW_SENDS_BACKSPACE),
+            AnsiMode::PixelGeometry => self.pixel_geometry(),
         }
     }
 
-    fn set_title(&mut self, title: Option<String>) {
-        self.title = title.unwrap_or_default();
-    }
-
-    #[inline]
-    fn set_cursor_style(&mut self, style: Option<CursorShape>, _blinking: bool) {
-        if let Some(cursor_shape) = style {
-            self.cursor_shape = cursor_shape;
-        } else {
-            self.cursor_shape = self.default_cursor_shape;
-        }
-
-        // self.blinking_cursor = blinking;
-        // self.event_proxy
-        //     .send_event(RioEvent::CursorBlinkingChange, self.window_id);
-    }
-
     #[inline]
-    fn set_cursor_shape(&mut self, shape: CursorShape) {
-        self.cursor_shape = shape;
-    }
+    fn dynamic_color_sequence(&mut self, prefix: String, index: usize, terminator: &str) {
+        debug!(
+            "Requested write of escape sequence for color code {}: color[{}]",
+            prefix, index
+        );
 
-    #[inline]
-    fn set_keypad_application_mode(&mut self) {
-        log::trace!("Setting keypad application mode");
-        self.mode.insert(Mode::APP_KEYPAD);
+        let terminator = terminator.to_owned();
+        self.send_event(RioEvent::ColorRequest(
+            index,
+            Arc::new(move |color| {
+                format!(
+                    "\x1b]{};rgb:{1:02x}{1:02x}/{2:02x}{2:02x}/{3:02x}{3:02x}{4}",
+                    prefix, color.r, color.g, color.b, terminator
+                )
+            }),
+        ));
     }
 
     #[inline]
-    fn unset_keypad_application_mode(&mut self) {
-        log::trace!("Unsetting keypad application mode");
-        self.mode.remove(Mode::APP_KEYPAD);
-    }
-
-    /// Store data into clipboard.
+    fn unset_mode(&mut self, mode: AnsiMode) {
+        match mode {
+            AnsiMode::UrgencyHints => self.mode.remove(Mode::URGENCY_HINTS),
+            AnsiMode::SwapScreenAndSetRestoreCursor => {
+                // Leave the alternate screen even when it got disabled while
+                // active, so the application can't get stuck in it.
+                if self.mode.contains(Mode::ALT_SCREEN) {
+                    self.swap_alt();
+                } else if self.disable_alt_screen {
+                    self.restore_cursor_position();
+                }
+            }
+            AnsiMode::ShowCursor => self.mode.remove(Mode::SHOW_CURSOR),
+            AnsiMode::ShowScrollbar => {
+                self.send_event(RioEvent::ScrollbarVisibility(false));
+            }
+            AnsiMode::CursorKeys => self.mode.remove(Mode::APP_CURSOR),
+            AnsiMode::X10Mouse => {
+                self.mode.remove(Mode::MOUSE_X10);
+                self.send_event(RioEvent::MouseCursorDirty);
+            }
+            AnsiMode::ReportMouseClicks => {
+                self.mode.remove(Mode::MOUSE_REPORT_CLICK);
+                self.send_event(RioEvent::MouseCursorDirty);
+            }
+            AnsiMode::ReportSquareMouseMotion => {
+                self.mode.remove(Mode::MOUSE_DRAG);
+                self.send_event(RioEvent::MouseCursorDirty);
+            }
+            AnsiMode::ReportAllMouseMotion => {
+                self.mode.remove(Mode::MOUSE_MOTION);
+                self.send_event(RioEvent::MouseCursorDirty);
+            }
+            AnsiMode::ReportFocusInOut => self.mode.remove(Mode::FOCUS_IN_OUT),
+            AnsiMode::BracketedPaste => self.mode.remove(Mode::BRACKETED_PASTE),
+            AnsiMode::SgrMouse => self.mode.remove(Mode::SGR_MOUSE),
+            AnsiMode::Utf8Mouse => self.mode.remove(Mode::UTF8_MOUSE),
+            AnsiMode::AlternateScroll => self.mode.remove(Mode::ALTERNATE_SCROLL),
+            AnsiMode::WheelToArrow => self.mode.remove(Mode::WHEEL_TO_ARROW),
+            AnsiMode::LineWrap => self.mode.remove(Mode::LINE_WRAP),
+            AnsiMode::LineFeedNewLine => self.mode.remove(Mode::LINE_FEED_NEW_LINE),
+            AnsiMode::Origin => self.mode.remove(Mode::ORIGIN),
+            AnsiMode::Column => self.deccolm(),
+            AnsiMode::Insert => {
+                self.mode.remove(Mode::INSERT);
+                self.mark_fully_damaged();
+            }
+            AnsiMode::BlinkingCursor => {
+                // TODO: Update it
+                // self.blinking_cursor = false;
+                // self.event_proxy
+                //     .send_event(RioEvent::CursorBlinkingChange, self.window_id);
+            }
+            AnsiMode::SixelDisplay => self.mode.remove(Mode::SIXEL_DISPLAY),
+            AnsiMode::SixelPrivateColorRegisters => {
+                self.graphics.sixel_shared_palette = None;
+                self.mode.remove(Mode::SIXEL_PRIV_PALETTE);
+            }
+            AnsiMode::SixelCursorToTheRight => {
+                self.mode.remove(Mode::SIXEL_CURSOR_TO_THE_RIGHT)
+            }
+            AnsiMode::PrintFormFeed => (),
+            AnsiMode::MetaSendsEscape => self.mode.remove(Mode::META_SENDS_ESCAPE),
+            AnsiMode::AltSendsEscape => self.mode.remove(Mode::ALT_SENDS_ESCAPE),
+            AnsiMode::BackarrowKey => self.mode.remove(Mode::BACKARROW_SENDS_BACKSPACE),
+            AnsiMode::PixelGeometry => (),
+        }
+    }
+
     #[inline]
-    fn clipboard_store(&mut self, clipboard: u8, base64: &[u8]) {
-        let clipboard_type = match clipboard {
-            b'c' => ClipboardType::Clipboard,
-            b'p' | b's' => ClipboardType::Selection,
-            _ => return,
+    fn goto(&mut self, line: Line, col: Column) {
+        self.wrapped_lines = 0;
+        let (y_offset, max_y) = if self.mode.contains(Mode::ORIGIN) {
+            (self.scroll_region.start, self.scroll_region.end - 1)
+        } else {
+            (Line(0), self.grid.bottommost_line())
         };
 
-        if let Ok(bytes) = general_purpose::STANDARD.decode(base64) {
-            if let Ok(text) = String::from_utf8(bytes) {
-                self.event_proxy.send_event(
-                    RioEvent::ClipboardStore(clipboard_type, text),
-                    self.window_id,
-                );
-            }
-        }
+        self.damage_cursor();
+        self.grid.cursor.pos.row =
+            std::cmp::max(std::cmp::min(line + y_offset, max_y), Line(0));
+        self.grid.cursor.pos.col = std::cmp::min(col, self.grid.last_column());
+        self.damage_cursor();
+        self.grid.cursor.should_wrap = false;
     }
 
     #[inline]
-    fn configure_charset(
-        &mut self,
-        index: pos::CharsetIndex,
-        charset: pos::StandardCharset,
-    ) {
-        log::trace!("Configuring charset {:?} as {:?}", index, charset);
-        self.grid.cursor.charsets[index] = charset;
+    fn set_active_charset(&mut self, index: CharsetIndex) {
+        self.active_charset = index;
     }
 
-    #[inline(never)]
-    fn input(&mut self, c: char) {
-        let width = match c.width() {
-            Some(width) => width,
-            None => return,
-        };
+    #[inline]
+    fn move_forward(&mut self, cols: Column) {
+        let last_column =
+            std::cmp::min(self.grid.cursor.pos.col + cols, self.grid.last_column());
 
-        // Handle zero-width characters.
-        if width == 0 {
-            // // Get previous column.
-            let mut column = self.grid.cursor.pos.col;
-            if !self.grid.cursor.should_wrap {
-                column.0 = column.saturating_sub(1);
-            }
+        let cursor_line = self.grid.cursor.pos.row.0 as usize;
+        self.damage
+            .damage_line(cursor_line, self.grid.cursor.pos.col.0, last_column.0);
 
-            // // Put zerowidth characters over first fullwidth character cell.
-            let row = self.grid.cursor.pos.row;
-            if self.grid[row][column]
-                .flags
-                .contains(square::Flags::WIDE_CHAR_SPACER)
-            {
-                column.0 = column.saturating_sub(1);
-            }
+        self.grid.cursor.pos.col = last_column;
+        self.grid.cursor.should_wrap = false;
+    }
 
-            self.grid[row][column].push_zerowidth(c);
-            return;
-        }
+    #[inline]
+    fn move_backward(&mut self, cols: Column) {
+        let column = self.grid.cursor.pos.col.saturating_sub(cols.0);
 
-        if self.grid.cursor.should_wrap {
-            self.wrapline();
-        }
+        let cursor_line = self.grid.cursor.pos.row.0 as usize;
+        self.damage
+            .damage_line(cursor_line, column, self.grid.cursor.pos.col.0);
 
-        let columns = self.grid.columns();
-        if self.mode.contains(Mode::INSERT) && self.grid.cursor.pos.col + width < columns
-        {
-            let line = self.grid.cursor.pos.row;
-            let col = self.grid.cursor.pos.col;
-            let row = &mut self.grid[line][..];
+        self.grid.cursor.pos.col = Column(column);
+        self.grid.cursor.should_wrap = false;
+    }
 
-            for col in (col.0..(columns - width)).rev() {
-                row.swap(col + width, col);
-            }
-        }
+    #[inline]
+    fn move_backward_tabs(&mut self, count: u16) {
+        self.damage_cursor();
 
-        if width == 1 {
-            self.write_at_cursor(c);
-        } else {
-            if self.grid.cursor.pos.col + 1 >= columns {
-                if self.mode.contains(Mode::LINE_WRAP) {
-                    // Insert placeholder before wide char if glyph does not fit in this row.
-                    self.grid
-                        .cursor
-                        .template
-                        .flags
-                        .insert(square::Flags::LEADING_WIDE_CHAR_SPACER);
-                    self.write_at_cursor(' ');
-                    self.grid
-                        .cursor
-                        .template
-                        .flags
-                        .remove(square::Flags::LEADING_WIDE_CHAR_SPACER);
-                    self.wrapline();
-                } else {
-                    // Prevent out of bounds crash when linewrapping is disabled.
-                    self.grid.cursor.should_wrap = true;
-                    return;
+        let old_col = self.grid.cursor.pos.col.0;
+        for _ in 0..count {
+            let mut col = self.grid.cursor.pos.col;
+            for i in (0..(col.0)).rev() {
+                if self.tabs[Column(i)] {
+                    col = Column(i);
+                    break;
                 }
             }
+            self.grid.cursor.pos.col = col;
+        }
+        self.grid.cursor.should_wrap = false;
 
-            self.grid
-                .cursor
-                .template
-                .flags
-                .insert(square::Flags::WIDE_CHAR);
-            self.write_at_cursor(c);
-            self.grid
-                .cursor
-                .template
-                .flags
-                .remove(square::Flags::WIDE_CHAR);
+        let line = self.grid.cursor.pos.row.0 as usize;
+        self.damage
+            .damage_line(line, self.grid.cursor.pos.col.0, old_col);
+    }
 
-            // Write spacer to cell following the wide glyph.
-            self.grid.cursor.pos.col += 1;
-            self.grid
-                .cursor
-                .template
-                .flags
-                .insert(square::Flags::WIDE_CHAR_SPACER);
-            self.write_at_cursor(' ');
-            self.grid
-                .cursor
-                .template
-                .flags
-                .remove(square::Flags::WIDE_CHAR_SPACER);
-        }
+    #[inline]
+    fn goto_line(&mut self, line: Line) {
+        self.goto(line, self.grid.cursor.pos.col)
+    }
 
-        if self.grid.cursor.pos.col + 1 < columns {
-            self.grid.cursor.pos.col += 1;
-        } else {
-            self.grid.cursor.should_wrap = true;
-        }
+    #[inline]
+    fn goto_col(&mut self, col: Column) {
+        self.goto(self.grid.cursor.pos.row, col)
     }
 
     #[inline]
-    fn identify_terminal(&mut self, intermediate: Option<char>) {
-        match intermediate {
-            None => {
-                log::trace!("Reporting primary device attributes");
-                let text = String::from("\x1b[?62;4;6;22c");
-                self.event_proxy
-                    .send_event(RioEvent::PtyWrite(text), self.window_id);
-            }
-            Some('>') => {
-                log::trace!("Reporting secondary device attributes");
-                let version = version_number(env!("CARGO_PKG_VERSION"));
-                let text = format!("\x1b[>0;{version};1c");
-                self.event_proxy
-                    .send_event(RioEvent::PtyWrite(text), self.window_id);
+    fn decaln(&mut self) {
+        for line in (0..self.grid.screen_lines()).map(Line::from) {
+            for column in 0..self.grid.columns() {
+                let cell = &mut self.grid[line][Column(column)];
+                *cell = Square::default();
+                cell.c = 'E';
             }
-            _ => debug!("Unsupported device attributes intermediate"),
         }
+
+        self.mark_fully_damaged();
     }
 
     #[inline]
-    fn report_keyboard_mode(&mut self) {
-        let current_mode = self
-            .keyboard_mode_stack
-            .last()
-            .unwrap_or(&KeyboardModes::NO_MODE)
-            .bits();
-        let text = format!("\x1b[?{current_mode}u");
-        self.event_proxy
-            .send_event(RioEvent::PtyWrite(text), self.window_id);
+    fn move_up(&mut self, rows: usize) {
+        self.goto(self.grid.cursor.pos.row - rows, self.grid.cursor.pos.col)
     }
 
     #[inline]
-    fn push_keyboard_mode(&mut self, mode: KeyboardModes) {
-        if self.keyboard_mode_stack.len() >= KEYBOARD_MODE_STACK_MAX_DEPTH {
-            let _removed = self.title_stack.remove(0);
-        }
+    fn move_down(&mut self, rows: usize) {
+        self.goto(self.grid.cursor.pos.row + rows, self.grid.cursor.pos.col)
+    }
 
-        self.keyboard_mode_stack.push(mode);
-        self.set_keyboard_mode(mode.into(), KeyboardModesApplyBehavior::Replace);
+    #[inline]
+    fn move_down_and_cr(&mut self, rows: usize) {
+        self.goto(self.grid.cursor.pos.row + rows, Column(0))
     }
 
     #[inline]
-    fn pop_keyboard_modes(&mut self, to_pop: u16) {
-        let new_len = self
-            .keyboard_mode_stack
-            .len()
-            .saturating_sub(to_pop as usize);
-        self.keyboard_mode_stack.truncate(new_len);
-
-        // Reload active mode.
-        let mode = self
-            .keyboard_mode_stack
-            .last()
-            .copied()
-            .unwrap_or(KeyboardModes::NO_MODE);
-        self.set_keyboard_mode(mode.into(), KeyboardModesApplyBehavior::Replace);
+    fn move_up_and_cr(&mut self, lines: usize) {
+        self.goto(self.grid.cursor.pos.row - lines, Column(0))
     }
 
     #[inline]
-    fn set_keyboard_mode(
-        &mut self,
-        mode: KeyboardModes,
-        apply: KeyboardModesApplyBehavior,
-    ) {
-        self.set_keyboard_mode(mode.into(), apply);
+    fn scroll_up(&mut self, lines: usize) {
+        let origin = self.scroll_region.start;
+        self.scroll_up_relative(origin, lines);
     }
 
     #[inline]
-    fn device_status(&mut self, arg: usize) {
-        log::trace!("Reporting device status: {}", arg);
-        match arg {
-            5 => {
-                let text = String::from("\x1b[0n");
-                self.event_proxy
-                    .send_event(RioEvent::PtyWrite(text), self.window_id);
-            }
-            6 => {
-                let pos = self.grid.cursor.pos;
-                let text = format!("\x1b[{};{}R", pos.row + 1, pos.col + 1);
-                self.event_proxy
-                    .send_event(RioEvent::PtyWrite(text), self.window_id);
-            }
-            _ => debug!("unknown device status query: {}", arg),
-        };
+    fn delete_lines(&mut self, lines: usize) {
+        let origin = self.grid.cursor.pos.row;
+        let lines = std::cmp::min(self.grid.screen_lines() - origin.0 as usize, lines);
+
+        if lines > 0 && self.scroll_region.contains(&origin) {
+            self.scroll_up_relative(origin, lines);
+        }
     }
 
     #[inline]
-    fn newline(&mut self) {
-        self.linefeed();
+    fn push_title(&mut self) {
+        log::trace!("Pushing '{:?}' onto title stack", self.title);
 
-        if self.mode.contains(Mode::LINE_FEED_NEW_LINE) {
-            self.carriage_return();
+        if self.title_stack.len() >= TITLE_STACK_MAX_DEPTH {
+            let removed = self.title_stack.remove(0);
+            log::trace!(
+                "Removing '{:?}' from bottom of title stack that exceeds its maximum depth",
+                removed
+            );
         }
+
+        self.title_stack.push(self.title.clone());
     }
 
     #[inline]
-    fn backspace(&mut self) {
-        if self.grid.cursor.pos.col > Column(0) {
-            let line = self.grid.cursor.pos.row.0 as usize;
-            let column = self.grid.cursor.pos.col.0;
-            self.grid.cursor.pos.col -= 1;
-            self.grid.cursor.should_wrap = false;
-            self.damage.damage_line(line, column - 1, column);
+    fn pop_title(&mut self) {
+        log::trace!("Attempting to pop title from stack...");
+
+        if let Some(popped) = self.title_stack.pop() {
+            log::trace!("Title '{:?}' popped from stack", popped);
+            self.set_title(Some(popped));
         }
     }
 
     #[inline]
-    fn clear_screen(&mut self, mode: ClearMode) {
-        let bg = self.grid.cursor.template.bg;
-
-        let screen_lines = self.grid.screen_lines();
-
-        match mode {
-            ClearMode::Above => {
-                let cursor = self.grid.cursor.pos;
-
-                // If clearing more than one line.
-                if cursor.row > 1 {
-                    // Fully clear all lines before the current line.
-                    self.grid.reset_region(..cursor.row);
-                }
-
-                // Clear up to the current column in the current line.
-                let end = std::cmp::min(cursor.col + 1, Column(self.grid.columns()));
-                for cell in &mut self.grid[cursor.row][..end] {
-                    *cell = bg.into();
-                }
-
-                let range = Line(0)..=cursor.row;
-                self.selection =
-                    self.selection.take().filter(|s| !s.intersects_range(range));
-            }
-            ClearMode::Below => {
-                let cursor = self.grid.cursor.pos;
-                for cell in &mut self.grid[cursor.row][cursor.col..] {
-                    *cell = bg.into();
-                }
+    fn erase_chars(&mut self, count: Column) {
+        let cursor = &self.grid.cursor;
 
-                if (cursor.row.0 as usize) < screen_lines - 1 {
-                    self.grid.reset_region((cursor.row + 1)..);
-                }
+        let start = cursor.pos.col;
+        let end = std::cmp::min(start + count, Column(self.grid.columns()));
 
-                let range = cursor.row..Line(screen_lines as i32);
-                self.selection =
-                    self.selection.take().filter(|s| !s.intersects_range(range));
-            }
-            ClearMode::All => {
-                if self.mode.contains(Mode::ALT_SCREEN) {
-                    self.grid.reset_region(..);
-                } else {
-                    let old_offset = self.grid.display_offset();
+        // Cleared cells have current background color set.
+        let bg = self.grid.cursor.template.bg;
+        let line = cursor.pos.row;
+        self.damage.damage_line(line.0 as usize, start.0, end.0);
+        let row = &mut self.grid[line];
+        for cell in &mut row[start..end] {
+            *cell = bg.into();
+        }
 
-                    self.grid.clear_viewport();
+        // Like xterm, editing the line cancels a pending wrap.
+        self.grid.cursor.should_wrap = false;
+    }
 
-                    // Compute number of lines scrolled by clearing the viewport.
-                    let lines = self.grid.display_offset().saturating_sub(old_offset);
+    #[inline]
+    fn delete_chars(&mut self, count: usize) {
+        let columns = self.grid.columns();
+        let cursor = &self.grid.cursor;
+        let bg = cursor.template.bg;
 
-                    self.vi_mode_cursor.pos.row = (self.vi_mode_cursor.pos.row - lines)
-                        .grid_clamp(&self.grid, Boundary::Grid);
-                }
+        // Ensure deleting within terminal bounds.
+        let count = std::cmp::min(count, columns);
 
-                self.selection = None;
-            }
-            ClearMode::Saved if self.history_size() > 0 => {
-                self.grid.clear_history();
+        let start = cursor.pos.col.0;
+        let end = std::cmp::min(start + count, columns - 1);
+        let num_cells = columns - end;
 
-                self.vi_mode_cursor.pos.row = self
-                    .vi_mode_cursor
-                    .pos
-                    .row
-                    .grid_clamp(&self.grid, Boundary::Cursor);
+        let line = cursor.pos.row;
+        self.grid.cursor.should_wrap = false;
+        self.damage
+            .damage_line(line.0 as usize, 0, self.grid.columns() - 1);
+        let row = &mut self.grid[line][..];
 
-                self.selection = self
-                    .selection
-                    .take()
-                    .filter(|s| !s.intersects_range(..Line(0)));
-            }
-            // We have no history to clear.
-            ClearMode::Saved => (),
+        for offset in 0..num_cells {
+            row.swap(start + offset, end + offset);
         }
 
-        self.mark_fully_damaged();
+        // Clear last `count` cells in the row. If deleting 1 char, need to delete
+        // 1 cell.
+        let end = columns - count;
+        for cell in &mut row[end..] {
+            *cell = bg.into();
+        }
     }
 
     #[inline]
-    fn clear_tabs(&mut self, mode: TabulationClearMode) {
-        match mode {
-            TabulationClearMode::Current => {
-                self.tabs[self.grid.cursor.pos.col] = false;
-            }
-            TabulationClearMode::All => {
-                self.tabs.clear_all();
-            }
-        }
+    fn scroll_down(&mut self, lines: usize) {
+        let origin = self.scroll_region.start;
+        self.scroll_down_relative(origin, lines);
     }
 
     #[inline]
-    fn linefeed(&mut self) {
-        let next = self.grid.cursor.pos.row + 1;
-        if next == self.scroll_region.end {
-            self.scroll_up_relative(self.scroll_region.start, 1);
-        } else if next < self.grid.screen_lines() {
-            self.damage_cursor();
-            self.grid.cursor.pos.row += 1;
-            self.damage_cursor();
+    fn insert_blank_lines(&mut self, lines: usize) {
+        let origin = self.grid.cursor.pos.row;
+        if self.scroll_region.contains(&origin) {
+            self.scroll_down_relative(origin, lines);
         }
     }
 
     #[inline]
-    fn set_horizontal_tabstop(&mut self) {
-        self.tabs[self.grid.cursor.pos.col] = true;
-    }
+    fn insert_blank(&mut self, count: usize) {
+        let cursor = &self.grid.cursor;
+        let bg = cursor.template.bg;
 
-    #[inline]
-    fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
-        self.grid.cursor.template.set_hyperlink(hyperlink);
-    }
+        // Ensure inserting within terminal bounds
+        let count = std::cmp::min(count, self.grid.columns() - cursor.pos.col.0);
 
-    /// Set the indexed color value.
-    #[inline]
-    fn set_color(&mut self, _index: usize, _color: ColorRgb) {
-        // Damage terminal if the color changed and it's not the cursor.
-        // if index != NamedColor::Cursor as usize && self.colors[index] != Some(color) {
-        // self.mark_fully_damaged();
-        // }
+        let source = cursor.pos.col;
+        let destination = cursor.pos.col.0 + count;
+        let num_cells = self.grid.columns() - destination;
 
-        // self.colors[index] = Some(color);
-    }
+        let line = cursor.pos.row;
+        self.grid.cursor.should_wrap = false;
+        self.damage
+            .damage_line(line.0 as usize, 0, self.grid.columns() - 1);
 
-    #[inline]
-    fn reset_color(&mut self, _index: usize) {
-        // Damage terminal if the color changed and it's not the cursor.
-        // if index != NamedColor::Cursor as usize && self.colors[index].is_some() {
-        // self.mark_fully_damaged();
-        // }
+        let row = &mut self.grid[line][..];
 
-        // self.colors[index] = None;
-    }
+        for offset in (0..num_cells).rev() {
+            row.swap(destination + offset, source.0 + offset);
+        }
 
-    #[inline]
-    fn bell(&mut self) {
-        warn!("[unimplemented] Bell");
+        // Squares were just moved out toward the end of the line;
+        // fill in between source and dest with blanks.
+        for cell in &mut row[source.0..destination] {
+            *cell = bg.into();
+        }
     }
 
     #[inline]
-    fn substitute(&mut self) {
-        warn!("[unimplemented] Substitute");
+    fn reverse_index(&mut self) {
+        // If cursor is at the top.
+        if self.grid.cursor.pos.row == self.scroll_region.start {
+            self.scroll_down(1);
+        } else {
+            self.damage_cursor();
+            self.grid.cursor.pos.row =
+                std::cmp::max(self.grid.cursor.pos.row - 1, Line(0));
+            self.damage_cursor();
+        }
     }
 
     #[inline]
-    fn clipboard_load(&mut self, clipboard: u8, terminator: &str) {
-        let clipboard_type = match clipboard {
-            b'c' => ClipboardType::Clipboard,
-            b'p' | b's' => ClipboardType::Selection,
-            _ => return,
-        };
-
-        let terminator = terminator.to_owned();
-
-        self.event_proxy.send_event(
-            RioEvent::ClipboardLoad(
-                clipboard_type,
-                Arc::new(move |text| {
-                    let base64 = general_purpose::STANDARD.encode(text);
-                    format!("\x1b]52;{};{}{}", clipboard as char, base64, terminator)
-                }),
-            ),
-            self.window_id,
+    fn reset_state(&mut self) {
+        if self.mode.contains(Mode::ALT_SCREEN) {
+            std::mem::swap(&mut self.grid, &mut self.inactive_grid);
+        }
+        self.active_charset = Default::default();
+        self.saved_cursor_state = Default::default();
+        self.inactive_saved_cursor_state = Default::default();
+        self.cursor_shape = self.default_cursor_shape;
+        self.cursor_style_blinking = None;
+        self.grid.reset();
+        self.inactive_grid.reset();
+        self.scroll_region = Line(0)..Line(self.grid.screen_lines() as i32);
+        self.tabs = TabStops::new(self.grid.columns());
+        self.title_stack = Vec::new();
+        self.keyboard_mode_stack = Vec::new();
+        self.rectangular_attribute_changes = false;
+        self.kitty_graphics = KittyGraphicsState::default();
+        self.title = String::from("");
+        self.icon_title = String::from("");
+        self.selection = None;
+        self.vi_mode_cursor = Default::default();
+        self.keyboard_mode_stack = Default::default();
+        self.inactive_keyboard_mode_stack = Default::default();
+
+        // Preserve vi mode across resets.
+        self.mode &= Mode::VI;
+        self.mode.insert(Mode::default());
+        self.mode
+            .set(Mode::META_SENDS_ESCAPE, self.alt_sends_escape);
+
+        self.send_event(RioEvent::CursorBlinkingChange);
+        self.mark_fully_damaged();
+    }
+
+    #[inline]
+    fn terminal_attribute(&mut self, attr: Attr) {
+        let cursor = &mut self.grid.cursor;
+        match attr {
+            Attr::Foreground(color) => cursor.template.fg = color,
+            Attr::Background(color) => cursor.template.bg = color,
+            Attr::UnderlineColor(color) => cursor.template.set_underline_color(color),
+            Attr::Reset => {
+                cursor.template.fg = AnsiColor::Named(NamedColor::Foreground);
+                cursor.template.bg = AnsiColor::Named(NamedColor::Background);
+                cursor.template.flags = square::Flags::empty();
+                cursor.template.set_underline_color(None);
+            }
+            Attr::Reverse => cursor.template.flags.insert(square::Flags::INVERSE),
+            Attr::CancelReverse => cursor.template.flags.remove(square::Flags::INVERSE),
+            Attr::Bold => cursor.template.flags.insert(square::Flags::BOLD),
+            Attr::CancelBold => cursor.template.flags.remove(square::Flags::BOLD),
+            Attr::Dim => cursor.template.flags.insert(square::Flags::DIM),
+            Attr::CancelBoldDim => cursor
+                .template
+                .flags
+                .remove(square::Flags::BOLD | square::Flags::DIM),
+            Attr::Italic => cursor.template.flags.insert(square::Flags::ITALIC),
+            Attr::CancelItalic => cursor.template.flags.remove(square::Flags::ITALIC),
+            Attr::Underline => {
+                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES);
+                cursor.template.flags.insert(square::Flags::UNDERLINE);
+            }
+            Attr::DoubleUnderline => {
+                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES);
+                cursor
+                    .template
+                    .flags
+                    .insert(square::Flags::DOUBLE_UNDERLINE);
+            }
+            Attr::Undercurl => {
+                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES);
+                cursor.template.flags.insert(square::Flags::UNDERCURL);
+            }
+            Attr::DottedUnderline => {
+                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES);
+                cursor
+                    .template
+                    .flags
+                    .insert(square::Flags::DOTTED_UNDERLINE);
+            }
+            Attr::DashedUnderline => {
+                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES);
+                cursor
+                    .template
+                    .flags
+                    .insert(square::Flags::DASHED_UNDERLINE);
+            }
+            Attr::CancelUnderline => {
+                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES)
+            }
+            Attr::Hidden => cursor.template.flags.insert(square::Flags::HIDDEN),
+            Attr::CancelHidden => cursor.template.flags.remove(square::Flags::HIDDEN),
+            Attr::Strike => cursor.template.flags.insert(square::Flags::STRIKEOUT),
+            Attr::CancelStrike => cursor.template.flags.remove(square::Flags::STRIKEOUT),
+            _ => {
+                warn!("Term got unhandled attr: {:?}", attr);
+            }
+        }
+    }
+
+    fn set_title(&mut self, title: Option<String>) {
+        self.title = title.unwrap_or_default();
+    }
+
+    fn set_icon_title(&mut self, title: Option<String>) {
+        self.icon_title = title.unwrap_or_default();
+    }
+
+    #[inline]
+    fn set_cursor_style(&mut self, style: Option<CursorShape>, blinking: bool) {
+        if let Some(cursor_shape) = style {
+            self.cursor_shape = cursor_shape;
+            self.cursor_style_blinking = Some(blinking);
+        } else {
+            self.cursor_shape = self.default_cursor_shape;
+            self.cursor_style_blinking = None;
+        }
+
+        // self.blinking_cursor = blinking;
+        // self.event_proxy
+        //     .send_event(RioEvent::CursorBlinkingChange, self.window_id);
+    }
+
+    #[inline]
+    fn set_cursor_shape(&mut self, shape: CursorShape) {
+        self.cursor_shape = shape;
+    }
+
+    #[inline]
+    fn set_keypad_application_mode(&mut self) {
+        log::trace!("Setting keypad application mode");
+        self.mode.insert(Mode::APP_KEYPAD);
+    }
+
+    #[inline]
+    fn unset_keypad_application_mode(&mut self) {
+        log::trace!("Unsetting keypad application mode");
+        self.mode.remove(Mode::APP_KEYPAD);
+    }
+
+    /// Store data into clipboard.
+    #[inline]
+    fn clipboard_store(&mut self, clipboard: u8, base64: &[u8]) {
+        let clipboard_type = match clipboard {
+            b'c' => ClipboardType::Clipboard,
+            b'p' | b's' => ClipboardType::Selection,
+            _ => return,
+        };
+
+        if let Ok(bytes) = general_purpose::STANDARD.decode(base64) {
+            if let Ok(text) = String::from_utf8(bytes) {
+                self.send_event(RioEvent::ClipboardStore(clipboard_type, text));
+            }
+        }
+    }
+
+    #[inline]
+    fn configure_charset(
+        &mut self,
+        index: pos::CharsetIndex,
+        charset: pos::StandardCharset,
+    ) {
+        log::trace!("Configuring charset {:?} as {:?}", index, charset);
+        self.grid.cursor.charsets[index] = charset;
+    }
+
+    #[inline]
+    fn show_control_characters(&self) -> bool {
+        self.show_control_characters
+    }
+
+    #[inline(never)]
+    fn input(&mut self, c: char) {
+        // Controls (e.g. NUL or DEL) never take a cell, even as zero-width.
+        if c.is_control() {
+            return;
+        }
+
+        let width = match c.width() {
+            Some(width) => width,
+            None => return,
+        };
+
+        // Handle zero-width characters.
+        if width == 0 {
+            // // Get previous column.
+            let mut column = self.grid.cursor.pos.col;
+            if !self.grid.cursor.should_wrap {
+                column.0 = column.saturating_sub(1);
+            }
+
+            // // Put zerowidth characters over first fullwidth character cell.
+            let row = self.grid.cursor.pos.row;
+            if self.grid[row][column]
+                .flags
+                .contains(square::Flags::WIDE_CHAR_SPACER)
+            {
+                column.0 = column.saturating_sub(1);
+            }
+
+            self.grid[row][column].push_zerowidth(c);
+            if self.grid[row][column].c == kitty_graphics::PLACEHOLDER {
+                self.update_placeholder(Pos::new(row, column));
+            }
+            return;
+        }
+
+        if self.grid.cursor.should_wrap {
+            self.wrapline();
+        }
+
+        let columns = self.grid.columns();
+        if self.mode.contains(Mode::INSERT) && self.grid.cursor.pos.col + width < columns
+        {
+            let line = self.grid.cursor.pos.row;
+            let col = self.grid.cursor.pos.col;
+            let row = &mut self.grid[line][..];
+
+            for col in (col.0..(columns - width)).rev() {
+                row.swap(col + width, col);
+            }
+        }
+
+        if width == 1 {
+            self.write_at_cursor(c);
+            if c == kitty_graphics::PLACEHOLDER {
+                self.update_placeholder(self.grid.cursor.pos);
+            }
+        } else {
+            if self.grid.cursor.pos.col + 1 >= columns {
+                if self.mode.contains(Mode::LINE_WRAP) {
+                    // Insert placeholder before wide char if glyph does not fit in this row.
+                    self.grid
+                        .cursor
+                        .template
+                        .flags
+                        .insert(square::Flags::LEADING_WIDE_CHAR_SPACER);
+                    self.write_at_cursor(' ');
+                    self.grid
+                        .cursor
+                        .template
+                        .flags
+                        .remove(square::Flags::LEADING_WIDE_CHAR_SPACER);
+                    self.wrapline();
+                } else {
+                    // Prevent out of bounds crash when linewrapping is disabled.
+                    self.grid.cursor.should_wrap = true;
+                    return;
+                }
+            }
+
+            self.grid
+                .cursor
+                .template
+                .flags
+                .insert(square::Flags::WIDE_CHAR);
+            self.write_at_cursor(c);
+            self.grid
+                .cursor
+                .template
+                .flags
+                .remove(square::Flags::WIDE_CHAR);
+
+            // Write spacer to cell following the wide glyph.
+            self.grid.cursor.pos.col += 1;
+            self.grid
+                .cursor
+                .template
+                .flags
+                .insert(square::Flags::WIDE_CHAR_SPACER);
+            self.write_at_cursor(' ');
+            self.grid
+                .cursor
+                .template
+                .flags
+                .remove(square::Flags::WIDE_CHAR_SPACER);
+        }
+
+        if self.grid.cursor.pos.col + 1 < columns {
+            self.grid.cursor.pos.col += 1;
+        } else {
+            self.grid.cursor.should_wrap = true;
+        }
+    }
+
+    #[inline]
+    fn identify_terminal(&mut self, intermediate: Option<char>) {
+        match intermediate {
+            None => {
+                log::trace!("Reporting primary device attributes");
+                let text = String::from("\x1b[?62;4;6;22c");
+                self.send_event(RioEvent::PtyWrite(text));
+            }
+            Some('>') => {
+                log::trace!("Reporting secondary device attributes");
+                let version = version_number(env!("CARGO_PKG_VERSION"));
+                let text = format!("\x1b[>0;{version};1c");
+                self.send_event(RioEvent::PtyWrite(text));
+            }
+            Some('=') => {
+                log::trace!("Reporting tertiary device attributes");
+                let text = format!("\x1bP!|{TERTIARY_DA_UNIT_ID}\x1b\\");
+                self.send_event(RioEvent::PtyWrite(text));
+            }
+            _ => debug!("Unsupported device attributes intermediate"),
+        }
+    }
+
+    #[inline]
+    fn report_keyboard_mode(&mut self) {
+        let current_mode = self
+            .keyboard_mode_stack
+            .last()
+            .unwrap_or(&KeyboardModes::NO_MODE)
+            .bits();
+        let text = format!("\x1b[?{current_mode}u");
+        self.send_event(RioEvent::PtyWrite(text));
+    }
+
+    #[inline]
+    fn push_keyboard_mode(&mut self, mode: KeyboardModes) {
+        if self.keyboard_mode_stack.len() >= KEYBOARD_MODE_STACK_MAX_DEPTH {
+            let _removed = self.title_stack.remove(0);
+        }
+
+        self.keyboard_mode_stack.push(mode);
+        self.set_keyboard_mode(mode.into(), KeyboardModesApplyBehavior::Replace);
+    }
+
+    #[inline]
+    fn pop_keyboard_modes(&mut self, to_pop: u16) {
+        let new_len = self
+            .keyboard_mode_stack
+            .len()
+            .saturating_sub(to_pop as usize);
+        self.keyboard_mode_stack.truncate(new_len);
+
+        // Reload active mode.
+        let mode = self
+            .keyboard_mode_stack
+            .last()
+            .copied()
+            .unwrap_or(KeyboardModes::NO_MODE);
+        self.set_keyboard_mode(mode.into(), KeyboardModesApplyBehavior::Replace);
+    }
+
+    #[inline]
+    fn set_keyboard_mode(
+        &mut self,
+        mode: KeyboardModes,
+        apply: KeyboardModesApplyBehavior,
+    ) {
+        self.set_keyboard_mode(mode.into(), apply);
+    }
+
+    #[inline]
+    fn device_status(&mut self, arg: usize) {
+        log::trace!("Reporting device status: {}", arg);
+        match arg {
+            5 => {
+                let text = String::from("\x1b[0n");
+                self.send_event(RioEvent::PtyWrite(text));
+            }
+            6 => {
+                let pos = self.grid.cursor.pos;
+                let text = format!("\x1b[{};{}R", pos.row + 1, pos.col + 1);
+                self.send_event(RioEvent::PtyWrite(text));
+            }
+            _ => debug!("unknown device status query: {}", arg),
+        };
+    }
+
+    #[inline]
+    fn newline(&mut self) {
+        self.linefeed();
+
+        if self.mode.contains(Mode::LINE_FEED_NEW_LINE) {
+            self.carriage_return();
+        }
+    }
+
+    #[inline]
+    fn backspace(&mut self) {
+        if self.grid.cursor.pos.col > Column(0) {
+            let line = self.grid.cursor.pos.row.0 as usize;
+            let column = self.grid.cursor.pos.col.0;
+            self.grid.cursor.pos.col -= 1;
+            self.grid.cursor.should_wrap = false;
+            self.damage.damage_line(line, column - 1, column);
+        }
+    }
+
+    #[inline]
+    fn clear_screen(&mut self, mode: ClearMode) {
+        let bg = self.grid.cursor.template.bg;
+
+        let screen_lines = self.grid.screen_lines();
+
+        match mode {
+            ClearMode::Above => {
+                let cursor = self.grid.cursor.pos;
+
+                // If clearing more than one line.
+                if cursor.row > 1 {
+                    // Fully clear all lines before the current line.
+                    self.grid.reset_region(..cursor.row);
+                }
+
+                // Clear up to the current column in the current line.
+                let end = std::cmp::min(cursor.col + 1, Column(self.grid.columns()));
+                for cell in &mut self.grid[cursor.row][..end] {
+                    *cell = bg.into();
+                }
+
+                let range = Line(0)..=cursor.row;
+                self.selection =
+                    self.selection.take().filter(|s| !s.intersects_range(range));
+            }
+            ClearMode::Below => {
+                let cursor = self.grid.cursor.pos;
+                for cell in &mut self.grid[cursor.row][cursor.col..] {
+                    *cell = bg.into();
+                }
+
+                if (cursor.row.0 as usize) < screen_lines - 1 {
+                    self.grid.reset_region((cursor.row + 1)..);
+                }
+
+                let range = cursor.row..Line(screen_lines as i32);
+                self.selection =
+                    self.selection.take().filter(|s| !s.intersects_range(range));
+            }
+            ClearMode::All => {
+                if self.mode.contains(Mode::ALT_SCREEN) {
+                    self.grid.reset_region(..);
+                } else {
+                    let old_offset = self.grid.display_offset();
+
+                    self.grid.clear_viewport();
+
+                    // Compute number of lines scrolled by clearing the viewport.
+                    let lines = self.grid.display_offset().saturating_sub(old_offset);
+
+                    self.vi_mode_cursor.pos.row = (self.vi_mode_cursor.pos.row - lines)
+                        .grid_clamp(&self.grid, Boundary::Grid);
+                }
+
+                self.selection = None;
+            }
+            ClearMode::Saved if self.preserve_scrollback_on_clear => (),
+            ClearMode::Saved => self.drop_history(),
+        }
+
+        self.mark_fully_damaged();
+    }
+
+    #[inline]
+    fn clear_tabs(&mut self, mode: TabulationClearMode) {
+        match mode {
+            TabulationClearMode::Current => {
+                self.tabs[self.grid.cursor.pos.col] = false;
+            }
+            TabulationClearMode::All => {
+                self.tabs.clear_all();
+            }
+        }
+    }
+
+    #[inline]
+    fn linefeed(&mut self) {
+        self.wrapped_lines = 0;
+        let next = self.grid.cursor.pos.row + 1;
+        if next == self.scroll_region.end {
+            self.scroll_up_relative(self.scroll_region.start, 1);
+        } else if next < self.grid.screen_lines() {
+            self.damage_cursor();
+            self.grid.cursor.pos.row += 1;
+            self.damage_cursor();
+        }
+    }
+
+    #[inline]
+    fn set_horizontal_tabstop(&mut self) {
+        self.tabs[self.grid.cursor.pos.col] = true;
+    }
+
+    #[inline]
+    fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
+        self.grid.cursor.template.set_hyperlink(hyperlink);
+    }
+
+    #[inline]
+    fn semantic_prompt(&mut self, mark: SemanticPrompt) {
+        if mark == SemanticPrompt::PromptStart {
+            let line = self.grid.cursor.pos.row;
+            self.grid[line].flags.insert(RowFlag::PROMPT_START);
+        }
+
+        if mark == SemanticPrompt::CommandStart {
+            self.send_event(RioEvent::PromptReady(self.route_id));
+        }
+    }
+
+    /// Set the indexed color value.
+    #[inline]
+    fn set_color(&mut self, _index: usize, _color: ColorRgb) {
+        // Damage terminal if the color changed and it's not the cursor.
+        // if index != NamedColor::Cursor as usize && self.colors[index] != Some(color) {
+        // self.mark_fully_damaged();
+        // }
+
+        // self.colors[index] = Some(color);
+    }
+
+    #[inline]
+    fn reset_color(&mut self, _index: usize) {
+        // Damage terminal if the color changed and it's not the cursor.
+        // if index != NamedColor::Cursor as usize && self.colors[index].is_some() {
+        // self.mark_fully_damaged();
+        // }
+
+        // self.colors[index] = None;
+    }
+
+    #[inline]
+    fn bell(&mut self) {
+        self.bell = true;
+    }
+
+    #[inline]
+    fn substitute(&mut self) {
+        warn!("[unimplemented] Substitute");
+    }
+
+    #[inline]
+    fn clipboard_load(&mut self, clipboard: u8, terminator: &str) {
+        let clipboard_type = match clipboard {
+            b'c' => ClipboardType::Clipboard,
+            b'p' | b's' => ClipboardType::Selection,
+            _ => return,
+        };
+
+        let terminator = terminator.to_owned();
+
+        self.send_event(RioEvent::ClipboardLoad(
+            clipboard_type,
+            Arc::new(move |text| {
+                let base64 = general_purpose::STANDARD.encode(text);
+                format!("\x1b]52;{};{}{}", clipboard as char, base64, terminator)
+            }),
+        ));
+    }
+
+    #[inline]
+    fn put_tab(&mut self, mut count: u16) {
+        // A tab after the last column is the same as a linebreak.
+        if self.grid.cursor.should_wrap {
+            self.wrapline();
+            return;
+        }
+
+        while self.grid.cursor.pos.col < self.grid.columns() && count != 0 {
+            count -= 1;
+
+            let c = self.grid.cursor.charsets[self.active_charset].map('\t');
+            let cell = self.grid.cursor_square();
+            if cell.c == ' ' {
+                cell.c = c;
+            }
+
+            loop {
+                if (self.grid.cursor.pos.col + 1) == self.grid.columns() {
+                    break;
+                }
+
+                self.grid.cursor.pos.col += 1;
+
+                if self.tabs[self.grid.cursor.pos.col] {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn carriage_return(&mut self) {
+        self.wrapped_lines = 0;
+        let new_col = 0;
+        let row = self.grid.cursor.pos.row.0 as usize;
+        self.damage
+            .damage_line(row, new_col, self.grid.cursor.pos.col.0);
+        self.grid.cursor.pos.col = Column(new_col);
+        self.grid.cursor.should_wrap = false;
+    }
+
+    #[inline]
+    fn move_forward_tabs(&mut self, count: u16) {
+        log::trace!("[unimplemented] Moving forward {} tabs", count);
+    }
+
+    #[inline]
+    fn save_cursor_position(&mut self) {
+        self.grid.saved_cursor = self.grid.cursor.clone();
+        self.saved_cursor_state = self.current_cursor_state();
+    }
+
+    /// Restores what DECSC saved, without a previous save it moves the
+    /// cursor home with default attributes.
+    #[inline]
+    fn restore_cursor_position(&mut self) {
+        self.damage_cursor();
+        self.grid.cursor = self.grid.saved_cursor.clone();
+        self.active_charset = self.saved_cursor_state.active_charset;
+        self.mode.set(Mode::ORIGIN, self.saved_cursor_state.origin);
+        self.damage_cursor();
+    }
+
+    #[inline]
+    fn clear_line(&mut self, mode: LineClearMode) {
+        let cursor = &self.grid.cursor;
+        let bg = cursor.template.bg;
+        let point = cursor.pos;
+
+        let (left, right) = match mode {
+            LineClearMode::Right if cursor.should_wrap => return,
+            LineClearMode::Right => (point.col, Column(self.grid.columns())),
+            LineClearMode::Left => (Column(0), point.col + 1),
+            LineClearMode::All => (Column(0), Column(self.grid.columns())),
+        };
+
+        self.damage
+            .damage_line(point.row.0 as usize, left.0, right.0 - 1);
+
+        let row = &mut self.grid[point.row];
+        for cell in &mut row[left..right] {
+            *cell = bg.into();
+        }
+
+        let range = self.grid.cursor.pos.row..=self.grid.cursor.pos.row;
+        self.selection = self.selection.take().filter(|s| !s.intersects_range(range));
+    }
+
+    #[inline]
+    fn set_scrolling_region(&mut self, top: usize, bottom: Option<usize>) {
+        // Fallback to the last line as default.
+        let bottom = bottom.unwrap_or_else(|| self.grid.screen_lines());
+
+        if top >= bottom {
+            warn!("Invalid scrolling region: ({};{})", top, bottom);
+            return;
+        }
+
+        // Bottom should be included in the range, but range end is not
+        // usually included. One option would be to use an inclusive
+        // range, but instead we just let the open range end be 1
+        // higher.
+        let start = Line(top as i32 - 1);
+        let end = Line(bottom as i32);
+
+        debug!("Setting scrolling region: ({};{})", start, end);
+
+        let screen_lines = Line(self.grid.screen_lines() as i32);
+        self.scroll_region.start = std::cmp::min(start, screen_lines);
+        self.scroll_region.end = std::cmp::min(end, screen_lines);
+        self.goto(Line(0), Column(0));
+    }
+
+    fn set_area_attributes(
+        &mut self,
+        top: usize,
+        left: usize,
+        bottom: Option<usize>,
+        right: Option<usize>,
+        attrs: &[Attr],
+    ) {
+        let (y_offset, max_y) = if self.mode.contains(Mode::ORIGIN) {
+            (
+                self.scroll_region.start.0 as usize,
+                self.scroll_region.end.0 as usize,
+            )
+        } else {
+            (0, self.grid.screen_lines())
+        };
+        let columns = self.grid.columns();
+
+        let top = (top.max(1) - 1 + y_offset).min(max_y);
+        let bottom = bottom.map_or(max_y, |bottom| (bottom + y_offset).min(max_y));
+        let left = left.max(1) - 1;
+        let right = right.map_or(columns, |right| right.min(columns));
+        if top >= bottom {
+            return;
+        }
+
+        for line in top..bottom {
+            let (start, end) = if self.rectangular_attribute_changes {
+                (left, right)
+            } else {
+                // The stream extent runs from the top left corner to the
+                // bottom right one, covering whole lines in between.
+                let start = if line == top { left } else { 0 };
+                let end = if line == bottom - 1 { right } else { columns };
+                (start, end)
+            };
+            if start >= end {
+                continue;
+            }
+
+            let row = &mut self.grid[Line(line as i32)];
+            Self::bulk_apply_sgr(&mut row[Column(start)..Column(end)], attrs);
+        }
+
+        self.mark_fully_damaged();
+    }
+
+    #[inline]
+    fn set_attribute_change_extent(&mut self, rectangle: bool) {
+        self.rectangular_attribute_changes = rectangle;
+    }
+
+    #[inline]
+    fn text_area_size_pixels(&mut self) {
+        debug!("text_area_size_pixels");
+        self.send_event(RioEvent::TextAreaSizeRequest(Arc::new(
+            move |window_size| {
+                let height = window_size.height;
+                let width = window_size.width;
+                format!("\x1b[4;{height};{width}t")
+            },
+        )));
+    }
+
+    #[inline]
+    fn cells_size_pixels(&mut self) {
+        // https://terminalguide.namepad.de/seq/csi_st-16/
+        let text = format!(
+            "\x1b[6;{};{}t",
+            self.graphics.cell_height, self.graphics.cell_width
+        );
+        debug!("cells_size_pixels {:?}", text);
+        self.send_event(RioEvent::PtyWrite(text));
+    }
+
+    #[inline]
+    fn text_area_size_chars(&mut self) {
+        let text = format!(
+            "\x1b[8;{};{}t",
+            self.grid.screen_lines(),
+            self.grid.columns()
+        );
+        debug!("text_area_size_chars {:?}", text);
+        self.send_event(RioEvent::PtyWrite(text));
+    }
+
+    #[inline]
+    fn graphics_attribute(&mut self, pi: u16, pa: u16) {
+        // From Xterm documentation:
+        //
+        //   CSI ? Pi ; Pa ; Pv S
+        //
+        //   Pi = 1  -> item is number of color registers.
+        //   Pi = 2  -> item is Sixel graphics geometry (in pixels).
+        //   Pi = 3  -> item is ReGIS graphics geometry (in pixels).
+        //
+        //   Pa = 1  -> read attribute.
+        //   Pa = 2  -> reset to default.
+        //   Pa = 3  -> set to value in Pv.
+        //   Pa = 4  -> read the maximum allowed value.
+        //
+        //   Pv is ignored by xterm except when setting (Pa == 3).
+        //   Pv = n <- A single integer is used for color registers.
+        //   Pv = width ; height <- Two integers for graphics geometry.
+        //
+        //   xterm replies with a control sequence of the same form:
+        //
+        //   CSI ? Pi ; Ps ; Pv S
+        //
+        //   where Ps is the status:
+        //   Ps = 0  <- success.
+        //   Ps = 1  <- error in Pi.
+        //   Ps = 2  <- error in Pa.
+        //   Ps = 3  <- failure.
+        //
+        //   On success, Pv represents the value read or set.
+
+        fn generate_response(pi: u16, ps: u16, pv: &[usize]) -> String {
+            use std::fmt::Write;
+            let mut text = format!("\x1b[?{};{}", pi, ps);
+            for item in pv {
+                let _ = write!(&mut text, ";{}", item);
+            }
+            text.push('S');
+            text
+        }
+
+        let (ps, pv) = match pi {
+            1 => {
+                match pa {
+                    1 => (0, &[sixel::MAX_COLOR_REGISTERS][..]), // current value is always the
+                    // maximum
+                    2 => (3, &[][..]), // Report unsupported
+                    3 => (3, &[][..]), // Report unsupported
+                    4 => (0, &[sixel::MAX_COLOR_REGISTERS][..]),
+                    _ => (2, &[][..]), // Report error in Pa
+                }
+            }
+
+            2 => {
+                match pa {
+                    1 => {
+                        self.send_event(RioEvent::TextAreaSizeRequest(Arc::new(
+                            move |window_size| {
+                                let width = window_size.width;
+                                let height = window_size.height;
+                                let graphic_dimensions = [
+                                    std::cmp::min(
+                                        width as usize,
+                                        MAX_GRAPHIC_DIMENSIONS[0],
+                                    ),
+                                    std::cmp::min(
+                                        height as usize,
+                                        MAX_GRAPHIC_DIMENSIONS[1],
+                                    ),
+                                ];
+
+                                let (ps, pv) = (0, &graphic_dimensions[..]);
+                                generate_response(pi, ps, pv)
+                            },
+                        )));
+                        return;
+                    }
+                    2 => (3, &[][..]), // Report unsupported
+                    3 => (3, &[][..]), // Report unsupported
+                    4 => (0, &MAX_GRAPHIC_DIMENSIONS[..]),
+                    _ => (2, &[][..]), // Report error in Pa
+                }
+            }
+
+            3 => {
+                (1, &[][..]) // Report error in Pi (ReGIS unknown)
+            }
+
+            _ => {
+                (1, &[][..]) // Report error in Pi
+            }
+        };
+
+        self.send_event(RioEvent::PtyWrite(generate_response(pi, ps, pv)));
+    }
+
+    #[inline]
+    fn start_sixel_graphic(&mut self, params: &Params) -> Option<Box<sixel::Parser>> {
+        let palette = self.graphics.sixel_shared_palette.take();
+        Some(Box::new(sixel::Parser::new(params, palette)))
+    }
+
+    #[inline]
+    fn insert_graphic(&mut self, graphic: GraphicData, palette: Option<Vec<ColorRgb>>) {
+        // Store last palette if we receive a new one, and it is shared.
+        if let Some(palette) = palette {
+            if !self.mode.contains(Mode::SIXEL_PRIV_PALETTE) {
+                self.graphics.sixel_shared_palette = Some(palette);
+            }
+        }
+
+        // If SIXEL_DISPLAY is disabled, the start of the graphic is the
+        // cursor position, and the grid can be scrolled if the graphic is
+        // larger than the screen. The cursor is moved to the next line
+        // after the graphic.
+        //
+        // If it is disabled, the graphic starts at (0, 0), the grid is never
+        // scrolled, and the cursor position is unmodified.
+
+        let scrolling = !self.mode.contains(Mode::SIXEL_DISPLAY);
+
+        let origin = if scrolling {
+            self.grid.cursor.pos
+        } else {
+            Pos::new(Line(0), Column(0))
+        };
+
+        let Some((_, graphic_columns)) = self.place_graphic(graphic, origin, scrolling)
+        else {
+            return;
+        };
+
+        if self.mode.contains(Mode::SIXEL_CURSOR_TO_THE_RIGHT) {
+            self.move_forward(Column(graphic_columns));
+        } else if scrolling {
+            self.linefeed();
+            self.carriage_return();
+        }
+    }
+
+    #[inline]
+    fn report_termprop(&mut self, name: &str) {
+        match self.encode_termprop(name) {
+            Some(reply) => {
+                let text = String::from_utf8_lossy(&reply).into_owned();
+                self.send_event(RioEvent::PtyWrite(text));
+            }
+            None => debug!("Unknown terminal property {name:?}"),
+        }
+    }
+
+    fn report_status_string(&mut self, setting: &[u8]) {
+        let value = match setting {
+            // DECSCUSR
+            b" q" => {
+                let blinking = self.cursor_style_blinking.unwrap_or(self.blinking_cursor);
+                let style = match self.cursor_shape {
+                    CursorShape::Underline => 4,
+                    CursorShape::Beam => 6,
+                    CursorShape::Block | CursorShape::Hidden => 2,
+                } - blinking as u8;
+                Some(format!("{style} q"))
+            }
+            // DECSTBM
+            b"r" => Some(format!(
+                "{};{}r",
+                self.scroll_region.start.0 + 1,
+                self.scroll_region.end.0
+            )),
+            _ => None,
+        };
+
+        let text = match value {
+            Some(value) => format!("\x1bP1$r{value}\x1b\\"),
+            None => {
+                debug!("Unsupported DECRQSS setting {setting:?}");
+                String::from("\x1bP0$r\x1b\\")
+            }
+        };
+        self.send_event(RioEvent::PtyWrite(text));
+    }
+
+    fn kitty_graphics(&mut self, command: kitty_graphics::Command) {
+        use kitty_graphics::{Action, Error};
+
+        let Some(command) = self.kitty_graphics.chunk(command) else {
+            return;
+        };
+
+        let mut image_id = command.image_id;
+        let result = match command.action {
+            _ if command.truncated => Err(Error::invalid("command is too large")),
+            Action::Query => self.kitty_graphics.load(&command).map(|_| ()),
+            Action::Transmit | Action::TransmitAndDisplay => {
+                match self.kitty_graphics.load(&command) {
+                    Ok(graphic) => {
+                        image_id = self.kitty_graphics.store(&command, graphic);
+                        if command.action == Action::TransmitAndDisplay {
+                            self.place_kitty_graphic(&command, image_id)
+                        } else {
+                            Ok(())
+                        }
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+            Action::Place => match self.kitty_graphics.image_id(&command) {
+                Some(id) => {
+                    image_id = id;
+                    self.place_kitty_graphic(&command, image_id)
+                }
+                None => Err(Error::not_found("no such image")),
+            },
+            Action::Delete => {
+                self.delete_kitty_graphics(&command);
+                return;
+            }
+            Action::Other(action) => Err(Error::invalid(format!(
+                "unsupported action {}",
+                action as char
+            ))),
+        };
+
+        if let Err(err) = &result {
+            debug!("Kitty graphics command failed: {err}");
+        }
+
+        if let Some(text) = kitty_graphics::response(&command, image_id, &result) {
+            self.send_event(RioEvent::PtyWrite(text));
+        }
+    }
+}
+
+/// Byte ranges of the URLs matched by `regex` in `text`. Trailing
+/// punctuation and unbalanced closing brackets are left out, they
+/// usually belong to the sentence around the URL.
+pub fn find_urls(regex: &regex::Regex, text: &str) -> Vec<Range<usize>> {
+    regex
+        .find_iter(text)
+        .filter_map(|url| {
+            let mut end = url.end();
+            loop {
+                let value = &text[url.start()..end];
+                let Some(last) = value.chars().last() else {
+                    break;
+                };
+
+                let unbalanced = |open: char| {
+                    value.matches(last).count() > value.matches(open).count()
+                };
+                let trim = match last {
+                    '.' | ',' | ':' | ';' | '!' | '?' | '\'' | '"' => true,
+                    ')' => unbalanced('('),
+                    ']' => unbalanced('['),
+                    '}' => unbalanced('{'),
+                    _ => false,
+                };
+                if !trim {
+                    break;
+                }
+                end -= last.len_utf8();
+            }
+
+            // Only the scheme is left.
+            let remaining = regex.find(&text[url.start()..end]);
+            remaining
+                .filter(|found| found.start() == 0)
+                .map(|_| url.start()..end)
+        })
+        .collect()
+}
+
+pub struct CrosswordsSize {
+    pub columns: usize,
+    pub screen_lines: usize,
+    pub width: u32,
+    pub height: u32,
+    pub square_width: u32,
+    pub square_height: u32,
+}
+
+impl CrosswordsSize {
+    pub fn new(columns: usize, screen_lines: usize) -> Self {
+        Self {
+            columns,
+            screen_lines,
+            width: 0,
+            height: 0,
+            square_width: 0,
+            square_height: 0,
+        }
+    }
+
+    pub fn new_with_dimensions(
+        columns: usize,
+        screen_lines: usize,
+        width: u32,
+        height: u32,
+        square_width: u32,
+        square_height: u32,
+    ) -> Self {
+        Self {
+            columns,
+            screen_lines,
+            width,
+            height,
+            square_width,
+            square_height,
+        }
+    }
+}
+
+impl Dimensions for CrosswordsSize {
+    fn total_lines(&self) -> usize {
+        self.screen_lines()
+    }
+
+    fn screen_lines(&self) -> usize {
+        self.screen_lines
+    }
+
+    fn columns(&self) -> usize {
+        self.columns
+    }
+
+    fn square_width(&self) -> f32 {
+        self.square_width as f32
+    }
+
+    fn square_height(&self) -> f32 {
+        self.square_height as f32
+    }
+}
+
+impl<T: EventListener> Dimensions for Crosswords<T> {
+    #[inline]
+    fn columns(&self) -> usize {
+        self.grid.columns()
+    }
+
+    #[inline]
+    fn screen_lines(&self) -> usize {
+        self.grid.screen_lines()
+    }
+
+    #[inline]
+    fn total_lines(&self) -> usize {
+        self.grid.total_lines()
+    }
+
+    fn square_width(&self) -> f32 {
+        0.
+    }
+
+    fn square_height(&self) -> f32 {
+        0.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crosswords::pos::{Column, Line, Pos, Side};
+    use crate::crosswords::CrosswordsSize;
+    use crate::event::VoidListener;
+
+    #[test]
+    fn scroll_up() {
+        let size = CrosswordsSize::new(1, 10);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        for i in 0..10 {
+            cw.grid[Line(i)][Column(0)].c = i as u8 as char;
+        }
+
+        cw.grid.scroll_up(&(Line(0)..Line(10)), 2);
+
+        assert_eq!(cw.grid[Line(0)][Column(0)].c, '\u{2}');
+        assert_eq!(cw.grid[Line(0)].occ, 1);
+        assert_eq!(cw.grid[Line(1)][Column(0)].c, '\u{3}');
+        assert_eq!(cw.grid[Line(1)].occ, 1);
+        assert_eq!(cw.grid[Line(2)][Column(0)].c, '\u{4}');
+        assert_eq!(cw.grid[Line(2)].occ, 1);
+        assert_eq!(cw.grid[Line(3)][Column(0)].c, '\u{5}');
+        assert_eq!(cw.grid[Line(3)].occ, 1);
+        assert_eq!(cw.grid[Line(4)][Column(0)].c, '\u{6}');
+        assert_eq!(cw.grid[Line(4)].occ, 1);
+        assert_eq!(cw.grid[Line(5)][Column(0)].c, '\u{7}');
+        assert_eq!(cw.grid[Line(5)].occ, 1);
+        assert_eq!(cw.grid[Line(6)][Column(0)].c, '\u{8}');
+        assert_eq!(cw.grid[Line(6)].occ, 1);
+        assert_eq!(cw.grid[Line(7)][Column(0)].c, '\u{9}');
+        assert_eq!(cw.grid[Line(7)].occ, 1);
+        assert_eq!(cw.grid[Line(8)][Column(0)].c, ' '); // was 0.
+        assert_eq!(cw.grid[Line(8)].occ, 0);
+        assert_eq!(cw.grid[Line(9)][Column(0)].c, ' '); // was 1.
+        assert_eq!(cw.grid[Line(9)].occ, 0);
+    }
+
+    #[test]
+    fn test_linefeed() {
+        let size = CrosswordsSize::new(1, 1);
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        assert_eq!(cw.grid.total_lines(), 1);
+
+        cw.linefeed();
+        assert_eq!(cw.grid.total_lines(), 2);
+    }
+
+    #[test]
+    fn test_linefeed_moving_cursor() {
+        let size = CrosswordsSize::new(1, 3);
+
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        let cursor = cw.cursor();
+        assert_eq!(cursor.pos.col, 0);
+        assert_eq!(cursor.pos.row, 0);
+
+        cw.linefeed();
+        let cursor = cw.cursor();
+        assert_eq!(cursor.pos.col, 0);
+        assert_eq!(cursor.pos.row, 1);
+
+        // Keep adding lines but keep cursor at max row
+        for _ in 0..20 {
+            cw.linefeed();
+        }
+        let cursor = cw.cursor();
+        assert_eq!(cursor.pos.col, 0);
+        assert_eq!(cursor.pos.row, 2);
+        assert_eq!(cw.grid.total_lines(), 22);
+    }
+
+    #[test]
+    fn test_input() {
+        let size = CrosswordsSize::new(5, 10);
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        for i in 0..4 {
+            cw.grid[Line(0)][Column(i)].c = i as u8 as char;
+        }
+        cw.grid[Line(1)][Column(3)].c = 'b';
+
+        assert_eq!(cw.grid[Line(0)][Column(0)].c, '\u{0}');
+        assert_eq!(cw.grid[Line(0)][Column(1)].c, '\u{1}');
+        assert_eq!(cw.grid[Line(0)][Column(2)].c, '\u{2}');
+        assert_eq!(cw.grid[Line(0)][Column(3)].c, '\u{3}');
+        assert_eq!(cw.grid[Line(0)][Column(4)].c, ' ');
+        assert_eq!(cw.grid[Line(1)][Column(2)].c, ' ');
+        assert_eq!(cw.grid[Line(1)][Column(3)].c, 'b');
+        assert_eq!(cw.grid[Line(0)][Column(4)].c, ' ');
+    }
+
+    #[test]
+    fn simple_selection_works() {
+        let size = CrosswordsSize::new(5, 5);
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        let grid = &mut term.grid;
+        for i in 0..4 {
+            if i == 1 {
+                continue;
+            }
+
+            grid[Line(i)][Column(0)].c = '"';
+
+            for j in 1..4 {
+                grid[Line(i)][Column(j)].c = 'a';
+            }
+
+            grid[Line(i)][Column(4)].c = '"';
+        }
+        grid[Line(2)][Column(0)].c = ' ';
+        grid[Line(2)][Column(4)].c = ' ';
+        grid[Line(2)][Column(4)]
+            .flags
+            .insert(square::Flags::WRAPLINE);
+        grid[Line(3)][Column(0)].c = ' ';
+
+        // Multiple lines contain an empty line.
+        term.selection = Some(Selection::new(
+            SelectionType::Simple,
+            Pos {
+                row: Line(0),
+                col: Column(0),
+            },
+            Side::Left,
+        ));
+        if let Some(s) = term.selection.as_mut() {
+            s.update(
+                Pos {
+                    row: Line(2),
+                    col: Column(4),
+                },
+                Side::Right,
+            );
+        }
+        assert_eq!(
+            term.selection_to_string(),
+            Some(String::from("\"aaa\"\n\n aaa "))
+        );
+
+        // A wrapline.
+        term.selection = Some(Selection::new(
+            SelectionType::Simple,
+            Pos {
+                row: Line(2),
+                col: Column(0),
+            },
+            Side::Left,
+        ));
+        if let Some(s) = term.selection.as_mut() {
+            s.update(
+                Pos {
+                    row: Line(3),
+                    col: Column(4),
+                },
+                Side::Right,
+            );
+        }
+        assert_eq!(
+            term.selection_to_string(),
+            Some(String::from(" aaa  aaa\""))
+        );
+    }
+
+    #[test]
+    fn line_selection_works() {
+        let size = CrosswordsSize::new(5, 1);
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        let mut grid: Grid<Square> = Grid::new(1, 5, 0);
+        for i in 0..5 {
+            grid[Line(0)][Column(i)].c = 'a';
+        }
+        grid[Line(0)][Column(0)].c = '"';
+        grid[Line(0)][Column(3)].c = '"';
+
+        mem::swap(&mut term.grid, &mut grid);
+
+        term.selection = Some(Selection::new(
+            SelectionType::Lines,
+            Pos {
+                row: Line(0),
+                col: Column(3),
+            },
+            Side::Left,
+        ));
+        assert_eq!(term.selection_to_string(), Some(String::from("\"aa\"a\n")));
+    }
+
+    #[test]
+    fn line_selection_drag_over_wrapped_lines() {
+        let size = CrosswordsSize::new(5, 6);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        // Rows: "$ cat", " a.tx", "t", "hello", "wrapp", "ed".
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"$ cat a.txt\r\nhello\r\nwrapped" {
+            parser.advance(&mut term, *byte);
+        }
+
+        // Triple click in the second row of the wrapped command.
+        let mut selection = Selection::new(
+            SelectionType::Lines,
+            Pos::new(Line(1), Column(2)),
+            Side::Left,
+        );
+        term.selection = Some(selection.clone());
+        assert_eq!(
+            term.selection_to_string(),
+            Some(String::from("$ cat a.txt\n"))
+        );
+
+        // Dragging into the first row of another wrapped line takes all of it.
+        selection.update(Pos::new(Line(4), Column(0)), Side::Left);
+        term.selection = Some(selection.clone());
+        assert_eq!(
+            term.selection_to_string(),
+            Some(String::from("$ cat a.txt\nhello\nwrapped\n"))
+        );
+
+        // Dragging back up shrinks it by whole lines again.
+        selection.update(Pos::new(Line(3), Column(4)), Side::Right);
+        term.selection = Some(selection.clone());
+        assert_eq!(
+            term.selection_to_string(),
+            Some(String::from("$ cat a.txt\nhello\n"))
+        );
+
+        // Dragging above the starting line keeps the whole starting line.
+        selection.update(Pos::new(Line(0), Column(0)), Side::Left);
+        term.selection = Some(selection);
+        assert_eq!(
+            term.selection_to_string(),
+            Some(String::from("$ cat a.txt\n"))
+        );
+    }
+
+    #[test]
+    fn block_selection_works() {
+        let size = CrosswordsSize::new(5, 5);
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        let grid = &mut term.grid;
+        for i in 1..4 {
+            grid[Line(i)][Column(0)].c = '"';
+
+            for j in 1..4 {
+                grid[Line(i)][Column(j)].c = 'a';
+            }
+
+            grid[Line(i)][Column(4)].c = '"';
+        }
+        grid[Line(2)][Column(2)].c = ' ';
+        grid[Line(2)][Column(4)]
+            .flags
+            .insert(square::Flags::WRAPLINE);
+        grid[Line(3)][Column(4)].c = ' ';
+
+        term.selection = Some(Selection::new(
+            SelectionType::Block,
+            Pos {
+                row: Line(0),
+                col: Column(3),
+            },
+            Side::Left,
+        ));
+
+        // The same column.
+        if let Some(s) = term.selection.as_mut() {
+            s.update(
+                Pos {
+                    row: Line(3),
+                    col: Column(3),
+                },
+                Side::Right,
+            );
+        }
+        assert_eq!(term.selection_to_string(), Some(String::from("\na\na\na")));
+
+        // The first column.
+        if let Some(s) = term.selection.as_mut() {
+            s.update(
+                Pos {
+                    row: Line(3),
+                    col: Column(0),
+                },
+                Side::Left,
+            );
+        }
+        assert_eq!(
+            term.selection_to_string(),
+            Some(String::from("\n\"aa\n\"a\n\"aa"))
+        );
+
+        // The last column.
+        if let Some(s) = term.selection.as_mut() {
+            s.update(
+                Pos {
+                    row: Line(3),
+                    col: Column(4),
+                },
+                Side::Right,
+            );
+        }
+        assert_eq!(
+            term.selection_to_string(),
+            Some(String::from("\na\"\na\"\na"))
+        );
+    }
+
+    #[test]
+    fn test_find_urls() {
+        let regex = regex::Regex::new(DEFAULT_URL_REGEX).unwrap();
+        let urls = |text: &str| {
+            find_urls(&regex, text)
+                .into_iter()
+                .map(|range| text[range].to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(urls("no links here"), Vec::<String>::new());
+        assert_eq!(urls("see https://rio.io."), ["https://rio.io"]);
+        assert_eq!(
+            urls("https://rio.io, http://a.b/c;"),
+            ["https://rio.io", "http://a.b/c"]
+        );
+        assert_eq!(urls("is it https://rio.io/docs?"), ["https://rio.io/docs"]);
+        assert_eq!(urls("\"https://rio.io/a?b=c\"!"), ["https://rio.io/a?b=c"]);
+        assert_eq!(urls("(see https://rio.io/x)."), ["https://rio.io/x"]);
+        assert_eq!(
+            urls("https://en.wikipedia.org/wiki/Rio_(band))"),
+            ["https://en.wikipedia.org/wiki/Rio_(band)"]
+        );
+        assert_eq!(urls("[https://rio.io/a[0]]"), ["https://rio.io/a[0]"]);
+        assert_eq!(urls("mailto:rio@rio.io: done"), ["mailto:rio@rio.io"]);
+        // Nothing left after the scheme.
+        assert_eq!(urls("https://..."), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_hyperlink_at() {
+        let size = CrosswordsSize::new(10, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        // A URL wrapped over two lines, then one that is not wrapped but
+        // reaches the end of the line.
+        for byte in "go https://rio.io/a.\r\nhttp://a.b\r\nc".as_bytes() {
+            parser.advance(&mut term, *byte);
+        }
+
+        let url = Some(String::from("https://rio.io/a"));
+        assert_eq!(term.hyperlink_at(Pos::new(Line(0), Column(3))), url);
+        assert_eq!(term.hyperlink_at(Pos::new(Line(1), Column(8))), url);
+        assert_eq!(term.hyperlink_at(Pos::new(Line(1), Column(9))), None);
+        assert_eq!(term.hyperlink_at(Pos::new(Line(0), Column(1))), None);
+        assert_eq!(
+            term.hyperlink_at(Pos::new(Line(2), Column(0))),
+            Some(String::from("http://a.b"))
+        );
+        assert_eq!(term.hyperlink_at(Pos::new(Line(3), Column(0))), None);
+        assert_eq!(term.hyperlink_at(Pos::new(Line(4), Column(0))), None);
+
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in "\x1b[H\x1b]8;;file:///tmp\x1b\\link\x1b]8;;\x1b\\".as_bytes() {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(
+            term.hyperlink_at(Pos::new(Line(0), Column(2))),
+            Some(String::from("file:///tmp"))
+        );
+
+        // A custom regex replaces the default one.
+        assert!(term.set_url_regex("(").is_err());
+        term.set_url_regex("http://[a-z.]+").unwrap();
+        assert_eq!(term.hyperlink_at(Pos::new(Line(1), Column(3))), None);
+        assert_eq!(
+            term.hyperlink_at(Pos::new(Line(2), Column(3))),
+            Some(String::from("http://a.b"))
+        );
+    }
+
+    #[test]
+    fn test_search_nearest_hyperlink_from_pos_on_single_line() {
+        let size = CrosswordsSize::new(20, 3);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        let grid = &mut term.grid;
+        for i in 0..19 {
+            grid[Line(0)][Column(i)].c = ' ';
+        }
+
+        // First line does not contain any hyperlink (it is empty as well)
+        let result = term
+            .search_nearest_hyperlink_from_pos(Pos::new(pos::Line(0), pos::Column(2)));
+        assert_eq!(result, None);
+
+        let grid = &mut term.grid;
+        for i in 0..19 {
+            grid[Line(0)][Column(i)].c = 'a';
+        }
+
+        // First line does not contain any hyperlink (does not contain any link)
+        let result = term
+            .search_nearest_hyperlink_from_pos(Pos::new(pos::Line(0), pos::Column(0)));
+        assert_eq!(result, None);
+
+        // Cleanup line
+        let grid = &mut term.grid;
+        for i in 0..19 {
+            grid[Line(0)][Column(i)].c = ' ';
+        }
+
+        let grid = &mut term.grid;
+        let link: [char; 14] = [
+            'h', 't', 't', 'p', 's', ':', '/', '/', 'r', 'i', 'o', '.', 'i', 'o',
+        ];
+        for (i, val) in link.iter().enumerate() {
+            grid[Line(0)][Column(i)].c = *val;
+        }
+
+        assert_eq!(term.grid[Line(0)][Column(0)].c, 'h');
+        assert!(term.grid[Line(0)][Column(0)].hyperlink().is_none());
+        assert_eq!(term.grid[Line(0)][Column(1)].c, 't');
+        assert!(term.grid[Line(0)][Column(1)].hyperlink().is_none());
+        assert_eq!(term.grid[Line(0)][Column(2)].c, 't');
+        assert!(term.grid[Line(0)][Column(2)].hyperlink().is_none());
+        assert_eq!(term.grid[Line(0)][Column(3)].c, 'p');
+        assert!(term.grid[Line(0)][Column(3)].hyperlink().is_none());
+        assert_eq!(term.grid[Line(0)][Column(12)].c, 'i');
+        assert!(term.grid[Line(0)][Column(12)].hyperlink().is_none());
+        assert_eq!(term.grid[Line(0)][Column(13)].c, 'o');
+        assert!(term.grid[Line(0)][Column(13)].hyperlink().is_none());
+
+        // First line does not a hyperlink from (0 to 13) position
+        let result = term
+            .search_nearest_hyperlink_from_pos(Pos::new(pos::Line(0), pos::Column(14)));
+        assert_eq!(result, None);
+
+        // From 'h'
+        let result = term
+            .search_nearest_hyperlink_from_pos(Pos::new(pos::Line(0), pos::Column(0)));
+        assert_eq!(
+            result,
+            Some(SelectionRange {
+                start: Pos {
+                    row: Line(0),
+                    col: Column(0)
+                },
+                end: Pos {
+                    row: Line(0),
+                    col: Column(13)
+                },
+                is_block: false
+            })
+        );
+
+        assert_eq!(term.grid[Line(0)][Column(0)].c, 'h');
+        assert!(term.grid[Line(0)][Column(0)].hyperlink().is_some());
+        assert_eq!(
+            term.grid[Line(0)][Column(0)].hyperlink().unwrap().uri(),
+            "https://rio.io"
+        );
+        assert_eq!(term.grid[Line(0)][Column(1)].c, 't');
+        assert!(term.grid[Line(0)][Column(1)].hyperlink().is_some());
+        assert_eq!(term.grid[Line(0)][Column(2)].c, 't');
+        assert!(term.grid[Line(0)][Column(2)].hyperlink().is_some());
+        assert_eq!(term.grid[Line(0)][Column(3)].c, 'p');
+        assert!(term.grid[Line(0)][Column(3)].hyperlink().is_some());
+        assert_eq!(term.grid[Line(0)][Column(12)].c, 'i');
+        assert!(term.grid[Line(0)][Column(12)].hyperlink().is_some());
+        assert_eq!(term.grid[Line(0)][Column(13)].c, 'o');
+        assert!(term.grid[Line(0)][Column(13)].hyperlink().is_some());
+        assert_eq!(
+            term.grid[Line(0)][Column(13)].hyperlink().unwrap().uri(),
+            "https://rio.io"
+        );
+        assert_eq!(term.grid[Line(0)][Column(14)].c, ' ');
+        assert!(term.grid[Line(0)][Column(14)].hyperlink().is_none());
+
+        // From 'r' (this case should hit square hyperlink info)
+        let result = term
+            .search_nearest_hyperlink_from_pos(Pos::new(pos::Line(0), pos::Column(8)));
+        assert_eq!(
+            result,
+            Some(SelectionRange {
+                start: Pos {
+                    row: Line(0),
+                    col: Column(0)
+                },
+                end: Pos {
+                    row: Line(0),
+                    col: Column(13)
+                },
+                is_block: false
+            })
+        );
+
+        assert_eq!(term.grid[Line(0)][Column(0)].c, 'h');
+        assert!(term.grid[Line(0)][Column(0)].hyperlink().is_some());
+        assert_eq!(
+            term.grid[Line(0)][Column(0)].hyperlink().unwrap().uri(),
+            "https://rio.io"
+        );
+        assert_eq!(term.grid[Line(0)][Column(13)].c, 'o');
+        assert!(term.grid[Line(0)][Column(13)].hyperlink().is_some());
+        assert_eq!(
+            term.grid[Line(0)][Column(13)].hyperlink().unwrap().uri(),
+            "https://rio.io"
+        );
+        assert_eq!(term.grid[Line(0)][Column(14)].c, ' ');
+        assert!(term.grid[Line(0)][Column(14)].hyperlink().is_none());
+    }
+
+    #[test]
+    fn test_search_nearest_hyperlink_from_pos_on_multiple_lines() {
+        let size = CrosswordsSize::new(4, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        let grid = &mut term.grid;
+        grid[Line(0)][Column(0)].c = 'h';
+        grid[Line(0)][Column(1)].c = 't';
+        grid[Line(0)][Column(2)].c = 't';
+        grid[Line(0)][Column(3)].c = 'p';
+        grid[Line(1)][Column(0)].c = 's';
+        grid[Line(1)][Column(1)].c = ':';
+        grid[Line(1)][Column(2)].c = '/';
+        grid[Line(1)][Column(3)].c = '/';
+        grid[Line(2)][Column(0)].c = 'r';
+        grid[Line(2)][Column(1)].c = 'i';
+        grid[Line(2)][Column(2)].c = 'o';
+        grid[Line(2)][Column(3)].c = '.';
+        grid[Line(3)][Column(0)].c = 'i';
+        grid[Line(3)][Column(1)].c = 'o';
+        for line in 0..3 {
+            grid[Line(line)][Column(3)]
+                .flags
+                .insert(square::Flags::WRAPLINE);
+        }
+
+        assert!(term.grid[Line(0)][Column(0)].hyperlink().is_none());
+        assert!(term.grid[Line(1)][Column(0)].hyperlink().is_none());
+        assert!(term.grid[Line(2)][Column(0)].hyperlink().is_none());
+        assert!(term.grid[Line(3)][Column(0)].hyperlink().is_none());
+
+        // From ' ' after '.io'
+        let result = term.search_nearest_hyperlink_from_pos(Pos {
+            row: pos::Line(3),
+            col: pos::Column(2),
+        });
+        assert_eq!(result, None);
+
+        // From 'r'
+        let result = term.search_nearest_hyperlink_from_pos(Pos {
+            row: pos::Line(2),
+            col: pos::Column(1),
+        });
+        assert_eq!(
+            result,
+            Some(SelectionRange {
+                start: Pos {
+                    row: Line(0),
+                    col: Column(0)
+                },
+                end: Pos {
+                    row: Line(3),
+                    col: Column(1)
+                },
+                is_block: false
+            })
+        );
+
+        assert!(term.grid[Line(0)][Column(0)].hyperlink().is_some());
+        assert_eq!(
+            term.grid[Line(0)][Column(0)].hyperlink().unwrap().uri(),
+            "https://rio.io"
+        );
+        assert!(term.grid[Line(0)][Column(0)].hyperlink().is_some());
+        assert!(term.grid[Line(0)][Column(1)].hyperlink().is_some());
+        assert!(term.grid[Line(0)][Column(2)].hyperlink().is_some());
+        assert!(term.grid[Line(0)][Column(3)].hyperlink().is_some());
+        assert!(term.grid[Line(1)][Column(0)].hyperlink().is_some());
+        assert!(term.grid[Line(1)][Column(1)].hyperlink().is_some());
+        assert!(term.grid[Line(1)][Column(2)].hyperlink().is_some());
+        assert!(term.grid[Line(1)][Column(3)].hyperlink().is_some());
+        assert!(term.grid[Line(2)][Column(0)].hyperlink().is_some());
+        assert!(term.grid[Line(2)][Column(1)].hyperlink().is_some());
+        assert!(term.grid[Line(2)][Column(2)].hyperlink().is_some());
+        assert!(term.grid[Line(2)][Column(3)].hyperlink().is_some());
+        assert!(term.grid[Line(3)][Column(0)].hyperlink().is_some());
+        assert_eq!(
+            term.grid[Line(3)][Column(1)].hyperlink().unwrap().uri(),
+            "https://rio.io"
         );
+        assert!(term.grid[Line(3)][Column(2)].hyperlink().is_none());
     }
 
-    #[inline]
-    fn put_tab(&mut self, mut count: u16) {
-        // A tab after the last column is the same as a linebreak.
-        if self.grid.cursor.should_wrap {
-            self.wrapline();
-            return;
-        }
+    #[test]
+    fn test_search_nearest_hyperlink_from_pos_on_existent_hyperlink() {
+        let size = CrosswordsSize::new(4, 4);
+        let window_id = crate::event::WindowId::from(0);
 
-        while self.grid.cursor.pos.col < self.grid.columns() && count != 0 {
-            count -= 1;
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
 
-            let c = self.grid.cursor.charsets[self.active_charset].map('\t');
-            let cell = self.grid.cursor_square();
-            if cell.c == ' ' {
-                cell.c = c;
-            }
+        let grid = &mut term.grid;
+        let hyperlink = Hyperlink::new(None, "https://rio.io");
+        grid[Line(0)][Column(2)].c = 'r';
+        grid[Line(0)][Column(2)].set_hyperlink(Some(hyperlink.clone()));
+        grid[Line(0)][Column(3)].c = ' ';
+        grid[Line(0)][Column(3)].set_hyperlink(Some(hyperlink.clone()));
+        grid[Line(1)][Column(0)].c = '2';
+        grid[Line(1)][Column(0)].set_hyperlink(Some(hyperlink.clone()));
+        grid[Line(1)][Column(1)].c = ' ';
+        grid[Line(1)][Column(2)].c = 'i';
+        grid[Line(1)][Column(2)].set_hyperlink(Some(hyperlink.clone()));
+        grid[Line(1)][Column(3)].c = 'o';
+        grid[Line(1)][Column(3)].set_hyperlink(Some(hyperlink.clone()));
 
-            loop {
-                if (self.grid.cursor.pos.col + 1) == self.grid.columns() {
-                    break;
-                }
+        // "  r "
+        // "2 io"
+        // "    "
+        // "    "
 
-                self.grid.cursor.pos.col += 1;
+        // Hyperlink that should be highlighted is "r 2"
 
-                if self.tabs[self.grid.cursor.pos.col] {
-                    break;
-                }
-            }
-        }
-    }
+        assert!(term.grid[Line(0)][Column(0)].hyperlink().is_none());
+        assert!(term.grid[Line(1)][Column(0)].hyperlink().is_some());
+        assert!(term.grid[Line(2)][Column(0)].hyperlink().is_none());
 
-    #[inline]
-    fn carriage_return(&mut self) {
-        let new_col = 0;
-        let row = self.grid.cursor.pos.row.0 as usize;
-        self.damage
-            .damage_line(row, new_col, self.grid.cursor.pos.col.0);
-        self.grid.cursor.pos.col = Column(new_col);
-        self.grid.cursor.should_wrap = false;
-    }
+        let result = term.search_nearest_hyperlink_from_pos(Pos {
+            row: pos::Line(0),
+            col: pos::Column(0),
+        });
+        assert_eq!(result, None);
 
-    #[inline]
-    fn move_forward_tabs(&mut self, count: u16) {
-        log::trace!("[unimplemented] Moving forward {} tabs", count);
-    }
+        let result = term.search_nearest_hyperlink_from_pos(Pos {
+            row: pos::Line(0),
+            col: pos::Column(3),
+        });
+        assert_eq!(
+            result,
+            Some(SelectionRange {
+                start: Pos {
+                    row: Line(0),
+                    col: Column(2)
+                },
+                end: Pos {
+                    row: Line(1),
+                    col: Column(0)
+                },
+                is_block: false
+            })
+        );
 
-    #[inline]
-    fn save_cursor_position(&mut self) {
-        self.grid.saved_cursor = self.grid.cursor.clone();
-    }
+        assert_eq!(
+            term.grid[Line(0)][Column(2)].hyperlink().unwrap().uri(),
+            "https://rio.io"
+        );
 
-    #[inline]
-    fn restore_cursor_position(&mut self) {
-        self.damage_cursor();
-        self.grid.cursor = self.grid.saved_cursor.clone();
-        self.damage_cursor();
+        // Then we "promote" col 1/ row 1 to hyperlink and connecting with " io"
+        term.grid[Line(1)][Column(1)].set_hyperlink(Some(hyperlink.clone()));
+        let result = term.search_nearest_hyperlink_from_pos(Pos {
+            row: pos::Line(0),
+            col: pos::Column(3),
+        });
+        assert_eq!(
+            result,
+            Some(SelectionRange {
+                start: Pos {
+                    row: Line(0),
+                    col: Column(2)
+                },
+                end: Pos {
+                    row: Line(1),
+                    col: Column(3)
+                },
+                is_block: false
+            })
+        );
     }
 
-    #[inline]
-    fn clear_line(&mut self, mode: LineClearMode) {
-        let cursor = &self.grid.cursor;
-        let bg = cursor.template.bg;
-        let point = cursor.pos;
-
-        let (left, right) = match mode {
-            LineClearMode::Right if cursor.should_wrap => return,
-            LineClearMode::Right => (point.col, Column(self.grid.columns())),
-            LineClearMode::Left => (Column(0), point.col + 1),
-            LineClearMode::All => (Column(0), Column(self.grid.columns())),
-        };
+    #[test]
+    fn test_x10_mouse_mode_is_exclusive() {
+        let size = CrosswordsSize::new(3, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
 
-        self.damage
-            .damage_line(point.row.0 as usize, left.0, right.0 - 1);
+        cw.set_mode(AnsiMode::ReportMouseClicks);
+        cw.set_mode(AnsiMode::X10Mouse);
+        assert!(cw.mode().contains(Mode::MOUSE_X10));
+        assert!(!cw.mode().contains(Mode::MOUSE_REPORT_CLICK));
 
-        let row = &mut self.grid[point.row];
-        for cell in &mut row[left..right] {
-            *cell = bg.into();
-        }
+        cw.set_mode(AnsiMode::ReportAllMouseMotion);
+        assert!(!cw.mode().contains(Mode::MOUSE_X10));
 
-        let range = self.grid.cursor.pos.row..=self.grid.cursor.pos.row;
-        self.selection = self.selection.take().filter(|s| !s.intersects_range(range));
+        cw.set_mode(AnsiMode::X10Mouse);
+        cw.unset_mode(AnsiMode::X10Mouse);
+        assert!(!cw.mode().intersects(Mode::MOUSE_MODE));
     }
 
-    #[inline]
-    fn set_scrolling_region(&mut self, top: usize, bottom: Option<usize>) {
-        // Fallback to the last line as default.
-        let bottom = bottom.unwrap_or_else(|| self.grid.screen_lines());
+    #[test]
+    fn test_wheel_to_arrow_mode() {
+        let size = CrosswordsSize::new(3, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
 
-        if top >= bottom {
-            warn!("Invalid scrolling region: ({};{})", top, bottom);
-            return;
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"\x1b[?1007l\x1b[?7786h" {
+            parser.advance(&mut cw, *byte);
         }
+        assert!(cw.mode().contains(Mode::WHEEL_TO_ARROW));
+        assert!(!cw.mode().contains(Mode::ALTERNATE_SCROLL));
 
-        // Bottom should be included in the range, but range end is not
-        // usually included. One option would be to use an inclusive
-        // range, but instead we just let the open range end be 1
-        // higher.
-        let start = Line(top as i32 - 1);
-        let end = Line(bottom as i32);
+        // Both modes are independent.
+        for byte in b"\x1b[?1007h\x1b[?7786l" {
+            parser.advance(&mut cw, *byte);
+        }
+        assert!(!cw.mode().contains(Mode::WHEEL_TO_ARROW));
+        assert!(cw.mode().contains(Mode::ALTERNATE_SCROLL));
+    }
 
-        debug!("Setting scrolling region: ({};{})", start, end);
+    #[test]
+    fn test_char_at() {
+        let size = CrosswordsSize::new(3, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        cw.grid[Line(1)][Column(2)].c = 'r';
 
-        let screen_lines = Line(self.grid.screen_lines() as i32);
-        self.scroll_region.start = std::cmp::min(start, screen_lines);
-        self.scroll_region.end = std::cmp::min(end, screen_lines);
-        self.goto(Line(0), Column(0));
+        assert_eq!(cw.char_at(Pos::new(Line(1), Column(2))), Some('r'));
+        assert_eq!(cw.char_at(Pos::new(Line(0), Column(0))), Some(' '));
+        assert_eq!(cw.char_at(Pos::new(Line(1), Column(3))), None);
+        assert_eq!(cw.char_at(Pos::new(Line(2), Column(0))), None);
+        assert_eq!(cw.char_at(Pos::new(Line(-1), Column(0))), None);
     }
 
-    #[inline]
-    fn text_area_size_pixels(&mut self) {
-        debug!("text_area_size_pixels");
-        self.event_proxy.send_event(
-            RioEvent::TextAreaSizeRequest(Arc::new(move |window_size| {
-                let height = window_size.height;
-                let width = window_size.width;
-                format!("\x1b[4;{height};{width}t")
-            })),
-            self.window_id,
-        );
-    }
+    #[test]
+    fn test_show_control_characters() {
+        let mut cw = term(12, 2);
+        cw.show_control_characters = true;
 
-    #[inline]
-    fn cells_size_pixels(&mut self) {
-        // https://terminalguide.namepad.de/seq/csi_st-16/
-        let text = format!(
-            "\x1b[6;{};{}t",
-            self.graphics.cell_height, self.graphics.cell_width
-        );
-        debug!("cells_size_pixels {:?}", text);
-        self.event_proxy
-            .send_event(RioEvent::PtyWrite(text), self.window_id);
-    }
+        feed(&mut cw, b"a\x1b[1mb\x07\r\n\x7f");
 
-    #[inline]
-    fn text_area_size_chars(&mut self) {
-        let text = format!(
-            "\x1b[8;{};{}t",
-            self.grid.screen_lines(),
-            self.grid.columns()
-        );
-        debug!("text_area_size_chars {:?}", text);
-        self.event_proxy
-            .send_event(RioEvent::PtyWrite(text), self.window_id);
+        let line: String = (0..9)
+            .filter_map(|column| cw.char_at(Pos::new(Line(0), Column(column))))
+            .collect();
+        assert_eq!(line, "a^[[1mb^G");
+        assert_eq!(cw.char_at(Pos::new(Line(1), Column(0))), Some('^'));
+        assert_eq!(cw.char_at(Pos::new(Line(1), Column(1))), Some('?'));
     }
 
-    #[inline]
-    fn graphics_attribute(&mut self, pi: u16, pa: u16) {
-        // From Xterm documentation:
-        //
-        //   CSI ? Pi ; Pa ; Pv S
-        //
-        //   Pi = 1  -> item is number of color registers.
-        //   Pi = 2  -> item is Sixel graphics geometry (in pixels).
-        //   Pi = 3  -> item is ReGIS graphics geometry (in pixels).
-        //
-        //   Pa = 1  -> read attribute.
-        //   Pa = 2  -> reset to default.
-        //   Pa = 3  -> set to value in Pv.
-        //   Pa = 4  -> read the maximum allowed value.
-        //
-        //   Pv is ignored by xterm except when setting (Pa == 3).
-        //   Pv = n <- A single integer is used for color registers.
-        //   Pv = width ; height <- Two integers for graphics geometry.
-        //
-        //   xterm replies with a control sequence of the same form:
-        //
-        //   CSI ? Pi ; Ps ; Pv S
-        //
-        //   where Ps is the status:
-        //   Ps = 0  <- success.
-        //   Ps = 1  <- error in Pi.
-        //   Ps = 2  <- error in Pa.
-        //   Ps = 3  <- failure.
-        //
-        //   On success, Pv represents the value read or set.
+    #[test]
+    fn test_nul_and_del_are_ignored() {
+        let size = CrosswordsSize::new(10, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
 
-        fn generate_response(pi: u16, ps: u16, pv: &[usize]) -> String {
-            use std::fmt::Write;
-            let mut text = format!("\x1b[?{};{}", pi, ps);
-            for item in pv {
-                let _ = write!(&mut text, ";{}", item);
-            }
-            text.push('S');
-            text
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"\x00r\x7fi\x00\x00o\x7f\x7f\xc3\xa9\x00" {
+            parser.advance(&mut cw, *byte);
+        }
+
+        let line: String = (0..4)
+            .filter_map(|column| cw.char_at(Pos::new(Line(0), Column(column))))
+            .collect();
+        assert_eq!(line, "rio\u{e9}");
+        assert_eq!(cw.grid.cursor.pos.col, Column(4));
+        for column in 0..4 {
+            assert!(cw.grid[Line(0)][Column(column)].zerowidth().is_none());
+        }
+
+        // Controls reaching the printable path are dropped too.
+        cw.input('\0');
+        cw.input('\x7f');
+        cw.input('\u{85}');
+        assert_eq!(cw.grid.cursor.pos.col, Column(4));
+        assert!(cw.grid[Line(0)][Column(3)].zerowidth().is_none());
+    }
+
+    #[test]
+    fn test_decsc_decrc() {
+        let size = CrosswordsSize::new(10, 5);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        // Restoring without a save goes home with default attributes.
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"\x1b[3;4H\x1b[1;31m\x1b8" {
+            parser.advance(&mut cw, *byte);
         }
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(0)));
+        assert_eq!(cw.grid.cursor.template, Square::default());
 
-        let (ps, pv) = match pi {
-            1 => {
-                match pa {
-                    1 => (0, &[sixel::MAX_COLOR_REGISTERS][..]), // current value is always the
-                    // maximum
-                    2 => (3, &[][..]), // Report unsupported
-                    3 => (3, &[][..]), // Report unsupported
-                    4 => (0, &[sixel::MAX_COLOR_REGISTERS][..]),
-                    _ => (2, &[][..]), // Report error in Pa
-                }
-            }
+        // Cursor at 2;3, bold red, DEC line drawing in G1 shifted in, DECOM.
+        for byte in b"\x1b[2;3H\x1b[1;31m\x1b)0\x0e\x1b[?6h\x1b[2;3H\x1b7" {
+            parser.advance(&mut cw, *byte);
+        }
+        let saved = cw.grid.cursor.clone();
 
-            2 => {
-                match pa {
-                    1 => {
-                        self.event_proxy.send_event(
-                            RioEvent::TextAreaSizeRequest(Arc::new(move |window_size| {
-                                let width = window_size.width;
-                                let height = window_size.height;
-                                let graphic_dimensions = [
-                                    std::cmp::min(
-                                        width as usize,
-                                        MAX_GRAPHIC_DIMENSIONS[0],
-                                    ),
-                                    std::cmp::min(
-                                        height as usize,
-                                        MAX_GRAPHIC_DIMENSIONS[1],
-                                    ),
-                                ];
+        // Reset everything, then restore.
+        for byte in b"\x1b[?6l\x1b[0m\x0f\x1b)B\x1b[5;5H\x1b8" {
+            parser.advance(&mut cw, *byte);
+        }
+        assert_eq!(cw.grid.cursor.pos, saved.pos);
+        assert_eq!(cw.grid.cursor.template, saved.template);
+        assert!(cw.grid.cursor.template.flags.contains(square::Flags::BOLD));
+        assert_eq!(cw.grid.cursor.charsets, saved.charsets);
+        assert_eq!(cw.active_charset, CharsetIndex::G1);
+        assert!(cw.mode().contains(Mode::ORIGIN));
 
-                                let (ps, pv) = (0, &graphic_dimensions[..]);
-                                generate_response(pi, ps, pv)
-                            })),
-                            self.window_id,
-                        );
-                        return;
-                    }
-                    2 => (3, &[][..]), // Report unsupported
-                    3 => (3, &[][..]), // Report unsupported
-                    4 => (0, &MAX_GRAPHIC_DIMENSIONS[..]),
-                    _ => (2, &[][..]), // Report error in Pa
-                }
-            }
+        // Line drawing is active again, `q` maps to a horizontal line.
+        parser.advance(&mut cw, b'q');
+        assert_eq!(cw.grid[saved.pos.row][saved.pos.col].c, '─');
+    }
 
-            3 => {
-                (1, &[][..]) // Report error in Pi (ReGIS unknown)
+    #[test]
+    fn test_semantic_prompt_ready() {
+        #[derive(Clone, Default)]
+        struct Recorder(std::sync::Arc<std::sync::Mutex<Vec<String>>>);
+
+        impl EventListener for Recorder {
+            fn event(&self) -> (Option<RioEvent>, bool) {
+                (None, false)
             }
 
-            _ => {
-                (1, &[][..]) // Report error in Pi
+            fn send_event(&self, event: RioEvent, _id: WindowId) {
+                self.0.lock().unwrap().push(format!("{event:?}"));
             }
-        };
+        }
+
+        let size = CrosswordsSize::new(10, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let recorder = Recorder::default();
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, recorder.clone(), window_id, 3);
+
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"\x1b]133;A\x07$ \x1b]133;B\x07ls\x1b]133;C\x07" {
+            parser.advance(&mut cw, *byte);
+        }
+
+        let events = recorder.0.lock().unwrap();
+        assert_eq!(*events, vec![String::from("PromptReady 3")]);
+    }
+
+    #[test]
+    fn test_select_range() {
+        let size = CrosswordsSize::new(5, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"hello\r\nworld" {
+            parser.advance(&mut cw, *byte);
+        }
+
+        // Reversed and out of the grid.
+        let range = cw.select_range(
+            Pos::new(Line(7), Column(9)),
+            Pos::new(Line(0), Column(3)),
+            SelectionType::Simple,
+        );
+        let range = range.unwrap();
+        assert_eq!(range.start, Pos::new(Line(0), Column(3)));
+        assert_eq!(range.end, Pos::new(Line(1), Column(4)));
+        assert_eq!(cw.selection_to_string().as_deref(), Some("lo\nworld"));
+
+        cw.select_range(
+            Pos::new(Line(0), Column(1)),
+            Pos::new(Line(1), Column(2)),
+            SelectionType::Block,
+        );
+        assert_eq!(cw.selection_to_string().as_deref(), Some("el\nor"));
+    }
+
+    #[test]
+    fn test_extend_selection() {
+        let size = CrosswordsSize::new(5, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"hello\r\nworld" {
+            parser.advance(&mut cw, *byte);
+        }
 
-        self.event_proxy.send_event(
-            RioEvent::PtyWrite(generate_response(pi, ps, pv)),
-            self.window_id,
+        // Without a selection the click only sets the anchor.
+        assert_eq!(
+            cw.extend_selection(Pos::new(Line(0), Column(1)), Side::Left),
+            None
         );
+        assert!(cw.selection.is_some());
+
+        let range = cw
+            .extend_selection(Pos::new(Line(1), Column(2)), Side::Right)
+            .unwrap();
+        assert_eq!(range.start, Pos::new(Line(0), Column(1)));
+        assert_eq!(cw.selection_to_string().as_deref(), Some("ello\nwor"));
+
+        // Later clicks move the end, out of the grid is clamped.
+        cw.extend_selection(Pos::new(Line(7), Column(9)), Side::Right);
+        assert_eq!(cw.selection_to_string().as_deref(), Some("ello\nworld"));
+        cw.extend_selection(Pos::new(Line(0), Column(3)), Side::Right);
+        assert_eq!(cw.selection_to_string().as_deref(), Some("ell"));
     }
 
-    #[inline]
-    fn start_sixel_graphic(&mut self, params: &Params) -> Option<Box<sixel::Parser>> {
-        let palette = self.graphics.sixel_shared_palette.take();
-        Some(Box::new(sixel::Parser::new(params, palette)))
+    #[test]
+    fn test_underline_styles() {
+        use crate::config::colors::{AnsiColor, ColorRgb};
+
+        let size = CrosswordsSize::new(10, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"\x1b[4:3;58:2::255:0:0mA\x1b[4mB\x1b[4:4mC\x1b[4:5;59mD\x1b[4:0mE" {
+            parser.advance(&mut cw, *byte);
+        }
+
+        let row = &cw.grid[Line(0)];
+        let underlines =
+            |col: usize| row[Column(col)].flags & square::Flags::ALL_UNDERLINES;
+        assert_eq!(underlines(0), square::Flags::UNDERCURL);
+        assert_eq!(underlines(1), square::Flags::UNDERLINE);
+        assert_eq!(underlines(2), square::Flags::DOTTED_UNDERLINE);
+        assert_eq!(underlines(3), square::Flags::DASHED_UNDERLINE);
+        assert_eq!(underlines(4), square::Flags::empty());
+
+        let red = Some(AnsiColor::Spec(ColorRgb { r: 255, g: 0, b: 0 }));
+        assert_eq!(row[Column(0)].underline_color(), red);
+        // The underline color stays until SGR 59.
+        assert_eq!(row[Column(2)].underline_color(), red);
+        assert_eq!(row[Column(3)].underline_color(), None);
     }
 
-    #[inline]
-    fn insert_graphic(&mut self, graphic: GraphicData, palette: Option<Vec<ColorRgb>>) {
-        let cell_width = self.graphics.cell_width as usize;
-        let cell_height = self.graphics.cell_height as usize;
+    #[test]
+    fn test_osc52_clipboard_store() {
+        let size = CrosswordsSize::new(10, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let recorder = Recorder::default();
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, recorder.clone(), window_id, 0);
 
-        // Store last palette if we receive a new one, and it is shared.
-        if let Some(palette) = palette {
-            if !self.mode.contains(Mode::SIXEL_PRIV_PALETTE) {
-                self.graphics.sixel_shared_palette = Some(palette);
-            }
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        // "hello rio" and malformed payloads, which are ignored.
+        for byte in b"\x1b]52;c;aGVsbG8gcmlv\x07\x1b]52;c;aGVsbG8\x07\x1b]52;p;!!!!\x07" {
+            parser.advance(&mut cw, *byte);
         }
 
-        if graphic.width > MAX_GRAPHIC_DIMENSIONS[0]
-            || graphic.height > MAX_GRAPHIC_DIMENSIONS[1]
-        {
-            return;
+        let events = recorder.0.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        match &events[0] {
+            RioEvent::ClipboardStore(ClipboardType::Clipboard, text) => {
+                assert_eq!(text, "hello rio");
+            }
+            event => panic!("unexpected event {event:?}"),
         }
+    }
 
-        let width = graphic.width as u16;
-        let height = graphic.height as u16;
+    #[test]
+    fn test_sixel_wider_than_columns_is_clipped() {
+        let size = CrosswordsSize::new_with_dimensions(4, 2, 40, 40, 10, 20);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
 
-        if width == 0 || height == 0 {
-            return;
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        // 80x6 pixels, twice the width of the grid.
+        for byte in b"\x1bP0;1q#1;2;100;0;0#1!80~\x1b\\" {
+            parser.advance(&mut cw, *byte);
         }
 
-        let graphic_id = self.graphics.next_id();
+        let row = &cw.grid[Line(0)];
+        assert_eq!(row.len(), 4);
+        for column in 0..4 {
+            let graphics = row[Column(column)].graphics().unwrap();
+            assert_eq!(graphics.len(), 1);
+            assert_eq!(graphics[0].offset_x, column as u16 * 10);
+            assert_eq!(graphics[0].texture.width, 80);
+        }
+    }
 
-        // If SIXEL_DISPLAY is disabled, the start of the graphic is the
-        // cursor position, and the grid can be scrolled if the graphic is
-        // larger than the screen. The cursor is moved to the next line
-        // after the graphic.
-        //
-        // If it is disabled, the graphic starts at (0, 0), the grid is never
-        // scrolled, and the cursor position is unmodified.
+    #[derive(Clone, Default)]
+    struct Recorder(std::sync::Arc<std::sync::Mutex<Vec<RioEvent>>>);
 
-        let scrolling = !self.mode.contains(Mode::SIXEL_DISPLAY);
+    impl EventListener for Recorder {
+        fn event(&self) -> (Option<RioEvent>, bool) {
+            (None, false)
+        }
 
-        let leftmost = if scrolling {
-            self.grid.cursor.pos.col.0
-        } else {
-            0
-        };
+        fn send_event(&self, event: RioEvent, _id: WindowId) {
+            self.0.lock().unwrap().push(event);
+        }
+    }
 
-        // A very simple optimization is to detect is a new graphic is replacing
-        // completely a previous one. This happens if the following conditions
-        // are met:
-        //
-        // - Both graphics are attached to the same top-left cell.
-        // - Both graphics have the same size.
-        // - The new graphic does not contain transparent pixels.
-        //
-        // In this case, we will ignore cells with a reference to the replaced
-        // graphic.
+    /// Terminal of `columns` by `lines` recording the events it sends.
+    fn term(columns: usize, lines: usize) -> Crosswords<Recorder> {
+        let size = CrosswordsSize::new(columns, lines);
+        let window_id = crate::event::WindowId::from(0);
+        Crosswords::new(size, CursorShape::Block, Recorder::default(), window_id, 0)
+    }
 
-        let skip_textures = {
-            if graphic.maybe_transparent() {
-                HashSet::new()
-            } else {
-                let mut set = HashSet::new();
+    /// Parse `bytes` as if the application wrote them.
+    fn feed<U: EventListener>(cw: &mut Crosswords<U>, bytes: impl AsRef<[u8]>) {
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in bytes.as_ref() {
+            parser.advance(cw, *byte);
+        }
+    }
 
-                let line = if scrolling {
-                    self.grid.cursor.pos.row
-                } else {
-                    Line(0)
-                };
+    #[test]
+    fn test_device_attributes() {
+        let size = CrosswordsSize::new(10, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let recorder = Recorder::default();
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, recorder.clone(), window_id, 0);
 
-                if let Some(old_graphics) = self.grid[line][Column(leftmost)].graphics() {
-                    for graphic in old_graphics {
-                        let tex = &*graphic.texture;
-                        if tex.width == width
-                            && tex.height == height
-                            && tex.cell_height == cell_height
-                        {
-                            set.insert(tex.id);
-                        }
-                    }
-                }
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"\x1b[c\x1b[=c\x1b[=0c" {
+            parser.advance(&mut cw, *byte);
+        }
 
-                set
+        let responses = recorder
+            .0
+            .lock()
+            .unwrap()
+            .drain(..)
+            .filter_map(|event| match event {
+                RioEvent::PtyWrite(text) => Some(text),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            responses,
+            [
+                "\x1b[?62;4;6;22c",
+                "\x1bP!|00000000\x1b\\",
+                "\x1bP!|00000000\x1b\\"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_osc_titles() {
+        let size = CrosswordsSize::new(10, 6);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        let mut feed = |cw: &mut Crosswords<VoidListener>, input: &str| {
+            for byte in input.as_bytes() {
+                parser.advance(cw, *byte);
             }
         };
 
-        // Fill the cells under the graphic.
-        //
-        // The cell in the first column contains a reference to the
-        // graphic, with the offset from the start. The rest of the
-        // cells are not overwritten, allowing any text behind
-        // transparent portions of the image to be visible.
+        feed(&mut cw, "\x1b]1;icon\x07");
+        assert_eq!(cw.title, "");
+        assert_eq!(cw.icon_title, "icon");
+        assert_eq!(cw.application_title(), "icon");
 
-        let texture = Arc::new(TextureRef {
-            id: graphic_id,
-            width,
-            height,
-            cell_height,
-            texture_operations: Arc::downgrade(&self.graphics.texture_operations),
-        });
+        feed(&mut cw, "\x1b]2;window\x07");
+        assert_eq!(cw.icon_title, "icon");
+        assert_eq!(cw.application_title(), "window");
 
-        for (top, offset_y) in (0..).zip((0..height).step_by(cell_height)) {
-            let line = if scrolling {
-                self.grid.cursor.pos.row
-            } else {
-                // Check if the image is beyond the screen limit.
-                if top >= self.grid.screen_lines() as i32 {
-                    break;
-                }
+        feed(&mut cw, "\x1b]0;both\x07");
+        assert_eq!(cw.title, "both");
+        assert_eq!(cw.icon_title, "both");
 
-                Line(top)
-            };
+        feed(&mut cw, "\x1b]2;\x07");
+        assert_eq!(cw.application_title(), "both");
+    }
 
-            // Store a reference to the graphic in the first column.
-            let row_len = self.grid[line].len();
-            for (left, offset_x) in (leftmost..).zip((0..width).step_by(cell_width)) {
-                if left >= row_len {
-                    break;
+    #[test]
+    fn test_report_status_string() {
+        let size = CrosswordsSize::new(10, 6);
+        let window_id = crate::event::WindowId::from(0);
+        let recorder = Recorder::default();
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, recorder.clone(), window_id, 0);
+
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        let input = concat!(
+            "\x1bP$q q\x1b\\",
+            "\x1b[5 q\x1bP$q q\x1b\\",
+            "\x1b[4 q\x1bP$q q\x1b\\",
+            "\x1b[0 q\x1bP$q q\x1b\\",
+            "\x1b[2;5r\x1bP$qr\x1b\\",
+            "\x1bP$qx\x1b\\",
+            "\x1b]12;?\x07",
+        );
+        for byte in input.as_bytes() {
+            parser.advance(&mut cw, *byte);
+        }
+
+        let mut color_requests = Vec::new();
+        let responses = recorder
+            .0
+            .lock()
+            .unwrap()
+            .drain(..)
+            .filter_map(|event| match event {
+                RioEvent::PtyWrite(text) => Some(text),
+                RioEvent::ColorRequest(index, format) => {
+                    color_requests.push(format(ColorRgb {
+                        r: 255,
+                        g: 0,
+                        b: 16,
+                    }));
+                    assert_eq!(index, NamedColor::Cursor as usize);
+                    None
                 }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            responses,
+            [
+                "\x1bP1$r2 q\x1b\\",
+                "\x1bP1$r5 q\x1b\\",
+                "\x1bP1$r4 q\x1b\\",
+                "\x1bP1$r2 q\x1b\\",
+                "\x1bP1$r2;5r\x1b\\",
+                "\x1bP0$r\x1b\\",
+            ]
+        );
+        assert_eq!(color_requests, ["\x1b]12;rgb:ffff/0000/1010\x07"]);
+    }
 
-                let texture_operations =
-                    Arc::downgrade(&self.graphics.texture_operations);
-                let graphic_cell = GraphicCell {
-                    texture: texture.clone(),
-                    offset_x,
-                    offset_y,
-                    texture_operations,
-                };
+    #[test]
+    fn test_encode_termprop() {
+        let size = CrosswordsSize::new(10, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let recorder = Recorder::default();
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, recorder.clone(), window_id, 0);
 
-                let mut cell = self.grid.cursor.template.clone();
-                let cell_ref = &mut self.grid[line][Column(left)];
+        let termprop = |cw: &Crosswords<Recorder>, name| {
+            cw.encode_termprop(name)
+                .map(|reply| String::from_utf8(reply).unwrap())
+        };
+        assert_eq!(termprop(&cw, "columns").unwrap(), "\x1bP>|columns=10\x1b\\");
+        assert_eq!(termprop(&cw, "rows").unwrap(), "\x1bP>|rows=2\x1b\\");
+        assert_eq!(termprop(&cw, "colors").unwrap(), "\x1bP>|colors=16M\x1b\\");
+        assert_eq!(
+            termprop(&cw, "version").unwrap(),
+            format!("\x1bP>|version={}\x1b\\", env!("CARGO_PKG_VERSION"))
+        );
+        assert_eq!(termprop(&cw, "font"), None);
 
-                // If the cell contains any graphics, and the region of the cell
-                // is not fully filled by the new graphic, the old graphics are
-                // kept in the cell.
-                let graphics = match cell_ref.take_graphics() {
-                    Some(mut old_graphics)
-                        if old_graphics.iter().any(|graphic| {
-                            !skip_textures.contains(&graphic.texture.id)
-                        }) && !graphic.is_filled(
-                            offset_x as usize,
-                            offset_y as usize,
-                            cell_width,
-                            cell_height,
-                        ) =>
-                    {
-                        // Ensure that we don't exceed the graphics limit per cell.
-                        while old_graphics.len() >= MAX_GRAPHICS_PER_CELL {
-                            drop(old_graphics.remove(0));
-                        }
+        let background = ColorRgb::from_color_arr(cw.colors[NamedColor::Background]);
+        let background = format!(
+            "\x1bP>|background=#{:02x}{:02x}{:02x}\x1b\\",
+            background.r, background.g, background.b
+        );
 
-                        old_graphics.push(graphic_cell);
-                        old_graphics
-                    }
+        // Names may be hex encoded, unknown ones get no reply.
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"\x1bP+Q726f7773;font;background\x1b\\" {
+            parser.advance(&mut cw, *byte);
+        }
 
-                    _ => smallvec::smallvec![graphic_cell],
+        let responses = recorder
+            .0
+            .lock()
+            .unwrap()
+            .drain(..)
+            .filter_map(|event| match event {
+                RioEvent::PtyWrite(text) => Some(text),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(responses, [String::from("\x1bP>|rows=2\x1b\\"), background]);
+    }
+
+    #[test]
+    fn test_pixel_geometry_query() {
+        let mut cw = term(10, 2);
+
+        // Resetting it is not a query.
+        feed(&mut cw, b"\x1b[?2500h\x1b[?2500l");
+
+        let events = cw.event_proxy.0.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        match &events[0] {
+            RioEvent::TextAreaSizeRequest(format) => {
+                let size = teletypewriter::WinsizeBuilder {
+                    rows: 2,
+                    cols: 10,
+                    width: 780,
+                    height: 560,
                 };
+                assert_eq!(format(size), "\x1b[?2500;780;560h");
+            }
+            event => panic!("unexpected event {event:?}"),
+        }
+    }
 
-                cell.set_graphics(graphics);
-                *cell_ref = cell;
+    #[test]
+    fn test_kitty_graphics() {
+        let size = CrosswordsSize::new_with_dimensions(4, 3, 40, 60, 10, 20);
+        let window_id = crate::event::WindowId::from(0);
+        let recorder = Recorder::default();
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, recorder.clone(), window_id, 0);
 
-                self.damage
-                    .damage_point(Pos::new((line.0 as usize).into(), Column(left)));
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        let mut advance = |cw: &mut Crosswords<Recorder>, bytes: &[u8]| {
+            for byte in bytes {
+                parser.advance(cw, *byte);
             }
+        };
+        let responses = || {
+            recorder
+                .0
+                .lock()
+                .unwrap()
+                .drain(..)
+                .filter_map(|event| match event {
+                    RioEvent::PtyWrite(text) => Some(text),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
 
-            if scrolling && offset_y < height.saturating_sub(cell_height as u16) {
-                self.linefeed();
-            }
+        // A single black pixel, scaled to two cells.
+        advance(
+            &mut cw,
+            b"\x1b_Ga=T,i=1,f=32,s=1,v=1,c=2,r=1;AAAA/w==\x1b\\",
+        );
+        assert_eq!(responses(), ["\x1b_Gi=1;OK\x1b\\"]);
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(2)));
+        for column in 0..2 {
+            let graphics = cw.grid[Line(0)][Column(column)].graphics().unwrap();
+            assert_eq!(graphics[0].texture.width, 20);
+            assert_eq!(graphics[0].texture.height, 20);
         }
-
-        if self.mode.contains(Mode::SIXEL_CURSOR_TO_THE_RIGHT) {
-            let graphic_columns = (graphic.width + cell_width - 1) / cell_width;
-            self.move_forward(Column(graphic_columns));
-        } else if scrolling {
-            self.linefeed();
-            self.carriage_return();
+        assert!(cw.grid[Line(0)][Column(2)].graphics().is_none());
+
+        advance(&mut cw, b"\x1b_Ga=p,i=2\x1b\\");
+        assert_eq!(responses(), ["\x1b_Gi=2;ENOENT:no such image\x1b\\"]);
+
+        // Deleting by id with `I` also frees the image.
+        advance(&mut cw, b"\x1b_Ga=d,d=I,i=1\x1b\\");
+        assert!(responses().is_empty());
+        for column in 0..2 {
+            let square = &cw.grid[Line(0)][Column(column)];
+            assert!(square.graphics().is_none());
+            assert!(!square.flags.contains(square::Flags::GRAPHICS));
         }
 
-        // Add the graphic data to the pending queue.
-        self.graphics.pending.push(GraphicData {
-            id: graphic_id,
-            ..graphic
-        });
+        advance(&mut cw, b"\x1b_Ga=p,i=1\x1b\\");
+        assert_eq!(responses(), ["\x1b_Gi=1;ENOENT:no such image\x1b\\"]);
 
-        self.event_proxy
-            .send_event(RioEvent::UpdateGraphicLibrary, self.window_id);
+        // Deleting the placements crossing a column, then a row.
+        advance(
+            &mut cw,
+            b"\x1b[1;1H\x1b_Ga=T,i=3,f=32,s=1,v=1,C=1,q=1;AAAA/w==\x1b\\",
+        );
+        advance(
+            &mut cw,
+            b"\x1b[2;3H\x1b_Ga=T,i=4,f=32,s=1,v=1,C=1,q=1;AAAA/w==\x1b\\",
+        );
+        assert!(cw.grid[Line(0)][Column(0)].graphics().is_some());
+        assert!(cw.grid[Line(1)][Column(2)].graphics().is_some());
+        advance(&mut cw, b"\x1b_Ga=d,d=x,x=3\x1b\\");
+        assert!(cw.grid[Line(0)][Column(0)].graphics().is_some());
+        assert!(cw.grid[Line(1)][Column(2)].graphics().is_none());
+        advance(&mut cw, b"\x1b_Ga=d,d=y,y=1\x1b\\");
+        assert!(cw.grid[Line(0)][Column(0)].graphics().is_none());
+        assert!(responses().is_empty());
+
+        // Commands cut by the parser are rejected instead of misread.
+        let mut command = b"\x1b_Ga=T,i=5,f=32,s=1,v=1;".to_vec();
+        command.resize(command.len() + 8192, b'A');
+        command.extend_from_slice(b"\x1b\\");
+        advance(&mut cw, &command);
+        assert_eq!(responses(), ["\x1b_Gi=5;EINVAL:command is too large\x1b\\"]);
     }
-}
 
-pub struct CrosswordsSize {
-    pub columns: usize,
-    pub screen_lines: usize,
-    pub width: u32,
-    pub height: u32,
-    pub square_width: u32,
-    pub square_height: u32,
-}
+    #[test]
+    fn test_kitty_unicode_placeholders() {
+        let size = CrosswordsSize::new_with_dimensions(4, 3, 40, 60, 10, 20);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
 
-impl CrosswordsSize {
-    pub fn new(columns: usize, screen_lines: usize) -> Self {
-        Self {
-            columns,
-            screen_lines,
-            width: 0,
-            height: 0,
-            square_width: 0,
-            square_height: 0,
-        }
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        let mut advance = |cw: &mut Crosswords<VoidListener>, text: &str| {
+            for byte in text.as_bytes() {
+                parser.advance(cw, *byte);
+            }
+        };
+        let offsets = |cw: &Crosswords<VoidListener>, line: i32, column: usize| {
+            cw.grid[Line(line)][Column(column)]
+                .graphics()
+                .map(|graphics| (graphics[0].offset_x, graphics[0].offset_y))
+        };
+
+        // A virtual placement of two by two cells draws nothing by itself.
+        advance(
+            &mut cw,
+            "\x1b_Ga=T,U=1,i=1,f=32,s=1,v=1,c=2,r=2,q=2;AAAA/w==\x1b\\",
+        );
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(0)));
+        assert!(cw.grid[Line(0)][Column(0)].graphics().is_none());
+
+        // Image id 1 in the foreground color, the diacritics give the row
+        // and the column, placeholders without them continue on the right.
+        advance(
+            &mut cw,
+            "\x1b[38;5;1m\u{10EEEE}\u{305}\u{305}\u{10EEEE}\u{10EEEE}",
+        );
+        advance(&mut cw, "\r\n\u{10EEEE}\u{30D}\u{10EEEE}");
+        assert_eq!(offsets(&cw, 0, 0), Some((0, 0)));
+        assert_eq!(offsets(&cw, 0, 1), Some((10, 0)));
+        assert_eq!(offsets(&cw, 1, 0), Some((0, 20)));
+        assert_eq!(offsets(&cw, 1, 1), Some((10, 20)));
+        let texture = &cw.grid[Line(0)][Column(0)].graphics().unwrap()[0].texture;
+        assert_eq!((texture.width, texture.height), (20, 40));
+
+        // Outside of the placement.
+        assert_eq!(offsets(&cw, 0, 2), None);
+
+        // Placeholders printed before their placement exists.
+        advance(&mut cw, "\r\n\x1b[38;5;7m\u{10EEEE}\u{305}\u{305}");
+        assert_eq!(offsets(&cw, 2, 0), None);
+        advance(
+            &mut cw,
+            "\x1b_Ga=T,U=1,i=7,f=32,s=1,v=1,c=1,r=1,q=2;AAAA/w==\x1b\\",
+        );
+        assert_eq!(offsets(&cw, 2, 0), Some((0, 0)));
+
+        // Deleting the placement clears its placeholders.
+        advance(&mut cw, "\x1b_Ga=d,d=I,i=1\x1b\\");
+        assert_eq!(offsets(&cw, 0, 0), None);
+        assert_eq!(offsets(&cw, 1, 1), None);
+        assert_eq!(offsets(&cw, 2, 0), Some((0, 0)));
     }
 
-    pub fn new_with_dimensions(
-        columns: usize,
-        screen_lines: usize,
-        width: u32,
-        height: u32,
-        square_width: u32,
-        square_height: u32,
-    ) -> Self {
-        Self {
-            columns,
-            screen_lines,
-            width,
-            height,
-            square_width,
-            square_height,
+    #[test]
+    fn test_scrollback_lines() {
+        let size = CrosswordsSize::new(5, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        cw.set_scrollback_lines(3);
+
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"1\r\n2\r\n3\r\n4\r\n5\r\n6\r\n7" {
+            parser.advance(&mut cw, *byte);
         }
-    }
-}
 
-impl Dimensions for CrosswordsSize {
-    fn total_lines(&self) -> usize {
-        self.screen_lines()
-    }
+        // Lines 1 and 2 no longer fit in the history.
+        assert_eq!(cw.grid.history_size(), 3);
+        assert_eq!(cw.grid[Line(-3)][Column(0)].c, '3');
+        assert_eq!(cw.grid[Line(1)][Column(0)].c, '7');
 
-    fn screen_lines(&self) -> usize {
-        self.screen_lines
-    }
+        cw.scroll_display(Scroll::Top);
+        assert_eq!(cw.display_offset(), 3);
 
-    fn columns(&self) -> usize {
-        self.columns
+        // Without scrollback, the history is dropped and scrolling does nothing.
+        cw.set_scrollback_lines(0);
+        assert_eq!(cw.grid.history_size(), 0);
+        assert_eq!(cw.display_offset(), 0);
+        parser.advance(&mut cw, b'\n');
+        cw.scroll_display(Scroll::Top);
+        assert_eq!(cw.display_offset(), 0);
+        assert_eq!(cw.grid.history_size(), 0);
     }
 
-    fn square_width(&self) -> f32 {
-        0.
-    }
+    #[test]
+    fn test_scrollback_lines_on_resize() {
+        let size = CrosswordsSize::new(5, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        cw.set_scrollback_lines(2);
 
-    fn square_height(&self) -> f32 {
-        0.
-    }
-}
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"1\r\n2\r\n3\r\n4" {
+            parser.advance(&mut cw, *byte);
+        }
 
-impl<T: EventListener> Dimensions for Crosswords<T> {
-    #[inline]
-    fn columns(&self) -> usize {
-        self.grid.columns()
+        // Shrinking pushes three lines up but only two are kept.
+        cw.resize(CrosswordsSize::new(5, 1));
+        assert_eq!(cw.grid.history_size(), 2);
+        assert_eq!(cw.grid[Line(-2)][Column(0)].c, '2');
+        assert_eq!(cw.grid[Line(0)][Column(0)].c, '4');
     }
 
-    #[inline]
-    fn screen_lines(&self) -> usize {
-        self.grid.screen_lines()
+    #[test]
+    fn test_clear_history_and_viewport() {
+        let size = CrosswordsSize::new(5, 3);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"1\r\n2\r\n3\r\n4\r\n5\r\n$ ls" {
+            parser.advance(&mut cw, *byte);
+        }
+        assert_eq!(cw.grid.history_size(), 3);
+
+        // Clearing the viewport keeps the history and the cursor line.
+        cw.scroll_display(Scroll::Delta(2));
+        cw.clear_viewport();
+        assert_eq!(cw.display_offset(), 0);
+        assert_eq!(cw.grid.history_size(), 3);
+        assert_eq!(cw.grid[Line(-1)][Column(0)].c, '3');
+        assert_eq!(cw.grid[Line(0)][Column(0)].c, '$');
+        assert_eq!(cw.grid[Line(1)][Column(0)].c, ' ');
+        assert_eq!(cw.grid[Line(2)][Column(0)].c, ' ');
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(4)));
+
+        cw.scroll_display(Scroll::Delta(2));
+        assert_eq!(cw.display_offset(), 2);
+        assert!(cw.is_scrolled());
+        assert!(!cw.is_at_bottom());
+        cw.clear_history();
+        assert_eq!(cw.display_offset(), 0);
+        assert!(cw.is_at_bottom());
+        assert!(!cw.is_scrolled());
+        assert_eq!(cw.grid.history_size(), 0);
+        assert_eq!(cw.grid[Line(0)][Column(0)].c, '$');
     }
 
-    #[inline]
-    fn total_lines(&self) -> usize {
-        self.grid.total_lines()
+    #[test]
+    fn test_clear_scrollback_sequence() {
+        let size = CrosswordsSize::new(5, 3);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        let mut feed = |cw: &mut Crosswords<VoidListener>, input: &[u8]| {
+            for byte in input {
+                parser.advance(cw, *byte);
+            }
+        };
+
+        feed(&mut cw, b"1\r\n2\r\n3\r\n4\r\n5\r\n$ ls");
+        cw.scroll_display(Scroll::Delta(2));
+        assert_eq!(cw.display_offset(), 2);
+
+        // Kept when the option is set, the viewport stays where it is.
+        cw.preserve_scrollback_on_clear = true;
+        feed(&mut cw, b"\x1b[3J");
+        assert_eq!(cw.grid.history_size(), 3);
+        assert_eq!(cw.display_offset(), 2);
+
+        cw.preserve_scrollback_on_clear = false;
+        feed(&mut cw, b"\x1b[3J");
+        assert_eq!(cw.grid.history_size(), 0);
+        assert_eq!(cw.display_offset(), 0);
+        assert_eq!(cw.grid[Line(0)][Column(0)].c, '4');
+
+        // A reset drops it either way.
+        cw.preserve_scrollback_on_clear = true;
+        feed(&mut cw, b"\r\n6\r\n7\r\n8");
+        assert_eq!(cw.grid.history_size(), 3);
+        feed(&mut cw, b"\x1bc");
+        assert_eq!(cw.grid.history_size(), 0);
     }
 
-    fn square_width(&self) -> f32 {
-        0.
+    #[test]
+    fn test_cell_color_at() {
+        let size = CrosswordsSize::new(10, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"a\x1b[31mb\x1b[1mc\x1b[0;2;34md\x1b[0;7;38;5;196;48;2;1;2;3me" {
+            parser.advance(&mut cw, *byte);
+        }
+
+        let palette = |color: colors::NamedColor| {
+            ColorRgb::from_color_arr(cw.colors[color as usize])
+        };
+        let at = |column| cw.cell_color_at(Pos::new(Line(0), Column(column))).unwrap();
+        let background = palette(colors::NamedColor::Background);
+
+        assert_eq!(at(0), (palette(colors::NamedColor::Foreground), background));
+        assert_eq!(at(1), (palette(colors::NamedColor::Red), background));
+        // Bold renders as the bright variant.
+        assert_eq!(at(2), (palette(colors::NamedColor::LightRed), background));
+        assert_eq!(at(3), (palette(colors::NamedColor::DimBlue), background));
+        // Inverse swaps the resolved colors.
+        assert_eq!(
+            at(4),
+            (
+                ColorRgb { r: 1, g: 2, b: 3 },
+                ColorRgb::from_color_arr(cw.colors[196])
+            )
+        );
+
+        // Outside of the grid.
+        assert_eq!(cw.cell_color_at(Pos::new(Line(0), Column(10))), None);
+        assert_eq!(cw.cell_color_at(Pos::new(Line(2), Column(0))), None);
+        assert_eq!(cw.cell_color_at(Pos::new(Line(-1), Column(0))), None);
     }
 
-    fn square_height(&self) -> f32 {
-        0.
+    #[test]
+    fn test_alt_screen_scrollback() {
+        let size = CrosswordsSize::new(5, 3);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        let mut feed = |cw: &mut Crosswords<VoidListener>, bytes: &[u8]| {
+            for byte in bytes {
+                parser.advance(cw, *byte);
+            }
+        };
+
+        // Standard behavior, nothing is kept for the alternate screen.
+        feed(&mut cw, b"\x1b[?1049h1\r\n2\r\n3\r\n4\r\n5");
+        assert_eq!(cw.grid.history_size(), 0);
+        feed(&mut cw, b"\x1b[?1049l");
+
+        cw.set_alt_screen_scrollback(true);
+        feed(&mut cw, b"\x1b[?1049h1\r\n2\r\n3\r\n4\r\n5");
+        assert_eq!(cw.grid.history_size(), 2);
+        assert_eq!(cw.grid[Line(-2)][Column(0)].c, '1');
+
+        // The primary screen history is left untouched.
+        feed(&mut cw, b"\x1b[?1049l");
+        assert_eq!(cw.grid.history_size(), 0);
+
+        // Every application starts with an empty history.
+        feed(&mut cw, b"\x1b[?1049h");
+        assert_eq!(cw.grid.history_size(), 0);
+
+        // Disabling it while the alternate screen is active drops it.
+        feed(&mut cw, b"a\r\nb\r\nc\r\nd");
+        assert_eq!(cw.grid.history_size(), 1);
+        cw.set_alt_screen_scrollback(false);
+        assert_eq!(cw.grid.history_size(), 0);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::crosswords::pos::{Column, Line, Pos, Side};
-    use crate::crosswords::CrosswordsSize;
-    use crate::event::VoidListener;
+    #[test]
+    fn test_disable_alt_screen() {
+        let size = CrosswordsSize::new(5, 3);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        cw.disable_alt_screen = true;
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"ab\x1b[?1049h\x1b[2;1Hx\r\ny\r\nz\r\nw\x1b[?1049l" {
+            parser.advance(&mut cw, *byte);
+        }
+
+        assert!(!cw.mode.contains(Mode::ALT_SCREEN));
+        // Everything stays in the primary screen and its history.
+        assert_eq!(cw.grid.history_size(), 2);
+        assert_eq!(cw.grid[Line(-2)][Column(0)].c, 'a');
+        assert_eq!(cw.grid[Line(2)][Column(0)].c, 'w');
+        // The cursor is restored to where the application found it.
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(2)));
+    }
 
     #[test]
-    fn scroll_up() {
-        let size = CrosswordsSize::new(1, 10);
+    fn test_scroll_to_prompt() {
+        let size = CrosswordsSize::new(5, 3);
         let window_id = crate::event::WindowId::from(0);
         let mut cw =
             Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
-        for i in 0..10 {
-            cw.grid[Line(i)][Column(0)].c = i as u8 as char;
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        let prompt = b"\x1b]133;A\x07$ \x1b]133;B\x07cmd\r\nout\r\n";
+        for _ in 0..3 {
+            for byte in prompt {
+                parser.advance(&mut cw, *byte);
+            }
+        }
+        for byte in b"\x1b]133;A\x07$ " {
+            parser.advance(&mut cw, *byte);
         }
 
-        cw.grid.scroll_up(&(Line(0)..Line(10)), 2);
+        // Prompts are at lines -4, -2, 0 and 2.
+        assert_eq!(cw.grid.history_size(), 4);
+        assert!(cw.grid[Line(-4)].flags.contains(RowFlag::PROMPT_START));
+        assert!(!cw.grid[Line(-3)].flags.contains(RowFlag::PROMPT_START));
 
-        assert_eq!(cw.grid[Line(0)][Column(0)].c, '\u{2}');
-        assert_eq!(cw.grid[Line(0)].occ, 1);
-        assert_eq!(cw.grid[Line(1)][Column(0)].c, '\u{3}');
-        assert_eq!(cw.grid[Line(1)].occ, 1);
-        assert_eq!(cw.grid[Line(2)][Column(0)].c, '\u{4}');
-        assert_eq!(cw.grid[Line(2)].occ, 1);
-        assert_eq!(cw.grid[Line(3)][Column(0)].c, '\u{5}');
-        assert_eq!(cw.grid[Line(3)].occ, 1);
-        assert_eq!(cw.grid[Line(4)][Column(0)].c, '\u{6}');
-        assert_eq!(cw.grid[Line(4)].occ, 1);
-        assert_eq!(cw.grid[Line(5)][Column(0)].c, '\u{7}');
-        assert_eq!(cw.grid[Line(5)].occ, 1);
-        assert_eq!(cw.grid[Line(6)][Column(0)].c, '\u{8}');
-        assert_eq!(cw.grid[Line(6)].occ, 1);
-        assert_eq!(cw.grid[Line(7)][Column(0)].c, '\u{9}');
-        assert_eq!(cw.grid[Line(7)].occ, 1);
-        assert_eq!(cw.grid[Line(8)][Column(0)].c, ' '); // was 0.
-        assert_eq!(cw.grid[Line(8)].occ, 0);
-        assert_eq!(cw.grid[Line(9)][Column(0)].c, ' '); // was 1.
-        assert_eq!(cw.grid[Line(9)].occ, 0);
+        assert!(cw.scroll_to_next_prompt());
+        assert_eq!(cw.grid.display_offset(), 2);
+        assert!(cw.scroll_to_next_prompt());
+        assert_eq!(cw.grid.display_offset(), 4);
+        assert!(!cw.scroll_to_next_prompt());
+        assert_eq!(cw.grid.display_offset(), 4);
+
+        assert!(cw.scroll_to_prev_prompt());
+        assert_eq!(cw.grid.display_offset(), 2);
+        assert!(cw.scroll_to_prev_prompt());
+        assert_eq!(cw.grid.display_offset(), 0);
+        assert!(!cw.scroll_to_prev_prompt());
+
+        assert_eq!(
+            cw.last_output_range(),
+            Some((Pos::new(Line(1), Column(0)), Pos::new(Line(1), Column(4))))
+        );
+
+        // A command without output.
+        for byte in b"cmd\r\n\x1b]133;A\x07$ " {
+            parser.advance(&mut cw, *byte);
+        }
+        assert_eq!(cw.last_output_range(), None);
     }
 
     #[test]
-    fn test_linefeed() {
-        let size = CrosswordsSize::new(1, 1);
+    fn test_get_last_output_line() {
+        let size = CrosswordsSize::new(5, 4);
         let window_id = crate::event::WindowId::from(0);
-
         let mut cw =
             Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
-        assert_eq!(cw.grid.total_lines(), 1);
+        assert_eq!(cw.get_last_output_line(), None);
 
-        cw.linefeed();
-        assert_eq!(cw.grid.total_lines(), 2);
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"a\r\nb\r\nc\r\nd\r\ne\r\n" {
+            parser.advance(&mut cw, *byte);
+        }
+
+        // "a" and "b" went to the history, the cursor line is blank.
+        assert_eq!(cw.grid.history_size(), 2);
+        assert_eq!(cw.get_last_output_line(), Some(2));
+
+        cw.scroll_display(Scroll::Delta(2));
+        assert_eq!(cw.get_last_output_line(), Some(1));
+        cw.scroll_display(Scroll::Bottom);
+
+        // After a clear the content is only in the history.
+        for byte in b"\x1b[H\x1b[2J" {
+            parser.advance(&mut cw, *byte);
+        }
+        let line = cw.get_last_output_line().unwrap();
+        assert!(line < 0);
+        assert_eq!(cw.line_content(line), "e");
     }
 
     #[test]
-    fn test_linefeed_moving_cursor() {
-        let size = CrosswordsSize::new(1, 3);
-
+    fn test_bell() {
+        let size = CrosswordsSize::new(10, 2);
         let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        assert!(!cw.bell);
+
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"rio\x07" {
+            parser.advance(&mut cw, *byte);
+        }
+        assert!(cw.bell);
+    }
 
+    #[test]
+    fn test_max_line_length() {
+        let size = CrosswordsSize::new(4, 10);
+        let window_id = crate::event::WindowId::from(0);
         let mut cw =
             Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
-        let cursor = cw.cursor();
-        assert_eq!(cursor.pos.col, 0);
-        assert_eq!(cursor.pos.row, 0);
+        cw.max_line_length = 8;
 
-        cw.linefeed();
-        let cursor = cw.cursor();
-        assert_eq!(cursor.pos.col, 0);
-        assert_eq!(cursor.pos.row, 1);
+        for c in "abcdefghijklmnopq".chars() {
+            cw.input(c);
+        }
 
-        // Keep adding lines but keep cursor at max row
-        for _ in 0..20 {
-            cw.linefeed();
+        let wraps = |cw: &Crosswords<VoidListener>, line: i32| {
+            cw.grid[Line(line)][Column(3)]
+                .flags
+                .contains(square::Flags::WRAPLINE)
+        };
+        assert!(wraps(&cw, 0));
+        assert!(!wraps(&cw, 1));
+        assert!(wraps(&cw, 2));
+        assert!(!wraps(&cw, 3));
+        assert_eq!(cw.char_at(Pos::new(Line(2), Column(0))), Some('i'));
+        assert_eq!(cw.char_at(Pos::new(Line(4), Column(0))), Some('q'));
+
+        // Disabled, every line keeps wrapping.
+        let size = CrosswordsSize::new(4, 10);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        for c in "abcdefghijklmnopq".chars() {
+            cw.input(c);
+        }
+        assert!((0..4).all(|line| wraps(&cw, line)));
+    }
+
+    #[test]
+    fn test_max_line_length_counts_from_cursor_moves() {
+        let window_id = crate::event::WindowId::from(0);
+        let wraps = |cw: &Crosswords<VoidListener>, line: i32| {
+            cw.grid[Line(line)][Column(3)]
+                .flags
+                .contains(square::Flags::WRAPLINE)
+        };
+        // Wrap once, then move the cursor back to the wrapped row and fill
+        // it again: the count starts over, so the row wraps instead of
+        // being broken as a second wrap.
+        let wrapped_then = |sequence: &[u8]| {
+            let size = CrosswordsSize::new(4, 10);
+            let mut cw =
+                Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+            cw.max_line_length = 8;
+            let mut parser = crate::performer::handler::ParserProcessor::new();
+            for byte in b"abcdef".iter().chain(sequence).chain(b"ghijk") {
+                parser.advance(&mut cw, *byte);
+            }
+            cw
+        };
+
+        // Carriage return.
+        let cw = wrapped_then(b"\r");
+        assert!(wraps(&cw, 1));
+        assert_eq!(cw.char_at(Pos::new(Line(2), Column(0))), Some('k'));
+
+        // CUP and HVP.
+        for sequence in [&b"\x1b[2;1H"[..], &b"\x1b[2;1f"[..]] {
+            let cw = wrapped_then(sequence);
+            assert!(wraps(&cw, 1));
+            assert_eq!(cw.char_at(Pos::new(Line(2), Column(0))), Some('k'));
         }
-        let cursor = cw.cursor();
-        assert_eq!(cursor.pos.col, 0);
-        assert_eq!(cursor.pos.row, 2);
-        assert_eq!(cw.grid.total_lines(), 22);
+
+        // Switching to the alternate screen keeps the cursor on the
+        // wrapped row, the new screen still starts a fresh line.
+        let cw = wrapped_then(b"\x1b[?1049h");
+        assert!(cw.mode().contains(Mode::ALT_SCREEN));
+        assert!(wraps(&cw, 1));
     }
 
     #[test]
-    fn test_input() {
-        let size = CrosswordsSize::new(5, 10);
+    fn test_line_feed_new_line_mode() {
+        let size = CrosswordsSize::new(5, 5);
         let window_id = crate::event::WindowId::from(0);
-
         let mut cw =
             Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
-        for i in 0..4 {
-            cw.grid[Line(0)][Column(i)].c = i as u8 as char;
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+
+        for byte in b"ab\ncd" {
+            parser.advance(&mut cw, *byte);
         }
-        cw.grid[Line(1)][Column(3)].c = 'b';
+        assert_eq!(cw.char_at(Pos::new(Line(1), Column(2))), Some('c'));
 
-        assert_eq!(cw.grid[Line(0)][Column(0)].c, '\u{0}');
-        assert_eq!(cw.grid[Line(0)][Column(1)].c, '\u{1}');
-        assert_eq!(cw.grid[Line(0)][Column(2)].c, '\u{2}');
-        assert_eq!(cw.grid[Line(0)][Column(3)].c, '\u{3}');
-        assert_eq!(cw.grid[Line(0)][Column(4)].c, ' ');
-        assert_eq!(cw.grid[Line(1)][Column(2)].c, ' ');
-        assert_eq!(cw.grid[Line(1)][Column(3)].c, 'b');
-        assert_eq!(cw.grid[Line(0)][Column(4)].c, ' ');
+        cw.set_mode(AnsiMode::LineFeedNewLine);
+        for byte in b"\nef\x0bgh\x0cij" {
+            parser.advance(&mut cw, *byte);
+        }
+        assert_eq!(cw.char_at(Pos::new(Line(2), Column(0))), Some('e'));
+        assert_eq!(cw.char_at(Pos::new(Line(3), Column(0))), Some('g'));
+        assert_eq!(cw.char_at(Pos::new(Line(4), Column(0))), Some('i'));
+
+        cw.unset_mode(AnsiMode::LineFeedNewLine);
+        assert!(!cw.mode().contains(Mode::LINE_FEED_NEW_LINE));
     }
 
     #[test]
-    fn simple_selection_works() {
+    fn test_scroll_to_cursor() {
         let size = CrosswordsSize::new(5, 5);
         let window_id = crate::event::WindowId::from(0);
-
-        let mut term =
+        let mut cw =
             Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
-        let grid = &mut term.grid;
-        for i in 0..4 {
-            if i == 1 {
-                continue;
-            }
+        for _ in 0..20 {
+            cw.linefeed();
+        }
 
-            grid[Line(i)][Column(0)].c = '"';
+        // Visible already, nothing changes.
+        cw.goto(Line(2), Column(0));
+        cw.scroll_display(Scroll::Delta(1));
+        cw.scroll_to_cursor();
+        assert_eq!(cw.display_offset(), 1);
 
-            for j in 1..4 {
-                grid[Line(i)][Column(j)].c = 'a';
-            }
+        cw.scroll_display(Scroll::Top);
+        cw.scroll_to_cursor();
+        assert_eq!(cw.display_offset(), 2);
 
-            grid[Line(i)][Column(4)].c = '"';
-        }
-        grid[Line(2)][Column(0)].c = ' ';
-        grid[Line(2)][Column(4)].c = ' ';
-        grid[Line(2)][Column(4)]
-            .flags
-            .insert(square::Flags::WRAPLINE);
-        grid[Line(3)][Column(0)].c = ' ';
+        cw.goto(Line(4), Column(0));
+        cw.scroll_to_cursor();
+        assert_eq!(cw.display_offset(), 0);
+    }
 
-        // Multiple lines contain an empty line.
-        term.selection = Some(Selection::new(
-            SelectionType::Simple,
-            Pos {
-                row: Line(0),
-                col: Column(0),
-            },
-            Side::Left,
-        ));
-        if let Some(s) = term.selection.as_mut() {
-            s.update(
-                Pos {
-                    row: Line(2),
-                    col: Column(4),
-                },
-                Side::Right,
-            );
-        }
-        assert_eq!(
-            term.selection_to_string(),
-            Some(String::from("\"aaa\"\n\n aaa "))
-        );
+    #[test]
+    fn test_pending_wrap() {
+        let size = CrosswordsSize::new(4, 3);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
 
-        // A wrapline.
-        term.selection = Some(Selection::new(
-            SelectionType::Simple,
-            Pos {
-                row: Line(2),
-                col: Column(0),
-            },
-            Side::Left,
-        ));
-        if let Some(s) = term.selection.as_mut() {
-            s.update(
-                Pos {
-                    row: Line(3),
-                    col: Column(4),
-                },
-                Side::Right,
-            );
+        // Printing at the last column keeps the cursor there until the next char.
+        for c in "abcd".chars() {
+            cw.input(c);
         }
-        assert_eq!(
-            term.selection_to_string(),
-            Some(String::from(" aaa  aaa\""))
-        );
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(3)));
+        assert!(cw.grid.cursor.should_wrap);
+
+        cw.input('e');
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(1), Column(1)));
+        assert_eq!(cw.char_at(Pos::new(Line(1), Column(0))), Some('e'));
+        assert!(!cw.grid.cursor.should_wrap);
     }
 
     #[test]
-    fn line_selection_works() {
-        let size = CrosswordsSize::new(5, 1);
+    fn test_pending_wrap_backspace() {
+        let size = CrosswordsSize::new(4, 3);
         let window_id = crate::event::WindowId::from(0);
-
-        let mut term =
+        let mut cw =
             Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
-        let mut grid: Grid<Square> = Grid::new(1, 5, 0);
-        for i in 0..5 {
-            grid[Line(0)][Column(i)].c = 'a';
-        }
-        grid[Line(0)][Column(0)].c = '"';
-        grid[Line(0)][Column(3)].c = '"';
 
-        mem::swap(&mut term.grid, &mut grid);
+        for c in "abcd".chars() {
+            cw.input(c);
+        }
+        cw.backspace();
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(2)));
+        assert!(!cw.grid.cursor.should_wrap);
 
-        term.selection = Some(Selection::new(
-            SelectionType::Lines,
-            Pos {
-                row: Line(0),
-                col: Column(3),
-            },
-            Side::Left,
-        ));
-        assert_eq!(term.selection_to_string(), Some(String::from("\"aa\"a\n")));
+        cw.input('x');
+        assert_eq!(cw.char_at(Pos::new(Line(0), Column(2))), Some('x'));
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(3)));
     }
 
     #[test]
-    fn block_selection_works() {
-        let size = CrosswordsSize::new(5, 5);
+    fn test_pending_wrap_cursor_moves() {
+        let size = CrosswordsSize::new(4, 3);
         let window_id = crate::event::WindowId::from(0);
-
-        let mut term =
+        let mut cw =
             Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
-        let grid = &mut term.grid;
-        for i in 1..4 {
-            grid[Line(i)][Column(0)].c = '"';
 
-            for j in 1..4 {
-                grid[Line(i)][Column(j)].c = 'a';
+        let fill = |cw: &mut Crosswords<VoidListener>| {
+            cw.goto(Line(0), Column(0));
+            for c in "abcd".chars() {
+                cw.input(c);
             }
+            assert!(cw.grid.cursor.should_wrap);
+        };
 
-            grid[Line(i)][Column(4)].c = '"';
-        }
-        grid[Line(2)][Column(2)].c = ' ';
-        grid[Line(2)][Column(4)]
-            .flags
-            .insert(square::Flags::WRAPLINE);
-        grid[Line(3)][Column(4)].c = ' ';
+        fill(&mut cw);
+        cw.move_backward(Column(1));
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(2)));
+        assert!(!cw.grid.cursor.should_wrap);
 
-        term.selection = Some(Selection::new(
-            SelectionType::Block,
-            Pos {
-                row: Line(0),
-                col: Column(3),
-            },
-            Side::Left,
-        ));
+        fill(&mut cw);
+        cw.move_forward(Column(1));
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(3)));
+        assert!(!cw.grid.cursor.should_wrap);
+        cw.input('x');
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(3)));
+        assert_eq!(cw.char_at(Pos::new(Line(0), Column(3))), Some('x'));
 
-        // The same column.
-        if let Some(s) = term.selection.as_mut() {
-            s.update(
-                Pos {
-                    row: Line(3),
-                    col: Column(3),
-                },
-                Side::Right,
-            );
-        }
-        assert_eq!(term.selection_to_string(), Some(String::from("\na\na\na")));
+        fill(&mut cw);
+        cw.move_down(1);
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(1), Column(3)));
+        assert!(!cw.grid.cursor.should_wrap);
 
-        // The first column.
-        if let Some(s) = term.selection.as_mut() {
-            s.update(
-                Pos {
-                    row: Line(3),
-                    col: Column(0),
-                },
-                Side::Left,
-            );
-        }
-        assert_eq!(
-            term.selection_to_string(),
-            Some(String::from("\n\"aa\n\"a\n\"aa"))
-        );
+        fill(&mut cw);
+        cw.carriage_return();
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(0)));
+        assert!(!cw.grid.cursor.should_wrap);
 
-        // The last column.
-        if let Some(s) = term.selection.as_mut() {
-            s.update(
-                Pos {
-                    row: Line(3),
-                    col: Column(4),
-                },
-                Side::Right,
-            );
-        }
-        assert_eq!(
-            term.selection_to_string(),
-            Some(String::from("\na\"\na\"\na"))
-        );
+        fill(&mut cw);
+        cw.move_backward_tabs(1);
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(0)));
+        assert!(!cw.grid.cursor.should_wrap);
+
+        fill(&mut cw);
+        cw.delete_chars(1);
+        assert!(!cw.grid.cursor.should_wrap);
+
+        fill(&mut cw);
+        cw.insert_blank(1);
+        assert!(!cw.grid.cursor.should_wrap);
+
+        fill(&mut cw);
+        cw.erase_chars(Column(1));
+        assert!(!cw.grid.cursor.should_wrap);
     }
 
     #[test]
-    fn test_search_nearest_hyperlink_from_pos_on_single_line() {
-        let size = CrosswordsSize::new(20, 3);
+    fn test_pending_wrap_without_autowrap() {
+        let size = CrosswordsSize::new(4, 3);
         let window_id = crate::event::WindowId::from(0);
-        let mut term =
+        let mut cw =
             Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        cw.unset_mode(AnsiMode::LineWrap);
 
-        let grid = &mut term.grid;
-        for i in 0..19 {
-            grid[Line(0)][Column(i)].c = ' ';
+        // Without DECAWM the last column keeps being overwritten.
+        for c in "abcdef".chars() {
+            cw.input(c);
         }
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(3)));
+        assert_eq!(cw.char_at(Pos::new(Line(0), Column(3))), Some('f'));
+        assert_eq!(cw.char_at(Pos::new(Line(1), Column(0))), Some(' '));
+    }
 
-        // First line does not contain any hyperlink (it is empty as well)
-        let result = term
-            .search_nearest_hyperlink_from_pos(Pos::new(pos::Line(0), pos::Column(2)));
-        assert_eq!(result, None);
+    #[test]
+    fn test_backarrow_key_mode() {
+        let size = CrosswordsSize::new(4, 3);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        assert!(!cw.mode().contains(Mode::BACKARROW_SENDS_BACKSPACE));
 
-        let grid = &mut term.grid;
-        for i in 0..19 {
-            grid[Line(0)][Column(i)].c = 'a';
+        for byte in b"\x1b[?67h" {
+            parser.advance(&mut cw, *byte);
         }
+        assert!(cw.mode().contains(Mode::BACKARROW_SENDS_BACKSPACE));
 
-        // First line does not contain any hyperlink (does not contain any link)
-        let result = term
-            .search_nearest_hyperlink_from_pos(Pos::new(pos::Line(0), pos::Column(0)));
-        assert_eq!(result, None);
+        for byte in b"\x1b[?67l" {
+            parser.advance(&mut cw, *byte);
+        }
+        assert!(!cw.mode().contains(Mode::BACKARROW_SENDS_BACKSPACE));
+    }
 
-        // Cleanup line
-        let grid = &mut term.grid;
-        for i in 0..19 {
-            grid[Line(0)][Column(i)].c = ' ';
+    #[test]
+    fn test_meta_sends_escape() {
+        let size = CrosswordsSize::new(4, 3);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        assert!(cw.mode().contains(Mode::META_SENDS_ESCAPE));
+
+        cw.unset_mode(AnsiMode::MetaSendsEscape);
+        assert!(!cw.mode().contains(Mode::META_SENDS_ESCAPE));
+        cw.set_mode(AnsiMode::MetaSendsEscape);
+        assert!(cw.mode().contains(Mode::META_SENDS_ESCAPE));
+
+        // Reset goes back to the configured state.
+        cw.set_alt_sends_escape(false);
+        assert!(!cw.mode().contains(Mode::META_SENDS_ESCAPE));
+        cw.set_mode(AnsiMode::MetaSendsEscape);
+        cw.reset_state();
+        assert!(!cw.mode().contains(Mode::META_SENDS_ESCAPE));
+
+        // A config reload doesn't override the application.
+        cw.set_mode(AnsiMode::MetaSendsEscape);
+        cw.set_alt_sends_escape(false);
+        assert!(cw.mode().contains(Mode::META_SENDS_ESCAPE));
+        cw.set_alt_sends_escape(true);
+        cw.unset_mode(AnsiMode::MetaSendsEscape);
+        cw.set_alt_sends_escape(true);
+        assert!(!cw.mode().contains(Mode::META_SENDS_ESCAPE));
+        cw.reset_state();
+        assert!(cw.mode().contains(Mode::META_SENDS_ESCAPE));
+    }
+
+    #[test]
+    fn test_toggle_bracketed_paste() {
+        let size = CrosswordsSize::new(4, 3);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        assert!(!cw.mode().contains(Mode::BRACKETED_PASTE));
+
+        cw.toggle_bracketed_paste();
+        assert!(cw.mode().contains(Mode::BRACKETED_PASTE));
+
+        // The application still has the last word.
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"\x1b[?2004l" {
+            parser.advance(&mut cw, *byte);
         }
+        assert!(!cw.mode().contains(Mode::BRACKETED_PASTE));
 
-        let grid = &mut term.grid;
-        let link: [char; 14] = [
-            'h', 't', 't', 'p', 's', ':', '/', '/', 'r', 'i', 'o', '.', 'i', 'o',
-        ];
-        for (i, val) in link.iter().enumerate() {
-            grid[Line(0)][Column(i)].c = *val;
+        for byte in b"\x1b[?2004h" {
+            parser.advance(&mut cw, *byte);
         }
+        cw.toggle_bracketed_paste();
+        assert!(!cw.mode().contains(Mode::BRACKETED_PASTE));
+    }
 
-        assert_eq!(term.grid[Line(0)][Column(0)].c, 'h');
-        assert!(term.grid[Line(0)][Column(0)].hyperlink().is_none());
-        assert_eq!(term.grid[Line(0)][Column(1)].c, 't');
-        assert!(term.grid[Line(0)][Column(1)].hyperlink().is_none());
-        assert_eq!(term.grid[Line(0)][Column(2)].c, 't');
-        assert!(term.grid[Line(0)][Column(2)].hyperlink().is_none());
-        assert_eq!(term.grid[Line(0)][Column(3)].c, 'p');
-        assert!(term.grid[Line(0)][Column(3)].hyperlink().is_none());
-        assert_eq!(term.grid[Line(0)][Column(12)].c, 'i');
-        assert!(term.grid[Line(0)][Column(12)].hyperlink().is_none());
-        assert_eq!(term.grid[Line(0)][Column(13)].c, 'o');
-        assert!(term.grid[Line(0)][Column(13)].hyperlink().is_none());
+    #[test]
+    fn test_alt_sends_escape_mode() {
+        let size = CrosswordsSize::new(4, 3);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        assert!(!cw.mode().contains(Mode::ALT_SENDS_ESCAPE));
 
-        // First line does not a hyperlink from (0 to 13) position
-        let result = term
-            .search_nearest_hyperlink_from_pos(Pos::new(pos::Line(0), pos::Column(14)));
-        assert_eq!(result, None);
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"\x1b[?1039h" {
+            parser.advance(&mut cw, *byte);
+        }
+        assert!(cw.mode().contains(Mode::ALT_SENDS_ESCAPE));
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(0)));
 
-        // From 'h'
-        let result = term
-            .search_nearest_hyperlink_from_pos(Pos::new(pos::Line(0), pos::Column(0)));
-        assert_eq!(
-            result,
-            Some(SelectionRange {
-                start: Pos {
-                    row: Line(0),
-                    col: Column(0)
-                },
-                end: Pos {
-                    row: Line(0),
-                    col: Column(13)
-                },
-                is_block: false
-            })
-        );
+        for byte in b"\x1b[?1039l" {
+            parser.advance(&mut cw, *byte);
+        }
+        assert!(!cw.mode().contains(Mode::ALT_SENDS_ESCAPE));
+        assert!(cw.mode().contains(Mode::META_SENDS_ESCAPE));
+    }
 
-        assert_eq!(term.grid[Line(0)][Column(0)].c, 'h');
-        assert!(term.grid[Line(0)][Column(0)].hyperlink().is_some());
-        assert_eq!(
-            term.grid[Line(0)][Column(0)].hyperlink().unwrap().uri(),
-            "https://rio.io"
-        );
-        assert_eq!(term.grid[Line(0)][Column(1)].c, 't');
-        assert!(term.grid[Line(0)][Column(1)].hyperlink().is_some());
-        assert_eq!(term.grid[Line(0)][Column(2)].c, 't');
-        assert!(term.grid[Line(0)][Column(2)].hyperlink().is_some());
-        assert_eq!(term.grid[Line(0)][Column(3)].c, 'p');
-        assert!(term.grid[Line(0)][Column(3)].hyperlink().is_some());
-        assert_eq!(term.grid[Line(0)][Column(12)].c, 'i');
-        assert!(term.grid[Line(0)][Column(12)].hyperlink().is_some());
-        assert_eq!(term.grid[Line(0)][Column(13)].c, 'o');
-        assert!(term.grid[Line(0)][Column(13)].hyperlink().is_some());
-        assert_eq!(
-            term.grid[Line(0)][Column(13)].hyperlink().unwrap().uri(),
-            "https://rio.io"
+    #[test]
+    fn test_bulk_apply_sgr() {
+        let mut cells = vec![Square::default(); 4];
+        cells[1].flags = square::Flags::ITALIC | square::Flags::UNDERCURL;
+        cells[2].fg = AnsiColor::Named(NamedColor::Red);
+
+        Crosswords::<VoidListener>::bulk_apply_sgr(
+            &mut cells[1..3],
+            &[
+                Attr::Bold,
+                Attr::Underline,
+                Attr::CancelItalic,
+                Attr::Background(AnsiColor::Named(NamedColor::Blue)),
+            ],
         );
-        assert_eq!(term.grid[Line(0)][Column(14)].c, ' ');
-        assert!(term.grid[Line(0)][Column(14)].hyperlink().is_none());
 
-        // From 'r' (this case should hit square hyperlink info)
-        let result = term
-            .search_nearest_hyperlink_from_pos(Pos::new(pos::Line(0), pos::Column(8)));
-        assert_eq!(
-            result,
-            Some(SelectionRange {
-                start: Pos {
-                    row: Line(0),
-                    col: Column(0)
-                },
-                end: Pos {
-                    row: Line(0),
-                    col: Column(13)
-                },
-                is_block: false
-            })
+        assert_eq!(cells[0], Square::default());
+        assert_eq!(cells[3], Square::default());
+        for cell in &cells[1..3] {
+            assert_eq!(cell.flags, square::Flags::BOLD | square::Flags::UNDERLINE);
+            assert_eq!(cell.bg, AnsiColor::Named(NamedColor::Blue));
+        }
+        assert_eq!(cells[1].fg, AnsiColor::Named(NamedColor::Foreground));
+        assert_eq!(cells[2].fg, AnsiColor::Named(NamedColor::Red));
+
+        cells[0].flags.insert(square::Flags::WRAPLINE);
+        cells[2].flags.insert(square::Flags::WIDE_CHAR);
+        Crosswords::<VoidListener>::bulk_apply_sgr(
+            &mut cells,
+            &[Attr::Bold, Attr::Reset, Attr::Strike],
         );
+        for cell in &cells {
+            assert!(cell.flags.contains(square::Flags::STRIKEOUT));
+            assert!(!cell
+                .flags
+                .intersects(square::Flags::BOLD | square::Flags::ALL_UNDERLINES));
+            assert_eq!(cell.fg, AnsiColor::Named(NamedColor::Foreground));
+            assert_eq!(cell.bg, AnsiColor::Named(NamedColor::Background));
+        }
+        assert!(cells[0].flags.contains(square::Flags::WRAPLINE));
+        assert!(cells[2].flags.contains(square::Flags::WIDE_CHAR));
+    }
 
-        assert_eq!(term.grid[Line(0)][Column(0)].c, 'h');
-        assert!(term.grid[Line(0)][Column(0)].hyperlink().is_some());
-        assert_eq!(
-            term.grid[Line(0)][Column(0)].hyperlink().unwrap().uri(),
-            "https://rio.io"
-        );
-        assert_eq!(term.grid[Line(0)][Column(13)].c, 'o');
-        assert!(term.grid[Line(0)][Column(13)].hyperlink().is_some());
-        assert_eq!(
-            term.grid[Line(0)][Column(13)].hyperlink().unwrap().uri(),
-            "https://rio.io"
-        );
-        assert_eq!(term.grid[Line(0)][Column(14)].c, ' ');
-        assert!(term.grid[Line(0)][Column(14)].hyperlink().is_none());
+    #[test]
+    fn test_change_area_attributes() {
+        let size = CrosswordsSize::new(5, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+
+        // Stream extent: from (1, 4) to (2, 2), whole lines in between.
+        for byte in b"\x1b[1;4;2;2;1$r" {
+            parser.advance(&mut cw, *byte);
+        }
+        let bold = |cw: &Crosswords<VoidListener>, line: i32, col: usize| {
+            cw.grid[Line(line)][Column(col)]
+                .flags
+                .contains(square::Flags::BOLD)
+        };
+        assert!(!bold(&cw, 0, 2));
+        assert!(bold(&cw, 0, 3));
+        assert!(bold(&cw, 0, 4));
+        assert!(bold(&cw, 1, 0));
+        assert!(bold(&cw, 1, 1));
+        assert!(!bold(&cw, 1, 2));
+
+        // Rectangle extent, the default parameters reset the attributes.
+        for byte in b"\x1b[2*x\x1b[1;2;4;3;7$r\x1b[$r" {
+            parser.advance(&mut cw, *byte);
+        }
+        for line in 0..4 {
+            for col in 0..5 {
+                assert!(!bold(&cw, line, col));
+                assert!(!cw.grid[Line(line)][Column(col)]
+                    .flags
+                    .contains(square::Flags::INVERSE));
+            }
+        }
+
+        for byte in b"\x1b[2;2;3;3;7$r" {
+            parser.advance(&mut cw, *byte);
+        }
+        for line in 0..4 {
+            for col in 0..5 {
+                let inverse = cw.grid[Line(line)][Column(col)]
+                    .flags
+                    .contains(square::Flags::INVERSE);
+                assert_eq!(inverse, (1..3).contains(&line) && (1..3).contains(&col));
+            }
+        }
     }
 
     #[test]
-    fn test_search_nearest_hyperlink_from_pos_on_multiple_lines() {
-        let size = CrosswordsSize::new(4, 4);
+    fn test_resize_to_tiny_sizes() {
+        let size = CrosswordsSize::new(10, 5);
         let window_id = crate::event::WindowId::from(0);
-        let mut term =
+        let mut cw =
             Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"rio\r\nterm\x1b[?1049h\x1b[5;9Hx" {
+            parser.advance(&mut cw, *byte);
+        }
 
-        let grid = &mut term.grid;
-        grid[Line(0)][Column(0)].c = 'h';
-        grid[Line(0)][Column(1)].c = 't';
-        grid[Line(0)][Column(2)].c = 't';
-        grid[Line(0)][Column(3)].c = 'p';
-        grid[Line(1)][Column(0)].c = 's';
-        grid[Line(1)][Column(1)].c = ':';
-        grid[Line(1)][Column(2)].c = '/';
-        grid[Line(1)][Column(3)].c = '/';
-        grid[Line(2)][Column(0)].c = 'r';
-        grid[Line(2)][Column(1)].c = 'i';
-        grid[Line(2)][Column(2)].c = 'o';
-        grid[Line(2)][Column(3)].c = '.';
-        grid[Line(3)][Column(0)].c = 'i';
-        grid[Line(3)][Column(1)].c = 'o';
+        for (columns, lines) in [(1, 1), (0, 0), (1, 10), (10, 0), (0, 5)] {
+            cw.resize(CrosswordsSize::new(columns, lines));
+            assert_eq!(cw.columns(), columns.max(MIN_COLUMNS));
+            assert_eq!(cw.screen_lines(), lines.max(MIN_LINES));
+            assert!(cw.grid.cursor.pos.col <= cw.grid.last_column());
+            assert!(cw.grid.cursor.pos.row <= cw.bottommost_line());
 
-        assert!(term.grid[Line(0)][Column(0)].hyperlink().is_none());
-        assert!(term.grid[Line(1)][Column(0)].hyperlink().is_none());
-        assert!(term.grid[Line(2)][Column(0)].hyperlink().is_none());
-        assert!(term.grid[Line(3)][Column(0)].hyperlink().is_none());
+            for byte in b"abc\r\n\x1b[3D\x1b[2P" {
+                parser.advance(&mut cw, *byte);
+            }
+        }
 
-        // From ' ' after '.io'
-        let result = term.search_nearest_hyperlink_from_pos(Pos {
-            row: pos::Line(3),
-            col: pos::Column(2),
-        });
-        assert_eq!(result, None);
+        for byte in b"\x1b[?1049l" {
+            parser.advance(&mut cw, *byte);
+        }
+        cw.resize(CrosswordsSize::new(10, 5));
+        assert_eq!(cw.columns(), 10);
+        assert_eq!(cw.screen_lines(), 5);
+    }
+
+    #[test]
+    fn test_block_to_string() {
+        let size = CrosswordsSize::new(8, 3);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in "abcdefgh\r\nA\u{4e2d}BCDE\r\n123".as_bytes() {
+            parser.advance(&mut cw, *byte);
+        }
 
-        // From 'r'
-        let result = term.search_nearest_hyperlink_from_pos(Pos {
-            row: pos::Line(2),
-            col: pos::Column(1),
-        });
         assert_eq!(
-            result,
-            Some(SelectionRange {
-                start: Pos {
-                    row: Line(0),
-                    col: Column(0)
-                },
-                end: Pos {
-                    row: Line(3),
-                    col: Column(1)
-                },
-                is_block: false
-            })
+            cw.block_to_string(
+                Pos::new(Line(0), Column(1)),
+                Pos::new(Line(2), Column(3))
+            ),
+            "bcd\n\u{4e2d}B\n23"
         );
-
-        assert!(term.grid[Line(0)][Column(0)].hyperlink().is_some());
+        // Corners can be given in any order.
         assert_eq!(
-            term.grid[Line(0)][Column(0)].hyperlink().unwrap().uri(),
-            "https://rio.io"
+            cw.block_to_string(
+                Pos::new(Line(1), Column(3)),
+                Pos::new(Line(0), Column(2))
+            ),
+            "cd\n\u{4e2d}B"
         );
-        assert!(term.grid[Line(0)][Column(0)].hyperlink().is_some());
-        assert!(term.grid[Line(0)][Column(1)].hyperlink().is_some());
-        assert!(term.grid[Line(0)][Column(2)].hyperlink().is_some());
-        assert!(term.grid[Line(0)][Column(3)].hyperlink().is_some());
-        assert!(term.grid[Line(1)][Column(0)].hyperlink().is_some());
-        assert!(term.grid[Line(1)][Column(1)].hyperlink().is_some());
-        assert!(term.grid[Line(1)][Column(2)].hyperlink().is_some());
-        assert!(term.grid[Line(1)][Column(3)].hyperlink().is_some());
-        assert!(term.grid[Line(2)][Column(0)].hyperlink().is_some());
-        assert!(term.grid[Line(2)][Column(1)].hyperlink().is_some());
-        assert!(term.grid[Line(2)][Column(2)].hyperlink().is_some());
-        assert!(term.grid[Line(2)][Column(3)].hyperlink().is_some());
-        assert!(term.grid[Line(3)][Column(0)].hyperlink().is_some());
+        // Wide char is included when only its spacer is inside the rectangle.
         assert_eq!(
-            term.grid[Line(3)][Column(1)].hyperlink().unwrap().uri(),
-            "https://rio.io"
+            cw.block_to_string(
+                Pos::new(Line(1), Column(2)),
+                Pos::new(Line(1), Column(4))
+            ),
+            "\u{4e2d}BC"
         );
-        assert!(term.grid[Line(3)][Column(2)].hyperlink().is_none());
     }
 
     #[test]
-    fn test_search_nearest_hyperlink_from_pos_on_existent_hyperlink() {
-        let size = CrosswordsSize::new(4, 4);
+    fn block_selection_with_wide_chars() {
+        let size = CrosswordsSize::new(6, 4);
         let window_id = crate::event::WindowId::from(0);
-
         let mut term =
             Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        // The last row wraps `字` to the next line, leaving a leading spacer.
+        for byte in "\u{4e2d}\u{6587}ab\r\na\u{65e5}\u{672c}b\r\nxyzab\u{5b57}".as_bytes()
+        {
+            parser.advance(&mut term, *byte);
+        }
 
-        let grid = &mut term.grid;
-        let hyperlink = Hyperlink::new(None, "https://rio.io");
-        grid[Line(0)][Column(2)].c = 'r';
-        grid[Line(0)][Column(2)].set_hyperlink(Some(hyperlink.clone()));
-        grid[Line(0)][Column(3)].c = ' ';
-        grid[Line(0)][Column(3)].set_hyperlink(Some(hyperlink.clone()));
-        grid[Line(1)][Column(0)].c = '2';
-        grid[Line(1)][Column(0)].set_hyperlink(Some(hyperlink.clone()));
-        grid[Line(1)][Column(1)].c = ' ';
-        grid[Line(1)][Column(2)].c = 'i';
-        grid[Line(1)][Column(2)].set_hyperlink(Some(hyperlink.clone()));
-        grid[Line(1)][Column(3)].c = 'o';
-        grid[Line(1)][Column(3)].set_hyperlink(Some(hyperlink.clone()));
-
-        // "  r "
-        // "2 io"
-        // "    "
-        // "    "
-
-        // Hyperlink that should be highlighted is "r 2"
-
-        assert!(term.grid[Line(0)][Column(0)].hyperlink().is_none());
-        assert!(term.grid[Line(1)][Column(0)].hyperlink().is_some());
-        assert!(term.grid[Line(2)][Column(0)].hyperlink().is_none());
-
-        let result = term.search_nearest_hyperlink_from_pos(Pos {
-            row: pos::Line(0),
-            col: pos::Column(0),
-        });
-        assert_eq!(result, None);
+        let block = |term: &mut Crosswords<VoidListener>, start: Pos, end: Pos| {
+            let mut selection = Selection::new(SelectionType::Block, start, Side::Left);
+            selection.update(end, Side::Right);
+            term.selection = Some(selection);
+            term.selection_to_string().unwrap()
+        };
 
-        let result = term.search_nearest_hyperlink_from_pos(Pos {
-            row: pos::Line(0),
-            col: pos::Column(3),
-        });
+        // Rows are joined by a newline, without padding to the block width.
         assert_eq!(
-            result,
-            Some(SelectionRange {
-                start: Pos {
-                    row: Line(0),
-                    col: Column(2)
-                },
-                end: Pos {
-                    row: Line(1),
-                    col: Column(0)
-                },
-                is_block: false
-            })
+            block(
+                &mut term,
+                Pos::new(Line(0), Column(0)),
+                Pos::new(Line(1), Column(5))
+            ),
+            "\u{4e2d}\u{6587}ab\na\u{65e5}\u{672c}b"
         );
 
+        // Wide chars straddling either edge are kept whole.
         assert_eq!(
-            term.grid[Line(0)][Column(2)].hyperlink().unwrap().uri(),
-            "https://rio.io"
+            block(
+                &mut term,
+                Pos::new(Line(0), Column(1)),
+                Pos::new(Line(1), Column(2))
+            ),
+            "\u{4e2d}\u{6587}\n\u{65e5}"
         );
 
-        // Then we "promote" col 1/ row 1 to hyperlink and connecting with " io"
-        term.grid[Line(1)][Column(1)].set_hyperlink(Some(hyperlink.clone()));
-        let result = term.search_nearest_hyperlink_from_pos(Pos {
-            row: pos::Line(0),
-            col: pos::Column(3),
-        });
+        // The leading spacer of a wrapped wide char never pulls in text
+        // from another row.
         assert_eq!(
-            result,
-            Some(SelectionRange {
-                start: Pos {
-                    row: Line(0),
-                    col: Column(2)
-                },
-                end: Pos {
-                    row: Line(1),
-                    col: Column(3)
-                },
-                is_block: false
-            })
+            block(
+                &mut term,
+                Pos::new(Line(1), Column(4)),
+                Pos::new(Line(2), Column(5))
+            ),
+            "\u{672c}b\nb"
         );
     }
 
+    #[test]
+    fn test_line_content() {
+        let size = CrosswordsSize::new(5, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"  rio terminal\r\nok" {
+            parser.advance(&mut cw, *byte);
+        }
+
+        assert_eq!(cw.line_content(0), "rio terminal");
+        assert_eq!(cw.line_content(1), "terminal");
+        assert_eq!(cw.line_content(3), "ok");
+        assert_eq!(cw.line_content(4), "");
+        assert_eq!(cw.line_content(10), "");
+        assert_eq!(cw.line_content(-1), "");
+    }
+
     #[test]
     fn parse_cargo_version() {
         assert_eq!(version_number("0.0.1-nightly"), 1);
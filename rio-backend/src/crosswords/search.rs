@@ -33,7 +33,23 @@ pub struct RegexSearch {
     right_fdfa: LazyDfa,
 }
 
+/// Options for building a search regex out of plain text.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RegexFlags {
+    /// Match the text as is, escaping any regex metacharacters.
+    pub literal: bool,
+}
+
 impl RegexSearch {
+    /// Build the search pattern for `text`.
+    pub fn pattern(text: &str, flags: RegexFlags) -> String {
+        if flags.literal {
+            regex::escape(text)
+        } else {
+            text.to_owned()
+        }
+    }
+
     /// Build the forward and backward search DFAs.
     pub fn new(search: &str) -> Result<RegexSearch, Box<BuildError>> {
         // Setup configs for both DFA directions.
@@ -160,6 +176,24 @@ impl<T: event::EventListener> Crosswords<T> {
         }
     }
 
+    /// Move the vi cursor to the start of the next match in `direction`,
+    /// skipping the match it is on and wrapping around the scrollback.
+    pub fn vi_search_next(
+        &mut self,
+        regex: &mut RegexSearch,
+        direction: Direction,
+    ) -> Option<Match> {
+        let cursor = self.vi_mode_cursor.pos;
+        let origin = match direction {
+            Direction::Right => cursor.add(self, Boundary::None, 1),
+            Direction::Left => cursor.sub(self, Boundary::None, 1),
+        };
+
+        let regex_match = self.search_next(regex, origin, direction, Side::Left, None)?;
+        self.vi_goto_pos(*regex_match.start());
+        Some(regex_match)
+    }
+
     /// Find the next match to the right of the origin.
     fn next_match_right(
         &self,
@@ -809,6 +843,27 @@ mod tests {
         term
     }
 
+    #[test]
+    fn regex_pattern_from_text() {
+        let literal = RegexFlags { literal: true };
+        assert_eq!(RegexSearch::pattern("rio", literal), "rio");
+        assert_eq!(RegexSearch::pattern("a.b*(c)", literal), "a\\.b\\*\\(c\\)");
+        assert_eq!(
+            RegexSearch::pattern("a.b*(c)", RegexFlags::default()),
+            "a.b*(c)"
+        );
+
+        // Escaped text only matches itself.
+        let term = mock_term("axb a.b");
+        let mut regex = RegexSearch::new(&RegexSearch::pattern("a.b", literal)).unwrap();
+        let start = Pos::new(Line(0), Column(0));
+        let end = Pos::new(Line(0), Column(6));
+        assert_eq!(
+            term.regex_search_right(&mut regex, start, end),
+            Some(Pos::new(Line(0), Column(4))..=Pos::new(Line(0), Column(6)))
+        );
+    }
+
     #[test]
     fn regex_right() {
         #[rustfmt::skip]
@@ -1283,6 +1338,59 @@ mod tests {
         assert_eq!(iter.next(), None);
     }
 
+    #[test]
+    fn case_insensitive_without_uppercase() {
+        #[rustfmt::skip]
+        let term = mock_term("\
+            Rio Term\r\n\
+            rio\
+        ");
+
+        // Lowercase queries ignore case.
+        let mut regex = RegexSearch::new("rio").unwrap();
+        let start = Pos::new(Line(0), Column(0));
+        let end = Pos::new(Line(1), Column(2));
+        let match_end = Pos::new(Line(0), Column(2));
+        assert_eq!(
+            term.regex_search_right(&mut regex, start, end),
+            Some(start..=match_end)
+        );
+
+        // Any uppercase character makes the query case sensitive.
+        let mut regex = RegexSearch::new("Rio").unwrap();
+        let start = Pos::new(Line(0), Column(1));
+        assert_eq!(term.regex_search_right(&mut regex, start, end), None);
+    }
+
+    #[test]
+    fn vi_search_next_cycles() {
+        #[rustfmt::skip]
+        let mut term = mock_term("\
+            rio a rio\r\n\
+            b rio cc\
+        ");
+        let matches = [
+            Pos::new(Line(0), Column(6)),
+            Pos::new(Line(1), Column(2)),
+            Pos::new(Line(0), Column(0)),
+        ];
+
+        let mut regex = RegexSearch::new("rio").unwrap();
+        for pos in matches.iter().chain(matches.iter()) {
+            let regex_match = term.vi_search_next(&mut regex, Direction::Right);
+            assert_eq!(regex_match.map(|m| *m.start()), Some(*pos));
+            assert_eq!(term.vi_mode_cursor.pos, *pos);
+        }
+
+        // Backwards from the first match wraps to the last one.
+        let regex_match = term.vi_search_next(&mut regex, Direction::Left);
+        assert_eq!(regex_match.map(|m| *m.start()), Some(matches[1]));
+
+        let mut regex = RegexSearch::new("zzz").unwrap();
+        assert_eq!(term.vi_search_next(&mut regex, Direction::Right), None);
+        assert_eq!(term.vi_mode_cursor.pos, matches[1]);
+    }
+
     #[test]
     fn wrap_around_to_another_end() {
         #[rustfmt::skip]
@@ -1314,6 +1422,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn unicode_search_wraps_through_history() {
+        let size = CrosswordsSize::new(6, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in "čaj\r\nab\r\nčaj\r\ncd".as_bytes() {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.grid.history_size(), 2);
+
+        let mut regex = RegexSearch::new("čaj").unwrap();
+        let in_history = Pos::new(Line(-2), Column(0));
+
+        // Forward from the bottom wraps to the top of the scrollback.
+        let origin = Pos::new(Line(1), Column(1));
+        let regex_match =
+            term.search_next(&mut regex, origin, Direction::Right, Side::Left, None);
+        assert_eq!(regex_match.map(|m| *m.start()), Some(in_history));
+
+        // Backward from before the first match wraps to the bottom.
+        let mut regex = RegexSearch::new("aj").unwrap();
+        let on_screen = Pos::new(Line(0), Column(1));
+        let origin = Pos::new(Line(-2), Column(0));
+        let regex_match =
+            term.search_next(&mut regex, origin, Direction::Left, Side::Left, None);
+        assert_eq!(regex_match.map(|m| *m.start()), Some(on_screen));
+    }
+
     #[test]
     fn nfa_compile_error() {
         assert!(RegexSearch::new("[0-9A-Za-z]{9999999}").is_err());
@@ -1398,4 +1537,28 @@ mod tests {
             Some(match_start..=match_end)
         );
     }
+
+    #[test]
+    fn custom_semantic_escape_chars() {
+        let mut term = mock_term("ab-cd ef");
+        let point = Pos::new(Line(0), Column(1));
+        assert_eq!(
+            term.semantic_search_left(point),
+            Pos::new(Line(0), Column(0))
+        );
+        assert_eq!(
+            term.semantic_search_right(point),
+            Pos::new(Line(0), Column(4))
+        );
+
+        term.set_semantic_escape_chars("- ");
+        assert_eq!(
+            term.semantic_search_left(point),
+            Pos::new(Line(0), Column(0))
+        );
+        assert_eq!(
+            term.semantic_search_right(point),
+            Pos::new(Line(0), Column(1))
+        );
+    }
 }
@@ -56,6 +56,12 @@ pub enum RioErrorType {
     InvalidConfigurationFormat(String),
     // configuration invalid theme
     InvalidConfigurationTheme(String),
+    // configuration key bindings that could not be used
+    InvalidKeyBindings(Vec<String>),
+    // custom hyperlink handler was not able to open the url
+    HyperlinkOpenFailed(String),
+    // the clipboard owner didn't answer a paste in time
+    ClipboardPasteTimeout,
 
     // reports that are ignored by RioErrorType
     IgnoredReport,
@@ -102,6 +108,22 @@ impl std::fmt::Display for RioErrorType {
             RioErrorType::InvalidConfigurationTheme(message) => {
                 write!(f, "Found an issue in the configured theme:\n\n{message}")
             }
+            RioErrorType::InvalidKeyBindings(errors) => {
+                write!(
+                    f,
+                    "Some key bindings were ignored:\n\n{}",
+                    errors.join("\n")
+                )
+            }
+            RioErrorType::HyperlinkOpenFailed(message) => {
+                write!(f, "Unable to open hyperlink:\n\n{message}")
+            }
+            RioErrorType::ClipboardPasteTimeout => {
+                write!(
+                    f,
+                    "Paste abandoned:\n\nthe application owning the clipboard didn't answer in time"
+                )
+            }
         }
     }
 }
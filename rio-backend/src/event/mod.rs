@@ -6,7 +6,9 @@ use crate::crosswords::grid::Scroll;
 use crate::crosswords::pos::{Direction, Pos};
 use crate::crosswords::search::{Match, RegexSearch};
 use crate::error::RioError;
+use crate::recording::SharedRecording;
 use rio_window::event::Event as RioWindowEvent;
+use rio_window::window::CursorIcon;
 use std::borrow::Cow;
 use std::collections::VecDeque;
 use std::fmt::Debug;
@@ -27,7 +29,13 @@ pub enum RioEventType {
     BlinkCursorTimeout,
 }
 
-#[derive(Debug)]
+/// Callback that receives raw PTY output before it reaches the parser and
+/// returns how many bytes, from the start of the slice, were consumed.
+///
+/// It runs on the PTY reader thread while the terminal is locked, so it must
+/// be cheap: any time spent in it delays parsing and rendering of the output.
+pub type OutputFilter = Box<dyn FnMut(&[u8]) -> usize + Send>;
+
 pub enum Msg {
     /// Data that should be written to the PTY.
     Input(Cow<'static, [u8]>),
@@ -36,6 +44,29 @@ pub enum Msg {
     Shutdown,
 
     Resize(WinsizeBuilder),
+
+    /// Replace the filter applied to the PTY output.
+    OutputFilter(OutputFilter),
+
+    /// Change how many bytes are parsed before the terminal is released.
+    ParseBudget(usize),
+
+    /// Start recording the input and output into this recording, or stop
+    /// with `None`.
+    Recording(Option<SharedRecording>),
+}
+
+impl Debug for Msg {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Msg::Input(input) => write!(f, "Input({input:?})"),
+            Msg::Shutdown => write!(f, "Shutdown"),
+            Msg::Resize(winsize) => write!(f, "Resize({winsize:?})"),
+            Msg::OutputFilter(_) => write!(f, "OutputFilter"),
+            Msg::ParseBudget(budget) => write!(f, "ParseBudget({budget})"),
+            Msg::Recording(recording) => write!(f, "Recording({})", recording.is_some()),
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -57,12 +88,16 @@ pub enum RioEvent {
     Paste,
     Copy(String),
     UpdateFontSize(u8),
+    UpdateRenderScale,
     Scroll(Scroll),
     ToggleFullScreen,
     Minimize(bool),
     Hide,
     HideOtherApplications,
     UpdateConfig,
+    /// The machine switched between battery and AC power, with the battery
+    /// level from 0.0 to 1.0.
+    PowerStateChange(f32, bool),
     CreateWindow,
     CloseWindow,
     CreateNativeTab(Option<String>),
@@ -77,6 +112,12 @@ pub enum RioEvent {
     /// Grid has changed possibly requiring a mouse cursor shape change.
     MouseCursorDirty,
 
+    /// Update the mouse cursor icon of the window.
+    SetCursorIcon(CursorIcon),
+
+    /// Show or hide the mouse cursor of the window.
+    SetCursorVisible(bool),
+
     /// Window title change.
     Title(String),
 
@@ -110,15 +151,50 @@ pub enum RioEvent {
     /// Write some text to the PTY.
     PtyWrite(String),
 
+    /// Schedule the report of the latest throttled mouse motion in this
+    /// many milliseconds.
+    PrepareMotionReport(u64),
+
+    /// Report the latest throttled mouse motion.
+    MotionReport,
+
+    /// Check the clipboards for changes every this many milliseconds.
+    PrepareClipboardPoll(u64),
+
+    /// Check the clipboards for changes.
+    ClipboardPoll,
+
+    /// Schedule the timeout of the clipboard paste request with this id.
+    PrepareClipboardPaste(u64),
+
+    /// Text read for the clipboard paste request with this id, `None` if
+    /// the request timed out.
+    ClipboardPaste(u64, Option<String>),
+
     /// Request to write the text area size.
     TextAreaSizeRequest(Arc<dyn Fn(WinsizeBuilder) -> String + Sync + Send + 'static>),
 
     /// Cursor blinking state has changed.
     CursorBlinkingChange,
 
+    /// Application requested to show or hide the scrollbar.
+    ScrollbarVisibility(bool),
+
+    /// A single line went over the maximum line length and was broken.
+    LineLengthExceeded(usize),
+
     /// Terminal bell ring.
     Bell,
 
+    /// The shell is waiting for input on this route (OSC 133;B).
+    PromptReady(usize),
+
+    /// Schedule sending the startup script after a delay in milliseconds.
+    PrepareStartupScript(u64),
+
+    /// Send the startup script if it was not sent yet.
+    StartupScript,
+
     /// Shutdown request.
     Exit,
 
@@ -146,6 +222,20 @@ impl Debug for RioEvent {
             RioEvent::TextAreaSizeRequest(_) => write!(f, "TextAreaSizeRequest"),
             RioEvent::ColorRequest(index, _) => write!(f, "ColorRequest({index})"),
             RioEvent::PtyWrite(text) => write!(f, "PtyWrite({text})"),
+            RioEvent::PrepareMotionReport(millis) => {
+                write!(f, "PrepareMotionReport({millis})")
+            }
+            RioEvent::MotionReport => write!(f, "MotionReport"),
+            RioEvent::PrepareClipboardPoll(millis) => {
+                write!(f, "PrepareClipboardPoll({millis})")
+            }
+            RioEvent::ClipboardPoll => write!(f, "ClipboardPoll"),
+            RioEvent::PrepareClipboardPaste(id) => {
+                write!(f, "PrepareClipboardPaste({id})")
+            }
+            RioEvent::ClipboardPaste(id, text) => {
+                write!(f, "ClipboardPaste({id}, {})", text.is_some())
+            }
             RioEvent::Title(title) => write!(f, "Title({title})"),
             RioEvent::TitleWithSubtitle(title, subtitle) => {
                 write!(f, "TitleWithSubtitle({title}, {subtitle})")
@@ -154,7 +244,17 @@ impl Debug for RioEvent {
             RioEvent::Hide => write!(f, "Hide)"),
             RioEvent::HideOtherApplications => write!(f, "HideOtherApplications)"),
             RioEvent::CursorBlinkingChange => write!(f, "CursorBlinkingChange"),
+            RioEvent::ScrollbarVisibility(visible) => {
+                write!(f, "ScrollbarVisibility({visible})")
+            }
+            RioEvent::LineLengthExceeded(limit) => {
+                write!(f, "LineLengthExceeded({limit})")
+            }
             RioEvent::MouseCursorDirty => write!(f, "MouseCursorDirty"),
+            RioEvent::SetCursorIcon(icon) => write!(f, "SetCursorIcon({icon:?})"),
+            RioEvent::SetCursorVisible(visible) => {
+                write!(f, "SetCursorVisible({visible})")
+            }
             RioEvent::ResetTitle => write!(f, "ResetTitle"),
             RioEvent::PrepareRender(millis) => write!(f, "PrepareRender({millis})"),
             RioEvent::PrepareRenderOnRoute(millis, route) => {
@@ -164,6 +264,11 @@ impl Debug for RioEvent {
             RioEvent::RenderRoute(route) => write!(f, "Render route {route}"),
             RioEvent::Scroll(scroll) => write!(f, "Scroll {scroll:?}"),
             RioEvent::Bell => write!(f, "Bell"),
+            RioEvent::PromptReady(route) => write!(f, "PromptReady {route}"),
+            RioEvent::PrepareStartupScript(millis) => {
+                write!(f, "PrepareStartupScript({millis})")
+            }
+            RioEvent::StartupScript => write!(f, "StartupScript"),
             RioEvent::Exit => write!(f, "Exit"),
             RioEvent::Quit => write!(f, "Quit"),
             RioEvent::CloseTerminal(route) => write!(f, "CloseTerminal {route}"),
@@ -178,6 +283,9 @@ impl Debug for RioEvent {
             RioEvent::SelectNativeTabPrev => write!(f, "SelectNativeTabPrev"),
             RioEvent::CreateConfigEditor => write!(f, "CreateConfigEditor"),
             RioEvent::UpdateConfig => write!(f, "ReloadConfiguration"),
+            RioEvent::PowerStateChange(battery_level, on_ac) => {
+                write!(f, "PowerStateChange({battery_level}, {on_ac})")
+            }
             RioEvent::ReportToAssistant(error_report) => {
                 write!(f, "ReportToAssistant({})", error_report.report)
             }
@@ -187,6 +295,7 @@ impl Debug for RioEvent {
             RioEvent::Copy(_) => write!(f, "Copy"),
             RioEvent::Paste => write!(f, "Paste"),
             RioEvent::UpdateFontSize(action) => write!(f, "UpdateFontSize({action:?})"),
+            RioEvent::UpdateRenderScale => write!(f, "UpdateRenderScale"),
             RioEvent::UpdateGraphicLibrary => write!(f, "UpdateGraphicLibrary"),
         }
     }
@@ -197,11 +306,22 @@ pub struct EventPayload {
     /// Event payload.
     pub payload: RioEventType,
     pub window_id: WindowId,
+    /// Terminal that sent the event, `None` for window events.
+    pub terminal_id: Option<u64>,
 }
 
 impl EventPayload {
     pub fn new(payload: RioEventType, window_id: WindowId) -> Self {
-        Self { payload, window_id }
+        Self {
+            payload,
+            window_id,
+            terminal_id: None,
+        }
+    }
+
+    pub fn with_terminal_id(mut self, terminal_id: u64) -> Self {
+        self.terminal_id = Some(terminal_id);
+        self
     }
 }
 
@@ -221,6 +341,11 @@ pub trait EventListener {
 
     fn send_event(&self, _event: RioEvent, _id: WindowId) {}
 
+    /// Send an event on behalf of the terminal `terminal_id`.
+    fn send_terminal_event(&self, event: RioEvent, id: WindowId, _terminal_id: u64) {
+        self.send_event(event, id);
+    }
+
     fn send_event_with_high_priority(&self, _event: RioEvent, _id: WindowId) {}
 
     fn send_redraw(&self, _id: WindowId) {}
@@ -266,6 +391,12 @@ impl EventListener for EventProxy {
     fn send_event(&self, event: RioEvent, id: WindowId) {
         let _ = self.proxy.send_event(EventPayload::new(event.into(), id));
     }
+
+    fn send_terminal_event(&self, event: RioEvent, id: WindowId, terminal_id: u64) {
+        let _ = self.proxy.send_event(
+            EventPayload::new(event.into(), id).with_terminal_id(terminal_id),
+        );
+    }
 }
 
 /// Regex search state.
@@ -5,6 +5,8 @@ pub mod crosswords;
 pub mod error;
 pub mod event;
 pub mod performer;
+pub mod recording;
 pub mod selection;
+pub mod session;
 
 pub use sugarloaf;
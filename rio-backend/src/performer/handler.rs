@@ -1,5 +1,7 @@
-use crate::ansi::CursorShape;
-use crate::ansi::{mode::Mode, sixel, KeyboardModes, KeyboardModesApplyBehavior};
+use crate::ansi::{
+    kitty_graphics, mode::Mode, sixel, KeyboardModes, KeyboardModesApplyBehavior,
+};
+use crate::ansi::{CursorShape, SemanticPrompt};
 use crate::config::colors::{AnsiColor, ColorRgb, NamedColor};
 use crate::crosswords::pos::{CharsetIndex, Column, Line, StandardCharset};
 use crate::crosswords::square::Hyperlink;
@@ -86,6 +88,32 @@ enum Dcs {
 
     /// Sixel data
     SixelData(Box<sixel::Parser>),
+
+    /// Names requested by `XTGETXRES`, separated by `;`.
+    XtGetXRes(Vec<u8>),
+
+    /// Setting requested by `DECRQSS`.
+    Decrqss(Vec<u8>),
+}
+
+/// Max. length of the setting requested by `DECRQSS`.
+const DECRQSS_MAX_LEN: usize = 16;
+
+/// Max. length of the names requested by a single `XTGETXRES`.
+const XTGETXRES_MAX_LEN: usize = 1024;
+
+/// Decode a hex encoded `XTGETXRES` name, plain names are used as is.
+fn decode_xtgetxres_name(name: &[u8]) -> Option<String> {
+    let decoded = name
+        .chunks(2)
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair).ok()?;
+            u8::from_str_radix(pair, 16).ok()
+        })
+        .collect::<Option<Vec<u8>>>()
+        .filter(|_| name.len() % 2 == 0);
+
+    String::from_utf8(decoded.unwrap_or_else(|| name.to_vec())).ok()
 }
 
 /// Parse colors in `#r(rrr)g(ggg)b(bbb)` format.
@@ -149,6 +177,9 @@ pub trait Handler {
     /// OSC to set window title.
     fn set_title(&mut self, _: Option<String>) {}
 
+    /// OSC to set icon title.
+    fn set_icon_title(&mut self, _: Option<String>) {}
+
     /// Set the cursor style.
     fn set_cursor_style(&mut self, _style: Option<CursorShape>, _blinking: bool) {}
 
@@ -158,6 +189,12 @@ pub trait Handler {
     /// A character to be displayed.
     fn input(&mut self, _c: char) {}
 
+    /// Whether C0 controls should be displayed in caret notation
+    /// instead of being interpreted.
+    fn show_control_characters(&self) -> bool {
+        false
+    }
+
     /// Set cursor to position.
     fn goto(&mut self, _: Line, _: Column) {}
 
@@ -214,7 +251,7 @@ pub trait Handler {
     /// Substitute char under cursor.
     fn substitute(&mut self) {}
 
-    /// Newline.
+    /// Newline, a line feed that also returns the carriage in LNM mode.
     fn newline(&mut self) {}
 
     /// Set current position as a tabstop.
@@ -287,6 +324,21 @@ pub trait Handler {
     /// DECSTBM - Set the terminal scrolling region.
     fn set_scrolling_region(&mut self, _top: usize, _bottom: Option<usize>) {}
 
+    /// DECCARA - Change the attributes of the cells in an area.
+    fn set_area_attributes(
+        &mut self,
+        _top: usize,
+        _left: usize,
+        _bottom: Option<usize>,
+        _right: Option<usize>,
+        _attrs: &[Attr],
+    ) {
+    }
+
+    /// DECSACE - Select whether area attribute changes cover a rectangle or
+    /// the character stream between the two corners.
+    fn set_attribute_change_extent(&mut self, _rectangle: bool) {}
+
     /// DECKPAM - Set keypad to applications mode (ESCape instead of digits).
     fn set_keypad_application_mode(&mut self) {}
 
@@ -349,9 +401,21 @@ pub trait Handler {
     /// Insert a new graphic item.
     fn insert_graphic(&mut self, _data: GraphicData, _palette: Option<Vec<ColorRgb>>) {}
 
+    /// Run a kitty graphics protocol command.
+    fn kitty_graphics(&mut self, _command: kitty_graphics::Command) {}
+
+    /// Report a named terminal property (`XTGETXRES`).
+    fn report_termprop(&mut self, _name: &str) {}
+
+    /// Report the value of a setting (`DECRQSS`).
+    fn report_status_string(&mut self, _setting: &[u8]) {}
+
     /// Set hyperlink.
     fn set_hyperlink(&mut self, _: Option<Hyperlink>) {}
 
+    /// OSC 133 shell integration mark.
+    fn semantic_prompt(&mut self, _: SemanticPrompt) {}
+
     /// Set mouse cursor icon.
     fn set_mouse_cursor_icon(&mut self, _: CursorIcon) {}
 
@@ -430,6 +494,14 @@ impl ParserProcessor {
     where
         H: Handler,
     {
+        if handler.show_control_characters() {
+            if let Some(caret) = caret_notation(byte) {
+                handler.input('^');
+                handler.input(caret);
+                return;
+            }
+        }
+
         if self.state.sync_state.timeout.is_none() {
             let mut performer = Performer::new(&mut self.state, handler);
             self.parser.advance(&mut performer, byte);
@@ -514,13 +586,26 @@ impl ParserProcessor {
                         Some(Instant::now() + SYNC_UPDATE_TIMEOUT);
                 }
                 Some(Dcs::SyncEnd) => self.stop_sync(handler),
-                Some(Dcs::SixelData(_)) => (),
+                Some(Dcs::SixelData(_) | Dcs::XtGetXRes(_) | Dcs::Decrqss(_)) => (),
                 None => (),
             },
         }
     }
 }
 
+/// Caret notation for C0 controls and DEL, like `cat -v` does.
+///
+/// Line feed, carriage return and tab are left out so the output
+/// keeps its layout.
+#[inline]
+fn caret_notation(byte: u8) -> Option<char> {
+    match byte {
+        C0::LF | C0::CR | C0::HT => None,
+        0x00..=0x1f | 0x7f => Some((byte ^ 0x40) as char),
+        _ => None,
+    }
+}
+
 struct Performer<'a, H: Handler> {
     state: &'a mut ProcessorState,
     handler: &'a mut H,
@@ -550,11 +635,13 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
             C0::HT => self.handler.put_tab(1),
             C0::BS => self.handler.backspace(),
             C0::CR => self.handler.carriage_return(),
-            C0::LF | C0::VT | C0::FF => self.handler.linefeed(),
+            C0::LF | C0::VT | C0::FF => self.handler.newline(),
             C0::BEL => self.handler.bell(),
             C0::SUB => self.handler.substitute(),
             C0::SI => self.handler.set_active_charset(CharsetIndex::G0),
             C0::SO => self.handler.set_active_charset(CharsetIndex::G1),
+            // Padding characters, common in binary output.
+            C0::NUL | C0::DEL => (),
             _ => warn!("[unhandled] execute byte={byte:02x}"),
         }
     }
@@ -571,6 +658,8 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
                 let parser = self.handler.start_sixel_graphic(params);
                 self.state.dcs = parser.map(Dcs::SixelData);
             }
+            ('Q', [b'+']) => self.state.dcs = Some(Dcs::XtGetXRes(Vec::new())),
+            ('q', [b'$']) => self.state.dcs = Some(Dcs::Decrqss(Vec::new())),
             _ => debug!(
                 "[unhandled hook] params={:?}, ints: {:?}, ignore: {:?}, action: {:?}",
                 params, intermediates, ignore, action
@@ -587,6 +676,16 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
                     self.state.dcs = None;
                 }
             }
+            Some(Dcs::XtGetXRes(ref mut names)) => {
+                if names.len() < XTGETXRES_MAX_LEN {
+                    names.push(byte);
+                }
+            }
+            Some(Dcs::Decrqss(ref mut setting)) => {
+                if setting.len() < DECRQSS_MAX_LEN {
+                    setting.push(byte);
+                }
+            }
 
             _ => debug!("[unhandled put] byte={:?}", byte),
         }
@@ -606,10 +705,26 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
                 }
                 Err(err) => log::warn!("Failed to parse Sixel data: {}", err),
             },
+            Some(Dcs::XtGetXRes(names)) => {
+                for name in names.split(|byte| *byte == b';') {
+                    match decode_xtgetxres_name(name) {
+                        Some(name) => self.handler.report_termprop(&name),
+                        None => debug!("[unhandled xtgetxres] name={:?}", name),
+                    }
+                }
+            }
+            Some(Dcs::Decrqss(setting)) => self.handler.report_status_string(&setting),
             _ => debug!("[unhandled unhook]"),
         }
     }
 
+    fn apc_dispatch(&mut self, data: &[u8], truncated: bool) {
+        match kitty_graphics::parse(data, truncated) {
+            Some(command) => self.handler.kitty_graphics(command),
+            None => debug!("[unhandled apc] data={:?}", data),
+        }
+    }
+
     fn osc_dispatch(&mut self, params: &[&[u8]], bell_terminated: bool) {
         debug!("[osc_dispatch] params={params:?} bell_terminated={bell_terminated}");
 
@@ -632,8 +747,8 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
         }
 
         match params[0] {
-            // Set window title.
-            b"0" | b"2" => {
+            // Set window and/or icon title.
+            b"0" | b"1" | b"2" => {
                 if params.len() >= 2 {
                     let title = params[1..]
                         .iter()
@@ -642,7 +757,12 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
                         .join(";")
                         .trim()
                         .to_owned();
-                    self.handler.set_title(Some(title));
+                    if params[0] != b"2" {
+                        self.handler.set_icon_title(Some(title.clone()));
+                    }
+                    if params[0] != b"1" {
+                        self.handler.set_title(Some(title));
+                    }
                     return;
                 }
                 unhandled(params);
@@ -802,6 +922,21 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
             // Reset text cursor color.
             b"112" => self.handler.reset_color(NamedColor::Cursor as usize),
 
+            // Shell integration (FinalTerm semantic prompts).
+            b"133" => {
+                let mark = match params.get(1).and_then(|param| param.first()) {
+                    Some(b'A') => SemanticPrompt::PromptStart,
+                    Some(b'B') => SemanticPrompt::CommandStart,
+                    Some(b'C') => SemanticPrompt::CommandExecuted,
+                    Some(b'D') => SemanticPrompt::CommandFinished,
+                    _ => {
+                        unhandled(params);
+                        return;
+                    }
+                };
+                self.handler.semantic_prompt(mark);
+            }
+
             // OSC 1337 is not necessarily only used by iTerm2 protocol
             // OSC 1337 is equal to xterm OSC 50
             // b"1337" => {
@@ -1006,6 +1141,31 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
 
                 handler.set_scrolling_region(top, bottom);
             }
+            ('r', [b'$']) => {
+                let top = next_param_or(1) as usize;
+                let left = next_param_or(1) as usize;
+                let mut next_corner = || {
+                    params_iter
+                        .next()
+                        .map(|param| param[0] as usize)
+                        .filter(|&param| param != 0)
+                };
+                let bottom = next_corner();
+                let right = next_corner();
+
+                let mut attrs = Vec::new();
+                for attr in attrs_from_sgr_parameters(&mut params_iter) {
+                    match attr {
+                        Some(attr) => attrs.push(attr),
+                        None => csi_unhandled!(),
+                    }
+                }
+                if attrs.is_empty() {
+                    attrs.push(Attr::Reset);
+                }
+
+                handler.set_area_attributes(top, left, bottom, right, &attrs);
+            }
             ('S', []) => handler.scroll_up(next_param_or(1) as usize),
             ('S', [b'?']) => {
                 handler.graphics_attribute(next_param_or(0), next_param_or(0))
@@ -1041,6 +1201,7 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
             }
             ('u', []) => handler.restore_cursor_position(),
             ('X', []) => handler.erase_chars(Column(next_param_or(1) as usize)),
+            ('x', [b'*']) => handler.set_attribute_change_extent(next_param_or(0) == 2),
             ('Z', []) => handler.move_backward_tabs(next_param_or(1)),
             _ => csi_unhandled!(),
         };
@@ -3,7 +3,8 @@ pub mod handler;
 use crate::crosswords::Crosswords;
 use crate::event::sync::FairMutex;
 use crate::event::RioEvent;
-use crate::event::{EventListener, Msg, WindowId};
+use crate::event::{EventListener, Msg, OutputFilter, WindowId};
+use crate::recording::SharedRecording;
 use corcovado::channel;
 #[cfg(unix)]
 use corcovado::unix::UnixReady;
@@ -30,8 +31,6 @@ where
 }
 
 const READ_BUFFER_SIZE: usize = 0x10_0000;
-/// Max bytes to read from the PTY while the terminal is locked.
-const MAX_LOCKED_READ: usize = u16::MAX as usize;
 
 pub struct Machine<T: teletypewriter::EventedPty, U: EventListener> {
     sender: channel::Sender<Msg>,
@@ -42,6 +41,9 @@ pub struct Machine<T: teletypewriter::EventedPty, U: EventListener> {
     event_proxy: U,
     window_id: WindowId,
     route_id: usize,
+    /// Max bytes to read from the PTY while the terminal is locked,
+    /// whatever is left is read once the renderer had a chance to run.
+    parse_budget: usize,
 }
 
 #[derive(Default)]
@@ -49,6 +51,8 @@ pub struct State {
     write_list: VecDeque<Cow<'static, [u8]>>,
     writing: Option<Writing>,
     parser: handler::ParserProcessor,
+    output_filter: Option<OutputFilter>,
+    recording: Option<SharedRecording>,
 }
 
 impl State {
@@ -121,6 +125,7 @@ where
         event_proxy: U,
         window_id: WindowId,
         route_id: usize,
+        parse_budget: usize,
     ) -> Result<Machine<T, U>, Box<dyn std::error::Error>> {
         let (sender, receiver) = channel::channel();
         let poll = corcovado::Poll::new()?;
@@ -134,6 +139,7 @@ where
             event_proxy,
             window_id,
             route_id,
+            parse_budget: parse_budget.max(1),
         })
     }
 
@@ -147,11 +153,25 @@ where
         let mut terminal = None;
 
         loop {
+            // Never read more than what is left of the budget, so a single
+            // read can't hold the terminal longer than configured.
+            let limit = self
+                .parse_budget
+                .saturating_sub(processed)
+                .clamp(1, buf.len());
+
             // Read from the PTY.
-            match self.pty.reader().read(&mut buf[unprocessed..]) {
+            match self.pty.reader().read(&mut buf[unprocessed..limit]) {
                 // This is received on Windows/macOS when no more data is readable from the PTY.
                 Ok(0) if unprocessed == 0 => break,
-                Ok(got) => unprocessed += got,
+                Ok(got) => {
+                    if let Some(recording) = &state.recording {
+                        recording
+                            .lock()
+                            .output(&buf[unprocessed..unprocessed + got]);
+                    }
+                    unprocessed += got;
+                }
                 Err(err) => match err.kind() {
                     ErrorKind::Interrupted | ErrorKind::WouldBlock => {
                         // Go back to mio if we're caught up on parsing and the PTY would block.
@@ -168,24 +188,28 @@ where
                 Some(terminal) => terminal,
                 None => terminal.insert(match self.terminal.try_lock_unfair() {
                     // Force block if we are at the buffer size limit.
-                    None if unprocessed >= READ_BUFFER_SIZE => {
-                        self.terminal.lock_unfair()
-                    }
+                    None if unprocessed >= limit => self.terminal.lock_unfair(),
                     None => continue,
                     Some(terminal) => terminal,
                 }),
             };
 
+            let consumed = match state.output_filter.as_mut() {
+                Some(filter) => filter(&buf[..unprocessed]).min(unprocessed),
+                None => 0,
+            };
+
             // Parse the incoming bytes.
-            for byte in &buf[..unprocessed] {
+            for byte in &buf[consumed..unprocessed] {
                 state.parser.advance(&mut **terminal, *byte);
             }
+            terminal.has_activity = true;
 
             processed += unprocessed;
             unprocessed = 0;
 
             // Assure we're not blocking the terminal too long unnecessarily.
-            if processed >= MAX_LOCKED_READ {
+            if processed >= self.parse_budget {
                 break;
             }
         }
@@ -203,11 +227,23 @@ where
         while let Ok(msg) = self.receiver.try_recv() {
             match msg {
                 Msg::Input(input) => {
+                    if let Some(recording) = &state.recording {
+                        recording.lock().input(&input);
+                    }
                     state.write_list.push_back(input);
                 }
                 Msg::Resize(window_size) => {
                     let _ = self.pty.set_winsize(window_size);
                 }
+                Msg::OutputFilter(filter) => {
+                    state.output_filter = Some(filter);
+                }
+                Msg::ParseBudget(budget) => {
+                    self.parse_budget = budget.max(1);
+                }
+                Msg::Recording(recording) => {
+                    state.recording = recording;
+                }
                 Msg::Shutdown => return false,
             }
         }
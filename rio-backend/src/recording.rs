@@ -0,0 +1,244 @@
+// Session recording in asciinema v2 format (asciicast).
+//
+// https://docs.asciinema.org/manual/asciicast/v2/
+//
+// Output is recorded by the PTY reader as it is read, so a UTF-8 sequence
+// can be split across two reads. Each direction keeps the incomplete tail
+// of its last chunk and decodes it with the next one.
+
+use parking_lot::Mutex;
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Recording shared between the screen and the PTY reader of a context.
+pub type SharedRecording = Arc<Mutex<Recording>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordingEventKind {
+    /// Data read from the PTY.
+    Output,
+    /// Data written to the PTY.
+    Input,
+}
+
+impl RecordingEventKind {
+    #[inline]
+    fn code(&self) -> &'static str {
+        match self {
+            RecordingEventKind::Output => "o",
+            RecordingEventKind::Input => "i",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordingEvent {
+    /// Time elapsed since the recording has started.
+    pub time: Duration,
+    pub kind: RecordingEventKind,
+    pub data: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Recording {
+    pub start_time: Instant,
+    pub events: Vec<RecordingEvent>,
+    columns: usize,
+    lines: usize,
+    timestamp: u64,
+    /// Start of a UTF-8 sequence cut at the end of the last output chunk.
+    output_tail: Vec<u8>,
+    /// Same as `output_tail` for the input.
+    input_tail: Vec<u8>,
+}
+
+impl Recording {
+    pub fn new(columns: usize, lines: usize) -> Recording {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+
+        Recording {
+            start_time: Instant::now(),
+            events: Vec::new(),
+            columns,
+            lines,
+            timestamp,
+            output_tail: Vec::new(),
+            input_tail: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn into_shared(self) -> SharedRecording {
+        Arc::new(Mutex::new(self))
+    }
+
+    #[inline]
+    pub fn output(&mut self, data: &[u8]) {
+        self.push(RecordingEventKind::Output, data);
+    }
+
+    #[inline]
+    pub fn input(&mut self, data: &[u8]) {
+        self.push(RecordingEventKind::Input, data);
+    }
+
+    fn push(&mut self, kind: RecordingEventKind, data: &[u8]) {
+        let tail = match kind {
+            RecordingEventKind::Output => &mut self.output_tail,
+            RecordingEventKind::Input => &mut self.input_tail,
+        };
+        let data = decode_utf8(tail, data);
+        if data.is_empty() {
+            return;
+        }
+
+        self.events.push(RecordingEvent {
+            time: self.start_time.elapsed(),
+            kind,
+            data,
+        });
+    }
+
+    /// Write the recording as asciicast v2, a header line followed by
+    /// one JSON array per event.
+    pub fn write_asciicast<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(
+            writer,
+            "{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {}}}",
+            self.columns, self.lines, self.timestamp
+        )?;
+
+        for event in &self.events {
+            writeln!(
+                writer,
+                "[{:.6}, \"{}\", \"{}\"]",
+                event.time.as_secs_f64(),
+                event.kind.code(),
+                escape_json(&event.data)
+            )?;
+        }
+
+        writer.flush()
+    }
+}
+
+/// Decode `tail` followed by `data`, invalid bytes are replaced and an
+/// incomplete sequence at the end is left in `tail` for the next chunk.
+fn decode_utf8(tail: &mut Vec<u8>, data: &[u8]) -> String {
+    let mut bytes = std::mem::take(tail);
+    bytes.extend_from_slice(data);
+
+    let mut decoded = String::with_capacity(bytes.len());
+    let mut rest = &bytes[..];
+    loop {
+        match std::str::from_utf8(rest) {
+            Ok(valid) => {
+                decoded.push_str(valid);
+                break;
+            }
+            Err(error) => {
+                let (valid, after) = rest.split_at(error.valid_up_to());
+                decoded.push_str(std::str::from_utf8(valid).unwrap_or_default());
+                match error.error_len() {
+                    Some(len) => {
+                        decoded.push(char::REPLACEMENT_CHARACTER);
+                        rest = &after[len..];
+                    }
+                    None => {
+                        tail.extend_from_slice(after);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    decoded
+}
+
+fn escape_json(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 || c == '\u{7f}' => {
+                escaped.push_str(&format!("\\u{:04x}", c as u32));
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_json() {
+        assert_eq!(escape_json("rio"), "rio");
+        assert_eq!(escape_json("\"a\"\\"), "\\\"a\\\"\\\\");
+        assert_eq!(escape_json("ls\r\n"), "ls\\r\\n");
+        assert_eq!(escape_json("\x1b[0m"), "\\u001b[0m");
+    }
+
+    #[test]
+    fn test_split_utf8() {
+        let mut recording = Recording::new(80, 24);
+        let text = "λ ✓".as_bytes();
+
+        // "✓" is cut in the middle, it comes out with the next chunk.
+        recording.output(&text[..4]);
+        recording.output(&text[4..]);
+        recording.output(b"\xffa");
+
+        let data: Vec<&str> = recording
+            .events
+            .iter()
+            .map(|event| event.data.as_str())
+            .collect();
+        assert_eq!(data, ["λ ", "✓", "\u{fffd}a"]);
+
+        // Input keeps its own tail.
+        recording.output(&text[..4]);
+        recording.input(b"ls");
+        assert_eq!(recording.events.last().unwrap().data, "ls");
+        assert_eq!(recording.output_tail, &text[3..4]);
+    }
+
+    #[test]
+    fn test_write_asciicast() {
+        let mut recording = Recording::new(80, 24);
+        recording.timestamp = 1;
+        recording.input(b"ls\r");
+        recording.output(b"");
+        recording.output(b"\x1b[1mrio\x1b[0m\r\n");
+        for event in recording.events.iter_mut() {
+            event.time = Duration::from_millis(1500);
+        }
+
+        let mut buffer = Vec::new();
+        recording.write_asciicast(&mut buffer).unwrap();
+        let content = String::from_utf8(buffer).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0],
+            "{\"version\": 2, \"width\": 80, \"height\": 24, \"timestamp\": 1}"
+        );
+        assert_eq!(lines[1], "[1.500000, \"i\", \"ls\\r\"]");
+        assert_eq!(
+            lines[2],
+            "[1.500000, \"o\", \"\\u001b[1mrio\\u001b[0m\\r\\n\"]"
+        );
+    }
+}
@@ -801,4 +801,34 @@ mod tests {
         assert!(!selection.intersects_range(..=Line(2)));
         assert!(!selection.intersects_range(Line(7)..=Line(8)));
     }
+
+    #[test]
+    fn semantic_selection_separators() {
+        let mut term = term(1, 14);
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"foo/bar:baz ok" {
+            parser.advance(&mut term, *byte);
+        }
+
+        // Word under column 5, the `a` of `bar`.
+        let word = |term: &Crosswords<VoidListener>| {
+            let location = Pos::new(Line(0), Column(5));
+            let selection = Selection::new(SelectionType::Semantic, location, Side::Left);
+            let range = selection.to_range(term).unwrap();
+            (range.start.col.0, range.end.col.0)
+        };
+
+        // Default separators split on `:` but not on `/`.
+        assert_eq!(word(&term), (0, 6));
+
+        term.set_semantic_escape_chars("/: ");
+        assert_eq!(word(&term), (4, 6));
+
+        term.set_semantic_escape_chars(" ");
+        assert_eq!(word(&term), (0, 10));
+
+        // Without separators the whole line is one word.
+        term.set_semantic_escape_chars("");
+        assert_eq!(word(&term), (0, 13));
+    }
 }
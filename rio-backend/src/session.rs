@@ -0,0 +1,479 @@
+// Session snapshots, the content of the primary screen and its scrollback
+// written to disk so it can be brought back after Rio restarts.
+//
+// All numbers are little endian:
+//
+//   header  magic "RIOSESSN", version u16, columns u32, rows u64
+//   row     wrapped u8, cells u32, `cells` times a cell
+//   cell    char u32, fg color, bg color, flags u16,
+//           zerowidth count u8, `count` times a char u32
+//   color   0 named u16 | 1 rgb u8 u8 u8 | 2 indexed u8
+//
+// The cursor and modes are not part of a session, they belong to the
+// program that was running when it was saved.
+//
+// Rows are read one at a time into a grid the size of the terminal, so
+// restoring a session with a large scrollback never needs to hold more
+// than the scrollback in memory.
+
+use std::io::{self, Read, Write};
+
+use crate::config::colors::{AnsiColor, ColorRgb, NamedColor};
+use crate::crosswords::grid::row::Row;
+use crate::crosswords::grid::{Dimensions, Grid};
+use crate::crosswords::pos::{Column, Line};
+use crate::crosswords::square::{Flags, Square};
+
+pub const SESSION_MAGIC: &[u8; 8] = b"RIOSESSN";
+pub const SESSION_VERSION: u16 = 1;
+
+/// Flags that only make sense for the terminal the row was written on.
+const SKIPPED_FLAGS: Flags = Flags::WRAPLINE.union(Flags::GRAPHICS);
+
+const NAMED_COLORS: [NamedColor; 29] = [
+    NamedColor::Black,
+    NamedColor::Red,
+    NamedColor::Green,
+    NamedColor::Yellow,
+    NamedColor::Blue,
+    NamedColor::Magenta,
+    NamedColor::Cyan,
+    NamedColor::White,
+    NamedColor::LightBlack,
+    NamedColor::LightRed,
+    NamedColor::LightGreen,
+    NamedColor::LightYellow,
+    NamedColor::LightBlue,
+    NamedColor::LightMagenta,
+    NamedColor::LightCyan,
+    NamedColor::LightWhite,
+    NamedColor::Foreground,
+    NamedColor::Background,
+    NamedColor::Cursor,
+    NamedColor::DimBlack,
+    NamedColor::DimRed,
+    NamedColor::DimGreen,
+    NamedColor::DimYellow,
+    NamedColor::DimBlue,
+    NamedColor::DimMagenta,
+    NamedColor::DimCyan,
+    NamedColor::DimWhite,
+    NamedColor::LightForeground,
+    NamedColor::DimForeground,
+];
+
+/// Rows of a session file, read ahead of time so the terminal only has to
+/// be locked to [`Session::restore`] them.
+pub struct Session {
+    grid: Grid<Square>,
+    filled: usize,
+}
+
+/// Size of the primary screen a session is read for.
+#[derive(Debug, Clone, Copy)]
+pub struct SessionSize {
+    pub lines: usize,
+    pub columns: usize,
+    pub scrollback: usize,
+}
+
+/// Write `grid` from the top of its scrollback down to the cursor line,
+/// the empty lines below the cursor are left out.
+pub fn write_session<W: Write>(grid: &Grid<Square>, writer: &mut W) -> io::Result<()> {
+    let top = grid.topmost_line().0;
+    let bottom = grid.cursor.pos.row.0;
+    let rows = (bottom - top + 1) as u64;
+
+    writer.write_all(SESSION_MAGIC)?;
+    writer.write_all(&SESSION_VERSION.to_le_bytes())?;
+    writer.write_all(&(grid.columns() as u32).to_le_bytes())?;
+    writer.write_all(&rows.to_le_bytes())?;
+
+    for line in top..=bottom {
+        write_row(&grid[Line(line)], writer)?;
+    }
+
+    writer.flush()
+}
+
+/// Read a session for a terminal of the given `size`. Rows wider than the
+/// terminal are cut, older rows are dropped once the scrollback is full.
+pub fn read_session<R: Read>(reader: &mut R, size: SessionSize) -> io::Result<Session> {
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != SESSION_MAGIC {
+        return Err(invalid_data("not a session file"));
+    }
+
+    let version = u16::from_le_bytes(read_array(reader)?);
+    if version != SESSION_VERSION {
+        return Err(invalid_data(&format!(
+            "unsupported session version {version}"
+        )));
+    }
+
+    let _columns = u32::from_le_bytes(read_array(reader)?);
+    let rows = u64::from_le_bytes(read_array(reader)?);
+
+    let mut session = Session {
+        grid: Grid::new(size.lines, size.columns, size.scrollback),
+        filled: 0,
+    };
+
+    for _ in 0..rows {
+        let row = push_row(&mut session.grid, &mut session.filled);
+        read_row(row, reader)?;
+    }
+
+    Ok(session)
+}
+
+impl Session {
+    /// Put the rows of the session above the content of `grid`, they end
+    /// up in the scrollback and the screen is left as it is.
+    pub fn restore(mut self, grid: &mut Grid<Square>) {
+        // The terminal may have been resized while the file was read.
+        // The cursor follows the last row read, so resizing keeps the rows
+        // and tells where the next one goes.
+        if self.grid.screen_lines() != grid.screen_lines()
+            || self.grid.columns() != grid.columns()
+        {
+            if self.filled == 0 {
+                self.grid = Grid::new(grid.screen_lines(), grid.columns(), 0);
+            } else {
+                self.grid.cursor.pos.row = Line(self.filled as i32 - 1);
+                self.grid.resize(false, grid.screen_lines(), grid.columns());
+                self.filled = self.grid.cursor.pos.row.0 as usize + 1;
+            }
+        }
+        self.grid.update_history(grid.max_scroll_limit());
+
+        for line in grid.topmost_line().0..=grid.bottommost_line().0 {
+            let row = push_row(&mut self.grid, &mut self.filled);
+            row.clone_from(&grid[Line(line)]);
+        }
+
+        self.grid.cursor = grid.cursor.clone();
+        self.grid.saved_cursor = grid.saved_cursor.clone();
+        *grid = self.grid;
+    }
+}
+
+/// Next row at the bottom of `grid`, the screen gets filled from the top
+/// first and then scrolled up into the history.
+fn push_row<'a>(grid: &'a mut Grid<Square>, filled: &mut usize) -> &'a mut Row<Square> {
+    let screen_lines = grid.screen_lines();
+    let line = if *filled < screen_lines {
+        *filled += 1;
+        Line(*filled as i32 - 1)
+    } else {
+        grid.scroll_up(&(Line(0)..Line(screen_lines as i32)), 1);
+        Line(screen_lines as i32 - 1)
+    };
+
+    &mut grid[line]
+}
+
+fn write_row<W: Write>(row: &Row<Square>, writer: &mut W) -> io::Result<()> {
+    let columns = row.len();
+    let wrapped = columns > 0 && row[Column(columns - 1)].flags.contains(Flags::WRAPLINE);
+
+    // Trailing blank cells are left out.
+    let default = Square::default();
+    let cells = (0..columns)
+        .rev()
+        .find(|column| {
+            let square = &row[Column(*column)];
+            square.c != ' '
+                || square.fg != default.fg
+                || square.bg != default.bg
+                || !(square.flags - SKIPPED_FLAGS).is_empty()
+                || square.zerowidth().is_some()
+        })
+        .map_or(0, |column| column + 1);
+
+    writer.write_all(&[wrapped as u8])?;
+    writer.write_all(&(cells as u32).to_le_bytes())?;
+
+    for column in 0..cells {
+        let square = &row[Column(column)];
+        writer.write_all(&(square.c as u32).to_le_bytes())?;
+        write_color(square.fg, writer)?;
+        write_color(square.bg, writer)?;
+        writer.write_all(&(square.flags - SKIPPED_FLAGS).bits().to_le_bytes())?;
+
+        let zerowidth = square.zerowidth().unwrap_or_default();
+        let count = zerowidth.len().min(u8::MAX as usize);
+        writer.write_all(&[count as u8])?;
+        for c in &zerowidth[..count] {
+            writer.write_all(&(*c as u32).to_le_bytes())?;
+        }
+    }
+
+    Ok(())
+}
+
+fn read_row<R: Read>(row: &mut Row<Square>, reader: &mut R) -> io::Result<()> {
+    let [wrapped] = read_array(reader)?;
+    let cells = u32::from_le_bytes(read_array(reader)?) as usize;
+    let columns = row.len();
+
+    for column in 0..cells {
+        let c = read_char(reader)?;
+        let fg = read_color(reader)?;
+        let bg = read_color(reader)?;
+        let flags = Flags::from_bits_truncate(u16::from_le_bytes(read_array(reader)?));
+
+        let mut square = Square {
+            c,
+            fg,
+            bg,
+            extra: None,
+            flags: flags - SKIPPED_FLAGS,
+        };
+
+        let [count] = read_array(reader)?;
+        for _ in 0..count {
+            square.push_zerowidth(read_char(reader)?);
+        }
+
+        // Cells that don't fit are still read to get to the next row.
+        if column < columns {
+            row[Column(column)] = square;
+        }
+    }
+
+    // A wide char whose spacer got cut doesn't fit anymore.
+    if cells > columns && columns > 0 {
+        let last = &mut row[Column(columns - 1)];
+        if last.flags.contains(Flags::WIDE_CHAR) {
+            *last = Square::default();
+        }
+    }
+
+    if wrapped != 0 && columns > 0 {
+        row[Column(columns - 1)].flags.insert(Flags::WRAPLINE);
+    }
+
+    Ok(())
+}
+
+fn write_color<W: Write>(color: AnsiColor, writer: &mut W) -> io::Result<()> {
+    match color {
+        AnsiColor::Named(named) => {
+            writer.write_all(&[0])?;
+            writer.write_all(&(named as u16).to_le_bytes())
+        }
+        AnsiColor::Spec(ColorRgb { r, g, b }) => writer.write_all(&[1, r, g, b]),
+        AnsiColor::Indexed(index) => writer.write_all(&[2, index]),
+    }
+}
+
+fn read_color<R: Read>(reader: &mut R) -> io::Result<AnsiColor> {
+    match read_array(reader)? {
+        [0] => {
+            let value = u16::from_le_bytes(read_array(reader)?);
+            NAMED_COLORS
+                .iter()
+                .find(|named| **named as u16 == value)
+                .map(|named| AnsiColor::Named(*named))
+                .ok_or_else(|| invalid_data("invalid named color"))
+        }
+        [1] => {
+            let [r, g, b] = read_array(reader)?;
+            Ok(AnsiColor::Spec(ColorRgb { r, g, b }))
+        }
+        [2] => {
+            let [index] = read_array(reader)?;
+            Ok(AnsiColor::Indexed(index))
+        }
+        _ => Err(invalid_data("invalid color")),
+    }
+}
+
+fn read_char<R: Read>(reader: &mut R) -> io::Result<char> {
+    char::from_u32(u32::from_le_bytes(read_array(reader)?))
+        .ok_or_else(|| invalid_data("invalid character"))
+}
+
+#[inline]
+fn read_array<R: Read, const N: usize>(reader: &mut R) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[inline]
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::CursorShape;
+    use crate::crosswords::pos::Pos;
+    use crate::crosswords::{Crosswords, CrosswordsSize};
+    use crate::event::{VoidListener, WindowId};
+    use crate::performer::handler::ParserProcessor;
+
+    fn term(columns: usize, lines: usize, input: &[u8]) -> Crosswords<VoidListener> {
+        let size = CrosswordsSize::new(columns, lines);
+        let mut term = Crosswords::new(
+            size,
+            CursorShape::Block,
+            VoidListener {},
+            WindowId::from(0),
+            0,
+        );
+        let mut parser = ParserProcessor::new();
+        for byte in input {
+            parser.advance(&mut term, *byte);
+        }
+        term
+    }
+
+    fn text(term: &Crosswords<VoidListener>, line: i32) -> String {
+        let row = &term.grid[Line(line)];
+        (0..row.len())
+            .map(|column| row[Column(column)].c)
+            .collect::<String>()
+            .trim_end()
+            .to_owned()
+    }
+
+    #[test]
+    fn test_session_round_trip() {
+        let saved = term(
+            5,
+            4,
+            b"\x1b[1;31mred\x1b[0m\r\n\x1b[48;2;1;2;3mbg\x1b[0m\r\nwrapped\r\ne\xcc\x81",
+        );
+
+        let mut buffer = Vec::new();
+        saved.save_session(&mut buffer).unwrap();
+        assert_eq!(&buffer[..8], SESSION_MAGIC);
+
+        // The new session keeps its prompt on screen.
+        let mut restored = term(5, 4, b"$ ");
+        restored.restore_session(&mut buffer.as_slice()).unwrap();
+
+        assert_eq!(restored.grid.history_size(), 5);
+        assert_eq!(text(&restored, -5), "red");
+        assert_eq!(text(&restored, -4), "bg");
+        assert_eq!(text(&restored, -3), "wrapp");
+        assert_eq!(text(&restored, -2), "ed");
+        assert_eq!(text(&restored, -1), "e");
+        assert_eq!(text(&restored, 0), "$");
+        assert_eq!(restored.grid.cursor.pos, Pos::new(Line(0), Column(2)));
+        assert_eq!(restored.display_offset(), 0);
+
+        let red = &restored.grid[Line(-5)][Column(0)];
+        assert_eq!(red.fg, AnsiColor::Named(NamedColor::Red));
+        assert!(red.flags.contains(Flags::BOLD));
+        assert_eq!(
+            restored.grid[Line(-4)][Column(1)].bg,
+            AnsiColor::Spec(ColorRgb { r: 1, g: 2, b: 3 })
+        );
+        assert!(restored.grid[Line(-3)][Column(4)]
+            .flags
+            .contains(Flags::WRAPLINE));
+        assert!(!restored.grid[Line(-2)][Column(4)]
+            .flags
+            .contains(Flags::WRAPLINE));
+        assert_eq!(
+            restored.grid[Line(-1)][Column(0)].zerowidth(),
+            Some(&['\u{301}'][..])
+        );
+    }
+
+    #[test]
+    fn test_session_larger_than_scrollback() {
+        let mut input = Vec::new();
+        for line in 0..3000 {
+            input.extend_from_slice(format!("{line}\r\n").as_bytes());
+        }
+        let saved = term(10, 5, &input);
+
+        let mut buffer = Vec::new();
+        saved.save_session(&mut buffer).unwrap();
+
+        let mut restored = term(10, 5, b"$ ");
+        restored.set_scrollback_lines(100);
+        restored.restore_session(&mut buffer.as_slice()).unwrap();
+
+        // Only the latest lines fit in the scrollback.
+        assert_eq!(restored.grid.history_size(), 100);
+        assert_eq!(text(&restored, -1), "");
+        assert_eq!(text(&restored, -2), "2999");
+        assert_eq!(text(&restored, -100), "2901");
+        assert_eq!(text(&restored, 0), "$");
+    }
+
+    #[test]
+    fn test_session_cut_wide_chars() {
+        let saved = term(6, 2, "ab漢字".as_bytes());
+        let mut buffer = Vec::new();
+        saved.save_session(&mut buffer).unwrap();
+
+        // "字" doesn't fit anymore, its spacer would be cut.
+        let mut restored = term(5, 2, b"$ ");
+        restored.restore_session(&mut buffer.as_slice()).unwrap();
+
+        let row = &restored.grid[Line(-1)];
+        assert_eq!(row[Column(2)].c, '漢');
+        assert!(row[Column(3)].flags.contains(Flags::WIDE_CHAR_SPACER));
+        assert_eq!(row[Column(4)].c, ' ');
+        assert!(!row[Column(4)]
+            .flags
+            .intersects(Flags::WIDE_CHAR | Flags::WIDE_CHAR_SPACER));
+    }
+
+    #[test]
+    fn test_session_resized_while_read() {
+        let saved = term(5, 4, b"a\r\nb\r\nc");
+        let mut buffer = Vec::new();
+        saved.save_session(&mut buffer).unwrap();
+
+        // The terminal shrinks between reading the file and restoring it.
+        let mut restored = term(5, 4, b"$ ");
+        let session =
+            read_session(&mut buffer.as_slice(), restored.session_size()).unwrap();
+        restored.resize(CrosswordsSize::new(4, 2));
+        restored.apply_session(session);
+
+        assert_eq!(restored.grid.history_size(), 3);
+        assert_eq!(text(&restored, -3), "a");
+        assert_eq!(text(&restored, -2), "b");
+        assert_eq!(text(&restored, -1), "c");
+        assert_eq!(text(&restored, 0), "$");
+    }
+
+    #[test]
+    fn test_session_invalid_header() {
+        let mut restored = term(5, 2, b"$ ");
+
+        let error = restored
+            .restore_session(&mut b"NOTRIO00".as_slice())
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let mut future = SESSION_MAGIC.to_vec();
+        future.extend_from_slice(&(SESSION_VERSION + 1).to_le_bytes());
+        let error = restored
+            .restore_session(&mut future.as_slice())
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        // Truncated files are rejected and the terminal is left alone.
+        let mut buffer = Vec::new();
+        term(5, 2, b"hello").save_session(&mut buffer).unwrap();
+        buffer.truncate(buffer.len() - 1);
+        let error = restored
+            .restore_session(&mut buffer.as_slice())
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(restored.grid.history_size(), 0);
+        assert_eq!(text(&restored, 0), "$");
+    }
+}
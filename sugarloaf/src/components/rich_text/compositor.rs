@@ -491,9 +491,14 @@ impl Compositor {
                     UnderlineShape::Dashed => {
                         let mut start = ux;
                         while start < end {
-                            start = start.min(end);
+                            // The last dash stops at the end of the run.
                             self.batches.add_rect(
-                                &Rect::new(start, uy, 6.0, underline.size),
+                                &Rect::new(
+                                    start,
+                                    uy,
+                                    6.0f32.min(end - start),
+                                    underline.size,
+                                ),
                                 depth,
                                 &underline.color,
                             );
@@ -503,9 +508,13 @@ impl Compositor {
                     UnderlineShape::Dotted => {
                         let mut start = ux;
                         while start < end {
-                            start = start.min(end);
                             self.batches.add_rect(
-                                &Rect::new(start, uy, 2.0, underline.size),
+                                &Rect::new(
+                                    start,
+                                    uy,
+                                    2.0f32.min(end - start),
+                                    underline.size,
+                                ),
                                 depth,
                                 &underline.color,
                             );
@@ -515,25 +524,14 @@ impl Compositor {
                     UnderlineShape::Curly => {
                         let style_line_height = (line_height / 10.).clamp(2.0, 16.0);
                         let size = (style_line_height / 1.5).clamp(1.0, 4.0);
-                        let offset = style_line_height * 1.6;
 
                         let mut curly_width = ux;
-                        let mut rect_width = 1.0f32.min(end - curly_width);
-
                         while curly_width < end {
-                            rect_width = rect_width.min(end - curly_width);
-
-                            let dot_bottom_offset = match curly_width as u32 % 8 {
-                                3..=5 => offset + style_line_height,
-                                2 | 6 => offset + 2.0 * style_line_height / 3.0,
-                                1 | 7 => offset + 1.0 * style_line_height / 3.0,
-                                _ => offset,
-                            };
-
+                            let rect_width = 1.0f32.min(end - curly_width);
                             self.batches.add_rect(
                                 &Rect::new(
                                     curly_width,
-                                    uy - (dot_bottom_offset - offset),
+                                    uy - curly_offset(curly_width, style_line_height),
                                     rect_width,
                                     size,
                                 ),
@@ -549,3 +547,30 @@ impl Compositor {
         }
     }
 }
+
+/// Height of an undercurl above its lowest point at `x`. The wave is a
+/// sine scaled by the line height; its phase comes from `x` alone so runs
+/// drawn separately join without a seam.
+#[inline]
+fn curly_offset(x: f32, amplitude: f32) -> f32 {
+    let period = amplitude * 4.0;
+    amplitude * (1.0 - (x * std::f32::consts::TAU / period).cos()) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::curly_offset;
+
+    #[test]
+    fn test_curly_offset() {
+        let amplitude = 2.0;
+        assert_eq!(curly_offset(0.0, amplitude), 0.0);
+        // Half a period later the wave is at its highest.
+        assert!((curly_offset(4.0, amplitude) - amplitude).abs() < 1e-5);
+        assert!(curly_offset(8.0, amplitude).abs() < 1e-5);
+        for x in 0..32 {
+            let offset = curly_offset(x as f32 * 0.5, amplitude);
+            assert!((0.0..=amplitude).contains(&offset));
+        }
+    }
+}
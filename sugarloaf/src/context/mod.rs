@@ -2,6 +2,7 @@ use crate::sugarloaf::{SugarloafWindow, SugarloafWindowSize};
 use crate::SugarloafRenderer;
 
 pub struct Context<'a> {
+    instance: wgpu::Instance,
     pub device: wgpu::Device,
     pub surface: wgpu::Surface<'a>,
     pub queue: wgpu::Queue,
@@ -9,7 +10,25 @@ pub struct Context<'a> {
     pub size: SugarloafWindowSize,
     pub scale: f32,
     alpha_mode: wgpu::CompositeAlphaMode,
+    usage: wgpu::TextureUsages,
     pub adapter_info: wgpu::AdapterInfo,
+    pub power_preference: wgpu::PowerPreference,
+}
+
+/// A rendered frame read back from the GPU.
+pub struct FrameCapture {
+    pub width: u32,
+    pub height: u32,
+    /// Tightly packed RGBA8 pixels, row by row.
+    pub rgba: Vec<u8>,
+}
+
+/// Buffer holding a copy of the surface texture until it can be mapped.
+pub struct PendingCapture {
+    buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: u32,
 }
 
 #[inline]
@@ -51,6 +70,22 @@ fn find_best_texture_format(formats: Vec<wgpu::TextureFormat>) -> wgpu::TextureF
     format
 }
 
+/// Picks the composite alpha mode for the surface. Post-multiplied is
+/// preferred since the clear color can be used as is, pre-multiplied
+/// requires the color channels to be scaled by alpha first.
+#[inline]
+fn find_best_alpha_mode(
+    alpha_modes: &[wgpu::CompositeAlphaMode],
+) -> wgpu::CompositeAlphaMode {
+    if alpha_modes.contains(&wgpu::CompositeAlphaMode::PostMultiplied) {
+        wgpu::CompositeAlphaMode::PostMultiplied
+    } else if alpha_modes.contains(&wgpu::CompositeAlphaMode::PreMultiplied) {
+        wgpu::CompositeAlphaMode::PreMultiplied
+    } else {
+        wgpu::CompositeAlphaMode::Auto
+    }
+}
+
 impl Context<'_> {
     pub fn new<'a>(
         sugarloaf_window: SugarloafWindow,
@@ -130,24 +165,24 @@ impl Context<'_> {
             }
         };
 
-        let alpha_mode = if caps
-            .alpha_modes
-            .contains(&wgpu::CompositeAlphaMode::PostMultiplied)
-        {
-            wgpu::CompositeAlphaMode::PostMultiplied
-        } else if caps
-            .alpha_modes
-            .contains(&wgpu::CompositeAlphaMode::PreMultiplied)
-        {
-            wgpu::CompositeAlphaMode::PreMultiplied
+        // Reading frames back (e.g. for screen captures) requires the
+        // surface texture to be a copy source, not every platform allows it.
+        let usage = if caps.usages.contains(wgpu::TextureUsages::COPY_SRC) {
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC
         } else {
-            wgpu::CompositeAlphaMode::Auto
+            wgpu::TextureUsages::RENDER_ATTACHMENT
         };
 
+        let alpha_mode = find_best_alpha_mode(&caps.alpha_modes);
+        log::info!(
+            "Sugarloaf selected alpha mode: {alpha_mode:?} from {:?}",
+            caps.alpha_modes
+        );
+
         surface.configure(
             &device,
             &wgpu::SurfaceConfiguration {
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                usage,
                 format,
                 width: size.width as u32,
                 height: size.height as u32,
@@ -159,18 +194,183 @@ impl Context<'_> {
         );
 
         Context {
+            instance,
             device,
             queue,
             surface,
             format,
             alpha_mode,
+            usage,
             size: SugarloafWindowSize {
                 width: size.width,
                 height: size.height,
             },
             scale,
             adapter_info: adapter.get_info(),
+            power_preference: renderer_config.power_preference,
+        }
+    }
+
+    /// Whether the surface can be composited with the content behind
+    /// the window, i.e. if a clear color with alpha < 1.0 has any effect.
+    #[inline]
+    pub fn supports_transparency(&self) -> bool {
+        matches!(
+            self.alpha_mode,
+            wgpu::CompositeAlphaMode::PostMultiplied
+                | wgpu::CompositeAlphaMode::PreMultiplied
+        )
+    }
+
+    /// Converts a color to what the surface alpha mode expects.
+    #[inline]
+    pub fn surface_color(&self, color: wgpu::Color) -> wgpu::Color {
+        if self.alpha_mode == wgpu::CompositeAlphaMode::PreMultiplied {
+            wgpu::Color {
+                r: color.r * color.a,
+                g: color.g * color.a,
+                b: color.b * color.a,
+                a: color.a,
+            }
+        } else {
+            color
+        }
+    }
+
+    #[inline]
+    pub fn supports_capture(&self) -> bool {
+        self.usage.contains(wgpu::TextureUsages::COPY_SRC)
+    }
+
+    /// Records a copy of `texture` into a readable buffer, the pixels are
+    /// available through [`Context::finish_capture`] once the encoder
+    /// has been submitted.
+    pub fn start_capture(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+    ) -> PendingCapture {
+        let width = texture.width();
+        let height = texture.height();
+        let padded_bytes_per_row = (width * 4)
+            .div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+            * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("sugarloaf::capture"),
+            size: (padded_bytes_per_row * height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+
+        PendingCapture {
+            buffer,
+            width,
+            height,
+            padded_bytes_per_row,
+        }
+    }
+
+    /// Blocks until the copy is done and converts it to RGBA.
+    pub fn finish_capture(&self, pending: PendingCapture) -> Option<FrameCapture> {
+        let slice = pending.buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        if let Err(error) = receiver.recv().ok()? {
+            log::error!("unable to read the captured frame: {error}");
+            return None;
+        }
+
+        let is_bgra = matches!(
+            self.format,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+        );
+        let row_len = (pending.width * 4) as usize;
+        let mut rgba = Vec::with_capacity(row_len * pending.height as usize);
+        {
+            let data = slice.get_mapped_range();
+            for row in data.chunks(pending.padded_bytes_per_row as usize) {
+                rgba.extend_from_slice(&row[..row_len]);
+            }
+        }
+        pending.buffer.unmap();
+
+        if is_bgra {
+            for pixel in rgba.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        Some(FrameCapture {
+            width: pending.width,
+            height: pending.height,
+            rgba,
+        })
+    }
+
+    /// Move to the adapter picked for `power_preference`. The surface is
+    /// kept and configured for the new device, the context is left as it
+    /// was if no adapter or device can be created.
+    ///
+    /// Resources created from the previous device must be recreated.
+    pub fn set_power_preference(
+        &mut self,
+        power_preference: wgpu::PowerPreference,
+    ) -> Result<(), String> {
+        let adapter = futures::executor::block_on(self.instance.request_adapter(
+            &wgpu::RequestAdapterOptions {
+                power_preference,
+                compatible_surface: Some(&self.surface),
+                force_fallback_adapter: false,
+            },
+        ))
+        .ok_or_else(|| format!("no adapter available for {power_preference:?}"))?;
+
+        let caps = self.surface.get_capabilities(&adapter);
+        if !caps.formats.contains(&self.format) {
+            return Err(format!(
+                "{} can't present {:?}",
+                adapter.get_info().name,
+                self.format
+            ));
         }
+
+        let (device, queue) = futures::executor::block_on(
+            adapter.request_device(&wgpu::DeviceDescriptor::default(), None),
+        )
+        .map_err(|err| err.to_string())?;
+
+        log::info!("Selected adapter: {:?}", adapter.get_info());
+
+        self.usage = if caps.usages.contains(wgpu::TextureUsages::COPY_SRC) {
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC
+        } else {
+            wgpu::TextureUsages::RENDER_ATTACHMENT
+        };
+        self.alpha_mode = find_best_alpha_mode(&caps.alpha_modes);
+        self.device = device;
+        self.queue = queue;
+        self.adapter_info = adapter.get_info();
+        self.power_preference = power_preference;
+        self.resize(self.size.width as u32, self.size.height as u32);
+
+        Ok(())
     }
 
     pub fn resize(&mut self, width: u32, height: u32) {
@@ -179,7 +379,7 @@ impl Context<'_> {
         self.surface.configure(
             &self.device,
             &wgpu::SurfaceConfiguration {
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                usage: self.usage,
                 format: self.format,
                 width,
                 height,
@@ -191,3 +391,24 @@ impl Context<'_> {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_best_alpha_mode() {
+        use wgpu::CompositeAlphaMode::*;
+
+        assert_eq!(
+            find_best_alpha_mode(&[Opaque, PreMultiplied, PostMultiplied]),
+            PostMultiplied
+        );
+        assert_eq!(
+            find_best_alpha_mode(&[Opaque, PreMultiplied]),
+            PreMultiplied
+        );
+        assert_eq!(find_best_alpha_mode(&[Opaque]), Auto);
+        assert_eq!(find_best_alpha_mode(&[]), Auto);
+    }
+}
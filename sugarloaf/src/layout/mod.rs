@@ -242,7 +242,8 @@ impl SugarloafLayout {
                 / (self.dimensions.width * self.dimensions.scale))
                 as usize;
             log::info!("recalculating columns due to font width, removing {stack_difference:?} columns");
-            self.columns -= stack_difference;
+            self.columns =
+                std::cmp::max(self.columns.saturating_sub(stack_difference), MIN_COLS);
         }
     }
 
@@ -8,11 +8,12 @@ use crate::components::layer::{self, LayerBrush};
 use crate::components::rect::{Rect, RectBrush};
 use crate::components::rich_text::RichTextBrush;
 use crate::components::text;
+use crate::context::{Context, FrameCapture};
 use crate::font::{fonts::SugarloafFont, FontLibrary};
 use crate::layout::SugarloafLayout;
 use crate::sugarloaf::graphics::{BottomLayer, GraphicData, GraphicId, Graphics};
 use crate::sugarloaf::layer::types;
-use crate::{context::Context, Content, Object};
+use crate::{Content, Object};
 use ab_glyph::{self, PxScale};
 use core::fmt::{Debug, Formatter};
 use primitives::ImageProperties;
@@ -31,6 +32,8 @@ pub struct Sugarloaf<'a> {
     pub background_color: wgpu::Color,
     pub background_image: Option<ImageProperties>,
     graphics: Graphics,
+    capture_requested: bool,
+    capture: Option<FrameCapture>,
 }
 
 #[derive(Debug)]
@@ -141,6 +144,8 @@ impl Sugarloaf<'_> {
             rich_text_brush,
             text_brush,
             graphics: Graphics::default(),
+            capture_requested: false,
+            capture: None,
         };
 
         Ok(instance)
@@ -154,6 +159,37 @@ impl Sugarloaf<'_> {
         self.state.set_fonts(font_library);
     }
 
+    /// Move the renderer to the adapter matching `power_preference`.
+    ///
+    /// All GPU resources are rebuilt, so it should only be called when the
+    /// preference actually changes. On error the current adapter is kept.
+    pub fn update_power_preference(
+        &mut self,
+        power_preference: wgpu::PowerPreference,
+    ) -> Result<(), String> {
+        if self.ctx.power_preference == power_preference {
+            return Ok(());
+        }
+
+        log::info!("requested a power preference change to {power_preference:?}");
+
+        self.ctx.set_power_preference(power_preference)?;
+
+        self.text_brush = {
+            let font_library = self.state.compositors.advanced.font_library();
+            let data = { &font_library.inner.read().unwrap().main };
+            text::GlyphBrushBuilder::using_fonts(vec![data.to_owned()])
+                .build(&self.ctx.device, self.ctx.format)
+        };
+        self.rect_brush = RectBrush::init(&self.ctx);
+        self.layer_brush = LayerBrush::new(&self.ctx);
+        self.rich_text_brush = RichTextBrush::new(&self.ctx);
+
+        self.state.reset_compositor();
+        self.state.mark_dirty();
+        Ok(())
+    }
+
     #[inline]
     pub fn get_context(&self) -> &Context {
         &self.ctx
@@ -263,6 +299,19 @@ impl Sugarloaf<'_> {
         self.state.mark_dirty();
     }
 
+    /// Keep a copy of the next rendered frame, it can be retrieved with
+    /// [`Sugarloaf::take_capture`] right after [`Sugarloaf::render`].
+    #[inline]
+    pub fn capture_next_frame(&mut self) {
+        self.capture_requested = true;
+        self.state.mark_dirty();
+    }
+
+    #[inline]
+    pub fn take_capture(&mut self) -> Option<FrameCapture> {
+        self.capture.take()
+    }
+
     #[inline]
     pub fn render(&mut self) {
         self.state.compute_changes();
@@ -322,7 +371,9 @@ impl Sugarloaf<'_> {
                                 view,
                                 resolve_target: None,
                                 ops: wgpu::Operations {
-                                    load: wgpu::LoadOp::Clear(self.background_color),
+                                    load: wgpu::LoadOp::Clear(
+                                        self.ctx.surface_color(self.background_color),
+                                    ),
                                     store: wgpu::StoreOp::Store,
                                 },
                             })],
@@ -360,7 +411,22 @@ impl Sugarloaf<'_> {
                     self.graphics.clear_top_layer();
                 }
 
+                let pending_capture = if self.capture_requested {
+                    self.capture_requested = false;
+                    if self.ctx.supports_capture() {
+                        Some(self.ctx.start_capture(&mut encoder, &frame.texture))
+                    } else {
+                        log::warn!("the surface does not support reading frames back");
+                        None
+                    }
+                } else {
+                    None
+                };
+
                 self.ctx.queue.submit(Some(encoder.finish()));
+                if let Some(pending) = pending_capture {
+                    self.capture = self.ctx.finish_capture(pending);
+                }
                 frame.present();
             }
             Err(error) => {
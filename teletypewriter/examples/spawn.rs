@@ -3,6 +3,7 @@
 #[cfg(unix)]
 fn main() -> std::io::Result<()> {
     use std::borrow::Cow;
+    use std::collections::HashMap;
     use std::io::Read;
     use std::io::Write;
     // use std::io::BufRead;
@@ -10,7 +11,7 @@ fn main() -> std::io::Result<()> {
     use teletypewriter::{create_pty_with_fork, ProcessReadWrite, Pty};
 
     let shell = Cow::Borrowed("bash");
-    let mut process: Pty = create_pty_with_fork(&shell, 80, 25)?;
+    let mut process: Pty = create_pty_with_fork(&shell, &HashMap::new(), 80, 25)?;
 
     process.writer().write_all(b"1").unwrap();
     process.writer().write_all(b"2").unwrap();
@@ -60,7 +60,7 @@ pub trait EventedPty: ProcessReadWrite {
     fn next_child_event(&mut self) -> Option<ChildEvent>;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct WinsizeBuilder {
     pub rows: u16,
     pub cols: u16,
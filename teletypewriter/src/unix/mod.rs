@@ -12,6 +12,7 @@ use corcovado::unix::EventedFd;
 use macos::*;
 use signal_hook::consts as sigconsts;
 use signals::Signals;
+use std::collections::HashMap;
 use std::ffi::{CStr, CString};
 use std::fs::File;
 use std::io;
@@ -20,6 +21,7 @@ use std::mem::MaybeUninit;
 use std::ops::Deref;
 use std::os::fd::OwnedFd;
 use std::os::fd::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::ffi::OsStringExt;
 use std::os::unix::process::CommandExt;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
@@ -61,29 +63,82 @@ extern "C" {
     fn ptsname(fd: *mut libc::c_int) -> *mut libc::c_char;
 }
 
-#[cfg(target_os = "macos")]
-fn default_shell_command(shell: &str) {
-    let command_shell_string = CString::new(shell).unwrap();
-    let command_pointer = command_shell_string.as_ptr();
-    let args = CString::new("--login").unwrap();
-    let args_pointer = args.as_ptr();
-    unsafe {
-        libc::execvp(command_pointer, vec![args_pointer].as_ptr());
-    }
+extern "C" {
+    static mut environ: *const *const libc::c_char;
 }
 
-#[cfg(not(target_os = "macos"))]
-fn default_shell_command(shell: &str) {
-    let command_shell_string = CString::new(shell).unwrap();
-    let command_pointer = command_shell_string.as_ptr();
-    // let home = std::env::var("HOME").unwrap();
-    // let args = CString::new(home).unwrap();
-    // let args_pointer = args.as_ptr() as *const i8;
-    unsafe {
-        libc::execvp(
-            command_pointer,
-            vec![command_pointer, std::ptr::null()].as_ptr(),
+/// Program, arguments and environment for the forked shell.
+///
+/// Everything is allocated before forking, the child may only make
+/// async-signal-safe calls until it execs.
+struct ShellCommand {
+    program: CString,
+    _args: Vec<CString>,
+    _env: Vec<CString>,
+    argv: Vec<*const libc::c_char>,
+    envp: Vec<*const libc::c_char>,
+}
+
+impl ShellCommand {
+    fn new(shell: &str, env: &HashMap<String, String>) -> Result<Self, Error> {
+        let program = CString::new(shell).map_err(|_| {
+            Error::new(ErrorKind::InvalidInput, format!("invalid shell {shell:?}"))
+        })?;
+
+        // macOS shells are started as login shells.
+        #[cfg(target_os = "macos")]
+        let args = vec![CString::new("--login").unwrap()];
+        #[cfg(not(target_os = "macos"))]
+        let args = vec![program.clone()];
+
+        // The child environment is the current one with `env` on top.
+        let mut vars: Vec<(Vec<u8>, Vec<u8>)> = std::env::vars_os()
+            .filter(|(key, _)| !key.to_str().is_some_and(|key| env.contains_key(key)))
+            .map(|(key, value)| (key.into_vec(), value.into_vec()))
+            .collect();
+        vars.extend(
+            env.iter().map(|(key, value)| {
+                (key.clone().into_bytes(), value.clone().into_bytes())
+            }),
         );
+        let env: Vec<CString> = vars
+            .into_iter()
+            .filter_map(|(mut key, value)| {
+                key.push(b'=');
+                key.extend(value);
+                CString::new(key).ok()
+            })
+            .collect();
+
+        let argv = args
+            .iter()
+            .map(|arg| arg.as_ptr())
+            .chain(std::iter::once(ptr::null()))
+            .collect();
+        let envp = env
+            .iter()
+            .map(|var| var.as_ptr())
+            .chain(std::iter::once(ptr::null()))
+            .collect();
+
+        Ok(Self {
+            program,
+            _args: args,
+            _env: env,
+            argv,
+            envp,
+        })
+    }
+
+    /// Replaces the current process, only returns on failure.
+    ///
+    /// # Safety
+    ///
+    /// Must only be called in a freshly forked child, since it replaces the
+    /// process environment.
+    unsafe fn exec(&self) {
+        environ = self.envp.as_ptr();
+        libc::execvp(self.program.as_ptr(), self.argv.as_ptr());
     }
 }
 
@@ -398,6 +453,7 @@ pub fn create_pty_with_spawn(
     shell: &str,
     args: Vec<String>,
     working_directory: &Option<String>,
+    env: &HashMap<String, String>,
     columns: u16,
     rows: u16,
 ) -> Result<Pty, Error> {
@@ -476,6 +532,10 @@ pub fn create_pty_with_spawn(
 
             with_args.push("--env=TERM_PROGRAM=rio".to_string());
 
+            for (key, value) in env {
+                with_args.push(format!("--env={key}={value}"));
+            }
+
             let output = std::process::Command::new("flatpak-spawn")
                 .args(["--host", "sh", "-c", "echo $SHELL"])
                 .output()?;
@@ -502,6 +562,7 @@ pub fn create_pty_with_spawn(
 
     builder.env("USER", user.user);
     builder.env("HOME", user.home);
+    builder.envs(env);
 
     unsafe {
         builder.pre_exec(move || {
@@ -581,7 +642,12 @@ pub fn create_pty_with_spawn(
 ///
 /// It returns two [`Pty`] along with respective process name [`String`] and process id (`libc::pid_`)
 ///
-pub fn create_pty_with_fork(shell: &str, columns: u16, rows: u16) -> Result<Pty, Error> {
+pub fn create_pty_with_fork(
+    shell: &str,
+    env: &HashMap<String, String>,
+    columns: u16,
+    rows: u16,
+) -> Result<Pty, Error> {
     let mut main = 0;
     let winsize = Winsize {
         ws_row: rows as libc::c_ushort,
@@ -608,6 +674,8 @@ pub fn create_pty_with_fork(shell: &str, columns: u16, rows: u16) -> Result<Pty,
 
     log::info!("fork {:?}", shell_program);
 
+    let command = ShellCommand::new(shell_program, env)?;
+
     match unsafe {
         forkpty(
             &mut main as *mut _,
@@ -616,13 +684,11 @@ pub fn create_pty_with_fork(shell: &str, columns: u16, rows: u16) -> Result<Pty,
             &winsize as *const _,
         )
     } {
-        0 => {
-            default_shell_command(shell_program);
-            Err(Error::new(
-                ErrorKind::Other,
-                format!("forkpty has reach unreachable with {}", shell_program),
-            ))
-        }
+        0 => unsafe {
+            command.exec();
+            // Never run the parent's code in the child if exec failed.
+            libc::_exit(1)
+        },
         id if id > 0 => {
             // TODO: Currently we fork the process and don't wait to know if led to failure
             // Whenever it happens it will just simply shut down the teletyperwriter
@@ -965,3 +1031,38 @@ where
             .map(|_| ())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn fork_passes_environment_to_child() {
+        let env = HashMap::from([("RIO_FORK_TEST".to_string(), "forked".to_string())]);
+        let mut pty = create_pty_with_fork("/bin/sh", &env, 80, 24).unwrap();
+        pty.writer()
+            .write_all(b"echo \"value:$RIO_FORK_TEST\"; exit\n")
+            .unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut output = Vec::new();
+        let mut buf = [0u8; 1024];
+        while Instant::now() < deadline {
+            match pty.reader().read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => output.extend_from_slice(&buf[..n]),
+                Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                    std::thread::sleep(Duration::from_millis(10))
+                }
+                Err(_) => break,
+            }
+            if String::from_utf8_lossy(&output).contains("value:forked") {
+                break;
+            }
+        }
+
+        assert!(String::from_utf8_lossy(&output).contains("value:forked"));
+    }
+}